@@ -7,6 +7,8 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::file_audit::{self, FileOperation};
+
 use super::{
     storage::{self, CheckpointStorage},
     Checkpoint, CheckpointMetadata, CheckpointPaths, CheckpointResult, CheckpointStrategy,
@@ -504,6 +506,7 @@ impl CheckpointManager {
                 let full_path = self.project_path.join(&current_file);
                 match fs::remove_file(&full_path) {
                     Ok(_) => {
+                        file_audit::record("restore_checkpoint", FileOperation::Delete, &full_path);
                         files_processed += 1;
                         log::info!("Deleted file not in checkpoint: {:?}", current_file);
                     }
@@ -606,6 +609,7 @@ impl CheckpointManager {
             // Delete the file if it exists
             if full_path.exists() {
                 fs::remove_file(&full_path).context("Failed to delete file")?;
+                file_audit::record("restore_checkpoint", FileOperation::Delete, &full_path);
             }
         } else {
             // Create parent directories if needed
@@ -614,7 +618,9 @@ impl CheckpointManager {
             }
 
             // Write file content
+            let operation = FileOperation::for_write(&full_path);
             fs::write(&full_path, &snapshot.content).context("Failed to write file")?;
+            file_audit::record("restore_checkpoint", operation, &full_path);
 
             // Restore permissions if available
             #[cfg(unix)]
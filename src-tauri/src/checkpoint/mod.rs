@@ -168,6 +168,9 @@ pub struct FileDiff {
     pub deletions: usize,
     /// Unified diff content (optional)
     pub diff_content: Option<String>,
+    /// Type-aware summary for lockfiles and notebooks
+    #[serde(default)]
+    pub special: Option<crate::diff_providers::SpecialDiff>,
 }
 
 impl Default for CheckpointStrategy {
@@ -39,7 +39,7 @@ pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
     if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
         let db_path = app_data_dir.join("agents.db");
         if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
+            if let Ok(conn) = crate::db_health::open_connection(&db_path) {
                 // Check for stored path first
                 if let Ok(stored_path) = conn.query_row(
                     "SELECT value FROM app_settings WHERE key = 'claude_binary_path'",
@@ -499,7 +499,7 @@ fn find_standard_installations() -> Vec<ClaudeInstallation> {
 }
 
 /// Get Claude version by running --version command
-fn get_claude_version(path: &str) -> Result<Option<String>, String> {
+pub fn get_claude_version(path: &str) -> Result<Option<String>, String> {
     match Command::new(path).arg("--version").output() {
         Ok(output) => {
             if output.status.success() {
@@ -578,7 +578,7 @@ fn select_best_installation(installations: Vec<ClaudeInstallation>) -> Option<Cl
 }
 
 /// Compare two version strings
-fn compare_versions(a: &str, b: &str) -> Ordering {
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
     // Simple semantic version comparison
     let a_parts: Vec<u32> = a
         .split('.')
@@ -643,12 +643,18 @@ pub fn create_command_with_env(program: &str) -> Command {
             || key == "HTTPS_PROXY"
             || key == "NO_PROXY"
             || key == "ALL_PROXY"
+            || key == crate::utils::CLAUDE_CONFIG_DIR_ENV
         {
             debug!("Inheriting env var: {}={}", key, value);
             cmd.env(&key, &value);
         }
     }
 
+    // A Claude directory chosen in the app overrides the inherited one
+    if let Some(dir) = crate::utils::custom_claude_dir() {
+        cmd.env(crate::utils::CLAUDE_CONFIG_DIR_ENV, dir);
+    }
+
     // Log proxy-related environment variables for debugging
     info!("Command will use proxy settings:");
     if let Ok(http_proxy) = std::env::var("HTTP_PROXY") {
@@ -0,0 +1,380 @@
+// Live updates when files under .claude directories change
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::skill_usage::installed_skills;
+use crate::event_bus;
+
+/// How long changes are collected before events are sent
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Payload of the skill, agent, command and settings events
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeDirChange {
+    /// "user" for `~/.claude`, "project" for a project's `.claude` directory
+    pub scope: String,
+    pub project_path: Option<String>,
+    /// The skill or settings file that changed
+    pub name: Option<String>,
+}
+
+/// Payload of `session-updated`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionUpdated {
+    /// Directory of the project under `~/.claude/projects`
+    pub project_id: String,
+    pub session_id: String,
+}
+
+/// What a changed path inside a `.claude` directory affects
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Skill(String),
+    Agents,
+    Commands,
+    Settings(String),
+    Session {
+        project_id: String,
+        session_id: String,
+    },
+}
+
+/// Classify a path relative to a `.claude` directory
+///
+/// Session transcripts only live in the user directory.
+pub fn classify(relative: &Path, user_dir: bool) -> Option<Change> {
+    let parts: Vec<&str> = relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+    match parts.as_slice() {
+        ["skills", name, ..] if !name.starts_with('.') => Some(Change::Skill(name.to_string())),
+        ["agents", ..] => Some(Change::Agents),
+        ["commands", ..] => Some(Change::Commands),
+        [file @ ("settings.json" | "settings.local.json")] => {
+            Some(Change::Settings(file.to_string()))
+        }
+        ["projects", project_id, file] if user_dir => {
+            file.strip_suffix(".jsonl")
+                .map(|session_id| Change::Session {
+                    project_id: project_id.to_string(),
+                    session_id: session_id.to_string(),
+                })
+        }
+        _ => None,
+    }
+}
+
+fn skill_names(dir: &Path) -> BTreeSet<String> {
+    installed_skills(&dir.join("skills"))
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect()
+}
+
+struct WatchedDir {
+    /// `None` for `~/.claude`
+    project: Option<PathBuf>,
+    /// Skills installed at the last check, to tell additions from edits
+    skills: BTreeSet<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    watcher: Option<RecommendedWatcher>,
+    dirs: HashMap<PathBuf, WatchedDir>,
+    /// Open projects; watched on their own so a new `.claude` is noticed
+    projects: HashSet<PathBuf>,
+}
+
+impl Shared {
+    fn watch_dir(&mut self, dir: PathBuf, project: Option<PathBuf>) -> Result<(), String> {
+        if self.dirs.contains_key(&dir) {
+            return Ok(());
+        }
+        let watcher = self
+            .watcher
+            .as_mut()
+            .ok_or("The file watcher is not running")?;
+        watcher
+            .watch(&dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+        let skills = skill_names(&dir);
+        self.dirs.insert(dir, WatchedDir { project, skills });
+        Ok(())
+    }
+
+    /// The watched directory `path` is in, and the path relative to it
+    fn locate(&self, path: &Path) -> Option<(PathBuf, PathBuf)> {
+        self.dirs.keys().find_map(|dir| {
+            path.strip_prefix(dir)
+                .ok()
+                .map(|relative| (dir.clone(), relative.to_path_buf()))
+        })
+    }
+}
+
+/// Watches `~/.claude` and open projects' `.claude` directories
+///
+/// Skills, agents, slash commands and settings change outside the app, so the
+/// changes are turned into typed events and the frontend can refresh its lists.
+#[derive(Clone, Default)]
+pub struct ClaudeWatcher {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ClaudeWatcher {
+    /// Start watching `~/.claude`; events are sent from a background thread
+    ///
+    /// If the platform watcher can't be created the returned watcher does
+    /// nothing, and the UI falls back to manual refreshes.
+    pub fn start(app: AppHandle) -> Self {
+        let this = Self::default();
+        let (sender, receiver) = channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        });
+        {
+            let mut shared = this.shared.lock().unwrap_or_else(|e| e.into_inner());
+            match watcher {
+                Ok(watcher) => shared.watcher = Some(watcher),
+                Err(e) => {
+                    log::warn!("Failed to start the .claude watcher: {}", e);
+                    return this;
+                }
+            }
+            match crate::utils::get_claude_dir() {
+                Ok(dir) => {
+                    let dir = dir.canonicalize().unwrap_or(dir);
+                    if let Err(e) = shared.watch_dir(dir, None) {
+                        log::warn!("{}", e);
+                    }
+                }
+                Err(e) => log::warn!("Not watching the Claude directory: {}", e),
+            }
+        }
+
+        let watcher = this.clone();
+        std::thread::spawn(move || watcher.run(app, receiver));
+        this
+    }
+
+    /// Also watch the `.claude` directory of `project`, once it exists
+    pub fn watch_project(&self, project: &Path) -> Result<(), String> {
+        let mut shared = self.shared.lock().map_err(|e| e.to_string())?;
+        if shared.projects.contains(project) {
+            return Ok(());
+        }
+        shared
+            .watcher
+            .as_mut()
+            .ok_or("The file watcher is not running")?
+            .watch(project, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", project.display(), e))?;
+        shared.projects.insert(project.to_path_buf());
+        let dir = project.join(".claude");
+        if dir.is_dir() {
+            shared.watch_dir(dir, Some(project.to_path_buf()))?;
+        }
+        Ok(())
+    }
+
+    /// Stop watching a project that was closed
+    pub fn unwatch_project(&self, project: &Path) -> Result<(), String> {
+        let mut shared = self.shared.lock().map_err(|e| e.to_string())?;
+        if !shared.projects.remove(project) {
+            return Ok(());
+        }
+        let dir = project.join(".claude");
+        let owned = shared
+            .dirs
+            .get(&dir)
+            .is_some_and(|d| d.project.as_deref() == Some(project));
+        if owned {
+            shared.dirs.remove(&dir);
+        }
+        if let Some(watcher) = shared.watcher.as_mut() {
+            let _ = watcher.unwatch(project);
+            if owned {
+                let _ = watcher.unwatch(&dir);
+            }
+        }
+        Ok(())
+    }
+
+    fn run(self, app: AppHandle, events: Receiver<notify::Result<Event>>) {
+        while let Ok(first) = events.recv() {
+            let mut paths = BTreeSet::new();
+            let mut collect = |event: notify::Result<Event>| match event {
+                Ok(event) if !event.kind.is_access() => paths.extend(event.paths),
+                Ok(_) => {}
+                Err(e) => log::debug!("File watcher error: {}", e),
+            };
+            collect(first);
+
+            // A fixed window, so a session that keeps writing still gets events
+            let deadline = Instant::now() + DEBOUNCE;
+            loop {
+                let left = deadline.saturating_duration_since(Instant::now());
+                match events.recv_timeout(left) {
+                    Ok(event) => collect(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            self.flush(&app, paths);
+        }
+    }
+
+    /// Turn a batch of changed paths into events
+    ///
+    /// A burst of changes becomes one event per skill or per kind. Skill, agent,
+    /// command and settings events go through the event bus; `session-updated`
+    /// fires for every transcript write and is a plain emit.
+    fn flush(&self, app: &AppHandle, paths: BTreeSet<PathBuf>) {
+        let mut published: Vec<(&str, ClaudeDirChange)> = Vec::new();
+        let mut sessions = BTreeSet::new();
+        {
+            let Ok(mut shared) = self.shared.lock() else {
+                return;
+            };
+            let mut changes: BTreeMap<PathBuf, BTreeSet<Change>> = BTreeMap::new();
+            for path in paths {
+                let project = path.parent().map(Path::to_path_buf);
+                if let Some(project) = project.filter(|p| shared.projects.contains(p)) {
+                    // A project's .claude directory was created
+                    if path.file_name() == Some(".claude".as_ref())
+                        && path.is_dir()
+                        && !shared.dirs.contains_key(&path)
+                    {
+                        match shared.watch_dir(path.clone(), Some(project)) {
+                            Ok(()) => {
+                                if let Some(watched) = shared.dirs.get_mut(&path) {
+                                    // Everything in it is new
+                                    let skills = std::mem::take(&mut watched.skills);
+                                    changes
+                                        .entry(path.clone())
+                                        .or_default()
+                                        .extend(skills.into_iter().map(Change::Skill));
+                                }
+                            }
+                            Err(e) => log::warn!("{}", e),
+                        }
+                    }
+                    if path.file_name() != Some(".claude".as_ref()) {
+                        continue;
+                    }
+                }
+                let Some((dir, relative)) = shared.locate(&path) else {
+                    continue;
+                };
+                let user_dir = shared.dirs[&dir].project.is_none();
+                if let Some(change) = classify(&relative, user_dir) {
+                    changes.entry(dir).or_default().insert(change);
+                }
+            }
+
+            for (dir, changes) in changes {
+                let Some(watched) = shared.dirs.get_mut(&dir) else {
+                    continue;
+                };
+                let event = |name: Option<String>| ClaudeDirChange {
+                    scope: if watched.project.is_some() {
+                        "project"
+                    } else {
+                        "user"
+                    }
+                    .to_string(),
+                    project_path: watched
+                        .project
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string()),
+                    name,
+                };
+
+                let mut touched = BTreeSet::new();
+                for change in changes {
+                    match change {
+                        Change::Skill(name) => {
+                            touched.insert(name);
+                        }
+                        Change::Agents => published.push(("agents-changed", event(None))),
+                        Change::Commands => published.push(("commands-changed", event(None))),
+                        Change::Settings(file) => {
+                            published.push(("settings-changed", event(Some(file))))
+                        }
+                        Change::Session {
+                            project_id,
+                            session_id,
+                        } => {
+                            sessions.insert((project_id, session_id));
+                        }
+                    }
+                }
+                if touched.is_empty() {
+                    continue;
+                }
+                let current = skill_names(&dir);
+                for name in &touched {
+                    let topic = match (watched.skills.contains(name), current.contains(name)) {
+                        (false, true) => "skill-added",
+                        (true, false) => "skill-removed",
+                        (true, true) => "skill-changed",
+                        // Files of a skill without SKILL.md yet
+                        (false, false) => continue,
+                    };
+                    published.push((topic, event(Some(name.clone()))));
+                }
+                watched.skills = current;
+            }
+        }
+
+        for (topic, change) in published {
+            event_bus::publish(app, topic, &change);
+        }
+        for (project_id, session_id) in sessions {
+            let _ = app.emit(
+                "session-updated",
+                SessionUpdated {
+                    project_id,
+                    session_id,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let skill = classify(Path::new("skills/pdf/scripts/fill.py"), false);
+        assert_eq!(skill, Some(Change::Skill("pdf".to_string())));
+        assert_eq!(
+            classify(Path::new("agents/reviewer.md"), true),
+            Some(Change::Agents)
+        );
+        assert_eq!(
+            classify(Path::new("settings.local.json"), false),
+            Some(Change::Settings("settings.local.json".to_string()))
+        );
+        assert_eq!(
+            classify(Path::new("projects/-home-me-app/abc.jsonl"), true),
+            Some(Change::Session {
+                project_id: "-home-me-app".to_string(),
+                session_id: "abc".to_string(),
+            })
+        );
+        // Transcripts only count in ~/.claude, and other files not at all
+        assert_eq!(classify(Path::new("projects/x/abc.jsonl"), false), None);
+        assert_eq!(classify(Path::new("todos/abc.json"), true), None);
+    }
+}
@@ -0,0 +1,249 @@
+// Which features the installed Claude Code CLI supports
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::claude_binary::{compare_versions, find_claude_binary, get_claude_version};
+
+/// How long a binary's detected version is reused
+const VERSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A CLI feature and the first release known to support it
+pub struct CliFeature {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub min_version: &'static str,
+    /// The command line flag that needs the feature, if any
+    pub flag: Option<&'static str>,
+    /// Runs can go on without the flag, e.g. with coarser streaming
+    pub optional: bool,
+}
+
+pub const CLI_FEATURES: &[CliFeature] = &[
+    CliFeature {
+        id: "stream_json",
+        description: "Streaming JSON output",
+        min_version: "1.0.0",
+        flag: Some("--output-format"),
+        optional: false,
+    },
+    CliFeature {
+        id: "resume",
+        description: "Resuming sessions by ID",
+        min_version: "1.0.0",
+        flag: Some("--resume"),
+        optional: false,
+    },
+    CliFeature {
+        id: "add_dir",
+        description: "Additional working directories",
+        min_version: "1.0.18",
+        flag: Some("--add-dir"),
+        optional: false,
+    },
+    CliFeature {
+        id: "hooks",
+        description: "Hooks in settings files",
+        min_version: "1.0.38",
+        flag: None,
+        optional: true,
+    },
+    CliFeature {
+        id: "partial_messages",
+        description: "Streaming partial messages",
+        min_version: "1.0.86",
+        flag: Some("--include-partial-messages"),
+        optional: true,
+    },
+    CliFeature {
+        id: "system_prompt",
+        description: "Replacing the system prompt",
+        min_version: "2.0.14",
+        flag: Some("--system-prompt"),
+        optional: false,
+    },
+    CliFeature {
+        id: "skills",
+        description: "Agent skills",
+        min_version: "2.0.20",
+        flag: None,
+        optional: true,
+    },
+];
+
+/// Whether the installed CLI supports one feature
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureSupport {
+    pub id: String,
+    pub description: String,
+    pub min_version: String,
+    pub flag: Option<String>,
+    pub supported: bool,
+}
+
+/// What the installed Claude Code CLI supports
+#[derive(Debug, Clone, Serialize)]
+pub struct CliCapabilities {
+    pub binary: Option<String>,
+    /// `None` when the CLI isn't installed or its version couldn't be read
+    pub version: Option<String>,
+    pub features: Vec<FeatureSupport>,
+    /// Unsupported features and why the matrix may be incomplete
+    pub warnings: Vec<String>,
+}
+
+fn version_cache() -> &'static Mutex<HashMap<String, (Option<String>, Instant)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Option<String>, Instant)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Version of the CLI at `binary`, cached for a few minutes
+pub fn cli_version(binary: &str) -> Option<String> {
+    if let Ok(cache) = version_cache().lock() {
+        if let Some((version, at)) = cache.get(binary) {
+            if at.elapsed() < VERSION_TTL {
+                return version.clone();
+            }
+        }
+    }
+    let version = get_claude_version(binary).ok().flatten();
+    if let Ok(mut cache) = version_cache().lock() {
+        cache.insert(binary.to_string(), (version.clone(), Instant::now()));
+    }
+    version
+}
+
+fn supported(feature: &CliFeature, version: Option<&str>) -> bool {
+    version.is_none_or(|v| compare_versions(v, feature.min_version) != Ordering::Less)
+}
+
+fn feature(id: &str) -> Option<&'static CliFeature> {
+    CLI_FEATURES.iter().find(|f| f.id == id)
+}
+
+fn upgrade_hint(feature: &CliFeature, version: &str) -> String {
+    format!(
+        "Claude Code {} doesn't support {} ({} or later is needed); update it with `claude update`",
+        version,
+        feature.description.to_lowercase(),
+        feature.min_version
+    )
+}
+
+/// Whether the CLI at `binary` supports the feature `id`
+///
+/// When the version can't be read, every feature is assumed to be supported.
+pub fn supports(binary: &str, id: &str) -> bool {
+    feature(id).is_none_or(|f| supported(f, cli_version(binary).as_deref()))
+}
+
+/// Drop optional flags the CLI at `binary` doesn't know, or explain which
+/// required one it lacks
+pub fn adapt_args(binary: &str, args: Vec<String>) -> Result<Vec<String>, String> {
+    let Some(version) = cli_version(binary) else {
+        return Ok(args);
+    };
+    adapt_args_for(&version, args)
+}
+
+fn adapt_args_for(version: &str, args: Vec<String>) -> Result<Vec<String>, String> {
+    let mut adapted = Vec::with_capacity(args.len());
+    for arg in args {
+        let unsupported = CLI_FEATURES
+            .iter()
+            .find(|f| f.flag == Some(arg.as_str()) && !supported(f, Some(version)));
+        match unsupported {
+            Some(feature) if feature.optional => {
+                log::warn!("Leaving out {}: {}", arg, upgrade_hint(feature, version));
+            }
+            Some(feature) => return Err(upgrade_hint(feature, version)),
+            None => adapted.push(arg),
+        }
+    }
+    Ok(adapted)
+}
+
+/// Warning for a feature the CLI at `binary` lacks, for runs that can go on without it
+pub fn unsupported_warning(binary: &str, id: &str) -> Option<String> {
+    let version = cli_version(binary)?;
+    feature(id)
+        .filter(|f| !supported(f, Some(&version)))
+        .map(|f| upgrade_hint(f, &version))
+}
+
+/// Detect the installed CLI and what it supports
+pub fn detect(app: &AppHandle) -> CliCapabilities {
+    let binary = find_claude_binary(app).ok();
+    let version = binary.as_deref().and_then(cli_version);
+    let features: Vec<FeatureSupport> = CLI_FEATURES
+        .iter()
+        .map(|f| FeatureSupport {
+            id: f.id.to_string(),
+            description: f.description.to_string(),
+            min_version: f.min_version.to_string(),
+            flag: f.flag.map(str::to_string),
+            supported: binary.is_some() && supported(f, version.as_deref()),
+        })
+        .collect();
+
+    let mut warnings = Vec::new();
+    match (&binary, &version) {
+        (None, _) => warnings.push("Claude Code is not installed".to_string()),
+        (Some(_), None) => warnings.push(
+            "The Claude Code version couldn't be read; all features are assumed to work"
+                .to_string(),
+        ),
+        (Some(_), Some(version)) => warnings.extend(
+            CLI_FEATURES
+                .iter()
+                .filter(|f| !supported(f, Some(version)))
+                .map(|f| upgrade_hint(f, version)),
+        ),
+    }
+    CliCapabilities {
+        binary,
+        version,
+        features,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapt_args_for() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let run = args(&[
+            "-p",
+            "hi",
+            "--output-format",
+            "stream-json",
+            "--include-partial-messages",
+            "--add-dir",
+            "/tmp/shared",
+        ]);
+
+        assert_eq!(adapt_args_for("2.0.30", run.clone()).unwrap(), run);
+        // Partial messages are optional and dropped
+        assert_eq!(
+            adapt_args_for("1.0.50", run.clone()).unwrap(),
+            args(&[
+                "-p",
+                "hi",
+                "--output-format",
+                "stream-json",
+                "--add-dir",
+                "/tmp/shared"
+            ])
+        );
+        // --add-dir is required
+        let error = adapt_args_for("1.0.10", run).unwrap_err();
+        assert!(error.contains("1.0.18"), "{}", error);
+        assert!(supported(feature("hooks").unwrap(), None));
+    }
+}
@@ -0,0 +1,341 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use super::agents::{
+    get_agent_run, get_agent_run_with_metrics, start_agent_run, AgentDb, AgentRun, AgentRunMetrics,
+};
+use super::git::git;
+use super::worktrees::{create_agent_worktree, discard_worktree, get_worktree};
+use crate::path_validation::validate_project_root;
+use crate::process::ProcessRegistryState;
+
+/// Variants one race can run side by side
+const MAX_VARIANTS: usize = 8;
+
+/// One way of doing the race's task
+#[derive(Debug, Clone, Deserialize)]
+pub struct RaceVariant {
+    pub agent_id: i64,
+    /// Defaults to the agent's model
+    pub model: Option<String>,
+    /// Replaces the race's task for this variant, e.g. a reworded prompt
+    pub task: Option<String>,
+    /// Shown in the results view; defaults to the variant number and model
+    pub label: Option<String>,
+}
+
+/// A variant of a race and the run doing it
+#[derive(Debug, Clone, Serialize)]
+pub struct RaceRun {
+    /// 1-based position in the variants the race was started with
+    pub variant: i64,
+    pub label: String,
+    /// Output streams as `agent-output:<run_id>`; `None` if it failed to start
+    pub run_id: Option<i64>,
+    pub status: Option<String>,
+    pub worktree_id: Option<i64>,
+    pub branch: Option<String>,
+    /// Why the variant didn't start
+    pub error: Option<String>,
+}
+
+/// Agents running the same task side by side, each in its own worktree
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentRace {
+    pub id: String,
+    pub project_path: String,
+    pub task: String,
+    pub runs: Vec<RaceRun>,
+}
+
+/// A finished or running race run, for the results view
+#[derive(Debug, Clone, Serialize)]
+pub struct RunComparison {
+    pub run: AgentRun,
+    pub metrics: Option<AgentRunMetrics>,
+    /// The run's final answer, from the `result` message of its session
+    pub result: Option<String>,
+    /// Branch of the worktree the run worked in, if any
+    pub branch: Option<String>,
+    pub worktree_id: Option<i64>,
+    /// Changes since the worktree was created, committed or not
+    pub files_changed: u64,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+/// Create the agent_race_runs table
+pub fn init_agent_races(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS agent_race_runs (
+            race_id TEXT NOT NULL,
+            variant INTEGER NOT NULL,
+            project_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            label TEXT NOT NULL,
+            run_id INTEGER,
+            worktree_id INTEGER,
+            branch TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (race_id, variant)
+        );
+        CREATE INDEX IF NOT EXISTS idx_agent_race_runs_run ON agent_race_runs(run_id);",
+    )
+}
+
+/// Files changed, lines added and lines removed in `git diff --numstat` output;
+/// binary files count as changed without lines
+pub fn diff_totals(numstat: &str) -> (u64, u64, u64) {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let added = fields.next()?;
+            let removed = fields.next()?;
+            fields.next()?;
+            Some((added.parse().unwrap_or(0), removed.parse().unwrap_or(0)))
+        })
+        .fold((0, 0, 0), |(files, add, del), (a, d)| {
+            (files + 1, add + a, del + d)
+        })
+}
+
+/// Text of the last `result` message of a session transcript
+pub fn final_result(jsonl: &str) -> Option<String> {
+    jsonl.lines().rev().find_map(|line| {
+        let entry: JsonValue = serde_json::from_str(line).ok()?;
+        if entry["type"] != "result" {
+            return None;
+        }
+        entry["result"].as_str().map(str::to_string)
+    })
+}
+
+fn load_race(conn: &Connection, race_id: &str) -> Result<AgentRace, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.project_path, r.task, r.variant, r.label, r.run_id, a.status,
+                    r.worktree_id, r.branch, r.error
+             FROM agent_race_runs r LEFT JOIN agent_runs a ON a.id = r.run_id
+             WHERE r.race_id = ?1 ORDER BY r.variant",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![race_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                RaceRun {
+                    variant: row.get(2)?,
+                    label: row.get(3)?,
+                    run_id: row.get(4)?,
+                    status: row.get(5)?,
+                    worktree_id: row.get(6)?,
+                    branch: row.get(7)?,
+                    error: row.get(8)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    let (project_path, task) = rows
+        .first()
+        .map(|(project, task, _)| (project.clone(), task.clone()))
+        .ok_or_else(|| format!("Race {} not found", race_id))?;
+    Ok(AgentRace {
+        id: race_id.to_string(),
+        project_path,
+        task,
+        runs: rows.into_iter().map(|(_, _, run)| run).collect(),
+    })
+}
+
+/// Run several agent variants on the same task at once, each in its own worktree
+///
+/// Every variant gets a fresh worktree on a `agent-race/<race>-<n>` branch
+/// from the project's current commit, so the runs can't step on each other or
+/// on the live checkout. Each run streams like any agent run, as
+/// `agent-output:<run_id>`. A variant that fails to start is reported in the
+/// race and its worktree removed; the others still run. Keep the winner with
+/// `merge_worktree_changes` and throw the rest away with `discard_worktree`.
+#[tauri::command]
+pub async fn start_agent_race(
+    window: tauri::Window,
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    registry: State<'_, ProcessRegistryState>,
+    project_path: String,
+    task: String,
+    variants: Vec<RaceVariant>,
+) -> Result<AgentRace, String> {
+    let project = validate_project_root(&project_path)?;
+    let project_path = project.to_string_lossy().to_string();
+    if task.trim().is_empty() {
+        return Err("The task is empty".to_string());
+    }
+    if !(2..=MAX_VARIANTS).contains(&variants.len()) {
+        return Err(format!(
+            "A race needs between 2 and {} variants, got {}",
+            MAX_VARIANTS,
+            variants.len()
+        ));
+    }
+
+    let race_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    for (i, variant) in variants.into_iter().enumerate() {
+        let number = i + 1;
+        let label = variant
+            .label
+            .clone()
+            .filter(|l| !l.trim().is_empty())
+            .unwrap_or_else(|| match &variant.model {
+                Some(model) => format!("#{} {}", number, model),
+                None => format!("#{}", number),
+            });
+        let variant_task = variant
+            .task
+            .clone()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| task.clone());
+        let branch = format!("agent-race/{}-{}", race_id, number);
+
+        let mut worktree_id = None;
+        let started = async {
+            let worktree =
+                create_agent_worktree(db.clone(), project_path.clone(), branch.clone()).await?;
+            worktree_id = Some(worktree.id);
+            start_agent_run(
+                app.clone(),
+                Some(window.label().to_string()),
+                variant.agent_id,
+                worktree.path,
+                variant_task,
+                variant.model.clone(),
+                None,
+                None,
+                db.clone(),
+                registry.clone(),
+            )
+            .await
+        }
+        .await;
+        let (run_id, error) = match started {
+            Ok(run_id) => (Some(run_id), None),
+            Err(e) => {
+                log::warn!("Race {} variant {} failed to start: {}", race_id, number, e);
+                if let Some(id) = worktree_id.take() {
+                    if let Err(e) = discard_worktree(db.clone(), id).await {
+                        log::warn!("Failed to remove worktree {}: {}", id, e);
+                    }
+                }
+                (None, Some(e))
+            }
+        };
+
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO agent_race_runs
+                (race_id, variant, project_path, task, label, run_id, worktree_id, branch, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                race_id,
+                number as i64,
+                project_path,
+                task,
+                label,
+                run_id,
+                worktree_id,
+                worktree_id.map(|_| &branch),
+                error,
+            ],
+        )
+        .map_err(|e| format!("Failed to record race run: {}", e))?;
+    }
+
+    log::info!("Started agent race {} in {}", race_id, project_path);
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_race(&conn, &race_id)
+}
+
+/// A race's variants with the current status of each run
+#[tauri::command]
+pub async fn get_agent_race(db: State<'_, AgentDb>, race_id: String) -> Result<AgentRace, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_race(&conn, &race_id)
+}
+
+/// Runs side by side: status, cost and tokens, final answer and what each
+/// changed in its worktree
+#[tauri::command]
+pub async fn compare_run_outputs(
+    db: State<'_, AgentDb>,
+    run_ids: Vec<i64>,
+) -> Result<Vec<RunComparison>, String> {
+    let mut comparisons = Vec::with_capacity(run_ids.len());
+    for run_id in run_ids {
+        let run = get_agent_run(db.clone(), run_id).await?;
+        let worktree_id: Option<i64> = {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            conn.query_row(
+                "SELECT worktree_id FROM agent_race_runs WHERE run_id = ?1",
+                params![run_id],
+                |row| row.get(0),
+            )
+            .unwrap_or(None)
+        };
+        // The worktree is gone once it was merged or discarded
+        let worktree = worktree_id.and_then(|id| get_worktree(&db, id).ok());
+
+        let (files_changed, additions, deletions) = match &worktree {
+            Some(worktree) => {
+                let path = Path::new(&worktree.path);
+                // Count new files too, without staging their content
+                let _ = git(path, &["add", "-A", "-N"]).await;
+                git(path, &["diff", "--numstat", &worktree.base_sha, "--", "."])
+                    .await
+                    .map(|numstat| diff_totals(&numstat))
+                    .unwrap_or_default()
+            }
+            None => (0, 0, 0),
+        };
+
+        let with_metrics = get_agent_run_with_metrics(run).await;
+        comparisons.push(RunComparison {
+            result: with_metrics.output.as_deref().and_then(final_result),
+            metrics: with_metrics.metrics,
+            run: with_metrics.run,
+            branch: worktree.as_ref().map(|w| w.branch.clone()),
+            worktree_id: worktree.map(|w| w.id),
+            files_changed,
+            additions,
+            deletions,
+        });
+    }
+    Ok(comparisons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_totals_and_final_result() {
+        let numstat = "3\t1\tsrc/main.rs\n-\t-\tlogo.png\n10\t0\tsrc/new.rs\n";
+        assert_eq!(diff_totals(numstat), (3, 13, 1));
+        assert_eq!(diff_totals(""), (0, 0, 0));
+
+        let jsonl = concat!(
+            r#"{"type":"assistant","message":{"content":[]}}"#,
+            "\n",
+            r#"{"type":"result","subtype":"success","result":"Fixed the test"}"#,
+            "\n"
+        );
+        assert_eq!(final_result(jsonl).as_deref(), Some("Fixed the test"));
+        assert_eq!(final_result("not json"), None);
+    }
+}
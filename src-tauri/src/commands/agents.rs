@@ -2,18 +2,41 @@ use anyhow::Result;
 use chrono;
 use dirs;
 use log::{debug, error, info, warn};
-use reqwest;
-use rusqlite::{params, Connection, Result as SqliteResult};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::io::{BufRead, BufReader};
 use std::process::Stdio;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager, State};
 // Sidecar support removed; using system binary execution only
 use tokio::io::{AsyncBufReadExt, BufReader as TokioBufReader};
 use tokio::process::Command;
 
+use crate::cli_compat;
+use crate::commands::run_history;
+use crate::commands::run_manifest;
+use crate::commands::streaming;
+use crate::commands::telemetry;
+use crate::db_health::open_connection;
+use crate::event_bus;
+use crate::file_audit::{self, FileOperation};
+use crate::network::{send_with_retry, NetworkError};
+use crate::notifications::RunNotifier;
+use crate::otlp::{self, RunTracer};
+use crate::process::output_stream::OutputBatcher;
+use crate::process::RunUsage;
+use crate::project_locks::{LockHolder, ProjectLockGuard, ProjectLocks};
+use crate::redaction;
+use crate::run_env::{
+    apply_env_overrides, record_env_overrides, validate_env_overrides, EnvOverrides,
+};
+use crate::run_queue;
+use crate::sandbox::{self, SandboxPlan};
+use crate::schema_drift;
+use crate::window_sessions::{self, WindowSessions};
+use crate::workspace::{add_dir_args, validate_additional_dirs};
+
 /// Finds the full path to the claude binary
 /// This is necessary because macOS apps have a limited PATH environment
 fn find_claude_binary(app_handle: &AppHandle) -> Result<String, String> {
@@ -82,7 +105,7 @@ pub struct AgentExport {
 }
 
 /// Agent data within export
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AgentData {
     pub name: String,
     pub icon: String,
@@ -174,7 +197,7 @@ pub async fn read_session_jsonl(session_id: &str, project_path: &str) -> Result<
         .join("projects");
 
     // Encode project path to match Claude Code's directory naming
-    let encoded_project = project_path.replace('/', "-");
+    let encoded_project = crate::utils::encode_project_path(project_path);
     let project_dir = claude_dir.join(&encoded_project);
     let session_file = project_dir.join(format!("{}.jsonl", session_id));
 
@@ -221,8 +244,8 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         .expect("Failed to get app data dir");
     std::fs::create_dir_all(&app_dir).expect("Failed to create app data dir");
 
-    let db_path = app_dir.join("agents.db");
-    let conn = Connection::open(db_path)?;
+    // Restores the latest backup if the database is corrupt
+    let conn = crate::db_health::open_database(&app_dir)?;
 
     // Create agents table
     conn.execute(
@@ -342,6 +365,24 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     )?;
 
+    // Create run history table for sessions and agent runs
+    crate::commands::run_history::init_run_history(&conn)?;
+    crate::sandbox::profile::init_execution_profiles(&conn)?;
+    crate::commands::bookmarks::init_bookmarks(&conn)?;
+    crate::commands::file_history::init_file_history(&conn)?;
+    crate::commands::schedules::init_schedules(&conn)?;
+    crate::commands::worktrees::init_worktrees(&conn)?;
+    crate::commands::agent_race::init_agent_races(&conn)?;
+    crate::commands::benchmarks::init_benchmarks(&conn)?;
+    crate::run_queue::init_run_queue(&conn)?;
+    crate::project_env::init_project_env(&conn)?;
+    crate::prompt_history::init_prompt_history(&conn)?;
+    crate::project_registry::init_project_registry(&conn)?;
+    crate::event_bus::init_event_log(&conn)?;
+    crate::error_reports::init_error_reports(&conn)?;
+    crate::commands::settings::migrate_settings(&conn)?;
+    crate::db_health::backup_if_stale(&conn, &app_dir);
+
     Ok(conn)
 }
 
@@ -677,59 +718,88 @@ pub async fn list_agent_runs_with_metrics(
 }
 
 /// Execute a CC agent with streaming output
+///
+/// `env` sets extra environment variables for this run only. The run is
+/// attached to the calling window.
 #[tauri::command]
 pub async fn execute_agent(
+    window: tauri::Window,
+    app: AppHandle,
+    agent_id: i64,
+    project_path: String,
+    task: String,
+    model: Option<String>,
+    env: Option<EnvOverrides>,
+    additional_dirs: Option<Vec<String>>,
+    db: State<'_, AgentDb>,
+    registry: State<'_, crate::process::ProcessRegistryState>,
+) -> Result<i64, String> {
+    start_agent_run(
+        app,
+        Some(window.label().to_string()),
+        agent_id,
+        project_path,
+        task,
+        model,
+        env,
+        additional_dirs,
+        db,
+        registry,
+    )
+    .await
+}
+
+/// Start an agent run; returns its ID
+///
+/// With a `window`, the run's events go to that window only; runs started by
+/// the scheduler, the run queue or the control server go to every window.
+pub async fn start_agent_run(
     app: AppHandle,
+    window: Option<String>,
     agent_id: i64,
     project_path: String,
     task: String,
     model: Option<String>,
+    env: Option<EnvOverrides>,
+    additional_dirs: Option<Vec<String>>,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
     info!("Executing agent {} with task: {}", agent_id, task);
+    let env = env.unwrap_or_default();
+    validate_env_overrides(&env)?;
+    let additional_dirs =
+        validate_additional_dirs(&project_path, &additional_dirs.unwrap_or_default())?;
 
     // Get the agent from database
     let agent = get_agent(db.clone(), agent_id).await?;
     let execution_model = model.unwrap_or(agent.model.clone());
 
-    // Create .claude/settings.json with agent hooks if it doesn't exist
-    if let Some(hooks_json) = &agent.hooks {
-        let claude_dir = std::path::Path::new(&project_path).join(".claude");
-        let settings_path = claude_dir.join("settings.json");
-
-        // Create .claude directory if it doesn't exist
-        if !claude_dir.exists() {
-            std::fs::create_dir_all(&claude_dir)
-                .map_err(|e| format!("Failed to create .claude directory: {}", e))?;
-            info!("Created .claude directory at: {:?}", claude_dir);
-        }
-
-        // Check if settings.json already exists
-        if !settings_path.exists() {
-            // Parse the hooks JSON
-            let hooks: serde_json::Value = serde_json::from_str(hooks_json)
-                .map_err(|e| format!("Failed to parse agent hooks: {}", e))?;
-
-            // Create a settings object with just the hooks
-            let settings = serde_json::json!({
-                "hooks": hooks
-            });
-
-            // Write the settings file
-            let settings_content = serde_json::to_string_pretty(&settings)
-                .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-            std::fs::write(&settings_path, settings_content)
-                .map_err(|e| format!("Failed to write settings.json: {}", e))?;
+    let tracer = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        telemetry::start_run_trace(&conn, "agent.run")
+    };
+    if let Some(tracer) = &tracer {
+        tracer.set_attribute("agent.id", agent_id);
+        tracer.set_attribute("agent.name", agent.name.clone());
+        tracer.set_attribute("run.model", execution_model.clone());
+        tracer.set_attribute("run.project_path", project_path.clone());
+    }
 
-            info!(
-                "Created settings.json with agent hooks at: {:?}",
-                settings_path
-            );
-        } else {
-            info!("settings.json already exists at: {:?}", settings_path);
+    // Set up hooks and locate the Claude binary before queueing the run
+    let validation_span = tracer.as_ref().map(|t| t.start_span("run.validate"));
+    let validation = match &agent.hooks {
+        Some(hooks_json) => write_agent_hooks_settings(&project_path, hooks_json),
+        None => Ok(()),
+    };
+    if let (Some(tracer), Some(span)) = (&tracer, validation_span) {
+        tracer.end_span(span, validation.as_ref().err().cloned());
+    }
+    if let Err(e) = validation {
+        if let Some(tracer) = &tracer {
+            tracer.finish(Some(e.clone()));
         }
+        return Err(e);
     }
 
     // Create a new run record
@@ -742,6 +812,14 @@ pub async fn execute_agent(
         .map_err(|e| e.to_string())?;
         conn.last_insert_rowid()
     };
+    let queued_at = otlp::now_ns();
+    if let Some(tracer) = &tracer {
+        tracer.set_attribute("run.id", run_id);
+    }
+    if let Some(label) = &window {
+        app.state::<WindowSessions>()
+            .attach(&run_id.to_string(), label);
+    }
 
     // Find Claude binary
     info!("Running agent '{}'", agent.name);
@@ -749,12 +827,15 @@ pub async fn execute_agent(
         Ok(path) => path,
         Err(e) => {
             error!("Failed to find claude binary: {}", e);
+            if let Some(tracer) = &tracer {
+                tracer.finish(Some(e.clone()));
+            }
             return Err(e);
         }
     };
 
     // Build arguments
-    let args = vec![
+    let mut args = vec![
         "-p".to_string(),
         task.clone(),
         "--system-prompt".to_string(),
@@ -766,6 +847,156 @@ pub async fn execute_agent(
         "--verbose".to_string(),
         "--dangerously-skip-permissions".to_string(),
     ];
+    args.extend(add_dir_args(&additional_dirs));
+    let args = match cli_compat::adapt_args(&claude_path, args) {
+        Ok(args) => args,
+        Err(e) => {
+            if let Some(tracer) = &tracer {
+                tracer.finish(Some(e.clone()));
+            }
+            return Err(e);
+        }
+    };
+    if agent.hooks.is_some() {
+        if let Some(warning) = cli_compat::unsupported_warning(&claude_path, "hooks") {
+            warn!("Hooks of agent '{}' won't run: {}", agent.name, warning);
+        }
+    }
+
+    // Apply the agent's execution profile, if any
+    let execution_profile = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        sandbox::profile::profile_for_agent(&conn, agent_id).map_err(|e| e.to_string())?
+    };
+    let sandbox_plan = execution_profile.map(|mut profile| {
+        // The run may write to every root of its workspace
+        profile
+            .allowed_paths
+            .extend(additional_dirs.iter().cloned());
+        info!(
+            "Applying execution profile '{}' to agent run {}",
+            profile.name, run_id
+        );
+        SandboxPlan::prepare(&profile, &project_path, &claude_path)
+    });
+    let sandbox_plan = match sandbox_plan.transpose() {
+        Ok(plan) => plan,
+        Err(e) => {
+            error!(
+                "Execution profile can't be enforced for run {}: {}",
+                run_id, e
+            );
+            if let Some(tracer) = &tracer {
+                tracer.finish(Some(e.clone()));
+            }
+            return Err(e);
+        }
+    };
+    let args = match &sandbox_plan {
+        Some(plan) => plan.apply_args(args),
+        None => args,
+    };
+
+    // Only one agent at a time edits a working tree
+    let locks = app.state::<ProjectLocks>().inner().clone();
+    let holder = LockHolder {
+        run_id,
+        label: agent.name.clone(),
+    };
+    let project_lock = match locks.try_acquire(&project_path, holder.clone()) {
+        Ok(guard) => Some(guard),
+        Err(busy) => {
+            let policy = {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                crate::commands::settings::get_text(&conn, "project_lock_policy")
+            };
+            let conflict = serde_json::json!({
+                "run_id": run_id,
+                "project_path": project_path,
+                "blocking_run_id": busy.run_id,
+                "blocking_label": busy.label,
+            });
+            if policy.as_deref() == Some("warn") {
+                warn!(
+                    "Agent run {} shares {} with run {}",
+                    run_id, project_path, busy.run_id
+                );
+                event_bus::publish(&app, "project-lock-conflict", &conflict);
+                None
+            } else {
+                info!(
+                    "Agent run {} waits for run {} to finish in {}",
+                    run_id, busy.run_id, project_path
+                );
+                event_bus::publish(&app, "project-lock-queued", &conflict);
+                // Written down so the run isn't lost if the app exits while it waits
+                {
+                    let conn = db.0.lock().map_err(|e| e.to_string())?;
+                    let queued = run_queue::NewQueuedRun {
+                        reason: run_queue::REASON_PROJECT_LOCK,
+                        agent_id,
+                        project_path: project_path.clone(),
+                        task: task.clone(),
+                        model: Some(execution_model.clone()),
+                        env_keys: env.keys().cloned().collect(),
+                        additional_dirs: additional_dirs.clone(),
+                        agent_run_id: Some(run_id),
+                        ..Default::default()
+                    };
+                    if let Err(e) = run_queue::enqueue(&conn, &queued, "waiting") {
+                        warn!("Failed to persist queued agent run {}: {}", run_id, e);
+                    }
+                }
+                tauri::async_runtime::spawn(async move {
+                    let guard = locks.acquire(&project_path, holder).await;
+                    let db = app.state::<AgentDb>();
+                    let pending = db.0.lock().ok().and_then(|conn| {
+                        let _ = run_queue::remove_waiting(&conn, run_id);
+                        conn.query_row(
+                            "SELECT status = 'pending' FROM agent_runs WHERE id = ?1",
+                            params![run_id],
+                            |row| row.get::<_, bool>(0),
+                        )
+                        .ok()
+                    });
+                    // Cancelled while it waited
+                    if pending != Some(true) {
+                        return;
+                    }
+                    let result = spawn_agent_system(
+                        app.clone(),
+                        run_id,
+                        agent_id,
+                        agent.name.clone(),
+                        claude_path,
+                        args,
+                        project_path,
+                        task,
+                        execution_model,
+                        env,
+                        db,
+                        app.state::<crate::process::ProcessRegistryState>(),
+                        tracer,
+                        queued_at,
+                        sandbox_plan,
+                        Some(guard),
+                    )
+                    .await;
+                    if let Err(e) = result {
+                        error!("Queued agent run {} failed to start: {}", run_id, e);
+                        if let Ok(conn) = app.state::<AgentDb>().0.lock() {
+                            let _ = conn.execute(
+                                "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                                params![run_id],
+                            );
+                        }
+                        event_bus::publish(&app, &format!("agent-complete:{}", run_id), &false);
+                    }
+                });
+                return Ok(run_id);
+            }
+        }
+    };
 
     // Always use system binary execution (sidecar removed)
     spawn_agent_system(
@@ -778,19 +1009,78 @@ pub async fn execute_agent(
         project_path,
         task,
         execution_model,
+        env,
         db,
         registry,
+        tracer,
+        queued_at,
+        sandbox_plan,
+        project_lock,
     )
     .await
 }
 
+/// Creates .claude/settings.json with the agent's hooks if it doesn't exist
+fn write_agent_hooks_settings(project_path: &str, hooks_json: &str) -> Result<(), String> {
+    let claude_dir = std::path::Path::new(project_path).join(".claude");
+    let settings_path = claude_dir.join("settings.json");
+
+    // Create .claude directory if it doesn't exist
+    if !claude_dir.exists() {
+        std::fs::create_dir_all(&claude_dir)
+            .map_err(|e| format!("Failed to create .claude directory: {}", e))?;
+        info!("Created .claude directory at: {:?}", claude_dir);
+    }
+
+    // Check if settings.json already exists
+    if !settings_path.exists() {
+        // Parse the hooks JSON
+        let hooks: serde_json::Value = serde_json::from_str(hooks_json)
+            .map_err(|e| format!("Failed to parse agent hooks: {}", e))?;
+
+        // Create a settings object with just the hooks
+        let settings = serde_json::json!({
+            "hooks": hooks
+        });
+
+        // Write the settings file
+        let settings_content = serde_json::to_string_pretty(&settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+        std::fs::write(&settings_path, settings_content)
+            .map_err(|e| format!("Failed to write settings.json: {}", e))?;
+        file_audit::record("execute_agent", FileOperation::Create, &settings_path);
+
+        info!(
+            "Created settings.json with agent hooks at: {:?}",
+            settings_path
+        );
+    } else {
+        info!("settings.json already exists at: {:?}", settings_path);
+    }
+
+    Ok(())
+}
+
 /// Creates a system binary command for agent execution
 fn create_agent_system_command(
     claude_path: &str,
     args: Vec<String>,
     project_path: &str,
+    sandbox_plan: Option<&SandboxPlan>,
 ) -> Command {
-    let mut cmd = create_command_with_env(claude_path);
+    let mut cmd = match sandbox_plan.and_then(|plan| plan.launcher.as_ref()) {
+        Some((launcher, launcher_args)) => {
+            let mut cmd = configure_command_env(Command::new(launcher), claude_path);
+            cmd.args(launcher_args);
+            cmd.arg(claude_path);
+            cmd
+        }
+        None => create_command_with_env(claude_path),
+    };
+    if let Some(plan) = sandbox_plan {
+        plan.apply_to_command(&mut cmd);
+    }
 
     // Add all arguments
     for arg in args {
@@ -816,18 +1106,62 @@ async fn spawn_agent_system(
     project_path: String,
     task: String,
     execution_model: String,
+    env: EnvOverrides,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
+    tracer: Option<Arc<RunTracer>>,
+    queued_at: u64,
+    sandbox_plan: Option<SandboxPlan>,
+    project_lock: Option<ProjectLockGuard>,
 ) -> Result<i64, String> {
+    if let Some(tracer) = &tracer {
+        tracer.record_span("run.queue", queued_at, otlp::now_ns());
+    }
+
+    // Surface restrictions the platform couldn't enforce
+    if let Some(plan) = &sandbox_plan {
+        for warning in &plan.warnings {
+            warn!("Execution profile for run {}: {}", run_id, warning);
+            event_bus::publish(
+                &app,
+                "agent-sandbox-warning",
+                &serde_json::json!({ "run_id": run_id, "message": warning }),
+            );
+        }
+    }
+
     // Build the command
-    let mut cmd = create_agent_system_command(&claude_path, args, &project_path);
+    let manifest_args = args.clone();
+    let mut cmd =
+        create_agent_system_command(&claude_path, args, &project_path, sandbox_plan.as_ref());
+    apply_env_overrides(
+        &mut cmd,
+        &crate::project_env::with_project_env(&app, &project_path, &env),
+    );
 
     // Spawn the process
     info!("🚀 Spawning Claude system process...");
-    let mut child = cmd.spawn().map_err(|e| {
+    let spawn_span = tracer.as_ref().map(|t| t.start_span("run.spawn"));
+    let spawned = cmd.spawn().map_err(|e| {
         error!("❌ Failed to spawn Claude process: {}", e);
         format!("Failed to spawn Claude: {}", e)
-    })?;
+    });
+    if let (Some(tracer), Some(span)) = (&tracer, spawn_span) {
+        let pid = spawned.as_ref().ok().and_then(|c| c.id()).unwrap_or(0);
+        tracer.end_span(
+            span.attribute("process.pid", pid as i64),
+            spawned.as_ref().err().cloned(),
+        );
+    }
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            if let Some(tracer) = &tracer {
+                tracer.finish(Some(e.clone()));
+            }
+            return Err(e);
+        }
+    };
 
     info!("🔌 Using Stdio::null() for stdin - no input expected");
 
@@ -837,15 +1171,50 @@ async fn spawn_agent_system(
     info!("✅ Claude process spawned successfully with PID: {}", pid);
 
     // Update the database with PID and status
-    {
+    let new_run = run_history::NewRun {
+        run_type: "agent",
+        project_path: project_path.clone(),
+        agent_id: Some(agent_id),
+        agent_name: Some(agent_name.clone()),
+        agent_run_id: Some(run_id),
+        prompt: task.clone(),
+        model: execution_model.clone(),
+        env_overrides: record_env_overrides(&env),
+        additional_dirs,
+    };
+    let history_id = {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
         conn.execute(
             "UPDATE agent_runs SET status = 'running', pid = ?1, process_started_at = ?2 WHERE id = ?3",
             params![pid as i64, now, run_id],
         ).map_err(|e| e.to_string())?;
         info!("📝 Updated database with running status and PID");
+
+        // Record the run in the persistent history
+        match run_history::record_run_started(&conn, &new_run) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                warn!("Failed to record agent run {} in history: {}", run_id, e);
+                None
+            }
+        }
+    };
+    if let Some(id) = history_id {
+        run_manifest::record_manifest_in_background(
+            &app,
+            id,
+            new_run,
+            claude_path,
+            manifest_args,
+            env,
+        );
     }
 
+    let streaming_settings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        streaming::load_streaming_settings(&conn)
+    };
+
     // Get stdout and stderr
     let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
@@ -875,103 +1244,119 @@ async fn spawn_agent_system(
     let first_output = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
     let first_output_clone = first_output.clone();
     let db_path_for_stdout = db_path.clone(); // Clone the db_path for the stdout task
+    let project_path_for_stdout = project_path.clone();
+    let tracer_for_stdout = tracer.clone();
+    let notifier = Arc::new(RunNotifier::new(app.clone(), agent_name.clone()));
+    let notifier_for_stdout = notifier.clone();
+
+    let batcher = OutputBatcher::spawn(app.clone(), None, streaming_settings.batch_config());
+    let emit_line_events = streaming_settings.emit_line_events;
+    let batch_event = format!("agent-output-batch:{}", run_id);
+    let run_key = run_id.to_string();
+    let run_key_stderr = run_key.clone();
 
     let stdout_task = tokio::spawn(async move {
         info!("📖 Starting to read Claude stdout...");
         let mut lines = stdout_reader.lines();
         let mut line_count = 0;
-        let mut buffer: Vec<String> = Vec::new();
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(50));
-        // Set the first tick to complete immediately
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         loop {
-            tokio::select! {
-                result = lines.next_line() => {
-                    match result {
-                        Ok(Some(line)) => {
-                            line_count += 1;
-
-                            // Log first output
-                            if !first_output_clone.load(std::sync::atomic::Ordering::Relaxed) {
-                                info!(
-                                    "🎉 First output received from Claude process! Line: {}",
-                                    line
-                                );
-                                first_output_clone.store(true, std::sync::atomic::Ordering::Relaxed);
-                            }
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    line_count += 1;
+                    // Hide secrets before the line is logged, stored or shown
+                    let line = redaction::redact_output(&app_handle, Some(&run_key), line);
+
+                    // Log first output
+                    if !first_output_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                        info!(
+                            "🎉 First output received from Claude process! Line: {}",
+                            line
+                        );
+                        first_output_clone.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
 
-                            if line_count <= 5 {
-                                info!("stdout[{}]: {}", line_count, line);
-                            } else {
-                                debug!("stdout[{}]: {}", line_count, line);
-                            }
+                    if line_count <= 5 {
+                        info!("stdout[{}]: {}", line_count, line);
+                    } else {
+                        debug!("stdout[{}]: {}", line_count, line);
+                    }
 
-                            // Store live output in both local buffer and registry
-                            if let Ok(mut output) = live_output_clone.lock() {
-                                output.push_str(&line);
-                                output.push('\n');
-                            }
+                    // Store live output in both local buffer and registry
+                    if let Ok(mut output) = live_output_clone.lock() {
+                        output.push_str(&line);
+                        output.push('\n');
+                    }
 
-                            // Also store in process registry for cross-session access
-                            let _ = registry_clone.append_live_output(run_id, &line);
-
-                            // Extract session ID from JSONL output
-                            if let Ok(json) = serde_json::from_str::<JsonValue>(&line) {
-                                if json.get("type").and_then(|t| t.as_str()) == Some("system")
-                                    && json.get("subtype").and_then(|s| s.as_str()) == Some("init")
-                                {
-                                    if let Some(sid) = json.get("session_id").and_then(|s| s.as_str()) {
-                                        if let Ok(mut current_session_id) = session_id_clone.lock() {
-                                            if current_session_id.is_empty() {
-                                                *current_session_id = sid.to_string();
-                                                info!("🔑 Extracted session ID: {}", sid);
-
-                                                if let Ok(conn) = Connection::open(&db_path_for_stdout) {
-                                                    let _ = conn.execute(
-                                                        "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
-                                                        params![sid, run_id],
-                                                    );
-                                                }
+                    // Also store in process registry for cross-session access
+                    let seq = registry_clone
+                        .append_live_output(run_id, &line)
+                        .ok()
+                        .flatten();
+
+                    // Extract session ID from JSONL output
+                    if let Ok(json) = serde_json::from_str::<JsonValue>(&line) {
+                        if json.get("type").and_then(|t| t.as_str()) == Some("system")
+                            && json.get("subtype").and_then(|s| s.as_str()) == Some("init")
+                        {
+                            if let Some(sid) = json.get("session_id").and_then(|s| s.as_str()) {
+                                if let Ok(mut current_session_id) = session_id_clone.lock() {
+                                    if current_session_id.is_empty() {
+                                        *current_session_id = sid.to_string();
+                                        info!("🔑 Extracted session ID: {}", sid);
+
+                                        if let Ok(conn) = open_connection(&db_path_for_stdout) {
+                                            let _ = conn.execute(
+                                                "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
+                                                params![sid, run_id],
+                                            );
+                                            if let Some(history_id) = history_id {
+                                                let _ = run_history::record_run_session(
+                                                    &conn,
+                                                    history_id,
+                                                    sid,
+                                                    &project_path_for_stdout,
+                                                );
                                             }
                                         }
                                     }
                                 }
                             }
-
-                            // Add to buffer
-                            buffer.push(line);
-
-                            // Flush if buffer is full
-                            if buffer.len() >= 50 {
-                                let _ = app_handle.emit(&format!("agent-output:{}", run_id), &buffer);
-                                let _ = app_handle.emit("agent-output", &buffer);
-                                buffer.clear();
-                            }
-                        }
-                        Ok(None) => break, // End of stream
-                        Err(e) => {
-                            error!("Error reading line: {}", e);
-                            break;
                         }
                     }
-                }
-                _ = interval.tick() => {
-                    // Flush buffer on interval
-                    if !buffer.is_empty() {
-                        let _ = app_handle.emit(&format!("agent-output:{}", run_id), &buffer);
-                        let _ = app_handle.emit("agent-output", &buffer);
-                        buffer.clear();
+
+                    // Track tool spans for trace export
+                    if let Some(tracer) = &tracer_for_stdout {
+                        tracer.observe_output_line(&line);
+                    }
+                    notifier_for_stdout.observe_line(&line);
+
+                    // Queue for the batched event stream
+                    batcher.push(&batch_event, seq, &line).await;
+
+                    if emit_line_events {
+                        window_sessions::emit(
+                            &app_handle,
+                            &format!("agent-output:{}", run_id),
+                            &line,
+                        );
+                        window_sessions::emit_to_window(
+                            &app_handle,
+                            window_sessions::window_for(&app_handle, &run_key).as_deref(),
+                            "agent-output",
+                            &line,
+                        );
                     }
                 }
+                Ok(None) => break, // End of stream
+                Err(e) => {
+                    error!("Error reading line: {}", e);
+                    break;
+                }
             }
         }
 
-        // Final flush
-        if !buffer.is_empty() {
-            let _ = app_handle.emit(&format!("agent-output:{}", run_id), &buffer);
-            let _ = app_handle.emit("agent-output", &buffer);
-        }
+        batcher.finish().await;
 
         info!(
             "📖 Finished reading Claude stdout. Total lines: {}",
@@ -990,6 +1375,7 @@ async fn spawn_agent_system(
 
         while let Ok(Some(line)) = lines.next_line().await {
             error_count += 1;
+            let line = redaction::redact_output(&app_handle_stderr, Some(&run_key_stderr), line);
 
             // Log first error
             if !first_error_clone.load(std::sync::atomic::Ordering::Relaxed) {
@@ -999,9 +1385,18 @@ async fn spawn_agent_system(
 
             error!("stderr[{}]: {}", error_count, line);
             // Emit error lines to the frontend with run_id for isolation
-            let _ = app_handle_stderr.emit(&format!("agent-error:{}", run_id), &line);
+            window_sessions::emit(
+                &app_handle_stderr,
+                &format!("agent-error:{}", run_id),
+                &line,
+            );
             // Also emit to the generic event for backward compatibility
-            let _ = app_handle_stderr.emit("agent-error", &line);
+            window_sessions::emit_to_window(
+                &app_handle_stderr,
+                window_sessions::window_for(&app_handle_stderr, &run_key_stderr).as_deref(),
+                "agent-error",
+                &line,
+            );
         }
 
         if error_count > 0 {
@@ -1031,9 +1426,12 @@ async fn spawn_agent_system(
     info!("📋 Registered process in registry");
 
     let db_path_for_monitor = db_path.clone(); // Clone for the monitor task
+    let child_for_monitor = registry.0.child_handle(run_id);
 
     // Monitor process status and wait for completion
     tokio::spawn(async move {
+        // Released when monitoring ends, i.e. once the process is done
+        let _project_lock = project_lock;
         info!("🕐 Starting process monitoring...");
 
         // Wait for first output with timeout
@@ -1082,15 +1480,33 @@ async fn spawn_agent_system(
                 }
 
                 // Update database
-                if let Ok(conn) = Connection::open(&db_path_for_monitor) {
+                if let Ok(conn) = open_connection(&db_path_for_monitor) {
                     let _ = conn.execute(
                         "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
                         params![run_id],
                     );
+                    if let Some(history_id) = history_id {
+                        let _ = run_history::record_run_finished(
+                            &conn,
+                            history_id,
+                            "failed",
+                            None,
+                            &RunUsage::default(),
+                        );
+                    }
                 }
+                if let Some(tracer) = &tracer {
+                    tracer.finish(Some("No output from Claude within 30 seconds".to_string()));
+                }
+                notifier.finished(history_id, "failed");
 
-                let _ = app.emit("agent-complete", false);
-                let _ = app.emit(&format!("agent-complete:{}", run_id), false);
+                event_bus::publish_to_window(
+                    &app,
+                    window_sessions::window_for(&app, &run_id.to_string()).as_deref(),
+                    "agent-complete",
+                    &false,
+                );
+                event_bus::publish(&app, &format!("agent-complete:{}", run_id), &false);
                 return;
             }
 
@@ -1101,6 +1517,14 @@ async fn spawn_agent_system(
         info!("⏳ Waiting for stdout/stderr reading to complete...");
         let _ = stdout_task.await;
         let _ = stderr_task.await;
+        let exit_status = match &child_for_monitor {
+            Some(child) => crate::process::wait_for_child(child).await,
+            None => None,
+        };
+        let status = match exit_status {
+            Some(status) if status.success() => "completed",
+            _ => "failed",
+        };
 
         let duration_ms = start_time.elapsed().as_millis() as i64;
         info!("⏱️ Process execution took {} ms", duration_ms);
@@ -1115,15 +1539,15 @@ async fn spawn_agent_system(
         // Wait for process completion and update status
         info!("✅ Claude process execution monitoring complete");
 
-        // Update the run record with session ID and mark as completed - open a new connection
-        if let Ok(conn) = Connection::open(&db_path_for_monitor) {
+        // Update the run record with session ID and final status - open a new connection
+        if let Ok(conn) = open_connection(&db_path_for_monitor) {
             info!(
                 "🔄 Updating database with extracted session ID: {}",
                 extracted_session_id
             );
             match conn.execute(
-                "UPDATE agent_runs SET session_id = ?1, status = 'completed', completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
-                params![extracted_session_id, run_id],
+                "UPDATE agent_runs SET session_id = ?1, status = CASE WHEN status = 'cancelled' THEN status ELSE ?2 END, completed_at = CURRENT_TIMESTAMP WHERE id = ?3",
+                params![extracted_session_id, status, run_id],
             ) {
                 Ok(rows_affected) => {
                     if rows_affected > 0 {
@@ -1136,6 +1560,25 @@ async fn spawn_agent_system(
                     error!("❌ Failed to update agent run {} with session ID: {}", run_id, e);
                 }
             }
+
+            if let Some(history_id) = history_id {
+                let usage = live_output
+                    .lock()
+                    .map(|o| RunUsage::from_jsonl(&o))
+                    .unwrap_or_default();
+                if let Err(e) = run_history::record_run_finished(
+                    &conn,
+                    history_id,
+                    status,
+                    exit_status.and_then(|s| s.code()),
+                    &usage,
+                ) {
+                    warn!(
+                        "Failed to record agent run {} completion in history: {}",
+                        run_id, e
+                    );
+                }
+            }
         } else {
             error!(
                 "❌ Failed to open database to update session ID for run {}",
@@ -1143,10 +1586,32 @@ async fn spawn_agent_system(
             );
         }
 
+        if let Some(tracer) = &tracer {
+            tracer.set_attribute("session.id", extracted_session_id.clone());
+            tracer.set_attribute("run.duration_ms", duration_ms);
+            if let Some(code) = exit_status.and_then(|s| s.code()) {
+                tracer.set_attribute("process.exit_code", code as i64);
+            }
+            tracer.finish(match exit_status {
+                Some(status) if !status.success() => Some(format!("Claude exited with {}", status)),
+                _ => None,
+            });
+        }
+        notifier.finished(history_id, status);
+
         // Cleanup will be handled by the cleanup_finished_processes function
 
-        let _ = app.emit("agent-complete", true);
-        let _ = app.emit(&format!("agent-complete:{}", run_id), true);
+        event_bus::publish_to_window(
+            &app,
+            window_sessions::window_for(&app, &run_id.to_string()).as_deref(),
+            "agent-complete",
+            &(status == "completed"),
+        );
+        event_bus::publish(
+            &app,
+            &format!("agent-complete:{}", run_id),
+            &(status == "completed"),
+        );
     });
 
     Ok(run_id)
@@ -1252,7 +1717,9 @@ pub async fn kill_agent_session(
                 params![run_id],
                 |row| row.get::<_, Option<i64>>(0),
             )
+            .optional()
             .map_err(|e| e.to_string())?
+            .flatten()
         };
 
         if let Some(pid) = pid_result {
@@ -1264,12 +1731,15 @@ pub async fn kill_agent_session(
     // Update the database to mark as cancelled
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let updated = conn.execute(
-        "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+        "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status IN ('pending', 'running')",
         params![run_id],
     ).map_err(|e| e.to_string())?;
 
+    let _ = run_history::record_agent_run_cancelled(&conn, run_id);
+    let _ = run_queue::remove_waiting(&conn, run_id);
+
     // Emit cancellation event with run_id for proper isolation
-    let _ = app.emit(&format!("agent-cancelled:{}", run_id), true);
+    event_bus::publish(&app, &format!("agent-cancelled:{}", run_id), &true);
 
     Ok(updated > 0 || killed_via_registry)
 }
@@ -1486,7 +1956,7 @@ pub async fn stream_session_output(
             None => return,
         };
 
-        let encoded_project = project_path.replace('/', "-");
+        let encoded_project = crate::utils::encode_project_path(&project_path);
         let project_dir = claude_dir.join(&encoded_project);
         let session_file = project_dir.join(format!("{}.jsonl", session_id));
 
@@ -1515,7 +1985,7 @@ pub async fn stream_session_output(
 
             // Check if the session is still running by querying the database
             // If the session is no longer running, stop streaming
-            if let Ok(conn) = rusqlite::Connection::open(
+            if let Ok(conn) = open_connection(
                 app.path()
                     .app_data_dir()
                     .expect("Failed to get app data dir")
@@ -1594,7 +2064,10 @@ pub async fn export_agent_to_file(
     let json_data = export_agent(db, id).await?;
 
     // Write to file
-    std::fs::write(&file_path, json_data).map_err(|e| format!("Failed to write file: {}", e))?;
+    let path = std::path::Path::new(&file_path);
+    let operation = FileOperation::for_write(path);
+    std::fs::write(path, json_data).map_err(|e| format!("Failed to write file: {}", e))?;
+    file_audit::record("export_agent_to_file", operation, path);
 
     Ok(())
 }
@@ -1670,8 +2143,11 @@ fn create_command_with_env(program: &str) -> Command {
     let _std_cmd = crate::claude_binary::create_command_with_env(program);
 
     // Create a new tokio Command from the program path
-    let mut tokio_cmd = Command::new(program);
+    configure_command_env(Command::new(program), program)
+}
 
+/// Sets up the environment `program` needs when launched through `tokio_cmd`
+fn configure_command_env(mut tokio_cmd: Command, program: &str) -> Command {
     // Copy over all environment variables from the std::process::Command
     // This is a workaround since we can't directly convert between the two types
     for (key, value) in std::env::vars() {
@@ -1845,30 +2321,27 @@ struct GitHubApiResponse {
 
 /// Fetch list of agents from GitHub repository
 #[tauri::command]
-pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, String> {
+pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, NetworkError> {
     info!("Fetching agents from GitHub repository...");
 
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client()?;
     let url = "https://api.github.com/repos/getAsterisk/opcode/contents/cc_agents";
 
-    let response = client
-        .get(url)
-        .header("Accept", "application/vnd.github+json")
-        .header("User-Agent", "opcode-App")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch from GitHub: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("GitHub API error ({}): {}", status, error_text));
-    }
+    let response = send_with_retry(|| {
+        client
+            .get(url)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "opcode-App")
+    })
+    .await
+    .map_err(|e| e.context("GitHub API error"))?;
 
-    let api_files: Vec<GitHubApiResponse> = response
+    let body: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse GitHub response: {}", e))?;
+        .map_err(|e| NetworkError::from(e).context("Failed to parse GitHub response"))?;
+    let api_files: Vec<GitHubApiResponse> =
+        schema_drift::decode_list("agents", body, &schema_drift::GITHUB_CONTENT)?;
 
     // Filter only .opcode.json agent files
     let agent_files: Vec<GitHubAgentFile> = api_files
@@ -1891,29 +2364,23 @@ pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, String> {
 
 /// Fetch and preview a specific agent from GitHub
 #[tauri::command]
-pub async fn fetch_github_agent_content(download_url: String) -> Result<AgentExport, String> {
+pub async fn fetch_github_agent_content(download_url: String) -> Result<AgentExport, NetworkError> {
     info!("Fetching agent content from: {}", download_url);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&download_url)
-        .header("Accept", "application/json")
-        .header("User-Agent", "opcode-App")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download agent: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to download agent: HTTP {}",
-            response.status()
-        ));
-    }
+    let client = crate::http_client::client()?;
+    let response = send_with_retry(|| {
+        client
+            .get(&download_url)
+            .header("Accept", "application/json")
+            .header("User-Agent", "opcode-App")
+    })
+    .await
+    .map_err(|e| e.context("Failed to download agent"))?;
 
     let json_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| NetworkError::from(e).context("Failed to read response"))?;
 
     // Parse and validate the agent data
     let export_data: AgentExport = serde_json::from_str(&json_text)
@@ -1921,10 +2388,7 @@ pub async fn fetch_github_agent_content(download_url: String) -> Result<AgentExp
 
     // Validate version
     if export_data.version != 1 {
-        return Err(format!(
-            "Unsupported agent version: {}",
-            export_data.version
-        ));
+        return Err(format!("Unsupported agent version: {}", export_data.version).into());
     }
 
     Ok(export_data)
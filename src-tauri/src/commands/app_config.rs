@@ -0,0 +1,649 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+use super::agents::{AgentData, AgentDb};
+use super::mcp::mcp_add_json;
+use super::settings;
+use crate::file_audit::{self, FileOperation};
+
+/// Version of the archive format written by `export_app_config`
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Settings that only make sense on the machine that wrote them
+const MACHINE_SETTINGS: &[&str] = &[
+    "claude_binary_path",
+    "claude_installation_preference",
+    "claude_dir_path",
+    "settings_schema_version",
+];
+
+/// Settings that decide which pricing tables are trusted; an archive could
+/// otherwise bring an unsigned table or its own signing key
+const TRUSTED_SETTINGS: &[&str] = &["pricing_table_cache", "pricing_signing_public_key"];
+
+/// Settings whose values are credentials
+const SECRET_SETTINGS: &[&str] = &["otel_headers", "control_server_token"];
+
+/// A full opcode configuration, as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigArchive {
+    pub version: u32,
+    pub exported_at: String,
+    pub app_version: String,
+    /// app_settings rows, without machine-specific settings and secrets
+    pub settings: BTreeMap<String, String>,
+    pub agents: Vec<AgentData>,
+    /// User-scope MCP servers from ~/.claude.json, with env values removed
+    pub mcp_servers: BTreeMap<String, JsonValue>,
+    /// Settings and MCP env values, present when exported with a passphrase
+    pub secrets: Option<EncryptedSecrets>,
+    /// Names of secrets that were left out because no passphrase was given
+    #[serde(default)]
+    pub omitted_secrets: Vec<String>,
+}
+
+/// Secrets sealed with XChaCha20-Poly1305 under an Argon2id-derived key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedSecrets {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Secrets {
+    settings: BTreeMap<String, String>,
+    /// Server name -> env var -> value
+    mcp_env: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// What importing one item would do
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    /// "settings", "agents", "mcp_servers" or "secrets"
+    pub section: String,
+    pub name: String,
+    /// "add", "update", "unchanged" or "skip"
+    pub action: String,
+    pub detail: Option<String>,
+}
+
+/// Outcome of `import_app_config`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigImportReport {
+    /// False for a dry run
+    pub applied: bool,
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigChange {
+    fn new(section: &str, name: &str, action: &str, detail: Option<String>) -> Self {
+        Self {
+            section: section.to_string(),
+            name: name.to_string(),
+            action: action.to_string(),
+            detail,
+        }
+    }
+}
+
+fn claude_json_path() -> Result<PathBuf, String> {
+    Ok(dirs::home_dir()
+        .ok_or("Could not find home directory")?
+        .join(".claude.json"))
+}
+
+/// User-scope MCP servers configured for Claude
+fn read_user_mcp_servers() -> Result<BTreeMap<String, JsonValue>, String> {
+    let path = claude_json_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: JsonValue = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(config["mcpServers"]
+        .as_object()
+        .map(|servers| {
+            servers
+                .iter()
+                .map(|(name, server)| (name.clone(), server.clone()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Whether a setting value carries credentials
+fn is_secret_setting(key: &str, value: &str) -> bool {
+    SECRET_SETTINGS.contains(&key)
+        || reqwest::Url::parse(value)
+            .map(|url| url.password().is_some())
+            .unwrap_or(false)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(Key::clone_from_slice(&key))
+}
+
+fn encrypt_secrets(secrets: &Secrets, passphrase: &str) -> Result<EncryptedSecrets, String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt secrets".to_string())?;
+    Ok(EncryptedSecrets {
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt_secrets(sealed: &EncryptedSecrets, passphrase: &str) -> Result<Secrets, String> {
+    let decode = |field: &str| {
+        STANDARD
+            .decode(field)
+            .map_err(|_| "Corrupted secrets in archive".to_string())
+    };
+    let (salt, nonce) = (decode(&sealed.salt)?, decode(&sealed.nonce)?);
+    if nonce.len() != 24 {
+        return Err("Corrupted secrets in archive".to_string());
+    }
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plaintext = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            decode(&sealed.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| "Wrong passphrase, or the archive is corrupted".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| e.to_string())
+}
+
+/// Build the archive from the database and Claude's MCP config
+fn build_archive(conn: &Connection, passphrase: Option<&str>) -> Result<ConfigArchive, String> {
+    let mut secrets = Secrets::default();
+    let mut omitted = Vec::new();
+
+    let mut settings = BTreeMap::new();
+    for spec in settings::SETTINGS {
+        let key = spec.key.to_string();
+        if MACHINE_SETTINGS.contains(&spec.key) || TRUSTED_SETTINGS.contains(&spec.key) {
+            continue;
+        }
+        let Some(value) = settings::get_stored(conn, spec.key)? else {
+            continue;
+        };
+        if is_secret_setting(&key, &value) {
+            omitted.push(format!("settings.{}", key));
+            secrets.settings.insert(key, value);
+        } else {
+            settings.insert(key, value);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, icon, system_prompt, default_task, model, hooks FROM agents ORDER BY name",
+        )
+        .map_err(|e| e.to_string())?;
+    let agents = stmt
+        .query_map([], |row| {
+            Ok(AgentData {
+                name: row.get(0)?,
+                icon: row.get(1)?,
+                system_prompt: row.get(2)?,
+                default_task: row.get(3)?,
+                model: row.get(4)?,
+                hooks: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut mcp_servers = read_user_mcp_servers()?;
+    for (name, server) in mcp_servers.iter_mut() {
+        let env = match server.get_mut("env").and_then(|e| e.as_object_mut()) {
+            Some(env) if !env.is_empty() => env,
+            _ => continue,
+        };
+        let mut values = BTreeMap::new();
+        for (var, value) in env.iter_mut() {
+            omitted.push(format!("mcp_servers.{}.env.{}", name, var));
+            values.insert(var.clone(), value.as_str().unwrap_or_default().to_string());
+            *value = JsonValue::String(String::new());
+        }
+        secrets.mcp_env.insert(name.clone(), values);
+    }
+
+    let (secrets, omitted_secrets) = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => (Some(encrypt_secrets(&secrets, passphrase)?), Vec::new()),
+        None => (None, omitted),
+    };
+
+    Ok(ConfigArchive {
+        version: ARCHIVE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        settings,
+        agents,
+        mcp_servers,
+        secrets,
+        omitted_secrets,
+    })
+}
+
+/// Settings in the archive with whether each came from the encrypted section
+fn archived_settings<'a>(
+    archive: &'a ConfigArchive,
+    secrets: &'a Secrets,
+) -> impl Iterator<Item = (&'a String, &'a String, bool)> {
+    let plain = archive.settings.iter().map(|(k, v)| (k, v, false));
+    plain.chain(secrets.settings.iter().map(|(k, v)| (k, v, true)))
+}
+
+/// Work out what importing the archive would change
+fn plan_import(
+    conn: &Connection,
+    archive: &ConfigArchive,
+    secrets: &Secrets,
+    existing_servers: &BTreeMap<String, JsonValue>,
+) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+
+    for (key, value, sealed) in archived_settings(archive, secrets) {
+        if MACHINE_SETTINGS.contains(&key.as_str()) {
+            changes.push(ConfigChange::new(
+                "settings",
+                key,
+                "skip",
+                Some("Specific to the machine it was exported from".to_string()),
+            ));
+            continue;
+        }
+        if TRUSTED_SETTINGS.contains(&key.as_str()) {
+            changes.push(ConfigChange::new(
+                "settings",
+                key,
+                "skip",
+                Some("Pricing trust is never imported".to_string()),
+            ));
+            continue;
+        }
+        if !sealed && is_secret_setting(key, value) {
+            changes.push(ConfigChange::new(
+                "settings",
+                key,
+                "skip",
+                Some("Secrets are only imported from the encrypted section".to_string()),
+            ));
+            continue;
+        }
+        let stored = settings::spec(key)
+            .and_then(|spec| settings::encode(spec, &JsonValue::String(value.clone())));
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(e) => {
+                changes.push(ConfigChange::new("settings", key, "skip", Some(e)));
+                continue;
+            }
+        };
+        let action = match settings::get_stored(conn, key).unwrap_or(None) {
+            current if current == stored => "unchanged",
+            None => "add",
+            Some(_) => "update",
+        };
+        changes.push(ConfigChange::new("settings", key, action, None));
+    }
+
+    for agent in &archive.agents {
+        let current = conn
+            .query_row(
+                "SELECT name, icon, system_prompt, default_task, model, hooks FROM agents WHERE name = ?1",
+                params![agent.name],
+                |row| {
+                    Ok(AgentData {
+                        name: row.get(0)?,
+                        icon: row.get(1)?,
+                        system_prompt: row.get(2)?,
+                        default_task: row.get(3)?,
+                        model: row.get(4)?,
+                        hooks: row.get(5)?,
+                    })
+                },
+            )
+            .optional()
+            .unwrap_or(None);
+        let action = match current {
+            None => "add",
+            Some(current) if &current == agent => "unchanged",
+            Some(_) => "update",
+        };
+        changes.push(ConfigChange::new("agents", &agent.name, action, None));
+    }
+
+    for (name, server) in &archive.mcp_servers {
+        let change = match existing_servers.get(name) {
+            Some(existing) if existing == server => {
+                ConfigChange::new("mcp_servers", name, "unchanged", None)
+            }
+            Some(_) => ConfigChange::new(
+                "mcp_servers",
+                name,
+                "skip",
+                Some("A different server with this name is already configured".to_string()),
+            ),
+            None => {
+                let missing: Vec<&str> = server["env"]
+                    .as_object()
+                    .map(|env| {
+                        env.keys()
+                            .filter(|var| {
+                                !secrets
+                                    .mcp_env
+                                    .get(name)
+                                    .is_some_and(|values| values.contains_key(*var))
+                            })
+                            .map(String::as_str)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let detail = (!missing.is_empty())
+                    .then(|| format!("Env values not included: {}", missing.join(", ")));
+                ConfigChange::new("mcp_servers", name, "add", detail)
+            }
+        };
+        changes.push(change);
+    }
+
+    if archive.secrets.is_none() {
+        for name in &archive.omitted_secrets {
+            changes.push(ConfigChange::new(
+                "secrets",
+                name,
+                "skip",
+                Some("Not included in the archive".to_string()),
+            ));
+        }
+    }
+
+    changes
+}
+
+/// Write the setting and agent changes in one transaction
+fn apply_database_changes(
+    conn: &Connection,
+    archive: &ConfigArchive,
+    secrets: &Secrets,
+    changes: &[ConfigChange],
+) -> Result<(), String> {
+    let wanted = |section: &str, name: &str| {
+        changes.iter().any(|c| {
+            c.section == section && c.name == name && (c.action == "add" || c.action == "update")
+        })
+    };
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (key, value, sealed) in archived_settings(archive, secrets) {
+        // A plaintext secret was skipped even if a sealed value of it is imported
+        if !wanted("settings", key) || (!sealed && is_secret_setting(key, value)) {
+            continue;
+        }
+        settings::set_value(&tx, key, &JsonValue::String(value.clone()))
+            .map_err(|e| format!("Failed to import setting {}: {}", key, e))?;
+    }
+    for agent in &archive.agents {
+        if !wanted("agents", &agent.name) {
+            continue;
+        }
+        let updated = tx
+            .execute(
+                "UPDATE agents SET icon = ?2, system_prompt = ?3, default_task = ?4, model = ?5, hooks = ?6
+                 WHERE name = ?1",
+                params![
+                    agent.name,
+                    agent.icon,
+                    agent.system_prompt,
+                    agent.default_task,
+                    agent.model,
+                    agent.hooks
+                ],
+            )
+            .map_err(|e| format!("Failed to import agent {}: {}", agent.name, e))?;
+        if updated == 0 {
+            tx.execute(
+                "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, 1, 1, 0, ?6)",
+                params![
+                    agent.name,
+                    agent.icon,
+                    agent.system_prompt,
+                    agent.default_task,
+                    agent.model,
+                    agent.hooks
+                ],
+            )
+            .map_err(|e| format!("Failed to import agent {}: {}", agent.name, e))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Export settings, agents and user MCP servers to a single archive file
+///
+/// Secrets (credential settings and MCP env values) are encrypted with
+/// `passphrase` when one is given, and left out otherwise.
+#[tauri::command]
+pub async fn export_app_config(
+    db: State<'_, AgentDb>,
+    path: String,
+    passphrase: Option<String>,
+) -> Result<ConfigArchive, String> {
+    let archive = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        build_archive(&conn, passphrase.as_deref())?
+    };
+    let json = serde_json::to_string_pretty(&archive)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    let operation = FileOperation::for_write(Path::new(&path));
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    file_audit::record("export_app_config", operation, Path::new(&path));
+    Ok(archive)
+}
+
+/// Re-apply the settings subsystems load once, as at startup
+///
+/// Settings read on each use, such as telemetry, need nothing.
+async fn apply_imported_settings(app: &AppHandle) -> Result<(), String> {
+    {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        super::proxy::apply_proxy_settings(&super::proxy::load_proxy_settings(&conn));
+        crate::http_client::configure_limits(&crate::http_client::load_limits(&conn));
+        crate::redaction::configure(&conn);
+        crate::dispatch::configure(&conn);
+        if let Some(level) = settings::get_text(&conn, "log_level") {
+            crate::logging::set_level(&level)?;
+        }
+        app.state::<crate::process::ProcessRegistryState>()
+            .0
+            .set_output_buffer_lines(super::streaming::load_streaming_settings(&conn).buffer_lines);
+    }
+    if !crate::safe_mode::is_active() {
+        crate::control_server::configure(app).await?;
+    }
+    Ok(())
+}
+
+/// Import an archive written by `export_app_config`
+///
+/// With `dry_run` nothing is written and the report lists what would change.
+/// Existing MCP servers are never replaced.
+#[tauri::command]
+pub async fn import_app_config(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    path: String,
+    passphrase: Option<String>,
+    dry_run: bool,
+) -> Result<ConfigImportReport, String> {
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let archive: ConfigArchive = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Invalid configuration archive: {}", e))?;
+    if archive.version != ARCHIVE_VERSION {
+        return Err(format!(
+            "Unsupported archive version: {}. This version of the app only supports version {}.",
+            archive.version, ARCHIVE_VERSION
+        ));
+    }
+
+    let secrets = match (&archive.secrets, passphrase.as_deref()) {
+        (Some(sealed), Some(passphrase)) => decrypt_secrets(sealed, passphrase)?,
+        (Some(_), None) => {
+            return Err(
+                "This archive's secrets are encrypted; a passphrase is required".to_string(),
+            )
+        }
+        (None, _) => Secrets::default(),
+    };
+    let existing_servers = read_user_mcp_servers()?;
+
+    let mut changes = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let changes = plan_import(&conn, &archive, &secrets, &existing_servers);
+        if !dry_run {
+            apply_database_changes(&conn, &archive, &secrets, &changes)?;
+        }
+        changes
+    };
+    if dry_run {
+        return Ok(ConfigImportReport {
+            applied: false,
+            changes,
+        });
+    }
+
+    for change in changes.iter_mut() {
+        if change.section != "mcp_servers" || change.action != "add" {
+            continue;
+        }
+        let mut server = archive.mcp_servers[&change.name].clone();
+        if let (Some(env), Some(values)) = (
+            server.get_mut("env").and_then(|e| e.as_object_mut()),
+            secrets.mcp_env.get(&change.name),
+        ) {
+            for (var, value) in values {
+                env.insert(var.clone(), JsonValue::String(value.clone()));
+            }
+        }
+        let result = mcp_add_json(
+            app.clone(),
+            change.name.clone(),
+            server.to_string(),
+            "user".to_string(),
+        )
+        .await?;
+        if !result.success {
+            change.action = "skip".to_string();
+            change.detail = Some(result.message);
+        }
+    }
+
+    apply_imported_settings(&app).await?;
+    Ok(ConfigImportReport {
+        applied: true,
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control_server::generate_token;
+
+    #[test]
+    fn test_secrets_round_trip() {
+        let mut secrets = Secrets::default();
+        secrets.settings.insert(
+            "otel_headers".to_string(),
+            "{\"authorization\":\"x\"}".to_string(),
+        );
+        let sealed = encrypt_secrets(&secrets, "correct horse").unwrap();
+
+        let opened = decrypt_secrets(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.settings, secrets.settings);
+        assert!(decrypt_secrets(&sealed, "battery staple").is_err());
+
+        assert!(is_secret_setting("proxy_http", "http://user:pw@proxy:8080"));
+        assert!(!is_secret_setting("proxy_http", "http://proxy:8080"));
+    }
+
+    #[test]
+    fn test_import_only_known_settings() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        let archive = ConfigArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: String::new(),
+            app_version: String::new(),
+            settings: BTreeMap::from([
+                ("pricing_table_cache".to_string(), "{}".to_string()),
+                ("pricing_signing_public_key".to_string(), "key".to_string()),
+                ("theme_preference".to_string(), "light".to_string()),
+                ("example_setting".to_string(), "dark".to_string()),
+                ("control_server_token".to_string(), generate_token()),
+            ]),
+            agents: Vec::new(),
+            mcp_servers: BTreeMap::new(),
+            secrets: None,
+            omitted_secrets: Vec::new(),
+        };
+
+        let changes = plan_import(&conn, &archive, &Secrets::default(), &BTreeMap::new());
+        let action = |name: &str| {
+            changes
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.action.clone())
+        };
+        assert_eq!(action("pricing_table_cache").as_deref(), Some("skip"));
+        assert_eq!(
+            action("pricing_signing_public_key").as_deref(),
+            Some("skip")
+        );
+        assert_eq!(action("theme_preference").as_deref(), Some("add"));
+        // Unknown settings and secrets outside the encrypted section aren't imported
+        assert_eq!(action("example_setting").as_deref(), Some("skip"));
+        assert_eq!(action("control_server_token").as_deref(), Some("skip"));
+
+        apply_database_changes(&conn, &archive, &Secrets::default(), &changes).unwrap();
+        let imported: Vec<String> = conn
+            .prepare("SELECT key FROM app_settings ORDER BY key")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(imported, vec!["theme_preference"]);
+    }
+}
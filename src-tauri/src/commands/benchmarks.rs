@@ -0,0 +1,441 @@
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::time::Instant;
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use super::models::configured_api_key;
+use super::operations::ProgressReporter;
+use super::pricing::{load_pricing, Pricing, TokenCounts};
+use crate::network::{send_with_policy, until_cancelled, NetworkError, RetryPolicy};
+use crate::path_validation::validate_project_root;
+
+/// Most models compared in one benchmark
+const MAX_MODELS: usize = 6;
+
+/// One prompt of a benchmark suite
+struct BenchmarkPrompt {
+    id: &'static str,
+    prompt: &'static str,
+    max_tokens: u32,
+}
+
+/// Prompt suites `benchmark_models` accepts; kept small so a run costs cents
+const PROMPT_SETS: &[(&str, &[BenchmarkPrompt])] = &[
+    (
+        "quick",
+        &[
+            BenchmarkPrompt {
+                id: "summarize",
+                prompt: "Summarize in two sentences: A write-ahead log records every change to a database before the change is applied, so after a crash the database can replay the log to recover committed transactions and discard incomplete ones.",
+                max_tokens: 200,
+            },
+            BenchmarkPrompt {
+                id: "fix_bug",
+                prompt: "This function should return the largest number in a list but fails on lists of negative numbers. Fix it and explain the bug in one sentence.\n\ndef largest(xs):\n    best = 0\n    for x in xs:\n        if x > best:\n            best = x\n    return best",
+                max_tokens: 400,
+            },
+            BenchmarkPrompt {
+                id: "reasoning",
+                prompt: "A build takes 12 minutes on one machine. Tests are 75% of that and split perfectly across machines; the rest can't be split. How long does the build take on 4 machines? Show your work briefly.",
+                max_tokens: 300,
+            },
+        ],
+    ),
+    (
+        "coding",
+        &[
+            BenchmarkPrompt {
+                id: "write_function",
+                prompt: "Write a Rust function `fn dedupe_sorted(v: &mut Vec<i32>)` that removes consecutive duplicates in place without allocating. Reply with only the code.",
+                max_tokens: 400,
+            },
+            BenchmarkPrompt {
+                id: "review",
+                prompt: "Review this TypeScript for bugs and reply with a short list:\n\nasync function loadAll(ids: string[]) {\n  const results = [];\n  ids.forEach(async (id) => {\n    results.push(await fetch(`/api/items/${id}`).then(r => r.json()));\n  });\n  return results;\n}",
+                max_tokens: 400,
+            },
+            BenchmarkPrompt {
+                id: "sql",
+                prompt: "Given tables orders(id, customer_id, total, created_at) and customers(id, name), write one SQL query returning each customer's name and their total spend in 2025, highest first, including customers with no orders.",
+                max_tokens: 300,
+            },
+        ],
+    ),
+];
+
+/// One prompt sent to one model
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub model: String,
+    pub prompt_id: String,
+    pub latency_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub output_chars: u64,
+    pub cost: f64,
+    /// Why the request failed; the other fields are zero then
+    pub error: Option<String>,
+}
+
+/// A model's row of the comparison table
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelBenchmark {
+    pub model: String,
+    pub prompt_set: String,
+    /// Prompts that succeeded
+    pub runs: u64,
+    pub failures: u64,
+    pub avg_latency_ms: u64,
+    pub avg_output_tokens: u64,
+    pub avg_output_chars: u64,
+    /// Cost of the whole suite
+    pub total_cost: f64,
+    pub project_path: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    /// One row per model, in the order requested
+    pub models: Vec<ModelBenchmark>,
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// Create the model_benchmarks table
+pub fn init_benchmarks(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS model_benchmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            model TEXT NOT NULL,
+            prompt_set TEXT NOT NULL,
+            runs INTEGER NOT NULL,
+            failures INTEGER NOT NULL,
+            avg_latency_ms INTEGER NOT NULL,
+            avg_output_tokens INTEGER NOT NULL,
+            avg_output_chars INTEGER NOT NULL,
+            total_cost REAL NOT NULL,
+            project_path TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Send one prompt and measure the response
+async fn run_prompt(
+    client: &reqwest::Client,
+    headers: &HeaderMap,
+    pricing: &Pricing,
+    model: &str,
+    prompt: &BenchmarkPrompt,
+) -> BenchmarkResult {
+    // A retry would count its backoff as latency
+    let policy = RetryPolicy {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    let body = json!({
+        "model": model,
+        "max_tokens": prompt.max_tokens,
+        "messages": [{ "role": "user", "content": prompt.prompt }],
+    });
+    let started = Instant::now();
+    let reply = async {
+        let response = send_with_policy(&policy, || {
+            client
+                .post("https://api.anthropic.com/v1/messages")
+                .headers(headers.clone())
+                .json(&body)
+        })
+        .await?;
+        Ok::<JsonValue, NetworkError>(response.json().await?)
+    }
+    .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let mut result = BenchmarkResult {
+        model: model.to_string(),
+        prompt_id: prompt.id.to_string(),
+        latency_ms: 0,
+        input_tokens: 0,
+        output_tokens: 0,
+        output_chars: 0,
+        cost: 0.0,
+        error: None,
+    };
+    match reply {
+        Ok(reply) => {
+            let usage = &reply["usage"];
+            let tokens = TokenCounts {
+                input: usage["input_tokens"].as_u64().unwrap_or(0),
+                output: usage["output_tokens"].as_u64().unwrap_or(0),
+                cache_write: usage["cache_creation_input_tokens"].as_u64().unwrap_or(0),
+                cache_read: usage["cache_read_input_tokens"].as_u64().unwrap_or(0),
+            };
+            let output_chars = reply["content"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|block| block["text"].as_str())
+                .map(|text| text.chars().count() as u64)
+                .sum();
+            result.latency_ms = latency_ms;
+            result.input_tokens = tokens.input;
+            result.output_tokens = tokens.output;
+            result.output_chars = output_chars;
+            result.cost = pricing.cost(model, tokens, None);
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+/// Average the results of each model into the comparison table
+fn summarize(
+    models: &[String],
+    prompt_set: &str,
+    project_path: Option<&str>,
+    results: &[BenchmarkResult],
+    created_at: &str,
+) -> Vec<ModelBenchmark> {
+    models
+        .iter()
+        .map(|model| {
+            let succeeded: Vec<&BenchmarkResult> = results
+                .iter()
+                .filter(|r| &r.model == model && r.error.is_none())
+                .collect();
+            let failures = results
+                .iter()
+                .filter(|r| &r.model == model && r.error.is_some())
+                .count() as u64;
+            let runs = succeeded.len() as u64;
+            let average = |value: fn(&BenchmarkResult) -> u64| {
+                succeeded.iter().copied().map(value).sum::<u64>() / runs.max(1)
+            };
+            ModelBenchmark {
+                model: model.clone(),
+                prompt_set: prompt_set.to_string(),
+                runs,
+                failures,
+                avg_latency_ms: average(|r| r.latency_ms),
+                avg_output_tokens: average(|r| r.output_tokens),
+                avg_output_chars: average(|r| r.output_chars),
+                total_cost: succeeded.iter().map(|r| r.cost).sum(),
+                project_path: project_path.map(str::to_string),
+                created_at: created_at.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Run a fixed prompt suite against each model and compare them
+///
+/// `prompt_set` is "quick" or "coding". Prompts run one at a time so latencies
+/// aren't skewed by concurrent requests; a failed prompt is recorded and the
+/// benchmark continues. The comparison table is saved, under `project_path`
+/// when given, for `list_model_benchmarks`.
+#[tauri::command]
+pub async fn benchmark_models(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    prompt_set: String,
+    models: Vec<String>,
+    project_path: Option<String>,
+) -> Result<BenchmarkReport, NetworkError> {
+    let progress = ProgressReporter::start(&app, "model_benchmark");
+    let result = run_benchmark(&db, &prompt_set, &models, project_path, &progress).await;
+    progress.finish(&result);
+    result
+}
+
+async fn run_benchmark(
+    db: &AgentDb,
+    prompt_set: &str,
+    models: &[String],
+    project_path: Option<String>,
+    progress: &ProgressReporter,
+) -> Result<BenchmarkReport, NetworkError> {
+    let prompts = PROMPT_SETS
+        .iter()
+        .find(|(name, _)| *name == prompt_set)
+        .map(|(_, prompts)| *prompts)
+        .ok_or_else(|| {
+            format!(
+                "Unknown prompt set '{}'; expected one of {}",
+                prompt_set,
+                PROMPT_SETS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+    let mut unique_models: Vec<String> = Vec::new();
+    for model in models.iter().map(|m| m.trim()).filter(|m| !m.is_empty()) {
+        if !unique_models.iter().any(|m| m == model) {
+            unique_models.push(model.to_string());
+        }
+    }
+    if unique_models.is_empty() || unique_models.len() > MAX_MODELS {
+        return Err(format!("Choose between 1 and {} models", MAX_MODELS).into());
+    }
+    let project_path = match project_path {
+        Some(path) => Some(validate_project_root(&path)?.to_string_lossy().to_string()),
+        None => None,
+    };
+    let (key, pricing) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let key = configured_api_key(&conn).ok_or_else(|| NetworkError::Auth {
+            message: "No Anthropic API key is configured".to_string(),
+        })?;
+        (key, load_pricing(&conn))
+    };
+
+    let client = crate::http_client::client()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(&key).map_err(|e| e.to_string())?,
+    );
+    headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    let total = unique_models.len() * prompts.len();
+    let mut results = Vec::with_capacity(total);
+    for model in &unique_models {
+        for prompt in prompts {
+            let done = results.len();
+            progress.report(
+                &format!("{}: {}", model, prompt.id),
+                Some((done * 100 / total) as u8),
+                None,
+            );
+            let result = until_cancelled(progress.token(), async {
+                Ok(run_prompt(&client, &headers, &pricing, model, prompt).await)
+            })
+            .await?;
+            results.push(result);
+        }
+    }
+
+    let created_at = chrono::Utc::now().to_rfc3339();
+    let summaries = summarize(
+        &unique_models,
+        prompt_set,
+        project_path.as_deref(),
+        &results,
+        &created_at,
+    );
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        for row in &summaries {
+            conn.execute(
+                "INSERT INTO model_benchmarks (model, prompt_set, runs, failures, avg_latency_ms,
+                    avg_output_tokens, avg_output_chars, total_cost, project_path, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    row.model,
+                    row.prompt_set,
+                    row.runs as i64,
+                    row.failures as i64,
+                    row.avg_latency_ms as i64,
+                    row.avg_output_tokens as i64,
+                    row.avg_output_chars as i64,
+                    row.total_cost,
+                    row.project_path,
+                    row.created_at,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    progress.report("done", Some(100), None);
+
+    Ok(BenchmarkReport {
+        models: summaries,
+        results,
+    })
+}
+
+/// List saved benchmark rows, newest first
+///
+/// With `project_path`, only benchmarks run for that project.
+#[tauri::command]
+pub async fn list_model_benchmarks(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ModelBenchmark>, String> {
+    let project_path = match project_path {
+        Some(path) => Some(validate_project_root(&path)?.to_string_lossy().to_string()),
+        None => None,
+    };
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, prompt_set, runs, failures, avg_latency_ms, avg_output_tokens,
+                avg_output_chars, total_cost, project_path, created_at
+             FROM model_benchmarks
+             WHERE ?1 IS NULL OR project_path = ?1
+             ORDER BY created_at DESC, id ASC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_path, limit.unwrap_or(100)], |row| {
+            Ok(ModelBenchmark {
+                model: row.get(0)?,
+                prompt_set: row.get(1)?,
+                runs: row.get::<_, i64>(2)? as u64,
+                failures: row.get::<_, i64>(3)? as u64,
+                avg_latency_ms: row.get::<_, i64>(4)? as u64,
+                avg_output_tokens: row.get::<_, i64>(5)? as u64,
+                avg_output_chars: row.get::<_, i64>(6)? as u64,
+                total_cost: row.get(7)?,
+                project_path: row.get(8)?,
+                created_at: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let result =
+            |model: &str, latency_ms: u64, output_tokens: u64, error: bool| BenchmarkResult {
+                model: model.to_string(),
+                prompt_id: "p".to_string(),
+                latency_ms,
+                input_tokens: 10,
+                output_tokens,
+                output_chars: output_tokens * 4,
+                cost: if error { 0.0 } else { 0.01 },
+                error: error.then(|| "HTTP 529".to_string()),
+            };
+        let results = [
+            result("haiku", 400, 100, false),
+            result("haiku", 600, 300, false),
+            result("sonnet", 900, 200, false),
+            result("sonnet", 0, 0, true),
+        ];
+        let models = ["haiku".to_string(), "sonnet".to_string()];
+        let rows = summarize(&models, "quick", None, &results, "now");
+        assert_eq!(rows[0].avg_latency_ms, 500);
+        assert_eq!(rows[0].avg_output_chars, 800);
+        assert!((rows[0].total_cost - 0.02).abs() < 1e-9);
+        assert_eq!((rows[1].runs, rows[1].failures), (1, 1));
+        assert_eq!(rows[1].avg_latency_ms, 900);
+        assert!(PROMPT_SETS.iter().all(|(_, prompts)| !prompts.is_empty()));
+    }
+}
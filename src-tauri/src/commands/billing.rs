@@ -0,0 +1,348 @@
+use chrono::{Duration as ChronoDuration, NaiveDate};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::models::configured_admin_key;
+use super::usage::{DailyUsage, ModelUsage, ProjectUsage, UsageStats};
+use crate::network::{send_with_retry, NetworkError};
+
+const ADMIN_API: &str = "https://api.anthropic.com/v1/organizations";
+/// Pages fetched at most per report; a page holds up to 31 days
+const MAX_PAGES: usize = 24;
+/// Stands in for usage outside any workspace
+const DEFAULT_WORKSPACE: &str = "Default workspace";
+
+/// Dates to fetch billed usage for, inclusive, as YYYY-MM-DD
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportPage<T> {
+    data: Vec<Bucket<T>>,
+    #[serde(default)]
+    has_more: bool,
+    next_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket<T> {
+    starting_at: String,
+    results: Vec<T>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct UsageResult {
+    model: Option<String>,
+    workspace_id: Option<String>,
+    uncached_input_tokens: u64,
+    output_tokens: u64,
+    cache_read_input_tokens: u64,
+    /// Cache writes by TTL, e.g. `ephemeral_5m_input_tokens`
+    cache_creation: HashMap<String, u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CostResult {
+    model: Option<String>,
+    workspace_id: Option<String>,
+    /// In cents, as a decimal string
+    amount: JsonValue,
+}
+
+fn parse_date(date: &str, what: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(date).map(|dt| dt.naive_local().date()))
+        .map_err(|e| format!("Invalid {}: {}", what, e))
+}
+
+/// Dollars of a cost report amount, which is in cents
+fn dollars(amount: &JsonValue) -> f64 {
+    let cents = match amount {
+        JsonValue::String(s) => s.parse().unwrap_or(0.0),
+        JsonValue::Number(n) => n.as_f64().unwrap_or(0.0),
+        _ => 0.0,
+    };
+    cents / 100.0
+}
+
+/// Every bucket of a report, following `next_page` until it's exhausted
+async fn fetch_report<T: DeserializeOwned>(
+    key: &str,
+    report: &str,
+    query: &[(&str, String)],
+) -> Result<Vec<Bucket<T>>, NetworkError> {
+    let client = crate::http_client::client()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(key).map_err(|e| e.to_string())?,
+    );
+    headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+    let url = format!("{}/{}", ADMIN_API, report);
+
+    let mut buckets = Vec::new();
+    let mut page: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let mut params = query.to_vec();
+        if let Some(page) = &page {
+            params.push(("page", page.clone()));
+        }
+        let response = send_with_retry(|| client.get(&url).headers(headers.clone()).query(&params))
+            .await
+            .map_err(|e| e.context(&format!("Failed to fetch {}", report)))?;
+        let body: ReportPage<T> = response.json().await?;
+        buckets.extend(body.data);
+        match body.next_page.filter(|_| body.has_more) {
+            Some(next) => page = Some(next),
+            None => return Ok(buckets),
+        }
+    }
+    log::warn!("Stopped reading {} after {} pages", report, MAX_PAGES);
+    Ok(buckets)
+}
+
+/// Combine the usage and cost reports into the structure local usage stats use
+///
+/// Workspaces take the place of projects; sessions aren't reported, so
+/// session counts are zero.
+fn normalize(usage: Vec<Bucket<UsageResult>>, costs: Vec<Bucket<CostResult>>) -> UsageStats {
+    let mut by_model: BTreeMap<String, ModelUsage> = BTreeMap::new();
+    let mut by_date: BTreeMap<String, DailyUsage> = BTreeMap::new();
+    let mut by_workspace: BTreeMap<String, ProjectUsage> = BTreeMap::new();
+    let mut totals = [0u64; 4];
+
+    let day_of = |starting_at: &str| {
+        starting_at
+            .split('T')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    };
+    let new_day = |date: &str| DailyUsage {
+        date: date.to_string(),
+        total_cost: 0.0,
+        total_tokens: 0,
+        models_used: Vec::new(),
+    };
+    let new_model = |model: &str| ModelUsage {
+        model: model.to_string(),
+        total_cost: 0.0,
+        total_tokens: 0,
+        input_tokens: 0,
+        output_tokens: 0,
+        cache_creation_tokens: 0,
+        cache_read_tokens: 0,
+        session_count: 0,
+    };
+    let new_workspace = |workspace: &str, date: &str| ProjectUsage {
+        project_path: workspace.to_string(),
+        project_name: workspace.to_string(),
+        total_cost: 0.0,
+        total_tokens: 0,
+        session_count: 0,
+        last_used: date.to_string(),
+    };
+
+    for bucket in usage {
+        let date = day_of(&bucket.starting_at);
+        for result in bucket.results {
+            let model = result.model.unwrap_or_else(|| "unknown".to_string());
+            let workspace = result
+                .workspace_id
+                .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+            let cache_creation: u64 = result.cache_creation.values().sum();
+            let tokens = [
+                result.uncached_input_tokens,
+                result.output_tokens,
+                cache_creation,
+                result.cache_read_input_tokens,
+            ];
+            let all_tokens: u64 = tokens.iter().sum();
+            for (total, count) in totals.iter_mut().zip(tokens) {
+                *total += count;
+            }
+
+            let model_stat = by_model
+                .entry(model.clone())
+                .or_insert_with(|| new_model(&model));
+            model_stat.input_tokens += tokens[0];
+            model_stat.output_tokens += tokens[1];
+            model_stat.cache_creation_tokens += tokens[2];
+            model_stat.cache_read_tokens += tokens[3];
+            model_stat.total_tokens = model_stat.input_tokens + model_stat.output_tokens;
+
+            let day = by_date
+                .entry(date.clone())
+                .or_insert_with(|| new_day(&date));
+            day.total_tokens += all_tokens;
+            if !day.models_used.contains(&model) {
+                day.models_used.push(model.clone());
+            }
+
+            let workspace_stat = by_workspace
+                .entry(workspace.clone())
+                .or_insert_with(|| new_workspace(&workspace, &date));
+            workspace_stat.total_tokens += all_tokens;
+            if date > workspace_stat.last_used {
+                workspace_stat.last_used = date.clone();
+            }
+        }
+    }
+
+    let mut total_cost = 0.0;
+    for bucket in costs {
+        let date = day_of(&bucket.starting_at);
+        for result in bucket.results {
+            let cost = dollars(&result.amount);
+            total_cost += cost;
+            by_date
+                .entry(date.clone())
+                .or_insert_with(|| new_day(&date))
+                .total_cost += cost;
+            // Costs such as web searches aren't tied to a model
+            if let Some(model) = result.model {
+                by_model
+                    .entry(model.clone())
+                    .or_insert_with(|| new_model(&model))
+                    .total_cost += cost;
+            }
+            let workspace = result
+                .workspace_id
+                .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+            by_workspace
+                .entry(workspace.clone())
+                .or_insert_with(|| new_workspace(&workspace, &date))
+                .total_cost += cost;
+        }
+    }
+
+    let mut by_model: Vec<ModelUsage> = by_model.into_values().collect();
+    by_model.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+    let mut by_date: Vec<DailyUsage> = by_date.into_values().collect();
+    by_date.sort_by(|a, b| b.date.cmp(&a.date));
+    let mut by_project: Vec<ProjectUsage> = by_workspace.into_values().collect();
+    by_project.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+
+    UsageStats {
+        total_cost,
+        total_tokens: totals.iter().sum(),
+        total_input_tokens: totals[0],
+        total_output_tokens: totals[1],
+        total_cache_creation_tokens: totals[2],
+        total_cache_read_tokens: totals[3],
+        total_sessions: 0,
+        by_model,
+        by_date,
+        by_project,
+    }
+}
+
+/// Fetch the organization's billed token usage and cost from the Anthropic
+/// Admin API
+///
+/// Needs an admin key (`sk-ant-admin...`), passed in or read from the
+/// environment variable named by the `anthropic_admin_key_env` setting. The
+/// result has the shape of `get_usage_by_date_range` so the two can be
+/// compared; workspaces are reported as projects.
+#[tauri::command]
+pub async fn fetch_anthropic_usage(
+    db: State<'_, AgentDb>,
+    api_key: Option<String>,
+    range: UsageRange,
+) -> Result<UsageStats, NetworkError> {
+    let key = match api_key.filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            configured_admin_key(&conn).ok_or_else(|| NetworkError::Auth {
+                message: "No Anthropic admin key is configured".to_string(),
+            })?
+        }
+    };
+    if !key.starts_with("sk-ant-admin") {
+        return Err(NetworkError::Auth {
+            message: "The Usage API needs an admin key (sk-ant-admin...), not a regular API key"
+                .to_string(),
+        });
+    }
+
+    let start = parse_date(&range.start_date, "start date")?;
+    let end = parse_date(&range.end_date, "end date")?;
+    if end < start {
+        return Err("The end date is before the start date".to_string().into());
+    }
+    let window = [
+        ("starting_at", format!("{}T00:00:00Z", start)),
+        (
+            "ending_at",
+            format!("{}T00:00:00Z", end + ChronoDuration::days(1)),
+        ),
+        ("bucket_width", "1d".to_string()),
+        ("limit", "31".to_string()),
+    ];
+    let mut usage_query = window.to_vec();
+    usage_query.push(("group_by[]", "model".to_string()));
+    usage_query.push(("group_by[]", "workspace_id".to_string()));
+    let mut cost_query = window.to_vec();
+    cost_query.push(("group_by[]", "description".to_string()));
+    cost_query.push(("group_by[]", "workspace_id".to_string()));
+
+    let (usage, costs) = tokio::try_join!(
+        fetch_report::<UsageResult>(&key, "usage_report/messages", &usage_query),
+        fetch_report::<CostResult>(&key, "cost_report", &cost_query),
+    )?;
+    Ok(normalize(usage, costs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_reports() {
+        let usage: ReportPage<UsageResult> = serde_json::from_str(
+            r#"{"data": [
+                {"starting_at": "2026-03-01T00:00:00Z", "ending_at": "2026-03-02T00:00:00Z", "results": [
+                    {"model": "claude-sonnet-4-5", "workspace_id": null, "uncached_input_tokens": 100,
+                     "output_tokens": 50, "cache_read_input_tokens": 10,
+                     "cache_creation": {"ephemeral_5m_input_tokens": 5, "ephemeral_1h_input_tokens": 1}}
+                ]},
+                {"starting_at": "2026-03-02T00:00:00Z", "ending_at": "2026-03-03T00:00:00Z", "results": [
+                    {"model": "claude-haiku-4-5", "workspace_id": "wrkspc_1", "uncached_input_tokens": 20,
+                     "output_tokens": 10, "cache_read_input_tokens": 0, "cache_creation": {}}
+                ]}
+            ], "has_more": false, "next_page": null}"#,
+        )
+        .unwrap();
+        let costs: ReportPage<CostResult> = serde_json::from_str(
+            r#"{"data": [
+                {"starting_at": "2026-03-01T00:00:00Z", "ending_at": "2026-03-02T00:00:00Z", "results": [
+                    {"currency": "USD", "amount": "150.5", "model": "claude-sonnet-4-5", "workspace_id": null},
+                    {"currency": "USD", "amount": "20", "model": null, "workspace_id": null}
+                ]}
+            ], "has_more": false, "next_page": null}"#,
+        )
+        .unwrap();
+
+        let stats = normalize(usage.data, costs.data);
+        assert!((stats.total_cost - 1.705).abs() < 1e-9);
+        assert_eq!(stats.total_input_tokens, 120);
+        assert_eq!(stats.total_cache_creation_tokens, 6);
+        assert_eq!(stats.total_tokens, 196);
+        assert_eq!(stats.by_model[0].model, "claude-sonnet-4-5");
+        assert!((stats.by_model[0].total_cost - 1.505).abs() < 1e-9);
+        assert_eq!(stats.by_date[0].date, "2026-03-02");
+        assert_eq!(stats.by_project[0].project_name, DEFAULT_WORKSPACE);
+        assert_eq!(stats.by_project.len(), 2);
+    }
+}
@@ -0,0 +1,417 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::claude::load_session_history;
+use super::file_history::{history_for_file, sync_file_history, FileEditLink};
+use super::git::git;
+
+/// Hash git blame reports for lines that are not committed yet
+const UNCOMMITTED_SHA: &str = "0000000000000000000000000000000000000000";
+
+/// Lines shorter than this are too generic to match against edit content
+const MIN_MATCH_LINE_LEN: usize = 4;
+
+/// How many candidate edits to open when looking for matching content
+const MAX_CONTENT_CANDIDATES: usize = 50;
+
+/// Inclusive, 1-based range of lines in a file
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One line as reported by `git blame --porcelain`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    pub line: usize,
+    pub commit: String,
+    pub author: Option<String>,
+    pub author_time: Option<i64>,
+    pub summary: Option<String>,
+    pub content: String,
+}
+
+/// How a conversation was matched to a blame hunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchConfidence {
+    /// The edit's input contains the blamed lines
+    Content,
+    /// Latest edit of the file before the commit, content not confirmed
+    Time,
+}
+
+/// The session message that most likely introduced a hunk
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationMatch {
+    pub session_id: String,
+    pub project_id: String,
+    pub message_index: usize,
+    pub tool_name: String,
+    pub timestamp: Option<String>,
+    /// The user prompt that led to the edit
+    pub prompt: Option<String>,
+    pub prompt_index: Option<usize>,
+    pub confidence: MatchConfidence,
+}
+
+/// Consecutive lines introduced by the same commit
+#[derive(Debug, Clone, Serialize)]
+pub struct BlameHunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// `None` for lines that are not committed yet
+    pub commit: Option<String>,
+    pub author: Option<String>,
+    pub authored_at: Option<String>,
+    pub summary: Option<String>,
+    pub lines: Vec<String>,
+    pub conversation: Option<ConversationMatch>,
+}
+
+/// Parse the output of `git blame --porcelain`
+///
+/// Commit details are only printed the first time a commit appears, so they
+/// are remembered and filled in for later lines.
+pub fn parse_blame_porcelain(output: &str) -> Vec<BlameLine> {
+    struct CommitInfo {
+        author: Option<String>,
+        author_time: Option<i64>,
+        summary: Option<String>,
+    }
+
+    let mut commits: HashMap<String, CommitInfo> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+
+    for line in output.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            if let Some((commit, line_number)) = current.take() {
+                let info = commits.get(&commit);
+                lines.push(BlameLine {
+                    line: line_number,
+                    author: info.and_then(|i| i.author.clone()),
+                    author_time: info.and_then(|i| i.author_time),
+                    summary: info.and_then(|i| i.summary.clone()),
+                    commit,
+                    content: content.to_string(),
+                });
+            }
+            continue;
+        }
+
+        match &current {
+            None => {
+                let mut parts = line.split_whitespace();
+                let commit = parts.next().unwrap_or_default();
+                let final_line = parts.nth(1).and_then(|n| n.parse().ok());
+                if let (40, Some(final_line)) = (commit.len(), final_line) {
+                    commits.entry(commit.to_string()).or_insert(CommitInfo {
+                        author: None,
+                        author_time: None,
+                        summary: None,
+                    });
+                    current = Some((commit.to_string(), final_line));
+                }
+            }
+            Some((commit, _)) => {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                if let Some(info) = commits.get_mut(commit) {
+                    match key {
+                        "author" => info.author = Some(value.to_string()),
+                        "author-time" => info.author_time = value.parse().ok(),
+                        "summary" => info.summary = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// Group consecutive lines from the same commit
+fn group_hunks(lines: Vec<BlameLine>) -> Vec<BlameHunk> {
+    let mut hunks: Vec<BlameHunk> = Vec::new();
+    for line in lines {
+        let commit = Some(line.commit.clone()).filter(|c| c != UNCOMMITTED_SHA);
+        if let Some(hunk) = hunks.last_mut() {
+            if hunk.commit == commit && hunk.end_line + 1 == line.line {
+                hunk.end_line = line.line;
+                hunk.lines.push(line.content);
+                continue;
+            }
+        }
+        hunks.push(BlameHunk {
+            start_line: line.line,
+            end_line: line.line,
+            authored_at: line
+                .author_time
+                .filter(|_| commit.is_some())
+                .and_then(|t| Utc.timestamp_opt(t, 0).single())
+                .map(|t| t.to_rfc3339()),
+            author: line.author.filter(|_| commit.is_some()),
+            summary: line.summary.filter(|_| commit.is_some()),
+            commit,
+            lines: vec![line.content],
+            conversation: None,
+        });
+    }
+    hunks
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// String values a tool input writes, skipping the target path and replaced text
+fn collect_input_text(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::String(s) => {
+            out.push_str(s);
+            out.push('\n');
+        }
+        JsonValue::Array(items) => items.iter().for_each(|v| collect_input_text(v, out)),
+        JsonValue::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !key.ends_with("_path") && !key.starts_with("old_"))
+            .for_each(|(_, v)| collect_input_text(v, out)),
+        _ => {}
+    }
+}
+
+/// Text written by the file edits of a message
+fn edit_text(message: &JsonValue, file_path: &str) -> String {
+    let mut text = String::new();
+    let blocks = message
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array());
+    for block in blocks.into_iter().flatten() {
+        let input = match block.get("input") {
+            Some(input) => input,
+            None => continue,
+        };
+        let targets_file = ["file_path", "notebook_path"]
+            .iter()
+            .any(|key| input.get(*key).and_then(|p| p.as_str()) == Some(file_path));
+        if targets_file {
+            collect_input_text(input, &mut text);
+        }
+    }
+    text
+}
+
+/// Text of a user prompt, ignoring tool results
+fn prompt_text(message: &JsonValue) -> Option<String> {
+    if message.get("type").and_then(|t| t.as_str()) != Some("user") {
+        return None;
+    }
+    let content = message.get("message")?.get("content")?;
+    if let Some(text) = content.as_str() {
+        return Some(text.to_string());
+    }
+    let text: Vec<&str> = content
+        .as_array()?
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+        .collect();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.join("\n"))
+    }
+}
+
+/// Closest user prompt at or before `index`
+fn find_prompt(messages: &[JsonValue], index: usize) -> Option<(usize, String)> {
+    messages
+        .iter()
+        .enumerate()
+        .take(index + 1)
+        .rev()
+        .find_map(|(i, m)| prompt_text(m).map(|text| (i, text)))
+}
+
+/// Match a hunk to the edit that most likely introduced it
+async fn match_conversation(
+    hunk: &BlameHunk,
+    links: &[FileEditLink],
+    transcripts: &mut HashMap<String, Vec<JsonValue>>,
+) -> Option<ConversationMatch> {
+    let cutoff = hunk
+        .authored_at
+        .as_deref()
+        .and_then(parse_time)
+        // Edits are committed after they are made
+        .map(|t| t + chrono::Duration::seconds(1));
+
+    // Newest first, only edits made before the commit
+    let mut candidates: Vec<&FileEditLink> = links
+        .iter()
+        .filter(
+            |link| match (cutoff, link.timestamp.as_deref().and_then(parse_time)) {
+                (Some(cutoff), Some(time)) => time <= cutoff,
+                (Some(_), None) => false,
+                (None, _) => true,
+            },
+        )
+        .collect();
+    candidates.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    let wanted: Vec<&str> = hunk
+        .lines
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| l.len() >= MIN_MATCH_LINE_LEN)
+        .collect();
+
+    let mut matched = None;
+    if !wanted.is_empty() {
+        for link in candidates.iter().take(MAX_CONTENT_CANDIDATES) {
+            let messages = load_transcript(transcripts, link).await;
+            let text = messages
+                .get(link.message_index)
+                .map(|m| edit_text(m, &link.file_path))
+                .unwrap_or_default();
+            if wanted.iter().all(|line| text.contains(line)) {
+                matched = Some((*link, MatchConfidence::Content));
+                break;
+            }
+        }
+    }
+
+    let (link, confidence) =
+        matched.or_else(|| candidates.first().map(|l| (*l, MatchConfidence::Time)))?;
+    let messages = load_transcript(transcripts, link).await;
+    let prompt = find_prompt(messages, link.message_index);
+
+    Some(ConversationMatch {
+        session_id: link.session_id.clone(),
+        project_id: link.project_id.clone(),
+        message_index: link.message_index,
+        tool_name: link.tool_name.clone(),
+        timestamp: link.timestamp.clone(),
+        prompt_index: prompt.as_ref().map(|(i, _)| *i),
+        prompt: prompt.map(|(_, text)| text),
+        confidence,
+    })
+}
+
+async fn load_transcript<'a>(
+    transcripts: &'a mut HashMap<String, Vec<JsonValue>>,
+    link: &FileEditLink,
+) -> &'a [JsonValue] {
+    if !transcripts.contains_key(&link.session_id) {
+        let messages = load_session_history(link.session_id.clone(), link.project_id.clone())
+            .await
+            .unwrap_or_default();
+        transcripts.insert(link.session_id.clone(), messages);
+    }
+    &transcripts[&link.session_id]
+}
+
+/// Run `git blame` for a range of lines in a file
+async fn blame_lines(path: &Path, range: LineRange) -> Result<Vec<BlameLine>, String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+
+    let lines = format!("{},{}", range.start, range.end);
+    let output = git(
+        dir,
+        &["blame", "--porcelain", "-L", &lines, "--", file_name],
+    )
+    .await?;
+    Ok(parse_blame_porcelain(&output))
+}
+
+/// Explain which conversation introduced a range of lines
+///
+/// Blames the lines with git, then matches each hunk to the session message
+/// whose file edit contains those lines, falling back to the latest edit of
+/// the file before the commit.
+#[tauri::command]
+pub async fn explain_change(
+    db: State<'_, AgentDb>,
+    path: String,
+    line_range: LineRange,
+) -> Result<Vec<BlameHunk>, String> {
+    if line_range.start == 0 || line_range.end < line_range.start {
+        return Err(format!(
+            "Invalid line range: {}-{}",
+            line_range.start, line_range.end
+        ));
+    }
+
+    let path = Path::new(&path)
+        .canonicalize()
+        .map_err(|e| format!("File not found: {} ({})", path, e))?;
+    let mut hunks = group_hunks(blame_lines(&path, line_range).await?);
+
+    sync_file_history(&db)?;
+    let links = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        history_for_file(&conn, &path.to_string_lossy()).map_err(|e| e.to_string())?
+    };
+    if links.is_empty() {
+        return Ok(hunks);
+    }
+
+    let mut transcripts = HashMap::new();
+    for hunk in &mut hunks {
+        hunk.conversation = match_conversation(hunk, &links, &mut transcripts).await;
+    }
+    Ok(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blame_porcelain_reuses_commit_details() {
+        let sha = "a".repeat(40);
+        let output = format!(
+            "{sha} 10 10 2\n\
+             author Jane\n\
+             author-time 1700000000\n\
+             summary Add parser\n\
+             filename src/lib.rs\n\
+             \tfn parse() {{\n\
+             {sha} 11 11\n\
+             \t}}\n\
+             {UNCOMMITTED_SHA} 12 12 1\n\
+             author Not Committed Yet\n\
+             filename src/lib.rs\n\
+             \t// todo\n"
+        );
+
+        let lines = parse_blame_porcelain(&output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].author.as_deref(), Some("Jane"));
+        assert_eq!(lines[1].author_time, Some(1_700_000_000));
+        assert_eq!(lines[1].content, "}");
+
+        let hunks = group_hunks(lines);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].start_line, hunks[0].end_line), (10, 11));
+        assert_eq!(hunks[0].summary.as_deref(), Some("Add parser"));
+        assert!(hunks[1].commit.is_none());
+        assert!(hunks[1].author.is_none());
+    }
+}
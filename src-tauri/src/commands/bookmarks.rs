@@ -0,0 +1,338 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::PathBuf;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::claude::load_session_history;
+use crate::utils::get_claude_dir;
+
+/// Format version written into exported transcripts
+const TRANSCRIPT_EXPORT_VERSION: u32 = 1;
+
+/// A reviewer note attached to a message in a session transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptBookmark {
+    pub id: Option<i64>,
+    pub session_id: String,
+    pub project_id: String,
+    /// Index of the message in the session's JSONL transcript
+    pub message_index: usize,
+    pub note: String,
+    pub created_at: Option<String>,
+}
+
+/// A bookmark together with the message it points at
+#[derive(Debug, Clone, Serialize)]
+pub struct BookmarkTarget {
+    pub bookmark: TranscriptBookmark,
+    pub message: Option<JsonValue>,
+    pub total_messages: usize,
+}
+
+/// A session transcript bundled with its bookmarks for sharing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptExport {
+    pub version: u32,
+    pub session_id: String,
+    pub project_id: String,
+    pub exported_at: String,
+    pub messages: Vec<JsonValue>,
+    pub bookmarks: Vec<TranscriptBookmark>,
+}
+
+/// Create the bookmarks table
+pub fn init_bookmarks(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transcript_bookmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            message_index INTEGER NOT NULL,
+            note TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_transcript_bookmarks_session ON transcript_bookmarks(session_id, message_index)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn row_to_bookmark(row: &Row) -> SqliteResult<TranscriptBookmark> {
+    Ok(TranscriptBookmark {
+        id: Some(row.get(0)?),
+        session_id: row.get(1)?,
+        project_id: row.get(2)?,
+        message_index: row.get::<_, i64>(3)? as usize,
+        note: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn insert_bookmark(conn: &Connection, bookmark: &TranscriptBookmark) -> SqliteResult<i64> {
+    conn.execute(
+        "INSERT INTO transcript_bookmarks (session_id, project_id, message_index, note) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            bookmark.session_id,
+            bookmark.project_id,
+            bookmark.message_index as i64,
+            bookmark.note
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn get_bookmark(conn: &Connection, id: i64) -> SqliteResult<Option<TranscriptBookmark>> {
+    conn.query_row(
+        "SELECT id, session_id, project_id, message_index, note, created_at FROM transcript_bookmarks WHERE id = ?1",
+        params![id],
+        row_to_bookmark,
+    )
+    .optional()
+}
+
+/// Bookmarks for a session ordered by position in the transcript
+pub fn bookmarks_for_session(
+    conn: &Connection,
+    session_id: &str,
+) -> SqliteResult<Vec<TranscriptBookmark>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, project_id, message_index, note, created_at FROM transcript_bookmarks
+         WHERE session_id = ?1 ORDER BY message_index, id",
+    )?;
+    let bookmarks = stmt
+        .query_map(params![session_id], row_to_bookmark)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(bookmarks)
+}
+
+/// Find the project directory containing a session transcript
+pub(crate) fn find_session_project(session_id: &str) -> Result<String, String> {
+    let projects_dir: PathBuf = get_claude_dir()?.join("projects");
+    let file_name = format!("{}.jsonl", session_id);
+
+    let entries = std::fs::read_dir(&projects_dir)
+        .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+    for entry in entries.flatten() {
+        if entry.path().join(&file_name).exists() {
+            return Ok(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+
+    Err(format!("Session file not found: {}", session_id))
+}
+
+/// Bookmark a message in a session transcript
+#[tauri::command]
+pub async fn add_transcript_bookmark(
+    db: State<'_, AgentDb>,
+    session_id: String,
+    message_index: usize,
+    note: String,
+    project_id: Option<String>,
+) -> Result<TranscriptBookmark, String> {
+    let project_id = match project_id {
+        Some(id) => id,
+        None => find_session_project(&session_id)?,
+    };
+
+    let messages = load_session_history(session_id.clone(), project_id.clone()).await?;
+    if message_index >= messages.len() {
+        return Err(format!(
+            "Message index {} is out of range (session has {} messages)",
+            message_index,
+            messages.len()
+        ));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let id = insert_bookmark(
+        &conn,
+        &TranscriptBookmark {
+            id: None,
+            session_id,
+            project_id,
+            message_index,
+            note,
+            created_at: None,
+        },
+    )
+    .map_err(|e| format!("Failed to add bookmark: {}", e))?;
+
+    get_bookmark(&conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Bookmark not found".to_string())
+}
+
+/// List the bookmarks of a session
+#[tauri::command]
+pub async fn list_transcript_bookmarks(
+    db: State<'_, AgentDb>,
+    session_id: String,
+) -> Result<Vec<TranscriptBookmark>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    bookmarks_for_session(&conn, &session_id).map_err(|e| e.to_string())
+}
+
+/// Change the note of a bookmark
+#[tauri::command]
+pub async fn update_transcript_bookmark(
+    db: State<'_, AgentDb>,
+    bookmark_id: i64,
+    note: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE transcript_bookmarks SET note = ?1 WHERE id = ?2",
+            params![note, bookmark_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Bookmark {} not found", bookmark_id));
+    }
+    Ok(())
+}
+
+/// Delete a bookmark
+#[tauri::command]
+pub async fn delete_transcript_bookmark(
+    db: State<'_, AgentDb>,
+    bookmark_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM transcript_bookmarks WHERE id = ?1",
+        params![bookmark_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Resolve a bookmark to the message it points at so the UI can jump to it
+#[tauri::command]
+pub async fn jump_to_transcript_bookmark(
+    db: State<'_, AgentDb>,
+    bookmark_id: i64,
+) -> Result<BookmarkTarget, String> {
+    let bookmark = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        get_bookmark(&conn, bookmark_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Bookmark {} not found", bookmark_id))?
+    };
+
+    let messages =
+        load_session_history(bookmark.session_id.clone(), bookmark.project_id.clone()).await?;
+
+    Ok(BookmarkTarget {
+        message: messages.get(bookmark.message_index).cloned(),
+        total_messages: messages.len(),
+        bookmark,
+    })
+}
+
+/// Export a session transcript with its bookmarks as JSON
+#[tauri::command]
+pub async fn export_transcript(
+    db: State<'_, AgentDb>,
+    session_id: String,
+    project_id: String,
+) -> Result<String, String> {
+    let messages = load_session_history(session_id.clone(), project_id.clone()).await?;
+    let bookmarks = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        bookmarks_for_session(&conn, &session_id).map_err(|e| e.to_string())?
+    };
+
+    let export = TranscriptExport {
+        version: TRANSCRIPT_EXPORT_VERSION,
+        session_id,
+        project_id,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        messages,
+        bookmarks,
+    };
+
+    serde_json::to_string_pretty(&export).map_err(|e| format!("Failed to export transcript: {}", e))
+}
+
+/// Import the bookmarks shared in an exported transcript, skipping ones that already exist
+#[tauri::command]
+pub async fn import_transcript_bookmarks(
+    db: State<'_, AgentDb>,
+    export_data: String,
+) -> Result<Vec<TranscriptBookmark>, String> {
+    let export: TranscriptExport = serde_json::from_str(&export_data)
+        .map_err(|e| format!("Invalid transcript export: {}", e))?;
+    if export.version > TRANSCRIPT_EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported transcript export version: {}",
+            export.version
+        ));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let existing = bookmarks_for_session(&conn, &export.session_id).map_err(|e| e.to_string())?;
+
+    let mut imported = Vec::new();
+    for bookmark in export.bookmarks {
+        if bookmark.message_index >= export.messages.len()
+            || existing
+                .iter()
+                .any(|b| b.message_index == bookmark.message_index && b.note == bookmark.note)
+        {
+            continue;
+        }
+
+        let bookmark = TranscriptBookmark {
+            id: None,
+            session_id: export.session_id.clone(),
+            project_id: export.project_id.clone(),
+            created_at: None,
+            ..bookmark
+        };
+        let id = insert_bookmark(&conn, &bookmark).map_err(|e| e.to_string())?;
+        if let Some(bookmark) = get_bookmark(&conn, id).map_err(|e| e.to_string())? {
+            imported.push(bookmark);
+        }
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmarks_ordered_by_message() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_bookmarks(&conn).unwrap();
+
+        for (index, note) in [(5, "went wrong here"), (1, "good start")] {
+            insert_bookmark(
+                &conn,
+                &TranscriptBookmark {
+                    id: None,
+                    session_id: "s1".to_string(),
+                    project_id: "p1".to_string(),
+                    message_index: index,
+                    note: note.to_string(),
+                    created_at: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let bookmarks = bookmarks_for_session(&conn, "s1").unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].message_index, 1);
+        assert_eq!(bookmarks[1].note, "went wrong here");
+        assert!(bookmarks_for_session(&conn, "s2").unwrap().is_empty());
+    }
+}
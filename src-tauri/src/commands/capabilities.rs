@@ -0,0 +1,124 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::cli_compat::{self, CliCapabilities};
+use crate::sandbox::SandboxCapabilities;
+
+/// Whether a subsystem can be used, and why not if it can't
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub available: bool,
+    /// Path, mechanism or reason, for display
+    pub detail: Option<String>,
+}
+
+impl Capability {
+    fn available(detail: impl Into<String>) -> Self {
+        Self {
+            available: true,
+            detail: Some(detail.into()),
+        }
+    }
+
+    fn unavailable(reason: impl Into<String>) -> Self {
+        Self {
+            available: false,
+            detail: Some(reason.into()),
+        }
+    }
+}
+
+/// What this build can do on this machine, so the UI can hide what would fail
+#[derive(Debug, Clone, Serialize)]
+pub struct AppCapabilities {
+    pub platform: String,
+    pub arch: String,
+    pub version: String,
+    /// OS-level filesystem confinement for execution profiles
+    pub sandboxing: Capability,
+    pub tray: Capability,
+    pub global_hotkeys: Capability,
+    /// `docker` on PATH, for MCP servers that run in containers
+    pub docker: Capability,
+    /// `ssh` on PATH, for remote projects
+    pub ssh: Capability,
+    /// AWS credentials Claude can use to reach Bedrock
+    pub bedrock: Capability,
+}
+
+fn binary_capability(name: &str) -> Capability {
+    match which::which(name) {
+        Ok(path) => Capability::available(path.to_string_lossy()),
+        Err(_) => Capability::unavailable(format!("`{}` was not found on PATH", name)),
+    }
+}
+
+fn sandboxing_capability() -> Capability {
+    let sandbox = SandboxCapabilities::detect();
+    match sandbox.mechanism {
+        Some(mechanism) if sandbox.filesystem_isolation => Capability::available(mechanism),
+        _ => Capability::unavailable(format!(
+            "No filesystem sandbox on {}; execution profiles only restrict tools",
+            sandbox.platform
+        )),
+    }
+}
+
+fn bedrock_capability() -> Capability {
+    let env_set = |key: &str| std::env::var(key).map(|v| !v.is_empty()).unwrap_or(false);
+    let aws_dir = dirs::home_dir().map(|home| home.join(".aws"));
+    let has_file = |name: &str| {
+        aws_dir
+            .as_ref()
+            .map(|dir| dir.join(name).is_file())
+            .unwrap_or(false)
+    };
+
+    let source = if env_set("AWS_ACCESS_KEY_ID") || env_set("AWS_PROFILE") {
+        Some("environment")
+    } else if has_file("credentials") || has_file("config") {
+        Some("~/.aws")
+    } else {
+        None
+    };
+
+    match source {
+        Some(source) if env_set("CLAUDE_CODE_USE_BEDROCK") => {
+            Capability::available(format!("Enabled, credentials from {}", source))
+        }
+        Some(source) => Capability::available(format!("Credentials from {}", source)),
+        None => Capability::unavailable("No AWS credentials found"),
+    }
+}
+
+/// Get which subsystems are available on this platform and build
+#[tauri::command]
+pub async fn get_capabilities() -> Result<AppCapabilities, String> {
+    let (docker, ssh) =
+        tokio::task::spawn_blocking(|| (binary_capability("docker"), binary_capability("ssh")))
+            .await
+            .map_err(|e| e.to_string())?;
+
+    Ok(AppCapabilities {
+        platform: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        sandboxing: sandboxing_capability(),
+        tray: Capability::unavailable("Not included in this build"),
+        global_hotkeys: Capability::unavailable("Not included in this build"),
+        docker,
+        ssh,
+        bedrock: bedrock_capability(),
+    })
+}
+
+/// Get the installed Claude Code version and which CLI features it supports
+///
+/// Runs already leave out optional flags the CLI lacks; use this to hide or
+/// explain features that would fail.
+#[tauri::command]
+pub async fn get_cli_capabilities(app: AppHandle) -> Result<CliCapabilities, String> {
+    tokio::task::spawn_blocking(move || cli_compat::detect(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
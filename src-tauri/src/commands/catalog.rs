@@ -0,0 +1,413 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::popularity::{popularity_for, Popularity};
+use super::skills::{
+    fetch_available_skills, fetch_mcp_marketplace, load_catalog_fetch_settings, SkillInfo,
+};
+
+/// Catalogs are fetched again once they are this old
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+
+/// Catalog kinds `search_catalog` accepts
+pub const CATALOG_KINDS: &[&str] = &["skills", "mcp_servers"];
+
+/// Orders `search_catalog` accepts
+pub const SORT_ORDERS: &[&str] = &["relevance", "popularity", "recent"];
+
+/// Categories and the words in a name or description that put an entry in them
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "Documents",
+        &[
+            "pdf",
+            "docx",
+            "xlsx",
+            "pptx",
+            "document",
+            "spreadsheet",
+            "presentation",
+        ],
+    ),
+    (
+        "Databases",
+        &[
+            "database", "sql", "postgres", "sqlite", "mysql", "redis", "mongo",
+        ],
+    ),
+    (
+        "Developer tools",
+        &[
+            "git", "github", "gitlab", "code", "test", "debug", "build", "mcp",
+        ],
+    ),
+    (
+        "Web",
+        &[
+            "web",
+            "fetch",
+            "browser",
+            "puppeteer",
+            "playwright",
+            "search",
+            "scrape",
+        ],
+    ),
+    (
+        "Communication",
+        &["slack", "email", "gmail", "discord", "message", "chat"],
+    ),
+    ("Files", &["file", "filesystem", "drive", "storage", "s3"]),
+    (
+        "Design",
+        &["design", "art", "canvas", "image", "theme", "brand", "gif"],
+    ),
+    ("Knowledge", &["memory", "knowledge", "notes", "wiki"]),
+];
+
+/// A catalog entry with the categories it was sorted into
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub description: String,
+    pub url: String,
+    pub categories: Vec<String>,
+    /// GitHub signals, when they have been fetched
+    pub popularity: Option<Popularity>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogPage {
+    pub entries: Vec<CatalogEntry>,
+    /// Entries matching the query and category, on all pages
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    /// Categories of the entries matching the query, for filter chips
+    pub categories: Vec<CategoryCount>,
+}
+
+struct CachedCatalog {
+    entries: Vec<CatalogEntry>,
+    fetched_at: Instant,
+}
+
+fn cache() -> &'static Mutex<HashMap<String, CachedCatalog>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, CachedCatalog>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn categorize(name: &str, description: &str) -> Vec<String> {
+    let words: Vec<String> = format!("{} {}", name, description)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect();
+    CATEGORIES
+        .iter()
+        .filter(|(_, keywords)| keywords.iter().any(|k| words.iter().any(|w| w == k)))
+        .map(|(category, _)| category.to_string())
+        .collect()
+}
+
+fn to_entries(items: Vec<SkillInfo>) -> Vec<CatalogEntry> {
+    items
+        .into_iter()
+        .map(|item| CatalogEntry {
+            categories: categorize(&item.name, &item.description),
+            name: item.name,
+            description: item.description,
+            url: item.url,
+            popularity: None,
+        })
+        .collect()
+}
+
+/// Keep a freshly fetched catalog for `search_catalog`
+pub(crate) fn remember(kind: &str, items: &[SkillInfo]) {
+    let entries = to_entries(items.to_vec());
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(
+            kind.to_string(),
+            CachedCatalog {
+                entries,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+}
+
+fn cached(kind: &str) -> Option<Vec<CatalogEntry>> {
+    let cache = cache().lock().ok()?;
+    cache
+        .get(kind)
+        .filter(|c| c.fetched_at.elapsed() < CACHE_TTL)
+        .map(|c| c.entries.clone())
+}
+
+/// `needle`'s characters appear in `haystack` in order
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// How well an entry matches every term of a query; `None` if a term doesn't
+fn match_score(terms: &[String], entry: &CatalogEntry) -> Option<u32> {
+    let name = entry.name.to_lowercase();
+    let description = entry.description.to_lowercase();
+    terms.iter().try_fold(0, |score, term| {
+        let term_score = if name == *term {
+            100
+        } else if name.starts_with(term.as_str()) {
+            75
+        } else if name.contains(term.as_str()) {
+            50
+        } else if description.contains(term.as_str()) {
+            20
+        } else if term.chars().count() >= 3 && is_subsequence(term, &name) {
+            // Typo-tolerant: "gthub" finds "github"
+            10
+        } else {
+            return None;
+        };
+        Some(score + term_score)
+    })
+}
+
+/// Stars of an entry, if known, for sorting by popularity
+fn stars(entry: &CatalogEntry) -> Option<u64> {
+    entry.popularity.as_ref().map(|p| p.stars)
+}
+
+/// Last commit of an entry, if known, for sorting by recency
+fn last_commit(entry: &CatalogEntry) -> Option<chrono::DateTime<chrono::Utc>> {
+    entry.popularity.as_ref().and_then(|p| p.last_commit_at)
+}
+
+/// Filter, rank and paginate catalog entries
+///
+/// Entries are ordered by `sort` ("relevance" uses the match score), then by
+/// name, so pages stay stable between calls. Entries without popularity
+/// signals come last when sorting by popularity or recency.
+fn search(
+    entries: Vec<CatalogEntry>,
+    query: &str,
+    category: Option<&str>,
+    sort: &str,
+    page: usize,
+    page_size: usize,
+) -> CatalogPage {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut matches: Vec<(u32, CatalogEntry)> = entries
+        .into_iter()
+        .filter_map(|entry| Some((match_score(&terms, &entry)?, entry)))
+        .collect();
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        let by_sort = match sort {
+            "popularity" => stars(b)
+                .cmp(&stars(a))
+                .then_with(|| last_commit(b).cmp(&last_commit(a))),
+            "recent" => last_commit(b).cmp(&last_commit(a)),
+            _ => std::cmp::Ordering::Equal,
+        };
+        by_sort
+            .then_with(|| b_score.cmp(a_score))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, entry) in &matches {
+        for category in &entry.categories {
+            *counts.entry(category.clone()).or_default() += 1;
+        }
+    }
+
+    let filtered: Vec<CatalogEntry> = matches
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .filter(|entry| {
+            category.map_or(true, |category| {
+                entry
+                    .categories
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(category))
+            })
+        })
+        .collect();
+    CatalogPage {
+        total: filtered.len(),
+        entries: filtered
+            .into_iter()
+            .skip(page * page_size)
+            .take(page_size)
+            .collect(),
+        page,
+        page_size,
+        categories: counts
+            .into_iter()
+            .map(|(name, count)| CategoryCount { name, count })
+            .collect(),
+    }
+}
+
+/// Fill in the popularity of entries from GitHub, or its cache
+async fn add_popularity(
+    db: &State<'_, AgentDb>,
+    entries: &mut [CatalogEntry],
+) -> Result<(), String> {
+    let settings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_catalog_fetch_settings(&conn)
+    };
+    let client = crate::http_client::client()?;
+    let urls = entries.iter().map(|e| e.url.clone()).collect();
+    let mut popularity = popularity_for(&client, urls, &settings).await;
+    for entry in entries {
+        entry.popularity = popularity.remove(&entry.url);
+    }
+    Ok(())
+}
+
+/// Search the skill or MCP server catalog by name and description
+///
+/// `kind` is "skills" or "mcp_servers". The catalog is fetched when it isn't
+/// cached or the cache is over an hour old. `sort` is "relevance" (default),
+/// "popularity" or "recent"; popularity is fetched for the whole catalog
+/// when sorting by it, otherwise only for the returned page. Pages start at 0.
+#[tauri::command]
+pub async fn search_catalog(
+    db: State<'_, AgentDb>,
+    kind: String,
+    query: Option<String>,
+    category: Option<String>,
+    sort: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> Result<CatalogPage, String> {
+    let sort = sort.unwrap_or_else(|| "relevance".to_string());
+    if !SORT_ORDERS.contains(&sort.as_str()) {
+        return Err(format!(
+            "Unknown sort order '{}'; expected one of {}",
+            sort,
+            SORT_ORDERS.join(", ")
+        ));
+    }
+    let mut entries = match cached(&kind) {
+        Some(entries) => entries,
+        None => match kind.as_str() {
+            "skills" => to_entries(
+                fetch_available_skills(db.clone())
+                    .await
+                    .map_err(|e| e.to_string())?,
+            ),
+            "mcp_servers" => to_entries(fetch_mcp_marketplace(db.clone()).await?),
+            _ => {
+                return Err(format!(
+                    "Unknown catalog '{}'; expected one of {}",
+                    kind,
+                    CATALOG_KINDS.join(", ")
+                ))
+            }
+        },
+    };
+    let page_size = page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    if sort != "relevance" {
+        add_popularity(&db, &mut entries).await?;
+    }
+    let mut result = search(
+        entries,
+        query.as_deref().unwrap_or_default(),
+        category.as_deref().filter(|c| !c.is_empty()),
+        &sort,
+        page.unwrap_or(0),
+        page_size,
+    );
+    if sort == "relevance" {
+        add_popularity(&db, &mut result.entries).await?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_ranking_and_pagination() {
+        let entries = to_entries(
+            [
+                ("postgres", "PostgreSQL Database"),
+                ("github", "GitHub API Integration"),
+                ("git", "Read and search Git repositories"),
+                ("sqlite", "SQLite Database"),
+                ("gitlab", "GitLab API"),
+            ]
+            .into_iter()
+            .map(|(name, description)| SkillInfo {
+                name: name.to_string(),
+                description: description.to_string(),
+                url: String::new(),
+            })
+            .collect(),
+        );
+
+        let names = |page: &CatalogPage| {
+            page.entries
+                .iter()
+                .map(|e| e.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let page = search(entries.clone(), "git", None, "relevance", 0, 2);
+        assert_eq!(names(&page), ["git", "github"]);
+        assert_eq!(page.total, 3);
+        assert_eq!(
+            names(&search(entries.clone(), "git", None, "relevance", 1, 2)),
+            ["gitlab"]
+        );
+
+        assert_eq!(
+            names(&search(entries.clone(), "gthub", None, "relevance", 0, 10)),
+            ["github"]
+        );
+        let databases = search(entries.clone(), "", Some("databases"), "relevance", 0, 10);
+        assert_eq!(names(&databases), ["postgres", "sqlite"]);
+        assert!(databases
+            .categories
+            .iter()
+            .any(|c| c.name == "Developer tools" && c.count == 3));
+
+        let mut entries = entries;
+        for (entry, (stars, day)) in entries.iter_mut().zip([(5, 1), (50, 2), (50, 3), (1, 9)]) {
+            entry.popularity = Some(Popularity {
+                stars,
+                open_issues: 0,
+                last_commit_at: chrono::DateTime::from_timestamp(day * 86_400, 0),
+            });
+        }
+        assert_eq!(
+            names(&search(entries.clone(), "", None, "popularity", 0, 10)),
+            ["git", "github", "postgres", "sqlite", "gitlab"]
+        );
+        assert_eq!(
+            names(&search(entries, "", None, "recent", 0, 10)),
+            ["sqlite", "git", "github", "postgres", "gitlab"]
+        );
+    }
+}
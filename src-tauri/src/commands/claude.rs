@@ -1,4 +1,22 @@
+use crate::cli_compat;
+use crate::commands::agents::AgentDb;
+use crate::commands::run_history;
+use crate::commands::run_manifest;
+use crate::commands::streaming;
+use crate::commands::telemetry;
+use crate::event_bus;
+use crate::file_audit::{self, FileOperation};
+use crate::notifications::RunNotifier;
+use crate::process::output_stream::OutputBatcher;
+use crate::project_registry;
+use crate::prompt_history;
+use crate::redaction;
+use crate::run_env::{
+    apply_env_overrides, record_env_overrides, validate_env_overrides, EnvOverrides,
+};
 use crate::utils::get_claude_dir;
+use crate::window_sessions;
+use crate::workspace::{add_dir_args, additional_dirs_from_args, validate_additional_dirs};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -7,7 +25,7 @@ use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::SystemTime;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Manager};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
@@ -360,7 +378,9 @@ pub async fn save_system_prompt(content: String) -> Result<String, String> {
     let claude_dir = get_claude_dir()?;
     let claude_md_path = claude_dir.join("CLAUDE.md");
 
+    let operation = FileOperation::for_write(&claude_md_path);
     fs::write(&claude_md_path, content).map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+    file_audit::record("save_system_prompt", operation, &claude_md_path);
 
     Ok("System prompt saved successfully".to_string())
 }
@@ -377,8 +397,10 @@ pub async fn save_claude_settings(settings: serde_json::Value) -> Result<String,
     let json_string = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
 
+    let operation = FileOperation::for_write(&settings_path);
     fs::write(&settings_path, json_string)
         .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    file_audit::record("save_claude_settings", operation, &settings_path);
 
     Ok("Settings saved successfully".to_string())
 }
@@ -495,7 +517,9 @@ pub async fn save_claude_md_file(file_path: String, content: String) -> Result<S
             .map_err(|e| format!("Failed to create parent directory: {}", e))?;
     }
 
+    let operation = FileOperation::for_write(&path);
     fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    file_audit::record("save_claude_md_file", operation, &path);
 
     Ok("File saved successfully".to_string())
 }
@@ -540,13 +564,24 @@ pub async fn load_session_history(
 }
 
 /// Execute a new interactive Claude Code session with streaming output
+///
+/// `env` sets extra environment variables for this run only; they are
+/// recorded in the run history.
 #[tauri::command]
 pub async fn execute_claude_code(
+    window: tauri::Window,
     app: AppHandle,
     project_path: String,
     prompt: String,
     model: String,
+    env: Option<EnvOverrides>,
+    additional_dirs: Option<Vec<String>>,
 ) -> Result<(), String> {
+    let env = env.unwrap_or_default();
+    validate_env_overrides(&env)?;
+    let additional_dirs =
+        validate_additional_dirs(&project_path, &additional_dirs.unwrap_or_default())?;
+
     log::info!(
         "Starting new Claude Code session in: {} with model: {}",
         project_path,
@@ -555,7 +590,7 @@ pub async fn execute_claude_code(
 
     let claude_path = find_claude_binary(&app)?;
 
-    let args = vec![
+    let mut args = vec![
         "-p".to_string(),
         prompt.clone(),
         "--model".to_string(),
@@ -566,19 +601,38 @@ pub async fn execute_claude_code(
         "--verbose".to_string(),
         "--dangerously-skip-permissions".to_string(),
     ];
+    args.extend(add_dir_args(&additional_dirs));
+    let args = cli_compat::adapt_args(&claude_path, args)?;
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(
+        app,
+        Some(window.label().to_string()),
+        cmd,
+        prompt,
+        model,
+        project_path,
+        env,
+    )
+    .await
 }
 
 /// Continue an existing Claude Code conversation with streaming output
 #[tauri::command]
 pub async fn continue_claude_code(
+    window: tauri::Window,
     app: AppHandle,
     project_path: String,
     prompt: String,
     model: String,
+    env: Option<EnvOverrides>,
+    additional_dirs: Option<Vec<String>>,
 ) -> Result<(), String> {
+    let env = env.unwrap_or_default();
+    validate_env_overrides(&env)?;
+    let additional_dirs =
+        validate_additional_dirs(&project_path, &additional_dirs.unwrap_or_default())?;
+
     log::info!(
         "Continuing Claude Code conversation in: {} with model: {}",
         project_path,
@@ -587,7 +641,7 @@ pub async fn continue_claude_code(
 
     let claude_path = find_claude_binary(&app)?;
 
-    let args = vec![
+    let mut args = vec![
         "-c".to_string(), // Continue flag
         "-p".to_string(),
         prompt.clone(),
@@ -599,20 +653,39 @@ pub async fn continue_claude_code(
         "--verbose".to_string(),
         "--dangerously-skip-permissions".to_string(),
     ];
+    args.extend(add_dir_args(&additional_dirs));
+    let args = cli_compat::adapt_args(&claude_path, args)?;
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(
+        app,
+        Some(window.label().to_string()),
+        cmd,
+        prompt,
+        model,
+        project_path,
+        env,
+    )
+    .await
 }
 
 /// Resume an existing Claude Code session by ID with streaming output
 #[tauri::command]
 pub async fn resume_claude_code(
+    window: tauri::Window,
     app: AppHandle,
     project_path: String,
     session_id: String,
     prompt: String,
     model: String,
+    env: Option<EnvOverrides>,
+    additional_dirs: Option<Vec<String>>,
 ) -> Result<(), String> {
+    let env = env.unwrap_or_default();
+    validate_env_overrides(&env)?;
+    let additional_dirs =
+        validate_additional_dirs(&project_path, &additional_dirs.unwrap_or_default())?;
+
     log::info!(
         "Resuming Claude Code session: {} in: {} with model: {}",
         session_id,
@@ -622,7 +695,7 @@ pub async fn resume_claude_code(
 
     let claude_path = find_claude_binary(&app)?;
 
-    let args = vec![
+    let mut args = vec![
         "--resume".to_string(),
         session_id.clone(),
         "-p".to_string(),
@@ -635,9 +708,20 @@ pub async fn resume_claude_code(
         "--verbose".to_string(),
         "--dangerously-skip-permissions".to_string(),
     ];
+    args.extend(add_dir_args(&additional_dirs));
+    let args = cli_compat::adapt_args(&claude_path, args)?;
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(
+        app,
+        Some(window.label().to_string()),
+        cmd,
+        prompt,
+        model,
+        project_path,
+        env,
+    )
+    .await
 }
 
 /// Cancel the currently running Claude Code execution
@@ -753,16 +837,23 @@ pub async fn cancel_claude_execution(
         log::warn!("No active Claude process found to cancel");
     }
 
+    if let Some(sid) = &session_id {
+        let db = app.state::<AgentDb>();
+        if let Ok(conn) = db.0.lock() {
+            let _ = run_history::record_session_cancelled(&conn, sid);
+        };
+    }
+
     // 发送取消事件 - 只发送一个以避免重复
     if let Some(sid) = session_id {
-        let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
+        event_bus::publish(&app, &format!("claude-cancelled:{}", sid), &true);
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = app.emit(&format!("claude-complete:{}", sid), false);
+        event_bus::publish(&app, &format!("claude-complete:{}", sid), &false);
     } else {
         // 只有在没有 session_id 时才发送通用事件
-        let _ = app.emit("claude-cancelled", true);
+        event_bus::publish(&app, "claude-cancelled", &true);
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = app.emit("claude-complete", false);
+        event_bus::publish(&app, "claude-complete", &false);
     }
 
     if killed {
@@ -800,6 +891,7 @@ pub async fn get_claude_session_output(
 /// Execute the Claude Agent SDK via a Node.js script (Sidecar pattern)
 #[tauri::command]
 pub async fn execute_sdk_agent(
+    window: tauri::Window,
     app: AppHandle,
     project_path: String,
     prompt: String,
@@ -841,25 +933,73 @@ pub async fn execute_sdk_agent(
     }
 
     // Reuse the existing spawn logic which handles streaming output mapping
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(
+        app,
+        Some(window.label().to_string()),
+        cmd,
+        prompt,
+        model,
+        project_path,
+        EnvOverrides::new(),
+    )
+    .await
 }
 
 /// Helper function to spawn Claude process and handle streaming
+///
+/// The session is attached to `window`, the one that started it, as soon as
+/// its ID is known; its events before that go to that window only.
 async fn spawn_claude_process(
     app: AppHandle,
+    window: Option<String>,
     mut cmd: Command,
     prompt: String,
     model: String,
     project_path: String,
+    env: EnvOverrides,
 ) -> Result<(), String> {
     use std::sync::Mutex;
+
+    apply_env_overrides(
+        &mut cmd,
+        &crate::project_env::with_project_env(&app, &project_path, &env),
+    );
+    let program = cmd.as_std().get_program().to_string_lossy().to_string();
+    let args: Vec<String> = cmd
+        .as_std()
+        .get_args()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
     use tokio::io::{AsyncBufReadExt, BufReader};
 
+    let tracer = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        telemetry::start_run_trace(&conn, "session.run")
+    };
+    if let Some(tracer) = &tracer {
+        tracer.set_attribute("run.model", model.clone());
+        tracer.set_attribute("run.project_path", project_path.clone());
+    }
+
     // Spawn the process with stdin set to null to prevent blocking
-    let mut child = cmd
+    let spawn_span = tracer.as_ref().map(|t| t.start_span("run.spawn"));
+    let spawned = cmd
         .stdin(Stdio::null())
         .spawn()
-        .map_err(|e| format!("Failed to spawn Claude: {}", e))?;
+        .map_err(|e| format!("Failed to spawn Claude: {}", e));
+    if let (Some(tracer), Some(span)) = (&tracer, spawn_span) {
+        tracer.end_span(span, spawned.as_ref().err().cloned());
+    }
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            if let Some(tracer) = &tracer {
+                tracer.finish(Some(e.clone()));
+            }
+            return Err(e);
+        }
+    };
 
     // Get stdout and stderr
     let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
@@ -877,6 +1017,41 @@ async fn spawn_claude_process(
     let session_id_holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     let run_id_holder: Arc<Mutex<Option<i64>>> = Arc::new(Mutex::new(None));
 
+    // Record the run in the persistent history
+    let new_run = run_history::NewRun {
+        run_type: "session",
+        project_path: project_path.clone(),
+        agent_id: None,
+        agent_name: None,
+        agent_run_id: None,
+        prompt: prompt.clone(),
+        model: model.clone(),
+        env_overrides: record_env_overrides(&env),
+        additional_dirs: additional_dirs_from_args(&args),
+    };
+    let history_id = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        prompt_history::remember(&conn, &project_path, &prompt, &model);
+        project_registry::touch(&conn, &project_path);
+        match run_history::record_run_started(&conn, &new_run) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                log::warn!("Failed to record Claude session in history: {}", e);
+                None
+            }
+        }
+    };
+    if let Some(id) = history_id {
+        run_manifest::record_manifest_in_background(&app, id, new_run, program, args, env);
+    }
+
+    let streaming_settings = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        streaming::load_streaming_settings(&conn)
+    };
+
     // Store the child process in the global state (for backward compatibility)
     let claude_state = app.state::<ClaudeProcessState>();
     {
@@ -898,6 +1073,23 @@ async fn spawn_claude_process(
     let project_path_clone = project_path.clone();
     let prompt_clone = prompt.clone();
     let model_clone = model.clone();
+    let batcher = OutputBatcher::spawn(
+        app.clone(),
+        window.clone(),
+        streaming_settings.batch_config(),
+    );
+    let window_for_stdout = window.clone();
+    let emit_line_events = streaming_settings.emit_line_events;
+    let tracer_for_stdout = tracer.clone();
+    let project_name = std::path::Path::new(&project_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| project_path.clone());
+    let notifier = Arc::new(RunNotifier::new(
+        app.clone(),
+        format!("Claude session in {}", project_name),
+    ));
+    let notifier_for_stdout = notifier.clone();
     let stdout_task = tokio::spawn(async move {
         let mut lines = stdout_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
@@ -910,6 +1102,28 @@ async fn spawn_claude_process(
                             *session_id_guard = Some(claude_session_id.to_string());
                             log::info!("Extracted Claude session ID: {}", claude_session_id);
 
+                            if let Some(label) = &window_for_stdout {
+                                app_handle
+                                    .state::<window_sessions::WindowSessions>()
+                                    .attach(claude_session_id, label);
+                            }
+
+                            if let Some(tracer) = &tracer_for_stdout {
+                                tracer.set_attribute("session.id", claude_session_id);
+                            }
+
+                            if let Some(history_id) = history_id {
+                                let db = app_handle.state::<AgentDb>();
+                                if let Ok(conn) = db.0.lock() {
+                                    let _ = run_history::record_run_session(
+                                        &conn,
+                                        history_id,
+                                        claude_session_id,
+                                        &project_path_clone,
+                                    );
+                                };
+                            }
+
                             // Now register with ProcessRegistry using Claude's session ID
                             match registry_clone.register_claude_session(
                                 claude_session_id.to_string(),
@@ -932,22 +1146,56 @@ async fn spawn_claude_process(
                 }
             }
 
+            // Hide secrets before the line is stored or shown anywhere
+            let session_id = session_id_holder_clone.lock().unwrap().clone();
+            let line = redaction::redact_output(&app_handle, session_id.as_deref(), line);
+
+            // Track tool spans for trace export
+            if let Some(tracer) = &tracer_for_stdout {
+                tracer.observe_output_line(&line);
+            }
+            notifier_for_stdout.observe_line(&line);
+
             // Store live output in registry if we have a run_id
+            let mut seq = None;
             if let Some(run_id) = *run_id_holder_clone.lock().unwrap() {
-                let _ = registry_clone.append_live_output(run_id, &line);
+                seq = registry_clone
+                    .append_live_output(run_id, &line)
+                    .ok()
+                    .flatten();
             }
 
             // Emit the line to the frontend with session isolation if we have session ID
-            if let Some(ref session_id) = *session_id_holder_clone.lock().unwrap() {
-                let _ = app_handle.emit(&format!("claude-output:{}", session_id), &line);
+            if let Some(ref session_id) = session_id {
+                batcher
+                    .push(&format!("claude-output-batch:{}", session_id), seq, &line)
+                    .await;
+                if emit_line_events {
+                    window_sessions::emit(
+                        &app_handle,
+                        &format!("claude-output:{}", session_id),
+                        &line,
+                    );
+                }
             } else {
-                let _ = app_handle.emit("claude-output", &line);
+                batcher.push("claude-output-batch", seq, &line).await;
+                if emit_line_events {
+                    window_sessions::emit_to_window(
+                        &app_handle,
+                        window_for_stdout.as_deref(),
+                        "claude-output",
+                        &line,
+                    );
+                }
             }
         }
+
+        batcher.finish().await;
     });
 
     let app_handle_stderr = app.clone();
     let session_id_holder_clone2 = session_id_holder.clone();
+    let window_for_stderr = window.clone();
     let stderr_task = tokio::spawn(async move {
         let mut lines = stderr_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
@@ -961,12 +1209,23 @@ async fn spawn_claude_process(
                 continue;
             }
 
+            let session_id = session_id_holder_clone2.lock().unwrap().clone();
+            let line = redaction::redact_output(&app_handle_stderr, session_id.as_deref(), line);
             log::error!("Claude stderr: {}", line);
             // Emit error lines to the frontend - 只发送一个事件避免重复
-            if let Some(ref session_id) = *session_id_holder_clone2.lock().unwrap() {
-                let _ = app_handle_stderr.emit(&format!("claude-error:{}", session_id), &line);
+            if let Some(ref session_id) = session_id {
+                window_sessions::emit(
+                    &app_handle_stderr,
+                    &format!("claude-error:{}", session_id),
+                    &line,
+                );
             } else {
-                let _ = app_handle_stderr.emit("claude-error", &line);
+                window_sessions::emit_to_window(
+                    &app_handle_stderr,
+                    window_for_stderr.as_deref(),
+                    "claude-error",
+                    &line,
+                );
             }
         }
     });
@@ -983,17 +1242,27 @@ async fn spawn_claude_process(
 
         // Get the child from the state to wait on it
         let mut current_process = claude_state_wait.lock().await;
+        let mut exit_status = None;
         if let Some(mut child) = current_process.take() {
             match child.wait().await {
                 Ok(status) => {
                     log::info!("Claude process exited with status: {}", status);
+                    exit_status = Some(status);
                     // Add a small delay to ensure all messages are processed
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                        let _ = app_handle_wait
-                            .emit(&format!("claude-complete:{}", session_id), status.success());
+                        event_bus::publish(
+                            &app_handle_wait,
+                            &format!("claude-complete:{}", session_id),
+                            &status.success(),
+                        );
                     } else {
-                        let _ = app_handle_wait.emit("claude-complete", status.success());
+                        event_bus::publish_to_window(
+                            &app_handle_wait,
+                            window.as_deref(),
+                            "claude-complete",
+                            &status.success(),
+                        );
                     }
                 }
                 Err(e) => {
@@ -1001,17 +1270,68 @@ async fn spawn_claude_process(
                     // Add a small delay to ensure all messages are processed
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                        let _ =
-                            app_handle_wait.emit(&format!("claude-complete:{}", session_id), false);
+                        event_bus::publish(
+                            &app_handle_wait,
+                            &format!("claude-complete:{}", session_id),
+                            &false,
+                        );
                     } else {
-                        let _ = app_handle_wait.emit("claude-complete", false);
+                        event_bus::publish_to_window(
+                            &app_handle_wait,
+                            window.as_deref(),
+                            "claude-complete",
+                            &false,
+                        );
                     }
                 }
             }
         }
 
+        let run_id = *run_id_holder_clone2.lock().unwrap();
+
+        // Record the outcome in the persistent history
+        let status = match exit_status {
+            Some(status) if status.success() => "completed",
+            _ => "failed",
+        };
+        if let Some(history_id) = history_id {
+            // The live output buffer drops old lines; its usage covers them all
+            let usage = run_id
+                .and_then(|id| registry_clone2.get_run_usage(id).ok())
+                .unwrap_or_default();
+            let db = app_handle_wait.state::<AgentDb>();
+            if let Ok(conn) = db.0.lock() {
+                if let Err(e) = run_history::record_run_finished(
+                    &conn,
+                    history_id,
+                    status,
+                    exit_status.and_then(|s| s.code()),
+                    &usage,
+                ) {
+                    log::warn!(
+                        "Failed to record Claude session completion in history: {}",
+                        e
+                    );
+                }
+            };
+        }
+
+        notifier.finished(history_id, status);
+
+        if let Some(tracer) = &tracer {
+            let error = match exit_status {
+                Some(status) if status.success() => None,
+                Some(status) => Some(format!("Claude exited with {}", status)),
+                None => Some("Failed to wait for Claude process".to_string()),
+            };
+            if let Some(code) = exit_status.and_then(|s| s.code()) {
+                tracer.set_attribute("process.exit_code", code as i64);
+            }
+            tracer.finish(error);
+        }
+
         // Unregister from ProcessRegistry if we have a run_id
-        if let Some(run_id) = *run_id_holder_clone2.lock().unwrap() {
+        if let Some(run_id) = run_id {
             let _ = registry_clone2.unregister_process(run_id);
         }
 
@@ -1319,8 +1639,10 @@ pub async fn restore_checkpoint(
         .load_checkpoint(&result.checkpoint.project_id, &session_id, &checkpoint_id)
         .map_err(|e| format!("Failed to load checkpoint data: {}", e))?;
 
+    let operation = FileOperation::for_write(&session_path);
     fs::write(&session_path, messages)
         .map_err(|e| format!("Failed to update session file: {}", e))?;
+    file_audit::record("restore_checkpoint", operation, &session_path);
 
     Ok(result)
 }
@@ -1377,8 +1699,10 @@ pub async fn fork_from_checkpoint(
         .join(format!("{}.jsonl", new_session_id));
 
     if source_session_path.exists() {
+        let operation = FileOperation::for_write(&new_session_path);
         fs::copy(&source_session_path, &new_session_path)
             .map_err(|e| format!("Failed to copy session file: {}", e))?;
+        file_audit::record("fork_from_checkpoint", operation, &new_session_path);
     }
 
     // Create manager for the new session
@@ -1498,6 +1822,7 @@ pub async fn get_checkpoint_diff(
     }
 
     // Calculate differences
+    let providers = crate::diff_providers::DiffProviders::with_default_providers();
     let mut modified_files = Vec::new();
     let mut added_files = Vec::new();
     let mut deleted_files = Vec::new();
@@ -1515,6 +1840,11 @@ pub async fn get_checkpoint_diff(
                     additions,
                     deletions,
                     diff_content: None, // TODO: Generate actual diff
+                    special: providers.diff(
+                        path,
+                        Some(from_file.content.as_bytes()),
+                        Some(to_file.content.as_bytes()),
+                    ),
                 });
             }
         } else {
@@ -1835,8 +2165,10 @@ pub async fn update_hooks_config(
     let json_string = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
 
+    let operation = FileOperation::for_write(&settings_path);
     fs::write(&settings_path, json_string)
         .map_err(|e| format!("Failed to write settings: {}", e))?;
+    file_audit::record("update_hooks_config", operation, &settings_path);
 
     Ok("Hooks configuration updated successfully".to_string())
 }
@@ -0,0 +1,136 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use walkdir::WalkDir;
+
+use super::agents::AgentDb;
+use super::settings as store;
+use crate::checkpoint::state::CheckpointState;
+use crate::utils::{self, ClaudeDirSource};
+
+/// app_settings key holding a custom Claude directory
+const CLAUDE_DIR_SETTING: &str = "claude_dir_path";
+
+/// Disk usage of a directory inside the Claude directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeSubdirInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub file_count: u64,
+}
+
+/// Location and disk usage of the Claude directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeDirInfo {
+    pub path: String,
+    pub source: ClaudeDirSource,
+    pub exists: bool,
+    pub total_size_bytes: u64,
+    /// Immediate subdirectories, largest first
+    pub subdirectories: Vec<ClaudeSubdirInfo>,
+}
+
+/// Load the custom Claude directory from app_settings
+pub fn load_claude_dir_setting(conn: &Connection) -> Option<PathBuf> {
+    store::get_text(conn, CLAUDE_DIR_SETTING)
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Total size and file count of everything under `path`
+fn dir_usage(path: &Path) -> (u64, u64) {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .fold((0, 0), |(size, count), m| (size + m.len(), count + 1))
+}
+
+/// Describe the Claude directory at `path` without creating it
+pub fn claude_dir_info(path: &Path, source: ClaudeDirSource) -> ClaudeDirInfo {
+    let mut info = ClaudeDirInfo {
+        path: path.to_string_lossy().to_string(),
+        source,
+        exists: path.is_dir(),
+        total_size_bytes: 0,
+        subdirectories: Vec::new(),
+    };
+    if !info.exists {
+        return info;
+    }
+
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                let (size_bytes, file_count) = dir_usage(&entry_path);
+                info.total_size_bytes += size_bytes;
+                info.subdirectories.push(ClaudeSubdirInfo {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    size_bytes,
+                    file_count,
+                });
+            } else if let Ok(metadata) = entry.metadata() {
+                info.total_size_bytes += metadata.len();
+            }
+        }
+    }
+
+    info.subdirectories
+        .sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then(a.name.cmp(&b.name)));
+    info
+}
+
+/// Report where the Claude directory is, whether it exists and what it holds
+#[tauri::command]
+pub async fn get_claude_dir_info() -> Result<ClaudeDirInfo, String> {
+    let (path, source) = utils::resolve_claude_dir()?;
+    tokio::task::spawn_blocking(move || claude_dir_info(&path, source))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Use a custom Claude directory, or pass `None` to go back to the default
+///
+/// The directory is created if needed. Claude processes started afterwards get it
+/// through `CLAUDE_CONFIG_DIR`.
+#[tauri::command]
+pub async fn set_claude_dir_location(
+    db: State<'_, AgentDb>,
+    checkpoint_state: State<'_, CheckpointState>,
+    path: Option<String>,
+) -> Result<ClaudeDirInfo, String> {
+    let path = path
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from);
+
+    if let Some(path) = &path {
+        if !path.is_absolute() {
+            return Err(format!(
+                "Claude directory must be an absolute path: {}",
+                path.display()
+            ));
+        }
+        std::fs::create_dir_all(path)
+            .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    }
+
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let value = path.as_ref().map(|path| path.to_string_lossy().to_string());
+        store::set_value(&conn, CLAUDE_DIR_SETTING, &value.into())?;
+    }
+
+    utils::set_custom_claude_dir(path);
+    let claude_dir = utils::get_claude_dir()?;
+    log::info!("Claude directory set to {}", claude_dir.display());
+
+    // Checkpoint managers cache paths under the old directory
+    checkpoint_state.clear_all().await;
+    checkpoint_state.set_claude_dir(claude_dir).await;
+
+    get_claude_dir_info().await
+}
@@ -0,0 +1,356 @@
+// Importing a Claude directory from another machine
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use zip::ZipArchive;
+
+use super::skills::write_atomically;
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::resolve_within;
+
+/// What happens to a file that exists locally with different content
+pub const CONFLICT_POLICIES: &[&str] = &["skip", "rename", "overwrite"];
+
+const MAX_ENTRIES: usize = 100_000;
+const MAX_FILE_SIZE: u64 = 512 * 1024 * 1024;
+const MAX_TOTAL_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// What the import does with one entry of the archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntryPlan {
+    /// "sessions", "commands", "agents", or "other" for entries not imported
+    pub section: String,
+    /// Path inside the Claude directory
+    pub path: String,
+    /// "add", "unchanged", "skip", "rename", "overwrite", "ignored" or "failed"
+    pub action: String,
+    /// Where a renamed file is written
+    pub target: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// Outcome of `import_claude_dir_archive`
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeDirImportReport {
+    /// False for a dry run
+    pub applied: bool,
+    pub policy: String,
+    pub entries: Vec<ArchiveEntryPlan>,
+}
+
+impl ArchiveEntryPlan {
+    fn new(section: &str, path: &Path, action: &str, detail: Option<String>) -> Self {
+        Self {
+            section: section.to_string(),
+            path: path.to_string_lossy().replace('\\', "/"),
+            action: action.to_string(),
+            target: None,
+            detail,
+        }
+    }
+}
+
+/// Which imported section a path inside the Claude directory belongs to
+fn section_of(path: &Path) -> Option<&'static str> {
+    let parts: Vec<&str> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let extension = path.extension().and_then(|e| e.to_str());
+    match (parts.as_slice(), extension) {
+        (["projects", _, _], Some("jsonl")) => Some("sessions"),
+        (["commands", .., _], Some("md")) => Some("commands"),
+        (["agents", .., _], Some("md")) => Some("agents"),
+        _ => None,
+    }
+}
+
+/// A free name next to `path`: `review-imported.md`, `review-imported-2.md`, ...
+fn renamed(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => path.with_file_name(format!("{}-imported{}", stem, extension)),
+            n => path.with_file_name(format!("{}-imported-{}{}", stem, n, extension)),
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded")
+}
+
+/// Read one entry, refusing entries larger than they claim or than the cap
+fn read_entry(archive: &mut ZipArchive<File>, index: usize) -> Result<Vec<u8>, String> {
+    let file = archive.by_index(index).map_err(|e| e.to_string())?;
+    let size = file.size();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("Larger than {} MB", MAX_FILE_SIZE / 1024 / 1024));
+    }
+    let mut content = Vec::with_capacity(size as usize);
+    file.take(size + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read: {}", e))?;
+    if content.len() as u64 != size {
+        return Err("The entry's size doesn't match the archive".to_string());
+    }
+    Ok(content)
+}
+
+/// Archives usually hold a `.claude/` folder; entries are taken relative to it
+fn common_root(names: &[PathBuf]) -> Option<PathBuf> {
+    let first = names.first()?.components().next()?;
+    let top = Path::new(first.as_os_str());
+    let shared = names
+        .iter()
+        .all(|name| name.starts_with(top) && name.components().count() > 1);
+    let is_section = matches!(top.to_str(), Some("projects" | "commands" | "agents"));
+    (shared && !is_section).then(|| top.to_path_buf())
+}
+
+fn import_archive(
+    archive_path: &Path,
+    claude_dir: &Path,
+    policy: &str,
+    dry_run: bool,
+) -> Result<ClaudeDirImportReport, String> {
+    if !CONFLICT_POLICIES.contains(&policy) {
+        return Err(format!(
+            "Unknown conflict policy '{}'; expected one of {}",
+            policy,
+            CONFLICT_POLICIES.join(", ")
+        ));
+    }
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Not a valid zip archive: {}", e))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(format!(
+            "The archive has {} entries; at most {} are imported",
+            archive.len(),
+            MAX_ENTRIES
+        ));
+    }
+
+    let mut entries = Vec::new();
+    // (index, path inside the archive)
+    let mut files: Vec<(usize, PathBuf)> = Vec::new();
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let raw_name = entry.name().to_string();
+        match entry.enclosed_name() {
+            Some(name) if !entry.is_symlink() => {
+                total += entry.size();
+                files.push((index, name));
+            }
+            _ => entries.push(ArchiveEntryPlan::new(
+                "other",
+                Path::new(&raw_name),
+                "ignored",
+                Some("Unsafe path or symlink".to_string()),
+            )),
+        }
+    }
+    if total > MAX_TOTAL_SIZE {
+        return Err(format!(
+            "The archive expands to {} MB; at most {} MB is imported",
+            total / 1024 / 1024,
+            MAX_TOTAL_SIZE / 1024 / 1024
+        ));
+    }
+
+    let names: Vec<PathBuf> = files.iter().map(|(_, name)| name.clone()).collect();
+    let root = common_root(&names);
+    // (index, plan position, destination)
+    let mut writes: Vec<(usize, usize, PathBuf)> = Vec::new();
+    for (index, name) in files {
+        let relative = match &root {
+            Some(root) => name.strip_prefix(root).unwrap_or(&name).to_path_buf(),
+            None => name,
+        };
+        let Some(section) = section_of(&relative) else {
+            entries.push(ArchiveEntryPlan::new(
+                "other",
+                &relative,
+                "ignored",
+                Some("Only sessions, commands and agents are imported".to_string()),
+            ));
+            continue;
+        };
+        let destination = match resolve_within(claude_dir, &relative) {
+            Ok(destination) => destination,
+            Err(e) => {
+                entries.push(ArchiveEntryPlan::new(
+                    section,
+                    &relative,
+                    "ignored",
+                    Some(e),
+                ));
+                continue;
+            }
+        };
+
+        let mut plan = ArchiveEntryPlan::new(section, &relative, "add", None);
+        if destination.exists() {
+            let incoming = match read_entry(&mut archive, index) {
+                Ok(content) => content,
+                Err(e) => {
+                    entries.push(ArchiveEntryPlan::new(section, &relative, "failed", Some(e)));
+                    continue;
+                }
+            };
+            plan.action = if fs::read(&destination).is_ok_and(|local| local == incoming) {
+                "unchanged"
+            } else {
+                policy
+            }
+            .to_string();
+        }
+        let destination = match plan.action.as_str() {
+            "unchanged" | "skip" => {
+                entries.push(plan);
+                continue;
+            }
+            "rename" => {
+                let target = renamed(&destination);
+                plan.target = target
+                    .strip_prefix(claude_dir)
+                    .ok()
+                    .map(|t| t.to_string_lossy().replace('\\', "/"));
+                target
+            }
+            _ => destination,
+        };
+        writes.push((index, entries.len(), destination));
+        entries.push(plan);
+    }
+
+    if !dry_run {
+        for (index, position, destination) in writes {
+            let operation = FileOperation::for_write(&destination);
+            let written = read_entry(&mut archive, index).and_then(|content| {
+                let dir = destination.parent().unwrap_or(claude_dir);
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+                write_atomically(dir, &destination, &content)
+            });
+            if written.is_ok() {
+                file_audit::record("import_claude_dir_archive", operation, &destination);
+            }
+            if let Err(e) = written {
+                entries[position].action = "failed".to_string();
+                entries[position].detail = Some(e);
+            }
+        }
+        log::info!(
+            "Imported {} into {}",
+            archive_path.display(),
+            claude_dir.display()
+        );
+    }
+
+    Ok(ClaudeDirImportReport {
+        applied: !dry_run,
+        policy: policy.to_string(),
+        entries,
+    })
+}
+
+/// Merge a zipped `~/.claude` from another machine into the Claude directory
+///
+/// Imports sessions, slash commands and agents; credentials, settings and
+/// caches belong to the machine they came from and are listed as ignored.
+/// Entries with absolute paths, `..` or symlinks are refused, and sizes are
+/// capped so a hostile archive can't fill the disk. `conflict_policy` is `skip`
+/// (the default), `rename` or `overwrite`, for files that exist here with
+/// different content. With `dry_run` nothing is written and the report lists
+/// what would happen to every entry.
+#[tauri::command]
+pub async fn import_claude_dir_archive(
+    archive_path: String,
+    conflict_policy: Option<String>,
+    dry_run: bool,
+) -> Result<ClaudeDirImportReport, String> {
+    let claude_dir = crate::utils::get_claude_dir()?;
+    let policy = conflict_policy.unwrap_or_else(|| "skip".to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        import_archive(Path::new(&archive_path), &claude_dir, &policy, dry_run)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    #[test]
+    fn test_import_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("claude.zip");
+        let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        for (name, content) in [
+            (".claude/projects/-home-me-app/abc.jsonl", "{}\n"),
+            (".claude/commands/review.md", "Review the diff"),
+            (".claude/agents/tester.md", "Write tests"),
+            (".claude/.credentials.json", "{\"token\":\"x\"}"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.start_file("../escape.md", options).unwrap();
+        zip.finish().unwrap();
+
+        let claude_dir = dir.path().join("claude");
+        fs::create_dir_all(claude_dir.join("commands")).unwrap();
+        fs::write(claude_dir.join("commands/review.md"), "Local review").unwrap();
+
+        let action = |report: &ClaudeDirImportReport, path: &str| {
+            report
+                .entries
+                .iter()
+                .find(|e| e.path == path)
+                .map(|e| e.action.clone())
+        };
+        let preview = import_archive(&archive_path, &claude_dir, "rename", true).unwrap();
+        assert_eq!(
+            action(&preview, "commands/review.md").as_deref(),
+            Some("rename")
+        );
+        assert_eq!(
+            action(&preview, ".credentials.json").as_deref(),
+            Some("ignored")
+        );
+        assert_eq!(action(&preview, "../escape.md").as_deref(), Some("ignored"));
+        assert!(!claude_dir.join("agents").exists());
+
+        import_archive(&archive_path, &claude_dir, "rename", false).unwrap();
+        assert_eq!(
+            fs::read_to_string(claude_dir.join("commands/review.md")).unwrap(),
+            "Local review"
+        );
+        assert_eq!(
+            fs::read_to_string(claude_dir.join("commands/review-imported.md")).unwrap(),
+            "Review the diff"
+        );
+        assert!(claude_dir.join("projects/-home-me-app/abc.jsonl").is_file());
+        assert!(!claude_dir.join(".credentials.json").exists());
+    }
+}
@@ -0,0 +1,653 @@
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::OnceLock;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::models::configured_api_key;
+use crate::file_audit::{self, FileOperation};
+use crate::network::send_with_retry;
+use crate::path_validation::validate_project_root;
+use crate::utils::read_json_file;
+
+/// Model used to rewrite sections when none is given
+const DEFAULT_REGENERATE_MODEL: &str = "claude-sonnet-4-20250514";
+const REGENERATE_MAX_TOKENS: u32 = 4096;
+/// Directories left out of the layout sent to the model
+const IGNORED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    "__pycache__",
+];
+/// Package manager subcommands that aren't scripts
+const RUNNER_BUILTINS: &[&str] = &[
+    "install", "i", "add", "remove", "rm", "ci", "dlx", "x", "exec", "create", "init", "upgrade",
+    "update", "link", "publish", "audit", "outdated", "why", "info",
+];
+
+/// A claim in CLAUDE.md that doesn't match the repository
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StaleClaim {
+    /// "command", "path" or "dependency"
+    pub kind: String,
+    pub claim: String,
+    /// 1-based line in CLAUDE.md
+    pub line: usize,
+    pub reason: String,
+}
+
+/// One heading's section of CLAUDE.md and what in it is out of date
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionFreshness {
+    /// Empty for the text before the first heading
+    pub heading: String,
+    pub level: usize,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Claims that could be checked
+    pub checked: usize,
+    pub problems: Vec<StaleClaim>,
+}
+
+/// Freshness of a project's CLAUDE.md, section by section
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeMdFreshness {
+    pub path: String,
+    pub sections: Vec<SectionFreshness>,
+    pub stale_sections: usize,
+}
+
+/// A section rewritten by the model
+#[derive(Debug, Clone, Serialize)]
+pub struct RegeneratedSection {
+    pub heading: String,
+    pub original: String,
+    pub replacement: String,
+    /// Whether CLAUDE.md was updated
+    pub applied: bool,
+}
+
+struct Section<'a> {
+    heading: String,
+    level: usize,
+    /// 0-based index of the first line, and the lines including the heading
+    start: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Split markdown into sections at headings outside code blocks
+fn split_sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = vec![Section {
+        heading: String::new(),
+        level: 0,
+        start: 0,
+        lines: Vec::new(),
+    }];
+    let mut in_code = false;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        if !in_code && (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            sections.push(Section {
+                heading: line[level..].trim().to_string(),
+                level,
+                start: i,
+                lines: Vec::new(),
+            });
+        }
+        if let Some(section) = sections.last_mut() {
+            section.lines.push(line);
+        }
+    }
+    if sections[0].lines.iter().all(|l| l.trim().is_empty()) {
+        sections.remove(0);
+    }
+    sections
+}
+
+/// What the repository actually has, for checking claims against
+#[derive(Debug, Default)]
+struct RepoFacts {
+    scripts: BTreeSet<String>,
+    make_targets: BTreeSet<String>,
+    just_recipes: BTreeSet<String>,
+    /// Lowercased package names from the manifests
+    dependencies: BTreeSet<String>,
+}
+
+/// Names declared in the dependency tables of a Cargo.toml
+fn cargo_dependencies(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut in_deps = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            let table = line.trim_matches(['[', ']']);
+            in_deps = table.ends_with("dependencies");
+            // [dependencies.serde] style tables name the crate in the header
+            if let Some((_, name)) = table.split_once("dependencies.") {
+                names.push(name.to_string());
+            }
+            continue;
+        }
+        if in_deps {
+            if let Some((name, _)) = line.split_once('=') {
+                let name = name.trim().trim_matches('"');
+                if !name.is_empty() && !name.starts_with('#') {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+fn collect_facts(project: &Path) -> RepoFacts {
+    let mut facts = RepoFacts::default();
+    // Manifests in the project and one level down (e.g. a Tauri app's src-tauri)
+    let mut dirs = vec![project.to_path_buf()];
+    if let Ok(entries) = std::fs::read_dir(project) {
+        dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| {
+            p.is_dir()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| !n.starts_with('.') && !IGNORED_DIRS.contains(&n))
+        }));
+    }
+
+    for dir in &dirs {
+        let package = read_json_file(&dir.join("package.json")).unwrap_or_default();
+        if let Some(scripts) = package["scripts"].as_object() {
+            facts.scripts.extend(scripts.keys().cloned());
+        }
+        for table in ["dependencies", "devDependencies", "peerDependencies"] {
+            if let Some(deps) = package[table].as_object() {
+                facts
+                    .dependencies
+                    .extend(deps.keys().map(|k| k.to_lowercase()));
+            }
+        }
+        if let Ok(content) = std::fs::read_to_string(dir.join("Cargo.toml")) {
+            let names = cargo_dependencies(&content);
+            facts
+                .dependencies
+                .extend(names.iter().map(|n| n.to_lowercase()));
+        }
+        if let Ok(content) = std::fs::read_to_string(dir.join("requirements.txt")) {
+            facts
+                .dependencies
+                .extend(content.lines().filter_map(|line| {
+                    let name = line
+                        .split(['=', '<', '>', '~', '[', ';', ' ', '!'])
+                        .next()?
+                        .trim();
+                    (!name.is_empty() && !name.starts_with('#')).then(|| name.to_lowercase())
+                }));
+        }
+    }
+
+    let recipe = |line: &str| {
+        if line.contains(":=") {
+            return None;
+        }
+        let (name, _) = line.split_once(':')?;
+        let name = name.trim();
+        let simple = !name.is_empty()
+            && !line.starts_with([' ', '\t', '.', '#'])
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_/".contains(c));
+        simple.then(|| name.to_string())
+    };
+    if let Ok(content) = std::fs::read_to_string(project.join("Makefile")) {
+        facts
+            .make_targets
+            .extend(content.lines().filter_map(recipe));
+    }
+    for name in ["justfile", "Justfile", ".justfile"] {
+        if let Ok(content) = std::fs::read_to_string(project.join(name)) {
+            // Recipes may take parameters: `test filter="":`
+            facts.just_recipes.extend(
+                content
+                    .lines()
+                    .filter(|line| !line.contains(":="))
+                    .filter_map(|line| recipe(&format!("{}:", line.split([' ', ':']).next()?))),
+            );
+        }
+    }
+    facts
+}
+
+fn command_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?:^|&&\s*|\$\s+)(npm|pnpm|yarn|bun|make|just)\s+(run\s+)?([A-Za-z0-9:._/-]+)")
+            .expect("valid pattern")
+    })
+}
+
+fn inline_code_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"`([^`\n]+)`").expect("valid pattern"))
+}
+
+/// Problem with a command like `npm run build` or `make test`, if any
+fn check_command(facts: &RepoFacts, text: &str) -> Option<Option<String>> {
+    let captures = command_pattern().captures(text.trim())?;
+    let (tool, run, name) = (&captures[1], captures.get(2).is_some(), &captures[3]);
+    let missing = |what: &str| Some(Some(format!("No {} named '{}'", what, name)));
+    match tool {
+        "make" if facts.make_targets.is_empty() => None,
+        "make" if !facts.make_targets.contains(name) => missing("Makefile target"),
+        "just" if facts.just_recipes.is_empty() => None,
+        "just" if !facts.just_recipes.contains(name) => missing("just recipe"),
+        "make" | "just" => Some(None),
+        // `npm test` and `npm start` run scripts; other bare npm commands are built in
+        "npm" if !run && !["test", "start"].contains(&name) => None,
+        _ if !run && RUNNER_BUILTINS.contains(&name) => None,
+        _ if !facts.scripts.contains(name) => missing("package.json script"),
+        _ => Some(None),
+    }
+}
+
+/// Problem with a path mentioned in inline code, if it looks like a repo path
+fn check_path(project: &Path, token: &str) -> Option<Option<String>> {
+    let token = token.trim().trim_start_matches("./");
+    let token = token.split(':').next().unwrap_or(token);
+    let looks_like_path = token.contains('/')
+        && !token.contains(char::is_whitespace)
+        && !token.starts_with(['/', '~', '@', '-', '$', '<'])
+        && !token.contains(['*', '{', '<', '>', '=', '(', '`', '\\'])
+        && !token.contains("://");
+    if !looks_like_path {
+        return None;
+    }
+    let first = token.split('/').next().unwrap_or_default();
+    let has_extension = Path::new(token).extension().is_some();
+    if !(project.join(first).exists() || has_extension || token.ends_with('/')) {
+        return None;
+    }
+    if project.join(token).exists() {
+        Some(None)
+    } else {
+        Some(Some(format!("{} doesn't exist", token)))
+    }
+}
+
+/// Paths drawn as a tree (`├── src/`), relative to the project
+///
+/// A first line without tree characters names the tree's root: a directory
+/// in the project, or the project itself.
+fn tree_paths(project: &Path, block: &[(usize, &str)]) -> Vec<(usize, String)> {
+    let mut paths = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut base = String::new();
+    for &(line_no, line) in block {
+        let Some(pos) = line.find(['├', '└']) else {
+            let root = line.trim().trim_end_matches('/');
+            if paths.is_empty() && !root.is_empty() && project.join(root).is_dir() {
+                base = format!("{}/", root);
+            }
+            continue;
+        };
+        let depth = line[..pos].chars().count() / 4;
+        let name = line[pos..]
+            .trim_start_matches(['├', '└', '─', ' ', '\u{a0}'])
+            .split(['#', ' ', '\t'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        // Placeholders like `...` or `*.rs`
+        if name.is_empty() || name.contains('*') || name.starts_with("..") || name == "…" {
+            continue;
+        }
+        stack.truncate(depth);
+        stack.push(name.to_string());
+        paths.push((line_no, format!("{}{}", base, stack.join("/"))));
+    }
+    paths
+}
+
+/// Whether a heading introduces a list of dependencies
+fn is_dependency_heading(heading: &str) -> bool {
+    let heading = heading.to_lowercase();
+    ["depend", "stack", "librar", "framework", "tech"]
+        .iter()
+        .any(|word| heading.contains(word))
+}
+
+/// Check one snippet of code, returning its kind and problem if it is a claim
+fn check_claim(
+    project: &Path,
+    facts: &RepoFacts,
+    heading: &str,
+    snippet: &str,
+    in_code: bool,
+) -> Option<(&'static str, Option<String>)> {
+    if let Some(result) = check_command(facts, snippet) {
+        return Some(("command", result));
+    }
+    if let Some(result) = check_path(project, snippet) {
+        return Some(("path", result));
+    }
+    // Package names only count as claims under a dependencies heading
+    let dependency = !in_code
+        && is_dependency_heading(heading)
+        && !facts.dependencies.is_empty()
+        && looks_like_package(snippet);
+    dependency.then(|| {
+        let found = facts.dependencies.contains(&snippet.to_lowercase());
+        (
+            "dependency",
+            (!found).then(|| format!("{} is not a dependency", snippet)),
+        )
+    })
+}
+
+fn check_section(project: &Path, facts: &RepoFacts, section: &Section) -> SectionFreshness {
+    let mut checked = 0;
+    let mut problems = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut record = |kind: &str, claim: &str, line: usize, result: Option<String>| {
+        if !seen.insert(claim.to_string()) {
+            return;
+        }
+        checked += 1;
+        if let Some(reason) = result {
+            problems.push(StaleClaim {
+                kind: kind.to_string(),
+                claim: claim.to_string(),
+                line,
+                reason,
+            });
+        }
+    };
+    let claim = |snippet: &str, in_code: bool| {
+        check_claim(project, facts, &section.heading, snippet.trim(), in_code)
+    };
+
+    // Lines of the code block being read
+    let mut block: Option<Vec<(usize, &str)>> = None;
+    for (offset, &line) in section.lines.iter().enumerate() {
+        let line_no = section.start + offset + 1;
+        if line.trim_start().starts_with("```") {
+            match block.take() {
+                // Every entry of a directory tree is a path, with or without a slash
+                Some(lines) if lines.iter().any(|(_, l)| l.contains(['├', '└'])) => {
+                    for (line_no, path) in tree_paths(project, &lines) {
+                        let missing = !project.join(&path).exists();
+                        let reason = missing.then(|| format!("{} doesn't exist", path));
+                        record("path", &path, line_no, reason);
+                    }
+                }
+                Some(lines) => {
+                    for (line_no, line) in lines {
+                        if let Some((kind, result)) = claim(line, true) {
+                            record(kind, line.trim(), line_no, result);
+                        }
+                    }
+                }
+                None => block = Some(Vec::new()),
+            }
+            continue;
+        }
+        match block.as_mut() {
+            Some(lines) => lines.push((line_no, line)),
+            None => {
+                for code in inline_code_pattern().captures_iter(line) {
+                    if let Some((kind, result)) = claim(&code[1], false) {
+                        record(kind, code[1].trim(), line_no, result);
+                    }
+                }
+            }
+        }
+    }
+
+    SectionFreshness {
+        heading: section.heading.clone(),
+        level: section.level,
+        start_line: section.start + 1,
+        end_line: section.start + section.lines.len(),
+        checked,
+        problems,
+    }
+}
+
+fn looks_like_package(token: &str) -> bool {
+    let name = token.strip_prefix('@').unwrap_or(token);
+    !name.is_empty()
+        && name.split('/').count() <= 2
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+}
+
+fn check_freshness(project: &Path, text: &str) -> Vec<SectionFreshness> {
+    let facts = collect_facts(project);
+    split_sections(text)
+        .iter()
+        .map(|section| check_section(project, &facts, section))
+        .collect()
+}
+
+/// Top two levels of the project layout, for the model
+fn layout(project: &Path) -> Vec<String> {
+    walkdir::WalkDir::new(project)
+        .min_depth(1)
+        .max_depth(2)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_ref())
+        })
+        .flatten()
+        .filter_map(|e| {
+            let relative = e
+                .path()
+                .strip_prefix(project)
+                .ok()?
+                .to_string_lossy()
+                .to_string();
+            Some(if e.file_type().is_dir() {
+                format!("{}/", relative)
+            } else {
+                relative
+            })
+        })
+        .take(300)
+        .collect()
+}
+
+/// Check the commands, paths and dependencies CLAUDE.md mentions against the repository
+///
+/// Commands (`npm run x`, `make x`, `just x`) must exist as scripts, targets
+/// or recipes; paths in inline code and directory trees must exist; inline
+/// code under a dependencies or tech stack heading must be a dependency in a
+/// manifest. Sections with any mismatch are stale.
+#[tauri::command]
+pub async fn check_claude_md_freshness(project_path: String) -> Result<ClaudeMdFreshness, String> {
+    let project = validate_project_root(&project_path)?;
+    let path = project.join("CLAUDE.md");
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let sections = check_freshness(&project, &text);
+    Ok(ClaudeMdFreshness {
+        path: path.to_string_lossy().to_string(),
+        stale_sections: sections.iter().filter(|s| !s.problems.is_empty()).count(),
+        sections,
+    })
+}
+
+/// Rewrite a stale CLAUDE.md section with the Anthropic API
+///
+/// The model gets the section, what is wrong with it and the repository's
+/// scripts, layout and dependencies. With `apply` the section is replaced in
+/// CLAUDE.md; otherwise the replacement is only returned for review.
+#[tauri::command]
+pub async fn regenerate_claude_md_section(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    heading: String,
+    apply: bool,
+    model: Option<String>,
+) -> Result<RegeneratedSection, String> {
+    let project = validate_project_root(&project_path)?;
+    let path = project.join("CLAUDE.md");
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let key = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        configured_api_key(&conn).ok_or("No Anthropic API key is configured")?
+    };
+
+    let sections = split_sections(&text);
+    let section = sections
+        .iter()
+        .find(|s| s.heading == heading.trim())
+        .ok_or_else(|| format!("CLAUDE.md has no section '{}'", heading))?;
+    let original = section.lines.join("\n");
+    let facts = collect_facts(&project);
+    let problems = check_section(&project, &facts, section).problems;
+
+    let problem_list = if problems.is_empty() {
+        "None found automatically; check it against the repository anyway.".to_string()
+    } else {
+        problems
+            .iter()
+            .map(|p| format!("- {}: {}", p.claim, p.reason))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let list = |items: &BTreeSet<String>| {
+        if items.is_empty() {
+            "(none)".to_string()
+        } else {
+            items.iter().cloned().collect::<Vec<_>>().join(", ")
+        }
+    };
+    let prompt = format!(
+        "This section of the project's CLAUDE.md is out of date:\n\n<section>\n{}\n</section>\n\n\
+         Problems found:\n{}\n\n\
+         Repository facts:\n- package.json scripts: {}\n- Makefile targets: {}\n- just recipes: {}\n\
+         - Dependencies: {}\n- Layout:\n{}\n\n\
+         Rewrite the section so every command, path and dependency it mentions matches the \
+         repository. Keep its heading, tone, formatting and roughly its length, and keep \
+         everything that is still accurate. Reply with only the rewritten section in markdown, \
+         starting with the heading line.",
+        original,
+        problem_list,
+        list(&facts.scripts),
+        list(&facts.make_targets),
+        list(&facts.just_recipes),
+        list(&facts.dependencies),
+        layout(&project)
+            .iter()
+            .map(|p| format!("  {}", p))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    let client = crate::http_client::client()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(&key).map_err(|e| e.to_string())?,
+    );
+    headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let body = json!({
+        "model": model.as_deref().unwrap_or(DEFAULT_REGENERATE_MODEL),
+        "max_tokens": REGENERATE_MAX_TOKENS,
+        "system": "You keep CLAUDE.md files, the instructions Claude Code reads at the start of every session, accurate and concise.",
+        "messages": [{ "role": "user", "content": prompt }],
+    });
+    let response = send_with_retry(|| {
+        client
+            .post("https://api.anthropic.com/v1/messages")
+            .headers(headers.clone())
+            .json(&body)
+    })
+    .await
+    .map_err(|e| e.context("Failed to regenerate the section"))?;
+    let reply: JsonValue = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid API response: {}", e))?;
+    let replacement = reply["content"]
+        .as_array()
+        .and_then(|blocks| blocks.iter().find_map(|b| b["text"].as_str()))
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or("The API returned no text")?;
+
+    if apply {
+        let mut lines: Vec<&str> = text.lines().collect();
+        let end = section.start + section.lines.len();
+        let mut new_lines: Vec<&str> = replacement.lines().collect();
+        // Keep a blank line before the next section
+        if end < lines.len() {
+            new_lines.push("");
+        }
+        lines.splice(section.start..end, new_lines);
+        std::fs::write(&path, format!("{}\n", lines.join("\n")))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        file_audit::record("regenerate_claude_md_section", FileOperation::Modify, &path);
+    }
+
+    Ok(RegeneratedSection {
+        heading: section.heading.clone(),
+        original,
+        replacement,
+        applied: apply,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_freshness_flags_stale_sections() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        std::fs::create_dir_all(root.join("src/lib")).unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"scripts":{"build":"vite build"},"dependencies":{"react":"^18"}}"#,
+        )
+        .unwrap();
+
+        let claude_md = "# Project\n\n\
+            ## Commands\n\n```bash\nnpm run build\nnpm run lint\nnpm install\n```\n\n\
+            ## Layout\n\n```\nsrc/\n├── lib/\n└── components/\n```\n\n\
+            See `src/lib/api.ts` and `src/lib`.\n\n\
+            ## Tech stack\n\n- `react`\n- `zustand`\n";
+        let sections = check_freshness(root, claude_md);
+        let headings: Vec<&str> = sections.iter().map(|s| s.heading.as_str()).collect();
+        assert_eq!(headings, ["Project", "Commands", "Layout", "Tech stack"]);
+
+        let claims = |i: usize| -> Vec<&str> {
+            sections[i]
+                .problems
+                .iter()
+                .map(|p| p.claim.as_str())
+                .collect()
+        };
+        assert!(sections[0].problems.is_empty());
+        assert_eq!(claims(1), ["npm run lint"]);
+        assert_eq!(sections[1].checked, 2);
+        assert_eq!(claims(2), ["src/components", "src/lib/api.ts"]);
+        assert_eq!(claims(3), ["zustand"]);
+        assert_eq!(sections[3].start_line, sections[2].end_line + 1);
+    }
+}
@@ -0,0 +1,24 @@
+use tauri::State;
+
+use crate::claude_watcher::ClaudeWatcher;
+use crate::path_validation::validate_project_root;
+
+/// Send live events for changes in a project's `.claude` directory while it is open
+#[tauri::command]
+pub async fn watch_project_claude_dir(
+    watcher: State<'_, ClaudeWatcher>,
+    project_path: String,
+) -> Result<(), String> {
+    let project = validate_project_root(&project_path)?;
+    watcher.watch_project(&project)
+}
+
+/// Stop the events for a project that was closed
+#[tauri::command]
+pub async fn unwatch_project_claude_dir(
+    watcher: State<'_, ClaudeWatcher>,
+    project_path: String,
+) -> Result<(), String> {
+    let project = validate_project_root(&project_path)?;
+    watcher.unwatch_project(&project)
+}
@@ -0,0 +1,535 @@
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+use super::agents::AgentDb;
+use super::settings as store;
+use crate::claude_binary::{find_claude_binary, get_claude_version};
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::validate_project_root;
+use crate::utils::read_json_file;
+
+/// app_settings key holding the CLI version the user last migrated to
+const ACKNOWLEDGED_VERSION_KEY: &str = "claude_cli_acknowledged_version";
+
+/// Which kind of file a rule checks
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigKind {
+    /// settings.json and settings.local.json
+    Settings,
+    /// .mcp.json and ~/.claude.json
+    Mcp,
+}
+
+/// A change in a CLI release that breaks configuration written for older ones
+struct BreakingChange {
+    id: &'static str,
+    title: &'static str,
+    /// First major version with the change
+    introduced_in: u32,
+    kind: ConfigKind,
+    /// What in the file is affected, one entry per problem
+    check: fn(&JsonValue) -> Vec<String>,
+    /// Rewrite the file; only for changes that can be fixed without guessing
+    fix: Option<fn(&mut JsonValue)>,
+    /// What to do by hand
+    instructions: &'static str,
+}
+
+const BREAKING_CHANGES: &[BreakingChange] = &[
+    BreakingChange {
+        id: "hooks-matcher-groups",
+        title: "Hooks are grouped by matcher",
+        introduced_in: 1,
+        kind: ConfigKind::Settings,
+        check: flat_hooks,
+        fix: Some(group_flat_hooks),
+        instructions: "Move each hook's `command` into a `hooks` list under its matcher: \
+                       `{\"matcher\": \"Bash\", \"hooks\": [{\"type\": \"command\", \"command\": \"...\"}]}`",
+    },
+    BreakingChange {
+        id: "allowed-tools-permissions",
+        title: "`allowedTools` moved to `permissions.allow`",
+        introduced_in: 1,
+        kind: ConfigKind::Settings,
+        check: top_level_allowed_tools,
+        fix: Some(move_allowed_tools),
+        instructions: "Move the entries of `allowedTools` into `permissions.allow`",
+    },
+    BreakingChange {
+        id: "ignore-patterns-permissions",
+        title: "`ignorePatterns` replaced by `Read` deny rules",
+        introduced_in: 1,
+        kind: ConfigKind::Settings,
+        check: ignore_patterns,
+        fix: Some(move_ignore_patterns),
+        instructions: "Replace each pattern with a `Read(<pattern>)` rule in `permissions.deny`",
+    },
+    BreakingChange {
+        id: "mcp-sse-transport",
+        title: "The SSE transport for MCP servers is deprecated",
+        introduced_in: 2,
+        kind: ConfigKind::Mcp,
+        check: sse_servers,
+        fix: None,
+        instructions: "Switch the server to `\"type\": \"http\"` if it supports streamable HTTP, \
+                       or ask its maintainer to",
+    },
+    BreakingChange {
+        id: "mcp-remote-type",
+        title: "Remote MCP servers need a transport type",
+        introduced_in: 2,
+        kind: ConfigKind::Mcp,
+        check: untyped_remote_servers,
+        fix: None,
+        instructions: "Add `\"type\": \"http\"` (or `\"sse\"` for older servers) next to the server's `url`",
+    },
+];
+
+/// One thing to migrate in one file
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationItem {
+    /// ID of the breaking change, passed to `apply_cli_migration_fix`
+    pub id: String,
+    pub title: String,
+    pub file: String,
+    pub problems: Vec<String>,
+    /// Major version that introduced the change
+    pub introduced_in: u32,
+    /// `apply_cli_migration_fix` can fix it
+    pub fixable: bool,
+    pub instructions: String,
+}
+
+/// Compatibility of the configuration with the installed CLI
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    /// Version the user last migrated to, if any
+    pub previous_version: Option<String>,
+    /// Installed version; `None` if it can't be detected
+    pub current_version: Option<String>,
+    /// The major version went up since the last migration
+    pub major_upgrade: bool,
+    pub items: Vec<MigrationItem>,
+}
+
+/// Sent as `claude-cli-upgraded` when a new major CLI version is detected
+#[derive(Debug, Clone, Serialize)]
+pub struct CliUpgraded {
+    pub previous_version: String,
+    pub current_version: String,
+    /// Items in the user-level migration checklist
+    pub items: usize,
+}
+
+fn major(version: &str) -> Option<u32> {
+    version.split('.').next()?.trim().parse().ok()
+}
+
+fn installed_version(app: &AppHandle) -> Option<String> {
+    let binary = find_claude_binary(app).ok()?;
+    get_claude_version(&binary).ok().flatten()
+}
+
+/// Settings and MCP files a session in `project` reads
+fn config_files(project: Option<&Path>) -> Vec<(PathBuf, ConfigKind)> {
+    let mut files = Vec::new();
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        files.push((claude_dir.join("settings.json"), ConfigKind::Settings));
+    }
+    if let Some(home) = dirs::home_dir() {
+        files.push((home.join(".claude.json"), ConfigKind::Mcp));
+    }
+    if let Some(project) = project {
+        let claude = project.join(".claude");
+        files.push((claude.join("settings.json"), ConfigKind::Settings));
+        files.push((claude.join("settings.local.json"), ConfigKind::Settings));
+        files.push((project.join(".mcp.json"), ConfigKind::Mcp));
+    }
+    files.into_iter().filter(|(p, _)| p.is_file()).collect()
+}
+
+/// Breaking changes between two major versions; all known ones up to `to`
+/// when the previous version is unknown
+fn changes_between(
+    from: Option<u32>,
+    to: Option<u32>,
+) -> impl Iterator<Item = &'static BreakingChange> {
+    BREAKING_CHANGES.iter().filter(move |change| {
+        from.map_or(true, |from| change.introduced_in > from)
+            && to.map_or(true, |to| change.introduced_in <= to)
+    })
+}
+
+/// Check the configuration files against the breaking changes between two versions
+fn scan(project: Option<&Path>, from: Option<u32>, to: Option<u32>) -> Vec<MigrationItem> {
+    let mut items = Vec::new();
+    for (path, kind) in config_files(project) {
+        // Files that don't parse are the doctor's and the editor's concern
+        let Ok(config) = read_json_file(&path) else {
+            continue;
+        };
+        for change in changes_between(from, to).filter(|c| c.kind == kind) {
+            let problems = (change.check)(&config);
+            if problems.is_empty() {
+                continue;
+            }
+            items.push(MigrationItem {
+                id: change.id.to_string(),
+                title: change.title.to_string(),
+                file: path.to_string_lossy().to_string(),
+                problems,
+                introduced_in: change.introduced_in,
+                fixable: change.fix.is_some(),
+                instructions: change.instructions.to_string(),
+            });
+        }
+    }
+    items
+}
+
+fn flat_hooks(settings: &JsonValue) -> Vec<String> {
+    let Some(events) = settings["hooks"].as_object() else {
+        return Vec::new();
+    };
+    events
+        .iter()
+        .flat_map(|(event, entries)| {
+            entries
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.get("command").is_some() && entry.get("hooks").is_none())
+                .map(move |entry| {
+                    format!(
+                        "{} hook `{}` is not in a matcher group",
+                        event,
+                        entry["command"].as_str().unwrap_or_default()
+                    )
+                })
+        })
+        .collect()
+}
+
+fn group_flat_hooks(settings: &mut JsonValue) {
+    let Some(events) = settings["hooks"].as_object_mut() else {
+        return;
+    };
+    for entries in events.values_mut().filter_map(|e| e.as_array_mut()) {
+        for entry in entries.iter_mut() {
+            let Some(object) = entry.as_object_mut() else {
+                continue;
+            };
+            if object.contains_key("hooks") {
+                continue;
+            }
+            let Some(command) = object.remove("command") else {
+                continue;
+            };
+            let mut hook = json!({ "type": "command", "command": command });
+            if let Some(timeout) = object.remove("timeout") {
+                hook["timeout"] = timeout;
+            }
+            object.remove("type");
+            object
+                .entry("matcher")
+                .or_insert_with(|| JsonValue::String(String::new()));
+            object.insert("hooks".to_string(), json!([hook]));
+        }
+    }
+}
+
+fn top_level_allowed_tools(settings: &JsonValue) -> Vec<String> {
+    match settings["allowedTools"].as_array() {
+        Some(tools) => vec![format!("{} tools in `allowedTools`", tools.len())],
+        None => Vec::new(),
+    }
+}
+
+/// Append `rules` to `permissions.<list>`, skipping rules already there
+fn add_permission_rules(settings: &mut JsonValue, list: &str, rules: Vec<JsonValue>) {
+    if !settings["permissions"].is_object() {
+        settings["permissions"] = json!({});
+    }
+    if !settings["permissions"][list].is_array() {
+        settings["permissions"][list] = json!([]);
+    }
+    if let Some(existing) = settings["permissions"][list].as_array_mut() {
+        for rule in rules {
+            if !existing.contains(&rule) {
+                existing.push(rule);
+            }
+        }
+    }
+}
+
+fn move_allowed_tools(settings: &mut JsonValue) {
+    let Some(tools) = settings
+        .as_object_mut()
+        .and_then(|s| s.remove("allowedTools"))
+    else {
+        return;
+    };
+    let tools = tools.as_array().cloned().unwrap_or_default();
+    add_permission_rules(settings, "allow", tools);
+}
+
+fn ignore_patterns(settings: &JsonValue) -> Vec<String> {
+    match settings["ignorePatterns"].as_array() {
+        Some(patterns) => vec![format!("{} patterns in `ignorePatterns`", patterns.len())],
+        None => Vec::new(),
+    }
+}
+
+fn move_ignore_patterns(settings: &mut JsonValue) {
+    let Some(patterns) = settings
+        .as_object_mut()
+        .and_then(|s| s.remove("ignorePatterns"))
+    else {
+        return;
+    };
+    let rules = patterns
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p.as_str())
+        .map(|p| JsonValue::String(format!("Read({})", p)))
+        .collect();
+    add_permission_rules(settings, "deny", rules);
+}
+
+/// MCP server configs in an `.mcp.json` or `~/.claude.json`, with where they are
+fn mcp_servers(config: &JsonValue) -> Vec<(String, &JsonValue)> {
+    let mut servers: Vec<(String, &JsonValue)> = config["mcpServers"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, server)| (name.clone(), server))
+        .collect();
+    // ~/.claude.json also has servers per project
+    if let Some(projects) = config["projects"].as_object() {
+        for (project, entry) in projects {
+            for (name, server) in entry["mcpServers"].as_object().into_iter().flatten() {
+                servers.push((format!("{} ({})", name, project), server));
+            }
+        }
+    }
+    servers
+}
+
+fn sse_servers(config: &JsonValue) -> Vec<String> {
+    mcp_servers(config)
+        .into_iter()
+        .filter(|(_, server)| server["type"] == "sse")
+        .map(|(name, _)| format!("Server '{}' uses SSE", name))
+        .collect()
+}
+
+fn untyped_remote_servers(config: &JsonValue) -> Vec<String> {
+    mcp_servers(config)
+        .into_iter()
+        .filter(|(_, server)| server.get("url").is_some() && server.get("type").is_none())
+        .map(|(name, _)| format!("Server '{}' has a URL but no type", name))
+        .collect()
+}
+
+fn report(
+    conn: &rusqlite::Connection,
+    current_version: Option<String>,
+    project: Option<&Path>,
+) -> MigrationReport {
+    let previous_version = store::get_text(conn, ACKNOWLEDGED_VERSION_KEY);
+    let (from, to) = (
+        previous_version.as_deref().and_then(major),
+        current_version.as_deref().and_then(major),
+    );
+    MigrationReport {
+        major_upgrade: matches!((from, to), (Some(from), Some(to)) if to > from),
+        items: scan(project, from, to),
+        previous_version,
+        current_version,
+    }
+}
+
+/// Check settings, hooks and MCP configs against the breaking changes since
+/// the CLI version the user last migrated to
+///
+/// Without a project only the user-level files are checked.
+#[tauri::command]
+pub async fn scan_cli_migration(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<MigrationReport, String> {
+    let project = project_path
+        .map(|p| validate_project_root(&p))
+        .transpose()?;
+    let current_version = tauri::async_runtime::spawn_blocking({
+        let app = app.clone();
+        move || installed_version(&app)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(report(&conn, current_version, project.as_deref()))
+}
+
+/// Apply the automated fix for a breaking change to one file
+///
+/// The file must be one the scan checks. The original is kept next to it with
+/// a `.bak` extension.
+#[tauri::command]
+pub async fn apply_cli_migration_fix(
+    project_path: Option<String>,
+    id: String,
+    file: String,
+) -> Result<MigrationItem, String> {
+    let project = project_path
+        .map(|p| validate_project_root(&p))
+        .transpose()?;
+    let change = BREAKING_CHANGES
+        .iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Unknown breaking change: {}", id))?;
+    let fix = change.fix.ok_or_else(|| {
+        format!(
+            "'{}' has to be fixed by hand: {}",
+            change.title, change.instructions
+        )
+    })?;
+    let (path, _) = config_files(project.as_deref())
+        .into_iter()
+        .find(|(path, kind)| *kind == change.kind && path.to_string_lossy() == file)
+        .ok_or_else(|| format!("{} is not a configuration file '{}' applies to", file, id))?;
+
+    let mut config = read_json_file(&path)?;
+    let problems = (change.check)(&config);
+    if problems.is_empty() {
+        return Err(format!("{} needs no migration for '{}'", file, id));
+    }
+    fix(&mut config);
+
+    let backup = PathBuf::from(format!("{}.bak", file));
+    let backup_operation = FileOperation::for_write(&backup);
+    fs::copy(&path, &backup).map_err(|e| format!("Failed to back up {}: {}", file, e))?;
+    file_audit::record("apply_cli_migration_fix", backup_operation, &backup);
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize {}: {}", file, e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", file, e))?;
+    file_audit::record("apply_cli_migration_fix", FileOperation::Modify, &path);
+    log::info!("Applied CLI migration {} to {}", id, file);
+
+    Ok(MigrationItem {
+        id: change.id.to_string(),
+        title: change.title.to_string(),
+        file,
+        problems,
+        introduced_in: change.introduced_in,
+        fixable: true,
+        instructions: change.instructions.to_string(),
+    })
+}
+
+/// Record that the configuration was migrated to the installed CLI version
+#[tauri::command]
+pub async fn acknowledge_cli_migration(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+) -> Result<Option<String>, String> {
+    let version = tauri::async_runtime::spawn_blocking(move || installed_version(&app))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(version) = &version {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        store::set_value(&conn, ACKNOWLEDGED_VERSION_KEY, &version.as_str().into())?;
+    }
+    Ok(version)
+}
+
+/// Compare the installed CLI with the one last migrated to, in the background
+///
+/// The first version seen is recorded as migrated. Minor upgrades are recorded
+/// silently; a major upgrade publishes `claude-cli-upgraded` and is recorded
+/// once the user acknowledges the checklist.
+pub fn spawn_cli_version_check(app: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(current) = installed_version(&app) else {
+            return;
+        };
+        let db = app.state::<AgentDb>();
+        let Ok(conn) = db.0.lock() else {
+            return;
+        };
+        let previous = store::get_text(&conn, ACKNOWLEDGED_VERSION_KEY);
+        let (from, to) = (previous.as_deref().and_then(major), major(&current));
+        match (previous, from, to) {
+            (Some(previous), Some(from), Some(to)) if to > from => {
+                drop(conn);
+                let items = scan(None, Some(from), Some(to)).len();
+                log::info!("Claude CLI upgraded from {} to {}", previous, current);
+                crate::event_bus::publish(
+                    &app,
+                    "claude-cli-upgraded",
+                    &CliUpgraded {
+                        previous_version: previous,
+                        current_version: current,
+                        items,
+                    },
+                );
+            }
+            (previous, _, _) if previous.as_deref() != Some(current.as_str()) => {
+                if let Err(e) = store::set_value(&conn, ACKNOWLEDGED_VERSION_KEY, &current.into()) {
+                    log::warn!("Failed to record the Claude CLI version: {}", e);
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_fixes() {
+        let mut settings = json!({
+            "allowedTools": ["Bash(npm test)"],
+            "ignorePatterns": ["secrets/**"],
+            "permissions": { "allow": ["Read"] },
+            "hooks": {
+                "PostToolUse": [
+                    { "matcher": "Edit", "command": "cargo fmt", "timeout": 30 },
+                    { "matcher": "Bash", "hooks": [{ "type": "command", "command": "true" }] }
+                ]
+            }
+        });
+        assert_eq!(flat_hooks(&settings).len(), 1);
+
+        group_flat_hooks(&mut settings);
+        move_allowed_tools(&mut settings);
+        move_ignore_patterns(&mut settings);
+        assert_eq!(
+            settings,
+            json!({
+                "permissions": {
+                    "allow": ["Read", "Bash(npm test)"],
+                    "deny": ["Read(secrets/**)"]
+                },
+                "hooks": {
+                    "PostToolUse": [
+                        {
+                            "matcher": "Edit",
+                            "hooks": [{ "type": "command", "command": "cargo fmt", "timeout": 30 }]
+                        },
+                        { "matcher": "Bash", "hooks": [{ "type": "command", "command": "true" }] }
+                    ]
+                }
+            })
+        );
+        assert!(flat_hooks(&settings).is_empty());
+        assert_eq!(changes_between(Some(1), Some(2)).count(), 2);
+    }
+}
@@ -0,0 +1,509 @@
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::skill_usage::installed_skills;
+use super::skills::{frontmatter, skill_dependencies};
+use super::tokens::configured_mcp_servers;
+use crate::path_validation::validate_project_root;
+use crate::utils::read_json_file;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigNodeKind {
+    Agent,
+    Skill,
+    McpServer,
+    Hook,
+    Permission,
+}
+
+/// A piece of a project's Claude configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigNode {
+    pub id: String,
+    pub kind: ConfigNodeKind,
+    pub name: String,
+    /// "project", "local" or "user"
+    pub scope: String,
+    /// File the node is defined in; empty for missing nodes
+    pub source: String,
+    /// Hook commands, or whether a permission rule allows, asks or denies
+    pub detail: Option<String>,
+    /// Referenced by another node but not configured anywhere
+    pub missing: bool,
+}
+
+/// `from` relies on `to`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEdge {
+    pub from: String,
+    pub to: String,
+    /// "uses" (a skill or MCP server), "triggers" (a hook runs on its tools)
+    /// or "governed_by" (a permission rule covers its tools)
+    pub relation: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigGraph {
+    pub nodes: Vec<ConfigNode>,
+    pub edges: Vec<ConfigEdge>,
+}
+
+/// An agent or skill and what it declares it needs
+struct Consumer {
+    id: String,
+    tools: Vec<String>,
+    skills: Vec<String>,
+}
+
+struct HookGroup {
+    id: String,
+    matcher: Option<Regex>,
+    matcher_text: String,
+}
+
+struct PermissionRule {
+    id: String,
+    tool: String,
+}
+
+impl ConfigGraph {
+    fn add_node(
+        &mut self,
+        id: String,
+        kind: ConfigNodeKind,
+        name: &str,
+        scope: &str,
+        source: &Path,
+        detail: Option<String>,
+    ) {
+        self.nodes.push(ConfigNode {
+            id,
+            kind,
+            name: name.to_string(),
+            scope: scope.to_string(),
+            source: source.to_string_lossy().to_string(),
+            detail,
+            missing: false,
+        });
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, relation: &str) {
+        let exists = self
+            .edges
+            .iter()
+            .any(|e| e.from == from && e.to == to && e.relation == relation);
+        if !exists {
+            self.edges.push(ConfigEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                relation: relation.to_string(),
+            });
+        }
+    }
+
+    /// Id of a skill or MCP server, adding a missing node when it isn't configured
+    fn resolve(&mut self, kind: ConfigNodeKind, name: &str) -> String {
+        let existing = self
+            .nodes
+            .iter()
+            .find(|n| n.kind == kind && n.name == name)
+            .map(|n| n.id.clone());
+        existing.unwrap_or_else(|| {
+            let id = format!("{}:missing:{}", kind_prefix(kind), name);
+            self.nodes.push(ConfigNode {
+                id: id.clone(),
+                kind,
+                name: name.to_string(),
+                scope: String::new(),
+                source: String::new(),
+                detail: None,
+                missing: true,
+            });
+            id
+        })
+    }
+}
+
+fn kind_prefix(kind: ConfigNodeKind) -> &'static str {
+    match kind {
+        ConfigNodeKind::Agent => "agent",
+        ConfigNodeKind::Skill => "skill",
+        ConfigNodeKind::McpServer => "mcp",
+        ConfigNodeKind::Hook => "hook",
+        ConfigNodeKind::Permission => "permission",
+    }
+}
+
+/// Strings of a frontmatter field written as a list or a comma-separated string
+fn string_list(value: Option<&YamlValue>) -> Vec<String> {
+    match value {
+        Some(YamlValue::String(s)) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Some(YamlValue::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|s| s.trim().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn read_frontmatter(path: &Path) -> Option<(String, YamlValue)> {
+    let content = fs::read_to_string(path).ok()?;
+    let yaml = frontmatter(&content)
+        .and_then(|front| serde_yaml::from_str(front).ok())
+        .unwrap_or(YamlValue::Null);
+    Some((content, yaml))
+}
+
+/// Server an `mcp__<server>__<tool>` tool name or permission rule refers to
+fn mcp_server_of(tool: &str) -> Option<&str> {
+    let rest = tool.strip_prefix("mcp__")?;
+    let server = rest.split("__").next().unwrap_or(rest);
+    (!server.is_empty()).then_some(server)
+}
+
+/// Whether text such as a hook matcher names one of a server's tools
+fn references_server(text: &str, server: &str) -> bool {
+    let prefix = format!("mcp__{}", server);
+    text.match_indices(&prefix).any(|(i, _)| {
+        let rest = &text[i + prefix.len()..];
+        rest.starts_with("__")
+            || !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+/// Tool name a permission rule such as `Bash(npm run test:*)` applies to
+fn rule_tool(rule: &str) -> &str {
+    rule.split('(').next().unwrap_or(rule).trim()
+}
+
+fn agents(graph: &mut ConfigGraph, dirs: &[(PathBuf, &str)]) -> Vec<Consumer> {
+    let mut consumers = Vec::new();
+    for (dir, scope) in dirs {
+        let Ok(entries) = fs::read_dir(dir.join("agents")) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        files.sort();
+        for file in files {
+            let Some((_, yaml)) = read_frontmatter(&file) else {
+                continue;
+            };
+            let name = yaml["name"]
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| Some(file.file_stem()?.to_string_lossy().to_string()))
+                .unwrap_or_default();
+            let id = format!("agent:{}:{}", scope, name);
+            graph.add_node(id.clone(), ConfigNodeKind::Agent, &name, scope, &file, None);
+            consumers.push(Consumer {
+                id,
+                tools: string_list(yaml.get("tools")),
+                skills: string_list(yaml.get("skills")),
+            });
+        }
+    }
+    consumers
+}
+
+fn skills(graph: &mut ConfigGraph, dirs: &[(PathBuf, &str)]) -> Vec<Consumer> {
+    let mut consumers = Vec::new();
+    for (dir, scope) in dirs {
+        let mut found = installed_skills(&dir.join("skills"));
+        found.sort();
+        for (name, file, _) in found {
+            // A project skill hides a user skill of the same name
+            if graph
+                .nodes
+                .iter()
+                .any(|n| n.kind == ConfigNodeKind::Skill && n.name == name)
+            {
+                continue;
+            }
+            let Some((content, yaml)) = read_frontmatter(&file) else {
+                continue;
+            };
+            let id = format!("skill:{}:{}", scope, name);
+            graph.add_node(id.clone(), ConfigNodeKind::Skill, &name, scope, &file, None);
+            consumers.push(Consumer {
+                id,
+                tools: string_list(yaml.get("allowed-tools")),
+                skills: skill_dependencies(&content)
+                    .map(|d| d.skills)
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    consumers
+}
+
+fn hooks_and_permissions(
+    graph: &mut ConfigGraph,
+    settings_files: &[(PathBuf, &str)],
+) -> (Vec<HookGroup>, Vec<PermissionRule>) {
+    let mut hooks = Vec::new();
+    let mut rules = Vec::new();
+    for (file, scope) in settings_files {
+        let Some(settings) = read_json_file(file).ok() else {
+            continue;
+        };
+        for (event, groups) in settings["hooks"].as_object().into_iter().flatten() {
+            for (index, group) in groups.as_array().into_iter().flatten().enumerate() {
+                let matcher_text = group["matcher"].as_str().unwrap_or_default().to_string();
+                let commands: Vec<&str> = group["hooks"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .chain(std::iter::once(group))
+                    .filter_map(|hook| hook["command"].as_str())
+                    .collect();
+                let id = format!("hook:{}:{}:{}", scope, event, index);
+                let name = if matcher_text.is_empty() {
+                    event.clone()
+                } else {
+                    format!("{} {}", event, matcher_text)
+                };
+                graph.add_node(
+                    id.clone(),
+                    ConfigNodeKind::Hook,
+                    &name,
+                    scope,
+                    file,
+                    Some(commands.join("; ")).filter(|c| !c.is_empty()),
+                );
+                // Catch-all hooks apply to every tool, so they aren't linked to any
+                let matcher = (!matcher_text.is_empty() && matcher_text != "*")
+                    .then(|| Regex::new(&format!("^(?:{})$", matcher_text)).ok())
+                    .flatten();
+                hooks.push(HookGroup {
+                    id,
+                    matcher,
+                    matcher_text,
+                });
+            }
+        }
+        for list in ["allow", "ask", "deny"] {
+            for rule in settings["permissions"][list]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|r| r.as_str())
+            {
+                let id = format!("permission:{}:{}:{}", scope, list, rule);
+                graph.add_node(
+                    id.clone(),
+                    ConfigNodeKind::Permission,
+                    rule,
+                    scope,
+                    file,
+                    Some(list.to_string()),
+                );
+                rules.push(PermissionRule {
+                    id,
+                    tool: rule_tool(rule).to_string(),
+                });
+            }
+        }
+    }
+    (hooks, rules)
+}
+
+/// Build the graph of a project's agents, skills, MCP servers, hooks and
+/// permission rules, with both project and user configuration
+fn build_graph(project: &Path, claude_dir: Option<&Path>) -> ConfigGraph {
+    let mut graph = ConfigGraph::default();
+    let mut config_dirs = vec![(project.join(".claude"), "project")];
+    let mut settings_files = vec![
+        (project.join(".claude").join("settings.json"), "project"),
+        (project.join(".claude").join("settings.local.json"), "local"),
+    ];
+    if let Some(claude_dir) = claude_dir {
+        config_dirs.push((claude_dir.to_path_buf(), "user"));
+        settings_files.push((claude_dir.join("settings.json"), "user"));
+    }
+
+    let mut consumers = agents(&mut graph, &config_dirs);
+    consumers.extend(skills(&mut graph, &config_dirs));
+    let mut servers = Vec::new();
+    for (name, scope, _) in configured_mcp_servers(project) {
+        let id = format!("mcp:{}:{}", scope, name);
+        let source = match scope.as_str() {
+            "project" => project.join(".mcp.json"),
+            _ => dirs::home_dir().unwrap_or_default().join(".claude.json"),
+        };
+        graph.add_node(
+            id.clone(),
+            ConfigNodeKind::McpServer,
+            &name,
+            &scope,
+            &source,
+            None,
+        );
+        servers.push((id, name));
+    }
+    let (hooks, rules) = hooks_and_permissions(&mut graph, &settings_files);
+
+    for consumer in &consumers {
+        for skill in &consumer.skills {
+            let to = graph.resolve(ConfigNodeKind::Skill, skill);
+            graph.add_edge(&consumer.id, &to, "uses");
+        }
+        for tool in &consumer.tools {
+            // MCP tools reach hooks and rules through their server's node
+            if let Some(server) = mcp_server_of(tool) {
+                let to = graph.resolve(ConfigNodeKind::McpServer, server);
+                graph.add_edge(&consumer.id, &to, "uses");
+                continue;
+            }
+            let tool = rule_tool(tool);
+            for hook in &hooks {
+                if hook.matcher.as_ref().is_some_and(|m| m.is_match(tool)) {
+                    graph.add_edge(&consumer.id, &hook.id, "triggers");
+                }
+            }
+            for rule in rules.iter().filter(|r| r.tool == tool) {
+                graph.add_edge(&consumer.id, &rule.id, "governed_by");
+            }
+        }
+    }
+    // Servers referenced above may only exist as missing nodes
+    let all_servers: Vec<(String, String)> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.kind == ConfigNodeKind::McpServer)
+        .map(|n| (n.id.clone(), n.name.clone()))
+        .collect();
+    for (id, name) in &all_servers {
+        for hook in hooks
+            .iter()
+            .filter(|h| references_server(&h.matcher_text, name))
+        {
+            graph.add_edge(id, &hook.id, "triggers");
+        }
+        for rule in rules.iter().filter(|r| references_server(&r.tool, name)) {
+            graph.add_edge(id, &rule.id, "governed_by");
+        }
+    }
+    for hook in &hooks {
+        let Some(matcher) = &hook.matcher else {
+            continue;
+        };
+        for rule in rules
+            .iter()
+            .filter(|r| mcp_server_of(&r.tool).is_none() && matcher.is_match(&r.tool))
+        {
+            graph.add_edge(&hook.id, &rule.id, "governed_by");
+        }
+    }
+    graph
+}
+
+/// Get how a project's agents, skills, MCP servers, hooks and permission
+/// rules depend on each other
+///
+/// Agents link to the skills and MCP servers their frontmatter lists, and to
+/// the hooks and permission rules that apply to their tools. References to
+/// skills or servers that aren't configured show up as missing nodes.
+#[tauri::command]
+pub async fn get_config_graph(project_path: String) -> Result<ConfigGraph, String> {
+    let project = validate_project_root(&project_path)?;
+    let claude_dir = crate::utils::get_claude_dir().ok();
+    Ok(build_graph(&project, claude_dir.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_graph() {
+        let project = tempfile::tempdir().unwrap();
+        let claude = project.path().join(".claude");
+        fs::create_dir_all(claude.join("agents")).unwrap();
+        fs::create_dir_all(claude.join("skills").join("pdf")).unwrap();
+        fs::write(
+            claude.join("agents").join("reviewer.md"),
+            "---\nname: reviewer\ntools: Bash, mcp__github__create_issue\nskills: [pdf, missing-skill]\n---\nReview.",
+        )
+        .unwrap();
+        fs::write(
+            claude.join("skills").join("pdf").join("SKILL.md"),
+            "---\ndescription: PDFs\nallowed-tools: [Read]\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            claude.join("settings.json"),
+            r#"{
+                "hooks": {"PreToolUse": [
+                    {"matcher": "Bash", "hooks": [{"type": "command", "command": "lint"}]},
+                    {"matcher": "mcp__github__.*", "hooks": [{"type": "command", "command": "audit"}]}
+                ]},
+                "permissions": {"allow": ["Bash(npm test)", "mcp__github"], "deny": ["Read(.env)"]}
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            project.path().join(".mcp.json"),
+            r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+        )
+        .unwrap();
+
+        let graph = build_graph(project.path(), None);
+        let has = |from: &str, to: &str, relation: &str| {
+            graph
+                .edges
+                .iter()
+                .any(|e| e.from == from && e.to == to && e.relation == relation)
+        };
+        let agent = "agent:project:reviewer";
+        assert!(has(agent, "skill:project:pdf", "uses"));
+        assert!(has(agent, "skill:missing:missing-skill", "uses"));
+        assert!(has(agent, "hook:project:PreToolUse:0", "triggers"));
+        assert!(has(
+            agent,
+            "permission:project:allow:Bash(npm test)",
+            "governed_by"
+        ));
+        assert!(has(
+            "skill:project:pdf",
+            "permission:project:deny:Read(.env)",
+            "governed_by"
+        ));
+        assert!(has(
+            "hook:project:PreToolUse:0",
+            "permission:project:allow:Bash(npm test)",
+            "governed_by"
+        ));
+        // github resolves to the local/project/user scope it's configured in
+        let github = graph
+            .nodes
+            .iter()
+            .find(|n| n.kind == ConfigNodeKind::McpServer && n.name == "github")
+            .unwrap();
+        assert!(has(agent, &github.id, "uses"));
+        assert!(has(&github.id, "hook:project:PreToolUse:1", "triggers"));
+        assert!(has(
+            &github.id,
+            "permission:project:allow:mcp__github",
+            "governed_by"
+        ));
+        assert!(graph
+            .nodes
+            .iter()
+            .any(|n| n.missing && n.name == "missing-skill"));
+    }
+}
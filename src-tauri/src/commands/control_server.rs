@@ -0,0 +1,51 @@
+use serde_json::json;
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use super::settings::set_value;
+use crate::control_server::{self, ControlServerStatus};
+
+/// Whether the local automation API is on, where it listens and its token
+#[tauri::command]
+pub async fn get_control_server_status(app: AppHandle) -> Result<ControlServerStatus, String> {
+    control_server::status(&app)
+}
+
+/// Turn the local automation API on or off, optionally on another port
+///
+/// It only ever listens on 127.0.0.1; a token is generated the first time it
+/// is turned on.
+#[tauri::command]
+pub async fn set_control_server_enabled(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<ControlServerStatus, String> {
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        if let Some(port) = port {
+            set_value(&conn, "control_server_port", &json!(port))?;
+        }
+        set_value(&conn, "control_server_enabled", &json!(enabled))?;
+    }
+    control_server::configure(&app).await
+}
+
+/// Replace the token; requests with the old one are refused from now on
+#[tauri::command]
+pub async fn rotate_control_server_token(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+) -> Result<ControlServerStatus, String> {
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        set_value(
+            &conn,
+            "control_server_token",
+            &json!(control_server::generate_token()),
+        )?;
+    }
+    log::info!("Control server token rotated");
+    control_server::status(&app)
+}
@@ -0,0 +1,303 @@
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::mcp::mcp_add_json;
+use super::skills::{install_skill, SKILLS_REPO};
+use crate::path_validation::validate_name;
+
+/// URL scheme registered for the app
+pub const SCHEME: &str = "opcode";
+
+/// Longest link accepted, so a pasted blob can't flood the confirmation UI
+const MAX_LINK_LEN: usize = 4096;
+/// Most links kept waiting for confirmation; the oldest is dropped first
+const MAX_PENDING: usize = 20;
+const MAX_MCP_ARGS: usize = 64;
+const MCP_CONFIG_KEYS: &[&str] = &["type", "command", "args", "env", "url"];
+
+/// What a deep link asks the app to do
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeepLinkAction {
+    /// `opcode://install-skill?repo=anthropics/skills&name=pdf`
+    InstallSkill { repo: String, name: String },
+    /// `opcode://install-mcp?name=fetch&config={"command":"uvx","args":["mcp-server-fetch"]}`
+    InstallMcp { name: String, config: JsonValue },
+}
+
+/// A deep link waiting for the user to confirm or dismiss it
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingDeepLink {
+    pub id: String,
+    pub action: DeepLinkAction,
+    pub received_at: String,
+}
+
+/// Deep links received but not yet confirmed
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Vec<PendingDeepLink>>,
+}
+
+/// Emitted when a deep link can't be used
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkRejected {
+    pub link: String,
+    pub error: String,
+}
+
+fn has_control_chars(value: &str) -> bool {
+    value.chars().any(char::is_control)
+}
+
+/// Check an MCP server config from a link; only plain stdio and remote servers are accepted
+fn validate_mcp_config(config: &JsonValue) -> Result<(), String> {
+    let object = config
+        .as_object()
+        .ok_or("MCP config must be a JSON object")?;
+    if let Some(key) = object
+        .keys()
+        .find(|k| !MCP_CONFIG_KEYS.contains(&k.as_str()))
+    {
+        return Err(format!("Unsupported MCP config field: {}", key));
+    }
+
+    let transport = match object.get("type") {
+        None => "stdio",
+        Some(JsonValue::String(t)) if matches!(t.as_str(), "stdio" | "sse" | "http") => t.as_str(),
+        Some(_) => return Err("MCP config type must be stdio, sse or http".to_string()),
+    };
+
+    if transport == "stdio" {
+        let command = object
+            .get("command")
+            .and_then(|c| c.as_str())
+            .ok_or("A stdio MCP server needs a command")?;
+        if command.trim().is_empty() || has_control_chars(command) {
+            return Err("Invalid MCP server command".to_string());
+        }
+        if object.contains_key("url") {
+            return Err("A stdio MCP server can't have a URL".to_string());
+        }
+    } else {
+        let url = object
+            .get("url")
+            .and_then(|u| u.as_str())
+            .ok_or("A remote MCP server needs a URL")?;
+        let parsed = Url::parse(url).map_err(|e| format!("Invalid MCP server URL: {}", e))?;
+        let local = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+        if !(parsed.scheme() == "https" || (parsed.scheme() == "http" && local)) {
+            return Err("Remote MCP servers must use https".to_string());
+        }
+        if object.contains_key("command") {
+            return Err("A remote MCP server can't have a command".to_string());
+        }
+    }
+
+    if let Some(args) = object.get("args") {
+        let args = args.as_array().ok_or("MCP args must be a list")?;
+        if args.len() > MAX_MCP_ARGS {
+            return Err(format!("At most {} MCP args are allowed", MAX_MCP_ARGS));
+        }
+        if args
+            .iter()
+            .any(|a| a.as_str().map(has_control_chars).unwrap_or(true))
+        {
+            return Err("MCP args must be plain strings".to_string());
+        }
+    }
+    if let Some(env) = object.get("env") {
+        let env = env.as_object().ok_or("MCP env must be an object")?;
+        for (key, value) in env {
+            let valid_key =
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key || !value.as_str().is_some_and(|v| !has_control_chars(v)) {
+                return Err(format!("Invalid MCP env entry: {}", key));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse and validate an `opcode://` link
+pub fn parse_deep_link(link: &str) -> Result<DeepLinkAction, String> {
+    if link.len() > MAX_LINK_LEN {
+        return Err("Link is too long".to_string());
+    }
+    let url = Url::parse(link).map_err(|e| format!("Invalid link: {}", e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Not an {}:// link", SCHEME));
+    }
+    if !matches!(url.path(), "" | "/") || url.fragment().is_some() {
+        return Err("Unexpected path in link".to_string());
+    }
+
+    let mut params = HashMap::new();
+    for (key, value) in url.query_pairs() {
+        if params.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(format!("Parameter '{}' is given more than once", key));
+        }
+    }
+    let mut take = |key: &str| {
+        params
+            .remove(key)
+            .ok_or_else(|| format!("Missing parameter '{}'", key))
+    };
+
+    let action = match url.host_str() {
+        Some("install-skill") => {
+            let repo = take("repo")?;
+            if repo != SKILLS_REPO {
+                return Err(format!(
+                    "Skills can only be installed from {}, not {}",
+                    SKILLS_REPO, repo
+                ));
+            }
+            let name = take("name")?;
+            validate_name(&name, "skill name")?;
+            DeepLinkAction::InstallSkill { repo, name }
+        }
+        Some("install-mcp") => {
+            let name = take("name")?;
+            validate_name(&name, "MCP server name")?;
+            let config: JsonValue = serde_json::from_str(&take("config")?)
+                .map_err(|e| format!("Invalid MCP config: {}", e))?;
+            validate_mcp_config(&config)?;
+            DeepLinkAction::InstallMcp { name, config }
+        }
+        Some(other) => return Err(format!("Unknown link action: {}", other)),
+        None => return Err("Link has no action".to_string()),
+    };
+
+    if let Some(key) = params.keys().next() {
+        return Err(format!("Unknown parameter '{}'", key));
+    }
+    Ok(action)
+}
+
+/// Queue a received link for confirmation and tell the frontend about it
+///
+/// Nothing is installed until the user calls `confirm_deep_link`.
+pub fn handle_deep_link(app: &AppHandle, link: &str) {
+    match parse_deep_link(link) {
+        Ok(action) => {
+            let pending = PendingDeepLink {
+                id: uuid::Uuid::new_v4().to_string(),
+                action,
+                received_at: chrono::Utc::now().to_rfc3339(),
+            };
+            log::info!("Received deep link {:?}", pending.action);
+            if let Some(state) = app.try_state::<DeepLinkState>() {
+                if let Ok(mut queue) = state.pending.lock() {
+                    if queue.len() >= MAX_PENDING {
+                        queue.remove(0);
+                    }
+                    queue.push(pending.clone());
+                }
+            }
+            let _ = app.emit("deep-link-request", &pending);
+
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }
+        Err(error) => {
+            log::warn!("Rejected deep link: {}", error);
+            let link: String = link.chars().take(200).collect();
+            let _ = app.emit("deep-link-rejected", &DeepLinkRejected { link, error });
+        }
+    }
+}
+
+fn take_pending(state: &DeepLinkState, id: &str) -> Result<PendingDeepLink, String> {
+    let mut queue = state.pending.lock().map_err(|e| e.to_string())?;
+    let index = queue
+        .iter()
+        .position(|p| p.id == id)
+        .ok_or_else(|| format!("No pending link with id {}", id))?;
+    Ok(queue.remove(index))
+}
+
+/// List deep links waiting for confirmation
+#[tauri::command]
+pub async fn get_pending_deep_links(
+    state: State<'_, DeepLinkState>,
+) -> Result<Vec<PendingDeepLink>, String> {
+    let queue = state.pending.lock().map_err(|e| e.to_string())?;
+    Ok(queue.clone())
+}
+
+/// Carry out a pending deep link the user has confirmed
+///
+/// Skills are installed into `project_path`; MCP servers are added with
+/// `scope` ("local", "project" or "user", default "user").
+#[tauri::command]
+pub async fn confirm_deep_link(
+    app: AppHandle,
+    state: State<'_, DeepLinkState>,
+    id: String,
+    project_path: Option<String>,
+    scope: Option<String>,
+) -> Result<JsonValue, String> {
+    let pending = take_pending(&state, &id)?;
+    match pending.action {
+        DeepLinkAction::InstallSkill { name, .. } => {
+            let project_path = project_path.ok_or("Choose a project to install the skill into")?;
+            let installed = install_skill(app, Some(project_path), name, None).await?;
+            serde_json::to_value(installed).map_err(|e| e.to_string())
+        }
+        DeepLinkAction::InstallMcp { name, config } => {
+            let scope = scope.unwrap_or_else(|| "user".to_string());
+            if !matches!(scope.as_str(), "local" | "project" | "user") {
+                return Err(format!("Invalid scope: {}", scope));
+            }
+            let result = mcp_add_json(app, name, config.to_string(), scope).await?;
+            serde_json::to_value(result).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Drop a pending deep link without acting on it
+#[tauri::command]
+pub async fn dismiss_deep_link(state: State<'_, DeepLinkState>, id: String) -> Result<(), String> {
+    take_pending(&state, &id).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deep_link() {
+        assert_eq!(
+            parse_deep_link("opcode://install-skill?repo=anthropics/skills&name=pdf").unwrap(),
+            DeepLinkAction::InstallSkill {
+                repo: "anthropics/skills".to_string(),
+                name: "pdf".to_string(),
+            }
+        );
+        let mcp = parse_deep_link(
+            "opcode://install-mcp?name=fetch&config=%7B%22command%22%3A%22uvx%22%2C%22args%22%3A%5B%22mcp-server-fetch%22%5D%7D",
+        )
+        .unwrap();
+        assert!(matches!(mcp, DeepLinkAction::InstallMcp { ref name, .. } if name == "fetch"));
+
+        for bad in [
+            "https://install-skill?repo=anthropics/skills&name=pdf",
+            "opcode://install-skill?repo=evil/skills&name=pdf",
+            "opcode://install-skill?repo=anthropics/skills&name=../../etc",
+            "opcode://install-skill?repo=anthropics/skills&name=pdf&name=docx",
+            "opcode://install-skill?repo=anthropics/skills&name=pdf&extra=1",
+            "opcode://install-mcp?name=x&config=%7B%22url%22%3A%22http%3A%2F%2Fexample.com%22%2C%22type%22%3A%22sse%22%7D",
+            "opcode://install-mcp?name=x&config=%7B%22command%22%3A%22a%22%2C%22shell%22%3Atrue%7D",
+            "opcode://uninstall-everything",
+        ] {
+            assert!(parse_deep_link(bad).is_err(), "accepted {}", bad);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use super::models::configured_api_key;
+use crate::claude_binary::{find_claude_binary, get_claude_version};
+use crate::network::NetworkError;
+use crate::schema_drift::{self, DriftStatus};
+
+/// How long each network check may take
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Not applicable here, e.g. no API key to validate
+    Skipped,
+    /// Works, but something may break later
+    Warn,
+    Fail,
+}
+
+/// Result of one doctor check
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub id: String,
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(id: &str, name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Everything `run_doctor` checked
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// The worst status of any check
+    pub status: CheckStatus,
+    pub checks: Vec<DoctorCheck>,
+    pub checked_at: String,
+}
+
+fn check_claude_binary(app: &AppHandle) -> DoctorCheck {
+    let name = "Claude Code CLI";
+    match find_claude_binary(app) {
+        Ok(path) => match get_claude_version(&path).ok().flatten() {
+            Some(version) => DoctorCheck::new(
+                "claude_binary",
+                name,
+                CheckStatus::Pass,
+                format!("{} ({})", version, path),
+            ),
+            None => DoctorCheck::new(
+                "claude_binary",
+                name,
+                CheckStatus::Warn,
+                format!("Found {} but `--version` failed", path),
+            )
+            .fix("Reinstall Claude Code or pick another installation in Settings"),
+        },
+        Err(e) => DoctorCheck::new("claude_binary", name, CheckStatus::Fail, e)
+            .fix("Install Claude Code with `npm install -g @anthropic-ai/claude-code`"),
+    }
+}
+
+fn check_claude_dir() -> DoctorCheck {
+    let name = "Claude directory";
+    let path = match crate::utils::resolve_claude_dir() {
+        Ok((path, _)) => path,
+        Err(e) => return DoctorCheck::new("claude_dir", name, CheckStatus::Fail, e),
+    };
+    if !path.is_dir() {
+        return DoctorCheck::new(
+            "claude_dir",
+            name,
+            CheckStatus::Warn,
+            format!("{} does not exist yet", path.display()),
+        )
+        .fix("Run Claude Code once, or choose another directory in Settings");
+    }
+    match tempfile::NamedTempFile::new_in(&path) {
+        Ok(_) => DoctorCheck::new(
+            "claude_dir",
+            name,
+            CheckStatus::Pass,
+            format!("{} is writable", path.display()),
+        ),
+        Err(e) => DoctorCheck::new(
+            "claude_dir",
+            name,
+            CheckStatus::Fail,
+            format!("Can't write to {}: {}", path.display(), e),
+        )
+        .fix(format!("Check the permissions of {}", path.display())),
+    }
+}
+
+async fn check_reachable(client: &Client, id: &str, name: &str, url: &str) -> DoctorCheck {
+    // Any HTTP response means the host is reachable
+    match client.get(url).timeout(NETWORK_TIMEOUT).send().await {
+        Ok(response) => DoctorCheck::new(
+            id,
+            name,
+            CheckStatus::Pass,
+            format!("{} answered with HTTP {}", url, response.status().as_u16()),
+        ),
+        Err(e) => DoctorCheck::new(
+            id,
+            name,
+            CheckStatus::Fail,
+            NetworkError::from(e).message().to_string(),
+        )
+        .fix("Check your internet connection and the proxy settings"),
+    }
+}
+
+async fn check_api_key(client: &Client, key: Option<String>) -> DoctorCheck {
+    let name = "Anthropic API key";
+    let Some(key) = key else {
+        return DoctorCheck::new(
+            "api_key",
+            name,
+            CheckStatus::Skipped,
+            "No API key in the environment; Claude Code will use its own login",
+        );
+    };
+    let response = client
+        .get("https://api.anthropic.com/v1/models")
+        .header("x-api-key", key)
+        .header("anthropic-version", "2023-06-01")
+        .timeout(NETWORK_TIMEOUT)
+        .send()
+        .await;
+    match response {
+        Ok(r) if r.status().is_success() => {
+            DoctorCheck::new("api_key", name, CheckStatus::Pass, "The API key is valid")
+        }
+        Ok(r) if matches!(r.status().as_u16(), 401 | 403) => DoctorCheck::new(
+            "api_key",
+            name,
+            CheckStatus::Fail,
+            format!("The API key was rejected (HTTP {})", r.status().as_u16()),
+        )
+        .fix("Create a new key in the Anthropic Console and update the environment variable"),
+        Ok(r) => DoctorCheck::new(
+            "api_key",
+            name,
+            CheckStatus::Warn,
+            format!("Could not validate the key (HTTP {})", r.status().as_u16()),
+        ),
+        Err(e) => DoctorCheck::new(
+            "api_key",
+            name,
+            CheckStatus::Warn,
+            format!(
+                "Could not validate the key: {}",
+                NetworkError::from(e).message()
+            ),
+        ),
+    }
+}
+
+fn check_node() -> DoctorCheck {
+    let name = "Node.js and npx";
+    let (node, npx) = (which::which("node"), which::which("npx"));
+    match (node, npx) {
+        (Ok(node), Ok(_)) => {
+            let version = std::process::Command::new(&node)
+                .arg("--version")
+                .output()
+                .ok()
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .unwrap_or_default();
+            DoctorCheck::new(
+                "node",
+                name,
+                CheckStatus::Pass,
+                format!("node {} ({})", version, node.display()),
+            )
+        }
+        (node, _) => DoctorCheck::new(
+            "node",
+            name,
+            CheckStatus::Warn,
+            if node.is_ok() {
+                "`npx` was not found on PATH"
+            } else {
+                "`node` was not found on PATH"
+            },
+        )
+        .fix("Install Node.js 18 or newer; MCP servers started with npx need it"),
+    }
+}
+
+fn check_marketplace() -> DoctorCheck {
+    let name = "GitHub marketplace";
+    let reports = schema_drift::latest_reports();
+    let degraded: Vec<String> = reports
+        .iter()
+        .filter(|r| r.status == DriftStatus::Degraded)
+        .map(|r| r.source.clone())
+        .collect();
+    if reports.is_empty() {
+        DoctorCheck::new(
+            "marketplace",
+            name,
+            CheckStatus::Skipped,
+            "No catalog has been fetched yet",
+        )
+    } else if degraded.is_empty() {
+        DoctorCheck::new(
+            "marketplace",
+            name,
+            CheckStatus::Pass,
+            "GitHub responses match the expected format",
+        )
+    } else {
+        DoctorCheck::new(
+            "marketplace",
+            name,
+            CheckStatus::Warn,
+            format!(
+                "The marketplace may be degraded; unexpected responses for {}",
+                degraded.join(", ")
+            ),
+        )
+        .fix("Update opcode; GitHub may have changed its API")
+    }
+}
+
+/// The worst status among `checks`; skipped checks count as passing
+pub fn overall_status(checks: &[DoctorCheck]) -> CheckStatus {
+    checks
+        .iter()
+        .map(|c| c.status)
+        .filter(|s| *s != CheckStatus::Skipped)
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+/// Check everything opcode depends on and suggest fixes for what's broken
+#[tauri::command]
+pub async fn run_doctor(app: AppHandle, db: State<'_, AgentDb>) -> Result<DoctorReport, String> {
+    let api_key = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        configured_api_key(&conn)
+    };
+    let client = crate::http_client::client()?;
+
+    let app_handle = app.clone();
+    let local = tokio::task::spawn_blocking(move || {
+        vec![
+            check_claude_binary(&app_handle),
+            check_claude_dir(),
+            check_node(),
+            check_marketplace(),
+        ]
+    });
+    let (github, anthropic, key) = tokio::join!(
+        check_reachable(&client, "github", "GitHub", "https://api.github.com"),
+        check_reachable(
+            &client,
+            "anthropic",
+            "Anthropic API",
+            "https://api.anthropic.com"
+        ),
+        check_api_key(&client, api_key),
+    );
+
+    let mut checks = local.await.map_err(|e| e.to_string())?;
+    checks.extend([github, anthropic, key]);
+    for check in checks.iter().filter(|c| c.status >= CheckStatus::Warn) {
+        log::warn!("Doctor: {} - {}", check.name, check.detail);
+    }
+
+    Ok(DoctorReport {
+        status: overall_status(&checks),
+        checks,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overall_status_is_worst_check() {
+        let check = |status| DoctorCheck::new("x", "X", status, "");
+        assert_eq!(overall_status(&[]), CheckStatus::Pass);
+        assert_eq!(
+            overall_status(&[check(CheckStatus::Pass), check(CheckStatus::Skipped)]),
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            overall_status(&[check(CheckStatus::Fail), check(CheckStatus::Warn)]),
+            CheckStatus::Fail
+        );
+    }
+}
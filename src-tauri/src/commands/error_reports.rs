@@ -0,0 +1,57 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::error_reports::{self, ErrorReport};
+use crate::network::NetworkError;
+
+/// Most reports `list_recent_errors` returns
+const MAX_LIST: usize = 1000;
+
+/// Recorded panics and command errors, newest first
+///
+/// `kind` is "panic" or "command"; `limit` defaults to 100.
+#[tauri::command]
+pub async fn list_recent_errors(
+    db: State<'_, AgentDb>,
+    kind: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ErrorReport>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    error_reports::recent(
+        &conn,
+        kind.as_deref(),
+        limit.unwrap_or(100).clamp(1, MAX_LIST),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Record a command that failed, as seen by the frontend
+#[tauri::command]
+pub async fn record_command_error(
+    db: State<'_, AgentDb>,
+    command: String,
+    message: String,
+) -> Result<(), String> {
+    if command.trim().is_empty() || message.trim().is_empty() {
+        return Ok(());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    error_reports::record(&conn, "command", command.trim(), &message, None)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Remove every recorded error; returns how many there were
+#[tauri::command]
+pub async fn clear_error_reports(db: State<'_, AgentDb>) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    error_reports::clear(&conn).map_err(|e| e.to_string())
+}
+
+/// Send the errors not sent yet to the configured endpoint, anonymized
+///
+/// Fails unless error reporting is turned on in settings.
+#[tauri::command]
+pub async fn submit_error_reports(db: State<'_, AgentDb>) -> Result<usize, NetworkError> {
+    error_reports::submit_pending(&db).await
+}
@@ -0,0 +1,51 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::event_bus::{events_since, EventFilter, EventPage, EventSubscriptions};
+
+/// Largest page `get_events_since` returns
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Get persisted events published after `cursor`
+///
+/// Pass the returned cursor on the next call to continue. Without a cursor the
+/// oldest retained events are returned. `topic_prefix` limits the result to
+/// topics such as `agent-complete`.
+#[tauri::command]
+pub async fn get_events_since(
+    db: State<'_, AgentDb>,
+    cursor: Option<i64>,
+    topic_prefix: Option<String>,
+    limit: Option<u32>,
+) -> Result<EventPage, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    events_since(
+        &conn,
+        cursor.unwrap_or(0),
+        topic_prefix.as_deref().filter(|p| !p.is_empty()),
+        limit.unwrap_or(200).clamp(1, MAX_PAGE_SIZE),
+    )
+    .map_err(|e| format!("Failed to read events: {}", e))
+}
+
+/// Receive only the events that match `filter`
+///
+/// Matching events are emitted as `event-bus:<id>` with the returned ID, in
+/// the same shape as `event-bus`. Close the subscription with
+/// `unsubscribe_events` when the view goes away.
+#[tauri::command]
+pub async fn subscribe_events(
+    subscriptions: State<'_, EventSubscriptions>,
+    filter: EventFilter,
+) -> Result<u64, String> {
+    subscriptions.subscribe(filter)
+}
+
+/// Close a subscription opened with `subscribe_events`
+#[tauri::command]
+pub async fn unsubscribe_events(
+    subscriptions: State<'_, EventSubscriptions>,
+    id: u64,
+) -> Result<bool, String> {
+    Ok(subscriptions.unsubscribe(id))
+}
@@ -0,0 +1,13 @@
+use crate::file_audit::{self, FileAuditEntry, FileAuditFilter};
+
+/// Files opcode created, modified or deleted, newest first
+///
+/// Every filter field is optional; page back with `before_id` set to the
+/// last ID returned.
+#[tauri::command]
+pub async fn query_audit_log(
+    filter: Option<FileAuditFilter>,
+) -> Result<Vec<FileAuditEntry>, String> {
+    let filter = filter.unwrap_or_default();
+    file_audit::with_log(|conn| file_audit::query(conn, &filter))
+}
@@ -0,0 +1,392 @@
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::utils::get_claude_dir;
+
+/// Tools whose input names a file they modify, with the input field holding the path
+const FILE_WRITE_TOOLS: &[(&str, &str)] = &[
+    ("Write", "file_path"),
+    ("Edit", "file_path"),
+    ("MultiEdit", "file_path"),
+    ("NotebookEdit", "notebook_path"),
+];
+
+/// A message in a session transcript that modified a file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEditLink {
+    pub file_path: String,
+    pub session_id: String,
+    pub project_id: String,
+    /// Index of the message in the session's JSONL transcript
+    pub message_index: usize,
+    pub message_uuid: Option<String>,
+    pub tool_name: String,
+    pub timestamp: Option<String>,
+}
+
+/// Result of bringing the index up to date
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileHistoryIndexStats {
+    pub sessions_indexed: usize,
+    pub sessions_removed: usize,
+    pub links_added: usize,
+}
+
+/// Create the file history tables
+pub fn init_file_history(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS file_edit_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_path TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            message_index INTEGER NOT NULL,
+            message_uuid TEXT,
+            tool_name TEXT NOT NULL,
+            timestamp TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_file_edit_links_path ON file_edit_links(file_path)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_file_edit_links_session ON file_edit_links(session_id)",
+        [],
+    )?;
+
+    // Size and mtime of each indexed transcript, to skip unchanged sessions
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS file_history_sessions (
+            session_id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            modified_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn row_to_link(row: &Row) -> SqliteResult<FileEditLink> {
+    Ok(FileEditLink {
+        file_path: row.get(0)?,
+        session_id: row.get(1)?,
+        project_id: row.get(2)?,
+        message_index: row.get::<_, i64>(3)? as usize,
+        message_uuid: row.get(4)?,
+        tool_name: row.get(5)?,
+        timestamp: row.get(6)?,
+    })
+}
+
+/// File edits made by the tool_use blocks of one transcript message
+pub fn edits_in_message(message: &JsonValue) -> Vec<(String, String)> {
+    let content = match message
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+    {
+        Some(content) => content,
+        None => return Vec::new(),
+    };
+
+    content
+        .iter()
+        .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+        .filter_map(|block| {
+            let name = block.get("name")?.as_str()?;
+            let (_, field) = FILE_WRITE_TOOLS.iter().find(|(tool, _)| *tool == name)?;
+            let path = block.get("input")?.get(*field)?.as_str()?;
+            Some((path.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+/// Extract the file edit links from a session transcript
+///
+/// Message indexes count parsed lines only, matching `load_session_history`.
+pub fn links_in_transcript(path: &Path, session_id: &str, project_id: &str) -> Vec<FileEditLink> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut links = Vec::new();
+    let messages = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<JsonValue>(&line).ok());
+
+    for (index, message) in messages.enumerate() {
+        for (file_path, tool_name) in edits_in_message(&message) {
+            links.push(FileEditLink {
+                file_path,
+                session_id: session_id.to_string(),
+                project_id: project_id.to_string(),
+                message_index: index,
+                message_uuid: message
+                    .get("uuid")
+                    .and_then(|u| u.as_str())
+                    .map(str::to_string),
+                tool_name,
+                timestamp: message
+                    .get("timestamp")
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string),
+            });
+        }
+    }
+    links
+}
+
+/// Replace the indexed links of a session
+fn store_session_links(
+    conn: &Connection,
+    session_id: &str,
+    project_id: &str,
+    file_size: i64,
+    modified_at: i64,
+    links: &[FileEditLink],
+) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM file_edit_links WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    for link in links {
+        tx.execute(
+            "INSERT INTO file_edit_links (file_path, session_id, project_id, message_index, message_uuid, tool_name, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                link.file_path,
+                link.session_id,
+                link.project_id,
+                link.message_index as i64,
+                link.message_uuid,
+                link.tool_name,
+                link.timestamp
+            ],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO file_history_sessions (session_id, project_id, file_size, modified_at) VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, project_id, file_size, modified_at],
+    )?;
+    tx.commit()
+}
+
+fn remove_session(conn: &Connection, session_id: &str) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM file_edit_links WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    conn.execute(
+        "DELETE FROM file_history_sessions WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    Ok(())
+}
+
+/// Edits of a file, oldest first
+pub fn history_for_file(conn: &Connection, file_path: &str) -> SqliteResult<Vec<FileEditLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_path, session_id, project_id, message_index, message_uuid, tool_name, timestamp
+         FROM file_edit_links WHERE file_path = ?1
+         ORDER BY timestamp, session_id, message_index",
+    )?;
+    let links = stmt
+        .query_map(params![file_path], row_to_link)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(links)
+}
+
+/// Distinct files under `root` that sessions have edited
+pub fn files_edited_under(conn: &Connection, root: &str) -> SqliteResult<Vec<String>> {
+    let prefix = format!("{}/", root.trim_end_matches('/'));
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT file_path FROM file_edit_links WHERE substr(file_path, 1, length(?1)) = ?1",
+    )?;
+    let files = stmt
+        .query_map(params![prefix], |row| row.get(0))?
+        .collect::<SqliteResult<Vec<String>>>()?;
+    Ok(files)
+}
+
+/// Bring the index up to date with the transcripts on disk
+///
+/// Only sessions whose transcript changed size or mtime since the last run are
+/// re-read, so this is cheap to call before every lookup.
+pub fn sync_file_history(db: &AgentDb) -> Result<FileHistoryIndexStats, String> {
+    let known: HashMap<String, (i64, i64)> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT session_id, file_size, modified_at FROM file_history_sessions")
+            .map_err(|e| e.to_string())?;
+        let known = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| e.to_string())?
+            .collect::<SqliteResult<_>>()
+            .map_err(|e| e.to_string())?;
+        known
+    };
+
+    let projects_dir = get_claude_dir()?.join("projects");
+    let mut seen = Vec::new();
+    let mut changed = Vec::new();
+
+    if let Ok(projects) = fs::read_dir(&projects_dir) {
+        for project in projects.flatten().filter(|p| p.path().is_dir()) {
+            let project_id = project.file_name().to_string_lossy().to_string();
+            let sessions = match fs::read_dir(project.path()) {
+                Ok(sessions) => sessions,
+                Err(_) => continue,
+            };
+
+            for session in sessions.flatten() {
+                let path = session.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                    continue;
+                }
+                let session_id = match path.file_stem().and_then(|s| s.to_str()) {
+                    Some(id) => id.to_string(),
+                    None => continue,
+                };
+                let metadata = match session.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                let size = metadata.len() as i64;
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as i64)
+                    .unwrap_or(0);
+
+                seen.push(session_id.clone());
+                if known.get(&session_id) != Some(&(size, modified)) {
+                    let links = links_in_transcript(&path, &session_id, &project_id);
+                    changed.push((session_id, project_id.clone(), size, modified, links));
+                }
+            }
+        }
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stats = FileHistoryIndexStats::default();
+
+    for (session_id, project_id, size, modified, links) in &changed {
+        store_session_links(&conn, session_id, project_id, *size, *modified, links)
+            .map_err(|e| format!("Failed to index session {}: {}", session_id, e))?;
+        stats.sessions_indexed += 1;
+        stats.links_added += links.len();
+    }
+
+    for session_id in known.keys().filter(|id| !seen.contains(id)) {
+        remove_session(&conn, session_id).map_err(|e| e.to_string())?;
+        stats.sessions_removed += 1;
+    }
+
+    if stats.sessions_indexed > 0 || stats.sessions_removed > 0 {
+        log::info!(
+            "File history index updated: {} sessions indexed, {} removed, {} links",
+            stats.sessions_indexed,
+            stats.sessions_removed,
+            stats.links_added
+        );
+    }
+    Ok(stats)
+}
+
+/// List every session message that modified a file, oldest first
+#[tauri::command]
+pub async fn get_file_history(
+    db: State<'_, AgentDb>,
+    file_path: String,
+) -> Result<Vec<FileEditLink>, String> {
+    sync_file_history(&db)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    history_for_file(&conn, file_path.trim()).map_err(|e| e.to_string())
+}
+
+/// Drop and rebuild the file history index from all transcripts
+#[tauri::command]
+pub async fn rebuild_file_history_index(
+    db: State<'_, AgentDb>,
+) -> Result<FileHistoryIndexStats, String> {
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM file_edit_links", [])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM file_history_sessions", [])
+            .map_err(|e| e.to_string())?;
+    }
+    sync_file_history(&db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_edits_in_message() {
+        let message = json!({
+            "type": "assistant",
+            "message": {
+                "content": [
+                    { "type": "text", "text": "Updating files" },
+                    { "type": "tool_use", "name": "Edit", "input": { "file_path": "/p/src/lib.rs" } },
+                    { "type": "tool_use", "name": "Read", "input": { "file_path": "/p/src/main.rs" } },
+                    { "type": "tool_use", "name": "NotebookEdit", "input": { "notebook_path": "/p/a.ipynb" } }
+                ]
+            }
+        });
+
+        assert_eq!(
+            edits_in_message(&message),
+            vec![
+                ("/p/src/lib.rs".to_string(), "Edit".to_string()),
+                ("/p/a.ipynb".to_string(), "NotebookEdit".to_string()),
+            ]
+        );
+        assert!(edits_in_message(&json!({ "type": "summary" })).is_empty());
+    }
+
+    #[test]
+    fn test_session_links_replace_previous_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_file_history(&conn).unwrap();
+
+        let link = |index: usize| FileEditLink {
+            file_path: "/p/src/lib.rs".to_string(),
+            session_id: "s1".to_string(),
+            project_id: "p".to_string(),
+            message_index: index,
+            message_uuid: None,
+            tool_name: "Write".to_string(),
+            timestamp: None,
+        };
+
+        store_session_links(&conn, "s1", "p", 10, 1, &[link(1), link(4)]).unwrap();
+        store_session_links(&conn, "s1", "p", 20, 2, &[link(4)]).unwrap();
+
+        let history = history_for_file(&conn, "/p/src/lib.rs").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].message_index, 4);
+
+        remove_session(&conn, "s1").unwrap();
+        assert!(history_for_file(&conn, "/p/src/lib.rs").unwrap().is_empty());
+    }
+}
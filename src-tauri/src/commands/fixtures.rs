@@ -0,0 +1,401 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const PROJECT_NAMES: &[&str] = &[
+    "web-app",
+    "api-server",
+    "data-pipeline",
+    "mobile-client",
+    "infra",
+    "design-system",
+    "billing-service",
+    "cli-tools",
+];
+
+const WORDS: &[&str] = &[
+    "the",
+    "function",
+    "returns",
+    "error",
+    "when",
+    "config",
+    "is",
+    "missing",
+    "update",
+    "test",
+    "cache",
+    "request",
+    "handler",
+    "should",
+    "retry",
+    "after",
+    "timeout",
+    "refactor",
+    "module",
+    "database",
+    "query",
+    "index",
+    "add",
+    "fix",
+    "build",
+    "component",
+    "state",
+    "render",
+    "parse",
+    "input",
+    "output",
+    "file",
+    "path",
+    "check",
+    "value",
+    "null",
+    "type",
+    "struct",
+    "field",
+];
+
+const TOOLS: &[&str] = &["Read", "Edit", "Bash", "Grep", "Write"];
+
+const MODELS: &[&str] = &["claude-sonnet-4-20250514", "claude-opus-4-20250514"];
+
+/// Options for `generate_test_fixtures`; unset fields use the defaults shown
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FixtureOptions {
+    /// Same seed and end date, same bytes (default 1)
+    pub seed: Option<u64>,
+    /// 1-50 (default 5)
+    pub projects: Option<u32>,
+    /// 1-200 (default 8)
+    pub sessions_per_project: Option<u32>,
+    /// Most lines per session, 2-20000 (default 2000); each session gets
+    /// between half and all of them
+    pub messages_per_session: Option<u32>,
+    /// How far back sessions go, 1-24 months (default 6)
+    pub months: Option<u32>,
+    /// Last day sessions are spread over, YYYY-MM-DD (default today, UTC)
+    pub end_date: Option<String>,
+}
+
+/// What was generated and where
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureSummary {
+    /// Claude directory to pass to `set_claude_dir_location`
+    pub path: String,
+    pub projects: u32,
+    pub sessions: u32,
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+/// SplitMix64; small and fully determined by the seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `low..=high`
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn uuid(&mut self) -> String {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.next().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next().to_le_bytes());
+        uuid::Builder::from_random_bytes(bytes)
+            .into_uuid()
+            .to_string()
+    }
+
+    fn sentence(&mut self, min_words: u64, max_words: u64) -> String {
+        let count = self.range(min_words, max_words);
+        let words: Vec<&str> = (0..count).map(|_| self.pick(WORDS)).collect();
+        let mut sentence = words.join(" ");
+        if let Some(first) = sentence.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        sentence.push('.');
+        sentence
+    }
+}
+
+fn bounded(
+    value: Option<u32>,
+    default: u32,
+    min: u32,
+    max: u32,
+    what: &str,
+) -> Result<u32, String> {
+    let value = value.unwrap_or(default);
+    if !(min..=max).contains(&value) {
+        return Err(format!("{} must be between {} and {}", what, min, max));
+    }
+    Ok(value)
+}
+
+/// Fully resolved fixture options
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    pub seed: u64,
+    pub projects: u32,
+    pub sessions_per_project: u32,
+    pub messages_per_session: u32,
+    pub months: u32,
+    pub end: DateTime<Utc>,
+}
+
+impl FixtureSpec {
+    pub fn from_options(options: &FixtureOptions) -> Result<Self, String> {
+        let end_date = match &options.end_date {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|e| format!("Invalid end date '{}': {}", date, e))?,
+            None => Utc::now().date_naive(),
+        };
+        Ok(FixtureSpec {
+            seed: options.seed.unwrap_or(1),
+            projects: bounded(options.projects, 5, 1, 50, "Projects")?,
+            sessions_per_project: bounded(
+                options.sessions_per_project,
+                8,
+                1,
+                200,
+                "Sessions per project",
+            )?,
+            messages_per_session: bounded(
+                options.messages_per_session,
+                2000,
+                2,
+                20_000,
+                "Messages per session",
+            )?,
+            months: bounded(options.months, 6, 1, 24, "Months")?,
+            end: end_date
+                .and_hms_opt(23, 59, 59)
+                .unwrap_or_default()
+                .and_utc(),
+        })
+    }
+}
+
+/// Write one session transcript; returns the number of lines
+fn write_session(
+    rng: &mut Rng,
+    path: &Path,
+    session_id: &str,
+    cwd: &str,
+    start: DateTime<Utc>,
+    lines: u32,
+) -> io::Result<u64> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let model = if rng.chance(25) { MODELS[1] } else { MODELS[0] };
+    let mut time = start;
+    let mut parent: Option<String> = None;
+    let mut pending_tool: Option<String> = None;
+
+    for i in 0..lines {
+        time += Duration::seconds(rng.range(2, 90) as i64);
+        let uuid = rng.uuid();
+        let timestamp = time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        let line = if i % 2 == 0 {
+            let content = match pending_tool.take() {
+                Some(tool_use_id) => json!([{
+                    "type": "tool_result",
+                    "tool_use_id": tool_use_id,
+                    "content": (0..rng.range(1, 40)).map(|_| rng.sentence(4, 14)).collect::<Vec<_>>().join("\n"),
+                }]),
+                None if i == 0 => json!(rng.sentence(8, 30)),
+                None => json!(rng.sentence(3, 40)),
+            };
+            json!({
+                "type": "user",
+                "uuid": uuid,
+                "parentUuid": parent,
+                "sessionId": session_id,
+                "cwd": cwd,
+                "timestamp": timestamp,
+                "message": { "role": "user", "content": content },
+            })
+        } else {
+            let mut content = vec![json!({
+                "type": "text",
+                "text": (0..rng.range(1, 8)).map(|_| rng.sentence(5, 25)).collect::<Vec<_>>().join(" "),
+            })];
+            if rng.chance(40) {
+                let tool_use_id = format!("toolu_{}", &rng.uuid().replace('-', "")[..24]);
+                content.push(json!({
+                    "type": "tool_use",
+                    "id": tool_use_id,
+                    "name": rng.pick(TOOLS),
+                    "input": { "file_path": format!("{}/src/{}.rs", cwd, rng.pick(WORDS)) },
+                }));
+                pending_tool = Some(tool_use_id);
+            }
+            json!({
+                "type": "assistant",
+                "uuid": uuid,
+                "parentUuid": parent,
+                "sessionId": session_id,
+                "cwd": cwd,
+                "timestamp": timestamp,
+                "requestId": format!("req_{}", &rng.uuid().replace('-', "")[..24]),
+                "message": {
+                    "id": format!("msg_{}", &rng.uuid().replace('-', "")[..24]),
+                    "role": "assistant",
+                    "model": model,
+                    "content": content,
+                    "usage": {
+                        "input_tokens": rng.range(5, 4000),
+                        "output_tokens": rng.range(20, 3000),
+                        "cache_creation_input_tokens": rng.range(0, 20_000),
+                        "cache_read_input_tokens": rng.range(0, 150_000),
+                    },
+                },
+            })
+        };
+        serde_json::to_writer(&mut out, &line)?;
+        out.write_all(b"\n")?;
+        parent = Some(uuid);
+    }
+    out.flush()?;
+
+    let file = out.into_inner().map_err(|e| e.into_error())?;
+    file.set_modified(SystemTime::from(time))?;
+    Ok(lines as u64)
+}
+
+/// Generate a Claude directory with synthetic projects and sessions under `root`
+pub fn write_fixtures(root: &Path, spec: &FixtureSpec) -> io::Result<FixtureSummary> {
+    let mut rng = Rng(spec.seed);
+    let projects_dir = root.join("projects");
+    fs::create_dir_all(&projects_dir)?;
+    fs::write(root.join("settings.json"), "{}\n")?;
+
+    let span_secs = spec.months as i64 * 30 * 24 * 3600;
+    let mut summary = FixtureSummary {
+        path: root.to_string_lossy().to_string(),
+        projects: spec.projects,
+        sessions: 0,
+        messages: 0,
+        bytes: 0,
+    };
+
+    for p in 0..spec.projects {
+        let name = PROJECT_NAMES[p as usize % PROJECT_NAMES.len()];
+        let cwd = format!("/home/fixture/code/{}-{}", name, p + 1);
+        let project_dir = projects_dir.join(cwd.replace('/', "-"));
+        fs::create_dir_all(&project_dir)?;
+
+        for _ in 0..spec.sessions_per_project {
+            let session_id = rng.uuid();
+            let max = spec.messages_per_session as u64;
+            let lines = rng.range((max / 2).max(2), max) as u32;
+            // Lines are at most 90s apart; keep the last one before the end date
+            let latest_start = spec.end - Duration::seconds(lines as i64 * 90);
+            let start = (spec.end - Duration::seconds(rng.range(0, span_secs as u64) as i64))
+                .min(latest_start);
+            let path = project_dir.join(format!("{}.jsonl", session_id));
+            let lines = write_session(&mut rng, &path, &session_id, &cwd, start, lines)?;
+            summary.sessions += 1;
+            summary.messages += lines;
+            summary.bytes += fs::metadata(&path)?.len();
+        }
+    }
+    Ok(summary)
+}
+
+/// Generate synthetic Claude data into a fresh temporary directory (development builds only)
+///
+/// Projects, long sessions and token usage spread over the last months are
+/// written to `<temp>/opcode-fixtures/seed-<seed>`, replacing an earlier run
+/// with the same seed. Point the app at it with `set_claude_dir_location`.
+#[tauri::command]
+pub async fn generate_test_fixtures(
+    options: Option<FixtureOptions>,
+) -> Result<FixtureSummary, String> {
+    if !cfg!(debug_assertions) {
+        return Err("Test fixtures can only be generated in development builds".to_string());
+    }
+    let spec = FixtureSpec::from_options(&options.unwrap_or_default())?;
+    let root: PathBuf = std::env::temp_dir()
+        .join("opcode-fixtures")
+        .join(format!("seed-{}", spec.seed));
+
+    tokio::task::spawn_blocking(move || {
+        if root.exists() {
+            fs::remove_dir_all(&root)
+                .map_err(|e| format!("Failed to clear {}: {}", root.display(), e))?;
+        }
+        let summary =
+            write_fixtures(&root, &spec).map_err(|e| format!("Failed to write fixtures: {}", e))?;
+        log::info!(
+            "Generated {} sessions ({} bytes) in {}",
+            summary.sessions,
+            summary.bytes,
+            summary.path
+        );
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        let spec = FixtureSpec::from_options(&FixtureOptions {
+            seed: Some(42),
+            projects: Some(2),
+            sessions_per_project: Some(3),
+            messages_per_session: Some(50),
+            end_date: Some("2025-06-30".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let first = write_fixtures(a.path(), &spec).unwrap();
+        let second = write_fixtures(b.path(), &spec).unwrap();
+        assert_eq!(first.sessions, 6);
+        assert_eq!(
+            (first.messages, first.bytes),
+            (second.messages, second.bytes)
+        );
+
+        let project = a.path().join("projects/-home-fixture-code-web-app-1");
+        let session = fs::read_dir(&project)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let first_line = fs::read_to_string(&session).unwrap();
+        let entry: serde_json::Value =
+            serde_json::from_str(first_line.lines().next().unwrap()).unwrap();
+        assert_eq!(entry["cwd"], "/home/fixture/code/web-app-1");
+        assert_eq!(entry["message"]["role"], "user");
+        assert!(entry["timestamp"].as_str().unwrap() < "2025-07-01");
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::health::load_health_settings;
+
+/// Days of history used to fit the trend when no range is given
+const DEFAULT_RANGE_DAYS: u32 = 30;
+const MIN_RANGE_DAYS: u32 = 7;
+const MAX_RANGE_DAYS: u32 = 90;
+
+/// z-score for the 95% bounds
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Projected spend for the rest of the month
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SpendForecast {
+    /// Trend value for today
+    pub daily_rate_usd: f64,
+    pub projected_usd: f64,
+    pub lower_usd: f64,
+    pub upper_usd: f64,
+}
+
+/// End-of-month spend forecast for one project
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectForecast {
+    pub project_path: String,
+    pub month_to_date_usd: f64,
+    pub daily_rate_usd: f64,
+    /// Expected spend by the end of the calendar month (UTC)
+    pub projected_month_end_usd: f64,
+    /// 95% confidence bounds on the month-end spend
+    pub lower_bound_usd: f64,
+    pub upper_bound_usd: f64,
+    pub budget_usd: Option<f64>,
+    /// Whether the projection reaches the budget before the month ends
+    pub projected_over_budget: bool,
+}
+
+/// Forecast spend over the next `remaining_days` from a daily series
+///
+/// Fits a least-squares line to `daily` (oldest first, one entry per day,
+/// zeros included) and sums it forward. The bounds assume the days are
+/// independent with the spread of the fit's residuals. Predicted days never
+/// go below zero.
+pub fn forecast_spend(daily: &[f64], remaining_days: u32) -> SpendForecast {
+    let n = daily.len();
+    if n == 0 {
+        return SpendForecast {
+            daily_rate_usd: 0.0,
+            projected_usd: 0.0,
+            lower_usd: 0.0,
+            upper_usd: 0.0,
+        };
+    }
+
+    let nf = n as f64;
+    let mean_x = (nf - 1.0) / 2.0;
+    let mean_y = daily.iter().sum::<f64>() / nf;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (i, y) in daily.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        sxy += dx * (y - mean_y);
+        sxx += dx * dx;
+    }
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let at = |day: f64| (mean_y + slope * (day - mean_x)).max(0.0);
+
+    let residual_ss: f64 = daily
+        .iter()
+        .enumerate()
+        .map(|(i, y)| (y - (mean_y + slope * (i as f64 - mean_x))).powi(2))
+        .sum();
+    let sigma = if n > 2 {
+        (residual_ss / (nf - 2.0)).sqrt()
+    } else {
+        0.0
+    };
+
+    let projected: f64 = (0..remaining_days).map(|d| at(nf + d as f64)).sum();
+    let margin = CONFIDENCE_Z * sigma * (remaining_days as f64).sqrt();
+    SpendForecast {
+        daily_rate_usd: at(nf - 1.0),
+        projected_usd: projected,
+        lower_usd: (projected - margin).max(0.0),
+        upper_usd: projected + margin,
+    }
+}
+
+/// Daily spend per project from the run history over the last `range_days` days
+fn daily_spend(
+    conn: &Connection,
+    project_path: Option<&str>,
+    today: NaiveDate,
+    range_days: u32,
+) -> SqliteResult<BTreeMap<String, Vec<f64>>> {
+    let start = today - Duration::days(range_days as i64 - 1);
+    let mut stmt = conn.prepare(
+        "SELECT project_path, date(started_at), COALESCE(SUM(cost_usd), 0.0) FROM run_history
+         WHERE date(started_at) >= ?1 AND (?2 IS NULL OR project_path = ?2)
+         GROUP BY project_path, date(started_at)",
+    )?;
+    let rows = stmt.query_map(
+        params![start.format("%Y-%m-%d").to_string(), project_path],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        },
+    )?;
+
+    let mut series: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for row in rows {
+        let (project, day, cost) = row?;
+        let day = match NaiveDate::parse_from_str(&day, "%Y-%m-%d") {
+            Ok(day) if day <= today => day,
+            _ => continue,
+        };
+        let index = (day - start).num_days() as usize;
+        series
+            .entry(project)
+            .or_insert_with(|| vec![0.0; range_days as usize])[index] += cost;
+    }
+    Ok(series)
+}
+
+fn month_to_date(conn: &Connection, project_path: &str) -> f64 {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0.0) FROM run_history
+         WHERE project_path = ?1 AND started_at >= datetime('now', 'start of month')",
+        params![project_path],
+        |row| row.get(0),
+    )
+    .unwrap_or(0.0)
+}
+
+/// Days left in the month after `today`
+fn days_left_in_month(today: NaiveDate) -> u32 {
+    let (year, month) = if today.month() == 12 {
+        (today.year() + 1, 1)
+    } else {
+        (today.year(), today.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .map(|next| (next - today).num_days() as u32 - 1)
+        .unwrap_or(0)
+}
+
+/// Forecast month-end spend for each project with recent runs, or just `project_path`
+pub fn forecast_projects(
+    conn: &Connection,
+    project_path: Option<&str>,
+    range_days: u32,
+) -> SqliteResult<Vec<ProjectForecast>> {
+    let today = Utc::now().date_naive();
+    let remaining = days_left_in_month(today);
+    let budget = load_health_settings(conn).monthly_budget_usd;
+
+    let series = daily_spend(conn, project_path, today, range_days)?;
+    let mut forecasts: Vec<ProjectForecast> = series
+        .into_iter()
+        .map(|(project, daily)| {
+            let spent = month_to_date(conn, &project);
+            let forecast = forecast_spend(&daily, remaining);
+            let projected = spent + forecast.projected_usd;
+            ProjectForecast {
+                month_to_date_usd: spent,
+                daily_rate_usd: forecast.daily_rate_usd,
+                projected_month_end_usd: projected,
+                lower_bound_usd: spent + forecast.lower_usd,
+                upper_bound_usd: spent + forecast.upper_usd,
+                budget_usd: budget,
+                projected_over_budget: budget.is_some_and(|b| projected >= b),
+                project_path: project,
+            }
+        })
+        .collect();
+    forecasts.sort_by(|a, b| {
+        b.projected_month_end_usd
+            .total_cmp(&a.projected_month_end_usd)
+    });
+    Ok(forecasts)
+}
+
+/// Forecast end-of-month spend per project from the trend over the last `range_days` days
+#[tauri::command]
+pub async fn forecast_usage(
+    db: State<'_, AgentDb>,
+    range_days: Option<u32>,
+    project_path: Option<String>,
+) -> Result<Vec<ProjectForecast>, String> {
+    let range_days = range_days.unwrap_or(DEFAULT_RANGE_DAYS);
+    if !(MIN_RANGE_DAYS..=MAX_RANGE_DAYS).contains(&range_days) {
+        return Err(format!(
+            "Range must be between {} and {} days",
+            MIN_RANGE_DAYS, MAX_RANGE_DAYS
+        ));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    forecast_projects(&conn, project_path.as_deref(), range_days)
+        .map_err(|e| format!("Failed to forecast usage: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast_spend_follows_trend() {
+        // $1/day growing by $1 each day: the next three days cost 6 + 7 + 8
+        let rising = forecast_spend(&[1.0, 2.0, 3.0, 4.0, 5.0], 3);
+        assert!((rising.projected_usd - 21.0).abs() < 1e-9);
+        assert!((rising.daily_rate_usd - 5.0).abs() < 1e-9);
+        assert_eq!(rising.lower_usd, rising.upper_usd);
+
+        // A falling trend bottoms out at zero instead of going negative
+        let falling = forecast_spend(&[4.0, 3.0, 2.0, 1.0], 10);
+        assert!((falling.projected_usd - 0.0).abs() < 1e-9);
+
+        let noisy = forecast_spend(&[1.0, 3.0, 1.0, 3.0, 1.0, 3.0], 5);
+        assert!(noisy.lower_usd < noisy.projected_usd && noisy.projected_usd < noisy.upper_usd);
+
+        assert_eq!(
+            days_left_in_month(NaiveDate::from_ymd_opt(2024, 2, 27).unwrap()),
+            2
+        );
+    }
+}
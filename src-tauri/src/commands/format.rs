@@ -0,0 +1,48 @@
+use std::time::Duration;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::settings;
+use crate::format::{Formatter, LOCALES};
+
+/// Format numbers the way the backend does, for the locale chosen in settings
+///
+/// `kind` is "cost" (USD), "duration_ms", "bytes" or "count". Lets the UI show
+/// the same text as notifications and health reports.
+#[tauri::command]
+pub async fn format_values(
+    db: State<'_, AgentDb>,
+    kind: String,
+    values: Vec<f64>,
+) -> Result<Vec<String>, String> {
+    let fmt = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        Formatter::from_settings(&conn)
+    };
+    let format: fn(&Formatter, f64) -> String = match kind.as_str() {
+        "cost" => |fmt, v| fmt.cost(v),
+        "duration_ms" => |fmt, v| fmt.duration(Duration::from_millis(v.max(0.0) as u64)),
+        "bytes" => |fmt, v| fmt.bytes(v.max(0.0) as u64),
+        "count" => |fmt, v| fmt.count(v.round() as i64),
+        other => return Err(format!("Unknown format kind '{}'", other)),
+    };
+    Ok(values.into_iter().map(|v| format(&fmt, v)).collect())
+}
+
+/// Switch the app's locale, e.g. "de-DE"
+///
+/// Numbers are formatted for it and catalog descriptions (agent templates,
+/// MCP servers) come in its language from the next fetch, in English where
+/// there is no translation.
+#[tauri::command]
+pub async fn set_app_locale(db: State<'_, AgentDb>, locale: String) -> Result<(), String> {
+    if !LOCALES.contains(&locale.as_str()) {
+        return Err(format!(
+            "Unsupported locale '{}'; expected one of {}",
+            locale,
+            LOCALES.join(", ")
+        ));
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::set_value(&conn, "locale", &serde_json::json!(locale))
+}
@@ -0,0 +1,435 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Output;
+use tokio::process::Command;
+
+use crate::diff_providers::{DiffProviders, SpecialDiff};
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::{resolve_within, validate_project_root};
+
+/// Tree object of an empty directory, the base to diff against before the first commit
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Diffs larger than this are cut off so the review view stays responsive
+const MAX_DIFF_BYTES: usize = 5 * 1024 * 1024;
+
+/// How a file differs from the last commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+/// A file with uncommitted changes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedFile {
+    /// Relative to the project directory
+    pub path: String,
+    /// Where a renamed file came from
+    pub original_path: Option<String>,
+    pub status: FileChangeStatus,
+    /// Some of the change is already in the index
+    pub staged: bool,
+    /// `None` for binary files
+    pub additions: Option<u64>,
+    pub deletions: Option<u64>,
+}
+
+/// Uncommitted changes of a project as a unified diff
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkingDiff {
+    pub diff: String,
+    /// The diff was cut off at `MAX_DIFF_BYTES`
+    pub truncated: bool,
+    /// Images, lockfiles and notebooks, summarized by a diff provider
+    pub special: Vec<SpecialFileDiff>,
+}
+
+/// A changed file with a type-aware summary next to its line diff
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecialFileDiff {
+    pub path: String,
+    pub diff: SpecialDiff,
+}
+
+/// The commit made by `stage_and_commit`
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitResult {
+    pub sha: String,
+    pub files: usize,
+}
+
+fn git_command(dir: &Path, args: &[&str]) -> std::process::Command {
+    let mut command = std::process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "core.quotePath=false"])
+        .args(args);
+    command
+}
+
+async fn git_output(dir: &Path, args: &[&str]) -> Result<Output, String> {
+    Command::from(git_command(dir, args))
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git: {}", e))
+}
+
+pub(crate) async fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    stdout_of(args, git_output(dir, args).await?)
+}
+
+/// `git` for code that runs on a blocking thread
+pub(crate) fn git_blocking(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = git_command(dir, args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    stdout_of(args, output)
+}
+
+fn stdout_of(args: &[&str], output: Output) -> Result<String, String> {
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse `git status --porcelain=v1 -z`
+///
+/// Paths are relative to the repository root; `prefix` (the project's place in
+/// the repository) is stripped so they become relative to the project.
+pub fn parse_status(output: &str, prefix: &str) -> Vec<ChangedFile> {
+    let relative = |path: &str| path.strip_prefix(prefix).unwrap_or(path).to_string();
+    let mut files = Vec::new();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let (x, y) = (record.as_bytes()[0] as char, record.as_bytes()[1] as char);
+        let path = relative(&record[3..]);
+        // Renames and copies are followed by the path they came from
+        let original_path = matches!(x, 'R' | 'C')
+            .then(|| records.next().map(relative))
+            .flatten();
+
+        let status = match (x, y) {
+            ('?', '?') => FileChangeStatus::Untracked,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => FileChangeStatus::Conflicted,
+            ('R', _) => FileChangeStatus::Renamed,
+            ('A', _) => FileChangeStatus::Added,
+            ('D', _) | (_, 'D') => FileChangeStatus::Deleted,
+            _ => FileChangeStatus::Modified,
+        };
+        files.push(ChangedFile {
+            path,
+            original_path,
+            status,
+            staged: !matches!(x, ' ' | '?'),
+            additions: None,
+            deletions: None,
+        });
+    }
+    files
+}
+
+/// The project's path inside its repository, e.g. `app/` (empty at the root)
+pub(crate) async fn repo_prefix(project: &Path) -> Result<String, String> {
+    Ok(git(project, &["rev-parse", "--show-prefix"])
+        .await?
+        .trim()
+        .to_string())
+}
+
+/// `HEAD`, or the empty tree in a repository without commits
+async fn diff_base(project: &Path) -> &'static str {
+    match git(project, &["rev-parse", "--verify", "-q", "HEAD"]).await {
+        Ok(_) => "HEAD",
+        Err(_) => EMPTY_TREE,
+    }
+}
+
+async fn changed_files(project: &Path) -> Result<Vec<ChangedFile>, String> {
+    let prefix = repo_prefix(project).await?;
+    let status = git(
+        project,
+        &[
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            ".",
+        ],
+    )
+    .await?;
+    let mut files = parse_status(&status, &prefix);
+
+    // Line counts for tracked files; untracked ones are counted from disk
+    let base = diff_base(project).await;
+    let numstat = git(
+        project,
+        &["diff", base, "--numstat", "--no-renames", "--relative"],
+    )
+    .await
+    .unwrap_or_default();
+    let counts: HashMap<&str, (Option<u64>, Option<u64>)> = numstat
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let (added, deleted, path) = (parts.next()?, parts.next()?, parts.next()?);
+            Some((path, (added.parse().ok(), deleted.parse().ok())))
+        })
+        .collect();
+    for file in &mut files {
+        if file.status == FileChangeStatus::Untracked {
+            if let Ok(content) = std::fs::read_to_string(project.join(&file.path)) {
+                file.additions = Some(content.lines().count() as u64);
+                file.deletions = Some(0);
+            }
+        } else if let Some((additions, deletions)) = counts.get(file.path.as_str()) {
+            file.additions = *additions;
+            file.deletions = *deletions;
+        }
+    }
+    Ok(files)
+}
+
+/// Check that every path in `files` stays inside the project
+fn validate_files(project: &Path, files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Err("No files selected".to_string());
+    }
+    for file in files {
+        resolve_within(project, file)?;
+    }
+    Ok(())
+}
+
+/// List files with uncommitted changes in a project, with line counts
+#[tauri::command]
+pub async fn get_changed_files(project_path: String) -> Result<Vec<ChangedFile>, String> {
+    let project = validate_project_root(&project_path)?;
+    changed_files(&project).await
+}
+
+/// Get the uncommitted changes of a project, untracked files included, as a unified diff
+#[tauri::command]
+pub async fn get_working_diff(project_path: String) -> Result<WorkingDiff, String> {
+    let project = validate_project_root(&project_path)?;
+    let base = diff_base(&project).await;
+    let mut diff = git(
+        &project,
+        &["diff", base, "--no-color", "--relative", "--", "."],
+    )
+    .await?;
+
+    let files = changed_files(&project).await?;
+    let untracked = files
+        .iter()
+        .filter(|f| f.status == FileChangeStatus::Untracked);
+    for file in untracked {
+        if diff.len() > MAX_DIFF_BYTES {
+            break;
+        }
+        // Exits with 1 when the files differ, which they always do here
+        let output = git_output(
+            &project,
+            &[
+                "diff",
+                "--no-index",
+                "--no-color",
+                "--",
+                "/dev/null",
+                file.path.as_str(),
+            ],
+        )
+        .await?;
+        diff.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+
+    let truncated = diff.len() > MAX_DIFF_BYTES;
+    if truncated {
+        let mut end = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+    }
+    let special = special_diffs(&project, base, &files).await?;
+    Ok(WorkingDiff {
+        diff,
+        truncated,
+        special,
+    })
+}
+
+/// Run the diff providers over the changed files they handle
+async fn special_diffs(
+    project: &Path,
+    base: &str,
+    files: &[ChangedFile],
+) -> Result<Vec<SpecialFileDiff>, String> {
+    let providers = DiffProviders::with_default_providers();
+    // (path, content at the base, content on disk)
+    let mut contents = Vec::new();
+    for file in files
+        .iter()
+        .filter(|f| providers.handles(Path::new(&f.path)))
+    {
+        let before = match file.status {
+            FileChangeStatus::Added | FileChangeStatus::Untracked => None,
+            _ => {
+                let path = file.original_path.as_deref().unwrap_or(&file.path);
+                git_output(project, &["show", &format!("{}:./{}", base, path)])
+                    .await
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| output.stdout)
+            }
+        };
+        let after = match file.status {
+            FileChangeStatus::Deleted => None,
+            _ => tokio::fs::read(project.join(&file.path)).await.ok(),
+        };
+        contents.push((file.path.clone(), before, after));
+    }
+    // Decoding images is CPU-bound
+    tauri::async_runtime::spawn_blocking(move || {
+        contents
+            .into_iter()
+            .filter_map(|(path, before, after)| {
+                let diff = providers.diff(Path::new(&path), before.as_deref(), after.as_deref())?;
+                Some(SpecialFileDiff { path, diff })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Stage the given files and commit only them
+///
+/// Other staged changes are left out of the commit. Paths are relative to the
+/// project directory.
+#[tauri::command]
+pub async fn stage_and_commit(
+    project_path: String,
+    message: String,
+    files: Vec<String>,
+) -> Result<CommitResult, String> {
+    let project = validate_project_root(&project_path)?;
+    if message.trim().is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    validate_files(&project, &files)?;
+    let paths: Vec<&str> = files.iter().map(String::as_str).collect();
+
+    let mut add = vec!["add", "-A", "--"];
+    add.extend(&paths);
+    git(&project, &add).await?;
+
+    let mut commit = vec!["commit", "-q", "-m", message.trim(), "--only", "--"];
+    commit.extend(&paths);
+    git(&project, &commit).await?;
+
+    let sha = git(&project, &["rev-parse", "HEAD"]).await?;
+    Ok(CommitResult {
+        sha: sha.trim().to_string(),
+        files: files.len(),
+    })
+}
+
+/// Throw away the uncommitted changes to the given files
+///
+/// Modified and deleted files are restored from the last commit; files that
+/// are new since then are deleted. Returns the files that were reverted.
+#[tauri::command]
+pub async fn revert_files(project_path: String, files: Vec<String>) -> Result<Vec<String>, String> {
+    let project = validate_project_root(&project_path)?;
+    validate_files(&project, &files)?;
+
+    let changed = changed_files(&project).await?;
+    let mut restore = Vec::new();
+    let mut remove = Vec::new();
+    let mut reverted = Vec::new();
+    for change in changed.iter().filter(|c| files.contains(&c.path)) {
+        match change.status {
+            FileChangeStatus::Untracked => {
+                let path = project.join(&change.path);
+                std::fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to delete {}: {}", change.path, e))?;
+                file_audit::record("revert_files", FileOperation::Delete, &path);
+            }
+            FileChangeStatus::Added => remove.push(change.path.as_str()),
+            FileChangeStatus::Renamed => {
+                remove.push(change.path.as_str());
+                if let Some(original) = &change.original_path {
+                    restore.push(original.as_str());
+                }
+            }
+            _ => restore.push(change.path.as_str()),
+        }
+        reverted.push(change.path.clone());
+    }
+
+    if !remove.is_empty() {
+        let mut args = vec!["rm", "-q", "-f", "--"];
+        args.extend(&remove);
+        git(&project, &args).await?;
+        for path in &remove {
+            file_audit::record("revert_files", FileOperation::Delete, &project.join(path));
+        }
+    }
+    if !restore.is_empty() {
+        let mut args = vec!["restore", "--source=HEAD", "--staged", "--worktree", "--"];
+        args.extend(&restore);
+        let operations: Vec<FileOperation> = restore
+            .iter()
+            .map(|path| FileOperation::for_write(&project.join(path)))
+            .collect();
+        git(&project, &args).await?;
+        for (path, operation) in restore.iter().zip(operations) {
+            file_audit::record("revert_files", operation, &project.join(path));
+        }
+    }
+    Ok(reverted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let output = " M app/src/main.rs\0A  app/new.rs\0R  app/b.rs\0app/a.rs\0 D app/gone.rs\0UU app/conflict.rs\0?? app/notes.md\0";
+        let files = parse_status(output, "app/");
+        let summary: Vec<(&str, FileChangeStatus, bool)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status, f.staged))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/main.rs", FileChangeStatus::Modified, false),
+                ("new.rs", FileChangeStatus::Added, true),
+                ("b.rs", FileChangeStatus::Renamed, true),
+                ("gone.rs", FileChangeStatus::Deleted, false),
+                ("conflict.rs", FileChangeStatus::Conflicted, true),
+                ("notes.md", FileChangeStatus::Untracked, false),
+            ]
+        );
+        assert_eq!(files[2].original_path.as_deref(), Some("a.rs"));
+    }
+}
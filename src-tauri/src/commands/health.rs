@@ -0,0 +1,352 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::file_history::{files_edited_under, sync_file_history};
+use super::forecast::forecast_projects;
+use super::git::git;
+use super::settings as store;
+use crate::format::Formatter;
+
+/// Commits since CLAUDE.md last changed before it is considered stale
+const CLAUDE_MD_STALE_COMMITS: u32 = 20;
+const CLAUDE_MD_VERY_STALE_COMMITS: u32 = 50;
+
+/// Share of the monthly budget spent before it is flagged
+const BUDGET_WARNING_RATIO: f64 = 0.8;
+/// Days of spend history the budget forecast is fitted to
+const FORECAST_RANGE_DAYS: u32 = 14;
+
+/// Settings used when scoring project health
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthSettings {
+    /// Spend per project per calendar month before the budget signal fails
+    pub monthly_budget_usd: Option<f64>,
+}
+
+/// One contributor to the health score, with the reasons behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSignal {
+    pub name: String,
+    /// Points taken off the score of 100
+    pub penalty: u32,
+    pub reasons: Vec<String>,
+}
+
+/// Health of a project, from 0 (unhealthy) to 100
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectHealth {
+    pub project_path: String,
+    pub score: u32,
+    /// "healthy", "needs_attention" or "unhealthy"
+    pub status: String,
+    pub signals: Vec<HealthSignal>,
+}
+
+impl HealthSignal {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            penalty: 0,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, penalty: u32, reason: String) {
+        self.penalty += penalty;
+        self.reasons.push(reason);
+    }
+
+    fn capped(mut self, max: u32) -> Self {
+        self.penalty = self.penalty.min(max);
+        self
+    }
+}
+
+/// Combine signals into a score and status
+pub fn score_health(project_path: String, signals: Vec<HealthSignal>) -> ProjectHealth {
+    let penalty: u32 = signals.iter().map(|s| s.penalty).sum();
+    let score = 100u32.saturating_sub(penalty);
+    let status = match score {
+        80..=100 => "healthy",
+        50..=79 => "needs_attention",
+        _ => "unhealthy",
+    };
+    ProjectHealth {
+        project_path,
+        score,
+        status: status.to_string(),
+        signals,
+    }
+}
+
+/// Load the health settings from app_settings
+pub fn load_health_settings(conn: &Connection) -> HealthSettings {
+    HealthSettings {
+        monthly_budget_usd: store::get_number(conn, "project_monthly_budget_usd")
+            .filter(|v| *v > 0.0),
+    }
+}
+
+/// Check that the project's Claude config files parse
+fn check_config_files(project: &Path) -> HealthSignal {
+    let mut signal = HealthSignal::new("validators");
+
+    for file in [
+        ".claude/settings.json",
+        ".claude/settings.local.json",
+        ".mcp.json",
+    ] {
+        let path = project.join(file);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        match serde_json::from_str::<JsonValue>(&content) {
+            Ok(json) if file == ".mcp.json" && !json["mcpServers"].is_object() => {
+                signal.add(15, format!("{} has no \"mcpServers\" object", file));
+            }
+            Ok(_) => {}
+            Err(e) => signal.add(15, format!("{} is not valid JSON: {}", file, e)),
+        }
+    }
+
+    if !project.join("CLAUDE.md").exists() {
+        signal.add(5, "CLAUDE.md is missing".to_string());
+    }
+
+    signal.capped(30)
+}
+
+/// Compare when CLAUDE.md last changed with how much the repo has moved since
+async fn check_claude_md_staleness(project: &Path) -> HealthSignal {
+    let mut signal = HealthSignal::new("claude_md_staleness");
+    if !project.join("CLAUDE.md").exists() {
+        return signal;
+    }
+
+    let last_commit = match git(project, &["log", "-1", "--format=%H", "--", "CLAUDE.md"]).await {
+        Ok(commit) if !commit.trim().is_empty() => commit.trim().to_string(),
+        Ok(_) => {
+            signal.add(5, "CLAUDE.md has never been committed".to_string());
+            return signal;
+        }
+        Err(_) => return signal,
+    };
+
+    let range = format!("{}..HEAD", last_commit);
+    let commits: u32 = git(project, &["rev-list", "--count", &range, "--", "."])
+        .await
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .unwrap_or(0);
+
+    if commits >= CLAUDE_MD_VERY_STALE_COMMITS {
+        signal.add(
+            15,
+            format!("{} commits since CLAUDE.md was last updated", commits),
+        );
+    } else if commits >= CLAUDE_MD_STALE_COMMITS {
+        signal.add(
+            8,
+            format!("{} commits since CLAUDE.md was last updated", commits),
+        );
+    }
+    signal
+}
+
+/// Files changed in the working tree that a session edited and nobody committed yet
+async fn check_unreviewed_changes(project: &Path, agent_files: &[String]) -> HealthSignal {
+    let mut signal = HealthSignal::new("unreviewed_agent_changes");
+    if agent_files.is_empty() {
+        return signal;
+    }
+
+    let (Ok(root), Ok(status)) = (
+        git(project, &["rev-parse", "--show-toplevel"]).await,
+        git(project, &["status", "--porcelain"]).await,
+    ) else {
+        return signal;
+    };
+    let root = PathBuf::from(root.trim_end());
+
+    let changed: HashSet<PathBuf> = status
+        .lines()
+        .filter_map(|line| line.get(3..))
+        .map(|path| path.rsplit(" -> ").next().unwrap_or(path))
+        .map(|path| root.join(path.trim_matches('"')))
+        .collect();
+
+    let mut unreviewed: Vec<&String> = agent_files
+        .iter()
+        .filter(|file| {
+            let path = Path::new(file.as_str());
+            changed.contains(path)
+                || path
+                    .canonicalize()
+                    .map(|p| changed.contains(&p))
+                    .unwrap_or(false)
+        })
+        .collect();
+    unreviewed.sort();
+
+    for file in unreviewed {
+        signal.add(2, format!("Uncommitted agent edit: {}", file));
+    }
+    signal.capped(20)
+}
+
+/// Spend on the project this month against the configured budget
+fn check_budget(conn: &Connection, project_path: &str, settings: &HealthSettings) -> HealthSignal {
+    let mut signal = HealthSignal::new("budget");
+    let budget = match settings.monthly_budget_usd {
+        Some(budget) => budget,
+        None => return signal,
+    };
+
+    let spent: f64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(cost_usd), 0.0) FROM run_history
+             WHERE project_path = ?1 AND started_at >= datetime('now', 'start of month')",
+            params![project_path],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.0);
+
+    let fmt = Formatter::from_settings(conn);
+    let reason = format!(
+        "Spent {} of the {} monthly budget",
+        fmt.cost(spent),
+        fmt.cost(budget)
+    );
+    if spent >= budget {
+        signal.add(20, reason);
+        return signal;
+    } else if spent >= budget * BUDGET_WARNING_RATIO {
+        signal.add(8, reason);
+    }
+
+    // Warn while there is still time to act, not once the budget is gone
+    let forecast = forecast_projects(conn, Some(project_path), FORECAST_RANGE_DAYS)
+        .ok()
+        .and_then(|f| f.into_iter().next());
+    if let Some(forecast) = forecast.filter(|f| f.projected_over_budget) {
+        signal.add(
+            5,
+            format!(
+                "On track to spend {} ({}-{}) by month end",
+                fmt.cost(forecast.projected_month_end_usd),
+                fmt.cost(forecast.lower_bound_usd),
+                fmt.cost(forecast.upper_bound_usd)
+            ),
+        );
+    }
+    signal.capped(20)
+}
+
+/// MCP servers in .mcp.json whose command can't be found
+fn check_mcp_servers(project: &Path) -> HealthSignal {
+    let mut signal = HealthSignal::new("mcp_failures");
+    let config = std::fs::read_to_string(project.join(".mcp.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str::<JsonValue>(&c).ok());
+    let servers = match config.as_ref().and_then(|c| c["mcpServers"].as_object()) {
+        Some(servers) => servers,
+        None => return signal,
+    };
+
+    for (name, server) in servers {
+        let command = match server["command"].as_str() {
+            Some(command) => command,
+            // Remote servers have a URL instead of a command
+            None => continue,
+        };
+        let found = if Path::new(command).is_absolute() {
+            Path::new(command).exists()
+        } else {
+            which::which(command).is_ok()
+        };
+        if !found {
+            signal.add(
+                10,
+                format!("MCP server '{}': command '{}' not found", name, command),
+            );
+        }
+    }
+    signal.capped(20)
+}
+
+/// Score a project's health and explain what drags it down
+#[tauri::command]
+pub async fn get_project_health(
+    db: State<'_, AgentDb>,
+    project_path: String,
+) -> Result<ProjectHealth, String> {
+    let project = PathBuf::from(&project_path);
+    if !project.is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+
+    sync_file_history(&db)?;
+    let (agent_files, budget) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let agent_files = files_edited_under(&conn, &project_path).map_err(|e| e.to_string())?;
+        let budget = check_budget(&conn, &project_path, &load_health_settings(&conn));
+        (agent_files, budget)
+    };
+
+    let signals = vec![
+        check_config_files(&project),
+        check_claude_md_staleness(&project).await,
+        check_unreviewed_changes(&project, &agent_files).await,
+        budget,
+        check_mcp_servers(&project),
+    ];
+
+    Ok(score_health(project_path, signals))
+}
+
+/// Get the settings used for project health scoring
+#[tauri::command]
+pub async fn get_health_settings(db: State<'_, AgentDb>) -> Result<HealthSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_health_settings(&conn))
+}
+
+/// Save the settings used for project health scoring
+#[tauri::command]
+pub async fn save_health_settings(
+    db: State<'_, AgentDb>,
+    settings: HealthSettings,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let budget = settings.monthly_budget_usd.filter(|b| *b > 0.0);
+    store::set_value(&conn, "project_monthly_budget_usd", &budget.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_health() {
+        let mut budget = HealthSignal::new("budget");
+        budget.add(20, "over budget".to_string());
+        let mut mcp = HealthSignal::new("mcp_failures");
+        mcp.add(10, "a".to_string());
+        mcp.add(10, "b".to_string());
+        mcp.add(10, "c".to_string());
+
+        let health = score_health("/p".to_string(), vec![budget, mcp.capped(20)]);
+        assert_eq!(health.score, 60);
+        assert_eq!(health.status, "needs_attention");
+        assert_eq!(health.signals[1].reasons.len(), 3);
+
+        let healthy = score_health("/p".to_string(), vec![HealthSignal::new("validators")]);
+        assert_eq!((healthy.score, healthy.status.as_str()), (100, "healthy"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::str::FromStr;
+use tauri::State;
+use tracing::Level;
+
+use super::agents::AgentDb;
+use super::settings;
+use crate::logging::{self, LogEntry};
+
+/// Most entries `get_recent_logs` returns
+const MAX_ENTRIES: usize = 5000;
+
+/// Get the most recent log entries at `level` or more severe, oldest first
+///
+/// `level` defaults to "info" and `limit` to 500.
+#[tauri::command]
+pub async fn get_recent_logs(
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = match level.as_deref() {
+        Some(level) if logging::LEVELS.contains(&level) => {
+            Level::from_str(level).map_err(|e| e.to_string())?
+        }
+        Some(level) => return Err(format!("Invalid log level: {}", level)),
+        None => Level::INFO,
+    };
+    let limit = limit.unwrap_or(500).clamp(1, MAX_ENTRIES);
+    tauri::async_runtime::spawn_blocking(move || logging::recent_entries(level, limit))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Change the log level now and keep it for future launches
+#[tauri::command]
+pub async fn set_log_level(db: State<'_, AgentDb>, level: String) -> Result<(), String> {
+    logging::set_level(&level)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::set_value(&conn, "log_level", &serde_json::json!(level))
+}
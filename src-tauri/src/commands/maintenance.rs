@@ -0,0 +1,32 @@
+use tauri::{AppHandle, State};
+
+use crate::db_health::{self, RecoveryReport};
+use crate::maintenance::{self, MaintenanceState, MaintenanceStatus};
+
+/// Get the state of idle-time maintenance and the outcome of each task
+#[tauri::command]
+pub async fn get_maintenance_status(
+    app: AppHandle,
+    state: State<'_, MaintenanceState>,
+) -> Result<MaintenanceStatus, String> {
+    let blocked_by = maintenance::idle_blocker(&app).await;
+    Ok(state.status(blocked_by))
+}
+
+/// Run maintenance now, regardless of idleness
+///
+/// Runs the named tasks, or all of them when `tasks` is omitted. Returns `false`
+/// if a run was already in progress.
+#[tauri::command]
+pub async fn run_maintenance_now(
+    app: AppHandle,
+    tasks: Option<Vec<String>>,
+) -> Result<bool, String> {
+    maintenance::run_now(&app, tasks).await
+}
+
+/// What was restored if the database was corrupt at launch; `None` otherwise
+#[tauri::command]
+pub fn get_database_recovery() -> Option<RecoveryReport> {
+    db_health::recovery_report()
+}
@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+use crate::schema_drift::{self, DriftReport, DriftStatus};
+
+/// Whether the GitHub-backed catalogs (agents, skills, MCP servers) can be trusted
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketplaceStatus {
+    /// The worst status of any source; `degraded` means the marketplace may be incomplete
+    pub status: DriftStatus,
+    /// One report per catalog fetched since the app started
+    pub sources: Vec<DriftReport>,
+}
+
+/// Get how the latest GitHub catalog responses compared to the expected schema
+#[tauri::command]
+pub async fn get_marketplace_status() -> Result<MarketplaceStatus, String> {
+    let sources = schema_drift::latest_reports();
+    Ok(MarketplaceStatus {
+        status: sources
+            .iter()
+            .map(|s| s.status)
+            .max()
+            .unwrap_or(DriftStatus::Ok),
+        sources,
+    })
+}
@@ -6,8 +6,12 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
 use tauri::AppHandle;
 
+use super::operations::ProgressReporter;
+use crate::file_audit::{self, FileOperation};
+
 /// Helper function to create a std::process::Command with proper environment variables
 /// This ensures commands like Claude can find Node.js and other dependencies
 fn create_command_with_env(program: &str) -> Command {
@@ -54,6 +58,33 @@ pub struct ServerStatus {
     pub last_checked: Option<u64>,
 }
 
+/// Statuses from the last `claude mcp list`, by server name
+fn last_statuses() -> &'static Mutex<HashMap<String, ServerStatus>> {
+    static STATUSES: OnceLock<Mutex<HashMap<String, ServerStatus>>> = OnceLock::new();
+    STATUSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The health check `claude mcp list` appends to a server's line, e.g.
+/// `npx server - ✓ Connected` or `npx server - ✗ Failed to connect`
+fn parse_health(command: &str, checked_at: u64) -> ServerStatus {
+    let check = command
+        .rsplit_once(" - ")
+        .map(|(_, check)| check.trim())
+        .unwrap_or_default();
+    let (running, error) = if check.starts_with('✓') {
+        (true, None)
+    } else if let Some(message) = check.strip_prefix('✗') {
+        (false, Some(message.trim().to_string()))
+    } else {
+        (false, None)
+    };
+    ServerStatus {
+        running,
+        error,
+        last_checked: (running || error.is_some()).then_some(checked_at),
+    }
+}
+
 /// MCP configuration for project scope (.mcp.json)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPProjectConfig {
@@ -233,6 +264,10 @@ pub async fn mcp_list(app: AppHandle) -> Result<Vec<MCPServer>, String> {
                 info!("Line {}: {:?}", idx, line);
             }
 
+            let checked_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
             let mut i = 0;
 
             while i < lines.len() {
@@ -286,6 +321,7 @@ pub async fn mcp_list(app: AppHandle) -> Result<Vec<MCPServer>, String> {
                         info!("Full command for server '{}': {:?}", name, full_command);
 
                         // For now, we'll create a basic server entry
+                        let status = parse_health(&full_command, checked_at);
                         servers.push(MCPServer {
                             name: name.clone(),
                             transport: "stdio".to_string(), // Default assumption
@@ -295,11 +331,7 @@ pub async fn mcp_list(app: AppHandle) -> Result<Vec<MCPServer>, String> {
                             url: None,
                             scope: "local".to_string(), // Default assumption
                             is_active: false,
-                            status: ServerStatus {
-                                running: false,
-                                error: None,
-                                last_checked: None,
-                            },
+                            status,
                         });
                         info!("Added server: {:?}", name);
 
@@ -315,6 +347,12 @@ pub async fn mcp_list(app: AppHandle) -> Result<Vec<MCPServer>, String> {
             }
 
             info!("Found {} MCP servers total", servers.len());
+            if let Ok(mut statuses) = last_statuses().lock() {
+                *statuses = servers
+                    .iter()
+                    .map(|server| (server.name.clone(), server.status.clone()))
+                    .collect();
+            }
             for (idx, server) in servers.iter().enumerate() {
                 info!(
                     "Server {}: name='{}', command={:?}",
@@ -462,6 +500,37 @@ pub async fn mcp_add_json(
 pub async fn mcp_add_from_claude_desktop(
     app: AppHandle,
     scope: String,
+) -> Result<ImportResult, String> {
+    let progress = ProgressReporter::start(&app, "mcp_import");
+    let result = import_from_claude_desktop(&app, scope, &progress).await;
+    progress.finish(&result);
+    result
+}
+
+/// Imports MCP servers from Claude Desktop in the background
+///
+/// Returns the operation ID; progress and the import result are reported
+/// through `operation-progress:{id}` and `operation-complete:{id}`.
+#[tauri::command]
+pub async fn start_mcp_import_from_claude_desktop(
+    app: AppHandle,
+    scope: String,
+) -> Result<String, String> {
+    let progress = ProgressReporter::start(&app, "mcp_import");
+    let operation_id = progress.operation_id().to_string();
+
+    tauri::async_runtime::spawn(async move {
+        let result = import_from_claude_desktop(&app, scope, &progress).await;
+        progress.finish(&result);
+    });
+
+    Ok(operation_id)
+}
+
+async fn import_from_claude_desktop(
+    app: &AppHandle,
+    scope: String,
+    progress: &ProgressReporter,
 ) -> Result<ImportResult, String> {
     info!(
         "Importing MCP servers from Claude Desktop with scope: {}",
@@ -514,7 +583,17 @@ pub async fn mcp_add_from_claude_desktop(
     let mut server_results = Vec::new();
 
     // Import each server using add-json
-    for (name, server_config) in mcp_servers {
+    let total = mcp_servers.len().max(1);
+    for (index, (name, server_config)) in mcp_servers.iter().enumerate() {
+        if progress.is_cancelled() {
+            info!("Import cancelled after {} of {} servers", index, total);
+            break;
+        }
+        progress.report(
+            "importing",
+            Some((index * 100 / total) as u8),
+            Some(name.as_str()),
+        );
         info!("Importing server: {}", name);
 
         // Convert Claude Desktop format to add-json format
@@ -602,6 +681,7 @@ pub async fn mcp_add_from_claude_desktop(
         "Import complete: {} imported, {} failed",
         imported_count, failed_count
     );
+    progress.report("done", Some(100), None);
 
     Ok(ImportResult {
         imported_count,
@@ -668,14 +748,15 @@ pub async fn mcp_reset_project_choices(app: AppHandle) -> Result<String, String>
     }
 }
 
-/// Gets the status of MCP servers
+/// Gets the status of MCP servers from the last health check
+///
+/// `mcp_list` runs the check; this returns what it found without starting the
+/// servers again.
 #[tauri::command]
 pub async fn mcp_get_server_status() -> Result<HashMap<String, ServerStatus>, String> {
     info!("Getting MCP server status");
-
-    // TODO: Implement actual status checking
-    // For now, return empty status
-    Ok(HashMap::new())
+    let statuses = last_statuses().lock().map_err(|e| e.to_string())?;
+    Ok(statuses.clone())
 }
 
 /// Reads .mcp.json from the current project
@@ -719,8 +800,10 @@ pub async fn mcp_save_project_config(
     let json_content = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
+    let operation = FileOperation::for_write(&mcp_json_path);
     fs::write(&mcp_json_path, json_content)
         .map_err(|e| format!("Failed to write .mcp.json: {}", e))?;
+    file_audit::record("mcp_save_project_config", operation, &mcp_json_path);
 
     Ok("Project MCP configuration saved".to_string())
 }
@@ -764,3 +847,130 @@ pub async fn plugin_uninstall(app: AppHandle, name: String) -> Result<String, St
         .map(|output| output.trim().to_string())
         .map_err(|e| e.to_string())
 }
+
+/// How long to wait for a server to list its tools
+const TOOLS_LIST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Send a JSON-RPC message to a stdio server
+async fn send_rpc(
+    stdin: &mut tokio::process::ChildStdin,
+    message: serde_json::Value,
+) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+    let line = format!("{}\n", message);
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+/// Read messages until the response to request `id`, skipping notifications and logs
+async fn read_response(
+    lines: &mut tokio::io::Lines<tokio::io::BufReader<tokio::process::ChildStdout>>,
+    id: u64,
+) -> Result<serde_json::Value, String> {
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read from server: {}", e))?
+    {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if message["id"].as_u64() != Some(id) {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            return Err(format!(
+                "Server error: {}",
+                error["message"].as_str().unwrap_or("unknown")
+            ));
+        }
+        return Ok(message["result"].clone());
+    }
+    Err("Server exited before responding".to_string())
+}
+
+/// Start a stdio MCP server from its config and ask it for its tools
+///
+/// Returns the tool definitions (name, description and input schema) as the
+/// server sends them. Remote servers aren't supported.
+pub(crate) async fn list_server_tools(
+    config: &serde_json::Value,
+) -> Result<Vec<serde_json::Value>, String> {
+    use tokio::io::AsyncBufReadExt;
+
+    let command = config["command"]
+        .as_str()
+        .ok_or("Only stdio servers can be probed")?;
+    let mut cmd = tokio::process::Command::from(create_command_with_env(command));
+    if let Some(args) = config["args"].as_array() {
+        cmd.args(args.iter().filter_map(|a| a.as_str()));
+    }
+    if let Some(env) = config["env"].as_object() {
+        for (key, value) in env {
+            if let Some(value) = value.as_str() {
+                cmd.env(key, value);
+            }
+        }
+    }
+    cmd.stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start '{}': {}", command, e))?;
+    let mut stdin = child.stdin.take().ok_or("Failed to open server stdin")?;
+    let stdout = child.stdout.take().ok_or("Failed to open server stdout")?;
+    let mut lines = tokio::io::BufReader::new(stdout).lines();
+
+    let exchange = async {
+        send_rpc(
+            &mut stdin,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "clientInfo": { "name": "opcode", "version": env!("CARGO_PKG_VERSION") }
+                }
+            }),
+        )
+        .await?;
+        read_response(&mut lines, 1).await?;
+        send_rpc(
+            &mut stdin,
+            serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        )
+        .await?;
+
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        for id in 2u64.. {
+            let params = match &cursor {
+                Some(cursor) => serde_json::json!({ "cursor": cursor }),
+                None => serde_json::json!({}),
+            };
+            send_rpc(
+                &mut stdin,
+                serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": "tools/list", "params": params }),
+            )
+            .await?;
+            let result = read_response(&mut lines, id).await?;
+            tools.extend(result["tools"].as_array().cloned().unwrap_or_default());
+            cursor = result["nextCursor"].as_str().map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok::<_, String>(tools)
+    };
+    let tools = tokio::time::timeout(TOOLS_LIST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| "Timed out waiting for the server's tools".to_string())?;
+    let _ = child.kill().await;
+    tools
+}
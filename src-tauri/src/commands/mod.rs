@@ -1,10 +1,71 @@
+pub mod agent_race;
 pub mod agents;
+pub mod app_config;
+pub mod benchmarks;
+pub mod billing;
+pub mod blame;
+pub mod bookmarks;
+pub mod capabilities;
+pub mod catalog;
 pub mod claude;
+pub mod claude_dir;
+pub mod claude_dir_import;
+pub mod claude_md;
+pub mod claude_watcher;
+pub mod cli_migration;
+pub mod config_graph;
+pub mod control_server;
+pub mod deep_link;
+pub mod doctor;
+pub mod error_reports;
+pub mod event_bus;
+pub mod file_audit;
+pub mod file_history;
+pub mod fixtures;
+pub mod forecast;
+pub mod format;
+pub mod git;
+pub mod health;
+pub mod logging;
+pub mod maintenance;
+pub mod marketplace;
 pub mod mcp;
+pub mod notifications;
+pub mod onboarding;
+pub mod operations;
+pub mod permissions;
+pub mod popularity;
+pub mod prewarm;
+pub mod pricing;
+pub mod project_env;
+pub mod project_locks;
 pub mod project_manager;
+pub mod project_profiles;
+pub mod project_registry;
+pub mod prompt_history;
 pub mod proxy;
+pub mod redaction;
+pub mod references;
+pub mod replay;
+pub mod run_history;
+pub mod run_manifest;
+pub mod run_queue;
+pub mod safe_mode;
+pub mod sandbox;
+pub mod schedules;
+pub mod session_export;
+pub mod session_fork;
+pub mod settings;
+pub mod skill_usage;
 pub mod slash_commands;
 pub mod storage;
+pub mod storage_usage;
+pub mod streaming;
+pub mod telemetry;
+pub mod tokens;
+pub mod updater;
 pub mod usage;
+pub mod window_sessions;
+pub mod worktrees;
 pub mod models;
 pub mod skills;
@@ -1,10 +1,25 @@
 use anyhow::Result;
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use std::env;
-use tauri::command;
+use tauri::{command, State};
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::agents::AgentDb;
+use super::settings;
+use crate::network::{send_with_retry, NetworkError};
+
+/// app_settings key holding the last model list fetched from the API
+const CACHE_KEY: &str = "anthropic_models_cache";
+
+/// Models asked for per page, the most the API returns
+const PAGE_SIZE: u32 = 1000;
+/// Pages fetched before giving up on the rest
+const MAX_PAGES: usize = 10;
+/// How long a fetched list is served without asking the API again
+const FRESH_FOR_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfo {
     pub id: String,
     pub display_name: String,
@@ -13,43 +28,277 @@ pub struct ModelInfo {
     pub model_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Where a model list came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelSource {
+    /// The Models API, just now
+    #[default]
+    Live,
+    /// The last list fetched from the API
+    Cached,
+    /// The table shipped with the app
+    Bundled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelsResponse {
     pub data: Vec<ModelInfo>,
     pub has_more: bool,
     pub first_id: Option<String>,
     pub last_id: Option<String>,
+    #[serde(default)]
+    pub source: ModelSource,
+    /// When a cached list was fetched
+    #[serde(default)]
+    pub fetched_at: Option<String>,
+    /// Why the live list couldn't be used
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
-#[command]
-pub async fn list_anthropic_models(api_key: Option<String>) -> Result<ModelsResponse, String> {
-    let key = if let Some(k) = api_key {
-        k
-    } else {
-        // Try env vars
-        env::var("ANTHROPIC_API_KEY")
-            .or_else(|_| env::var("CLAUDE_API_KEY"))
-            .map_err(|_| "No API key provided and none found in environment variables".to_string())?
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedModels {
+    fetched_at: String,
+    data: Vec<ModelInfo>,
+}
+
+/// Models known when this version was released, for when the API can't be reached
+fn bundled_models() -> Vec<ModelInfo> {
+    let model = |id: &str, display_name: &str, created_at: &str| ModelInfo {
+        id: id.to_string(),
+        display_name: display_name.to_string(),
+        created_at: created_at.to_string(),
+        model_type: "model".to_string(),
     };
+    vec![
+        model(
+            "claude-opus-4-5-20251101",
+            "Claude Opus 4.5",
+            "2025-11-24T00:00:00Z",
+        ),
+        model(
+            "claude-haiku-4-5-20251001",
+            "Claude Haiku 4.5",
+            "2025-10-15T00:00:00Z",
+        ),
+        model(
+            "claude-sonnet-4-5-20250929",
+            "Claude Sonnet 4.5",
+            "2025-09-29T00:00:00Z",
+        ),
+        model(
+            "claude-opus-4-1-20250805",
+            "Claude Opus 4.1",
+            "2025-08-05T00:00:00Z",
+        ),
+        model(
+            "claude-opus-4-20250514",
+            "Claude Opus 4",
+            "2025-05-22T00:00:00Z",
+        ),
+        model(
+            "claude-sonnet-4-20250514",
+            "Claude Sonnet 4",
+            "2025-05-22T00:00:00Z",
+        ),
+    ]
+}
+
+/// API key from the configured environment variable, falling back to CLAUDE_API_KEY
+pub fn configured_api_key(conn: &rusqlite::Connection) -> Option<String> {
+    let key_env = settings::get_text(conn, "anthropic_api_key_env")
+        .unwrap_or_else(|| "ANTHROPIC_API_KEY".to_string());
+    env::var(&key_env)
+        .or_else(|_| env::var("CLAUDE_API_KEY"))
+        .ok()
+        .filter(|k| !k.is_empty())
+}
+
+/// Admin API key from the configured environment variable
+///
+/// The organization Usage and Cost APIs only accept admin keys, which are
+/// separate from the key used to run Claude.
+pub fn configured_admin_key(conn: &rusqlite::Connection) -> Option<String> {
+    let key_env = settings::get_text(conn, "anthropic_admin_key_env")
+        .unwrap_or_else(|| "ANTHROPIC_ADMIN_KEY".to_string());
+    env::var(&key_env).ok().filter(|k| !k.is_empty())
+}
 
-    let client = reqwest::Client::new();
+/// Every page of the Models API
+async fn fetch_models(key: &str) -> Result<ModelsResponse, NetworkError> {
+    let client = crate::http_client::client()?;
     let mut headers = HeaderMap::new();
-    headers.insert("x-api-key", HeaderValue::from_str(&key).map_err(|e| e.to_string())?);
+    headers.insert("x-api-key", HeaderValue::from_str(key).map_err(|e| e.to_string())?);
     headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
-    let res = client
-        .get("https://api.anthropic.com/v1/models")
-        .headers(headers)
-        .send()
+    let mut models: Option<ModelsResponse> = None;
+    for _ in 0..MAX_PAGES {
+        let after_id = models.as_ref().and_then(|m| m.last_id.clone());
+        let res = send_with_retry(|| {
+            let mut request = client
+                .get("https://api.anthropic.com/v1/models")
+                .headers(headers.clone())
+                .query(&[("limit", PAGE_SIZE.to_string())]);
+            if let Some(after_id) = &after_id {
+                request = request.query(&[("after_id", after_id)]);
+            }
+            request
+        })
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.context("API request failed"))?;
+        let page = res.json::<ModelsResponse>().await?;
 
-    if !res.status().is_success() {
-        let error_text = res.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API request failed: {}", error_text));
+        let models = match &mut models {
+            Some(models) => {
+                models.data.extend(page.data);
+                models.has_more = page.has_more;
+                models.last_id = page.last_id;
+                models
+            }
+            None => models.insert(page),
+        };
+        if !models.has_more || models.last_id.is_none() {
+            break;
+        }
     }
+    models.ok_or_else(|| "The Models API returned nothing".to_string().into())
+}
+
+fn load_cached_models(conn: &Connection) -> Option<CachedModels> {
+    settings::get_value(conn, CACHE_KEY)
+        .ok()
+        .and_then(|cached| serde_json::from_value::<CachedModels>(cached).ok())
+        .filter(|cached| !cached.data.is_empty())
+}
 
-    let body = res.json::<ModelsResponse>().await.map_err(|e| e.to_string())?;
-    Ok(body)
+fn cache_models(conn: &Connection, models: &ModelsResponse) {
+    let cached = CachedModels {
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+        data: models.data.clone(),
+    };
+    let stored = serde_json::to_value(&cached)
+        .map_err(|e| e.to_string())
+        .and_then(|cached| settings::set_value(conn, CACHE_KEY, &cached));
+    if let Err(e) = stored {
+        log::warn!("Failed to cache the model list: {}", e);
+    }
+}
+
+/// The cached list, if it was fetched within the last hour
+fn fresh_cached_models(conn: &Connection) -> Option<ModelsResponse> {
+    let cached = load_cached_models(conn)?;
+    let fetched = chrono::DateTime::parse_from_rfc3339(&cached.fetched_at).ok()?;
+    if chrono::Utc::now().signed_duration_since(fetched)
+        > chrono::Duration::minutes(FRESH_FOR_MINUTES)
+    {
+        return None;
+    }
+    Some(ModelsResponse {
+        first_id: cached.data.first().map(|m| m.id.clone()),
+        last_id: cached.data.last().map(|m| m.id.clone()),
+        has_more: false,
+        data: cached.data,
+        source: ModelSource::Cached,
+        fetched_at: Some(cached.fetched_at),
+        error: None,
+    })
+}
+
+/// The last fetched list, or the bundled one, with why the live list failed
+fn fallback_models(conn: &Connection, error: String) -> ModelsResponse {
+    let (data, source, fetched_at) = match load_cached_models(conn) {
+        Some(cached) => (cached.data, ModelSource::Cached, Some(cached.fetched_at)),
+        None => (bundled_models(), ModelSource::Bundled, None),
+    };
+    ModelsResponse {
+        first_id: data.first().map(|m| m.id.clone()),
+        last_id: data.last().map(|m| m.id.clone()),
+        has_more: false,
+        data,
+        source,
+        fetched_at,
+        error: Some(error),
+    }
+}
+
+/// List the available models, following every page of the Models API
+///
+/// When there is no API key or the request fails, the last list fetched is
+/// returned instead, and the list bundled with the app when nothing was ever
+/// fetched. `source` says which one it is and `error` why. A list fetched
+/// within the last hour is served from the cache unless `refresh` is set or
+/// an API key is given.
+#[command]
+pub async fn list_anthropic_models(
+    db: State<'_, AgentDb>,
+    api_key: Option<String>,
+    refresh: Option<bool>,
+) -> Result<ModelsResponse, NetworkError> {
+    let key = match api_key {
+        Some(k) => Some(k),
+        None => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            if !refresh.unwrap_or(false) {
+                if let Some(cached) = fresh_cached_models(&conn) {
+                    return Ok(cached);
+                }
+            }
+            configured_api_key(&conn)
+        }
+    };
+    let live = match key {
+        Some(key) => fetch_models(&key).await,
+        None => Err(NetworkError::Auth {
+            message: "No API key provided and none found in environment variables".to_string(),
+        }),
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    match live {
+        Ok(models) => {
+            cache_models(&conn, &models);
+            Ok(models)
+        }
+        Err(e) => {
+            log::warn!("Using a fallback model list: {}", e);
+            Ok(fallback_models(&conn, e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_prefers_cache() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+
+        let bundled = fallback_models(&conn, "offline".to_string());
+        assert_eq!(bundled.source, ModelSource::Bundled);
+        assert!(!bundled.data.is_empty());
+
+        let live = ModelsResponse {
+            data: vec![bundled.data[0].clone()],
+            has_more: false,
+            first_id: None,
+            last_id: None,
+            source: ModelSource::Live,
+            fetched_at: None,
+            error: None,
+        };
+        cache_models(&conn, &live);
+        let cached = fallback_models(&conn, "401".to_string());
+        assert_eq!(cached.source, ModelSource::Cached);
+        assert_eq!(cached.data.len(), 1);
+        assert!(cached.fetched_at.is_some());
+        assert_eq!(cached.error.as_deref(), Some("401"));
+    }
 }
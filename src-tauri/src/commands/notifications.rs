@@ -0,0 +1,35 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::settings as store;
+use crate::notifications::{load_notification_settings, NotificationSettings};
+
+/// Get which run events show a desktop notification
+#[tauri::command]
+pub async fn get_notification_settings(
+    db: State<'_, AgentDb>,
+) -> Result<NotificationSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_notification_settings(&conn))
+}
+
+/// Save which run events show a desktop notification
+#[tauri::command]
+pub async fn save_notification_settings(
+    db: State<'_, AgentDb>,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store::set_value(
+        &conn,
+        "notify_run_completed",
+        &settings.run_completed.into(),
+    )?;
+    store::set_value(&conn, "notify_run_failed", &settings.run_failed.into())?;
+    store::set_value(
+        &conn,
+        "notify_permission_request",
+        &settings.permission_request.into(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use super::models::configured_api_key;
+use super::settings as store;
+use crate::claude_binary::find_claude_binary;
+
+/// Setup steps in the order the guided setup walks through them, with titles
+pub const ONBOARDING_STEPS: &[(&str, &str)] = &[
+    ("claude_cli", "Install Claude Code"),
+    ("credentials", "Sign in or add an API key"),
+    ("claude_dir", "Initialize the Claude directory"),
+    ("first_project", "Add a project"),
+];
+
+/// One setup step and whether it's done
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStep {
+    pub id: String,
+    pub title: String,
+    /// The app found the step's requirement on this machine
+    pub detected: bool,
+    /// Detected, or marked complete by the user
+    pub completed: bool,
+    pub detail: String,
+    /// What to do while the step isn't complete
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStatus {
+    pub steps: Vec<OnboardingStep>,
+    /// The first step that isn't complete; `None` once setup is done
+    pub current_step: Option<String>,
+    pub complete: bool,
+}
+
+/// What was found for one step: (detected, detail, hint when missing)
+type Detection = (bool, String, &'static str);
+
+/// Claude Code signs in with OAuth instead of an API key; the login is kept in
+/// `.credentials.json` or, on macOS, the keychain with the account in `~/.claude.json`
+fn has_oauth_login(claude_dir: &Path) -> bool {
+    if claude_dir.join(".credentials.json").is_file() {
+        return true;
+    }
+    dirs::home_dir()
+        .and_then(|home| std::fs::read_to_string(home.join(".claude.json")).ok())
+        .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok())
+        .is_some_and(|config| config["oauthAccount"].is_object())
+}
+
+fn detect(app: &AppHandle, has_api_key: bool) -> Vec<Detection> {
+    let claude_dir = crate::utils::resolve_claude_dir().map(|(path, _)| path);
+    let claude_dir_exists = claude_dir.as_ref().is_ok_and(|dir| dir.is_dir());
+
+    let cli = match find_claude_binary(app) {
+        Ok(path) => (true, format!("Found {}", path), ""),
+        Err(e) => (
+            false,
+            e,
+            "Install Claude Code with `npm install -g @anthropic-ai/claude-code`",
+        ),
+    };
+    let oauth = claude_dir.as_deref().is_ok_and(has_oauth_login);
+    let credentials = if has_api_key {
+        (true, "An API key is configured".to_string(), "")
+    } else if oauth {
+        (true, "Signed in to Claude".to_string(), "")
+    } else {
+        (
+            false,
+            "No API key or Claude login found".to_string(),
+            "Run `claude` and sign in, or set ANTHROPIC_API_KEY",
+        )
+    };
+    let dir = match &claude_dir {
+        Ok(dir) if claude_dir_exists => (true, dir.display().to_string(), ""),
+        Ok(dir) => (
+            false,
+            format!("{} does not exist yet", dir.display()),
+            "Run Claude Code once to create it, or choose another directory in Settings",
+        ),
+        Err(e) => (false, e.clone(), "Choose a Claude directory in Settings"),
+    };
+    let projects = claude_dir
+        .as_ref()
+        .ok()
+        .and_then(|dir| std::fs::read_dir(dir.join("projects")).ok())
+        .map(|entries| entries.flatten().filter(|e| e.path().is_dir()).count())
+        .unwrap_or(0);
+    let project = if projects > 0 {
+        (true, format!("{} projects", projects), "")
+    } else {
+        (
+            false,
+            "No projects yet".to_string(),
+            "Open a folder and start a session in it",
+        )
+    };
+    vec![cli, credentials, dir, project]
+}
+
+/// Combine what was detected with the steps the user marked complete
+fn build_status(detections: Vec<Detection>, acknowledged: &[String]) -> OnboardingStatus {
+    let steps: Vec<OnboardingStep> = ONBOARDING_STEPS
+        .iter()
+        .zip(detections)
+        .map(|((id, title), (detected, detail, hint))| {
+            let completed = detected || acknowledged.iter().any(|s| s == id);
+            OnboardingStep {
+                id: id.to_string(),
+                title: title.to_string(),
+                detected,
+                completed,
+                detail,
+                hint: (!completed && !hint.is_empty()).then(|| hint.to_string()),
+            }
+        })
+        .collect();
+    let current_step = steps.iter().find(|s| !s.completed).map(|s| s.id.clone());
+    OnboardingStatus {
+        complete: current_step.is_none(),
+        current_step,
+        steps,
+    }
+}
+
+fn acknowledged_steps(conn: &rusqlite::Connection) -> Vec<String> {
+    store::get_value(conn, "onboarding_completed_steps")
+        .ok()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn status(app: &AppHandle, db: &AgentDb) -> Result<OnboardingStatus, String> {
+    let (has_api_key, acknowledged) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            configured_api_key(&conn).is_some(),
+            acknowledged_steps(&conn),
+        )
+    };
+    Ok(build_status(detect(app, has_api_key), &acknowledged))
+}
+
+/// Get which first-run setup steps are complete
+///
+/// Steps are checked on every call, so one finished outside the app, e.g.
+/// installing the CLI, completes without the user saying so.
+#[tauri::command]
+pub async fn get_onboarding_status(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+) -> Result<OnboardingStatus, String> {
+    status(&app, &db)
+}
+
+/// Mark a setup step complete, e.g. one the app can't detect such as a
+/// keychain login, and return the updated status
+#[tauri::command]
+pub async fn complete_onboarding_step(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    step: String,
+) -> Result<OnboardingStatus, String> {
+    if !ONBOARDING_STEPS.iter().any(|(id, _)| *id == step) {
+        return Err(format!(
+            "Unknown onboarding step '{}'; expected one of {}",
+            step,
+            ONBOARDING_STEPS
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut acknowledged = acknowledged_steps(&conn);
+        if !acknowledged.contains(&step) {
+            acknowledged.push(step);
+            store::set_value(
+                &conn,
+                "onboarding_completed_steps",
+                &serde_json::json!(acknowledged),
+            )?;
+        }
+    }
+    status(&app, &db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_status() {
+        let detections = vec![
+            (true, "Found claude".to_string(), ""),
+            (false, "None".to_string(), "Sign in"),
+            (false, "Missing".to_string(), "Run Claude"),
+            (false, "No projects".to_string(), "Open a folder"),
+        ];
+        let status = build_status(detections.clone(), &[]);
+        assert_eq!(status.current_step.as_deref(), Some("credentials"));
+        assert_eq!(status.steps[1].hint.as_deref(), Some("Sign in"));
+        assert!(status.steps[0].hint.is_none());
+
+        let acknowledged = ["credentials".to_string(), "claude_dir".to_string()];
+        let status = build_status(detections.clone(), &acknowledged);
+        assert_eq!(status.current_step.as_deref(), Some("first_project"));
+        assert!(status.steps[1].completed && !status.steps[1].detected);
+
+        let all: Vec<String> = ONBOARDING_STEPS
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect();
+        let status = build_status(detections, &all);
+        assert!(status.complete);
+        assert!(status.current_step.is_none());
+    }
+}
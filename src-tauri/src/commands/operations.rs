@@ -0,0 +1,190 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio_util::sync::CancellationToken;
+
+use crate::event_bus;
+
+/// Tracks long-running operations so they can be cancelled
+#[derive(Default)]
+pub struct OperationState {
+    operations: Mutex<HashMap<String, RunningOperation>>,
+}
+
+struct RunningOperation {
+    kind: String,
+    token: CancellationToken,
+}
+
+/// A running operation, as listed to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub operation_id: String,
+    pub kind: String,
+    pub cancelled: bool,
+}
+
+/// Progress of a long-running operation
+///
+/// Emitted as `operation-progress:{operation_id}` and `operation-progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    pub operation_id: String,
+    /// What is being done, e.g. "skill_install"
+    pub kind: String,
+    /// Current step, e.g. "downloading" or "verifying"
+    pub stage: String,
+    /// Overall completion from 0 to 100, when it can be estimated
+    pub percent: Option<u8>,
+    pub current_file: Option<String>,
+}
+
+/// Emitted as `operation-complete:{operation_id}` once an operation ends
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationComplete {
+    pub operation_id: String,
+    pub kind: String,
+    pub success: bool,
+    pub cancelled: bool,
+    pub error: Option<String>,
+    /// The command's return value, for operations started in the background
+    pub result: Option<JsonValue>,
+}
+
+/// Reports progress for one operation and tells it when to stop
+///
+/// The operation is registered on creation and unregistered when dropped.
+pub struct ProgressReporter {
+    app: AppHandle,
+    operation_id: String,
+    kind: String,
+    token: CancellationToken,
+}
+
+impl ProgressReporter {
+    /// Register a new operation of the given kind
+    pub fn start(app: &AppHandle, kind: &str) -> Self {
+        let operation_id = uuid::Uuid::new_v4().to_string();
+        let token = CancellationToken::new();
+        if let Some(state) = app.try_state::<OperationState>() {
+            if let Ok(mut operations) = state.operations.lock() {
+                operations.insert(
+                    operation_id.clone(),
+                    RunningOperation {
+                        kind: kind.to_string(),
+                        token: token.clone(),
+                    },
+                );
+            }
+        }
+
+        Self {
+            app: app.clone(),
+            operation_id,
+            kind: kind.to_string(),
+            token,
+        }
+    }
+
+    pub fn operation_id(&self) -> &str {
+        &self.operation_id
+    }
+
+    /// Emit a progress event
+    pub fn report(&self, stage: &str, percent: Option<u8>, current_file: Option<&str>) {
+        let progress = OperationProgress {
+            operation_id: self.operation_id.clone(),
+            kind: self.kind.clone(),
+            stage: stage.to_string(),
+            percent: percent.map(|p| p.min(100)),
+            current_file: current_file.map(str::to_string),
+        };
+        let _ = self.app.emit(
+            &format!("operation-progress:{}", self.operation_id),
+            &progress,
+        );
+        let _ = self.app.emit("operation-progress", &progress);
+    }
+
+    /// Token that is cancelled by `cancel_operation`
+    ///
+    /// Pass it to async work (see `network::until_cancelled`) so it stops
+    /// promptly instead of at the next checkpoint.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Emit the completion event for the operation's outcome
+    pub fn finish<T: Serialize, E: ToString>(&self, outcome: &Result<T, E>) {
+        let complete = OperationComplete {
+            operation_id: self.operation_id.clone(),
+            kind: self.kind.clone(),
+            success: outcome.is_ok(),
+            cancelled: self.is_cancelled(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            result: outcome
+                .as_ref()
+                .ok()
+                .and_then(|r| serde_json::to_value(r).ok()),
+        };
+        event_bus::publish(
+            &self.app,
+            &format!("operation-complete:{}", self.operation_id),
+            &complete,
+        );
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        if let Some(state) = self.app.try_state::<OperationState>() {
+            if let Ok(mut operations) = state.operations.lock() {
+                operations.remove(&self.operation_id);
+            }
+        }
+    }
+}
+
+/// List operations that are still running
+#[tauri::command]
+pub async fn list_operations(
+    state: State<'_, OperationState>,
+) -> Result<Vec<OperationInfo>, String> {
+    let operations = state.operations.lock().map_err(|e| e.to_string())?;
+    let mut list: Vec<OperationInfo> = operations
+        .iter()
+        .map(|(id, op)| OperationInfo {
+            operation_id: id.clone(),
+            kind: op.kind.clone(),
+            cancelled: op.token.is_cancelled(),
+        })
+        .collect();
+    list.sort_by(|a, b| a.operation_id.cmp(&b.operation_id));
+    Ok(list)
+}
+
+/// Ask a running operation to stop
+///
+/// In-flight requests are aborted, partial files removed, and the completion
+/// event reports `cancelled: true`. Returns `false` if no such operation is
+/// running.
+#[tauri::command]
+pub async fn cancel_operation(
+    state: State<'_, OperationState>,
+    operation_id: String,
+) -> Result<bool, String> {
+    let operations = state.operations.lock().map_err(|e| e.to_string())?;
+    match operations.get(&operation_id) {
+        Some(op) => {
+            op.token.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,176 @@
+use serde_json::Value as JsonValue;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::validate_project_root;
+use crate::permissions::{self, PermissionEvaluation, PermissionRules, ScopedRules};
+use crate::utils::get_claude_dir;
+
+/// Scopes in the order their rules are searched, most specific first
+const SCOPES: &[&str] = &["local", "project", "user"];
+
+/// The settings file of a scope and the directory its `/path` rules are relative to
+fn settings_file(scope: &str, project: Option<&Path>) -> Result<(PathBuf, PathBuf), String> {
+    match (scope, project) {
+        ("user", _) => {
+            let claude_dir = get_claude_dir()?;
+            let root = claude_dir
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| claude_dir.clone());
+            Ok((claude_dir.join("settings.json"), root))
+        }
+        ("project", Some(project)) => Ok((
+            project.join(".claude").join("settings.json"),
+            project.to_path_buf(),
+        )),
+        ("local", Some(project)) => Ok((
+            project.join(".claude").join("settings.local.json"),
+            project.to_path_buf(),
+        )),
+        ("project" | "local", None) => Err(format!("Project path required for {} scope", scope)),
+        _ => Err(format!("Invalid scope: {}", scope)),
+    }
+}
+
+fn read_settings(path: &Path) -> Result<JsonValue, String> {
+    if !path.exists() {
+        return Ok(serde_json::json!({}));
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+fn project_root(project_path: Option<String>) -> Result<Option<PathBuf>, String> {
+    project_path
+        .as_deref()
+        .map(validate_project_root)
+        .transpose()
+}
+
+/// Get the allow, ask and deny rules of a settings scope
+#[tauri::command]
+pub async fn get_permission_rules(
+    scope: String,
+    project_path: Option<String>,
+) -> Result<PermissionRules, String> {
+    let project = project_root(project_path)?;
+    let (path, _) = settings_file(&scope, project.as_deref())?;
+    Ok(PermissionRules::from_settings(&read_settings(&path)?))
+}
+
+/// Replace the allow, ask and deny rules of a settings scope
+///
+/// Every rule is checked first; other settings, including the rest of
+/// `permissions`, are kept. Returns the rules as saved.
+#[tauri::command]
+pub async fn update_permission_rules(
+    scope: String,
+    rules: PermissionRules,
+    project_path: Option<String>,
+) -> Result<PermissionRules, String> {
+    let rules = rules.normalized()?;
+    let project = project_root(project_path)?;
+    let (path, _) = settings_file(&scope, project.as_deref())?;
+    let mut settings = read_settings(&path)?;
+    let Some(object) = settings.as_object_mut() else {
+        return Err(format!("{} is not a JSON object", path.display()));
+    };
+    let section = object
+        .entry("permissions")
+        .or_insert_with(|| serde_json::json!({}));
+    let Some(section) = section.as_object_mut() else {
+        return Err(format!(
+            "permissions in {} is not an object",
+            path.display()
+        ));
+    };
+    for (name, list) in [
+        ("allow", &rules.allow),
+        ("ask", &rules.ask),
+        ("deny", &rules.deny),
+    ] {
+        if list.is_empty() {
+            section.remove(name);
+        } else {
+            section.insert(name.to_string(), serde_json::json!(list));
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json_string = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let operation = FileOperation::for_write(&path);
+    fs::write(&path, json_string).map_err(|e| format!("Failed to write settings: {}", e))?;
+    file_audit::record("update_permission_rules", operation, &path);
+    Ok(rules)
+}
+
+/// Check whether Claude would run a tool invocation, without running it
+///
+/// `input` is the tool input as Claude sends it, e.g. `{"command": "npm run
+/// test"}` for Bash or `{"file_path": "src/main.rs"}` for Edit. With `scope`
+/// only that settings file's rules count, otherwise every scope's rules do.
+/// `draft` stands in for the rules of `scope`, to try rules before saving them.
+#[tauri::command]
+pub async fn evaluate_permission(
+    tool: String,
+    input: JsonValue,
+    scope: Option<String>,
+    project_path: Option<String>,
+    draft: Option<PermissionRules>,
+) -> Result<PermissionEvaluation, String> {
+    let tool = tool.trim();
+    if tool.is_empty() {
+        return Err("Tool name is empty".to_string());
+    }
+    if draft.is_some() && scope.is_none() {
+        return Err("A draft needs the scope it would be saved to".to_string());
+    }
+    let project = project_root(project_path)?;
+    let scopes: Vec<&str> = match &scope {
+        Some(scope) => vec![scope.as_str()],
+        None => SCOPES
+            .iter()
+            .copied()
+            .filter(|s| project.is_some() || *s == "user")
+            .collect(),
+    };
+
+    let mut sources = Vec::new();
+    let mut default_mode = None;
+    for scope in scopes {
+        let (path, root) = settings_file(scope, project.as_deref())?;
+        let settings = read_settings(&path)?;
+        if default_mode.is_none() {
+            default_mode = settings["permissions"]["defaultMode"]
+                .as_str()
+                .map(str::to_string);
+        }
+        let rules = match &draft {
+            Some(draft) => draft.normalized()?,
+            None => PermissionRules::from_settings(&settings),
+        };
+        sources.push(ScopedRules {
+            scope: scope.to_string(),
+            root,
+            rules,
+        });
+    }
+
+    let home = dirs::home_dir().ok_or("Could not find the home directory")?;
+    let cwd = project.unwrap_or_else(|| home.clone());
+    Ok(permissions::evaluate(
+        tool,
+        &input,
+        &sources,
+        default_mode.as_deref(),
+        &cwd,
+        &home,
+    ))
+}
@@ -0,0 +1,297 @@
+// Popularity of marketplace entries from their GitHub repositories
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::skills::{load_catalog_fetch_settings, CatalogFetchSettings};
+use crate::network::{send_with_policy, NetworkError, RetryPolicy};
+
+/// Signals are fetched again once they are this old
+const CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Most entries `get_marketplace_popularity` looks up in one call
+const MAX_URLS: usize = 500;
+
+/// How popular and active a marketplace entry is
+///
+/// Fetched only for entries someone looks at or sorts by, and cached to stay
+/// within GitHub's rate limit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Popularity {
+    /// Stars of the repository the entry lives in
+    pub stars: u64,
+    /// Open issues and pull requests of that repository
+    pub open_issues: u64,
+    /// Last commit touching the entry itself
+    pub last_commit_at: Option<DateTime<Utc>>,
+}
+
+/// An entry's location on GitHub
+#[derive(Debug, Clone, PartialEq)]
+struct GitHubPath {
+    /// `owner/name`
+    repo: String,
+    /// Path inside the repository; empty for the whole repository
+    path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubRepo {
+    stargazers_count: u64,
+    open_issues_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubCommit {
+    commit: GitHubCommitDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubCommitDetail {
+    committer: Option<GitHubSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubSignature {
+    date: DateTime<Utc>,
+}
+
+struct Cached<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+type Cache<T> = Mutex<HashMap<String, Cached<T>>>;
+
+fn repo_cache() -> &'static Cache<GitHubRepo> {
+    static CACHE: OnceLock<Cache<GitHubRepo>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn entry_cache() -> &'static Cache<Popularity> {
+    static CACHE: OnceLock<Cache<Popularity>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn cached<T: Clone>(cache: &Cache<T>, key: &str) -> Option<T> {
+    let cache = cache.lock().ok()?;
+    cache
+        .get(key)
+        .filter(|c| c.fetched_at.elapsed() < CACHE_TTL)
+        .map(|c| c.value.clone())
+}
+
+fn remember<T>(cache: &Cache<T>, key: &str, value: T) {
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(
+            key.to_string(),
+            Cached {
+                value,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+}
+
+/// Repository and path of a github.com, raw.githubusercontent.com or
+/// api.github.com contents URL
+fn parse_github_url(url: &str) -> Option<GitHubPath> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let (owner, name, path) = match url.host_str()? {
+        // /<owner>/<repo>[/tree|blob/<ref>/<path>]
+        "github.com" => match segments.as_slice() {
+            [owner, name] => (*owner, *name, &[][..]),
+            [owner, name, "tree" | "blob", _, path @ ..] => (*owner, *name, path),
+            _ => return None,
+        },
+        // /<owner>/<repo>/<ref>/<path>
+        "raw.githubusercontent.com" => match segments.as_slice() {
+            [owner, name, _, path @ ..] => (*owner, *name, path),
+            _ => return None,
+        },
+        // /repos/<owner>/<repo>/contents/<path>
+        "api.github.com" => match segments.as_slice() {
+            ["repos", owner, name, "contents", path @ ..] => (*owner, *name, path),
+            ["repos", owner, name] => (*owner, *name, &[][..]),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(GitHubPath {
+        repo: format!("{}/{}", owner, name.trim_end_matches(".git")),
+        path: path.join("/"),
+    })
+}
+
+async fn get_json<T: for<'de> Deserialize<'de>>(
+    client: &Client,
+    url: &str,
+    timeout: Duration,
+) -> Result<T, NetworkError> {
+    let policy = RetryPolicy {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    let response = send_with_policy(&policy, || {
+        client
+            .get(url)
+            .header(USER_AGENT, "Opcode-Agent")
+            .header("Accept", "application/vnd.github+json")
+            .timeout(timeout)
+    })
+    .await?;
+    Ok(response.json().await?)
+}
+
+async fn fetch_repo(
+    client: &Client,
+    repo: &str,
+    timeout: Duration,
+) -> Result<GitHubRepo, NetworkError> {
+    if let Some(stats) = cached(repo_cache(), repo) {
+        return Ok(stats);
+    }
+    let url = format!("https://api.github.com/repos/{}", repo);
+    let stats: GitHubRepo = get_json(client, &url, timeout).await?;
+    remember(repo_cache(), repo, stats.clone());
+    Ok(stats)
+}
+
+async fn fetch_popularity(
+    client: &Client,
+    location: &GitHubPath,
+    timeout: Duration,
+) -> Result<Popularity, NetworkError> {
+    let repo = fetch_repo(client, &location.repo, timeout).await?;
+    let mut url = reqwest::Url::parse(&format!(
+        "https://api.github.com/repos/{}/commits",
+        location.repo
+    ))
+    .map_err(|e| e.to_string())?;
+    url.query_pairs_mut().append_pair("per_page", "1");
+    if !location.path.is_empty() {
+        url.query_pairs_mut().append_pair("path", &location.path);
+    }
+    let commits: Vec<GitHubCommit> = get_json(client, url.as_str(), timeout).await?;
+    Ok(Popularity {
+        stars: repo.stargazers_count,
+        open_issues: repo.open_issues_count,
+        last_commit_at: commits
+            .into_iter()
+            .next()
+            .and_then(|c| c.commit.committer)
+            .map(|c| c.date),
+    })
+}
+
+/// Popularity of every GitHub URL, at most `settings.concurrency` fetched at a time
+///
+/// URLs that aren't on GitHub or whose signals can't be fetched, e.g. because
+/// the rate limit was hit, are left out.
+pub async fn popularity_for(
+    client: &Client,
+    urls: Vec<String>,
+    settings: &CatalogFetchSettings,
+) -> HashMap<String, Popularity> {
+    let timeout = Duration::from_secs(settings.request_timeout_secs);
+    let mut found = HashMap::new();
+    let mut missing = Vec::new();
+    for url in urls {
+        match cached(entry_cache(), &url) {
+            Some(popularity) => {
+                found.insert(url, popularity);
+            }
+            None => {
+                if let Some(location) = parse_github_url(&url) {
+                    missing.push((url, location));
+                }
+            }
+        }
+    }
+
+    let fetched: Vec<(String, Result<Popularity, NetworkError>)> = stream::iter(missing)
+        .map(|(url, location)| async move {
+            let result = fetch_popularity(client, &location, timeout).await;
+            (url, result)
+        })
+        .buffer_unordered(settings.concurrency.max(1))
+        .collect()
+        .await;
+    for (url, result) in fetched {
+        match result {
+            Ok(popularity) => {
+                remember(entry_cache(), &url, popularity.clone());
+                found.insert(url, popularity);
+            }
+            Err(e) => log::debug!("No popularity for {}: {}", url, e),
+        }
+    }
+    found
+}
+
+/// Get the popularity of marketplace entries by their GitHub URL
+///
+/// Works for skill, MCP server and agent URLs. Entries whose signals couldn't
+/// be fetched are missing from the result.
+#[tauri::command]
+pub async fn get_marketplace_popularity(
+    db: State<'_, AgentDb>,
+    urls: Vec<String>,
+) -> Result<HashMap<String, Popularity>, String> {
+    if urls.len() > MAX_URLS {
+        return Err(format!(
+            "At most {} entries can be looked up at once",
+            MAX_URLS
+        ));
+    }
+    let settings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_catalog_fetch_settings(&conn)
+    };
+    let client = crate::http_client::client()?;
+    Ok(popularity_for(&client, urls, &settings).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_url() {
+        let location = |repo: &str, path: &str| {
+            Some(GitHubPath {
+                repo: repo.to_string(),
+                path: path.to_string(),
+            })
+        };
+        assert_eq!(
+            parse_github_url("https://github.com/anthropics/skills/tree/main/skills/pdf"),
+            location("anthropics/skills", "skills/pdf")
+        );
+        assert_eq!(
+            parse_github_url(
+                "https://raw.githubusercontent.com/getAsterisk/opcode/main/cc_agents/git.opcode.json"
+            ),
+            location("getAsterisk/opcode", "cc_agents/git.opcode.json")
+        );
+        assert_eq!(
+            parse_github_url(
+                "https://api.github.com/repos/modelcontextprotocol/servers/contents/src/git"
+            ),
+            location("modelcontextprotocol/servers", "src/git")
+        );
+        assert_eq!(
+            parse_github_url("https://github.com/owner/tool.git"),
+            location("owner/tool", "")
+        );
+        assert_eq!(parse_github_url("https://example.com/owner/tool"), None);
+        assert_eq!(parse_github_url("https://github.com/owner"), None);
+    }
+}
@@ -0,0 +1,17 @@
+use tauri::AppHandle;
+
+use crate::prewarm::{self, PrewarmStatus};
+
+/// Get the prewarm schedule and the outcome of the last prewarm
+#[tauri::command]
+pub async fn get_prewarm_status(app: AppHandle) -> Result<PrewarmStatus, String> {
+    prewarm::status(&app)
+}
+
+/// Refresh the model list, marketplace catalogs and MCP health checks now
+///
+/// Returns `false` if a prewarm was already in progress.
+#[tauri::command]
+pub async fn run_prewarm_now(app: AppHandle) -> Result<bool, String> {
+    Ok(prewarm::run_now(&app).await)
+}
@@ -0,0 +1,331 @@
+use minisign_verify::{PublicKey, Signature};
+use reqwest::header::USER_AGENT;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::settings;
+use crate::network::{send_with_retry, NetworkError};
+
+/// app_settings key holding the last verified remote pricing table
+const CACHE_KEY: &str = "pricing_table_cache";
+
+/// Prices for models whose name contains `model`, in USD per million tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub model: String,
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+    #[serde(default)]
+    pub context_window: Option<u64>,
+}
+
+/// Prices and capabilities of known models; the first matching entry wins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingTable {
+    /// Increases with every published table; older tables are refused
+    pub version: u32,
+    pub updated_at: String,
+    pub models: Vec<ModelPricing>,
+}
+
+/// User prices for models whose name contains `model`; unset fields keep the table price
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceOverride {
+    pub model: String,
+    pub input: Option<f64>,
+    pub output: Option<f64>,
+    pub cache_write: Option<f64>,
+    pub cache_read: Option<f64>,
+}
+
+/// Negotiated prices and markups applied on top of the table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PricingOverrides {
+    #[serde(default)]
+    pub models: Vec<PriceOverride>,
+    /// Added to every cost, e.g. 10 for a proxy that charges 10% on top
+    #[serde(default)]
+    pub markup_percent: f64,
+}
+
+/// Token counts of one API response
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenCounts {
+    pub input: u64,
+    pub output: u64,
+    pub cache_write: u64,
+    pub cache_read: u64,
+}
+
+/// The pricing in effect, as returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct Pricing {
+    pub table: PricingTable,
+    /// "built_in" or "remote"
+    pub source: String,
+    pub overrides: PricingOverrides,
+}
+
+/// Claude 4 list prices, used until a remote table has been fetched
+pub fn built_in_table() -> PricingTable {
+    let model = |model: &str, input, output, cache_write, cache_read| ModelPricing {
+        model: model.to_string(),
+        input,
+        output,
+        cache_write,
+        cache_read,
+        context_window: Some(200_000),
+    };
+    PricingTable {
+        version: 0,
+        updated_at: "2025-05-22T00:00:00Z".to_string(),
+        models: vec![
+            model("opus-4", 15.0, 75.0, 18.75, 1.50),
+            model("sonnet-4", 3.0, 15.0, 3.75, 0.30),
+        ],
+    }
+}
+
+impl Pricing {
+    /// Cost in USD of a response from `model`
+    ///
+    /// `reported` is the cost Claude logged, if any. It is used as-is unless an
+    /// override matches the model, since it reflects list prices. Models the
+    /// table doesn't know cost nothing rather than a guess.
+    pub fn cost(&self, model: &str, tokens: TokenCounts, reported: Option<f64>) -> f64 {
+        let base = self.table.models.iter().find(|p| model.contains(&p.model));
+        let custom = self
+            .overrides
+            .models
+            .iter()
+            .find(|o| model.contains(&o.model));
+
+        let cost = match (custom, reported) {
+            (None, Some(reported)) => reported,
+            _ => {
+                let price = |custom: Option<f64>, base: Option<f64>| custom.or(base).unwrap_or(0.0);
+                let o = custom.cloned().unwrap_or_default();
+                let per_million = tokens.input as f64 * price(o.input, base.map(|b| b.input))
+                    + tokens.output as f64 * price(o.output, base.map(|b| b.output))
+                    + tokens.cache_write as f64 * price(o.cache_write, base.map(|b| b.cache_write))
+                    + tokens.cache_read as f64 * price(o.cache_read, base.map(|b| b.cache_read));
+                per_million / 1_000_000.0
+            }
+        };
+        cost * (1.0 + self.overrides.markup_percent / 100.0)
+    }
+}
+
+fn load_cached_table(conn: &Connection) -> Option<PricingTable> {
+    settings::get_value(conn, CACHE_KEY)
+        .ok()
+        .and_then(|cached| serde_json::from_value(cached).ok())
+}
+
+/// Load the pricing in effect: the cached remote table (or the built-in one) plus overrides
+pub fn load_pricing(conn: &Connection) -> Pricing {
+    let (table, source) = match load_cached_table(conn) {
+        Some(table) => (table, "remote"),
+        None => (built_in_table(), "built_in"),
+    };
+    let overrides = settings::get_value(conn, "pricing_overrides")
+        .ok()
+        .filter(|v| !v.is_null())
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    Pricing {
+        table,
+        source: source.to_string(),
+        overrides,
+    }
+}
+
+fn check_price(label: &str, value: f64) -> Result<(), String> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("Invalid price for {}: {}", label, value));
+    }
+    Ok(())
+}
+
+fn validate_table(table: &PricingTable) -> Result<(), String> {
+    if table.models.is_empty() {
+        return Err("Pricing table lists no models".to_string());
+    }
+    for model in &table.models {
+        if model.model.trim().is_empty() {
+            return Err("Pricing table has an entry without a model name".to_string());
+        }
+        for value in [
+            model.input,
+            model.output,
+            model.cache_write,
+            model.cache_read,
+        ] {
+            check_price(&model.model, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch the pricing table from `pricing_table_url` and cache it if its signature checks out
+///
+/// The table must be signed with minisign (`<url>.minisig`) by the key in
+/// `pricing_signing_public_key`; a table older than the cached one is refused.
+pub async fn refresh_pricing(db: &AgentDb) -> Result<PricingTable, NetworkError> {
+    let (url, public_key, cached_version) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            settings::get_text(&conn, "pricing_table_url"),
+            settings::get_text(&conn, "pricing_signing_public_key"),
+            load_cached_table(&conn).map(|t| t.version),
+        )
+    };
+    let url = url.ok_or_else(|| "No pricing table URL is configured".to_string())?;
+    let public_key = public_key
+        .ok_or_else(|| "A signing key is required to refresh the pricing table".to_string())?;
+
+    let client = crate::http_client::client()?;
+    let fetch = |url: String| {
+        let client = client.clone();
+        async move {
+            let response =
+                send_with_retry(|| client.get(&url).header(USER_AGENT, "Opcode-Pricing")).await?;
+            Ok::<_, NetworkError>(response.bytes().await?)
+        }
+    };
+    let content = fetch(url.clone())
+        .await
+        .map_err(|e| e.context("Failed to download pricing table"))?;
+    let signature = fetch(format!("{}.minisig", url))
+        .await
+        .map_err(|e| e.context("Failed to download pricing table signature"))?;
+
+    let integrity = |message: String| NetworkError::Integrity { message };
+    let public_key = PublicKey::from_base64(public_key.trim())
+        .or_else(|_| PublicKey::decode(public_key.trim()))
+        .map_err(|e| integrity(format!("Invalid pricing signing key: {}", e)))?;
+    let signature = Signature::decode(&String::from_utf8_lossy(&signature))
+        .map_err(|e| integrity(format!("Invalid pricing table signature: {}", e)))?;
+    public_key
+        .verify(&content, &signature, false)
+        .map_err(|e| integrity(format!("Pricing table signature does not match: {}", e)))?;
+
+    let table: PricingTable = serde_json::from_slice(&content)
+        .map_err(|e| integrity(format!("Invalid pricing table: {}", e)))?;
+    validate_table(&table).map_err(integrity)?;
+    if cached_version.is_some_and(|cached| table.version < cached) {
+        return Err(integrity(format!(
+            "Pricing table version {} is older than the cached version {}",
+            table.version,
+            cached_version.unwrap_or_default()
+        )));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let cached = serde_json::to_value(&table).map_err(|e| e.to_string())?;
+    settings::set_value(&conn, CACHE_KEY, &cached)
+        .map_err(|e| format!("Failed to cache pricing table: {}", e))?;
+    Ok(table)
+}
+
+/// Get the pricing table and overrides used for cost numbers
+#[tauri::command]
+pub async fn get_pricing(db: State<'_, AgentDb>) -> Result<Pricing, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_pricing(&conn))
+}
+
+/// Refresh the pricing table from the configured URL now
+#[tauri::command]
+pub async fn refresh_pricing_table(db: State<'_, AgentDb>) -> Result<PricingTable, NetworkError> {
+    refresh_pricing(&db).await
+}
+
+/// Drop the fetched pricing table and go back to the built-in prices
+#[tauri::command]
+pub async fn reset_pricing_table(db: State<'_, AgentDb>) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::set_value(&conn, CACHE_KEY, &JsonValue::Null)
+        .map_err(|e| format!("Failed to reset pricing table: {}", e))?;
+    Ok(())
+}
+
+/// Save negotiated prices and markups
+#[tauri::command]
+pub async fn save_pricing_overrides(
+    db: State<'_, AgentDb>,
+    overrides: PricingOverrides,
+) -> Result<(), String> {
+    if !overrides.markup_percent.is_finite()
+        || overrides.markup_percent < -100.0
+        || overrides.markup_percent > 1000.0
+    {
+        return Err("Markup must be between -100% and 1000%".to_string());
+    }
+    for o in &overrides.models {
+        if o.model.trim().is_empty() {
+            return Err("Each price override needs a model name".to_string());
+        }
+        for value in [o.input, o.output, o.cache_write, o.cache_read]
+            .into_iter()
+            .flatten()
+        {
+            check_price(&o.model, value)?;
+        }
+    }
+
+    let value = if overrides.models.is_empty() && overrides.markup_percent == 0.0 {
+        JsonValue::Null
+    } else {
+        serde_json::to_value(&overrides).map_err(|e| e.to_string())?
+    };
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::set_value(&conn, "pricing_overrides", &value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_with_overrides() {
+        let tokens = TokenCounts {
+            input: 1_000_000,
+            output: 1_000_000,
+            ..Default::default()
+        };
+        let mut pricing = Pricing {
+            table: built_in_table(),
+            source: "built_in".to_string(),
+            overrides: PricingOverrides::default(),
+        };
+
+        assert_eq!(pricing.cost("claude-sonnet-4-20250514", tokens, None), 18.0);
+        assert_eq!(
+            pricing.cost("claude-sonnet-4-20250514", tokens, Some(1.0)),
+            1.0
+        );
+        assert_eq!(pricing.cost("unknown-model", tokens, None), 0.0);
+
+        // A negotiated input price replaces the reported cost; output keeps list price
+        pricing.overrides.models.push(PriceOverride {
+            model: "sonnet-4".to_string(),
+            input: Some(2.0),
+            ..Default::default()
+        });
+        pricing.overrides.markup_percent = 50.0;
+        assert_eq!(
+            pricing.cost("claude-sonnet-4-20250514", tokens, Some(1.0)),
+            25.5
+        );
+        assert_eq!(
+            pricing.cost("claude-opus-4-20250514", tokens, Some(1.0)),
+            1.5
+        );
+    }
+}
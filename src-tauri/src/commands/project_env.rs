@@ -0,0 +1,51 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::path_validation::validate_project_root;
+use crate::project_env::{self, ProjectEnvVar};
+
+/// Projects are keyed by their canonical path, as runs see it
+fn project_key(project_path: &str) -> Result<String, String> {
+    Ok(validate_project_root(project_path)?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Set an environment variable for every run in a project
+///
+/// With `secret` the value is kept in the OS keychain and never returned.
+#[tauri::command]
+pub async fn set_project_env(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    key: String,
+    value: String,
+    secret: bool,
+) -> Result<ProjectEnvVar, String> {
+    let project = project_key(&project_path)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    project_env::set_var(&conn, &project, key.trim(), &value, secret)
+}
+
+/// The environment variables of a project; secret values are left out
+#[tauri::command]
+pub async fn list_project_env(
+    db: State<'_, AgentDb>,
+    project_path: String,
+) -> Result<Vec<ProjectEnvVar>, String> {
+    let project = project_key(&project_path)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    project_env::list_vars(&conn, &project).map_err(|e| e.to_string())
+}
+
+/// Remove an environment variable from a project; returns whether it existed
+#[tauri::command]
+pub async fn delete_project_env(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    key: String,
+) -> Result<bool, String> {
+    let project = project_key(&project_path)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    project_env::delete_var(&conn, &project, &key)
+}
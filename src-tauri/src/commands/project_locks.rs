@@ -0,0 +1,11 @@
+use tauri::State;
+
+use crate::project_locks::{ProjectLockInfo, ProjectLocks};
+
+/// Projects agent runs are working in, with the runs waiting for each
+#[tauri::command]
+pub async fn list_project_locks(
+    locks: State<'_, ProjectLocks>,
+) -> Result<Vec<ProjectLockInfo>, String> {
+    Ok(locks.list())
+}
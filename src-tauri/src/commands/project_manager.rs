@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::utils::get_claude_dir;
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::{resolve_within, validate_project_root};
+use crate::utils::{encode_project_path, get_claude_dir};
 
 /// Represents a project in the ~/.claude/projects directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -252,7 +254,7 @@ pub async fn create_project(path: String) -> Result<Project, String> {
     log::info!("Creating project for path: {}", path);
 
     // Encode the path to create a project ID
-    let project_id = path.replace('/', "-");
+    let project_id = encode_project_path(&path);
 
     // Get claude directory
     let claude_dir = get_claude_dir()?;
@@ -379,3 +381,166 @@ pub async fn get_project_sessions(project_id: String) -> Result<Vec<Session>, St
     );
     Ok(sessions)
 }
+
+/// Options for scaffolding a project's `.claude/` directory
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScaffoldOptions {
+    /// Whether to create a CLAUDE.md at the project root
+    #[serde(default = "default_true")]
+    pub create_claude_md: bool,
+    /// Contents for CLAUDE.md; `{{project_name}}` is replaced with the directory name
+    pub claude_md_template: Option<String>,
+}
+
+impl Default for ScaffoldOptions {
+    fn default() -> Self {
+        Self {
+            create_claude_md: true,
+            claude_md_template: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// What `init_claude_project` created and what was already there
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScaffoldReport {
+    /// Paths relative to the project root
+    pub created: Vec<String>,
+    pub already_present: Vec<String>,
+}
+
+/// Directories created by `init_claude_project`, relative to the project root
+const SCAFFOLD_DIRS: &[&str] = &[
+    ".claude",
+    ".claude/commands",
+    ".claude/agents",
+    ".claude/skills",
+];
+
+const DEFAULT_SETTINGS_JSON: &str = r#"{
+  "permissions": {
+    "allow": [],
+    "deny": []
+  }
+}
+"#;
+
+const DEFAULT_CLAUDE_MD: &str = "# {{project_name}}
+
+## Overview
+
+Describe what this project does and how it is organized.
+
+## Development
+
+- Build:
+- Test:
+- Lint:
+
+## Conventions
+
+List coding conventions Claude should follow in this project.
+";
+
+/// Create the standard `.claude/` layout in `project_path`, leaving existing files untouched
+pub fn scaffold_claude_project(
+    project_path: &str,
+    options: &ScaffoldOptions,
+) -> Result<ScaffoldReport, String> {
+    let project_path = &validate_project_root(project_path)?;
+
+    let mut report = ScaffoldReport::default();
+    let mut record = |relative: &str, created: bool| {
+        if created {
+            report.created.push(relative.to_string());
+        } else {
+            report.already_present.push(relative.to_string());
+        }
+    };
+
+    for dir in SCAFFOLD_DIRS {
+        let path = resolve_within(project_path, dir)?;
+        let created = !path.exists();
+        if created {
+            fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+        }
+        record(dir, created);
+    }
+
+    let settings = resolve_within(project_path, ".claude/settings.json")?;
+    let created = write_if_missing(&settings, DEFAULT_SETTINGS_JSON)?;
+    record(".claude/settings.json", created);
+
+    if options.create_claude_md {
+        let project_name = project_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let content = options
+            .claude_md_template
+            .as_deref()
+            .unwrap_or(DEFAULT_CLAUDE_MD)
+            .replace("{{project_name}}", &project_name);
+        let created = write_if_missing(&resolve_within(project_path, "CLAUDE.md")?, &content)?;
+        record("CLAUDE.md", created);
+    }
+
+    Ok(report)
+}
+
+/// Write `content` to `path` unless it already exists; returns whether it was written
+fn write_if_missing(path: &Path, content: &str) -> Result<bool, String> {
+    if path.exists() {
+        return Ok(false);
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    file_audit::record("init_claude_project", FileOperation::Create, path);
+    Ok(true)
+}
+
+/// Onboard a project by creating its `.claude/` structure
+///
+/// Safe to run repeatedly: existing files and directories are reported, not overwritten.
+#[tauri::command]
+pub async fn init_claude_project(
+    project_path: String,
+    options: Option<ScaffoldOptions>,
+) -> Result<ScaffoldReport, String> {
+    log::info!("Initializing .claude structure in {}", project_path);
+    let report = scaffold_claude_project(&project_path, &options.unwrap_or_default())?;
+    log::info!(
+        "Scaffolded {}: {} created, {} already present",
+        project_path,
+        report.created.len(),
+        report.already_present.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("CLAUDE.md"), "keep me").unwrap();
+
+        let first = scaffold_claude_project(path, &ScaffoldOptions::default()).unwrap();
+        assert!(first.created.contains(&".claude/settings.json".to_string()));
+        assert_eq!(first.already_present, vec!["CLAUDE.md".to_string()]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("CLAUDE.md")).unwrap(),
+            "keep me"
+        );
+
+        let second = scaffold_claude_project(path, &ScaffoldOptions::default()).unwrap();
+        assert!(second.created.is_empty());
+        assert_eq!(second.already_present.len(), 6);
+    }
+}
@@ -0,0 +1,567 @@
+// Skill bundles and project profiles, applied all or nothing
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use super::agents::{AgentData, AgentDb};
+use super::operations::ProgressReporter;
+use super::settings as store;
+use super::skills::{prepare_skill, write_atomically, write_skills, PreparedSkill, SkillScope};
+use crate::file_audit::{self, FileOperation};
+use crate::network::NetworkError;
+use crate::path_validation::{validate_name, validate_project_root};
+
+/// Skills, MCP servers, agents and settings to apply to a project together
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectProfile {
+    pub name: String,
+    /// Registry skills, installed in the project
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// Server name -> `.mcp.json` entry
+    #[serde(default)]
+    pub mcp_servers: BTreeMap<String, JsonValue>,
+    /// Agents to add, or update when one with the same name exists
+    #[serde(default)]
+    pub agents: Vec<AgentData>,
+    /// App settings, validated like `set_setting`
+    #[serde(default)]
+    pub settings: BTreeMap<String, JsonValue>,
+}
+
+/// What happened to one item of a bundle or profile
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    /// "skill", "mcp_server", "agent" or "setting"
+    pub kind: String,
+    pub name: String,
+    /// "applied", "unchanged", "failed", or "not_applied" when another item failed
+    pub status: String,
+    pub detail: Option<String>,
+}
+
+/// Outcome of `install_skill_bundle` and `apply_project_profile`
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkReport {
+    /// False when an item failed and nothing was written
+    pub applied: bool,
+    pub items: Vec<BulkItemResult>,
+}
+
+impl BulkItemResult {
+    fn new(kind: &str, name: &str, status: &str, detail: Option<String>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            detail,
+        }
+    }
+}
+
+impl BulkReport {
+    fn from_items(items: Vec<BulkItemResult>) -> Self {
+        Self {
+            applied: !items.iter().any(|item| item.status == "failed"),
+            items,
+        }
+    }
+
+    /// Mark one item failed and every item that would have been written as not applied
+    fn fail(mut self, kind: &str, name: &str, detail: String) -> Self {
+        for item in self.items.iter_mut() {
+            if item.kind == kind && item.name == name {
+                item.status = "failed".to_string();
+                item.detail = Some(detail.clone());
+            } else if item.status == "applied" {
+                item.status = "not_applied".to_string();
+                item.detail = Some("Nothing was written because another item failed".to_string());
+            }
+        }
+        self.applied = false;
+        self
+    }
+
+    fn abandon(mut self) -> Self {
+        if let Some(item) = self.items.iter().find(|item| item.status == "failed") {
+            let (kind, name) = (item.kind.clone(), item.name.clone());
+            let detail = item.detail.clone().unwrap_or_default();
+            self = self.fail(&kind, &name, detail);
+        }
+        self
+    }
+}
+
+fn load_profiles(conn: &Connection) -> Vec<ProjectProfile> {
+    store::get_value(conn, "project_profiles")
+        .ok()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Download and verify skills for a project, recording the ones that fail
+async fn prepare_skills(
+    app: &AppHandle,
+    project_path: &str,
+    names: &[String],
+    progress: &ProgressReporter,
+    items: &mut Vec<BulkItemResult>,
+) -> Result<Vec<PreparedSkill>, NetworkError> {
+    let mut prepared = Vec::new();
+    for name in names {
+        match prepare_skill(app, Some(project_path), name, SkillScope::Project, progress).await {
+            Ok(skill) => {
+                prepared.push(skill);
+                items.push(BulkItemResult::new("skill", name, "applied", None));
+            }
+            Err(e) if progress.is_cancelled() => return Err(e),
+            Err(e) => items.push(BulkItemResult::new(
+                "skill",
+                name,
+                "failed",
+                Some(e.to_string()),
+            )),
+        }
+    }
+    Ok(prepared)
+}
+
+/// Install several registry skills in a project, all or nothing
+///
+/// Reports progress as a `skill_bundle` operation. Every skill and its
+/// dependencies are downloaded and verified first; when one fails none is
+/// installed and the report says which.
+#[tauri::command]
+pub async fn install_skill_bundle(
+    app: AppHandle,
+    project_path: String,
+    names: Vec<String>,
+) -> Result<BulkReport, NetworkError> {
+    let progress = ProgressReporter::start(&app, "skill_bundle");
+    let result = install_bundle(&app, &project_path, names, &progress).await;
+    progress.finish(&result);
+    result
+}
+
+async fn install_bundle(
+    app: &AppHandle,
+    project_path: &str,
+    mut names: Vec<String>,
+    progress: &ProgressReporter,
+) -> Result<BulkReport, NetworkError> {
+    validate_project_root(project_path)?;
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    if names.is_empty() {
+        return Err("No skills to install".to_string().into());
+    }
+
+    let mut items = Vec::new();
+    let prepared = prepare_skills(app, project_path, &names, progress, &mut items).await?;
+    let report = BulkReport::from_items(items);
+    if !report.applied {
+        return Ok(report.abandon());
+    }
+    if progress.is_cancelled() {
+        return Err(NetworkError::cancelled());
+    }
+
+    progress.report("writing", Some(90), None);
+    let report = match write_skills(prepared) {
+        Ok(_) => report,
+        Err((name, e)) => report.fail("skill", &name, e.to_string()),
+    };
+    progress.report("done", Some(100), None);
+    Ok(report)
+}
+
+/// Profiles saved in settings
+#[tauri::command]
+pub async fn list_project_profiles(db: State<'_, AgentDb>) -> Result<Vec<ProjectProfile>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_profiles(&conn))
+}
+
+/// Save a profile, replacing the one with the same name
+#[tauri::command]
+pub async fn save_project_profile(
+    db: State<'_, AgentDb>,
+    profile: ProjectProfile,
+) -> Result<(), String> {
+    validate_name(&profile.name, "profile name")?;
+    for skill in &profile.skills {
+        validate_name(skill, "skill name")?;
+    }
+    for (key, value) in &profile.settings {
+        store::encode(store::spec(key)?, value)?;
+    }
+    for (name, server) in &profile.mcp_servers {
+        if !server.is_object() {
+            return Err(format!("MCP server '{}' must be a JSON object", name));
+        }
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut profiles = load_profiles(&conn);
+    profiles.retain(|p| p.name != profile.name);
+    profiles.push(profile);
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    store::set_value(
+        &conn,
+        "project_profiles",
+        &serde_json::to_value(profiles).map_err(|e| e.to_string())?,
+    )
+}
+
+/// Delete a profile; returns whether it existed
+#[tauri::command]
+pub async fn delete_project_profile(db: State<'_, AgentDb>, name: String) -> Result<bool, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut profiles = load_profiles(&conn);
+    let count = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == count {
+        return Ok(false);
+    }
+    store::set_value(
+        &conn,
+        "project_profiles",
+        &serde_json::to_value(profiles).map_err(|e| e.to_string())?,
+    )?;
+    Ok(true)
+}
+
+/// Check the settings and agents of a profile against the database
+fn plan_database(conn: &Connection, profile: &ProjectProfile, items: &mut Vec<BulkItemResult>) {
+    for (key, value) in &profile.settings {
+        let item = match store::spec(key).and_then(|spec| store::encode(spec, value)) {
+            Err(e) => BulkItemResult::new("setting", key, "failed", Some(e)),
+            Ok(_) if store::get_value(conn, key).ok().as_ref() == Some(value) => {
+                BulkItemResult::new("setting", key, "unchanged", None)
+            }
+            Ok(_) => BulkItemResult::new("setting", key, "applied", None),
+        };
+        items.push(item);
+    }
+
+    for agent in &profile.agents {
+        if agent.name.trim().is_empty() || agent.system_prompt.trim().is_empty() {
+            items.push(BulkItemResult::new(
+                "agent",
+                &agent.name,
+                "failed",
+                Some("An agent needs a name and a system prompt".to_string()),
+            ));
+            continue;
+        }
+        let current = conn
+            .query_row(
+                "SELECT name, icon, system_prompt, default_task, model, hooks FROM agents WHERE name = ?1",
+                params![agent.name],
+                |row| {
+                    Ok(AgentData {
+                        name: row.get(0)?,
+                        icon: row.get(1)?,
+                        system_prompt: row.get(2)?,
+                        default_task: row.get(3)?,
+                        model: row.get(4)?,
+                        hooks: row.get(5)?,
+                    })
+                },
+            )
+            .optional()
+            .unwrap_or(None);
+        let item = match current {
+            Some(current) if &current == agent => {
+                BulkItemResult::new("agent", &agent.name, "unchanged", None)
+            }
+            Some(_) => BulkItemResult::new(
+                "agent",
+                &agent.name,
+                "applied",
+                Some("Updates the existing agent".to_string()),
+            ),
+            None => BulkItemResult::new("agent", &agent.name, "applied", None),
+        };
+        items.push(item);
+    }
+}
+
+/// Add the profile's servers to a `.mcp.json` document; returns whether it changed
+fn merge_mcp_servers(
+    config: &mut JsonValue,
+    servers: &BTreeMap<String, JsonValue>,
+    items: &mut Vec<BulkItemResult>,
+) -> bool {
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+    if !config["mcpServers"].is_object() {
+        config["mcpServers"] = serde_json::json!({});
+    }
+    let existing = config["mcpServers"].as_object_mut().expect("set above");
+    let mut changed = false;
+    for (name, server) in servers {
+        let valid = server.get("command").is_some_and(JsonValue::is_string)
+            || server.get("url").is_some_and(JsonValue::is_string);
+        let item = if !valid {
+            BulkItemResult::new(
+                "mcp_server",
+                name,
+                "failed",
+                Some("A server needs a `command` or a `url`".to_string()),
+            )
+        } else if existing.get(name) == Some(server) {
+            BulkItemResult::new("mcp_server", name, "unchanged", None)
+        } else {
+            let detail = existing
+                .contains_key(name)
+                .then(|| "Replaces the existing server".to_string());
+            existing.insert(name.clone(), server.clone());
+            changed = true;
+            BulkItemResult::new("mcp_server", name, "applied", detail)
+        };
+        items.push(item);
+    }
+    changed
+}
+
+/// Write the settings and agents that change; not committed until the caller does
+fn write_database(
+    tx: &rusqlite::Transaction,
+    profile: &ProjectProfile,
+    report: &BulkReport,
+) -> Result<(), (&'static str, String, String)> {
+    let applied = |kind: &str, name: &str| {
+        report
+            .items
+            .iter()
+            .any(|item| item.kind == kind && item.name == name && item.status == "applied")
+    };
+    for (key, value) in &profile.settings {
+        if applied("setting", key) {
+            store::set_value(tx, key, value).map_err(|e| ("setting", key.clone(), e))?;
+        }
+    }
+    for agent in &profile.agents {
+        if !applied("agent", &agent.name) {
+            continue;
+        }
+        let fail = |e: rusqlite::Error| ("agent", agent.name.clone(), e.to_string());
+        let updated = tx
+            .execute(
+                "UPDATE agents SET icon = ?2, system_prompt = ?3, default_task = ?4, model = ?5, hooks = ?6
+                 WHERE name = ?1",
+                params![
+                    agent.name,
+                    agent.icon,
+                    agent.system_prompt,
+                    agent.default_task,
+                    agent.model,
+                    agent.hooks
+                ],
+            )
+            .map_err(fail)?;
+        if updated == 0 {
+            tx.execute(
+                "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, 1, 1, 0, ?6)",
+                params![
+                    agent.name,
+                    agent.icon,
+                    agent.system_prompt,
+                    agent.default_task,
+                    agent.model,
+                    agent.hooks
+                ],
+            )
+            .map_err(fail)?;
+        }
+    }
+    Ok(())
+}
+
+/// Put `.mcp.json` back as it was
+fn restore_mcp_config(root: &Path, previous: Option<&str>) {
+    let path = root.join(".mcp.json");
+    match previous {
+        Some(content) => {
+            if write_atomically(root, &path, content.as_bytes()).is_ok() {
+                file_audit::record("apply_project_profile", FileOperation::Modify, &path);
+            }
+        }
+        None => {
+            if fs::remove_file(&path).is_ok() {
+                file_audit::record("apply_project_profile", FileOperation::Delete, &path);
+            }
+        }
+    }
+}
+
+/// Apply a saved profile to a project, all or nothing
+///
+/// Skills are installed in the project and MCP servers added to its
+/// `.mcp.json`; agents and settings are app-wide. Reports progress as a
+/// `project_profile` operation. When any item fails, nothing is written and
+/// the report says which item failed.
+#[tauri::command]
+pub async fn apply_project_profile(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    project_path: String,
+    profile: String,
+) -> Result<BulkReport, NetworkError> {
+    let progress = ProgressReporter::start(&app, "project_profile");
+    let result = apply_profile(&app, db.inner(), &project_path, &profile, &progress).await;
+    progress.finish(&result);
+    result
+}
+
+async fn apply_profile(
+    app: &AppHandle,
+    db: &AgentDb,
+    project_path: &str,
+    name: &str,
+    progress: &ProgressReporter,
+) -> Result<BulkReport, NetworkError> {
+    progress.report("validating", Some(0), None);
+    let root = validate_project_root(project_path)?;
+    let mut items = Vec::new();
+    let profile = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let profile = load_profiles(&conn)
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("Project profile '{}' not found", name))?;
+        plan_database(&conn, &profile, &mut items);
+        profile
+    };
+
+    let mcp_path = root.join(".mcp.json");
+    let previous_mcp = fs::read_to_string(&mcp_path).ok();
+    let mut mcp_config: JsonValue = match &previous_mcp {
+        Some(content) => serde_json::from_str(content)
+            .map_err(|e| format!("Failed to parse {}: {}", mcp_path.display(), e))?,
+        None => JsonValue::Null,
+    };
+    let mcp_changed = merge_mcp_servers(&mut mcp_config, &profile.mcp_servers, &mut items);
+
+    let prepared = prepare_skills(app, project_path, &profile.skills, progress, &mut items).await?;
+    let report = BulkReport::from_items(items);
+    if !report.applied {
+        return Ok(report.abandon());
+    }
+    if progress.is_cancelled() {
+        return Err(NetworkError::cancelled());
+    }
+
+    progress.report("writing", Some(90), None);
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if let Err((kind, name, e)) = write_database(&tx, &profile, &report) {
+        return Ok(report.fail(kind, &name, e));
+    }
+    if mcp_changed {
+        let content = serde_json::to_string_pretty(&mcp_config).map_err(|e| e.to_string())?;
+        if let Err(e) = write_atomically(&root, &mcp_path, content.as_bytes()) {
+            restore_mcp_config(&root, previous_mcp.as_deref());
+            let server = report
+                .items
+                .iter()
+                .find(|item| item.kind == "mcp_server" && item.status == "applied")
+                .map(|item| item.name.clone())
+                .unwrap_or_default();
+            return Ok(report.fail("mcp_server", &server, e));
+        }
+        let operation = match previous_mcp {
+            Some(_) => FileOperation::Modify,
+            None => FileOperation::Create,
+        };
+        file_audit::record("apply_project_profile", operation, &mcp_path);
+    }
+    let undo = match write_skills(prepared) {
+        Ok((_, undo)) => undo,
+        Err((name, e)) => {
+            if mcp_changed {
+                restore_mcp_config(&root, previous_mcp.as_deref());
+            }
+            return Ok(report.fail("skill", &name, e.to_string()));
+        }
+    };
+    if let Err(e) = tx.commit() {
+        undo.undo();
+        if mcp_changed {
+            restore_mcp_config(&root, previous_mcp.as_deref());
+        }
+        return Err(format!("Failed to save profile '{}': {}", name, e).into());
+    }
+
+    if profile.settings.keys().any(|key| key.starts_with("proxy_")) {
+        super::proxy::apply_proxy_settings(&super::proxy::load_proxy_settings(&conn));
+    }
+    if profile.settings.keys().any(|key| key.starts_with("http_")) {
+        crate::http_client::configure_limits(&crate::http_client::load_limits(&conn));
+    }
+    if profile
+        .settings
+        .keys()
+        .any(|key| key.starts_with("redaction_"))
+    {
+        crate::redaction::configure(&conn);
+    }
+    log::info!("Applied project profile '{}' to {}", name, root.display());
+    progress.report("done", Some(100), None);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_mcp_servers() {
+        let mut config = serde_json::json!({
+            "mcpServers": { "docs": { "command": "docs-server" } },
+            "other": true
+        });
+        let servers = BTreeMap::from([
+            (
+                "docs".to_string(),
+                serde_json::json!({ "command": "docs-server" }),
+            ),
+            (
+                "web".to_string(),
+                serde_json::json!({ "url": "https://example.com/mcp" }),
+            ),
+            ("broken".to_string(), serde_json::json!({ "args": [] })),
+        ]);
+        let mut items = Vec::new();
+        assert!(merge_mcp_servers(&mut config, &servers, &mut items));
+
+        let status = |name: &str| {
+            items
+                .iter()
+                .find(|item| item.name == name)
+                .map(|item| item.status.as_str())
+        };
+        assert_eq!(status("docs"), Some("unchanged"));
+        assert_eq!(status("web"), Some("applied"));
+        assert_eq!(status("broken"), Some("failed"));
+        assert_eq!(config["other"], true);
+
+        let report = BulkReport::from_items(items).abandon();
+        assert!(!report.applied);
+        assert_eq!(
+            report
+                .items
+                .iter()
+                .find(|item| item.name == "web")
+                .unwrap()
+                .status,
+            "not_applied"
+        );
+    }
+}
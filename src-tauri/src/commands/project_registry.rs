@@ -0,0 +1,112 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::git::git;
+use crate::path_validation::validate_project_root;
+use crate::project_registry::{self, RegisteredProject};
+
+/// A registered project with the state of its checkout
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectRegistryEntry {
+    #[serde(flatten)]
+    pub project: RegisteredProject,
+    /// Whether the directory is still there
+    pub exists: bool,
+    pub is_git: bool,
+    /// Checked out branch; `None` on a detached HEAD or outside git
+    pub branch: Option<String>,
+    /// Uncommitted or untracked changes
+    pub dirty: bool,
+}
+
+/// Branch from the header line of `git status --porcelain=v1 --branch`, e.g.
+/// `## main...origin/main [ahead 1]` or `## No commits yet on main`
+fn branch_from_header(header: &str) -> Option<String> {
+    let branch = header.strip_prefix("## ")?;
+    let branch = branch
+        .strip_prefix("No commits yet on ")
+        .or_else(|| branch.strip_prefix("Initial commit on "))
+        .unwrap_or(branch);
+    let branch = branch.split("...").next()?.split(" [").next()?.trim();
+    (!branch.is_empty() && !branch.starts_with("HEAD (")).then(|| branch.to_string())
+}
+
+async fn with_checkout_state(project: RegisteredProject) -> ProjectRegistryEntry {
+    let path = Path::new(&project.path);
+    let exists = path.is_dir();
+    let status = if exists {
+        git(path, &["status", "--porcelain=v1", "--branch"])
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let mut lines = status.as_deref().unwrap_or_default().lines();
+    let branch = lines.next().and_then(branch_from_header);
+    ProjectRegistryEntry {
+        exists,
+        is_git: status.is_some(),
+        branch,
+        dirty: lines.any(|line| !line.trim().is_empty()),
+        project,
+    }
+}
+
+/// Add a project to the registry, or mark it opened if it is already there
+///
+/// `name` is shown in the project switcher; it defaults to the directory name.
+#[tauri::command]
+pub async fn add_project(
+    db: State<'_, AgentDb>,
+    path: String,
+    name: Option<String>,
+) -> Result<ProjectRegistryEntry, String> {
+    let root = validate_project_root(&path)?;
+    let project = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        project_registry::add(&conn, &root.to_string_lossy(), name.as_deref())
+            .map_err(|e| e.to_string())?
+    };
+    Ok(with_checkout_state(project).await)
+}
+
+/// Remove a project from the registry; its files and sessions are kept
+#[tauri::command]
+pub async fn remove_project(db: State<'_, AgentDb>, path: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    if !project_registry::remove(&conn, path.trim()).map_err(|e| e.to_string())? {
+        return Err(format!("Project {} is not registered", path));
+    }
+    Ok(())
+}
+
+/// Pin a project as a favorite, or unpin it with `pinned` false
+#[tauri::command]
+pub async fn pin_project(
+    db: State<'_, AgentDb>,
+    path: String,
+    pinned: Option<bool>,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    if !project_registry::set_pinned(&conn, path.trim(), pinned.unwrap_or(true))
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!("Project {} is not registered", path));
+    }
+    Ok(())
+}
+
+/// Registered projects, pinned first, then most recently opened, with the
+/// branch and working tree state of each checkout
+#[tauri::command]
+pub async fn list_projects_registry(
+    db: State<'_, AgentDb>,
+) -> Result<Vec<ProjectRegistryEntry>, String> {
+    let projects = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        project_registry::list(&conn).map_err(|e| e.to_string())?
+    };
+    Ok(futures::future::join_all(projects.into_iter().map(with_checkout_state)).await)
+}
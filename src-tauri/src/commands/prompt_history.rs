@@ -0,0 +1,52 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::prompt_history::{self, PromptHistoryEntry};
+
+/// Prompts sent in sessions, pinned first, then most recently used
+///
+/// `project_filter` limits them to one project and `query` to prompts
+/// containing it.
+#[tauri::command]
+pub async fn list_prompt_history(
+    db: State<'_, AgentDb>,
+    project_filter: Option<String>,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<PromptHistoryEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    prompt_history::list(&conn, project_filter.as_deref(), query.as_deref(), limit)
+        .map_err(|e| e.to_string())
+}
+
+/// Pin a prompt as a favorite, or unpin it with `pinned` false
+#[tauri::command]
+pub async fn pin_prompt(
+    db: State<'_, AgentDb>,
+    id: i64,
+    pinned: Option<bool>,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    if !prompt_history::set_pinned(&conn, id, pinned.unwrap_or(true)).map_err(|e| e.to_string())? {
+        return Err(format!("Prompt {} not found", id));
+    }
+    Ok(())
+}
+
+/// Delete prompts from the history; returns how many were removed
+///
+/// With `ids` those prompts are deleted, pinned or not. Without, every
+/// unpinned prompt is, of `project_path` only if given.
+#[tauri::command]
+pub async fn delete_prompt_history(
+    db: State<'_, AgentDb>,
+    ids: Option<Vec<i64>>,
+    project_path: Option<String>,
+) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    match ids {
+        Some(ids) => prompt_history::delete(&conn, &ids),
+        None => prompt_history::clear(&conn, project_path.as_deref()),
+    }
+    .map_err(|e| e.to_string())
+}
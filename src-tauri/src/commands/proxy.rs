@@ -1,8 +1,10 @@
-use rusqlite::params;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use tauri::State;
 
 use crate::commands::agents::AgentDb;
+use crate::commands::settings;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProxySettings {
@@ -10,6 +12,9 @@ pub struct ProxySettings {
     pub https_proxy: Option<String>,
     pub no_proxy: Option<String>,
     pub all_proxy: Option<String>,
+    /// PEM file with extra root certificates (e.g. a corporate TLS-inspecting proxy)
+    #[serde(default)]
+    pub ca_bundle_path: Option<String>,
     pub enabled: bool,
 }
 
@@ -20,45 +25,29 @@ impl Default for ProxySettings {
             https_proxy: None,
             no_proxy: None,
             all_proxy: None,
+            ca_bundle_path: None,
             enabled: false,
         }
     }
 }
 
+/// Load the proxy settings from the settings store
+pub fn load_proxy_settings(conn: &Connection) -> ProxySettings {
+    ProxySettings {
+        http_proxy: settings::get_text(conn, "proxy_http"),
+        https_proxy: settings::get_text(conn, "proxy_https"),
+        no_proxy: settings::get_text(conn, "proxy_no"),
+        all_proxy: settings::get_text(conn, "proxy_all"),
+        ca_bundle_path: settings::get_text(conn, "proxy_ca_bundle"),
+        enabled: settings::get_bool(conn, "proxy_enabled"),
+    }
+}
+
 /// Get proxy settings from the database
 #[tauri::command]
 pub async fn get_proxy_settings(db: State<'_, AgentDb>) -> Result<ProxySettings, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-
-    let mut settings = ProxySettings::default();
-
-    // Query each proxy setting
-    let keys = vec![
-        ("proxy_enabled", "enabled"),
-        ("proxy_http", "http_proxy"),
-        ("proxy_https", "https_proxy"),
-        ("proxy_no", "no_proxy"),
-        ("proxy_all", "all_proxy"),
-    ];
-
-    for (db_key, field) in keys {
-        if let Ok(value) = conn.query_row(
-            "SELECT value FROM app_settings WHERE key = ?1",
-            params![db_key],
-            |row| row.get::<_, String>(0),
-        ) {
-            match field {
-                "enabled" => settings.enabled = value == "true",
-                "http_proxy" => settings.http_proxy = Some(value).filter(|s| !s.is_empty()),
-                "https_proxy" => settings.https_proxy = Some(value).filter(|s| !s.is_empty()),
-                "no_proxy" => settings.no_proxy = Some(value).filter(|s| !s.is_empty()),
-                "all_proxy" => settings.all_proxy = Some(value).filter(|s| !s.is_empty()),
-                _ => {}
-            }
-        }
-    }
-
-    Ok(settings)
+    Ok(load_proxy_settings(&conn))
 }
 
 /// Save proxy settings to the database
@@ -67,29 +56,33 @@ pub async fn save_proxy_settings(
     db: State<'_, AgentDb>,
     settings: ProxySettings,
 ) -> Result<(), String> {
+    // Reject settings the HTTP client can't use before persisting them
+    crate::http_client::builder_for(&settings)?;
+
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    // Save each setting
+    // Save each setting; unset fields are removed rather than stored empty
+    let text = |value: &Option<String>| {
+        value
+            .clone()
+            .map(JsonValue::String)
+            .unwrap_or(JsonValue::Null)
+    };
     let values = vec![
-        ("proxy_enabled", settings.enabled.to_string()),
-        (
-            "proxy_http",
-            settings.http_proxy.clone().unwrap_or_default(),
-        ),
-        (
-            "proxy_https",
-            settings.https_proxy.clone().unwrap_or_default(),
-        ),
-        ("proxy_no", settings.no_proxy.clone().unwrap_or_default()),
-        ("proxy_all", settings.all_proxy.clone().unwrap_or_default()),
+        ("proxy_enabled", JsonValue::Bool(settings.enabled)),
+        ("proxy_http", text(&settings.http_proxy)),
+        ("proxy_https", text(&settings.https_proxy)),
+        ("proxy_no", text(&settings.no_proxy)),
+        ("proxy_all", text(&settings.all_proxy)),
+        ("proxy_ca_bundle", text(&settings.ca_bundle_path)),
     ];
 
+    // Validate everything before writing anything
+    for (key, value) in &values {
+        settings::encode(settings::spec(key)?, value)?;
+    }
     for (key, value) in values {
-        conn.execute(
-            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
-        )
-        .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+        settings::set_value(&conn, key, &value)?;
     }
 
     // Apply the proxy settings immediately to the current process
@@ -98,10 +91,12 @@ pub async fn save_proxy_settings(
     Ok(())
 }
 
-/// Apply proxy settings as environment variables
+/// Apply proxy settings as environment variables and to the shared HTTP client
 pub fn apply_proxy_settings(settings: &ProxySettings) {
     log::info!("Applying proxy settings: enabled={}", settings.enabled);
 
+    crate::http_client::configure(settings);
+
     if !settings.enabled {
         // Clear proxy environment variables if disabled
         log::info!("Clearing proxy environment variables");
@@ -0,0 +1,32 @@
+use tauri::State;
+
+use crate::redaction::{RedactionState, RunRedactions};
+
+/// Turn redaction on or off for one run while it streams
+///
+/// `run` is the Claude session ID of a session or the run ID of an agent run.
+/// Lines already printed are left as they were.
+#[tauri::command]
+pub async fn set_run_redaction(
+    redaction: State<'_, RedactionState>,
+    run: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let run = run.trim();
+    if run.is_empty() {
+        return Err("Run ID is empty".to_string());
+    }
+    redaction.set_enabled(run, enabled);
+    Ok(())
+}
+
+/// Redactions made since the app started, per run and pattern
+///
+/// With `run`, only that run's; a run without redactions isn't listed.
+#[tauri::command]
+pub async fn get_redaction_counts(
+    redaction: State<'_, RedactionState>,
+    run: Option<String>,
+) -> Result<Vec<RunRedactions>, String> {
+    Ok(redaction.counts(run.as_deref()))
+}
@@ -0,0 +1,343 @@
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::bookmarks::find_session_project;
+use super::claude::load_session_history;
+use super::git::git;
+use super::session_export::tool_result_text;
+use crate::path_validation::{resolve_within, validate_project_root};
+
+/// Commits searched for renames of a file that no longer exists
+const MAX_RENAME_COMMITS: &str = "2000";
+
+/// A `file:line` reference found in a message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReference {
+    /// The reference as written, e.g. `src/main.rs:42:7`
+    pub text: String,
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    /// Last line of a range such as `src/main.rs:10-20`
+    pub end_line: Option<u32>,
+}
+
+/// Where to open a reference
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferenceTarget {
+    /// Absolute path of the file as it is now
+    pub path: String,
+    /// Relative to the project
+    pub relative_path: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: Option<u32>,
+    /// `path:line:column`, as editors take it on the command line
+    pub location: String,
+    /// The path in the message, when the file was renamed since
+    pub renamed_from: Option<String>,
+    /// Commit that renamed it, or `index` for a staged rename
+    pub renamed_in: Option<String>,
+    /// Why the line differs from the reference, e.g. the file got shorter
+    pub note: Option<String>,
+}
+
+fn reference_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"((?:[A-Za-z]:)?[\w.\-/\\@+~]*[\w\-]\.[A-Za-z0-9]+)(?::(\d+)(?::(\d+))?(?:-(\d+))?|#L(\d+)(?:-L(\d+))?)",
+        )
+        .expect("valid pattern")
+    })
+}
+
+/// Find `path:line`, `path:line:column`, `path:start-end` and `path#Lline`
+/// references in text
+pub fn parse_references(text: &str) -> Vec<FileReference> {
+    let number = |m: Option<regex::Match>| m.and_then(|m| m.as_str().parse::<u32>().ok());
+    let mut references: Vec<FileReference> = Vec::new();
+    for caps in reference_pattern().captures_iter(text) {
+        let path = &caps[1];
+        // Host names in URLs look like files with a port
+        if path.starts_with("//") || text[..caps.get(0).unwrap().start()].ends_with(':') {
+            continue;
+        }
+        let Some(line) = number(caps.get(2)).or(number(caps.get(5))) else {
+            continue;
+        };
+        if line == 0 {
+            continue;
+        }
+        let reference = FileReference {
+            text: caps[0].to_string(),
+            path: path.to_string(),
+            line,
+            column: number(caps.get(3)),
+            end_line: number(caps.get(4)).or(number(caps.get(6))),
+        };
+        if !references.contains(&reference) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// Text of the assistant's replies and the tool outputs in a transcript entry
+fn message_text(message: &JsonValue) -> String {
+    match &message["message"]["content"] {
+        JsonValue::String(text) => text.clone(),
+        JsonValue::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block["type"].as_str() {
+                Some("text") => block["text"].as_str().unwrap_or_default().to_string(),
+                Some("tool_result") => tool_result_text(&block["content"]),
+                _ => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+async fn load_message(
+    session_id: &str,
+    message_index: usize,
+) -> Result<(Vec<JsonValue>, usize), String> {
+    let project_id = find_session_project(session_id)?;
+    let messages = load_session_history(session_id.to_string(), project_id).await?;
+    if message_index >= messages.len() {
+        return Err(format!(
+            "Message index {} is out of range (session has {} messages)",
+            message_index,
+            messages.len()
+        ));
+    }
+    Ok((messages, message_index))
+}
+
+/// The file:line references in one message of a session
+#[tauri::command]
+pub async fn list_message_references(
+    session_id: String,
+    message_index: usize,
+) -> Result<Vec<FileReference>, String> {
+    let (messages, index) = load_message(&session_id, message_index).await?;
+    Ok(parse_references(&message_text(&messages[index])))
+}
+
+/// A rename or deletion in the project's history, oldest first
+#[derive(Debug, Clone, PartialEq)]
+struct PathChange {
+    commit: String,
+    from: String,
+    /// `None` when the file was deleted
+    to: Option<String>,
+}
+
+/// Parse `git log --name-status --format=commit %H` output
+fn parse_path_changes(output: &str) -> Vec<PathChange> {
+    let mut changes = Vec::new();
+    let mut commit = String::new();
+    for line in output.lines() {
+        if let Some(sha) = line.strip_prefix("commit ") {
+            commit = sha.trim().to_string();
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            [status, from, to] if status.starts_with('R') => changes.push(PathChange {
+                commit: commit.clone(),
+                from: from.to_string(),
+                to: Some(to.to_string()),
+            }),
+            ["D", from] => changes.push(PathChange {
+                commit: commit.clone(),
+                from: from.to_string(),
+                to: None,
+            }),
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// Follow a path through renames; the last rename, or an error if it was deleted
+fn follow_renames(changes: &[PathChange], path: &str) -> Result<Option<PathChange>, String> {
+    let mut current = path.to_string();
+    let mut last = None;
+    for change in changes {
+        if change.from != current {
+            continue;
+        }
+        match &change.to {
+            Some(to) => {
+                current = to.clone();
+                last = Some(PathChange {
+                    commit: change.commit.clone(),
+                    from: path.to_string(),
+                    to: Some(to.clone()),
+                });
+            }
+            None => {
+                return Err(format!(
+                    "{} was deleted in {}",
+                    current,
+                    &change.commit[..change.commit.len().min(12)]
+                ))
+            }
+        }
+    }
+    Ok(last)
+}
+
+/// Renames and deletions of the project's files, oldest first, then staged ones
+async fn path_changes(project: &Path) -> Result<Vec<PathChange>, String> {
+    let log = git(
+        project,
+        &[
+            "log",
+            "-M",
+            "--relative",
+            "--diff-filter=RD",
+            "--name-status",
+            "--format=commit %H",
+            "--max-count",
+            MAX_RENAME_COMMITS,
+        ],
+    )
+    .await?;
+    let mut changes = parse_path_changes(&log);
+    changes.reverse();
+    let staged = git(
+        project,
+        &["diff", "-M", "--relative", "--cached", "--name-status"],
+    )
+    .await
+    .unwrap_or_default();
+    changes.extend(parse_path_changes(&format!("commit index\n{}", staged)));
+    Ok(changes)
+}
+
+/// Resolve a file:line reference from a message to a file in the session's project
+///
+/// The path must stay inside the project the session ran in. When the file no
+/// longer exists, git history is searched for renames. The line is kept
+/// within the file.
+#[tauri::command]
+pub async fn resolve_reference(
+    session_id: String,
+    message_index: usize,
+    reference: String,
+) -> Result<ReferenceTarget, String> {
+    let parsed = parse_references(&reference)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("'{}' is not a file:line reference", reference))?;
+    let (messages, index) = load_message(&session_id, message_index).await?;
+    let cwd = messages[..=index]
+        .iter()
+        .rev()
+        .find_map(|m| m["cwd"].as_str().filter(|cwd| !cwd.is_empty()))
+        .ok_or("The session doesn't say which project it ran in")?;
+    let project = validate_project_root(cwd)?;
+
+    let path = Path::new(&parsed.path);
+    let relative: PathBuf = if path.is_absolute() {
+        path.strip_prefix(&project)
+            .or_else(|_| path.strip_prefix(cwd))
+            .map_err(|_| format!("{} is outside the project {}", parsed.path, cwd))?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    let relative_str = relative.to_string_lossy().replace('\\', "/");
+
+    let mut file = resolve_within(&project, &relative)?;
+    let mut renamed = None;
+    if !file.is_file() {
+        let changes = path_changes(&project)
+            .await
+            .map_err(|e| format!("{} doesn't exist ({})", relative_str, e))?;
+        let change = follow_renames(&changes, &relative_str)?
+            .ok_or_else(|| format!("{} doesn't exist in the project", relative_str))?;
+        let to = change.to.clone().unwrap_or_default();
+        file = resolve_within(&project, &to)?;
+        if !file.is_file() {
+            return Err(format!(
+                "{} was renamed to {}, which doesn't exist either",
+                relative_str, to
+            ));
+        }
+        renamed = Some(change);
+    }
+
+    let line_count = std::fs::read(&file)
+        .map(|content| content.lines().count().max(1) as u32)
+        .unwrap_or(1);
+    let mut note = None;
+    let line = if parsed.line > line_count {
+        note = Some(format!(
+            "The file has {} lines now; line {} is past the end",
+            line_count, parsed.line
+        ));
+        line_count
+    } else {
+        parsed.line
+    };
+    let column = parsed.column.unwrap_or(1).max(1);
+    let path = file.to_string_lossy().to_string();
+
+    Ok(ReferenceTarget {
+        location: format!("{}:{}:{}", path, line, column),
+        relative_path: file
+            .strip_prefix(&project)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| relative_str.clone()),
+        path,
+        line,
+        column,
+        end_line: parsed.end_line.map(|end| end.clamp(line, line_count)),
+        renamed_from: renamed.as_ref().map(|_| relative_str.clone()),
+        renamed_in: renamed.map(|change| change.commit),
+        note,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references_and_follow_renames() {
+        let text = "Fixed in src/main.rs:42:7 and lib/util.ts:10-20; see README.md#L3. \
+                    Served at http://localhost:8080 and https://example.com:443/x.";
+        let refs = parse_references(text);
+        let summary: Vec<(&str, u32, Option<u32>, Option<u32>)> = refs
+            .iter()
+            .map(|r| (r.path.as_str(), r.line, r.column, r.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/main.rs", 42, Some(7), None),
+                ("lib/util.ts", 10, None, Some(20)),
+                ("README.md", 3, None, None),
+            ]
+        );
+
+        let mut changes = parse_path_changes(
+            "commit bbb\n\nR090\tsrc/util.rs\tsrc/utils/mod.rs\ncommit aaa\n\nR100\tsrc/helpers.rs\tsrc/util.rs\nD\tsrc/old.rs\n",
+        );
+        changes.reverse();
+        let change = follow_renames(&changes, "src/helpers.rs").unwrap().unwrap();
+        assert_eq!(change.to.as_deref(), Some("src/utils/mod.rs"));
+        assert_eq!(change.commit, "bbb");
+        assert!(follow_renames(&changes, "src/old.rs").is_err());
+        assert!(follow_renames(&changes, "src/other.rs").unwrap().is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::claude::load_session_history;
+use crate::event_bus;
+
+/// Longest pause between two replayed messages unless overridden
+const DEFAULT_MAX_DELAY_MS: u64 = 10_000;
+
+/// Tracks active replays so they can be stopped
+#[derive(Default)]
+pub struct ReplayState {
+    replays: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Options for replaying a session
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReplayOptions {
+    /// Playback speed multiplier; 2.0 plays twice as fast. Defaults to 1.0
+    pub speed: Option<f64>,
+    /// Cap on the pause between two messages, so long idle gaps don't stall the replay
+    pub max_delay_ms: Option<u64>,
+    /// Index of the first message to replay
+    pub start_index: Option<usize>,
+}
+
+/// A single replayed message
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayEvent {
+    pub replay_id: String,
+    pub index: usize,
+    pub total: usize,
+    /// Delay applied before this message, after speed scaling
+    pub delay_ms: u64,
+    /// Original timestamp of the message, if recorded
+    pub timestamp: Option<String>,
+    pub message: JsonValue,
+}
+
+/// Emitted once a replay finishes or is stopped
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayComplete {
+    pub replay_id: String,
+    pub messages_replayed: usize,
+    pub stopped: bool,
+}
+
+fn message_time(message: &JsonValue) -> Option<DateTime<Utc>> {
+    message
+        .get("timestamp")
+        .and_then(|t| t.as_str())
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Delay before each message, derived from the original timestamps
+///
+/// Messages without a timestamp (or out of order) are replayed immediately.
+pub fn replay_delays(messages: &[JsonValue], speed: f64, max_delay_ms: u64) -> Vec<u64> {
+    let speed = if speed.is_finite() && speed > 0.0 {
+        speed
+    } else {
+        1.0
+    };
+
+    let mut previous: Option<DateTime<Utc>> = None;
+    messages
+        .iter()
+        .map(|message| {
+            let time = message_time(message);
+            let delay = match (previous, time) {
+                (Some(prev), Some(time)) => {
+                    let gap = (time - prev).num_milliseconds().max(0) as f64;
+                    ((gap / speed) as u64).min(max_delay_ms)
+                }
+                _ => 0,
+            };
+            if time.is_some() {
+                previous = time;
+            }
+            delay
+        })
+        .collect()
+}
+
+/// Replay a past session's messages as events, keeping the original pacing
+///
+/// Emits `session-replay:{replay_id}` for every message and
+/// `session-replay-complete:{replay_id}` at the end. Returns the replay ID.
+#[tauri::command]
+pub async fn start_session_replay(
+    app: AppHandle,
+    state: State<'_, ReplayState>,
+    session_id: String,
+    project_id: String,
+    options: Option<ReplayOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let messages = load_session_history(session_id.clone(), project_id).await?;
+
+    let start_index = options.start_index.unwrap_or(0).min(messages.len());
+    let messages: Vec<JsonValue> = messages.into_iter().skip(start_index).collect();
+    let delays = replay_delays(
+        &messages,
+        options.speed.unwrap_or(1.0),
+        options.max_delay_ms.unwrap_or(DEFAULT_MAX_DELAY_MS),
+    );
+
+    let replay_id = uuid::Uuid::new_v4().to_string();
+    let stop = Arc::new(AtomicBool::new(false));
+    state
+        .replays
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(replay_id.clone(), stop.clone());
+
+    log::info!(
+        "Starting replay {} of session {} ({} messages)",
+        replay_id,
+        session_id,
+        messages.len()
+    );
+
+    let id = replay_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let total = start_index + messages.len();
+        let mut replayed = 0;
+
+        for (offset, (message, delay_ms)) in messages.into_iter().zip(delays).enumerate() {
+            if delay_ms > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+            }
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let event = ReplayEvent {
+                replay_id: id.clone(),
+                index: start_index + offset,
+                total,
+                delay_ms,
+                timestamp: message
+                    .get("timestamp")
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string),
+                message,
+            };
+            let _ = app.emit(&format!("session-replay:{}", id), &event);
+            replayed += 1;
+        }
+
+        let complete = ReplayComplete {
+            replay_id: id.clone(),
+            messages_replayed: replayed,
+            stopped: stop.load(Ordering::Relaxed),
+        };
+        event_bus::publish(&app, &format!("session-replay-complete:{}", id), &complete);
+
+        if let Some(state) = app.try_state::<ReplayState>() {
+            if let Ok(mut replays) = state.replays.lock() {
+                replays.remove(&id);
+            }
+        }
+    });
+
+    Ok(replay_id)
+}
+
+/// Stop a running replay
+#[tauri::command]
+pub async fn stop_session_replay(
+    state: State<'_, ReplayState>,
+    replay_id: String,
+) -> Result<bool, String> {
+    let replays = state.replays.lock().map_err(|e| e.to_string())?;
+    match replays.get(&replay_id) {
+        Some(stop) => {
+            stop.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replay_delays_scale_and_cap() {
+        let messages = vec![
+            json!({ "type": "user", "timestamp": "2025-01-01T00:00:00Z" }),
+            json!({ "type": "assistant", "timestamp": "2025-01-01T00:00:02Z" }),
+            json!({ "type": "summary" }),
+            json!({ "type": "assistant", "timestamp": "2025-01-01T00:01:00Z" }),
+        ];
+
+        assert_eq!(
+            replay_delays(&messages, 1.0, 10_000),
+            vec![0, 2000, 0, 10_000]
+        );
+        assert_eq!(
+            replay_delays(&messages, 2.0, 60_000),
+            vec![0, 1000, 0, 29_000]
+        );
+        // Invalid speeds fall back to real time
+        assert_eq!(replay_delays(&messages[..2], 0.0, 10_000), vec![0, 2000]);
+    }
+}
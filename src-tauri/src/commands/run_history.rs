@@ -0,0 +1,567 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::commands::agents::AgentDb;
+use crate::commands::settings as store;
+use crate::process::RunUsage;
+
+/// Default number of days a finished run is kept in the history
+const DEFAULT_MAX_AGE_DAYS: u32 = 90;
+/// Default maximum number of finished runs kept in the history
+const DEFAULT_MAX_RUNS: u32 = 1000;
+
+/// Represents a single run (interactive session or agent execution) in the history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub id: i64,
+    pub run_type: String, // 'session' or 'agent'
+    pub project_path: String,
+    pub agent_id: Option<i64>,
+    pub agent_name: Option<String>,
+    pub agent_run_id: Option<i64>,
+    pub session_id: Option<String>,
+    pub prompt: String,
+    pub model: String,
+    pub status: String, // 'running', 'completed', 'failed', 'cancelled', 'detached'
+    pub exit_code: Option<i32>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub total_tokens: Option<i64>,
+    pub cost_usd: Option<f64>,
+    pub output_path: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    /// Environment overrides the run was started with (JSON, secret values masked)
+    pub env_overrides: Option<String>,
+    /// Project roots besides `project_path` the run could reach
+    pub additional_dirs: Vec<String>,
+}
+
+/// Filter used when listing runs from the history
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunFilter {
+    pub project_path: Option<String>,
+    pub agent_id: Option<i64>,
+    pub run_type: Option<String>,
+    pub status: Option<String>,
+    pub model: Option<String>,
+    /// Only include runs started at or after this timestamp (YYYY-MM-DD HH:MM:SS)
+    pub started_after: Option<String>,
+    /// Only include runs started before this timestamp (YYYY-MM-DD HH:MM:SS)
+    pub started_before: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Pruning policy applied to finished runs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunPrunePolicy {
+    /// Remove finished runs older than this many days (None disables age pruning)
+    pub max_age_days: Option<u32>,
+    /// Keep at most this many finished runs (None disables count pruning)
+    pub max_runs: Option<u32>,
+}
+
+impl Default for RunPrunePolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(DEFAULT_MAX_AGE_DAYS),
+            max_runs: Some(DEFAULT_MAX_RUNS),
+        }
+    }
+}
+
+/// Information needed to record the start of a run
+#[derive(Debug, Clone)]
+pub struct NewRun {
+    pub run_type: &'static str,
+    pub project_path: String,
+    pub agent_id: Option<i64>,
+    pub agent_name: Option<String>,
+    pub agent_run_id: Option<i64>,
+    pub prompt: String,
+    pub model: String,
+    pub env_overrides: Option<String>,
+    pub additional_dirs: Vec<String>,
+}
+
+const RUN_COLUMNS: &str = "id, run_type, project_path, agent_id, agent_name, agent_run_id, session_id, prompt, model, status, exit_code, input_tokens, output_tokens, total_tokens, cost_usd, output_path, started_at, ended_at, env_overrides, additional_dirs";
+
+/// Create the run history table and its indexes
+pub fn init_run_history(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS run_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_type TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            agent_id INTEGER,
+            agent_name TEXT,
+            agent_run_id INTEGER,
+            session_id TEXT,
+            prompt TEXT NOT NULL,
+            model TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            exit_code INTEGER,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
+            total_tokens INTEGER,
+            cost_usd REAL,
+            output_path TEXT,
+            started_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            ended_at TEXT
+        )",
+        [],
+    )?;
+    let _ = conn.execute("ALTER TABLE run_history ADD COLUMN env_overrides TEXT", []);
+    // JSON run manifest, see run_manifest.rs
+    let _ = conn.execute("ALTER TABLE run_history ADD COLUMN manifest TEXT", []);
+    // JSON array of extra project roots, see workspace.rs
+    let _ = conn.execute(
+        "ALTER TABLE run_history ADD COLUMN additional_dirs TEXT",
+        [],
+    );
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_run_history_started_at ON run_history(started_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_run_history_session_id ON run_history(session_id)",
+        [],
+    )?;
+
+    // Runs still marked as running belong to a previous app instance and can no longer finish
+    conn.execute(
+        "UPDATE run_history SET status = 'failed', ended_at = CURRENT_TIMESTAMP WHERE status = 'running'",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn map_run_row(row: &Row) -> SqliteResult<RunRecord> {
+    Ok(RunRecord {
+        id: row.get(0)?,
+        run_type: row.get(1)?,
+        project_path: row.get(2)?,
+        agent_id: row.get(3)?,
+        agent_name: row.get(4)?,
+        agent_run_id: row.get(5)?,
+        session_id: row.get(6)?,
+        prompt: row.get(7)?,
+        model: row.get(8)?,
+        status: row.get(9)?,
+        exit_code: row.get(10)?,
+        input_tokens: row.get(11)?,
+        output_tokens: row.get(12)?,
+        total_tokens: row.get(13)?,
+        cost_usd: row.get(14)?,
+        output_path: row.get(15)?,
+        started_at: row.get(16)?,
+        ended_at: row.get(17)?,
+        env_overrides: row.get(18)?,
+        additional_dirs: row
+            .get::<_, Option<String>>(19)?
+            .and_then(|dirs| serde_json::from_str(&dirs).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// Record the start of a run and return its history ID
+pub fn record_run_started(conn: &Connection, run: &NewRun) -> SqliteResult<i64> {
+    conn.execute(
+        "INSERT INTO run_history (run_type, project_path, agent_id, agent_name, agent_run_id, prompt, model, env_overrides, additional_dirs, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'running')",
+        params![
+            run.run_type,
+            run.project_path,
+            run.agent_id,
+            run.agent_name,
+            run.agent_run_id,
+            run.prompt,
+            run.model,
+            run.env_overrides,
+            (!run.additional_dirs.is_empty())
+                .then(|| serde_json::to_string(&run.additional_dirs).ok())
+                .flatten()
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Extra project roots of every multi-root session, by session ID
+pub fn additional_dirs_by_session(conn: &Connection) -> SqliteResult<HashMap<String, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id, additional_dirs FROM run_history
+         WHERE session_id IS NOT NULL AND additional_dirs IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut sessions = HashMap::new();
+    for (session_id, dirs) in rows.flatten() {
+        if let Ok(dirs) = serde_json::from_str::<Vec<String>>(&dirs) {
+            sessions.insert(session_id, dirs);
+        }
+    }
+    Ok(sessions)
+}
+
+/// Attach the Claude session ID (and the JSONL transcript location) to a run
+pub fn record_run_session(
+    conn: &Connection,
+    history_id: i64,
+    session_id: &str,
+    project_path: &str,
+) -> SqliteResult<()> {
+    let output_path = session_output_path(session_id, project_path);
+    conn.execute(
+        "UPDATE run_history SET session_id = ?1, output_path = ?2 WHERE id = ?3",
+        params![session_id, output_path, history_id],
+    )?;
+    Ok(())
+}
+
+/// Record the end of a run with the usage its output reported. Runs that were
+/// already finished (e.g. cancelled) are left untouched.
+pub fn record_run_finished(
+    conn: &Connection,
+    history_id: i64,
+    status: &str,
+    exit_code: Option<i32>,
+    usage: &RunUsage,
+) -> SqliteResult<()> {
+    let positive = |tokens: i64| Some(tokens).filter(|t| *t > 0);
+    conn.execute(
+        "UPDATE run_history
+         SET status = ?1, exit_code = ?2, input_tokens = ?3, output_tokens = ?4, total_tokens = ?5,
+             cost_usd = ?6, ended_at = CURRENT_TIMESTAMP
+         WHERE id = ?7 AND status = 'running'",
+        params![
+            status,
+            exit_code,
+            positive(usage.input_tokens),
+            positive(usage.output_tokens),
+            positive(usage.input_tokens + usage.output_tokens),
+            Some(usage.cost_usd).filter(|c| *c > 0.0),
+            history_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Mark the running history entry for a Claude session as cancelled
+pub fn record_session_cancelled(conn: &Connection, session_id: &str) -> SqliteResult<usize> {
+    conn.execute(
+        "UPDATE run_history SET status = 'cancelled', ended_at = CURRENT_TIMESTAMP
+         WHERE session_id = ?1 AND status = 'running'",
+        params![session_id],
+    )
+}
+
+/// Mark the running history entry for an agent run as cancelled
+pub fn record_agent_run_cancelled(conn: &Connection, agent_run_id: i64) -> SqliteResult<usize> {
+    conn.execute(
+        "UPDATE run_history SET status = 'cancelled', ended_at = CURRENT_TIMESTAMP
+         WHERE agent_run_id = ?1 AND status = 'running'",
+        params![agent_run_id],
+    )
+}
+
+/// Location of the JSONL transcript Claude Code writes for a session
+fn session_output_path(session_id: &str, project_path: &str) -> Option<String> {
+    crate::utils::get_claude_dir().ok().map(|claude_dir| {
+        claude_dir
+            .join("projects")
+            .join(crate::utils::encode_project_path(project_path))
+            .join(format!("{}.jsonl", session_id))
+            .to_string_lossy()
+            .to_string()
+    })
+}
+
+/// Query runs matching a filter, newest first
+pub fn query_runs(conn: &Connection, filter: &RunFilter) -> SqliteResult<Vec<RunRecord>> {
+    let mut conditions: Vec<&str> = Vec::new();
+    let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(project_path) = &filter.project_path {
+        conditions.push("project_path = ?");
+        values.push(Box::new(project_path.clone()));
+    }
+    if let Some(agent_id) = filter.agent_id {
+        conditions.push("agent_id = ?");
+        values.push(Box::new(agent_id));
+    }
+    if let Some(run_type) = &filter.run_type {
+        conditions.push("run_type = ?");
+        values.push(Box::new(run_type.clone()));
+    }
+    if let Some(status) = &filter.status {
+        conditions.push("status = ?");
+        values.push(Box::new(status.clone()));
+    }
+    if let Some(model) = &filter.model {
+        conditions.push("model = ?");
+        values.push(Box::new(model.clone()));
+    }
+    if let Some(started_after) = &filter.started_after {
+        conditions.push("started_at >= ?");
+        values.push(Box::new(started_after.clone()));
+    }
+    if let Some(started_before) = &filter.started_before {
+        conditions.push("started_at < ?");
+        values.push(Box::new(started_before.clone()));
+    }
+
+    let mut query = format!("SELECT {} FROM run_history", RUN_COLUMNS);
+    if !conditions.is_empty() {
+        query.push_str(" WHERE ");
+        query.push_str(&conditions.join(" AND "));
+    }
+    query.push_str(" ORDER BY started_at DESC, id DESC LIMIT ? OFFSET ?");
+    values.push(Box::new(filter.limit.unwrap_or(-1)));
+    values.push(Box::new(filter.offset.unwrap_or(0)));
+
+    let mut stmt = conn.prepare(&query)?;
+    let runs = stmt
+        .query_map(
+            rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+            map_run_row,
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(runs)
+}
+
+/// Load the pruning policy from app settings, falling back to defaults
+pub fn load_prune_policy(conn: &Connection) -> RunPrunePolicy {
+    let defaults = RunPrunePolicy::default();
+    // A stored 0 turns the limit off
+    let limit = |key: &str, default: Option<u32>| match store::get_integer(conn, key) {
+        Some(value) => u32::try_from(value).ok().filter(|v| *v > 0),
+        None => default,
+    };
+    RunPrunePolicy {
+        max_age_days: limit("run_history_max_age_days", defaults.max_age_days),
+        max_runs: limit("run_history_max_runs", defaults.max_runs),
+    }
+}
+
+/// Delete finished runs according to the pruning policy, returning the number removed
+pub fn prune_run_history(conn: &Connection, policy: &RunPrunePolicy) -> SqliteResult<usize> {
+    let mut removed = 0;
+
+    if let Some(days) = policy.max_age_days {
+        removed += conn.execute(
+            "DELETE FROM run_history WHERE status != 'running' AND started_at < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?;
+    }
+
+    if let Some(max_runs) = policy.max_runs {
+        removed += conn.execute(
+            "DELETE FROM run_history WHERE status != 'running' AND id NOT IN (
+                SELECT id FROM run_history WHERE status != 'running'
+                ORDER BY started_at DESC, id DESC LIMIT ?1
+            )",
+            params![max_runs],
+        )?;
+    }
+
+    Ok(removed)
+}
+
+/// List runs from the persistent history
+#[tauri::command]
+pub async fn list_runs(
+    db: State<'_, AgentDb>,
+    filter: Option<RunFilter>,
+) -> Result<Vec<RunRecord>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    query_runs(&conn, &filter.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Get a single run from the persistent history
+#[tauri::command]
+pub async fn get_run(db: State<'_, AgentDb>, run_id: i64) -> Result<RunRecord, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM run_history WHERE id = ?1", RUN_COLUMNS),
+        params![run_id],
+        map_run_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Run not found: {}", run_id))
+}
+
+/// Delete a run from the persistent history
+#[tauri::command]
+pub async fn delete_run(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM run_history WHERE id = ?1", params![run_id])
+        .map_err(|e| e.to_string())?;
+
+    if deleted == 0 {
+        return Err(format!("Run not found: {}", run_id));
+    }
+    Ok(())
+}
+
+/// Get the run history pruning policy
+#[tauri::command]
+pub async fn get_run_prune_policy(db: State<'_, AgentDb>) -> Result<RunPrunePolicy, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_prune_policy(&conn))
+}
+
+/// Save the run history pruning policy and apply it immediately
+#[tauri::command]
+pub async fn save_run_prune_policy(
+    db: State<'_, AgentDb>,
+    policy: RunPrunePolicy,
+) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store::set_value(
+        &conn,
+        "run_history_max_age_days",
+        &policy.max_age_days.unwrap_or(0).into(),
+    )?;
+    store::set_value(
+        &conn,
+        "run_history_max_runs",
+        &policy.max_runs.unwrap_or(0).into(),
+    )?;
+
+    prune_run_history(&conn, &policy).map_err(|e| e.to_string())
+}
+
+/// Prune the run history using the stored policy
+#[tauri::command]
+pub async fn prune_runs(db: State<'_, AgentDb>) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let policy = load_prune_policy(&conn);
+    prune_run_history(&conn, &policy).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        init_run_history(&conn).unwrap();
+        conn
+    }
+
+    fn new_run(project_path: &str) -> NewRun {
+        NewRun {
+            run_type: "session",
+            project_path: project_path.to_string(),
+            agent_id: None,
+            agent_name: None,
+            agent_run_id: None,
+            prompt: "hello".to_string(),
+            model: "sonnet".to_string(),
+            env_overrides: None,
+            additional_dirs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_run_lifecycle() {
+        let conn = setup();
+        let id = record_run_started(&conn, &new_run("/tmp/project")).unwrap();
+        record_run_session(&conn, id, "abc", "/tmp/project").unwrap();
+
+        let output =
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#;
+        let usage = RunUsage::from_jsonl(output);
+        record_run_finished(&conn, id, "completed", Some(0), &usage).unwrap();
+
+        let runs = query_runs(&conn, &RunFilter::default()).unwrap();
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!(run.status, "completed");
+        assert_eq!(run.session_id.as_deref(), Some("abc"));
+        assert_eq!(run.input_tokens, Some(10));
+        assert_eq!(run.output_tokens, Some(5));
+        assert_eq!(run.total_tokens, Some(15));
+        assert!(run.ended_at.is_some());
+    }
+
+    #[test]
+    fn test_cancelled_run_is_not_overwritten() {
+        let conn = setup();
+        let id = record_run_started(&conn, &new_run("/tmp/project")).unwrap();
+        record_run_session(&conn, id, "abc", "/tmp/project").unwrap();
+        assert_eq!(record_session_cancelled(&conn, "abc").unwrap(), 1);
+
+        record_run_finished(&conn, id, "failed", Some(1), &RunUsage::default()).unwrap();
+
+        let runs = query_runs(&conn, &RunFilter::default()).unwrap();
+        assert_eq!(runs[0].status, "cancelled");
+    }
+
+    #[test]
+    fn test_query_runs_filter() {
+        let conn = setup();
+        record_run_started(&conn, &new_run("/a")).unwrap();
+        record_run_started(&conn, &new_run("/b")).unwrap();
+        record_run_started(&conn, &new_run("/b")).unwrap();
+
+        let filter = RunFilter {
+            project_path: Some("/b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_runs(&conn, &filter).unwrap().len(), 2);
+
+        let filter = RunFilter {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(query_runs(&conn, &filter).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_keeps_running_and_newest() {
+        let conn = setup();
+        for _ in 0..3 {
+            let id = record_run_started(&conn, &new_run("/a")).unwrap();
+            record_run_finished(&conn, id, "completed", Some(0), &RunUsage::default()).unwrap();
+        }
+        record_run_started(&conn, &new_run("/a")).unwrap();
+
+        let policy = RunPrunePolicy {
+            max_age_days: None,
+            max_runs: Some(1),
+        };
+        assert_eq!(prune_run_history(&conn, &policy).unwrap(), 2);
+
+        let runs = query_runs(&conn, &RunFilter::default()).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().any(|r| r.status == "running"));
+    }
+
+    #[test]
+    fn test_load_prune_policy_zero_disables() {
+        let conn = setup();
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES ('run_history_max_runs', '0')",
+            [],
+        )
+        .unwrap();
+
+        let policy = load_prune_policy(&conn);
+        assert_eq!(policy.max_runs, None);
+        assert_eq!(policy.max_age_days, Some(DEFAULT_MAX_AGE_DAYS));
+    }
+}
@@ -0,0 +1,450 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+use super::agents::{execute_agent, get_agent, AgentDb};
+use super::claude::execute_claude_code;
+use super::run_history::NewRun;
+use super::skills::git_blob_sha;
+use crate::claude_binary::get_claude_version;
+use crate::run_env::{mask_env_overrides, validate_env_overrides, EnvOverrides};
+use crate::workspace::additional_dirs_from_args;
+
+/// Bumped when fields change meaning
+const MANIFEST_VERSION: u32 = 1;
+
+/// Value `mask_env_overrides` leaves in place of secrets
+const MASKED_VALUE: &str = "***";
+
+/// The program a run executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryInfo {
+    pub path: String,
+    pub version: Option<String>,
+}
+
+/// A context file Claude reads at startup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextFileHash {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// An installed skill, identified by the git blob SHA of its SKILL.md
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillVersion {
+    pub name: String,
+    /// "project" or "user"
+    pub scope: String,
+    pub sha: String,
+}
+
+/// Everything needed to reproduce or audit a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub manifest_version: u32,
+    pub created_at: String,
+    pub run_type: String,
+    pub project_path: String,
+    pub agent_id: Option<i64>,
+    pub agent_name: Option<String>,
+    pub binary: BinaryInfo,
+    pub args: Vec<String>,
+    pub model: String,
+    pub prompt: String,
+    pub system_prompt: Option<String>,
+    pub context_files: Vec<ContextFileHash>,
+    /// Secret-looking values are masked and must be supplied again to rerun
+    pub env_overrides: EnvOverrides,
+    pub skills: Vec<SkillVersion>,
+}
+
+/// A rerun that was started, and how its inputs differ from the original
+#[derive(Debug, Clone, Serialize)]
+pub struct RerunResult {
+    pub run_type: String,
+    /// Set for agent runs; sessions report through the usual session events
+    pub agent_run_id: Option<i64>,
+    pub differences: Vec<String>,
+}
+
+fn sha256_file(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&content)))
+}
+
+/// CLAUDE.md files Claude loads when starting in `project_path`
+///
+/// The user file, then every directory from the filesystem root down to the
+/// project. Files in subdirectories are only read on demand and are left out.
+fn context_file_paths(project_path: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(".claude").join("CLAUDE.md"));
+    }
+    let mut dirs: Vec<&Path> = project_path.ancestors().collect();
+    dirs.reverse();
+    for dir in dirs {
+        paths.push(dir.join("CLAUDE.md"));
+        paths.push(dir.join("CLAUDE.local.md"));
+    }
+    paths.push(project_path.join(".claude").join("CLAUDE.md"));
+    paths
+}
+
+fn hash_context_files(project_path: &Path) -> Vec<ContextFileHash> {
+    context_file_paths(project_path)
+        .into_iter()
+        .filter_map(|path| {
+            sha256_file(&path).map(|sha256| ContextFileHash {
+                path: path.to_string_lossy().to_string(),
+                sha256,
+            })
+        })
+        .collect()
+}
+
+fn installed_skills(project_path: &Path) -> Vec<SkillVersion> {
+    let mut roots = vec![(project_path.join(".claude").join("skills"), "project")];
+    if let Some(home) = dirs::home_dir() {
+        roots.push((home.join(".claude").join("skills"), "user"));
+    }
+
+    let mut skills = Vec::new();
+    for (root, scope) in roots {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(content) = fs::read(entry.path().join("SKILL.md")) else {
+                continue;
+            };
+            skills.push(SkillVersion {
+                name: entry.file_name().to_string_lossy().to_string(),
+                scope: scope.to_string(),
+                sha: git_blob_sha(&content),
+            });
+        }
+    }
+    skills.sort_by(|a, b| (&a.scope, &a.name).cmp(&(&b.scope, &b.name)));
+    skills
+}
+
+impl RunManifest {
+    /// Describe a run about to start; runs `<binary> --version`, so keep it off async threads
+    pub fn capture(run: &NewRun, binary: &str, args: Vec<String>, env: &EnvOverrides) -> Self {
+        let project_path = Path::new(&run.project_path);
+        let system_prompt = args
+            .iter()
+            .position(|a| a == "--system-prompt")
+            .and_then(|i| args.get(i + 1))
+            .cloned();
+        RunManifest {
+            manifest_version: MANIFEST_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            run_type: run.run_type.to_string(),
+            project_path: run.project_path.clone(),
+            agent_id: run.agent_id,
+            agent_name: run.agent_name.clone(),
+            binary: BinaryInfo {
+                path: binary.to_string(),
+                version: get_claude_version(binary).ok().flatten(),
+            },
+            args,
+            model: run.model.clone(),
+            prompt: run.prompt.clone(),
+            system_prompt,
+            context_files: hash_context_files(project_path),
+            env_overrides: mask_env_overrides(env),
+            skills: installed_skills(project_path),
+        }
+    }
+
+    /// Differences in `current` that could make a rerun behave differently
+    pub fn differences(&self, current: &RunManifest) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.binary.version != current.binary.version {
+            differences.push(format!(
+                "Claude version changed from {} to {}",
+                self.binary.version.as_deref().unwrap_or("unknown"),
+                current.binary.version.as_deref().unwrap_or("unknown")
+            ));
+        }
+        if self.system_prompt != current.system_prompt {
+            differences.push("The agent's system prompt changed".to_string());
+        }
+        if self.args.iter().any(|a| a == "--resume" || a == "-c") {
+            differences.push(
+                "The original run continued an earlier conversation; the rerun starts a new one"
+                    .to_string(),
+            );
+        }
+
+        for file in &self.context_files {
+            match current.context_files.iter().find(|f| f.path == file.path) {
+                Some(now) if now.sha256 == file.sha256 => {}
+                Some(_) => differences.push(format!("{} changed", file.path)),
+                None => differences.push(format!("{} was removed", file.path)),
+            }
+        }
+        for file in &current.context_files {
+            if !self.context_files.iter().any(|f| f.path == file.path) {
+                differences.push(format!("{} was added", file.path));
+            }
+        }
+
+        for skill in &self.skills {
+            let same = |s: &&SkillVersion| s.name == skill.name && s.scope == skill.scope;
+            match current.skills.iter().find(same) {
+                Some(now) if now.sha == skill.sha => {}
+                Some(_) => differences.push(format!("Skill '{}' was updated", skill.name)),
+                None => differences.push(format!("Skill '{}' is no longer installed", skill.name)),
+            }
+        }
+        for skill in &current.skills {
+            if !self
+                .skills
+                .iter()
+                .any(|s| s.name == skill.name && s.scope == skill.scope)
+            {
+                differences.push(format!("Skill '{}' was installed since", skill.name));
+            }
+        }
+        differences
+    }
+}
+
+/// Capture the manifest of a started run and store it with the run history entry
+///
+/// Done on a blocking thread since asking the binary for its version can take a moment.
+pub fn record_manifest_in_background(
+    app: &AppHandle,
+    history_id: i64,
+    run: NewRun,
+    binary: String,
+    args: Vec<String>,
+    env: EnvOverrides,
+) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = RunManifest::capture(&run, &binary, args, &env);
+        let json = match serde_json::to_string(&manifest) {
+            Ok(json) => json,
+            Err(e) => {
+                log::warn!("Failed to serialize manifest of run {}: {}", history_id, e);
+                return;
+            }
+        };
+        let db = app.state::<AgentDb>();
+        let Ok(conn) = db.0.lock() else {
+            return;
+        };
+        if let Err(e) = conn.execute(
+            "UPDATE run_history SET manifest = ?1 WHERE id = ?2",
+            params![json, history_id],
+        ) {
+            log::warn!("Failed to record manifest of run {}: {}", history_id, e);
+        }
+    });
+}
+
+fn load_manifest(conn: &Connection, run_id: i64) -> Result<Option<RunManifest>, String> {
+    let raw: Option<Option<String>> = conn
+        .query_row(
+            "SELECT manifest FROM run_history WHERE id = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match raw {
+        None => Err(format!("Run not found: {}", run_id)),
+        Some(None) => Ok(None),
+        Some(Some(json)) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Invalid manifest for run {}: {}", run_id, e)),
+    }
+}
+
+/// Get the manifest of a run, if one was recorded
+#[tauri::command]
+pub async fn get_run_manifest(
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<Option<RunManifest>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_manifest(&conn, run_id)
+}
+
+/// Start a new run with the inputs recorded in a past run's manifest
+///
+/// `env` supplies values for masked environment overrides and may replace
+/// others. With `strict`, the rerun is refused if the binary, context files,
+/// skills or agent changed since the original run; otherwise the differences
+/// are returned alongside the new run.
+#[tauri::command]
+pub async fn rerun_from_manifest(
+    window: tauri::Window,
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    registry: State<'_, crate::process::ProcessRegistryState>,
+    run_id: i64,
+    env: Option<EnvOverrides>,
+    strict: Option<bool>,
+) -> Result<RerunResult, String> {
+    let manifest = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_manifest(&conn, run_id)?
+    }
+    .ok_or_else(|| format!("Run {} has no manifest to rerun from", run_id))?;
+
+    let mut overrides = manifest.env_overrides.clone();
+    overrides.extend(env.unwrap_or_default());
+    let missing: Vec<&str> = overrides
+        .iter()
+        .filter(|(_, value)| value.as_str() == MASKED_VALUE)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Provide values for the masked environment variables: {}",
+            missing.join(", ")
+        ));
+    }
+    validate_env_overrides(&overrides)?;
+
+    let mut args = manifest.args.clone();
+    if let Some(agent_id) = manifest.agent_id {
+        let agent = get_agent(db.clone(), agent_id).await?;
+        if let Some(i) = args.iter().position(|a| a == "--system-prompt") {
+            if let Some(prompt) = args.get_mut(i + 1) {
+                *prompt = agent.system_prompt;
+            }
+        }
+    }
+    let run = NewRun {
+        run_type: if manifest.run_type == "agent" {
+            "agent"
+        } else {
+            "session"
+        },
+        project_path: manifest.project_path.clone(),
+        agent_id: manifest.agent_id,
+        agent_name: manifest.agent_name.clone(),
+        agent_run_id: None,
+        prompt: manifest.prompt.clone(),
+        model: manifest.model.clone(),
+        env_overrides: None,
+        additional_dirs: additional_dirs_from_args(&manifest.args),
+    };
+    let binary = crate::claude_binary::find_claude_binary(&app)?;
+    let current = tauri::async_runtime::spawn_blocking(move || {
+        RunManifest::capture(&run, &binary, args, &EnvOverrides::new())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let differences = manifest.differences(&current);
+    if strict.unwrap_or(false) && !differences.is_empty() {
+        return Err(format!(
+            "Run {} can't be reproduced exactly: {}",
+            run_id,
+            differences.join("; ")
+        ));
+    }
+
+    let additional_dirs = additional_dirs_from_args(&manifest.args);
+    let agent_run_id = match manifest.agent_id {
+        Some(agent_id) => Some(
+            execute_agent(
+                window,
+                app,
+                agent_id,
+                manifest.project_path,
+                manifest.prompt,
+                Some(manifest.model),
+                Some(overrides),
+                Some(additional_dirs),
+                db,
+                registry,
+            )
+            .await?,
+        ),
+        None => {
+            execute_claude_code(
+                window,
+                app,
+                manifest.project_path,
+                manifest.prompt,
+                manifest.model,
+                Some(overrides),
+                Some(additional_dirs),
+            )
+            .await?;
+            None
+        }
+    };
+
+    Ok(RerunResult {
+        run_type: manifest.run_type,
+        agent_run_id,
+        differences,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> RunManifest {
+        RunManifest {
+            manifest_version: MANIFEST_VERSION,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            run_type: "agent".to_string(),
+            project_path: "/tmp/project".to_string(),
+            agent_id: Some(1),
+            agent_name: Some("reviewer".to_string()),
+            binary: BinaryInfo {
+                path: "/usr/local/bin/claude".to_string(),
+                version: Some("1.0.41".to_string()),
+            },
+            args: vec!["--system-prompt".to_string(), "Review".to_string()],
+            model: "sonnet".to_string(),
+            prompt: "Review the diff".to_string(),
+            system_prompt: Some("Review".to_string()),
+            context_files: vec![ContextFileHash {
+                path: "/tmp/project/CLAUDE.md".to_string(),
+                sha256: "aa".to_string(),
+            }],
+            env_overrides: EnvOverrides::new(),
+            skills: vec![SkillVersion {
+                name: "pdf".to_string(),
+                scope: "project".to_string(),
+                sha: "bb".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_manifest_differences() {
+        let original = manifest();
+        assert!(original.differences(&manifest()).is_empty());
+
+        let mut current = manifest();
+        current.binary.version = Some("1.0.50".to_string());
+        current.context_files[0].sha256 = "cc".to_string();
+        current.skills.clear();
+        let differences = original.differences(&current);
+        assert_eq!(
+            differences,
+            vec![
+                "Claude version changed from 1.0.41 to 1.0.50",
+                "/tmp/project/CLAUDE.md changed",
+                "Skill 'pdf' is no longer installed",
+            ]
+        );
+    }
+}
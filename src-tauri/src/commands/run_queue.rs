@@ -0,0 +1,50 @@
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use crate::run_env::EnvOverrides;
+use crate::run_queue::{self, QueuedRun};
+
+/// Runs waiting for a project lock or held for the user to start or dismiss
+#[tauri::command]
+pub async fn list_queued_runs(db: State<'_, AgentDb>) -> Result<Vec<QueuedRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    run_queue::list_queued(&conn).map_err(|e| e.to_string())
+}
+
+/// Start a held run now; returns the ID of the agent run
+///
+/// `env` gives the values of the run's `env_keys`, which aren't stored.
+#[tauri::command]
+pub async fn start_queued_run(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+    env: Option<EnvOverrides>,
+) -> Result<i64, String> {
+    let run = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        run_queue::get_queued_run(&conn, id)?
+    };
+    if run.status != "held" {
+        return Err(format!(
+            "Queued run {} is waiting for its project and starts on its own",
+            id
+        ));
+    }
+    run_queue::start_queued(&app, run, env.unwrap_or_default()).await
+}
+
+/// Drop a held run without starting it
+#[tauri::command]
+pub async fn dismiss_queued_run(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let run = run_queue::get_queued_run(&conn, id)?;
+    if run.status != "held" {
+        return Err(format!(
+            "Queued run {} is waiting for its project; cancel its agent run instead",
+            id
+        ));
+    }
+    run_queue::delete_queued(&conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
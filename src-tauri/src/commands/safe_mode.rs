@@ -0,0 +1,30 @@
+use tauri::AppHandle;
+
+use crate::safe_mode::{self, LaunchState};
+
+/// Whether the last launch crashed and whether this one is in safe mode
+#[tauri::command]
+pub fn get_safe_mode_status() -> LaunchState {
+    safe_mode::launch_state()
+}
+
+fn restart(app: &AppHandle, safe: bool) -> Result<(), String> {
+    safe_mode::request_safe_mode(safe)?;
+    // `restart` doesn't emit the exit event, so shut down here to leave a clean marker
+    crate::shutdown::shutdown(app);
+    app.restart()
+}
+
+/// Restart the app in safe mode
+///
+/// Runs still in progress are handled as on a normal exit.
+#[tauri::command]
+pub fn restart_in_safe_mode(app: AppHandle) -> Result<(), String> {
+    restart(&app, true)
+}
+
+/// Restart the app with plugins and background tasks enabled again
+#[tauri::command]
+pub fn restart_normally(app: AppHandle) -> Result<(), String> {
+    restart(&app, false)
+}
@@ -0,0 +1,77 @@
+use tauri::State;
+
+use super::agents::AgentDb;
+use crate::sandbox::profile;
+use crate::sandbox::{ExecutionProfile, SandboxCapabilities};
+
+/// List all execution profiles
+#[tauri::command]
+pub async fn list_execution_profiles(
+    db: State<'_, AgentDb>,
+) -> Result<Vec<ExecutionProfile>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    profile::list_profiles(&conn).map_err(|e| e.to_string())
+}
+
+/// Create or update an execution profile
+#[tauri::command]
+pub async fn save_execution_profile(
+    db: State<'_, AgentDb>,
+    profile: ExecutionProfile,
+) -> Result<ExecutionProfile, String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if let Some(path) = profile
+        .allowed_paths
+        .iter()
+        .find(|p| !std::path::Path::new(p).is_absolute())
+    {
+        return Err(format!(
+            "Allowed directory must be an absolute path: {}",
+            path
+        ));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let id = profile::save_profile(&conn, &profile)
+        .map_err(|e| format!("Failed to save execution profile: {}", e))?;
+
+    profile::get_profile(&conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Execution profile not found".to_string())
+}
+
+/// Delete an execution profile
+#[tauri::command]
+pub async fn delete_execution_profile(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    profile::delete_profile(&conn, id).map_err(|e| e.to_string())
+}
+
+/// Get the execution profile assigned to an agent
+#[tauri::command]
+pub async fn get_agent_execution_profile(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<Option<ExecutionProfile>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    profile::profile_for_agent(&conn, agent_id).map_err(|e| e.to_string())
+}
+
+/// Assign an execution profile to an agent, or clear it
+#[tauri::command]
+pub async fn set_agent_execution_profile(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    profile_id: Option<i64>,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    profile::set_agent_profile(&conn, agent_id, profile_id).map_err(|e| e.to_string())
+}
+
+/// Report which isolation primitives are available on this platform
+#[tauri::command]
+pub async fn get_sandbox_capabilities() -> Result<SandboxCapabilities, String> {
+    Ok(SandboxCapabilities::detect())
+}
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use super::agents::AgentDb;
+use crate::path_validation::validate_project_root;
+use crate::scheduler;
+
+/// An agent run that repeats on a cron schedule while the app is open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: i64,
+    pub name: String,
+    pub project_path: String,
+    pub agent_id: i64,
+    pub agent_name: Option<String>,
+    pub prompt: String,
+    /// Overrides the agent's model when set
+    pub model: Option<String>,
+    /// Five-field cron expression, evaluated in local time
+    pub cron: String,
+    pub enabled: bool,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub last_agent_run_id: Option<i64>,
+    /// Status of the last agent run, from agent_runs
+    pub last_run_status: Option<String>,
+    /// Why the last run failed to start
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+/// Fields needed to create a schedule
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSchedule {
+    pub name: String,
+    pub project_path: String,
+    pub agent_id: i64,
+    pub prompt: String,
+    pub model: Option<String>,
+    pub cron: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Create the schedules table
+pub fn init_schedules(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            agent_id INTEGER NOT NULL,
+            prompt TEXT NOT NULL,
+            model TEXT,
+            cron TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            next_run_at TEXT,
+            last_run_at TEXT,
+            last_agent_run_id INTEGER,
+            last_error TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+const SCHEDULE_QUERY: &str = "SELECT s.id, s.name, s.project_path, s.agent_id, a.name, s.prompt, s.model, s.cron, s.enabled, s.next_run_at, s.last_run_at, s.last_agent_run_id, r.status, s.last_error, s.created_at
+     FROM schedules s
+     LEFT JOIN agents a ON a.id = s.agent_id
+     LEFT JOIN agent_runs r ON r.id = s.last_agent_run_id";
+
+fn row_to_schedule(row: &Row) -> SqliteResult<Schedule> {
+    Ok(Schedule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        project_path: row.get(2)?,
+        agent_id: row.get(3)?,
+        agent_name: row.get(4)?,
+        prompt: row.get(5)?,
+        model: row.get(6)?,
+        cron: row.get(7)?,
+        enabled: row.get(8)?,
+        next_run_at: row.get(9)?,
+        last_run_at: row.get(10)?,
+        last_agent_run_id: row.get(11)?,
+        last_run_status: row.get(12)?,
+        last_error: row.get(13)?,
+        created_at: row.get(14)?,
+    })
+}
+
+pub(crate) fn get_schedule(conn: &Connection, id: i64) -> Result<Schedule, String> {
+    conn.query_row(
+        &format!("{} WHERE s.id = ?1", SCHEDULE_QUERY),
+        params![id],
+        row_to_schedule,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Schedule {} not found", id))
+}
+
+/// Enabled schedules whose next run is at or before `now`, with the time each was due
+///
+/// Moves each one's next run past `now`, whether or not the caller ends up
+/// running it.
+pub fn due_schedules(
+    conn: &Connection,
+    now: DateTime<Utc>,
+) -> SqliteResult<Vec<(Schedule, DateTime<Utc>)>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE s.enabled = 1 AND s.next_run_at IS NOT NULL AND s.next_run_at <= ?1",
+        SCHEDULE_QUERY
+    ))?;
+    let schedules = stmt
+        .query_map(
+            params![now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)],
+            row_to_schedule,
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut due = Vec::new();
+    for schedule in schedules {
+        let next = scheduler::next_run_at(&schedule.cron).unwrap_or_else(|e| {
+            log::warn!("Disabling schedule '{}': {}", schedule.name, e);
+            None
+        });
+        conn.execute(
+            "UPDATE schedules SET next_run_at = ?1 WHERE id = ?2",
+            params![next, schedule.id],
+        )?;
+        let scheduled_for = schedule
+            .next_run_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or(now);
+        due.push((schedule, scheduled_for));
+    }
+    Ok(due)
+}
+
+/// Remember the outcome of starting a scheduled run
+pub fn record_schedule_run(
+    conn: &Connection,
+    id: i64,
+    result: &Result<i64, String>,
+) -> SqliteResult<()> {
+    let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    match result {
+        Ok(run_id) => conn.execute(
+            "UPDATE schedules SET last_run_at = ?1, last_agent_run_id = ?2, last_error = NULL WHERE id = ?3",
+            params![now, run_id, id],
+        )?,
+        Err(e) => conn.execute(
+            "UPDATE schedules SET last_run_at = ?1, last_error = ?2 WHERE id = ?3",
+            params![now, e, id],
+        )?,
+    };
+    Ok(())
+}
+
+/// Create a schedule for an agent; the first run is at the next cron match
+#[tauri::command]
+pub async fn create_schedule(
+    db: State<'_, AgentDb>,
+    schedule: NewSchedule,
+) -> Result<Schedule, String> {
+    let name = schedule.name.trim();
+    if name.is_empty() {
+        return Err("Schedule name is empty".to_string());
+    }
+    if schedule.prompt.trim().is_empty() {
+        return Err("Schedule prompt is empty".to_string());
+    }
+    let next_run_at = scheduler::next_run_at(&schedule.cron)?
+        .ok_or_else(|| format!("Cron expression '{}' never matches", schedule.cron))?;
+    let project_path = validate_project_root(&schedule.project_path)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let agent_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM agents WHERE id = ?1)",
+            params![schedule.agent_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !agent_exists {
+        return Err(format!("Agent {} not found", schedule.agent_id));
+    }
+
+    conn.execute(
+        "INSERT INTO schedules (name, project_path, agent_id, prompt, model, cron, enabled, next_run_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            name,
+            project_path.to_string_lossy(),
+            schedule.agent_id,
+            schedule.prompt,
+            schedule.model.filter(|m| !m.trim().is_empty()),
+            schedule.cron.trim(),
+            schedule.enabled,
+            next_run_at,
+        ],
+    )
+    .map_err(|e| format!("Failed to create schedule: {}", e))?;
+    get_schedule(&conn, conn.last_insert_rowid())
+}
+
+/// List all schedules, soonest next run first
+#[tauri::command]
+pub async fn list_schedules(db: State<'_, AgentDb>) -> Result<Vec<Schedule>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} ORDER BY s.next_run_at IS NULL, s.next_run_at, s.id",
+            SCHEDULE_QUERY
+        ))
+        .map_err(|e| e.to_string())?;
+    let schedules = stmt
+        .query_map([], row_to_schedule)
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(schedules)
+}
+
+/// Delete a schedule; runs it already started are kept in the run history
+#[tauri::command]
+pub async fn delete_schedule(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM schedules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Schedule {} not found", id));
+    }
+    Ok(())
+}
+
+/// Run a schedule immediately, without changing when it next runs
+///
+/// Returns the ID of the agent run.
+#[tauri::command]
+pub async fn run_schedule_now(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+) -> Result<i64, String> {
+    let schedule = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        get_schedule(&conn, id)?
+    };
+    scheduler::run_schedule(&app, &schedule).await
+}
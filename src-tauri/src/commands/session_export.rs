@@ -0,0 +1,487 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::bookmarks::find_session_project;
+use super::claude::load_session_history;
+use crate::file_audit::{self, FileOperation};
+
+/// Format version of JSON exports
+const SESSION_EXPORT_VERSION: u32 = 1;
+
+/// What to hide in an export before it is shared
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// Show the project as `<project>` and home directories as `~`
+    pub redact_paths: bool,
+    /// Replace API keys, tokens and private keys with `[REDACTED]`; always on
+    /// while redaction is enabled in settings
+    pub redact_secrets: bool,
+}
+
+/// A piece of a message
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: JsonValue,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        is_error: bool,
+    },
+}
+
+/// A user or assistant message with its text, tool calls and tool results
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportMessage {
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub blocks: Vec<ExportBlock>,
+}
+
+/// The normalized transcript written by the JSON format
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionExport {
+    pub version: u32,
+    pub session_id: String,
+    pub project_path: Option<String>,
+    pub exported_at: String,
+    pub messages: Vec<ExportMessage>,
+}
+
+/// What `export_session` wrote
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionExportResult {
+    pub path: String,
+    pub messages: usize,
+    pub bytes: u64,
+}
+
+/// Home directories on macOS, Linux and Windows
+fn home_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?:/Users|/home|[A-Za-z]:\\Users)[/\\][^/\\\s]+").expect("valid pattern")
+    })
+}
+
+/// Applies the redactions chosen in `ExportOptions`
+struct Redactor<'a> {
+    options: &'a ExportOptions,
+    project_path: Option<&'a str>,
+}
+
+impl Redactor<'_> {
+    fn text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.options.redact_secrets {
+            text = crate::redaction::redactor()
+                .redact_text(&text, &mut Default::default())
+                .into_owned();
+        }
+        if self.options.redact_paths {
+            if let Some(project) = self.project_path.filter(|p| p.len() > 1) {
+                text = text.replace(project, "<project>");
+            }
+            text = home_pattern().replace_all(&text, "~").into_owned();
+        }
+        text
+    }
+
+    fn json(&self, value: &JsonValue) -> JsonValue {
+        let value = if self.options.redact_secrets {
+            crate::dispatch::mask_sensitive(value)
+        } else {
+            value.clone()
+        };
+        self.strings(value)
+    }
+
+    fn strings(&self, value: JsonValue) -> JsonValue {
+        match value {
+            JsonValue::String(s) => JsonValue::String(self.text(&s)),
+            JsonValue::Array(items) => {
+                JsonValue::Array(items.into_iter().map(|v| self.strings(v)).collect())
+            }
+            JsonValue::Object(map) => {
+                JsonValue::Object(map.into_iter().map(|(k, v)| (k, self.strings(v))).collect())
+            }
+            other => other,
+        }
+    }
+}
+
+/// Text of a tool result, which is either a string or a list of content blocks
+pub(crate) fn tool_result_text(content: &JsonValue) -> String {
+    match content {
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| match item["type"].as_str() {
+                Some("text") => item["text"].as_str().unwrap_or_default().to_string(),
+                Some(other) => format!("[{}]", other),
+                None => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        JsonValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// The user and assistant messages of a transcript, redacted
+///
+/// Thinking, system and bookkeeping entries are left out.
+fn normalize_transcript(entries: &[JsonValue], redactor: &Redactor) -> Vec<ExportMessage> {
+    let mut messages = Vec::new();
+    for entry in entries {
+        let role = match entry["type"].as_str() {
+            Some(role @ ("user" | "assistant")) => role,
+            _ => continue,
+        };
+        if entry["isMeta"].as_bool() == Some(true) {
+            continue;
+        }
+        let content = &entry["message"]["content"];
+        let blocks: Vec<ExportBlock> = match content {
+            JsonValue::String(text) => vec![ExportBlock::Text {
+                text: redactor.text(text),
+            }],
+            JsonValue::Array(items) => items
+                .iter()
+                .filter_map(|item| match item["type"].as_str()? {
+                    "text" => Some(ExportBlock::Text {
+                        text: redactor.text(item["text"].as_str()?),
+                    }),
+                    "tool_use" => Some(ExportBlock::ToolUse {
+                        id: item["id"].as_str().unwrap_or_default().to_string(),
+                        name: item["name"].as_str().unwrap_or_default().to_string(),
+                        input: redactor.json(&item["input"]),
+                    }),
+                    "tool_result" => Some(ExportBlock::ToolResult {
+                        tool_use_id: item["tool_use_id"].as_str().unwrap_or_default().to_string(),
+                        content: redactor.text(&tool_result_text(&item["content"])),
+                        is_error: item["is_error"].as_bool().unwrap_or(false),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let blocks: Vec<ExportBlock> = blocks
+            .into_iter()
+            .filter(|b| !matches!(b, ExportBlock::Text { text } if text.trim().is_empty()))
+            .collect();
+        if blocks.is_empty() {
+            continue;
+        }
+        messages.push(ExportMessage {
+            role: role.to_string(),
+            timestamp: entry["timestamp"].as_str().map(str::to_string),
+            blocks,
+        });
+    }
+    messages
+}
+
+/// A code fence longer than any backtick run in `content`
+fn fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn heading(message: &ExportMessage) -> String {
+    let role = if message.role == "user" {
+        "User"
+    } else {
+        "Assistant"
+    };
+    match &message.timestamp {
+        Some(ts) => format!("{} · {}", role, ts),
+        None => role.to_string(),
+    }
+}
+
+fn pretty_input(input: &JsonValue) -> String {
+    serde_json::to_string_pretty(input).unwrap_or_else(|_| input.to_string())
+}
+
+fn render_markdown(export: &SessionExport) -> String {
+    let mut out = format!("# Session {}\n\n", export.session_id);
+    if let Some(project) = &export.project_path {
+        let _ = writeln!(out, "- Project: `{}`", project);
+    }
+    let _ = writeln!(out, "- Exported: {}\n", export.exported_at);
+
+    for message in &export.messages {
+        let _ = writeln!(out, "## {}\n", heading(message));
+        for block in &message.blocks {
+            match block {
+                ExportBlock::Text { text } => {
+                    let _ = writeln!(out, "{}\n", text.trim_end());
+                }
+                ExportBlock::ToolUse { name, input, .. } => {
+                    let input = pretty_input(input);
+                    let fence = fence(&input);
+                    let _ = writeln!(
+                        out,
+                        "**Tool: {}**\n\n{}json\n{}\n{}\n",
+                        name, fence, input, fence
+                    );
+                }
+                ExportBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let fence = fence(content);
+                    let _ = writeln!(
+                        out,
+                        "<details><summary>{}</summary>\n\n{}\n{}\n{}\n\n</details>\n",
+                        label,
+                        fence,
+                        content.trim_end(),
+                        fence
+                    );
+                }
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Message text as HTML: fenced code blocks become `<pre>`, the rest paragraphs
+fn text_to_html(text: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+    let flush = |out: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let _ = write!(
+                out,
+                "<p>{}</p>",
+                escape_html(&paragraph.join("\n")).replace('\n', "<br>")
+            );
+            paragraph.clear();
+        }
+    };
+    for line in text.lines() {
+        let is_fence = line.trim_start().starts_with("```");
+        match (&mut code, is_fence) {
+            (Some(lines), false) => lines.push(line),
+            (Some(lines), true) => {
+                let _ = write!(
+                    out,
+                    "<pre><code>{}</code></pre>",
+                    escape_html(&lines.join("\n"))
+                );
+                code = None;
+            }
+            (None, true) => {
+                flush(&mut out, &mut paragraph);
+                code = Some(Vec::new());
+            }
+            (None, false) if line.trim().is_empty() => flush(&mut out, &mut paragraph),
+            (None, false) => paragraph.push(line),
+        }
+    }
+    // An unclosed fence runs to the end of the message
+    if let Some(lines) = code {
+        let _ = write!(
+            out,
+            "<pre><code>{}</code></pre>",
+            escape_html(&lines.join("\n"))
+        );
+    }
+    flush(&mut out, &mut paragraph);
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;line-height:1.5;color:#1f2328}\
+.meta{color:#656d76}.message{border-top:1px solid #d0d7de;padding:.5rem 0}\
+.message h2{font-size:1rem;margin:.5rem 0}.user h2{color:#0969da}.assistant h2{color:#8250df}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;border-radius:6px}\
+.tool{font-weight:600}.error summary{color:#cf222e}";
+
+fn render_html(export: &SessionExport) -> String {
+    let title = escape_html(&format!("Session {}", export.session_id));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, title
+    );
+    if let Some(project) = &export.project_path {
+        let _ = writeln!(
+            out,
+            "<p class=\"meta\">Project: <code>{}</code></p>",
+            escape_html(project)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\">Exported: {}</p>",
+        escape_html(&export.exported_at)
+    );
+
+    for message in &export.messages {
+        let _ = write!(
+            out,
+            "<section class=\"message {}\"><h2>{}</h2>",
+            escape_html(&message.role),
+            escape_html(&heading(message))
+        );
+        for block in &message.blocks {
+            match block {
+                ExportBlock::Text { text } => out.push_str(&text_to_html(text)),
+                ExportBlock::ToolUse { name, input, .. } => {
+                    let _ = write!(
+                        out,
+                        "<p class=\"tool\">Tool: {}</p><pre><code>{}</code></pre>",
+                        escape_html(name),
+                        escape_html(&pretty_input(input))
+                    );
+                }
+                ExportBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let (class, label) = if *is_error {
+                        ("error", "Tool error")
+                    } else {
+                        ("result", "Tool result")
+                    };
+                    let _ = write!(
+                        out,
+                        "<details class=\"{}\"><summary>{}</summary><pre><code>{}</code></pre></details>",
+                        class,
+                        label,
+                        escape_html(content)
+                    );
+                }
+            }
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+/// Write a session transcript to `path` as Markdown, standalone HTML or JSON
+///
+/// `format` is "markdown", "html" or "json". The export keeps the text, tool
+/// calls, tool results and timestamps; thinking and bookkeeping entries are
+/// left out.
+#[tauri::command]
+pub async fn export_session(
+    session_id: String,
+    format: String,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<SessionExportResult, String> {
+    if !Path::new(&path).is_absolute() {
+        return Err(format!("Export path must be absolute: {}", path));
+    }
+    let project_id = find_session_project(&session_id)?;
+    let entries = load_session_history(session_id.clone(), project_id).await?;
+    let project_path = entries
+        .iter()
+        .find_map(|e| e["cwd"].as_str())
+        .map(str::to_string);
+
+    let mut options = options.unwrap_or_default();
+    options.redact_secrets |= crate::redaction::enabled();
+    let redactor = Redactor {
+        options: &options,
+        project_path: project_path.as_deref(),
+    };
+    let export = SessionExport {
+        version: SESSION_EXPORT_VERSION,
+        messages: normalize_transcript(&entries, &redactor),
+        project_path: project_path.as_deref().map(|p| {
+            if options.redact_paths {
+                "<project>".to_string()
+            } else {
+                p.to_string()
+            }
+        }),
+        session_id,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let content = match format.to_lowercase().as_str() {
+        "markdown" | "md" => render_markdown(&export),
+        "html" => render_html(&export),
+        "json" => serde_json::to_string_pretty(&export)
+            .map_err(|e| format!("Failed to serialize session: {}", e))?,
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+    let operation = FileOperation::for_write(Path::new(&path));
+    std::fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    file_audit::record("export_session", operation, Path::new(&path));
+
+    Ok(SessionExportResult {
+        path,
+        messages: export.messages.len(),
+        bytes: content.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_redacts_paths_and_secrets() {
+        let entries: Vec<JsonValue> = [
+            r#"{"type":"user","cwd":"/home/ana/app","timestamp":"2025-01-01T10:00:00Z","message":{"role":"user","content":"Use key sk-ant-REDACTED in /home/ana/app/.env"}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-01T10:00:05Z","message":{"content":[{"type":"thinking","thinking":"hmm"},{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/home/ana/app/src/main.rs"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"fn main() {}"}]}}"#,
+            r#"{"type":"summary","summary":"Setup"}"#,
+        ]
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+        let options = ExportOptions {
+            redact_paths: true,
+            redact_secrets: true,
+        };
+        let redactor = Redactor {
+            options: &options,
+            project_path: Some("/home/ana/app"),
+        };
+        let messages = normalize_transcript(&entries, &redactor);
+        assert_eq!(messages.len(), 3);
+
+        let markdown = render_markdown(&SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            session_id: "s1".to_string(),
+            project_path: None,
+            exported_at: "now".to_string(),
+            messages,
+        });
+        assert!(markdown.contains("Use key [REDACTED] in <project>/.env"));
+        assert!(markdown.contains("\"file_path\": \"<project>/src/main.rs\""));
+        assert!(markdown.contains("## Assistant · 2025-01-01T10:00:05Z"));
+        assert!(!markdown.contains("hmm"));
+        assert!(!markdown.contains("ana"));
+    }
+}
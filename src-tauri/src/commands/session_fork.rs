@@ -0,0 +1,206 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::bookmarks::find_session_project;
+use super::claude::resume_claude_code;
+use crate::file_audit::{self, FileOperation};
+use crate::path_validation::validate_project_root;
+use crate::utils::get_claude_dir;
+
+/// Model a fork is resumed with when neither the caller nor the transcript names one
+const DEFAULT_MODEL: &str = "sonnet";
+
+/// A new session holding the start of another one's conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct ForkedSession {
+    pub session_id: String,
+    pub project_id: String,
+    /// Project the original session ran in
+    pub project_path: String,
+    pub forked_from: String,
+    /// Messages copied into the new session
+    pub message_count: usize,
+    /// Whether a prompt was sent to continue the fork
+    pub resumed: bool,
+}
+
+/// Transcript entries with their raw lines, indexed like `load_session_history`
+fn read_entries(path: &Path) -> Result<Vec<(String, JsonValue)>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open session file: {}", e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let entry = serde_json::from_str::<JsonValue>(&line).ok()?;
+            Some((line, entry))
+        })
+        .collect())
+}
+
+/// IDs of tool calls in `entries` that no later entry answers
+fn unanswered_tool_uses(entries: &[(String, JsonValue)]) -> HashSet<String> {
+    let mut pending = HashSet::new();
+    for (_, entry) in entries {
+        for block in entry["message"]["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("tool_use") => {
+                    if let Some(id) = block["id"].as_str() {
+                        pending.insert(id.to_string());
+                    }
+                }
+                Some("tool_result") => {
+                    if let Some(id) = block["tool_use_id"].as_str() {
+                        pending.remove(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    pending
+}
+
+/// The lines of a fork: entries up to and including `up_to`, moved to the new session
+fn fork_lines(
+    entries: &[(String, JsonValue)],
+    up_to: usize,
+    old_session_id: &str,
+    new_session_id: &str,
+) -> Result<Vec<String>, String> {
+    if up_to >= entries.len() {
+        return Err(format!(
+            "Message index {} is out of range (session has {} messages)",
+            up_to,
+            entries.len()
+        ));
+    }
+    let kept = &entries[..=up_to];
+    if !unanswered_tool_uses(kept).is_empty() {
+        return Err(format!(
+            "Message {} is waiting for a tool result; fork from a message before the tool call or after its result",
+            up_to
+        ));
+    }
+
+    let old_field = format!("\"sessionId\":\"{}\"", old_session_id);
+    let new_field = format!("\"sessionId\":\"{}\"", new_session_id);
+    kept.iter()
+        .map(|(line, entry)| {
+            if entry["sessionId"].as_str() != Some(old_session_id) {
+                Ok(line.clone())
+            } else if line.contains(&old_field) {
+                Ok(line.replacen(&old_field, &new_field, 1))
+            } else {
+                let mut entry = entry.clone();
+                entry["sessionId"] = JsonValue::String(new_session_id.to_string());
+                serde_json::to_string(&entry).map_err(|e| e.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Copy a session's conversation up to a message into a new session
+///
+/// The original is left untouched. With `prompt`, the fork is resumed with it
+/// right away, so the conversation continues from that point differently;
+/// `model` defaults to the one the original last used. Output streams under
+/// the new session's ID like any resumed session.
+#[tauri::command]
+pub async fn fork_session(
+    window: tauri::Window,
+    app: AppHandle,
+    session_id: String,
+    up_to_message_index: usize,
+    prompt: Option<String>,
+    model: Option<String>,
+) -> Result<ForkedSession, String> {
+    let project_id = find_session_project(&session_id)?;
+    let project_dir = get_claude_dir()?.join("projects").join(&project_id);
+    let entries = read_entries(&project_dir.join(format!("{}.jsonl", session_id)))?;
+
+    let new_session_id = uuid::Uuid::new_v4().to_string();
+    let lines = fork_lines(&entries, up_to_message_index, &session_id, &new_session_id)?;
+    let kept = &entries[..=up_to_message_index];
+    let cwd = kept
+        .iter()
+        .rev()
+        .find_map(|(_, e)| e["cwd"].as_str().filter(|cwd| !cwd.is_empty()))
+        .ok_or("The session doesn't say which project it ran in")?;
+    let project_path = validate_project_root(cwd)?.to_string_lossy().to_string();
+
+    let new_path = project_dir.join(format!("{}.jsonl", new_session_id));
+    fs::write(&new_path, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write the forked session: {}", e))?;
+    file_audit::record("fork_session", FileOperation::Create, &new_path);
+    log::info!(
+        "Forked session {} at message {} into {}",
+        session_id,
+        up_to_message_index,
+        new_session_id
+    );
+
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    let resumed = prompt.is_some();
+    if let Some(prompt) = prompt {
+        let model = model
+            .or_else(|| {
+                kept.iter()
+                    .rev()
+                    .find_map(|(_, e)| e["message"]["model"].as_str().map(str::to_string))
+            })
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        resume_claude_code(
+            window,
+            app,
+            project_path.clone(),
+            new_session_id.clone(),
+            prompt,
+            model,
+            None,
+            None,
+        )
+        .await?;
+    }
+
+    Ok(ForkedSession {
+        session_id: new_session_id,
+        project_id,
+        project_path,
+        forked_from: session_id,
+        message_count: lines.len(),
+        resumed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fork_lines() {
+        let raw = [
+            r#"{"type":"user","sessionId":"old","message":{"role":"user","content":"List the files"}}"#,
+            r#"{"type":"assistant","sessionId":"old","message":{"content":[{"type":"tool_use","id":"t1","name":"LS","input":{}}]}}"#,
+            r#"{"type":"user","sessionId":"old","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"a.rs"}]}}"#,
+            r#"{"type":"assistant","sessionId":"old","message":{"content":[{"type":"text","text":"Just a.rs"}]}}"#,
+        ];
+        let entries: Vec<(String, JsonValue)> = raw
+            .iter()
+            .map(|line| (line.to_string(), serde_json::from_str(line).unwrap()))
+            .collect();
+
+        let lines = fork_lines(&entries, 2, "old", "new").unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.contains(r#""sessionId":"new""#)));
+        assert!(lines[2].contains("a.rs"));
+
+        assert!(fork_lines(&entries, 1, "old", "new").is_err());
+        assert!(fork_lines(&entries, 4, "old", "new").is_err());
+        assert_eq!(fork_lines(&entries, 0, "old", "new").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,650 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use super::agents::AgentDb;
+
+/// app_settings key holding the version of the settings format
+const SCHEMA_VERSION_KEY: &str = "settings_schema_version";
+
+/// Settings only the app writes, such as caches of verified downloads
+const INTERNAL_SETTINGS: &[&str] = &["pricing_table_cache", "anthropic_models_cache"];
+
+/// Shortest bearer token accepted
+const MIN_TOKEN_LEN: usize = 32;
+/// Least estimated entropy of a bearer token, in bits
+const MIN_TOKEN_BITS: f64 = 128.0;
+
+/// What values a setting accepts
+#[derive(Debug, Clone, Copy)]
+pub enum SettingKind {
+    Bool,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Number {
+        min: f64,
+        max: f64,
+    },
+    Text,
+    /// A bearer token, which has to be long and random
+    Token,
+    /// An http(s) or socks URL
+    Url,
+    Choice(&'static [&'static str]),
+    /// Any JSON value, stored serialized
+    Json,
+}
+
+/// A known setting
+#[derive(Debug, Clone, Copy)]
+pub struct SettingSpec {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    /// Stored form of the value used when the setting is unset
+    pub default: Option<&'static str>,
+}
+
+const fn setting(
+    key: &'static str,
+    kind: SettingKind,
+    default: Option<&'static str>,
+) -> SettingSpec {
+    SettingSpec { key, kind, default }
+}
+
+/// Every setting stored in app_settings
+pub const SETTINGS: &[SettingSpec] = &[
+    // Proxy
+    setting("proxy_enabled", SettingKind::Bool, Some("false")),
+    setting("proxy_http", SettingKind::Url, None),
+    setting("proxy_https", SettingKind::Url, None),
+    setting("proxy_all", SettingKind::Url, None),
+    setting("proxy_no", SettingKind::Text, None),
+    setting("proxy_ca_bundle", SettingKind::Text, None),
+    // HTTP
+    setting(
+        "http_connect_timeout_secs",
+        SettingKind::Integer { min: 1, max: 120 },
+        Some("10"),
+    ),
+    setting(
+        "http_read_timeout_secs",
+        SettingKind::Integer { min: 1, max: 600 },
+        Some("60"),
+    ),
+    setting(
+        "http_host_requests_per_minute",
+        SettingKind::Integer { min: 1, max: 1000 },
+        Some("60"),
+    ),
+    setting(
+        "http_host_burst",
+        SettingKind::Integer { min: 1, max: 100 },
+        Some("10"),
+    ),
+    // Trace export
+    setting("otel_enabled", SettingKind::Bool, Some("false")),
+    setting("otel_endpoint", SettingKind::Url, None),
+    setting("otel_headers", SettingKind::Json, None),
+    setting("otel_service_name", SettingKind::Text, None),
+    // Run history retention; 0 turns a limit off
+    setting(
+        "run_history_max_age_days",
+        SettingKind::Integer { min: 0, max: 36500 },
+        None,
+    ),
+    setting(
+        "run_history_max_runs",
+        SettingKind::Integer {
+            min: 0,
+            max: 10_000_000,
+        },
+        None,
+    ),
+    // Output streaming
+    setting(
+        "output_batch_interval_ms",
+        SettingKind::Integer {
+            min: 1,
+            max: 60_000,
+        },
+        None,
+    ),
+    setting(
+        "output_batch_max_lines",
+        SettingKind::Integer {
+            min: 1,
+            max: 100_000,
+        },
+        None,
+    ),
+    setting(
+        "output_buffer_lines",
+        SettingKind::Integer {
+            min: 1,
+            max: 1_000_000,
+        },
+        None,
+    ),
+    setting("output_emit_line_events", SettingKind::Bool, Some("false")),
+    // Project health
+    setting(
+        "project_monthly_budget_usd",
+        SettingKind::Number {
+            min: 0.0,
+            max: 1_000_000_000.0,
+        },
+        None,
+    ),
+    // Claude directory
+    setting("claude_dir_path", SettingKind::Text, None),
+    // Control server
+    setting("control_server_enabled", SettingKind::Bool, Some("false")),
+    setting(
+        "control_server_port",
+        SettingKind::Integer {
+            min: 1024,
+            max: 65535,
+        },
+        Some("7823"),
+    ),
+    setting("control_server_token", SettingKind::Token, None),
+    // Error reporting
+    setting("error_reporting_enabled", SettingKind::Bool, Some("false")),
+    setting("error_reporting_endpoint", SettingKind::Url, None),
+    // Audit log
+    setting("audit_log_full_args", SettingKind::Bool, Some("false")),
+    // Redaction
+    setting("redaction_enabled", SettingKind::Bool, Some("true")),
+    setting("redaction_patterns", SettingKind::Json, None),
+    // Skill registry
+    setting("skills_signing_public_key", SettingKind::Text, None),
+    setting("skills_require_signature", SettingKind::Bool, Some("false")),
+    setting(
+        "catalog_fetch_concurrency",
+        SettingKind::Integer { min: 1, max: 32 },
+        Some("8"),
+    ),
+    setting(
+        "catalog_request_timeout_secs",
+        SettingKind::Integer { min: 1, max: 120 },
+        Some("10"),
+    ),
+    // Pricing
+    setting("pricing_table_url", SettingKind::Url, None),
+    setting("pricing_signing_public_key", SettingKind::Text, None),
+    setting("pricing_overrides", SettingKind::Json, None),
+    setting("pricing_table_cache", SettingKind::Json, None),
+    // Models
+    setting("anthropic_models_cache", SettingKind::Json, None),
+    // Appearance
+    setting(
+        "theme_preference",
+        SettingKind::Choice(&["dark", "gray", "light", "custom"]),
+        Some("dark"),
+    ),
+    setting("theme_custom_colors", SettingKind::Json, None),
+    setting("startup_intro_enabled", SettingKind::Bool, Some("true")),
+    // API key source
+    setting(
+        "anthropic_api_key_env",
+        SettingKind::Text,
+        Some("ANTHROPIC_API_KEY"),
+    ),
+    setting(
+        "anthropic_admin_key_env",
+        SettingKind::Text,
+        Some("ANTHROPIC_ADMIN_KEY"),
+    ),
+    // Formatting
+    setting(
+        "locale",
+        SettingKind::Choice(crate::format::LOCALES),
+        Some(crate::format::DEFAULT_LOCALE),
+    ),
+    // Desktop notifications
+    setting("notify_run_completed", SettingKind::Bool, Some("true")),
+    setting("notify_run_failed", SettingKind::Bool, Some("true")),
+    setting("notify_permission_request", SettingKind::Bool, Some("true")),
+    // Project locks
+    setting(
+        "project_lock_policy",
+        SettingKind::Choice(crate::project_locks::CONFLICT_POLICIES),
+        Some(crate::project_locks::DEFAULT_CONFLICT_POLICY),
+    ),
+    // Shutdown
+    setting(
+        "shutdown_running_runs",
+        SettingKind::Choice(crate::shutdown::RUN_POLICIES),
+        Some(crate::shutdown::DEFAULT_RUN_POLICY),
+    ),
+    // Updates
+    setting("auto_check_updates", SettingKind::Bool, Some("true")),
+    // Run queue
+    setting(
+        "run_catch_up_policy",
+        SettingKind::Choice(crate::run_queue::CATCH_UP_POLICIES),
+        Some(crate::run_queue::DEFAULT_CATCH_UP_POLICY),
+    ),
+    // File audit log
+    setting(
+        "file_audit_retention_days",
+        SettingKind::Integer { min: 1, max: 3650 },
+        Some("365"),
+    ),
+    setting(
+        "file_audit_max_entries",
+        SettingKind::Integer {
+            min: 1_000,
+            max: 10_000_000,
+        },
+        Some("1000000"),
+    ),
+    // Storage cleanup
+    setting("storage_auto_cleanup", SettingKind::Bool, Some("false")),
+    setting(
+        "storage_cleanup_max_age_days",
+        SettingKind::Integer { min: 1, max: 3650 },
+        Some("30"),
+    ),
+    setting(
+        "storage_cleanup_max_size_mb",
+        SettingKind::Integer {
+            min: 1,
+            max: 1_000_000,
+        },
+        None,
+    ),
+    // Cache prewarm
+    setting("prewarm_enabled", SettingKind::Bool, Some("true")),
+    setting("prewarm_start_time", SettingKind::Text, None),
+    setting(
+        "prewarm_lead_minutes",
+        SettingKind::Integer { min: 5, max: 240 },
+        Some("15"),
+    ),
+    // Prompt history
+    setting("prompt_history_enabled", SettingKind::Bool, Some("true")),
+    // Project profiles
+    setting("project_profiles", SettingKind::Json, None),
+    // Claude CLI
+    setting("claude_cli_acknowledged_version", SettingKind::Text, None),
+    // Onboarding
+    setting("onboarding_completed_steps", SettingKind::Json, None),
+    // Diagnostics
+    setting(
+        "log_level",
+        SettingKind::Choice(crate::logging::LEVELS),
+        Some(crate::logging::DEFAULT_LEVEL),
+    ),
+];
+
+/// Migrations from one settings format version to the next, in order
+///
+/// Entry `i` upgrades version `i` to `i + 1`. Append to change the format;
+/// never edit a migration that has shipped.
+const MIGRATIONS: &[fn(&Connection) -> SqliteResult<()>] = &[migrate_v0_normalize];
+
+/// Version the settings are at once all migrations have run
+pub fn schema_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+/// v0 -> v1: store booleans as "true"/"false" and drop empty text values
+///
+/// Older saves wrote "" for unset proxy fields and a mix of boolean spellings.
+fn migrate_v0_normalize(conn: &Connection) -> SqliteResult<()> {
+    for spec in SETTINGS {
+        match spec.kind {
+            SettingKind::Bool => {
+                conn.execute(
+                    "UPDATE app_settings
+                     SET value = CASE WHEN lower(trim(value)) IN ('1', 'true', 'yes', 'on')
+                                      THEN 'true' ELSE 'false' END
+                     WHERE key = ?1",
+                    params![spec.key],
+                )?;
+            }
+            SettingKind::Text | SettingKind::Token | SettingKind::Url => {
+                conn.execute(
+                    "DELETE FROM app_settings WHERE key = ?1 AND trim(value) = ''",
+                    params![spec.key],
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Bring the stored settings up to the current format
+///
+/// Runs every pending migration in one transaction and returns the version
+/// the settings were at before.
+pub fn migrate_settings(conn: &Connection) -> SqliteResult<u32> {
+    let current: u32 = read_raw(conn, SCHEMA_VERSION_KEY)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if current >= schema_version() {
+        return Ok(current);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        log::info!("Migrating settings from version {}", version);
+        migration(&tx)?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+        params![SCHEMA_VERSION_KEY, schema_version().to_string()],
+    )?;
+    tx.commit()?;
+    Ok(current)
+}
+
+pub fn spec(key: &str) -> Result<&'static SettingSpec, String> {
+    SETTINGS
+        .iter()
+        .find(|s| s.key == key)
+        .ok_or_else(|| format!("Unknown setting: {}", key))
+}
+
+/// Stored form of a known setting; `None` when it is unset
+pub fn get_stored(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    spec(key)?;
+    read_raw(conn, key).map_err(|e| e.to_string())
+}
+
+fn read_raw(conn: &Connection, key: &str) -> SqliteResult<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        params![key],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+}
+
+/// Typed value from its stored form; `Null` if it doesn't parse
+fn decode(kind: SettingKind, raw: &str) -> JsonValue {
+    match kind {
+        SettingKind::Bool => JsonValue::Bool(raw == "true"),
+        SettingKind::Integer { .. } => raw
+            .parse::<i64>()
+            .map(JsonValue::from)
+            .unwrap_or(JsonValue::Null),
+        SettingKind::Number { .. } => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        SettingKind::Text | SettingKind::Token | SettingKind::Url | SettingKind::Choice(_) => {
+            JsonValue::String(raw.to_string())
+        }
+        SettingKind::Json => serde_json::from_str(raw).unwrap_or(JsonValue::Null),
+    }
+}
+
+/// Check `value` against the setting and return its stored form
+///
+/// `null` (or an empty string) means "unset", returned as `None`. Strings are
+/// accepted for every kind so values typed into text fields round-trip.
+pub fn encode(spec: &SettingSpec, value: &JsonValue) -> Result<Option<String>, String> {
+    let invalid = |expected: &str| format!("Invalid value for {}: expected {}", spec.key, expected);
+    let text = value.as_str().map(str::trim);
+    if value.is_null() || text == Some("") {
+        return Ok(None);
+    }
+
+    let stored = match spec.kind {
+        SettingKind::Bool => match (value.as_bool(), text) {
+            (Some(b), _) => b.to_string(),
+            (None, Some("true")) | (None, Some("false")) => text.unwrap_or_default().to_string(),
+            _ => return Err(invalid("true or false")),
+        },
+        SettingKind::Integer { min, max } => {
+            let n = value
+                .as_i64()
+                .or_else(|| text.and_then(|t| t.parse().ok()))
+                .ok_or_else(|| invalid("an integer"))?;
+            if n < min || n > max {
+                return Err(invalid(&format!("a number between {} and {}", min, max)));
+            }
+            n.to_string()
+        }
+        SettingKind::Number { min, max } => {
+            let n = value
+                .as_f64()
+                .or_else(|| text.and_then(|t| t.parse().ok()))
+                .filter(|n: &f64| n.is_finite())
+                .ok_or_else(|| invalid("a number"))?;
+            if n < min || n > max {
+                return Err(invalid(&format!("a number between {} and {}", min, max)));
+            }
+            n.to_string()
+        }
+        SettingKind::Text => text.ok_or_else(|| invalid("a string"))?.to_string(),
+        SettingKind::Token => {
+            let token = text.ok_or_else(|| invalid("a string"))?;
+            if !is_strong_token(token) {
+                return Err(invalid(&format!(
+                    "a random token of at least {} characters",
+                    MIN_TOKEN_LEN
+                )));
+            }
+            token.to_string()
+        }
+        SettingKind::Url => {
+            let url = text.ok_or_else(|| invalid("a URL"))?;
+            let parsed =
+                reqwest::Url::parse(url).map_err(|e| format!("{} ({})", invalid("a URL"), e))?;
+            if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                return Err(invalid("an http, https or socks5 URL"));
+            }
+            url.to_string()
+        }
+        SettingKind::Choice(choices) => match text {
+            Some(choice) if choices.contains(&choice) => choice.to_string(),
+            _ => return Err(invalid(&format!("one of {}", choices.join(", ")))),
+        },
+        SettingKind::Json => match text {
+            Some(json) => {
+                serde_json::from_str::<JsonValue>(json).map_err(|_| invalid("JSON"))?;
+                json.to_string()
+            }
+            None => value.to_string(),
+        },
+    };
+    Ok(Some(stored))
+}
+
+/// Whether `token` is long and varied enough to be used as a bearer token
+///
+/// Entropy is estimated as length × log2(distinct characters), which rejects
+/// short or repetitive tokens.
+pub fn is_strong_token(token: &str) -> bool {
+    let distinct = token
+        .chars()
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    token.len() >= MIN_TOKEN_LEN
+        && !token.chars().any(char::is_whitespace)
+        && token.chars().count() as f64 * (distinct as f64).log2() >= MIN_TOKEN_BITS
+}
+
+/// Current value of a known setting, or its default
+pub fn get_value(conn: &Connection, key: &str) -> Result<JsonValue, String> {
+    let spec = spec(key)?;
+    let raw = read_raw(conn, key).map_err(|e| e.to_string())?;
+    Ok(raw
+        .as_deref()
+        .or(spec.default)
+        .map(|raw| decode(spec.kind, raw))
+        .unwrap_or(JsonValue::Null))
+}
+
+/// Validate and store a known setting; `null` resets it to the default
+pub fn set_value(conn: &Connection, key: &str, value: &JsonValue) -> Result<(), String> {
+    let spec = spec(key)?;
+    match encode(spec, value)? {
+        Some(stored) => conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+            params![key, stored],
+        ),
+        None => conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key]),
+    }
+    .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+    Ok(())
+}
+
+pub fn get_bool(conn: &Connection, key: &str) -> bool {
+    get_value(conn, key)
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+pub fn get_text(conn: &Connection, key: &str) -> Option<String> {
+    get_value(conn, key)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+}
+
+pub fn get_integer(conn: &Connection, key: &str) -> Option<i64> {
+    get_value(conn, key).ok().and_then(|v| v.as_i64())
+}
+
+pub fn get_number(conn: &Connection, key: &str) -> Option<f64> {
+    get_value(conn, key).ok().and_then(|v| v.as_f64())
+}
+
+/// Get a setting's value, or its default when unset
+#[tauri::command]
+pub async fn get_setting(db: State<'_, AgentDb>, key: String) -> Result<JsonValue, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    get_value(&conn, &key)
+}
+
+/// Validate and save a setting; `null` resets it to the default
+#[tauri::command]
+pub async fn set_setting(
+    db: State<'_, AgentDb>,
+    key: String,
+    value: JsonValue,
+) -> Result<(), String> {
+    if INTERNAL_SETTINGS.contains(&key.as_str()) {
+        return Err(format!("{} is managed by the app and can't be set", key));
+    }
+    if key == "redaction_patterns" {
+        crate::redaction::validate_patterns(&value)?;
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    set_value(&conn, &key, &value)?;
+
+    if key.starts_with("proxy_") {
+        super::proxy::apply_proxy_settings(&super::proxy::load_proxy_settings(&conn));
+    }
+    if key.starts_with("http_") {
+        crate::http_client::configure_limits(&crate::http_client::load_limits(&conn));
+    }
+    if key.starts_with("redaction_") {
+        crate::redaction::configure(&conn);
+    }
+    if key.starts_with("audit_log_") {
+        crate::dispatch::configure(&conn);
+    }
+    Ok(())
+}
+
+/// Get every known setting with its current value
+#[tauri::command]
+pub async fn get_all_settings(
+    db: State<'_, AgentDb>,
+) -> Result<BTreeMap<String, JsonValue>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    SETTINGS
+        .iter()
+        .map(|spec| Ok((spec.key.to_string(), get_value(&conn, spec.key)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_set_and_get_validate() {
+        let conn = test_conn();
+
+        assert_eq!(get_value(&conn, "theme_preference").unwrap(), "dark");
+        set_value(&conn, "theme_preference", &json!("light")).unwrap();
+        assert_eq!(get_value(&conn, "theme_preference").unwrap(), "light");
+        assert!(set_value(&conn, "theme_preference", &json!("neon")).is_err());
+
+        set_value(&conn, "catalog_fetch_concurrency", &json!("4")).unwrap();
+        assert_eq!(get_integer(&conn, "catalog_fetch_concurrency"), Some(4));
+        assert!(set_value(&conn, "catalog_fetch_concurrency", &json!(100)).is_err());
+
+        assert!(set_value(&conn, "proxy_http", &json!("ftp://x")).is_err());
+        set_value(&conn, "proxy_http", &json!("http://proxy:8080")).unwrap();
+        set_value(&conn, "proxy_http", &JsonValue::Null).unwrap();
+        assert!(get_value(&conn, "proxy_http").unwrap().is_null());
+
+        set_value(&conn, "project_monthly_budget_usd", &json!("12.5")).unwrap();
+        assert_eq!(get_number(&conn, "project_monthly_budget_usd"), Some(12.5));
+        assert!(set_value(&conn, "project_monthly_budget_usd", &json!(-1)).is_err());
+
+        assert!(get_value(&conn, "no_such_setting").is_err());
+    }
+
+    #[test]
+    fn test_tokens_must_be_strong() {
+        let conn = test_conn();
+        for weak in [
+            "secret",
+            &"a".repeat(64),
+            &"ab".repeat(32),
+            "correct horse battery staple 1234",
+        ] {
+            assert!(
+                set_value(&conn, "control_server_token", &json!(weak)).is_err(),
+                "{}",
+                weak
+            );
+        }
+        let token = crate::control_server::generate_token();
+        set_value(&conn, "control_server_token", &json!(token)).unwrap();
+        assert_eq!(get_text(&conn, "control_server_token"), Some(token));
+    }
+
+    #[test]
+    fn test_migration_normalizes_legacy_values() {
+        let conn = test_conn();
+        conn.execute_batch(
+            "INSERT INTO app_settings (key, value) VALUES ('proxy_enabled', '1');
+             INSERT INTO app_settings (key, value) VALUES ('proxy_http', '');",
+        )
+        .unwrap();
+
+        assert_eq!(migrate_settings(&conn).unwrap(), 0);
+        assert!(get_bool(&conn, "proxy_enabled"));
+        assert_eq!(read_raw(&conn, "proxy_http").unwrap(), None);
+
+        // Already current: nothing to do
+        assert_eq!(migrate_settings(&conn).unwrap(), schema_version());
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::path_validation::validate_project_root;
+use crate::tokens::estimate_tokens;
+use crate::utils::get_claude_dir;
+
+/// How one skill is used in one project
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillUsage {
+    pub name: String,
+    /// "project" or "user" for installed skills, `None` for skills that were
+    /// used but are no longer installed
+    pub scope: Option<String>,
+    /// SKILL.md of the installed skill
+    pub path: Option<String>,
+    pub invocations: u64,
+    pub sessions: u64,
+    pub last_used: Option<String>,
+    /// Tokens of the name and description, which are in context in every session
+    pub context_tokens: u64,
+}
+
+/// Skill usage in one project, most used first; unused skills last
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectSkillUsage {
+    pub project_path: String,
+    pub skills: Vec<SkillUsage>,
+}
+
+/// A Skill tool call found in a transcript line: (tool use ID, skill name)
+pub fn skill_invocations(entry: &JsonValue) -> Vec<(String, String)> {
+    let Some(content) = entry["message"]["content"].as_array() else {
+        return Vec::new();
+    };
+    content
+        .iter()
+        .filter(|c| c["type"] == "tool_use" && c["name"] == "Skill")
+        .filter_map(|c| {
+            let input = &c["input"];
+            let name = ["skill", "command", "name"]
+                .iter()
+                .find_map(|key| input[*key].as_str())?
+                .trim()
+                .trim_start_matches('/');
+            if name.is_empty() {
+                return None;
+            }
+            let id = c["id"].as_str().unwrap_or_default().to_string();
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// Skills installed in a skills directory, with their always-loaded token cost
+pub(crate) fn installed_skills(dir: &Path) -> Vec<(String, PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let skill_file = entry.path().join("SKILL.md");
+            let content = fs::read_to_string(&skill_file).ok()?;
+            // Only the frontmatter is loaded up front; the body when the skill is used
+            let frontmatter = content
+                .strip_prefix("---")
+                .and_then(|rest| rest.split_once("\n---"))
+                .map(|(front, _)| front)
+                .unwrap_or_default();
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some((name, skill_file, estimate_tokens(frontmatter)))
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Tally {
+    invocations: u64,
+    sessions: HashSet<String>,
+    last_used: Option<String>,
+}
+
+/// Count Skill tool calls per project and skill across all transcripts
+fn tally_invocations(
+    projects_dir: &Path,
+    project_filter: Option<&str>,
+    since: Option<DateTime<Utc>>,
+) -> BTreeMap<String, BTreeMap<String, Tally>> {
+    let mut tallies: BTreeMap<String, BTreeMap<String, Tally>> = BTreeMap::new();
+    // Resumed sessions repeat earlier messages
+    let mut seen_ids = HashSet::new();
+
+    let transcripts = walkdir::WalkDir::new(projects_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"));
+    for transcript in transcripts {
+        let Ok(content) = fs::read_to_string(transcript.path()) else {
+            continue;
+        };
+        let mut cwd: Option<String> = None;
+        for line in content.lines() {
+            if !line.contains("\"Skill\"") && cwd.is_some() {
+                continue;
+            }
+            let Ok(entry) = serde_json::from_str::<JsonValue>(line) else {
+                continue;
+            };
+            if cwd.is_none() {
+                cwd = entry["cwd"].as_str().map(str::to_string);
+            }
+            let invocations = skill_invocations(&entry);
+            if invocations.is_empty() {
+                continue;
+            }
+            let Some(project) = cwd.as_deref() else {
+                continue;
+            };
+            if project_filter.is_some_and(|p| p != project) {
+                continue;
+            }
+            let timestamp = entry["timestamp"].as_str().map(str::to_string);
+            let in_range = match (since, timestamp.as_deref()) {
+                (Some(since), Some(ts)) => DateTime::parse_from_rfc3339(ts)
+                    .map(|t| t >= since)
+                    .unwrap_or(true),
+                _ => true,
+            };
+            if !in_range {
+                continue;
+            }
+            let session = entry["sessionId"].as_str().unwrap_or_default().to_string();
+
+            for (id, name) in invocations {
+                if !id.is_empty() && !seen_ids.insert(id) {
+                    continue;
+                }
+                let tally = tallies
+                    .entry(project.to_string())
+                    .or_default()
+                    .entry(name)
+                    .or_default();
+                tally.invocations += 1;
+                tally.sessions.insert(session.clone());
+                if timestamp > tally.last_used {
+                    tally.last_used = timestamp.clone();
+                }
+            }
+        }
+    }
+    tallies
+}
+
+/// Which installed skills are used, and how often, per project
+///
+/// Counts Skill tool calls in session transcripts. Skills installed in a
+/// project or in `~/.claude/skills` that were never used are listed with zero
+/// invocations, so they can be pruned.
+#[tauri::command]
+pub async fn get_skill_usage(
+    project_path: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<ProjectSkillUsage>, String> {
+    let project_filter = project_path
+        .map(|p| validate_project_root(&p))
+        .transpose()?
+        .map(|p| p.to_string_lossy().to_string());
+    let since = days.map(|d| Utc::now() - Duration::days(d as i64));
+    let claude_dir = get_claude_dir()?;
+
+    let mut tallies = tally_invocations(
+        &claude_dir.join("projects"),
+        project_filter.as_deref(),
+        since,
+    );
+    if let Some(project) = &project_filter {
+        tallies.entry(project.clone()).or_default();
+    }
+    let user_skills = installed_skills(&claude_dir.join("skills"));
+
+    let mut result = Vec::new();
+    for (project, mut tally) in tallies {
+        let mut skills = Vec::new();
+        let project_skills = installed_skills(&Path::new(&project).join(".claude").join("skills"));
+        let installed = project_skills
+            .into_iter()
+            .map(|s| (s, "project"))
+            .chain(user_skills.iter().cloned().map(|s| (s, "user")));
+        let mut listed = HashSet::new();
+        for ((name, path, context_tokens), scope) in installed {
+            // A project skill hides a user skill of the same name
+            if !listed.insert(name.clone()) {
+                continue;
+            }
+            let used = tally.remove(&name).unwrap_or_default();
+            skills.push(SkillUsage {
+                name,
+                scope: Some(scope.to_string()),
+                path: Some(path.to_string_lossy().to_string()),
+                invocations: used.invocations,
+                sessions: used.sessions.len() as u64,
+                last_used: used.last_used,
+                context_tokens,
+            });
+        }
+        skills.extend(tally.into_iter().map(|(name, used)| SkillUsage {
+            name,
+            scope: None,
+            path: None,
+            invocations: used.invocations,
+            sessions: used.sessions.len() as u64,
+            last_used: used.last_used,
+            context_tokens: 0,
+        }));
+        skills.sort_by(|a, b| {
+            b.invocations
+                .cmp(&a.invocations)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        result.push(ProjectSkillUsage {
+            project_path: project,
+            skills,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_invocations() {
+        let entry: JsonValue = serde_json::from_str(
+            r#"{"type":"assistant","cwd":"/p","message":{"content":[
+                {"type":"text","text":"Using the pdf skill"},
+                {"type":"tool_use","id":"toolu_1","name":"Skill","input":{"skill":"pdf"}},
+                {"type":"tool_use","id":"toolu_2","name":"Skill","input":{"command":"/xlsx"}},
+                {"type":"tool_use","id":"toolu_3","name":"Bash","input":{"command":"ls"}}
+            ]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            skill_invocations(&entry),
+            vec![
+                ("toolu_1".to_string(), "pdf".to_string()),
+                ("toolu_2".to_string(), "xlsx".to_string()),
+            ]
+        );
+    }
+}
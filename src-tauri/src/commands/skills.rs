@@ -1,15 +1,36 @@
 use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use minisign_verify::{PublicKey, Signature};
 use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
-use tauri::command;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State};
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::agents::AgentDb;
+use super::catalog;
+use super::operations::ProgressReporter;
+use super::settings as store;
+use crate::file_audit::{self, FileOperation};
+use crate::format::{locale_from_settings, localize, LocalizedText, DEFAULT_LOCALE};
+use crate::network::{
+    send_with_policy, send_with_retry, until_cancelled, NetworkError, RetryPolicy,
+};
+use crate::path_validation::{resolve_within, validate_name, validate_project_root};
+use crate::prompt_template::{self, TemplateVariable};
+use crate::schema_drift::{self, GITHUB_CONTENT};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillInfo {
-    name: String,
-    description: String,
-    url: String, // GitHub HTML URL or API URL
+    pub name: String,
+    pub description: String,
+    pub url: String, // GitHub HTML URL or API URL
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,213 +42,1743 @@ struct GitHubContent {
     html_url: String,
 }
 
+/// GitHub repository skills are installed from
+pub const SKILLS_REPO: &str = "anthropics/skills";
+
+/// How catalog entries (skill and MCP server descriptions) are fetched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogFetchSettings {
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Give up on a single description after this long
+    pub request_timeout_secs: u64,
+}
+
+impl Default for CatalogFetchSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            request_timeout_secs: 10,
+        }
+    }
+}
+
+/// Where disabled skills are kept, inside a skills directory; skill names
+/// can't start with a dot, so it never clashes with one
+const DISABLED_SKILLS_DIR: &str = ".disabled";
+
+/// Dependencies are followed at most this many levels below the installed skill
+const MAX_DEPENDENCY_DEPTH: usize = 5;
+
+/// Files listed at most when previewing a skill
+const MAX_PREVIEW_FILES: usize = 200;
+
+/// Frontmatter of a SKILL.md file
+#[derive(Debug, Deserialize)]
+struct SkillFrontmatter {
+    description: Option<String>,
+    #[serde(default)]
+    dependencies: SkillDependencies,
+    /// A list, or a comma-separated string
+    #[serde(rename = "allowed-tools")]
+    allowed_tools: Option<serde_yaml::Value>,
+}
+
+/// What a skill needs besides itself, declared in its frontmatter:
+///
+/// ```yaml
+/// dependencies:
+///   skills: [docx]
+///   binaries: [pandoc, soffice]
+/// ```
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkillDependencies {
+    /// Other skills from the registry, installed along with it
+    pub skills: Vec<String>,
+    /// Programs it runs, which must be on PATH
+    pub binaries: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AgentTemplate {
     name: String,
     description: String,
+    /// May contain `{{variable}}` placeholders, see `render_agent_template`
+    prompt: String,
+    category: String,
+    #[serde(default)]
+    variables: Vec<TemplateVariable>,
+}
+
+/// Built-in agent templates, with their text in every language we have
+const BUNDLED_AGENT_TEMPLATES: &str = include_str!("../../locales/agent_templates.json");
+/// Descriptions of the MCP servers, used when GitHub can't be reached
+const BUNDLED_MCP_SERVERS: &str = include_str!("../../locales/mcp_servers.json");
+
+/// An agent template as bundled, before it is localized
+#[derive(Debug, Deserialize)]
+struct BundledTemplate {
+    name: String,
+    description: LocalizedText,
     prompt: String,
     category: String,
+    #[serde(default)]
+    variables: Vec<BundledVariable>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundledVariable {
+    name: String,
+    description: LocalizedText,
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    choices: Vec<String>,
+}
+
+/// The bundled MCP server list, before it is localized
+#[derive(Debug, Deserialize)]
+struct BundledMcpServers {
+    /// For servers without a README summary; `{name}` is the server's name
+    default_description: LocalizedText,
+    fallback: Vec<BundledMcpServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundledMcpServer {
+    name: String,
+    url: String,
+    description: LocalizedText,
+}
+
+/// Load the catalog fetch settings from the settings store
+pub fn load_catalog_fetch_settings(conn: &Connection) -> CatalogFetchSettings {
+    let defaults = CatalogFetchSettings::default();
+    CatalogFetchSettings {
+        concurrency: store::get_integer(conn, "catalog_fetch_concurrency")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.concurrency),
+        request_timeout_secs: store::get_integer(conn, "catalog_request_timeout_secs")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.request_timeout_secs),
+    }
+}
+
+/// YAML frontmatter of a SKILL.md file, without the `---` lines
+pub(crate) fn frontmatter(skill_md: &str) -> Option<&str> {
+    let rest = skill_md.trim_start().strip_prefix("---")?;
+    let end = rest.find("\n---")?;
+    Some(&rest[..end])
+}
+
+/// Description from the YAML frontmatter of a SKILL.md file
+pub fn skill_description(skill_md: &str) -> Option<String> {
+    serde_yaml::from_str::<SkillFrontmatter>(frontmatter(skill_md)?)
+        .ok()?
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+}
+
+/// Dependencies from the YAML frontmatter of a SKILL.md file; none if it
+/// has no frontmatter
+pub fn skill_dependencies(skill_md: &str) -> Result<SkillDependencies, String> {
+    let Some(frontmatter) = frontmatter(skill_md) else {
+        return Ok(SkillDependencies::default());
+    };
+    serde_yaml::from_str::<SkillFrontmatter>(frontmatter)
+        .map(|f| f.dependencies)
+        .map_err(|e| e.to_string())
+}
+
+/// Tools a SKILL.md lets Claude use without asking, from `allowed-tools`
+pub fn skill_allowed_tools(skill_md: &str) -> Vec<String> {
+    let tools = frontmatter(skill_md)
+        .and_then(|f| serde_yaml::from_str::<SkillFrontmatter>(f).ok())
+        .and_then(|f| f.allowed_tools);
+    let tools: Vec<String> = match tools {
+        Some(serde_yaml::Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        Some(serde_yaml::Value::String(list)) => list.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    tools
+        .into_iter()
+        .map(|tool| tool.trim().to_string())
+        .filter(|tool| !tool.is_empty())
+        .collect()
+}
+
+/// A SKILL.md without its YAML frontmatter
+pub fn skill_body(skill_md: &str) -> &str {
+    let Some(rest) = skill_md.trim_start().strip_prefix("---") else {
+        return skill_md;
+    };
+    let Some(end) = rest.find("\n---") else {
+        return skill_md;
+    };
+    rest[end + "\n---".len()..]
+        .split_once('\n')
+        .map_or("", |(_, body)| body)
+        .trim_start_matches(['\r', '\n'])
+}
+
+/// `owner/repo` of a skill registry on GitHub, `SKILLS_REPO` when not given
+fn registry_repo(registry: Option<&str>) -> Result<String, String> {
+    let Some(registry) = registry.map(str::trim).filter(|r| !r.is_empty()) else {
+        return Ok(SKILLS_REPO.to_string());
+    };
+    let repo = registry
+        .trim_start_matches("https://github.com/")
+        .trim_end_matches('/');
+    let (owner, name) = repo
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid skill registry '{}': expected owner/repo", registry))?;
+    validate_name(owner, "registry owner")?;
+    validate_name(name, "registry repository")?;
+    Ok(format!("{}/{}", owner, name))
+}
+
+/// First prose paragraph of a README, skipping headings, badges and HTML
+pub fn readme_summary(readme: &str) -> Option<String> {
+    const MAX_LEN: usize = 200;
+
+    let paragraph: Vec<&str> = readme
+        .split("\n\n")
+        .map(|p| p.trim())
+        .find(|p| {
+            !p.is_empty()
+                && !p.starts_with('#')
+                && !p.starts_with('<')
+                && !p.starts_with("[![")
+                && !p.starts_with("![")
+                && !p.starts_with("```")
+        })?
+        .lines()
+        .map(str::trim)
+        .collect();
+    let summary = paragraph.join(" ");
+
+    if summary.chars().count() > MAX_LEN {
+        let truncated: String = summary.chars().take(MAX_LEN).collect();
+        Some(format!("{}…", truncated.trim_end()))
+    } else {
+        Some(summary)
+    }
+}
+
+/// Fetch a description for every name, at most `settings.concurrency` at a time
+///
+/// Each request gets a single attempt and its own timeout so one slow entry
+/// doesn't hold up the catalog. Names whose fetch or parse fails are left out.
+async fn fetch_descriptions<U, P>(
+    client: &Client,
+    names: Vec<String>,
+    settings: &CatalogFetchSettings,
+    url_for: U,
+    parse: P,
+) -> HashMap<String, String>
+where
+    U: Fn(&str) -> String,
+    P: Fn(&str) -> Option<String>,
+{
+    let policy = RetryPolicy {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    let timeout = Duration::from_secs(settings.request_timeout_secs);
+
+    let results: Vec<(String, Result<String, NetworkError>)> = stream::iter(names)
+        .map(|name| {
+            let url = url_for(&name);
+            let policy = &policy;
+            async move {
+                let result = async {
+                    let response = send_with_policy(policy, || {
+                        client
+                            .get(&url)
+                            .header(USER_AGENT, "Opcode-Agent")
+                            .timeout(timeout)
+                    })
+                    .await?;
+                    Ok::<_, NetworkError>(response.text().await?)
+                }
+                .await;
+                (name, result)
+            }
+        })
+        .buffer_unordered(settings.concurrency.max(1))
+        .collect()
+        .await;
+
+    results
+        .into_iter()
+        .filter_map(|(name, result)| match result {
+            Ok(text) => parse(&text).map(|description| (name, description)),
+            Err(e) => {
+                log::debug!("No catalog description for {}: {}", name, e);
+                None
+            }
+        })
+        .collect()
 }
 
 #[command]
-pub async fn fetch_available_skills() -> Result<Vec<SkillInfo>, String> {
+pub async fn fetch_available_skills(
+    db: State<'_, AgentDb>,
+) -> Result<Vec<SkillInfo>, NetworkError> {
     // 1. Fetch from anthropics/skills
-    let client = reqwest::Client::new();
-    let url = "https://api.github.com/repos/anthropics/skills/contents/skills";
+    let client = crate::http_client::client()?;
+    let url = format!(
+        "https://api.github.com/repos/{}/contents/skills",
+        SKILLS_REPO
+    );
 
-    let response = client
-        .get(url)
-        .header(USER_AGENT, "Opcode-Agent")
-        .send()
+    let response = send_with_retry(|| client.get(&url).header(USER_AGENT, "Opcode-Agent"))
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.context("GitHub API error"))?;
 
-    if !response.status().is_success() {
-        return Err(format!("GitHub API Error: {}", response.status()));
-    }
+    let contents: Vec<GitHubContent> =
+        schema_drift::decode_list("skills", response.json().await?, &GITHUB_CONTENT)?;
+    let dirs: Vec<GitHubContent> = contents
+        .into_iter()
+        .filter(|item| item.content_type == "dir")
+        .collect();
 
-    let contents: Vec<GitHubContent> = response.json().await.map_err(|e| e.to_string())?;
+    // 2. Read each SKILL.md's description, a bounded number at a time
+    let settings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_catalog_fetch_settings(&conn)
+    };
+    let mut descriptions = fetch_descriptions(
+        &client,
+        dirs.iter().map(|item| item.name.clone()).collect(),
+        &settings,
+        |name| {
+            format!(
+                "https://raw.githubusercontent.com/{}/main/skills/{}/SKILL.md",
+                SKILLS_REPO, name
+            )
+        },
+        skill_description,
+    )
+    .await;
 
-    let mut skills = Vec::new();
+    let skills: Vec<SkillInfo> = dirs
+        .into_iter()
+        .map(|item| SkillInfo {
+            description: descriptions
+                .remove(&item.name)
+                .unwrap_or_else(|| format!("Official Skill: {}", item.name)),
+            name: item.name,
+            url: item.html_url,
+        })
+        .collect();
 
-    for item in contents {
-        if item.content_type == "dir" {
-            skills.push(SkillInfo {
-                name: item.name.clone(),
-                description: format!("Official Skill: {}", item.name),
-                url: item.html_url,
-            });
+    catalog::remember("skills", &skills);
+    Ok(skills)
+}
+
+/// An entry of a skill directory listing
+#[derive(Debug, Deserialize)]
+struct GitHubEntry {
+    path: String,
+    #[serde(rename = "type")]
+    content_type: String,
+    #[serde(default)]
+    size: u64,
+    sha: String,
+    download_url: Option<String>,
+}
+
+/// A file of a skill in the registry
+#[derive(Debug, Serialize)]
+pub struct SkillFile {
+    /// Relative to the skill directory
+    path: String,
+    size: u64,
+}
+
+/// A skill as it is in the registry, read without installing it
+#[derive(Debug, Serialize)]
+pub struct SkillPreview {
+    name: String,
+    registry: String,
+    description: Option<String>,
+    /// SKILL.md without its frontmatter, ready to render
+    markdown: String,
+    /// Tools the skill lets Claude use without asking
+    allowed_tools: Vec<String>,
+    dependencies: SkillDependencies,
+    files: Vec<SkillFile>,
+    /// Bytes in the listed files
+    total_size: u64,
+    /// Whether the skill has more files than were listed
+    truncated: bool,
+}
+
+/// Files of a skill directory, breadth first, at most `MAX_PREVIEW_FILES`
+/// besides SKILL.md; the flag says whether some were left out
+async fn list_skill_files(
+    client: &Client,
+    registry: &str,
+    skill_name: &str,
+) -> Result<(Vec<GitHubEntry>, bool), NetworkError> {
+    let skill_md = format!("skills/{}/SKILL.md", skill_name);
+    let mut files = Vec::new();
+    let mut truncated = false;
+    let mut dirs = VecDeque::from([format!("skills/{}", skill_name)]);
+    while let Some(dir) = dirs.pop_front() {
+        let url = format!("https://api.github.com/repos/{}/contents/{}", registry, dir);
+        let response = send_with_retry(|| {
+            client
+                .get(&url)
+                .header(USER_AGENT, "Opcode-Agent")
+                .header("Accept", "application/vnd.github+json")
+        })
+        .await
+        .map_err(|e| e.context(&format!("Failed to list {}", dir)))?;
+        let entries: Vec<GitHubEntry> =
+            schema_drift::decode_list("skill_preview", response.json().await?, &GITHUB_CONTENT)?;
+        for entry in entries {
+            match entry.content_type.as_str() {
+                "dir" => dirs.push_back(entry.path),
+                "file" if files.len() < MAX_PREVIEW_FILES || entry.path == skill_md => {
+                    files.push(entry)
+                }
+                "file" => truncated = true,
+                _ => {}
+            }
+        }
+        if truncated {
+            break;
         }
     }
+    Ok((files, truncated || !dirs.is_empty()))
+}
 
-    Ok(skills)
+/// Read a skill from a registry without installing it
+///
+/// Downloads SKILL.md and lists the skill's files so they can be inspected
+/// before the skill is trusted in a project; nothing is written to disk.
+/// `registry` is a GitHub `owner/repo` keeping skills under `skills/`,
+/// `anthropics/skills` by default.
+#[command]
+pub async fn preview_skill(
+    skill_name: String,
+    registry: Option<String>,
+) -> Result<SkillPreview, NetworkError> {
+    validate_name(&skill_name, "skill name")?;
+    let registry = registry_repo(registry.as_deref())?;
+    let client = crate::http_client::client()?;
+
+    let (files, truncated) = list_skill_files(&client, &registry, &skill_name).await?;
+    let prefix = format!("skills/{}/", skill_name);
+    let skill_md = files
+        .iter()
+        .find(|f| f.path.strip_prefix(&prefix) == Some("SKILL.md"))
+        .ok_or_else(|| NetworkError::NotFound {
+            message: format!("Skill '{}' in {} has no SKILL.md", skill_name, registry),
+        })?;
+    let url = skill_md.download_url.clone().unwrap_or_else(|| {
+        format!(
+            "https://raw.githubusercontent.com/{}/HEAD/{}",
+            registry, skill_md.path
+        )
+    });
+    let content = send_with_retry(|| client.get(&url).header(USER_AGENT, "Opcode-Agent"))
+        .await
+        .map_err(|e| e.context("Failed to download SKILL.md"))?
+        .bytes()
+        .await?;
+    let actual_sha = git_blob_sha(&content);
+    if !actual_sha.eq_ignore_ascii_case(&skill_md.sha) {
+        return Err(NetworkError::Integrity {
+            message: format!(
+                "SKILL.md for '{}' does not match the published checksum (expected {}, got {})",
+                skill_name, skill_md.sha, actual_sha
+            ),
+        });
+    }
+
+    let content = String::from_utf8_lossy(&content);
+    let dependencies = skill_dependencies(&content).map_err(|e| {
+        format!(
+            "SKILL.md for '{}' has invalid dependencies: {}",
+            skill_name, e
+        )
+    })?;
+    let total_size = files.iter().map(|f| f.size).sum();
+    Ok(SkillPreview {
+        description: skill_description(&content),
+        markdown: skill_body(&content).to_string(),
+        allowed_tools: skill_allowed_tools(&content),
+        dependencies,
+        files: files
+            .into_iter()
+            .map(|f| SkillFile {
+                path: f
+                    .path
+                    .strip_prefix(&prefix)
+                    .map(str::to_string)
+                    .unwrap_or(f.path),
+                size: f.size,
+            })
+            .collect(),
+        total_size,
+        truncated,
+        name: skill_name,
+        registry,
+    })
 }
 
 #[command]
-pub async fn fetch_mcp_marketplace() -> Result<Vec<SkillInfo>, String> {
+pub async fn fetch_mcp_marketplace(db: State<'_, AgentDb>) -> Result<Vec<SkillInfo>, String> {
     // 1. Try Fetch from modelcontextprotocol/servers/src
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client()?;
     // Try 'src' first, as official repo usually puts them there
     let url = "https://api.github.com/repos/modelcontextprotocol/servers/contents/src";
 
-    // Define Fallback List
-    let fallback_servers = vec![
-        SkillInfo {
-            name: "filesystem".to_string(),
-            description: "Read/Write local files".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/filesystem"
-                .to_string(),
-        },
-        SkillInfo {
-            name: "memory".to_string(),
-            description: "Graph-based memory".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/memory".to_string(),
-        },
-        SkillInfo {
-            name: "fetch".to_string(),
-            description: "Fetch web content".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/fetch".to_string(),
-        },
-        SkillInfo {
-            name: "postgres".to_string(),
-            description: "PostgreSQL Database".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/postgres"
-                .to_string(),
-        },
-        SkillInfo {
-            name: "sqlite".to_string(),
-            description: "SQLite Database".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/sqlite".to_string(),
-        },
-        SkillInfo {
-            name: "github".to_string(),
-            description: "GitHub API Integration".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/github".to_string(),
-        },
-        SkillInfo {
-            name: "slack".to_string(),
-            description: "Slack Integration".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/slack".to_string(),
-        },
-        SkillInfo {
-            name: "google-drive".to_string(),
-            description: "Google Drive Access".to_string(),
-            url: "https://github.com/modelcontextprotocol/servers/tree/main/src/google-drive"
-                .to_string(),
-        },
-    ];
+    let locale = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        locale_from_settings(&conn)
+    };
+    let bundled: BundledMcpServers =
+        serde_json::from_str(BUNDLED_MCP_SERVERS).expect("bundled MCP servers");
+    let fallback_servers: Vec<SkillInfo> = bundled
+        .fallback
+        .iter()
+        .map(|server| SkillInfo {
+            name: server.name.clone(),
+            description: localize(&server.description, &locale),
+            url: server.url.clone(),
+        })
+        .collect();
 
-    let response = client
-        .get(url)
-        .header(USER_AGENT, "Opcode-Agent")
-        .send()
-        .await;
+    let response = send_with_retry(|| client.get(url).header(USER_AGENT, "Opcode-Agent")).await;
 
-    // Use fallback if request fails
+    // Use fallback if request fails, including HTTP errors (e.g. 403 Rate Limit, 404),
+    // but record it so the UI can say the marketplace may be degraded
     let response = match response {
         Ok(res) => res,
-        Err(_) => return Ok(fallback_servers),
+        Err(e) => {
+            schema_drift::record_fallback("mcp_servers", e.message());
+            return Ok(fallback_servers);
+        }
     };
 
-    if !response.status().is_success() {
-        // Fallback on HTTP error (e.g. 403 Rate Limit, 404)
+    let contents = match response.json().await {
+        Ok(body) => {
+            schema_drift::decode_list::<GitHubContent>("mcp_servers", body, &GITHUB_CONTENT)
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    let dirs: Vec<GitHubContent> = match contents {
+        Ok(items) => items
+            .into_iter()
+            .filter(|item| item.content_type == "dir")
+            .collect(),
+        Err(e) => {
+            schema_drift::record_fallback("mcp_servers", &e);
+            return Ok(fallback_servers);
+        }
+    };
+    if dirs.is_empty() {
+        schema_drift::record_fallback("mcp_servers", "the listing has no servers");
         return Ok(fallback_servers);
     }
 
-    let contents: Result<Vec<GitHubContent>, _> = response.json().await;
+    // 2. Summarize each server's README, a bounded number at a time
+    let settings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_catalog_fetch_settings(&conn)
+    };
+    let mut descriptions = fetch_descriptions(
+        &client,
+        dirs.iter().map(|item| item.name.clone()).collect(),
+        &settings,
+        |name| {
+            format!(
+                "https://raw.githubusercontent.com/modelcontextprotocol/servers/main/src/{}/README.md",
+                name
+            )
+        },
+        readme_summary,
+    )
+    .await;
+
+    let servers: Vec<SkillInfo> = dirs
+        .into_iter()
+        .map(|item| SkillInfo {
+            description: descriptions.remove(&item.name).unwrap_or_else(|| {
+                localize(&bundled.default_description, &locale).replace("{name}", &item.name)
+            }),
+            name: item.name,
+            url: item.html_url,
+        })
+        .collect();
+
+    // The fallback list isn't cached, so the next search tries GitHub again
+    catalog::remember("mcp_servers", &servers);
+    Ok(servers)
+}
+
+/// The built-in agent templates with descriptions in `locale`
+fn agent_templates(locale: &str) -> Vec<AgentTemplate> {
+    let bundled: Vec<BundledTemplate> =
+        serde_json::from_str(BUNDLED_AGENT_TEMPLATES).expect("bundled agent templates");
+    bundled
+        .into_iter()
+        .map(|template| AgentTemplate {
+            name: template.name,
+            description: localize(&template.description, locale),
+            prompt: template.prompt,
+            category: template.category,
+            variables: template
+                .variables
+                .into_iter()
+                .map(|variable| TemplateVariable {
+                    name: variable.name,
+                    description: localize(&variable.description, locale),
+                    default: variable.default,
+                    choices: variable.choices,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The built-in agent templates, described in the locale chosen in settings
+/// or in English where there is no translation
+#[command]
+pub async fn fetch_agent_templates(db: State<'_, AgentDb>) -> Result<Vec<AgentTemplate>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(agent_templates(&locale_from_settings(&conn)))
+}
+
+/// Render an agent template's prompt with values for its variables
+///
+/// Variables without a value fall back to their default; see
+/// `prompt_template::render` for what is rejected.
+#[command]
+pub async fn render_agent_template(
+    name: String,
+    variables: HashMap<String, String>,
+) -> Result<String, String> {
+    let template = agent_templates(DEFAULT_LOCALE)
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| format!("Agent template '{}' not found", name))?;
+    prompt_template::render(&template.prompt, &template.variables, &variables)
+}
+
+/// Metadata the GitHub contents API returns for a single file
+#[derive(Debug, Deserialize)]
+struct GitHubFileMeta {
+    sha: String,
+    download_url: Option<String>,
+}
+
+/// How downloaded skills are verified before being installed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkillVerificationSettings {
+    /// Minisign public key the skill registry signs SKILL.md files with
+    pub public_key: Option<String>,
+    /// Refuse skills that have no `.minisig` signature next to them
+    pub require_signature: bool,
+}
+
+/// Where a skill is installed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkillScope {
+    /// `.claude/skills` in one project
+    #[default]
+    Project,
+    /// `~/.claude/skills`, for every project
+    User,
+}
+
+/// A skill written to disk after passing verification
+#[derive(Debug, Serialize)]
+pub struct InstalledSkill {
+    name: String,
+    scope: SkillScope,
+    path: String,
+    /// Git blob SHA reported by GitHub, which the content was checked against
+    sha: String,
+    signature_verified: bool,
+    /// Skills installed because this one depends on them, in install order
+    dependencies: Vec<String>,
+    /// Programs this skill or its dependencies run that aren't on PATH
+    missing_binaries: Vec<MissingBinary>,
+}
+
+/// A program an installed skill runs that isn't on PATH
+#[derive(Debug, Serialize)]
+pub struct MissingBinary {
+    name: String,
+    /// Skills that run it
+    required_by: Vec<String>,
+    /// How to install it
+    hint: String,
+}
+
+/// A downloaded and verified skill, not written yet
+struct FetchedSkill {
+    name: String,
+    content: Vec<u8>,
+    sha: String,
+    signature_verified: bool,
+    dependencies: SkillDependencies,
+}
+
+/// Load the skill verification settings from the settings store
+pub fn load_skill_verification_settings(conn: &Connection) -> SkillVerificationSettings {
+    SkillVerificationSettings {
+        public_key: store::get_text(conn, "skills_signing_public_key")
+            .filter(|k| !k.trim().is_empty()),
+        require_signature: store::get_bool(conn, "skills_require_signature"),
+    }
+}
+
+/// Git blob hash of `content`, as reported by the GitHub contents API
+pub fn git_blob_sha(content: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Check a minisign signature over `content`
+pub fn verify_skill_signature(
+    content: &[u8],
+    signature: &str,
+    public_key: &str,
+) -> Result<(), String> {
+    let public_key = PublicKey::from_base64(public_key.trim())
+        .or_else(|_| PublicKey::decode(public_key.trim()))
+        .map_err(|e| format!("Invalid skill signing key: {}", e))?;
+    let signature =
+        Signature::decode(signature).map_err(|e| format!("Invalid skill signature: {}", e))?;
+    public_key
+        .verify(content, &signature, false)
+        .map_err(|e| format!("Skill signature does not match: {}", e))
+}
+
+/// Install a skill from the registry, with the skills it depends on
+///
+/// `scope` defaults to the project; user skills go to `~/.claude/skills` and
+/// don't need a project.
+#[command]
+pub async fn install_skill(
+    app: AppHandle,
+    project_path: Option<String>,
+    skill_name: String,
+    scope: Option<SkillScope>,
+) -> Result<InstalledSkill, NetworkError> {
+    let progress = ProgressReporter::start(&app, "skill_install");
+    let scope = scope.unwrap_or_default();
+    let result =
+        install_skill_with_progress(&app, project_path.as_deref(), &skill_name, scope, &progress)
+            .await;
+    progress.finish(&result);
+    result
+}
+
+/// Install a skill in the background and return the operation ID
+///
+/// Progress is emitted as `operation-progress:{id}` and the installed skill (or
+/// error) as `operation-complete:{id}`. Cancel with `cancel_operation`.
+#[command]
+pub async fn start_skill_install(
+    app: AppHandle,
+    project_path: Option<String>,
+    skill_name: String,
+    scope: Option<SkillScope>,
+) -> Result<String, String> {
+    let progress = ProgressReporter::start(&app, "skill_install");
+    let operation_id = progress.operation_id().to_string();
+    let scope = scope.unwrap_or_default();
+
+    tauri::async_runtime::spawn(async move {
+        let result = install_skill_with_progress(
+            &app,
+            project_path.as_deref(),
+            &skill_name,
+            scope,
+            &progress,
+        )
+        .await;
+        progress.finish(&result);
+    });
+
+    Ok(operation_id)
+}
+
+async fn install_skill_with_progress(
+    app: &AppHandle,
+    project_path: Option<&str>,
+    skill_name: &str,
+    scope: SkillScope,
+    progress: &ProgressReporter,
+) -> Result<InstalledSkill, NetworkError> {
+    let prepared = prepare_skill(app, project_path, skill_name, scope, progress).await?;
+
+    // Past this point nothing is cancelled
+    if progress.token().is_cancelled() {
+        return Err(NetworkError::cancelled());
+    }
+    progress.report("writing", Some(90), None);
+    let installed = prepared.write()?;
+    progress.report("done", Some(100), None);
+    Ok(installed)
+}
+
+/// A skill and the skills it depends on, downloaded and verified but not
+/// written yet
+pub(crate) struct PreparedSkill {
+    scope: SkillScope,
+    root: PathBuf,
+    skills_dir: PathBuf,
+    /// The skill first, then its dependencies in the order they were found
+    fetched: Vec<FetchedSkill>,
+    /// Program -> skills that run it
+    binaries: BTreeMap<String, Vec<String>>,
+}
+
+/// Download and verify a skill and every skill it depends on
+///
+/// Nothing is written, so a broken dependency leaves nothing behind.
+pub(crate) async fn prepare_skill(
+    app: &AppHandle,
+    project_path: Option<&str>,
+    skill_name: &str,
+    scope: SkillScope,
+    progress: &ProgressReporter,
+) -> Result<PreparedSkill, NetworkError> {
+    // Validate before touching the network so bad input fails fast
+    progress.report("validating", Some(0), None);
+    let (root, skills_dir) = skills_location(scope, project_path)?;
+    validate_name(skill_name, "skill name")?;
+
+    let settings = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_skill_verification_settings(&conn)
+    };
+    let client = crate::http_client::client()?;
+
+    let mut fetched: Vec<FetchedSkill> = Vec::new();
+    let mut seen = HashSet::from([skill_name.to_string()]);
+    let mut queue = VecDeque::from([(skill_name.to_string(), 0)]);
+    let mut binaries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    while let Some((name, depth)) = queue.pop_front() {
+        let skill = fetch_skill(&client, &settings, progress, &name).await?;
+        for binary in &skill.dependencies.binaries {
+            binaries
+                .entry(binary.clone())
+                .or_default()
+                .push(name.clone());
+        }
+        for dependency in &skill.dependencies.skills {
+            if !seen.insert(dependency.clone()) {
+                continue;
+            }
+            validate_name(dependency, "skill dependency")?;
+            if let Some(existing) = installed_skill_file(&root.join(&skills_dir), dependency) {
+                // Already installed, but it may still need programs that are missing
+                let content = fs::read_to_string(&existing).unwrap_or_default();
+                for binary in skill_dependencies(&content).unwrap_or_default().binaries {
+                    binaries.entry(binary).or_default().push(dependency.clone());
+                }
+                continue;
+            }
+            if depth >= MAX_DEPENDENCY_DEPTH {
+                return Err(format!(
+                    "Skill '{}' needs '{}', which is more than {} dependencies deep; not installing",
+                    name, dependency, MAX_DEPENDENCY_DEPTH
+                )
+                .into());
+            }
+            queue.push_back((dependency.clone(), depth + 1));
+        }
+        fetched.push(skill);
+    }
+
+    Ok(PreparedSkill {
+        scope,
+        root,
+        skills_dir,
+        fetched,
+        binaries,
+    })
+}
+
+impl PreparedSkill {
+    pub(crate) fn name(&self) -> &str {
+        &self.fetched[0].name
+    }
+
+    /// Write the skill and its dependencies
+    pub(crate) fn write(self) -> Result<InstalledSkill, NetworkError> {
+        // Dependencies first, so the skill is never on disk without them
+        let mut dest_path = PathBuf::new();
+        for skill in self.fetched.iter().rev() {
+            dest_path = write_skill(&self.root, &self.skills_dir, skill)?;
+        }
+
+        let missing_binaries: Vec<MissingBinary> = self
+            .binaries
+            .into_iter()
+            .filter(|(binary, _)| which::which(binary).is_err())
+            .map(|(name, required_by)| MissingBinary {
+                hint: install_hint(&name),
+                name,
+                required_by,
+            })
+            .collect();
+        for missing in &missing_binaries {
+            log::warn!(
+                "Skill {} needs `{}`, which is not on PATH",
+                missing.required_by.join(", "),
+                missing.name
+            );
+        }
+
+        let skill = &self.fetched[0];
+        Ok(InstalledSkill {
+            name: skill.name.clone(),
+            scope: self.scope,
+            path: dest_path.to_string_lossy().to_string(),
+            sha: skill.sha.clone(),
+            signature_verified: skill.signature_verified,
+            dependencies: self.fetched[1..]
+                .iter()
+                .rev()
+                .map(|s| s.name.clone())
+                .collect(),
+            missing_binaries,
+        })
+    }
+}
+
+/// How to put skill directories back as they were before a set of skills was
+/// written
+#[derive(Default)]
+pub(crate) struct SkillUndo {
+    /// Skill directory, whether it existed and the SKILL.md it held
+    previous: Vec<(PathBuf, bool, Option<Vec<u8>>)>,
+}
 
-    match contents {
-        Ok(items) => {
-            let mut servers = Vec::new();
-            for item in items {
-                if item.content_type == "dir" {
-                    servers.push(SkillInfo {
-                        name: item.name.clone(),
-                        description: format!("Official MCP Server: {}", item.name),
-                        url: item.html_url,
-                    });
+impl SkillUndo {
+    pub(crate) fn undo(self) {
+        for (dir, existed, content) in self.previous.into_iter().rev() {
+            match (existed, content) {
+                (_, Some(content)) => {
+                    let path = dir.join("SKILL.md");
+                    if write_atomically(&dir, &path, &content).is_ok() {
+                        file_audit::record("install_skill", FileOperation::Modify, &path);
+                    }
+                }
+                (true, None) => {
+                    let path = dir.join("SKILL.md");
+                    if fs::remove_file(&path).is_ok() {
+                        file_audit::record("install_skill", FileOperation::Delete, &path);
+                    }
+                }
+                (false, None) => {
+                    if fs::remove_dir_all(&dir).is_ok() {
+                        file_audit::record("install_skill", FileOperation::Delete, &dir);
+                    }
                 }
             }
-            if servers.is_empty() {
-                Ok(fallback_servers)
-            } else {
-                Ok(servers)
+        }
+    }
+}
+
+/// Write prepared skills all or nothing
+///
+/// When one fails, the skills already written are put back as they were and
+/// the error comes with the name of the skill that failed.
+pub(crate) fn write_skills(
+    prepared: Vec<PreparedSkill>,
+) -> Result<(Vec<InstalledSkill>, SkillUndo), (String, NetworkError)> {
+    let mut undo = SkillUndo::default();
+    let mut installed = Vec::new();
+    for skill in prepared {
+        for fetched in &skill.fetched {
+            let dir = skill.root.join(&skill.skills_dir).join(&fetched.name);
+            if undo.previous.iter().any(|(seen, _, _)| *seen == dir) {
+                continue;
+            }
+            let content = fs::read(dir.join("SKILL.md")).ok();
+            undo.previous.push((dir.clone(), dir.exists(), content));
+        }
+        let name = skill.name().to_string();
+        match skill.write() {
+            Ok(skill) => installed.push(skill),
+            Err(e) => {
+                undo.undo();
+                return Err((name, e));
             }
         }
-        Err(_) => Ok(fallback_servers),
     }
+    Ok((installed, undo))
 }
 
-#[command]
-pub async fn fetch_agent_templates() -> Result<Vec<AgentTemplate>, String> {
-    // Hardcoded high-quality agent templates
-    let templates = vec![
-        AgentTemplate {
-            name: "React Engineer".to_string(),
-            description: "Expert in React, TypeScript, and modern frontend development.".to_string(),
-            category: "Coding".to_string(),
-            prompt: "You are a Senior React Engineer. You write clean, performant, and accessible code using modern React patterns (Hooks, Context). You prefer functional components and TypeScript.".to_string(),
-        },
-        AgentTemplate {
-            name: "Python Architect".to_string(),
-            description: "Specializes in Python backend systems, FastAPI, and data structures.".to_string(),
-            category: "Coding".to_string(),
-            prompt: "You are a Python System Architect. You design robust, scalable backend systems. You follow PEP 8 and use type hints. You are expert in FastAPI, Django, and AsyncIO.".to_string(),
-        },
-        AgentTemplate {
-            name: "Tech Writer".to_string(),
-            description: "Creates clear, concise, and technical documentation.".to_string(),
-            category: "Writing".to_string(),
-            prompt: "You are a Technical Writer. You create documentation that is easy to understand for developers. You use clear language, code examples, and proper formatting.".to_string(),
-        },
-        AgentTemplate {
-            name: "Security Auditor".to_string(),
-            description: "Analyzes code for vulnerabilities and security flaws.".to_string(),
-            category: "Security".to_string(),
-            prompt: "You are a Security Auditor. checking for OWASP Top 10 vulnerabilities, insecure dependencies, and bad practices.".to_string(),
+/// Download SKILL.md for `skill_name` and check it against its checksum and,
+/// when a signing key is configured, its signature
+async fn fetch_skill(
+    client: &Client,
+    settings: &SkillVerificationSettings,
+    progress: &ProgressReporter,
+    skill_name: &str,
+) -> Result<FetchedSkill, NetworkError> {
+    let file = format!("{}/SKILL.md", skill_name);
+    let file = Some(file.as_str());
+
+    // 1. Look up the blob SHA of SKILL.md
+    progress.report("resolving", Some(5), file);
+    let meta_url = format!(
+        "https://api.github.com/repos/{}/contents/skills/{}/SKILL.md",
+        SKILLS_REPO, skill_name
+    );
+    let token = progress.token();
+    let meta: GitHubFileMeta = until_cancelled(token, async {
+        let response = send_with_retry(|| {
+            client
+                .get(&meta_url)
+                .header(USER_AGENT, "Opcode-Agent")
+                .header("Accept", "application/vnd.github+json")
+        })
+        .await
+        .map_err(|e| e.context("Failed to look up SKILL.md"))?;
+        let body = response.json().await?;
+        Ok::<_, NetworkError>(schema_drift::decode_one(
+            "skill_file",
+            body,
+            &GITHUB_CONTENT,
+        )?)
+    })
+    .await?;
+
+    // 2. Download it and check the content against the SHA
+    // https://raw.githubusercontent.com/anthropics/skills/main/skills/<name>/SKILL.md
+    let raw_url = meta.download_url.unwrap_or_else(|| {
+        format!(
+            "https://raw.githubusercontent.com/{}/main/skills/{}/SKILL.md",
+            SKILLS_REPO, skill_name
+        )
+    });
+    progress.report("downloading", Some(10), file);
+    let mut response = until_cancelled(
+        token,
+        send_with_retry(|| client.get(&raw_url).header(USER_AGENT, "Opcode-Agent")),
+    )
+    .await
+    .map_err(|e| e.context("Failed to download SKILL.md"))?;
+    let total = response.content_length().filter(|t| *t > 0);
+    let mut content = Vec::new();
+    while let Some(chunk) = until_cancelled(token, async {
+        Ok::<_, NetworkError>(response.chunk().await?)
+    })
+    .await?
+    {
+        content.extend_from_slice(&chunk);
+        let percent = total.map(|t| 10 + (content.len() as u64 * 60 / t).min(60) as u8);
+        progress.report("downloading", percent, file);
+    }
+
+    progress.report("verifying", Some(75), file);
+    let actual_sha = git_blob_sha(&content);
+    if !actual_sha.eq_ignore_ascii_case(&meta.sha) {
+        return Err(NetworkError::Integrity {
+            message: format!(
+                "SKILL.md for '{}' does not match the published checksum (expected {}, got {}); not installing",
+                skill_name, meta.sha, actual_sha
+            ),
+        });
+    }
+
+    // 3. Check the registry signature, when a signing key is configured
+    let signature = match &settings.public_key {
+        Some(_) => {
+            let signature_url = format!("{}.minisig", raw_url);
+            let response = until_cancelled(
+                token,
+                send_with_retry(|| {
+                    client
+                        .get(&signature_url)
+                        .header(USER_AGENT, "Opcode-Agent")
+                }),
+            )
+            .await;
+            match response {
+                Ok(response) => Some(
+                    until_cancelled(token, async move {
+                        Ok::<_, NetworkError>(response.text().await?)
+                    })
+                    .await?,
+                ),
+                Err(NetworkError::NotFound { .. }) => None,
+                Err(e) => return Err(e.context("Failed to download skill signature")),
+            }
+        }
+        None => None,
+    };
+
+    let signature_verified = match (&settings.public_key, signature) {
+        (Some(key), Some(signature)) => {
+            verify_skill_signature(&content, &signature, key).map_err(|message| {
+                NetworkError::Integrity {
+                    message: format!("{} for skill '{}'; not installing", message, skill_name),
+                }
+            })?;
+            true
+        }
+        _ if settings.require_signature => {
+            return Err(NetworkError::Integrity {
+                message: match settings.public_key {
+                    Some(_) => format!("Skill '{}' is not signed; not installing", skill_name),
+                    None => {
+                        "Skill signatures are required but no signing key is configured".to_string()
+                    }
+                },
+            });
+        }
+        _ => false,
+    };
+
+    let dependencies = skill_dependencies(&String::from_utf8_lossy(&content)).map_err(|e| {
+        format!(
+            "SKILL.md for '{}' has invalid dependencies: {}; not installing",
+            skill_name, e
+        )
+    })?;
+
+    Ok(FetchedSkill {
+        name: skill_name.to_string(),
+        content,
+        sha: actual_sha,
+        signature_verified,
+        dependencies,
+    })
+}
+
+/// Write a verified skill to `<skills_dir>/<name>/SKILL.md` under `root`
+fn write_skill(
+    root: &Path,
+    skills_dir: &Path,
+    skill: &FetchedSkill,
+) -> Result<PathBuf, NetworkError> {
+    // 4. Ensure <skills_dir>/<name> exists
+    let relative = skills_dir.join(&skill.name);
+    let skill_dir = resolve_within(root, &relative)?;
+    let created_dir = !skill_dir.exists();
+    fs::create_dir_all(&skill_dir).map_err(|e| e.to_string())?;
+
+    // 5. Write SKILL.md, re-checking now that the directory exists
+    let dest_path = match resolve_within(root, relative.join("SKILL.md")).and_then(|dest| {
+        let operation = FileOperation::for_write(&dest);
+        write_atomically(&skill_dir, &dest, &skill.content)?;
+        file_audit::record("install_skill", operation, &dest);
+        Ok(dest)
+    }) {
+        Ok(dest) => dest,
+        Err(e) => {
+            // Don't leave an empty skill directory behind
+            if created_dir {
+                let _ = fs::remove_dir_all(&skill_dir);
+            }
+            return Err(e.into());
+        }
+    };
+
+    log::info!(
+        "Installed skill {} (blob {}, signature verified: {})",
+        skill.name,
+        skill.sha,
+        skill.signature_verified
+    );
+    Ok(dest_path)
+}
+
+/// Where skills of a scope are installed: a root directory and the skills
+/// directory inside it, which is resolved with `resolve_within`
+fn skills_location(
+    scope: SkillScope,
+    project_path: Option<&str>,
+) -> Result<(PathBuf, PathBuf), String> {
+    match scope {
+        SkillScope::Project => {
+            let project_path =
+                project_path.ok_or("A project is required to install a project skill")?;
+            Ok((
+                validate_project_root(project_path)?,
+                PathBuf::from(".claude").join("skills"),
+            ))
+        }
+        SkillScope::User => {
+            let claude_dir = crate::utils::get_claude_dir()?;
+            fs::create_dir_all(&claude_dir)
+                .map_err(|e| format!("Failed to create {}: {}", claude_dir.display(), e))?;
+            let claude_dir = claude_dir
+                .canonicalize()
+                .map_err(|e| format!("Failed to resolve {}: {}", claude_dir.display(), e))?;
+            Ok((claude_dir, PathBuf::from("skills")))
         }
+    }
+}
+
+/// SKILL.md of a skill already installed in `skills_dir` or for the user
+fn installed_skill_file(skills_dir: &Path, skill_name: &str) -> Option<PathBuf> {
+    let mut dirs = vec![skills_dir.to_path_buf()];
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        dirs.push(claude_dir.join("skills"));
+    }
+    dirs.into_iter()
+        .map(|dir| dir.join(skill_name).join("SKILL.md"))
+        .find(|file| file.is_file())
+}
+
+/// How to install a program a skill needs, for the current platform
+fn install_hint(binary: &str) -> String {
+    // Programs whose package is named differently: (binary, Homebrew, apt)
+    const PACKAGES: &[(&str, &str, &str)] = &[
+        ("soffice", "--cask libreoffice", "libreoffice"),
+        ("pdftotext", "poppler", "poppler-utils"),
+        ("pdftoppm", "poppler", "poppler-utils"),
+        ("tesseract", "tesseract", "tesseract-ocr"),
+        ("magick", "imagemagick", "imagemagick"),
+        ("convert", "imagemagick", "imagemagick"),
     ];
-    Ok(templates)
+    let (brew, apt) = PACKAGES
+        .iter()
+        .find(|(name, _, _)| *name == binary)
+        .map(|(_, brew, apt)| (*brew, *apt))
+        .unwrap_or((binary, binary));
+    if cfg!(target_os = "macos") {
+        format!("Install it with `brew install {}`", brew)
+    } else if cfg!(target_os = "linux") {
+        format!(
+            "Install it with your package manager, e.g. `sudo apt install {}`",
+            apt
+        )
+    } else {
+        format!("Install {} and make sure `{}` is on PATH", apt, binary)
+    }
 }
 
+/// Write `content` to `dest` through a temporary file in `dir`
+///
+/// The temporary file is removed if anything fails, so a reader never sees a
+/// partially written file.
+pub(crate) fn write_atomically(dir: &Path, dest: &Path, content: &[u8]) -> Result<(), String> {
+    let mut file = tempfile::NamedTempFile::new_in(dir)
+        .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+    file.write_all(content)
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    file.persist(dest)
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e.error))?;
+    Ok(())
+}
+
+/// A skill found on disk, enabled or not
+#[derive(Debug, Serialize)]
+pub struct SkillEntry {
+    name: String,
+    scope: SkillScope,
+    /// SKILL.md of the skill
+    path: String,
+    description: Option<String>,
+    /// Disabled skills are kept in `.claude/skills/.disabled`, where Claude doesn't load them
+    enabled: bool,
+}
+
+/// Skills in one skills directory
+fn skill_entries(dir: &Path, scope: SkillScope, enabled: bool) -> Vec<SkillEntry> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let skill_file = entry.path().join("SKILL.md");
+            let content = fs::read_to_string(&skill_file).ok()?;
+            Some(SkillEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                scope,
+                path: skill_file.to_string_lossy().to_string(),
+                description: skill_description(&content),
+                enabled,
+            })
+        })
+        .collect()
+}
+
+/// List the skills installed in a project and for the user, including disabled ones
+///
+/// Without a project only user skills are listed.
 #[command]
-pub async fn install_skill(project_path: String, skill_name: String) -> Result<(), String> {
-    // 1. Construct raw URL
-    // https://raw.githubusercontent.com/anthropics/skills/main/skills/<name>/SKILL.md
+pub async fn list_installed_skills(
+    project_path: Option<String>,
+) -> Result<Vec<SkillEntry>, String> {
+    let mut dirs = Vec::new();
+    if let Some(project_path) = project_path {
+        let project_root = validate_project_root(&project_path)?;
+        dirs.push((
+            project_root.join(".claude").join("skills"),
+            SkillScope::Project,
+        ));
+    }
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        dirs.push((claude_dir.join("skills"), SkillScope::User));
+    }
 
-    let raw_url = format!(
-        "https://raw.githubusercontent.com/anthropics/skills/main/skills/{}/SKILL.md",
-        skill_name
-    );
+    let mut skills = Vec::new();
+    for (dir, scope) in dirs {
+        skills.extend(skill_entries(&dir, scope, true));
+        skills.extend(skill_entries(&dir.join(DISABLED_SKILLS_DIR), scope, false));
+    }
+    // Project skills first, as they hide user skills of the same name
+    skills.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| (a.scope == SkillScope::User).cmp(&(b.scope == SkillScope::User)))
+    });
+    Ok(skills)
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&raw_url)
-        .header(USER_AGENT, "Opcode-Agent")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+/// Enable or disable a project skill without deleting it
+///
+/// Disabling moves `.claude/skills/<name>` to `.claude/skills/.disabled/<name>`;
+/// enabling moves it back.
+#[command]
+pub async fn set_skill_enabled(
+    project_path: String,
+    skill_name: String,
+    enabled: bool,
+) -> Result<SkillEntry, String> {
+    let project_root = validate_project_root(&project_path)?;
+    validate_name(&skill_name, "skill name")?;
+    let skills_dir = PathBuf::from(".claude").join("skills");
+    let active = resolve_within(&project_root, skills_dir.join(&skill_name))?;
+    let disabled = resolve_within(
+        &project_root,
+        skills_dir.join(DISABLED_SKILLS_DIR).join(&skill_name),
+    )?;
+    let (from, to) = if enabled {
+        (disabled, active)
+    } else {
+        (active, disabled)
+    };
+
+    // Already in the requested state when only `to` has the skill
+    let needs_move = from.join("SKILL.md").is_file();
+    if !needs_move && !to.join("SKILL.md").is_file() {
+        return Err(format!(
+            "Skill '{}' is not installed in this project",
+            skill_name
+        ));
+    }
+    if needs_move {
+        if to.exists() {
+            return Err(format!(
+                "Can't {} skill '{}': {} already exists",
+                if enabled { "enable" } else { "disable" },
+                skill_name,
+                to.display()
+            ));
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::rename(&from, &to).map_err(|e| {
+            format!(
+                "Failed to move {} to {}: {}",
+                from.display(),
+                to.display(),
+                e
+            )
+        })?;
+        file_audit::record("set_skill_enabled", FileOperation::Delete, &from);
+        file_audit::record("set_skill_enabled", FileOperation::Create, &to);
+        log::info!(
+            "{} skill {} in {}",
+            if enabled { "Enabled" } else { "Disabled" },
+            skill_name,
+            project_root.display()
+        );
+    }
+
+    let skill_file = to.join("SKILL.md");
+    Ok(SkillEntry {
+        description: fs::read_to_string(&skill_file)
+            .ok()
+            .and_then(|content| skill_description(&content)),
+        name: skill_name,
+        scope: SkillScope::Project,
+        path: skill_file.to_string_lossy().to_string(),
+        enabled,
+    })
+}
 
-    if !response.status().is_success() {
+/// Move a project skill to `~/.claude/skills` so every project can use it
+///
+/// Fails when the user already has a skill of that name, unless `overwrite`
+/// is set.
+#[command]
+pub async fn promote_skill_to_user_scope(
+    project_path: String,
+    skill_name: String,
+    overwrite: Option<bool>,
+) -> Result<SkillEntry, String> {
+    let project_root = validate_project_root(&project_path)?;
+    validate_name(&skill_name, "skill name")?;
+    let from = resolve_within(
+        &project_root,
+        PathBuf::from(".claude").join("skills").join(&skill_name),
+    )?;
+    if !from.join("SKILL.md").is_file() {
         return Err(format!(
-            "Failed to download SKILL.md: {}",
-            response.status()
+            "Skill '{}' is not installed in this project",
+            skill_name
         ));
     }
 
-    let content = response.text().await.map_err(|e| e.to_string())?;
+    let (user_root, user_skills) = skills_location(SkillScope::User, None)?;
+    let to = resolve_within(&user_root, user_skills.join(&skill_name))?;
+    let replaced = to.exists();
+    replace_dir(&from, &to, overwrite.unwrap_or(false))?;
+    if replaced {
+        file_audit::record("promote_skill_to_user_scope", FileOperation::Delete, &to);
+    }
+    file_audit::record("promote_skill_to_user_scope", FileOperation::Delete, &from);
+    file_audit::record("promote_skill_to_user_scope", FileOperation::Create, &to);
+    log::info!(
+        "Promoted skill {} from {} to user scope",
+        skill_name,
+        project_root.display()
+    );
+
+    let skill_file = to.join("SKILL.md");
+    Ok(SkillEntry {
+        description: fs::read_to_string(&skill_file)
+            .ok()
+            .and_then(|content| skill_description(&content)),
+        name: skill_name,
+        scope: SkillScope::User,
+        path: skill_file.to_string_lossy().to_string(),
+        enabled: true,
+    })
+}
 
-    // 2. Ensure .claude/skills/<name> exists
-    let mut dest_path = PathBuf::from(&project_path);
-    dest_path.push(".claude");
-    dest_path.push("skills");
-    dest_path.push(&skill_name);
+/// Move `from` to `to`, replacing an existing `to` only when `overwrite` is set
+///
+/// The replaced directory is moved aside first and put back if the move fails.
+fn replace_dir(from: &Path, to: &Path, overwrite: bool) -> Result<(), String> {
+    let name = to
+        .file_name()
+        .ok_or_else(|| format!("Invalid destination: {}", to.display()))?;
+    let backup = to.with_file_name(format!(".{}.replaced", name.to_string_lossy()));
+    let replacing = to.exists();
+    if replacing {
+        if !overwrite {
+            return Err(format!(
+                "A user skill named '{}' already exists at {}",
+                name.to_string_lossy(),
+                to.display()
+            ));
+        }
+        if backup.exists() {
+            fs::remove_dir_all(&backup)
+                .map_err(|e| format!("Failed to remove {}: {}", backup.display(), e))?;
+        }
+        fs::rename(to, &backup)
+            .map_err(|e| format!("Failed to move {} aside: {}", to.display(), e))?;
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
 
-    fs::create_dir_all(&dest_path).map_err(|e| e.to_string())?;
+    if let Err(e) = move_dir(from, to) {
+        if replacing {
+            if let Err(restore) = fs::rename(&backup, to) {
+                log::error!(
+                    "Failed to restore {} from {}: {}",
+                    to.display(),
+                    backup.display(),
+                    restore
+                );
+            }
+        }
+        return Err(e);
+    }
+    if replacing {
+        if let Err(e) = fs::remove_dir_all(&backup) {
+            log::warn!("Failed to remove {}: {}", backup.display(), e);
+        }
+    }
+    Ok(())
+}
 
-    // 3. Write SKILL.md
-    dest_path.push("SKILL.md");
-    fs::write(&dest_path, content).map_err(|e| e.to_string())?;
+/// Move a directory, copying it when it's on another filesystem than `to`
+///
+/// On error `from` is left intact and nothing is left at `to`.
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(e);
+    }
+    if let Err(e) = fs::remove_dir_all(from) {
+        log::warn!("Copied {} but failed to remove it: {}", from.display(), e);
+    }
+    Ok(())
+}
 
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        let target = to.join(relative);
+        let result = if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+        } else {
+            fs::copy(entry.path(), &target).map(|_| ())
+        };
+        result.map_err(|e| format!("Failed to copy to {}: {}", target.display(), e))?;
+    }
     Ok(())
 }
+
+/// Get how catalog descriptions are fetched
+#[command]
+pub async fn get_catalog_fetch_settings(
+    db: State<'_, AgentDb>,
+) -> Result<CatalogFetchSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_catalog_fetch_settings(&conn))
+}
+
+/// Save how catalog descriptions are fetched
+#[command]
+pub async fn save_catalog_fetch_settings(
+    db: State<'_, AgentDb>,
+    settings: CatalogFetchSettings,
+) -> Result<(), String> {
+    let values = [
+        ("catalog_fetch_concurrency", settings.concurrency.into()),
+        (
+            "catalog_request_timeout_secs",
+            settings.request_timeout_secs.into(),
+        ),
+    ];
+    // Validate both before writing either
+    for (key, value) in &values {
+        store::encode(store::spec(key)?, value)?;
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    for (key, value) in &values {
+        store::set_value(&conn, key, value)?;
+    }
+    Ok(())
+}
+
+/// Get how downloaded skills are verified
+#[command]
+pub async fn get_skill_verification_settings(
+    db: State<'_, AgentDb>,
+) -> Result<SkillVerificationSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_skill_verification_settings(&conn))
+}
+
+/// Save how downloaded skills are verified
+#[command]
+pub async fn save_skill_verification_settings(
+    db: State<'_, AgentDb>,
+    settings: SkillVerificationSettings,
+) -> Result<(), String> {
+    let public_key = settings
+        .public_key
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or_default();
+    if !public_key.is_empty()
+        && PublicKey::from_base64(public_key).is_err()
+        && PublicKey::decode(public_key).is_err()
+    {
+        return Err("Invalid minisign public key".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store::set_value(&conn, "skills_signing_public_key", &public_key.into())?;
+    store::set_value(
+        &conn,
+        "skills_require_signature",
+        &settings.require_signature.into(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_description_parsing() {
+        let skill = "---\nname: pdf\ndescription: Read and fill PDF forms\n---\n# PDF\n";
+        assert_eq!(
+            skill_description(skill).as_deref(),
+            Some("Read and fill PDF forms")
+        );
+        assert_eq!(skill_description("# No frontmatter"), None);
+
+        let readme = "# Fetch MCP Server\n\n[![badge](x)](y)\n\nA server that\nfetches web pages.\n\n## Tools";
+        assert_eq!(
+            readme_summary(readme).as_deref(),
+            Some("A server that fetches web pages.")
+        );
+    }
+
+    #[test]
+    fn test_skill_dependencies_parsing() {
+        let skill =
+            "---\nname: report\ndependencies:\n  skills: [docx]\n  binaries:\n    - pandoc\n---\n";
+        assert_eq!(
+            skill_dependencies(skill).unwrap(),
+            SkillDependencies {
+                skills: vec!["docx".to_string()],
+                binaries: vec!["pandoc".to_string()],
+            }
+        );
+        assert_eq!(
+            skill_dependencies("# No frontmatter").unwrap(),
+            SkillDependencies::default()
+        );
+        assert!(skill_dependencies("---\ndependencies: pandoc\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_skill_preview_parsing() {
+        let skill =
+            "---\nname: pdf\nallowed-tools: Read, Bash(pdftotext:*)\n---\n\n# PDF\nUse it.\n";
+        assert_eq!(skill_body(skill), "# PDF\nUse it.\n");
+        assert_eq!(
+            skill_allowed_tools(skill),
+            vec!["Read", "Bash(pdftotext:*)"]
+        );
+        assert_eq!(
+            skill_allowed_tools("---\nallowed-tools:\n  - Grep\n---\n"),
+            vec!["Grep"]
+        );
+        assert_eq!(skill_body("# No frontmatter"), "# No frontmatter");
+
+        assert_eq!(registry_repo(None).unwrap(), SKILLS_REPO);
+        assert_eq!(
+            registry_repo(Some("https://github.com/acme/skills/")).unwrap(),
+            "acme/skills"
+        );
+        assert!(registry_repo(Some("acme")).is_err());
+        assert!(registry_repo(Some("acme/../x")).is_err());
+    }
+
+    #[test]
+    fn test_bundled_catalog_is_localized() {
+        let german = agent_templates("de-DE");
+        let english = agent_templates("en-US");
+        assert_eq!(german.len(), english.len());
+        let reviewer = german.iter().find(|t| t.name == "Code Reviewer").unwrap();
+        assert!(reviewer.description.starts_with("Prüft"));
+        assert_eq!(reviewer.variables.len(), 3);
+        // No Korean translation; English is used
+        assert_eq!(
+            agent_templates("ko-KR")[0].description,
+            english[0].description
+        );
+
+        let servers: BundledMcpServers = serde_json::from_str(BUNDLED_MCP_SERVERS).unwrap();
+        assert!(servers
+            .fallback
+            .iter()
+            .all(|server| !localize(&server.description, "zh-CN").is_empty()));
+        assert!(localize(&servers.default_description, "ja-JP").contains("{name}"));
+    }
+
+    #[test]
+    fn test_git_blob_sha_matches_git() {
+        // `printf 'hello\n' | git hash-object --stdin`
+        assert_eq!(
+            git_blob_sha(b"hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+        assert_eq!(
+            git_blob_sha(b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+    }
+
+    fn write_skill(dir: &Path, body: &str) {
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(dir.join("SKILL.md"), body).unwrap();
+        fs::write(dir.join("scripts").join("run.sh"), "echo hi").unwrap();
+    }
+
+    #[test]
+    fn test_skills_location_project_scope() {
+        let project = tempfile::tempdir().unwrap();
+        let path = project.path().to_str().unwrap();
+        let (root, skills) = skills_location(SkillScope::Project, Some(path)).unwrap();
+        assert_eq!(root, project.path().canonicalize().unwrap());
+        assert_eq!(skills, PathBuf::from(".claude").join("skills"));
+        assert!(skills_location(SkillScope::Project, None).is_err());
+    }
+
+    #[test]
+    fn test_promote_moves_skill() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("project").join("pdf");
+        let to = tmp.path().join("user").join("skills").join("pdf");
+        write_skill(&from, "new");
+
+        replace_dir(&from, &to, false).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join("SKILL.md")).unwrap(), "new");
+        assert!(to.join("scripts").join("run.sh").is_file());
+    }
+
+    #[test]
+    fn test_promote_overwrite_conflict() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("project").join("pdf");
+        let to = tmp.path().join("user").join("pdf");
+        let backup = tmp.path().join("user").join(".pdf.replaced");
+        write_skill(&from, "new");
+        write_skill(&to, "old");
+
+        // Without overwrite both copies stay untouched
+        assert!(replace_dir(&from, &to, false).is_err());
+        assert_eq!(fs::read_to_string(from.join("SKILL.md")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(to.join("SKILL.md")).unwrap(), "old");
+
+        // A failed move puts the existing skill back
+        let missing = tmp.path().join("project").join("missing");
+        assert!(replace_dir(&missing, &to, true).is_err());
+        assert_eq!(fs::read_to_string(to.join("SKILL.md")).unwrap(), "old");
+        assert!(!backup.exists());
+
+        replace_dir(&from, &to, true).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join("SKILL.md")).unwrap(), "new");
+        assert!(!backup.exists());
+    }
+}
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::file_audit::{self, FileOperation};
+
 /// Represents a custom slash command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlashCommand {
@@ -404,8 +406,10 @@ pub async fn slash_command_save(
     full_content.push_str(&content);
 
     // Write file
+    let operation = FileOperation::for_write(&file_path);
     fs::write(&file_path, &full_content)
         .map_err(|e| format!("Failed to write command file: {}", e))?;
+    file_audit::record("slash_command_save", operation, &file_path);
 
     // Load and return the saved command
     load_command_from_file(&file_path, &base_dir, &scope)
@@ -440,6 +444,11 @@ pub async fn slash_command_delete(
     // Delete the file
     fs::remove_file(&command.file_path)
         .map_err(|e| format!("Failed to delete command file: {}", e))?;
+    file_audit::record(
+        "slash_command_delete",
+        FileOperation::Delete,
+        Path::new(&command.file_path),
+    );
 
     // Clean up empty directories
     if let Some(parent) = Path::new(&command.file_path).parent() {
@@ -0,0 +1,40 @@
+use tauri::{AppHandle, Manager};
+
+use super::agents::AgentDb;
+use crate::storage_usage::{self, CleanupOptions, CleanupReport, StorageUsage};
+
+fn app_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+/// Disk used by the app's database, backups, logs, checkpoints, worktrees,
+/// session artifacts and caches, and the size of the run history
+#[tauri::command]
+pub async fn get_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    let app_dir = app_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        storage_usage::usage(&app.state::<AgentDb>(), &app_dir)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Remove orphaned artifacts, then items older than `max_age_days`, then the
+/// oldest items until each category fits in `max_size_mb`
+///
+/// Set `dry_run` to see what would be removed first.
+#[tauri::command]
+pub async fn cleanup_storage(
+    app: AppHandle,
+    options: Option<CleanupOptions>,
+) -> Result<CleanupReport, String> {
+    let app_dir = app_dir(&app)?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        storage_usage::cleanup(&app.state::<AgentDb>(), &app_dir, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,127 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::settings as store;
+use crate::process::output_stream::BatchConfig;
+use crate::process::{LiveOutputChunk, ProcessRegistryState, DEFAULT_BUFFER_LINES};
+
+/// Settings for streaming live process output to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingSettings {
+    /// How often batched output events are flushed
+    pub batch_interval_ms: u64,
+    /// Maximum number of lines in a single batched event
+    pub max_batch_lines: usize,
+    /// Number of output lines kept in memory per run for catching up
+    pub buffer_lines: usize,
+    /// Whether to also emit one event per line for older listeners; off by
+    /// default, since it undoes the batching
+    pub emit_line_events: bool,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            batch_interval_ms: 50,
+            max_batch_lines: 200,
+            buffer_lines: DEFAULT_BUFFER_LINES,
+            emit_line_events: false,
+        }
+    }
+}
+
+impl StreamingSettings {
+    pub fn batch_config(&self) -> BatchConfig {
+        BatchConfig {
+            interval_ms: self.batch_interval_ms,
+            max_lines: self.max_batch_lines,
+        }
+    }
+}
+
+/// Load the streaming settings, falling back to defaults
+pub fn load_streaming_settings(conn: &Connection) -> StreamingSettings {
+    let defaults = StreamingSettings::default();
+    let integer = |key: &str| store::get_integer(conn, key).and_then(|v| u64::try_from(v).ok());
+    StreamingSettings {
+        batch_interval_ms: integer("output_batch_interval_ms")
+            .unwrap_or(defaults.batch_interval_ms),
+        max_batch_lines: integer("output_batch_max_lines")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.max_batch_lines),
+        buffer_lines: integer("output_buffer_lines")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.buffer_lines),
+        emit_line_events: store::get_bool(conn, "output_emit_line_events"),
+    }
+}
+
+/// Get buffered live output for a run, starting at `since_seq`
+///
+/// Pass the `next_seq` from the previous response (or the `last_seq + 1` of the last
+/// batch received) to catch up after a UI reload without re-reading the whole output.
+#[tauri::command]
+pub async fn get_live_output(
+    registry: State<'_, ProcessRegistryState>,
+    run_id: i64,
+    since_seq: Option<u64>,
+) -> Result<LiveOutputChunk, String> {
+    let chunk = registry
+        .0
+        .get_live_output_since(run_id, since_seq.unwrap_or(0))?;
+    Ok(chunk.unwrap_or(LiveOutputChunk {
+        run_id,
+        lines: Vec::new(),
+        next_seq: 0,
+        missed: 0,
+    }))
+}
+
+/// Get the output streaming settings
+#[tauri::command]
+pub async fn get_output_streaming_settings(
+    db: State<'_, AgentDb>,
+) -> Result<StreamingSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_streaming_settings(&conn))
+}
+
+/// Save the output streaming settings
+///
+/// The buffer size applies to runs started after the change.
+#[tauri::command]
+pub async fn save_output_streaming_settings(
+    db: State<'_, AgentDb>,
+    registry: State<'_, ProcessRegistryState>,
+    settings: StreamingSettings,
+) -> Result<(), String> {
+    if settings.batch_interval_ms == 0 {
+        return Err("Batch interval must be greater than 0".to_string());
+    }
+    if settings.max_batch_lines == 0 || settings.buffer_lines == 0 {
+        return Err("Batch and buffer sizes must be greater than 0".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store::set_value(
+        &conn,
+        "output_batch_interval_ms",
+        &settings.batch_interval_ms.into(),
+    )?;
+    store::set_value(
+        &conn,
+        "output_batch_max_lines",
+        &settings.max_batch_lines.into(),
+    )?;
+    store::set_value(&conn, "output_buffer_lines", &settings.buffer_lines.into())?;
+    store::set_value(
+        &conn,
+        "output_emit_line_events",
+        &settings.emit_line_events.into(),
+    )?;
+
+    registry.0.set_output_buffer_lines(settings.buffer_lines);
+    Ok(())
+}
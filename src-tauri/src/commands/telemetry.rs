@@ -0,0 +1,111 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::settings as store;
+use crate::otlp::{export_traces, OtlpConfig, RunTracer};
+
+/// Settings for exporting run traces to an OpenTelemetry collector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    /// Base URL of the OTLP/HTTP collector, e.g. `http://localhost:4318`
+    pub endpoint: String,
+    /// Extra headers sent with each export (e.g. API keys)
+    pub headers: HashMap<String, String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            headers: HashMap::new(),
+            service_name: "opcode".to_string(),
+        }
+    }
+}
+
+impl TelemetrySettings {
+    /// Collector configuration, if export is enabled and an endpoint is set
+    pub fn otlp_config(&self) -> Option<OtlpConfig> {
+        if !self.enabled || self.endpoint.trim().is_empty() {
+            return None;
+        }
+        Some(OtlpConfig {
+            endpoint: self.endpoint.trim().to_string(),
+            headers: self.headers.clone(),
+            service_name: self.service_name.clone(),
+        })
+    }
+}
+
+/// Load the telemetry settings, falling back to defaults
+pub fn load_telemetry_settings(conn: &Connection) -> TelemetrySettings {
+    let defaults = TelemetrySettings::default();
+    TelemetrySettings {
+        enabled: store::get_bool(conn, "otel_enabled"),
+        endpoint: store::get_text(conn, "otel_endpoint").unwrap_or_default(),
+        headers: store::get_value(conn, "otel_headers")
+            .ok()
+            .and_then(|headers| serde_json::from_value(headers).ok())
+            .unwrap_or_default(),
+        service_name: store::get_text(conn, "otel_service_name")
+            .filter(|v| !v.is_empty())
+            .unwrap_or(defaults.service_name),
+    }
+}
+
+/// Create a tracer for a run if trace export is enabled
+pub fn start_run_trace(conn: &Connection, root_name: &str) -> Option<Arc<RunTracer>> {
+    load_telemetry_settings(conn)
+        .otlp_config()
+        .map(|config| RunTracer::new(config, root_name))
+}
+
+/// Get the telemetry settings
+#[tauri::command]
+pub async fn get_telemetry_settings(db: State<'_, AgentDb>) -> Result<TelemetrySettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_telemetry_settings(&conn))
+}
+
+/// Save the telemetry settings
+#[tauri::command]
+pub async fn save_telemetry_settings(
+    db: State<'_, AgentDb>,
+    settings: TelemetrySettings,
+) -> Result<(), String> {
+    if settings.enabled && settings.endpoint.trim().is_empty() {
+        return Err("A collector endpoint is required to enable trace export".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store::set_value(&conn, "otel_enabled", &settings.enabled.into())?;
+    store::set_value(&conn, "otel_endpoint", &settings.endpoint.into())?;
+    store::set_value(&conn, "otel_headers", &serde_json::json!(settings.headers))?;
+    store::set_value(&conn, "otel_service_name", &settings.service_name.into())?;
+    Ok(())
+}
+
+/// Send a single test span to the collector and return its trace ID
+#[tauri::command]
+pub async fn test_telemetry_export(settings: TelemetrySettings) -> Result<String, String> {
+    let config = TelemetrySettings {
+        enabled: true,
+        ..settings
+    }
+    .otlp_config()
+    .ok_or("A collector endpoint is required")?;
+
+    let tracer = RunTracer::new(config.clone(), "opcode.test");
+    tracer.set_attribute("opcode.test", true);
+    let payload = tracer.finish_payload(None);
+
+    export_traces(&config, &payload).await?;
+    Ok(tracer.trace_id().to_string())
+}
@@ -0,0 +1,432 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::mcp::list_server_tools;
+use super::skill_usage::installed_skills;
+use crate::path_validation::validate_project_root;
+use crate::tokens;
+use crate::utils::read_json_file;
+
+/// Most files read for one project estimate
+const MAX_FILES: usize = 2_000;
+/// Larger files are skipped; they wouldn't fit a context window anyway
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Estimated size of some text against a model's context window
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenEstimate {
+    pub tokens: u64,
+    pub context_window: u64,
+    /// Share of the context window used, 0-100 (can exceed 100)
+    pub percent_of_window: f64,
+}
+
+impl TokenEstimate {
+    fn new(tokens: u64, context_window: u64) -> Self {
+        Self {
+            tokens,
+            context_window,
+            percent_of_window: tokens as f64 * 100.0 / context_window.max(1) as f64,
+        }
+    }
+}
+
+/// A file counted in a project estimate
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTokens {
+    /// Relative to the project, or `~/.claude/CLAUDE.md` for the user memory
+    pub path: String,
+    pub bytes: u64,
+    pub tokens: u64,
+}
+
+/// Estimated context of a run: memory files plus the selected files
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectContextEstimate {
+    pub files: Vec<FileTokens>,
+    /// Matched files left out because they are binary or too large
+    pub skipped: Vec<String>,
+    /// More files matched than are counted; the estimate is a lower bound
+    pub truncated: bool,
+    pub total: TokenEstimate,
+    /// The estimate is larger than the context window
+    pub exceeds_window: bool,
+}
+
+/// Something loaded into every session before the first prompt
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextContributor {
+    /// "memory", "skill" or "mcp_server"
+    pub kind: String,
+    pub name: String,
+    /// The file it comes from, or the scope of an MCP server
+    pub source: String,
+    pub tokens: u64,
+    /// e.g. an MCP server's tool count, or why it couldn't be measured
+    pub detail: Option<String>,
+}
+
+/// What a project's setup costs in context before any work is done
+#[derive(Debug, Clone, Serialize)]
+pub struct StaticContextEstimate {
+    /// Largest first
+    pub contributors: Vec<ContextContributor>,
+    pub memory_tokens: u64,
+    pub skill_tokens: u64,
+    pub mcp_tokens: u64,
+    pub total: TokenEstimate,
+}
+
+/// Memory files Claude Code loads on its own, with their labels
+fn memory_files(project: &Path) -> Vec<(PathBuf, String)> {
+    let mut memory = vec![
+        (project.join("CLAUDE.md"), "CLAUDE.md".to_string()),
+        (
+            project.join("CLAUDE.local.md"),
+            "CLAUDE.local.md".to_string(),
+        ),
+    ];
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        memory.push((
+            claude_dir.join("CLAUDE.md"),
+            "~/.claude/CLAUDE.md".to_string(),
+        ));
+    }
+    memory.into_iter().filter(|(p, _)| p.is_file()).collect()
+}
+
+/// MCP servers a session in `project` starts with, as (name, scope, config)
+///
+/// Local servers win over project servers, which win over user servers.
+pub(crate) fn configured_mcp_servers(project: &Path) -> Vec<(String, String, JsonValue)> {
+    let claude_json = dirs::home_dir()
+        .map(|home| read_json_file(&home.join(".claude.json")).unwrap_or_default())
+        .unwrap_or(JsonValue::Null);
+    let project_entry = &claude_json["projects"][project.to_string_lossy().as_ref()];
+    let disabled: Vec<&str> = project_entry["disabledMcpjsonServers"]
+        .as_array()
+        .map(|names| names.iter().filter_map(|n| n.as_str()).collect())
+        .unwrap_or_default();
+    let mcp_json = read_json_file(&project.join(".mcp.json")).unwrap_or_default();
+
+    let mut servers: Vec<(String, String, JsonValue)> = Vec::new();
+    let scopes = [
+        ("local", &project_entry["mcpServers"]),
+        ("project", &mcp_json["mcpServers"]),
+        ("user", &claude_json["mcpServers"]),
+    ];
+    for (scope, configs) in scopes {
+        let Some(configs) = configs.as_object() else {
+            continue;
+        };
+        for (name, config) in configs {
+            if scope == "project" && disabled.contains(&name.as_str()) {
+                continue;
+            }
+            if servers.iter().any(|(n, _, _)| n == name) {
+                continue;
+            }
+            servers.push((name.clone(), scope.to_string(), config.clone()));
+        }
+    }
+    servers
+}
+
+/// Settings the user wrote that can approve a project's `.mcp.json` servers:
+/// the project's `~/.claude.json` entry, the user settings and the project's
+/// local settings
+///
+/// The checked-in `.claude/settings.json` isn't one of them; it comes with the
+/// repository, like `.mcp.json` itself.
+fn mcp_approval_sources(project: &Path) -> Vec<JsonValue> {
+    let mut sources = vec![
+        read_json_file(&project.join(".claude").join("settings.local.json")).unwrap_or_default(),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        let claude_json = read_json_file(&home.join(".claude.json")).unwrap_or_default();
+        sources.push(claude_json["projects"][project.to_string_lossy().as_ref()].clone());
+    }
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        sources.push(read_json_file(&claude_dir.join("settings.json")).unwrap_or_default());
+    }
+    sources
+}
+
+/// Whether a server may be started to measure it: user and local servers
+/// always, project servers only once approved with `enabledMcpjsonServers` or
+/// `enableAllProjectMcpServers`
+fn mcp_server_approved(name: &str, scope: &str, sources: &[JsonValue]) -> bool {
+    scope != "project"
+        || sources.iter().any(|source| {
+            source["enableAllProjectMcpServers"].as_bool() == Some(true)
+                || source["enabledMcpjsonServers"]
+                    .as_array()
+                    .is_some_and(|names| names.iter().any(|n| n.as_str() == Some(name)))
+        })
+}
+
+/// Tokens of an MCP server's tool definitions, with a short description
+async fn measure_mcp_server(config: &JsonValue) -> (u64, String) {
+    let tools = match list_server_tools(config).await {
+        Ok(tools) => tools,
+        Err(e) => return (0, format!("Not measured: {}", e)),
+    };
+    let sizes: Vec<(String, u64)> = tools
+        .iter()
+        .map(|tool| {
+            let name = tool["name"].as_str().unwrap_or_default().to_string();
+            (name, tokens::estimate_tokens(&tool.to_string()))
+        })
+        .collect();
+    let total = sizes.iter().map(|(_, t)| t).sum();
+    let detail = match sizes.iter().max_by_key(|(_, t)| *t) {
+        Some((name, largest)) => format!(
+            "{} tools; largest: {} ({} tokens)",
+            sizes.len(),
+            name,
+            largest
+        ),
+        None => "No tools".to_string(),
+    };
+    (total, detail)
+}
+
+fn count_file(path: &Path, label: String) -> Result<FileTokens, String> {
+    let bytes = std::fs::metadata(path).map_err(|_| label.clone())?.len();
+    if bytes > MAX_FILE_BYTES {
+        return Err(label);
+    }
+    let text = std::fs::read_to_string(path).map_err(|_| label.clone())?;
+    Ok(FileTokens {
+        path: label,
+        bytes,
+        tokens: tokens::estimate_tokens(&text),
+    })
+}
+
+/// Estimate how many tokens `text` takes up for `model`
+#[tauri::command]
+pub async fn estimate_tokens(
+    db: State<'_, AgentDb>,
+    text: String,
+    model: String,
+) -> Result<TokenEstimate, String> {
+    let window = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        tokens::context_window(&conn, &model)
+    };
+    Ok(TokenEstimate::new(tokens::estimate_tokens(&text), window))
+}
+
+/// Estimate the context a run starts with: the CLAUDE.md memory files plus
+/// the project files matching `include_globs` (relative, e.g. `src/**/*.rs`)
+#[tauri::command]
+pub async fn estimate_project_context(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    include_globs: Vec<String>,
+    model: String,
+) -> Result<ProjectContextEstimate, String> {
+    let project = validate_project_root(&project_path)?;
+    let window = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        tokens::context_window(&conn, &model)
+    };
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    let mut truncated = false;
+    let mut seen = std::collections::HashSet::new();
+
+    for (path, label) in memory_files(&project) {
+        seen.insert(path.clone());
+        match count_file(&path, label) {
+            Ok(file) => files.push(file),
+            Err(label) => skipped.push(label),
+        }
+    }
+
+    let base = glob::Pattern::escape(&project.to_string_lossy());
+    for pattern in &include_globs {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            continue;
+        }
+        if Path::new(pattern).is_absolute() || pattern.split(['/', '\\']).any(|p| p == "..") {
+            return Err(format!(
+                "Pattern '{}' must stay inside the project",
+                pattern
+            ));
+        }
+        let matches = glob::glob(&format!("{}/{}", base, pattern))
+            .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        for path in matches.flatten() {
+            if !path.is_file() || !seen.insert(path.clone()) {
+                continue;
+            }
+            if files.len() >= MAX_FILES {
+                truncated = true;
+                break;
+            }
+            // Symlinks may point out of the project
+            let canonical = path.canonicalize().ok();
+            let Some(relative) = canonical
+                .as_deref()
+                .and_then(|p| p.strip_prefix(&project).ok())
+            else {
+                continue;
+            };
+            let label = relative.to_string_lossy().to_string();
+            match count_file(&path, label) {
+                Ok(file) => files.push(file),
+                Err(label) => skipped.push(label),
+            }
+        }
+    }
+
+    let total = TokenEstimate::new(files.iter().map(|f| f.tokens).sum(), window);
+    Ok(ProjectContextEstimate {
+        exceeds_window: total.tokens > total.context_window,
+        files,
+        skipped,
+        truncated,
+        total,
+    })
+}
+
+/// Total what a project's setup puts into every session's context: the
+/// CLAUDE.md memory files, the frontmatter of installed skills and the tool
+/// definitions of configured MCP servers
+///
+/// MCP servers are started briefly to list their tools; remote servers and
+/// servers that fail to start are listed with no tokens and the reason.
+/// Project servers from `.mcp.json` are only started once the user approved
+/// them; until then their size is unknown.
+#[tauri::command]
+pub async fn estimate_static_context(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    model: Option<String>,
+) -> Result<StaticContextEstimate, String> {
+    let project = validate_project_root(&project_path)?;
+    let window = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        tokens::context_window(&conn, model.as_deref().unwrap_or_default())
+    };
+    let contributor =
+        |kind: &str, name: String, source: String, tokens, detail| ContextContributor {
+            kind: kind.to_string(),
+            name,
+            source,
+            tokens,
+            detail,
+        };
+
+    let mut contributors = Vec::new();
+    for (path, label) in memory_files(&project) {
+        let (tokens, detail) = match count_file(&path, label.clone()) {
+            Ok(file) => (file.tokens, None),
+            Err(_) => (0, Some("Not measured: unreadable or too large".to_string())),
+        };
+        let source = path.to_string_lossy().to_string();
+        contributors.push(contributor("memory", label, source, tokens, detail));
+    }
+
+    let mut skill_dirs = vec![project.join(".claude").join("skills")];
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        skill_dirs.push(claude_dir.join("skills"));
+    }
+    let mut skill_names = std::collections::HashSet::new();
+    for (name, path, tokens) in skill_dirs.iter().flat_map(|dir| installed_skills(dir)) {
+        // A project skill hides a user skill of the same name
+        if skill_names.insert(name.clone()) {
+            let source = path.to_string_lossy().to_string();
+            contributors.push(contributor("skill", name, source, tokens, None));
+        }
+    }
+
+    let servers = configured_mcp_servers(&project);
+    let approvals = mcp_approval_sources(&project);
+    let measured = futures::future::join_all(servers.iter().map(|(name, scope, config)| {
+        let approved = mcp_server_approved(name, scope, &approvals);
+        async move {
+            if approved {
+                measure_mcp_server(config).await
+            } else {
+                (
+                    0,
+                    "Unknown: not started until the project server is approved".to_string(),
+                )
+            }
+        }
+    }))
+    .await;
+    for ((name, scope, _), (tokens, detail)) in servers.into_iter().zip(measured) {
+        contributors.push(contributor("mcp_server", name, scope, tokens, Some(detail)));
+    }
+
+    contributors.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+    let sum = |kind: &str| {
+        contributors
+            .iter()
+            .filter(|c| c.kind == kind)
+            .map(|c| c.tokens)
+            .sum::<u64>()
+    };
+    let (memory_tokens, skill_tokens, mcp_tokens) =
+        (sum("memory"), sum("skill"), sum("mcp_server"));
+    Ok(StaticContextEstimate {
+        total: TokenEstimate::new(memory_tokens + skill_tokens + mcp_tokens, window),
+        contributors,
+        memory_tokens,
+        skill_tokens,
+        mcp_tokens,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_project_mcp_servers_need_approval() {
+        assert!(mcp_server_approved("db", "user", &[]));
+        assert!(mcp_server_approved("db", "local", &[]));
+        assert!(!mcp_server_approved("db", "project", &[]));
+
+        let listed = [JsonValue::Null, json!({ "enabledMcpjsonServers": ["db"] })];
+        assert!(mcp_server_approved("db", "project", &listed));
+        assert!(!mcp_server_approved("evil", "project", &listed));
+
+        let all = [json!({ "enableAllProjectMcpServers": true })];
+        assert!(mcp_server_approved("evil", "project", &all));
+        let off = [json!({ "enableAllProjectMcpServers": false })];
+        assert!(!mcp_server_approved("evil", "project", &off));
+    }
+
+    #[test]
+    fn test_checked_in_settings_do_not_approve() {
+        let project = tempfile::tempdir().unwrap();
+        let claude = project.path().join(".claude");
+        std::fs::create_dir_all(&claude).unwrap();
+        std::fs::write(
+            claude.join("settings.json"),
+            r#"{"enableAllProjectMcpServers": true}"#,
+        )
+        .unwrap();
+        let sources = mcp_approval_sources(project.path());
+        assert!(!mcp_server_approved("evil", "project", &sources[..1]));
+
+        std::fs::write(
+            claude.join("settings.local.json"),
+            r#"{"enabledMcpjsonServers": ["db"]}"#,
+        )
+        .unwrap();
+        let sources = mcp_approval_sources(project.path());
+        assert!(mcp_server_approved("db", "project", &sources[..1]));
+    }
+}
@@ -0,0 +1,208 @@
+// Updates of opcode itself
+use chrono::{DateTime, Utc};
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_updater::UpdaterExt;
+
+use super::agents::AgentDb;
+use super::operations::ProgressReporter;
+use super::settings as store;
+use crate::network::{send_with_policy, until_cancelled, NetworkError, RetryPolicy};
+
+/// Repository whose releases are opcode's updates
+const RELEASES_REPO: &str = "getAsterisk/opcode";
+/// Public key update bundles are signed with, set for release builds
+const UPDATER_PUBKEY: Option<&str> = option_env!("OPCODE_UPDATER_PUBKEY");
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The running version and the latest release
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    /// Markdown notes of the latest release
+    pub release_notes: Option<String>,
+    pub release_url: String,
+    pub published_at: Option<DateTime<Utc>>,
+    /// This build can install the update itself; otherwise download it from `release_url`
+    pub can_install: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    body: Option<String>,
+    html_url: String,
+    published_at: Option<DateTime<Utc>>,
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Whether `latest` is a later version than `current`; pre-release suffixes are ignored
+fn is_newer(latest: &str, current: &str) -> bool {
+    let (mut latest, mut current) = (version_parts(latest), version_parts(current));
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+    latest > current
+}
+
+fn updater_pubkey() -> Option<&'static str> {
+    UPDATER_PUBKEY.filter(|key| !key.trim().is_empty())
+}
+
+async fn latest_release() -> Result<GitHubRelease, NetworkError> {
+    let client = crate::http_client::client()?;
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        RELEASES_REPO
+    );
+    let policy = RetryPolicy {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    let response = send_with_policy(&policy, || {
+        client
+            .get(&url)
+            .header(USER_AGENT, "Opcode-Agent")
+            .header("Accept", "application/vnd.github+json")
+            .timeout(CHECK_TIMEOUT)
+    })
+    .await?;
+    Ok(response.json().await?)
+}
+
+async fn check(app: &AppHandle) -> Result<UpdateInfo, NetworkError> {
+    let current_version = app.package_info().version.to_string();
+    let release = latest_release().await?;
+    let latest_version = release.tag_name.trim_start_matches('v').to_string();
+    Ok(UpdateInfo {
+        update_available: is_newer(&latest_version, &current_version),
+        current_version,
+        latest_version,
+        release_notes: release.body.filter(|notes| !notes.trim().is_empty()),
+        release_url: release.html_url,
+        published_at: release.published_at,
+        can_install: updater_pubkey().is_some(),
+    })
+}
+
+/// Compare the running version with the latest GitHub release
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<UpdateInfo, NetworkError> {
+    check(&app).await
+}
+
+/// Download, verify and install the latest version
+///
+/// The Tauri updater verifies the signed bundle against the public key the
+/// build was made with, so builds without a key can only check for updates.
+/// Reports progress as an `app_update` operation, which can be cancelled until
+/// the download completes. The new version is used from the next launch.
+/// Returns the installed version.
+#[tauri::command]
+pub async fn download_and_install_update(app: AppHandle) -> Result<String, NetworkError> {
+    let progress = ProgressReporter::start(&app, "app_update");
+    let result = install(&app, &progress).await;
+    progress.finish(&result);
+    result
+}
+
+async fn install(app: &AppHandle, progress: &ProgressReporter) -> Result<String, NetworkError> {
+    let Some(pubkey) = updater_pubkey() else {
+        return Err(format!(
+            "This build can't install updates; download the latest version from https://github.com/{}/releases",
+            RELEASES_REPO
+        )
+        .into());
+    };
+    progress.report("checking", None, None);
+    let updater = app
+        .updater_builder()
+        .pubkey(pubkey)
+        .build()
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?;
+    let update = until_cancelled(progress.token(), async {
+        updater
+            .check()
+            .await
+            .map_err(|e| NetworkError::from(format!("Failed to check for updates: {}", e)))
+    })
+    .await?
+    .ok_or_else(|| "opcode is already up to date".to_string())?;
+
+    let version = update.version.clone();
+    log::info!("Installing opcode {}", version);
+    let mut downloaded: u64 = 0;
+    until_cancelled(progress.token(), async {
+        update
+            .download_and_install(
+                |chunk, total| {
+                    downloaded += chunk as u64;
+                    let percent = total
+                        .filter(|total| *total > 0)
+                        .map(|total| (downloaded * 100 / total) as u8);
+                    progress.report("downloading", percent, None);
+                },
+                || progress.report("installing", Some(100), None),
+            )
+            .await
+            .map_err(|e| NetworkError::from(format!("Failed to install the update: {}", e)))
+    })
+    .await?;
+    Ok(version)
+}
+
+/// Check for a newer version in the background when automatic checks are on
+///
+/// Publishes `app-update-available` with the `UpdateInfo` when there is one.
+pub fn spawn_update_check(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let enabled = {
+            let db = app.state::<AgentDb>();
+            let Ok(conn) = db.0.lock() else {
+                return;
+            };
+            store::get_bool(&conn, "auto_check_updates")
+        };
+        if !enabled {
+            return;
+        }
+        match check(&app).await {
+            Ok(info) if info.update_available => {
+                log::info!("opcode {} is available", info.latest_version);
+                crate::event_bus::publish(&app, "app-update-available", &info);
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("Update check failed: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.2", "0.2.1"));
+        assert!(is_newer("0.10.0", "0.9.9"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("0.2.1", "0.2.1"));
+        assert!(!is_newer("0.2.1-beta", "0.2.1"));
+        assert!(!is_newer("0.2.0", "0.2.1"));
+    }
+}
@@ -4,7 +4,12 @@ use serde_json;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
-use tauri::command;
+use tauri::{command, State};
+
+use super::agents::AgentDb;
+use super::pricing::{load_pricing, Pricing, TokenCounts};
+use super::run_history::additional_dirs_by_session;
+use crate::workspace::usage_shares;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UsageEntry {
@@ -21,59 +26,48 @@ pub struct UsageEntry {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UsageStats {
-    total_cost: f64,
-    total_tokens: u64,
-    total_input_tokens: u64,
-    total_output_tokens: u64,
-    total_cache_creation_tokens: u64,
-    total_cache_read_tokens: u64,
-    total_sessions: u64,
-    by_model: Vec<ModelUsage>,
-    by_date: Vec<DailyUsage>,
-    by_project: Vec<ProjectUsage>,
+    pub(crate) total_cost: f64,
+    pub(crate) total_tokens: u64,
+    pub(crate) total_input_tokens: u64,
+    pub(crate) total_output_tokens: u64,
+    pub(crate) total_cache_creation_tokens: u64,
+    pub(crate) total_cache_read_tokens: u64,
+    pub(crate) total_sessions: u64,
+    pub(crate) by_model: Vec<ModelUsage>,
+    pub(crate) by_date: Vec<DailyUsage>,
+    pub(crate) by_project: Vec<ProjectUsage>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelUsage {
-    model: String,
-    total_cost: f64,
-    total_tokens: u64,
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_creation_tokens: u64,
-    cache_read_tokens: u64,
-    session_count: u64,
+    pub(crate) model: String,
+    pub(crate) total_cost: f64,
+    pub(crate) total_tokens: u64,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) cache_creation_tokens: u64,
+    pub(crate) cache_read_tokens: u64,
+    pub(crate) session_count: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DailyUsage {
-    date: String,
-    total_cost: f64,
-    total_tokens: u64,
-    models_used: Vec<String>,
+    pub(crate) date: String,
+    pub(crate) total_cost: f64,
+    pub(crate) total_tokens: u64,
+    pub(crate) models_used: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectUsage {
-    project_path: String,
-    project_name: String,
-    total_cost: f64,
-    total_tokens: u64,
-    session_count: u64,
-    last_used: String,
+    pub(crate) project_path: String,
+    pub(crate) project_name: String,
+    pub(crate) total_cost: f64,
+    pub(crate) total_tokens: u64,
+    pub(crate) session_count: u64,
+    pub(crate) last_used: String,
 }
 
-// Claude 4 pricing constants (per million tokens)
-const OPUS_4_INPUT_PRICE: f64 = 15.0;
-const OPUS_4_OUTPUT_PRICE: f64 = 75.0;
-const OPUS_4_CACHE_WRITE_PRICE: f64 = 18.75;
-const OPUS_4_CACHE_READ_PRICE: f64 = 1.50;
-
-const SONNET_4_INPUT_PRICE: f64 = 3.0;
-const SONNET_4_OUTPUT_PRICE: f64 = 15.0;
-const SONNET_4_CACHE_WRITE_PRICE: f64 = 3.75;
-const SONNET_4_CACHE_READ_PRICE: f64 = 0.30;
-
 #[derive(Debug, Deserialize)]
 struct JsonlEntry {
     timestamp: String,
@@ -101,46 +95,77 @@ struct UsageData {
     cache_read_input_tokens: Option<u64>,
 }
 
-fn calculate_cost(model: &str, usage: &UsageData) -> f64 {
-    let input_tokens = usage.input_tokens.unwrap_or(0) as f64;
-    let output_tokens = usage.output_tokens.unwrap_or(0) as f64;
-    let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
-    let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0) as f64;
-
-    // Calculate cost based on model
-    let (input_price, output_price, cache_write_price, cache_read_price) =
-        if model.contains("opus-4") || model.contains("claude-opus-4") {
-            (
-                OPUS_4_INPUT_PRICE,
-                OPUS_4_OUTPUT_PRICE,
-                OPUS_4_CACHE_WRITE_PRICE,
-                OPUS_4_CACHE_READ_PRICE,
-            )
-        } else if model.contains("sonnet-4") || model.contains("claude-sonnet-4") {
-            (
-                SONNET_4_INPUT_PRICE,
-                SONNET_4_OUTPUT_PRICE,
-                SONNET_4_CACHE_WRITE_PRICE,
-                SONNET_4_CACHE_READ_PRICE,
-            )
-        } else {
-            // Return 0 for unknown models to avoid incorrect cost estimations.
-            (0.0, 0.0, 0.0, 0.0)
-        };
+fn token_counts(usage: &UsageData) -> TokenCounts {
+    TokenCounts {
+        input: usage.input_tokens.unwrap_or(0),
+        output: usage.output_tokens.unwrap_or(0),
+        cache_write: usage.cache_creation_input_tokens.unwrap_or(0),
+        cache_read: usage.cache_read_input_tokens.unwrap_or(0),
+    }
+}
 
-    // Calculate cost (prices are per million tokens)
-    let cost = (input_tokens * input_price / 1_000_000.0)
-        + (output_tokens * output_price / 1_000_000.0)
-        + (cache_creation_tokens * cache_write_price / 1_000_000.0)
-        + (cache_read_tokens * cache_read_price / 1_000_000.0);
+/// Pricing in effect, including the user's overrides
+fn current_pricing(db: &AgentDb) -> Result<Pricing, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_pricing(&conn))
+}
+
+/// Extra project roots of multi-root sessions, by session ID
+fn multi_root_sessions(db: &AgentDb) -> HashMap<String, Vec<String>> {
+    let sessions =
+        db.0.lock()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| additional_dirs_by_session(&conn).map_err(|e| e.to_string()));
+    sessions.unwrap_or_else(|e| {
+        log::warn!("Failed to load multi-root sessions: {}", e);
+        HashMap::new()
+    })
+}
 
-    cost
+/// Add an entry to the usage of its project, or split it evenly across the
+/// roots of a multi-root session
+fn add_project_usage(
+    project_stats: &mut HashMap<String, ProjectUsage>,
+    entry: &UsageEntry,
+    workspaces: &HashMap<String, Vec<String>>,
+) {
+    let shares = match workspaces.get(&entry.session_id) {
+        Some(dirs) => usage_shares(&entry.project_path, dirs),
+        None => vec![(entry.project_path.clone(), 1.0)],
+    };
+    let tokens = entry.input_tokens
+        + entry.output_tokens
+        + entry.cache_creation_tokens
+        + entry.cache_read_tokens;
+    for (project_path, share) in shares {
+        let project_stat = project_stats
+            .entry(project_path.clone())
+            .or_insert_with(|| ProjectUsage {
+                project_name: project_path
+                    .split('/')
+                    .last()
+                    .unwrap_or(&project_path)
+                    .to_string(),
+                project_path,
+                total_cost: 0.0,
+                total_tokens: 0,
+                session_count: 0,
+                last_used: entry.timestamp.clone(),
+            });
+        project_stat.total_cost += entry.cost * share;
+        project_stat.total_tokens += (tokens as f64 * share).round() as u64;
+        project_stat.session_count += 1;
+        if entry.timestamp > project_stat.last_used {
+            project_stat.last_used = entry.timestamp.clone();
+        }
+    }
 }
 
 fn parse_jsonl_file(
     path: &PathBuf,
     encoded_project_name: &str,
     processed_hashes: &mut HashSet<String>,
+    pricing: &Pricing,
 ) -> Vec<UsageEntry> {
     let mut entries = Vec::new();
     let mut actual_project_path: Option<String> = None;
@@ -189,13 +214,12 @@ fn parse_jsonl_file(
                                 continue;
                             }
 
-                            let cost = entry.cost_usd.unwrap_or_else(|| {
-                                if let Some(model_str) = &message.model {
-                                    calculate_cost(model_str, usage)
-                                } else {
-                                    0.0
+                            let cost = match &message.model {
+                                Some(model_str) => {
+                                    pricing.cost(model_str, token_counts(usage), entry.cost_usd)
                                 }
-                            });
+                                None => entry.cost_usd.unwrap_or(0.0),
+                            };
 
                             // Use actual project path if found, otherwise use encoded name
                             let project_path = actual_project_path
@@ -249,7 +273,7 @@ fn get_earliest_timestamp(path: &PathBuf) -> Option<String> {
     None
 }
 
-fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
+fn get_all_usage_entries(claude_path: &PathBuf, pricing: &Pricing) -> Vec<UsageEntry> {
     let mut all_entries = Vec::new();
     let mut processed_hashes = HashSet::new();
     let projects_dir = claude_path.join("projects");
@@ -278,7 +302,7 @@ fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
     files_to_process.sort_by_cached_key(|(path, _)| get_earliest_timestamp(path));
 
     for (path, project_name) in files_to_process {
-        let entries = parse_jsonl_file(&path, &project_name, &mut processed_hashes);
+        let entries = parse_jsonl_file(&path, &project_name, &mut processed_hashes, pricing);
         all_entries.extend(entries);
     }
 
@@ -289,12 +313,13 @@ fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
 }
 
 #[command]
-pub fn get_usage_stats(days: Option<u32>) -> Result<UsageStats, String> {
+pub fn get_usage_stats(db: State<'_, AgentDb>, days: Option<u32>) -> Result<UsageStats, String> {
     let claude_path = dirs::home_dir()
         .ok_or("Failed to get home directory")?
         .join(".claude");
 
-    let all_entries = get_all_usage_entries(&claude_path);
+    let all_entries = get_all_usage_entries(&claude_path, &current_pricing(&db)?);
+    let workspaces = multi_root_sessions(&db);
 
     if all_entries.is_empty() {
         return Ok(UsageStats {
@@ -391,31 +416,7 @@ pub fn get_usage_stats(days: Option<u32>) -> Result<UsageStats, String> {
         }
 
         // Update project stats
-        let project_stat =
-            project_stats
-                .entry(entry.project_path.clone())
-                .or_insert(ProjectUsage {
-                    project_path: entry.project_path.clone(),
-                    project_name: entry
-                        .project_path
-                        .split('/')
-                        .last()
-                        .unwrap_or(&entry.project_path)
-                        .to_string(),
-                    total_cost: 0.0,
-                    total_tokens: 0,
-                    session_count: 0,
-                    last_used: entry.timestamp.clone(),
-                });
-        project_stat.total_cost += entry.cost;
-        project_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        project_stat.session_count += 1;
-        if entry.timestamp > project_stat.last_used {
-            project_stat.last_used = entry.timestamp.clone();
-        }
+        add_project_usage(&mut project_stats, entry, &workspaces);
     }
 
     let total_tokens = total_input_tokens
@@ -449,12 +450,17 @@ pub fn get_usage_stats(days: Option<u32>) -> Result<UsageStats, String> {
 }
 
 #[command]
-pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<UsageStats, String> {
+pub fn get_usage_by_date_range(
+    db: State<'_, AgentDb>,
+    start_date: String,
+    end_date: String,
+) -> Result<UsageStats, String> {
     let claude_path = dirs::home_dir()
         .ok_or("Failed to get home directory")?
         .join(".claude");
 
-    let all_entries = get_all_usage_entries(&claude_path);
+    let all_entries = get_all_usage_entries(&claude_path, &current_pricing(&db)?);
+    let workspaces = multi_root_sessions(&db);
 
     // Parse dates
     let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").or_else(|_| {
@@ -561,31 +567,7 @@ pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<U
         }
 
         // Update project stats
-        let project_stat =
-            project_stats
-                .entry(entry.project_path.clone())
-                .or_insert(ProjectUsage {
-                    project_path: entry.project_path.clone(),
-                    project_name: entry
-                        .project_path
-                        .split('/')
-                        .last()
-                        .unwrap_or(&entry.project_path)
-                        .to_string(),
-                    total_cost: 0.0,
-                    total_tokens: 0,
-                    session_count: 0,
-                    last_used: entry.timestamp.clone(),
-                });
-        project_stat.total_cost += entry.cost;
-        project_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        project_stat.session_count += 1;
-        if entry.timestamp > project_stat.last_used {
-            project_stat.last_used = entry.timestamp.clone();
-        }
+        add_project_usage(&mut project_stats, entry, &workspaces);
     }
 
     let total_tokens = total_input_tokens
@@ -620,6 +602,7 @@ pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<U
 
 #[command]
 pub fn get_usage_details(
+    db: State<'_, AgentDb>,
     project_path: Option<String>,
     date: Option<String>,
 ) -> Result<Vec<UsageEntry>, String> {
@@ -627,7 +610,7 @@ pub fn get_usage_details(
         .ok_or("Failed to get home directory")?
         .join(".claude");
 
-    let mut all_entries = get_all_usage_entries(&claude_path);
+    let mut all_entries = get_all_usage_entries(&claude_path, &current_pricing(&db)?);
 
     // Filter by project if specified
     if let Some(project) = project_path {
@@ -644,6 +627,7 @@ pub fn get_usage_details(
 
 #[command]
 pub fn get_session_stats(
+    db: State<'_, AgentDb>,
     since: Option<String>,
     until: Option<String>,
     order: Option<String>,
@@ -652,7 +636,7 @@ pub fn get_session_stats(
         .ok_or("Failed to get home directory")?
         .join(".claude");
 
-    let all_entries = get_all_usage_entries(&claude_path);
+    let all_entries = get_all_usage_entries(&claude_path, &current_pricing(&db)?);
 
     let since_date = since.and_then(|s| NaiveDate::parse_from_str(&s, "%Y%m%d").ok());
     let until_date = until.and_then(|s| NaiveDate::parse_from_str(&s, "%Y%m%d").ok());
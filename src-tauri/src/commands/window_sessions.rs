@@ -0,0 +1,42 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::window_sessions::WindowSessions;
+
+/// Send a session's output and events only to one window
+///
+/// Moves the session if it was attached to another window; returns that
+/// window's label.
+#[tauri::command]
+pub async fn attach_session_to_window(
+    app: AppHandle,
+    sessions: State<'_, WindowSessions>,
+    session_id: String,
+    window_label: String,
+) -> Result<Option<String>, String> {
+    if session_id.trim().is_empty() {
+        return Err("Session ID is empty".to_string());
+    }
+    if app.get_webview_window(&window_label).is_none() {
+        return Err(format!("No window is labelled '{}'", window_label));
+    }
+    Ok(sessions.attach(&session_id, &window_label))
+}
+
+/// Send a session's output and events to every window again; returns the
+/// window it was attached to
+#[tauri::command]
+pub async fn detach_session_from_window(
+    sessions: State<'_, WindowSessions>,
+    session_id: String,
+) -> Result<Option<String>, String> {
+    Ok(sessions.detach(&session_id))
+}
+
+/// The sessions attached to a window
+#[tauri::command]
+pub async fn get_window_sessions(
+    sessions: State<'_, WindowSessions>,
+    window_label: String,
+) -> Result<Vec<String>, String> {
+    Ok(sessions.sessions_of(&window_label))
+}
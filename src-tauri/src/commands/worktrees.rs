@@ -0,0 +1,338 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::agents::AgentDb;
+use super::git::{git, repo_prefix};
+use crate::path_validation::validate_project_root;
+
+/// A git worktree an agent can run in without touching the live checkout
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentWorktree {
+    pub id: i64,
+    /// The checkout the worktree was created from
+    pub project_path: String,
+    /// The project inside the worktree; pass it as the project path of a run
+    pub path: String,
+    pub branch: String,
+    /// Commit the branch started from
+    pub base_sha: String,
+    pub created_at: String,
+}
+
+/// What `merge_worktree_changes` merged
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeMergeResult {
+    /// Commits on the worktree branch, including one for uncommitted changes
+    pub commits: u64,
+    /// The merge commit; `None` when there was nothing to merge
+    pub sha: Option<String>,
+}
+
+/// Create the agent_worktrees table
+pub fn init_worktrees(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_worktrees (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_path TEXT NOT NULL,
+            repo_root TEXT NOT NULL,
+            worktree_path TEXT NOT NULL,
+            path TEXT NOT NULL,
+            branch TEXT NOT NULL,
+            base_sha TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// A worktree row with the paths needed to finish it
+struct WorktreeRecord {
+    worktree: AgentWorktree,
+    repo_root: PathBuf,
+    worktree_path: PathBuf,
+}
+
+fn row_to_record(row: &Row) -> SqliteResult<WorktreeRecord> {
+    Ok(WorktreeRecord {
+        worktree: AgentWorktree {
+            id: row.get(0)?,
+            project_path: row.get(1)?,
+            path: row.get(2)?,
+            branch: row.get(3)?,
+            base_sha: row.get(4)?,
+            created_at: row.get(5)?,
+        },
+        repo_root: PathBuf::from(row.get::<_, String>(6)?),
+        worktree_path: PathBuf::from(row.get::<_, String>(7)?),
+    })
+}
+
+const WORKTREE_QUERY: &str = "SELECT id, project_path, path, branch, base_sha, created_at, repo_root, worktree_path FROM agent_worktrees";
+
+fn get_record(db: &AgentDb, id: i64) -> Result<WorktreeRecord, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("{} WHERE id = ?1", WORKTREE_QUERY),
+        params![id],
+        row_to_record,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Worktree {} not found", id))
+}
+
+/// A worktree that hasn't been merged or discarded yet
+pub(crate) fn get_worktree(db: &AgentDb, id: i64) -> Result<AgentWorktree, String> {
+    get_record(db, id).map(|record| record.worktree)
+}
+
+fn delete_record(db: &AgentDb, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_worktrees WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Directory name for a worktree, e.g. `opcode-agent-fix-tests`
+pub fn worktree_dir_name(repo_root: &Path, branch: &str) -> String {
+    let repo = repo_root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    let name: String = format!("{}-{}", repo, branch)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+/// A directory for a new worktree under the app's data directory
+fn new_worktree_path(repo_root: &Path, branch: &str) -> Result<PathBuf, String> {
+    let base = dirs::data_dir()
+        .ok_or("Could not find the app data directory")?
+        .join(crate::utils::APP_IDENTIFIER)
+        .join("worktrees");
+    std::fs::create_dir_all(&base)
+        .map_err(|e| format!("Failed to create the worktrees directory: {}", e))?;
+    let name = worktree_dir_name(repo_root, branch);
+    let mut path = base.join(&name);
+    let mut n = 2;
+    while path.exists() {
+        path = base.join(format!("{}-{}", name, n));
+        n += 1;
+    }
+    Ok(path)
+}
+
+/// Remove a worktree's checkout and branch
+async fn remove_worktree(record: &WorktreeRecord) -> Result<(), String> {
+    let path = record.worktree_path.to_string_lossy();
+    if record.worktree_path.exists() {
+        git(&record.repo_root, &["worktree", "remove", "--force", &path]).await?;
+    } else {
+        // Deleted by hand; drop git's record of it
+        git(&record.repo_root, &["worktree", "prune"]).await?;
+    }
+    git(
+        &record.repo_root,
+        &["branch", "-D", "--", &record.worktree.branch],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Create a worktree on a new branch from the project's current commit
+///
+/// Agents run in the returned `path` instead of the project, so several can
+/// work on the same repository at once. Finish with `merge_worktree_changes`
+/// or `discard_worktree`.
+#[tauri::command]
+pub async fn create_agent_worktree(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    branch: String,
+) -> Result<AgentWorktree, String> {
+    let project = validate_project_root(&project_path)?;
+    let branch = branch.trim();
+    if branch.is_empty() || branch.starts_with('-') {
+        return Err(format!("Invalid branch name '{}'", branch));
+    }
+    git(&project, &["check-ref-format", "--branch", branch])
+        .await
+        .map_err(|_| format!("Invalid branch name '{}'", branch))?;
+
+    let repo_root = PathBuf::from(
+        git(&project, &["rev-parse", "--show-toplevel"])
+            .await
+            .map_err(|_| "The project is not in a git repository".to_string())?
+            .trim(),
+    );
+    let prefix = repo_prefix(&project).await?;
+    let base_sha = git(&project, &["rev-parse", "--verify", "-q", "HEAD"])
+        .await
+        .map_err(|_| "The repository has no commits to branch from".to_string())?
+        .trim()
+        .to_string();
+
+    let worktree_path = new_worktree_path(&repo_root, branch)?;
+    git(
+        &repo_root,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            branch,
+            &worktree_path.to_string_lossy(),
+            &base_sha,
+        ],
+    )
+    .await?;
+    let path = match prefix.trim_end_matches('/') {
+        "" => worktree_path.clone(),
+        prefix => worktree_path.join(prefix),
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agent_worktrees (project_path, repo_root, worktree_path, path, branch, base_sha)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            project.to_string_lossy(),
+            repo_root.to_string_lossy(),
+            worktree_path.to_string_lossy(),
+            path.to_string_lossy(),
+            branch,
+            base_sha,
+        ],
+    )
+    .map_err(|e| format!("Failed to record worktree: {}", e))?;
+    let id = conn.last_insert_rowid();
+    conn.query_row(
+        &format!("{} WHERE id = ?1", WORKTREE_QUERY),
+        params![id],
+        row_to_record,
+    )
+    .map(|record| record.worktree)
+    .map_err(|e| e.to_string())
+}
+
+/// List agent worktrees, optionally only those created from one project
+#[tauri::command]
+pub async fn list_agent_worktrees(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<Vec<AgentWorktree>, String> {
+    let project = project_path
+        .map(|p| validate_project_root(&p))
+        .transpose()?
+        .map(|p| p.to_string_lossy().to_string());
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE ?1 IS NULL OR project_path = ?1 ORDER BY created_at DESC, id DESC",
+            WORKTREE_QUERY
+        ))
+        .map_err(|e| e.to_string())?;
+    let worktrees = stmt
+        .query_map(params![project], row_to_record)
+        .map_err(|e| e.to_string())?
+        .map(|r| r.map(|record| record.worktree))
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(worktrees)
+}
+
+/// Merge a worktree's branch into the project's current branch and remove the worktree
+///
+/// Uncommitted changes in the worktree are committed first with `message`.
+/// If the merge conflicts it is aborted and the worktree is kept.
+#[tauri::command]
+pub async fn merge_worktree_changes(
+    db: State<'_, AgentDb>,
+    id: i64,
+    message: Option<String>,
+) -> Result<WorktreeMergeResult, String> {
+    let record = get_record(&db, id)?;
+    let branch = record.worktree.branch.as_str();
+    let message = message
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| format!("Agent changes from {}", branch));
+
+    if record.worktree_path.exists() {
+        let status = git(&record.worktree_path, &["status", "--porcelain"]).await?;
+        if !status.trim().is_empty() {
+            git(&record.worktree_path, &["add", "-A"]).await?;
+            git(&record.worktree_path, &["commit", "-q", "-m", &message]).await?;
+        }
+    }
+
+    let range = format!("{}..{}", record.worktree.base_sha, branch);
+    let commits: u64 = git(&record.repo_root, &["rev-list", "--count", &range])
+        .await?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to count commits: {}", e))?;
+
+    let project = Path::new(&record.worktree.project_path);
+    let sha = if commits > 0 {
+        let merge_message = format!("Merge {}", branch);
+        if let Err(e) = git(
+            project,
+            &["merge", "--no-ff", "-q", "-m", &merge_message, branch],
+        )
+        .await
+        {
+            let _ = git(project, &["merge", "--abort"]).await;
+            return Err(format!(
+                "Could not merge {}; the worktree was kept so the changes aren't lost: {}",
+                branch, e
+            ));
+        }
+        Some(
+            git(project, &["rev-parse", "HEAD"])
+                .await?
+                .trim()
+                .to_string(),
+        )
+    } else {
+        None
+    };
+
+    remove_worktree(&record).await?;
+    delete_record(&db, id)?;
+    Ok(WorktreeMergeResult { commits, sha })
+}
+
+/// Delete a worktree and its branch, throwing away everything done in it
+#[tauri::command]
+pub async fn discard_worktree(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let record = get_record(&db, id)?;
+    remove_worktree(&record).await?;
+    delete_record(&db, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worktree_dir_name() {
+        let root = Path::new("/home/me/opcode");
+        assert_eq!(
+            worktree_dir_name(root, "agent/fix-tests"),
+            "opcode-agent-fix-tests"
+        );
+        assert_eq!(worktree_dir_name(root, "wip.1"), "opcode-wip-1");
+    }
+}
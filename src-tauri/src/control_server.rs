@@ -0,0 +1,294 @@
+// Local HTTP API for scripts and CI jobs to drive agent runs
+use axum::extract::{Path, Request, State as AxumState};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::commands::agent_race::final_result;
+use crate::commands::agents::{
+    get_agent_run, get_agent_run_with_metrics, kill_agent_session, list_agents, start_agent_run,
+    AgentDb, AgentRunWithMetrics,
+};
+use crate::commands::settings::{get_bool, get_integer, get_text, is_strong_token, set_value};
+
+pub const DEFAULT_PORT: u16 = 7823;
+
+struct RunningServer {
+    address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    /// Finishes once the listener is closed
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// The control server, while it runs
+#[derive(Default)]
+pub struct ControlServerState {
+    server: Mutex<Option<RunningServer>>,
+}
+
+/// Whether the control server is on and where it listens
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlServerStatus {
+    pub enabled: bool,
+    /// `http://127.0.0.1:<port>` while it runs
+    pub address: Option<String>,
+    /// Send as `Authorization: Bearer <token>`
+    pub token: Option<String>,
+}
+
+/// A run to start
+#[derive(Debug, Deserialize)]
+struct StartRun {
+    agent_id: i64,
+    project_path: String,
+    task: String,
+    model: Option<String>,
+}
+
+/// A run's outcome, for scripts waiting on it
+#[derive(Debug, Serialize)]
+struct RunResult {
+    #[serde(flatten)]
+    run: AgentRunWithMetrics,
+    /// Text of the final `result` message, once the run has finished
+    result: Option<String>,
+}
+
+/// A new random bearer token
+pub fn generate_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Compare without stopping at the first difference
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+/// Reject requests without `Authorization: Bearer <control_server_token>`
+///
+/// The token is read per request, so a rotated token applies at once.
+async fn require_token(
+    AxumState(app): AxumState<AppHandle>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let expected = app
+        .state::<AgentDb>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|conn| get_text(&conn, "control_server_token"))
+        .filter(|token| !token.is_empty());
+    let authorized = match (bearer_token(request.headers()), expected) {
+        (Some(given), Some(expected)) => tokens_match(given, &expected),
+        _ => false,
+    };
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token");
+    }
+    next.run(request).await
+}
+
+async fn agents(AxumState(app): AxumState<AppHandle>) -> Response {
+    match list_agents(app.state()).await {
+        Ok(agents) => Json(agents).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn start_run(AxumState(app): AxumState<AppHandle>, Json(body): Json<StartRun>) -> Response {
+    log::info!(
+        "Control server starting agent {} in {}",
+        body.agent_id,
+        body.project_path
+    );
+    match start_agent_run(
+        app.clone(),
+        None,
+        body.agent_id,
+        body.project_path,
+        body.task,
+        body.model,
+        None,
+        None,
+        app.state(),
+        app.state(),
+    )
+    .await
+    {
+        Ok(run_id) => (StatusCode::CREATED, Json(json!({ "run_id": run_id }))).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn run_status(AxumState(app): AxumState<AppHandle>, Path(id): Path<i64>) -> Response {
+    match get_agent_run(app.state(), id).await {
+        Ok(run) => {
+            let mut run = get_agent_run_with_metrics(run).await;
+            run.output = None;
+            Json(run).into_response()
+        }
+        Err(e) => error(StatusCode::NOT_FOUND, e),
+    }
+}
+
+async fn run_result(AxumState(app): AxumState<AppHandle>, Path(id): Path<i64>) -> Response {
+    match get_agent_run(app.state(), id).await {
+        Ok(run) => {
+            let run = get_agent_run_with_metrics(run).await;
+            let result = run.output.as_deref().and_then(final_result);
+            Json(RunResult { run, result }).into_response()
+        }
+        Err(e) => error(StatusCode::NOT_FOUND, e),
+    }
+}
+
+async fn cancel_run(AxumState(app): AxumState<AppHandle>, Path(id): Path<i64>) -> Response {
+    if let Err(e) = get_agent_run(app.state(), id).await {
+        return error(StatusCode::NOT_FOUND, e);
+    }
+    match kill_agent_session(app.clone(), app.state(), app.state(), id).await {
+        Ok(killed) => Json(json!({ "cancelled": killed })).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// Handlers call the same functions as the Tauri commands, so a run started
+/// here is locked, recorded and shown in the UI like any other
+fn router(app: AppHandle) -> Router {
+    Router::new()
+        .route("/v1/agents", get(agents))
+        .route("/v1/runs", post(start_run))
+        .route("/v1/runs/{id}", get(run_status))
+        .route("/v1/runs/{id}/result", get(run_result))
+        .route("/v1/runs/{id}/cancel", post(cancel_run))
+        .layer(middleware::from_fn_with_state(app.clone(), require_token))
+        .with_state(app)
+}
+
+/// Where the server listens and its token, as configured
+pub fn status(app: &AppHandle) -> Result<ControlServerStatus, String> {
+    let (enabled, token) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            get_bool(&conn, "control_server_enabled"),
+            get_text(&conn, "control_server_token"),
+        )
+    };
+    let address = app
+        .state::<ControlServerState>()
+        .server
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|server| format!("http://{}", server.address));
+    Ok(ControlServerStatus {
+        enabled,
+        address,
+        token,
+    })
+}
+
+/// Start, restart or stop the server to match the settings
+///
+/// It listens on 127.0.0.1 only, on `control_server_port`. A token is
+/// generated if the server is enabled without a strong one.
+pub async fn configure(app: &AppHandle) -> Result<ControlServerStatus, String> {
+    let state = app.state::<ControlServerState>();
+    let running = state.server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = running {
+        let _ = server.shutdown.send(());
+        // Wait for the listener to close so the port can be bound again
+        let _ = server.task.await;
+        log::info!("Control server on {} stopped", server.address);
+    }
+
+    let port = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        if !get_bool(&conn, "control_server_enabled") {
+            drop(conn);
+            return status(app);
+        }
+        if !get_text(&conn, "control_server_token").is_some_and(|t| is_strong_token(&t)) {
+            set_value(&conn, "control_server_token", &json!(generate_token()))?;
+        }
+        get_integer(&conn, "control_server_port")
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or(DEFAULT_PORT)
+    };
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .map_err(|e| format!("Failed to listen on 127.0.0.1:{}: {}", port, e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    let (shutdown, stopped) = oneshot::channel();
+    let server = axum::serve(listener, router(app.clone())).with_graceful_shutdown(async {
+        let _ = stopped.await;
+    });
+    let task = tauri::async_runtime::spawn(async move {
+        if let Err(e) = server.await {
+            log::error!("Control server failed: {}", e);
+        }
+    });
+    *state.server.lock().map_err(|e| e.to_string())? = Some(RunningServer {
+        address,
+        shutdown,
+        task,
+    });
+    log::info!("Control server listening on {}", address);
+    status(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearer_token_check() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer abc123 ".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc123"));
+        headers.insert(header::AUTHORIZATION, "Basic abc123".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert!(tokens_match(&token, &token.clone()));
+        assert!(!tokens_match(&token, &generate_token()));
+        assert!(!tokens_match("abc", "abcd"));
+    }
+}
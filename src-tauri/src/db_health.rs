@@ -0,0 +1,265 @@
+// Database health: WAL mode, daily backups and recovery from corruption
+use chrono::{DateTime, Local, Utc};
+use rusqlite::{Connection, ErrorCode, OpenFlags, Result as SqliteResult};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+pub const DB_FILE: &str = "agents.db";
+const BACKUP_DIR: &str = "backups";
+/// Backups kept; older ones are removed
+const MAX_BACKUPS: usize = 5;
+/// A backup is taken at launch when the latest one is older than this
+const BACKUP_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What was done about a corrupt database at launch
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    /// What the integrity check or SQLite reported
+    pub problems: Vec<String>,
+    /// Where the damaged database was moved
+    pub corrupt_copy: String,
+    /// The backup restored; `None` when the app started with an empty database
+    pub restored_backup: Option<String>,
+    /// When that backup was taken; changes after it are lost
+    pub backup_taken_at: Option<DateTime<Utc>>,
+    pub recovered_at: DateTime<Utc>,
+}
+
+static RECOVERY: OnceLock<RecoveryReport> = OnceLock::new();
+
+/// The recovery done at this launch, if the database was corrupt
+pub fn recovery_report() -> Option<RecoveryReport> {
+    RECOVERY.get().cloned()
+}
+
+/// Open another connection to the app database that waits out other writers
+pub fn open_connection(path: impl AsRef<Path>) -> SqliteResult<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+/// WAL journal, busy timeout and the sync level WAL needs
+///
+/// Background writers (the event log, maintenance) and commands then wait for
+/// each other instead of failing with "database is locked".
+pub fn configure(conn: &Connection) -> SqliteResult<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(())
+}
+
+/// Problems found by SQLite's quick check; empty when the database is sound
+pub fn integrity_problems(conn: &Connection) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(rows.into_iter().filter(|row| row != "ok").collect())
+}
+
+fn is_corruption(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// Check a freshly opened database; `Ok(Err(problems))` when it is corrupt
+fn check(opened: SqliteResult<Connection>) -> SqliteResult<Result<Connection, Vec<String>>> {
+    let checked = opened.and_then(|conn| {
+        let problems = integrity_problems(&conn)?;
+        Ok((conn, problems))
+    });
+    match checked {
+        Ok((conn, problems)) if problems.is_empty() => Ok(Ok(conn)),
+        Ok((_, problems)) => Ok(Err(problems)),
+        Err(e) if is_corruption(&e) => Ok(Err(vec![e.to_string()])),
+        Err(e) => Err(e),
+    }
+}
+
+/// Open and check the app database; `Ok(Err(problems))` when it is corrupt
+fn open_checked(path: &Path) -> SqliteResult<Result<Connection, Vec<String>>> {
+    check(Connection::open(path).and_then(|conn| {
+        configure(&conn)?;
+        Ok(conn)
+    }))
+}
+
+/// Whether a backup passes the check; it is opened read-only so checking it
+/// doesn't change its journal mode or leave WAL files next to it
+fn backup_is_sound(path: &Path) -> bool {
+    matches!(
+        check(Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+        )),
+        Ok(Ok(_))
+    )
+}
+
+fn backup_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join(BACKUP_DIR)
+}
+
+/// Backups, newest first
+fn backups(app_dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut backups: Vec<(PathBuf, SystemTime)> = fs::read_dir(backup_dir(app_dir))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "db"))
+                .filter_map(|p| Some((p.clone(), p.metadata().ok()?.modified().ok()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.1.cmp(&a.1));
+    backups
+}
+
+/// Write a consistent copy of the database to the backup directory and drop
+/// the oldest backups beyond the limit
+pub fn backup_database(conn: &Connection, app_dir: &Path) -> Result<PathBuf, String> {
+    let dir = backup_dir(app_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!(
+        "agents-{}.db",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let _ = fs::remove_file(&path);
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+        .map_err(|e| format!("Failed to back up the database: {}", e))?;
+    for (old, _) in backups(app_dir).into_iter().skip(MAX_BACKUPS) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Back up when the latest backup is older than a day
+pub fn backup_if_stale(conn: &Connection, app_dir: &Path) {
+    let fresh = backups(app_dir)
+        .first()
+        .and_then(|(_, modified)| modified.elapsed().ok())
+        .is_some_and(|age| age < BACKUP_AGE);
+    if !fresh {
+        if let Err(e) = backup_database(conn, app_dir) {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+/// Move a damaged database and its WAL files out of the way
+fn move_aside(db_path: &Path) -> Result<PathBuf, String> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let corrupt = db_path.with_file_name(format!("{}.corrupt-{}", DB_FILE, stamp));
+    fs::rename(db_path, &corrupt)
+        .map_err(|e| format!("Failed to move the damaged database aside: {}", e))?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if side.exists() {
+            let _ = fs::rename(&side, format!("{}{}", corrupt.display(), suffix));
+        }
+    }
+    Ok(corrupt)
+}
+
+/// Replace a corrupt database with the newest backup that passes the check
+fn recover(app_dir: &Path, problems: Vec<String>) -> SqliteResult<Connection> {
+    let db_path = app_dir.join(DB_FILE);
+    let corrupt_copy = move_aside(&db_path).map_err(|e| {
+        log::error!("{}", e);
+        rusqlite::Error::InvalidPath(db_path.clone())
+    })?;
+
+    let mut restored = None;
+    for (backup, taken_at) in backups(app_dir) {
+        if !backup_is_sound(&backup) {
+            log::warn!("Skipping damaged backup {}", backup.display());
+            continue;
+        }
+        if fs::copy(&backup, &db_path).is_ok() {
+            restored = Some((backup, taken_at));
+            break;
+        }
+    }
+
+    let report = RecoveryReport {
+        problems,
+        corrupt_copy: corrupt_copy.to_string_lossy().to_string(),
+        restored_backup: restored
+            .as_ref()
+            .map(|(path, _)| path.to_string_lossy().to_string()),
+        backup_taken_at: restored.as_ref().map(|(_, at)| (*at).into()),
+        recovered_at: Utc::now(),
+    };
+    match &report.backup_taken_at {
+        Some(at) => log::error!(
+            "The database was corrupt and was restored from the backup of {}; changes since then are lost",
+            at
+        ),
+        None => log::error!(
+            "The database was corrupt and no usable backup exists; starting with an empty database. The damaged copy is at {}",
+            report.corrupt_copy
+        ),
+    }
+    let _ = RECOVERY.set(report);
+
+    let conn = Connection::open(&db_path)?;
+    configure(&conn)?;
+    Ok(conn)
+}
+
+/// Open the app database in `app_dir`, recovering it if it is corrupt
+///
+/// A database that fails the quick check or isn't a database at all is moved
+/// aside and replaced with the newest sound backup, or with an empty database
+/// when there is none, so the app still starts. What happened is kept for
+/// `get_database_recovery`.
+pub fn open_database(app_dir: &Path) -> SqliteResult<Connection> {
+    let db_path = app_dir.join(DB_FILE);
+    match open_checked(&db_path)? {
+        Ok(conn) => Ok(conn),
+        Err(problems) => recover(app_dir, problems),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = open_database(dir.path()).unwrap();
+        conn.execute_batch("CREATE TABLE notes (body TEXT); INSERT INTO notes VALUES ('kept');")
+            .unwrap();
+        let backup = backup_database(&conn, dir.path()).unwrap();
+        let backup_bytes = fs::read(&backup).unwrap();
+        drop(conn);
+
+        let db_path = dir.path().join(DB_FILE);
+        for suffix in ["-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", db_path.display(), suffix));
+        }
+        fs::write(&db_path, b"this is not a database, just garbage bytes").unwrap();
+
+        let conn = open_database(dir.path()).unwrap();
+        let body: String = conn
+            .query_row("SELECT body FROM notes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(body, "kept");
+        let report = recovery_report().unwrap();
+        assert!(report.restored_backup.is_some());
+        assert!(Path::new(&report.corrupt_copy).exists());
+
+        // Checking the backup left it as it was
+        assert_eq!(fs::read(&backup).unwrap(), backup_bytes);
+    }
+}
@@ -0,0 +1,670 @@
+// Type-aware diffs for images, lockfiles and notebooks
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Larger files are left to the line diff
+const MAX_INPUT_BYTES: usize = 50 * 1024 * 1024;
+/// Images with more pixels get dimensions but no pixel comparison
+const MAX_COMPARED_PIXELS: u64 = 64 * 1024 * 1024;
+/// Notebooks with more cells aren't aligned cell by cell
+const MAX_NOTEBOOK_CELLS: usize = 1_000;
+
+/// Summary of a change to a file a provider understands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpecialDiff {
+    Image {
+        before: Option<ImageInfo>,
+        after: Option<ImageInfo>,
+        /// Share of pixels that differ, 0 to 1; `None` unless both images
+        /// have the same dimensions
+        changed_pixel_ratio: Option<f64>,
+    },
+    Lockfile {
+        added: Vec<DependencyChange>,
+        removed: Vec<DependencyChange>,
+        /// Dependencies locked to one version before and another after
+        changed: Vec<DependencyChange>,
+    },
+    Notebook {
+        /// Added, removed and modified cells, in notebook order
+        cells: Vec<CellChange>,
+        unchanged: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// File extension of the detected format, e.g. `png`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyChange {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellChange {
+    /// "added", "removed" or "modified"
+    pub change: String,
+    /// "code", "markdown" or "raw"
+    pub cell_type: String,
+    pub before_index: Option<usize>,
+    pub after_index: Option<usize>,
+    pub source_changed: bool,
+    pub outputs_changed: bool,
+    /// First line of the cell's source
+    pub preview: String,
+}
+
+/// Diffs one kind of file
+pub trait DiffProvider: Send + Sync {
+    fn handles(&self, path: &Path) -> bool;
+    /// `before` is `None` for an added file, `after` for a deleted one
+    fn diff(
+        &self,
+        path: &Path,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Result<SpecialDiff, String>;
+}
+
+/// The providers a diff is looked up in
+///
+/// Providers are picked by file name and the first match wins; a file its
+/// provider can't parse keeps its line diff.
+#[derive(Default)]
+pub struct DiffProviders {
+    providers: Vec<Box<dyn DiffProvider>>,
+}
+
+impl DiffProviders {
+    /// Providers for images, lockfiles and notebooks
+    pub fn with_default_providers() -> Self {
+        Self::default()
+            .with(ImageDiff)
+            .with(LockfileDiff)
+            .with(NotebookDiff)
+    }
+
+    pub fn with(mut self, provider: impl DiffProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    pub fn handles(&self, path: &Path) -> bool {
+        self.providers.iter().any(|p| p.handles(path))
+    }
+
+    /// The provider's diff of a file, or `None` when no provider takes it or
+    /// its contents can't be parsed
+    pub fn diff(
+        &self,
+        path: &Path,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Option<SpecialDiff> {
+        let provider = self.providers.iter().find(|p| p.handles(path))?;
+        let too_large = |content: Option<&[u8]>| content.is_some_and(|c| c.len() > MAX_INPUT_BYTES);
+        if (before.is_none() && after.is_none()) || too_large(before) || too_large(after) {
+            return None;
+        }
+        provider
+            .diff(path, before, after)
+            .map_err(|e| log::debug!("No special diff for {}: {}", path.display(), e))
+            .ok()
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+}
+
+/// Dimensions and, for same-sized images, the share of changed pixels
+pub struct ImageDiff;
+
+impl DiffProvider for ImageDiff {
+    fn handles(&self, path: &Path) -> bool {
+        image::ImageFormat::from_path(path).is_ok()
+    }
+
+    fn diff(
+        &self,
+        _path: &Path,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Result<SpecialDiff, String> {
+        let decode = |content: Option<&[u8]>| -> Result<Option<image::DynamicImage>, String> {
+            content
+                .map(|bytes| image::load_from_memory(bytes).map_err(|e| e.to_string()))
+                .transpose()
+        };
+        let info = |content: Option<&[u8]>, decoded: &Option<image::DynamicImage>| {
+            decoded.as_ref().map(|img| ImageInfo {
+                width: img.width(),
+                height: img.height(),
+                format: content
+                    .and_then(|bytes| image::guess_format(bytes).ok())
+                    .and_then(|f| f.extensions_str().first())
+                    .map(|e| e.to_string()),
+            })
+        };
+        let (old, new) = (decode(before)?, decode(after)?);
+
+        let changed_pixel_ratio = match (&old, &new) {
+            (Some(old), Some(new))
+                if old.width() == new.width()
+                    && old.height() == new.height()
+                    && (old.width() as u64) * (old.height() as u64) <= MAX_COMPARED_PIXELS =>
+            {
+                let (old, new) = (old.to_rgba8(), new.to_rgba8());
+                let total = old.width() as usize * old.height() as usize;
+                let changed = old
+                    .pixels()
+                    .zip(new.pixels())
+                    .filter(|(a, b)| a != b)
+                    .count();
+                (total > 0).then(|| changed as f64 / total as f64)
+            }
+            _ => None,
+        };
+        Ok(SpecialDiff::Image {
+            before: info(before, &old),
+            after: info(after, &new),
+            changed_pixel_ratio,
+        })
+    }
+}
+
+/// Locked versions by package name; a package can be locked at several versions
+type LockedVersions = BTreeMap<String, BTreeSet<String>>;
+
+/// Dependencies added, removed and bumped between two lockfiles
+pub struct LockfileDiff;
+
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "poetry.lock",
+    "uv.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Gemfile.lock",
+];
+
+impl DiffProvider for LockfileDiff {
+    fn handles(&self, path: &Path) -> bool {
+        LOCKFILES.contains(&file_name(path))
+    }
+
+    fn diff(
+        &self,
+        path: &Path,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Result<SpecialDiff, String> {
+        let parse = |content: Option<&[u8]>| -> Result<LockedVersions, String> {
+            let Some(content) = content else {
+                return Ok(LockedVersions::new());
+            };
+            let text = std::str::from_utf8(content).map_err(|e| e.to_string())?;
+            match file_name(path) {
+                "package-lock.json" | "npm-shrinkwrap.json" => parse_package_lock(text),
+                "yarn.lock" => Ok(parse_yarn_lock(text)),
+                "pnpm-lock.yaml" => parse_pnpm_lock(text),
+                "Gemfile.lock" => Ok(parse_gemfile_lock(text)),
+                _ => Ok(parse_toml_packages(text)),
+            }
+        };
+        let (old, new) = (parse(before)?, parse(after)?);
+        Ok(dependency_delta(&old, &new))
+    }
+}
+
+fn lock(versions: &mut LockedVersions, name: &str, version: &str) {
+    if !name.is_empty() && !version.is_empty() {
+        versions
+            .entry(name.to_string())
+            .or_default()
+            .insert(version.to_string());
+    }
+}
+
+/// `[[package]]` tables with `name` and `version`, as in Cargo, Poetry and uv lockfiles
+fn parse_toml_packages(text: &str) -> LockedVersions {
+    let mut versions = LockedVersions::new();
+    let mut current: Option<(String, String)> = None;
+    let mut in_package = false;
+    let mut flush = |current: &mut Option<(String, String)>| {
+        if let Some((name, version)) = current.take() {
+            lock(&mut versions, &name, &version);
+        }
+    };
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[[package]]";
+            if in_package {
+                flush(&mut current);
+                current = Some(Default::default());
+            }
+            continue;
+        }
+        let (Some((name, version)), true) = (current.as_mut(), in_package) else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "name" => *name = value,
+            "version" => *version = value,
+            _ => {}
+        }
+    }
+    flush(&mut current);
+    versions
+}
+
+fn parse_package_lock(text: &str) -> Result<LockedVersions, String> {
+    fn walk(dependencies: &JsonValue, versions: &mut LockedVersions) {
+        for (name, entry) in dependencies.as_object().into_iter().flatten() {
+            lock(
+                versions,
+                name,
+                entry["version"].as_str().unwrap_or_default(),
+            );
+            walk(&entry["dependencies"], versions);
+        }
+    }
+    let lock_file: JsonValue = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mut versions = LockedVersions::new();
+    match lock_file["packages"].as_object() {
+        // lockfileVersion 2 and 3
+        Some(packages) => {
+            for (key, entry) in packages {
+                if let Some((_, name)) = key.rsplit_once("node_modules/") {
+                    lock(
+                        &mut versions,
+                        name,
+                        entry["version"].as_str().unwrap_or_default(),
+                    );
+                }
+            }
+        }
+        None => walk(&lock_file["dependencies"], &mut versions),
+    }
+    Ok(versions)
+}
+
+/// Package name of a spec such as `@babel/core@^7.0.0` or `lodash@npm:4.17.21`
+fn spec_name(spec: &str) -> &str {
+    let spec = spec.trim().trim_matches('"');
+    match spec.char_indices().skip(1).find(|(_, c)| *c == '@') {
+        Some((at, _)) => &spec[..at],
+        None => spec,
+    }
+}
+
+/// Both the classic and the Berry format
+fn parse_yarn_lock(text: &str) -> LockedVersions {
+    let mut versions = LockedVersions::new();
+    let mut name: Option<String> = None;
+    for line in text.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            let header = line.trim_end().trim_end_matches(':');
+            name = header
+                .split(',')
+                .next()
+                .filter(|_| header != "__metadata")
+                .map(|spec| spec_name(spec).to_string());
+            continue;
+        }
+        let trimmed = line.trim();
+        let version = trimmed
+            .strip_prefix("version ")
+            .or_else(|| trimmed.strip_prefix("version: "));
+        if let (Some(name), Some(version)) = (&name, version) {
+            lock(&mut versions, name, version.trim().trim_matches('"'));
+        }
+    }
+    versions
+}
+
+fn parse_pnpm_lock(text: &str) -> Result<LockedVersions, String> {
+    let lock_file: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    let mut versions = LockedVersions::new();
+    let Some(packages) = lock_file.get("packages").and_then(|p| p.as_mapping()) else {
+        return Ok(versions);
+    };
+    for key in packages.keys().filter_map(|k| k.as_str()) {
+        // `/name@1.0.0(peer@2.0.0)` (v6), `name@1.0.0` (v9) or `/name/1.0.0` (v5)
+        let key = key.trim_start_matches('/');
+        let key = key.split('(').next().unwrap_or(key);
+        let split = match key.rfind('@').filter(|&at| at > 0) {
+            Some(at) => Some((&key[..at], &key[at + 1..])),
+            None => key.rsplit_once('/'),
+        };
+        if let Some((name, version)) = split {
+            lock(&mut versions, name, version);
+        }
+    }
+    Ok(versions)
+}
+
+/// Gems under `specs:`, written `    name (version)`
+fn parse_gemfile_lock(text: &str) -> LockedVersions {
+    let mut versions = LockedVersions::new();
+    for line in text.lines() {
+        let Some(spec) = line.strip_prefix("    ").filter(|s| !s.starts_with(' ')) else {
+            continue;
+        };
+        if let Some((name, version)) = spec
+            .trim_end()
+            .strip_suffix(')')
+            .and_then(|s| s.split_once(" ("))
+        {
+            lock(&mut versions, name, version);
+        }
+    }
+    versions
+}
+
+fn dependency_delta(old: &LockedVersions, new: &LockedVersions) -> SpecialDiff {
+    let empty = BTreeSet::new();
+    let (mut added, mut removed, mut changed) = (Vec::new(), Vec::new(), Vec::new());
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        let before = old.get(name).unwrap_or(&empty);
+        let after = new.get(name).unwrap_or(&empty);
+        if before == after {
+            continue;
+        }
+        if before.len() == 1 && after.len() == 1 {
+            changed.push(DependencyChange {
+                name: name.clone(),
+                from: before.first().cloned(),
+                to: after.first().cloned(),
+            });
+            continue;
+        }
+        for version in before.difference(after) {
+            removed.push(DependencyChange {
+                name: name.clone(),
+                from: Some(version.clone()),
+                to: None,
+            });
+        }
+        for version in after.difference(before) {
+            added.push(DependencyChange {
+                name: name.clone(),
+                from: None,
+                to: Some(version.clone()),
+            });
+        }
+    }
+    SpecialDiff::Lockfile {
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// Cells added, removed or modified between two Jupyter notebooks
+pub struct NotebookDiff;
+
+struct Cell {
+    cell_type: String,
+    source: String,
+    outputs: JsonValue,
+}
+
+impl Cell {
+    fn preview(&self) -> String {
+        let line = self
+            .source
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or_default()
+            .trim();
+        line.chars().take(80).collect()
+    }
+}
+
+fn parse_cells(content: Option<&[u8]>) -> Result<Vec<Cell>, String> {
+    let Some(content) = content else {
+        return Ok(Vec::new());
+    };
+    let notebook: JsonValue = serde_json::from_slice(content).map_err(|e| e.to_string())?;
+    let cells = notebook["cells"]
+        .as_array()
+        .ok_or("Not a notebook: no cells")?;
+    if cells.len() > MAX_NOTEBOOK_CELLS {
+        return Err(format!("More than {} cells", MAX_NOTEBOOK_CELLS));
+    }
+    Ok(cells
+        .iter()
+        .map(|cell| Cell {
+            cell_type: cell["cell_type"].as_str().unwrap_or("code").to_string(),
+            source: match &cell["source"] {
+                JsonValue::Array(lines) => lines.iter().filter_map(|l| l.as_str()).collect(),
+                source => source.as_str().unwrap_or_default().to_string(),
+            },
+            outputs: cell["outputs"].clone(),
+        })
+        .collect())
+}
+
+/// Pairs of cells with the same type and source, by longest common subsequence
+fn matching_cells(old: &[Cell], new: &[Cell]) -> Vec<(usize, usize)> {
+    let same = |a: &Cell, b: &Cell| a.cell_type == b.cell_type && a.source == b.source;
+    let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if same(&old[i], &new[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < old.len() && j < new.len() {
+        if same(&old[i], &new[j]) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+impl DiffProvider for NotebookDiff {
+    fn handles(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "ipynb")
+    }
+
+    fn diff(
+        &self,
+        _path: &Path,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Result<SpecialDiff, String> {
+        let (old, new) = (parse_cells(before)?, parse_cells(after)?);
+        let mut cells = Vec::new();
+        let mut unchanged = 0;
+        let (mut i, mut j) = (0, 0);
+        let mut pairs = matching_cells(&old, &new);
+        // Sentinel so the cells after the last match are handled like a gap
+        pairs.push((old.len(), new.len()));
+        for (next_i, next_j) in pairs {
+            // Cells between two matches were edited in place as far as they pair up
+            while i < next_i && j < next_j {
+                cells.push(CellChange {
+                    change: "modified".to_string(),
+                    cell_type: new[j].cell_type.clone(),
+                    before_index: Some(i),
+                    after_index: Some(j),
+                    source_changed: true,
+                    outputs_changed: old[i].outputs != new[j].outputs,
+                    preview: new[j].preview(),
+                });
+                i += 1;
+                j += 1;
+            }
+            cells.extend(
+                old[i..next_i]
+                    .iter()
+                    .zip(i..)
+                    .map(|(cell, index)| CellChange {
+                        change: "removed".to_string(),
+                        cell_type: cell.cell_type.clone(),
+                        before_index: Some(index),
+                        after_index: None,
+                        source_changed: true,
+                        outputs_changed: false,
+                        preview: cell.preview(),
+                    }),
+            );
+            cells.extend(
+                new[j..next_j]
+                    .iter()
+                    .zip(j..)
+                    .map(|(cell, index)| CellChange {
+                        change: "added".to_string(),
+                        cell_type: cell.cell_type.clone(),
+                        before_index: None,
+                        after_index: Some(index),
+                        source_changed: true,
+                        outputs_changed: false,
+                        preview: cell.preview(),
+                    }),
+            );
+            if next_i < old.len() {
+                if old[next_i].outputs == new[next_j].outputs {
+                    unchanged += 1;
+                } else {
+                    cells.push(CellChange {
+                        change: "modified".to_string(),
+                        cell_type: new[next_j].cell_type.clone(),
+                        before_index: Some(next_i),
+                        after_index: Some(next_j),
+                        source_changed: false,
+                        outputs_changed: true,
+                        preview: new[next_j].preview(),
+                    });
+                }
+            }
+            (i, j) = (next_i + 1, next_j + 1);
+        }
+        Ok(SpecialDiff::Notebook { cells, unchanged })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_default_providers() {
+        let providers = DiffProviders::with_default_providers();
+
+        let png = |pixels: &[u8]| {
+            let image = image::RgbaImage::from_raw(2, 2, pixels.to_vec()).unwrap();
+            let mut bytes = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+                .unwrap();
+            bytes
+        };
+        let (old, new) = (png(&[0; 16]), png(&[[0; 12], [255; 4]].concat()));
+        match providers.diff(Path::new("logo.png"), Some(&old), Some(&new)) {
+            Some(SpecialDiff::Image {
+                after,
+                changed_pixel_ratio,
+                ..
+            }) => {
+                assert_eq!(after.unwrap().width, 2);
+                assert_eq!(changed_pixel_ratio, Some(0.25));
+            }
+            other => panic!("unexpected diff {:?}", other),
+        }
+
+        let old = "[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\n\n[[package]]\nname = \"log\"\nversion = \"0.4.0\"\n";
+        let new = "[[package]]\nname = \"serde\"\nversion = \"1.0.2\"\n\n[[package]]\nname = \"zip\"\nversion = \"4.0.0\"\n";
+        assert_eq!(
+            providers.diff(
+                Path::new("Cargo.lock"),
+                Some(old.as_bytes()),
+                Some(new.as_bytes())
+            ),
+            Some(SpecialDiff::Lockfile {
+                added: vec![DependencyChange {
+                    name: "zip".to_string(),
+                    from: None,
+                    to: Some("4.0.0".to_string()),
+                }],
+                removed: vec![DependencyChange {
+                    name: "log".to_string(),
+                    from: Some("0.4.0".to_string()),
+                    to: None,
+                }],
+                changed: vec![DependencyChange {
+                    name: "serde".to_string(),
+                    from: Some("1.0.1".to_string()),
+                    to: Some("1.0.2".to_string()),
+                }],
+            })
+        );
+        let yarn = "\"@babel/core@^7.0.0\", \"@babel/core@^7.1.0\":\n  version \"7.2.0\"\n";
+        assert!(parse_yarn_lock(yarn)["@babel/core"].contains("7.2.0"));
+
+        let notebook = |cells: &[(&str, &str)]| {
+            let cells: Vec<JsonValue> = cells
+                .iter()
+                .map(|(kind, source)| serde_json::json!({"cell_type": kind, "source": [source], "outputs": []}))
+                .collect();
+            serde_json::to_vec(&serde_json::json!({ "cells": cells })).unwrap()
+        };
+        let old = notebook(&[
+            ("markdown", "# Title"),
+            ("code", "x = 1"),
+            ("code", "print(x)"),
+        ]);
+        let new = notebook(&[
+            ("markdown", "# Title"),
+            ("code", "x = 2"),
+            ("code", "print(x)"),
+            ("code", "y"),
+        ]);
+        match providers.diff(Path::new("analysis.ipynb"), Some(&old), Some(&new)) {
+            Some(SpecialDiff::Notebook { cells, unchanged }) => {
+                assert_eq!(unchanged, 2);
+                let changes: Vec<(&str, Option<usize>)> = cells
+                    .iter()
+                    .map(|c| (c.change.as_str(), c.after_index))
+                    .collect();
+                assert_eq!(changes, vec![("modified", Some(1)), ("added", Some(3))]);
+            }
+            other => panic!("unexpected diff {:?}", other),
+        }
+    }
+}
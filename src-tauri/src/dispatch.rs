@@ -0,0 +1,468 @@
+// Command dispatch through middleware before the Tauri handler
+use rusqlite::Connection;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::{Runtime, Url};
+
+use crate::commands::settings::get_bool;
+
+/// Argument keys whose values never appear in logs
+const SENSITIVE_KEYS: &[&str] = &[
+    "key",
+    "token",
+    "secret",
+    "password",
+    "authorization",
+    "credential",
+];
+
+/// Arguments that are masked for one command although their names look harmless
+const MASKED_ARGS: &[(&str, &str)] = &[("set_project_env", "value"), ("set_setting", "value")];
+
+/// Arguments that are logged for one command although their names look sensitive
+const PLAIN_ARGS: &[(&str, &str)] = &[("set_setting", "key")];
+
+/// Windows whose webviews may call commands; mirrors `capabilities/default.json`
+const COMMAND_WINDOWS: &[&str] = &["main", "project-*"];
+
+/// Longest argument dump in the audit log when full arguments are logged
+const MAX_LOGGED_ARGS: usize = 2048;
+
+/// Whether the audit log gets the (masked, truncated) arguments rather than
+/// just their names and sizes
+static FULL_ARGS: AtomicBool = AtomicBool::new(false);
+
+/// Commands the frontend polls; logged at trace level to keep the audit log readable
+const QUIET_COMMANDS: &[&str] = &[
+    "get_live_output",
+    "get_live_session_output",
+    "get_session_output",
+    "get_claude_session_output",
+    "list_running_sessions",
+    "list_running_claude_sessions",
+    "get_session_status",
+    "get_maintenance_status",
+    "get_events_since",
+    "list_operations",
+];
+
+/// A command call, as seen by middleware
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+    pub command: String,
+    /// Label of the webview that made the call
+    pub webview: String,
+    /// Whether that webview shows the app's own frontend rather than a remote page
+    pub local: bool,
+    /// Arguments with sensitive values masked; `Null` for binary payloads
+    pub args: JsonValue,
+}
+
+impl CommandContext {
+    fn from_invoke<R: Runtime>(invoke: &Invoke<R>) -> Self {
+        let command = invoke.message.command();
+        let args = match invoke.message.payload() {
+            InvokeBody::Json(args) => mask_args(command, args),
+            InvokeBody::Raw(_) => JsonValue::Null,
+        };
+        let webview = invoke.message.webview_ref();
+        Self {
+            command: command.to_string(),
+            webview: webview.label().to_string(),
+            local: webview.url().is_ok_and(|url| is_app_url(&url)),
+            args,
+        }
+    }
+}
+
+/// Runs before a command; returning an error rejects the call with that message
+pub trait Middleware: Send + Sync {
+    fn before(&self, ctx: &CommandContext) -> Result<(), String>;
+}
+
+/// Routes IPC calls through middleware before the command handler
+///
+/// Cross-cutting concerns such as audit logging and masking secrets live here
+/// instead of in each command. Cancellation stays with `ProgressReporter`,
+/// since it has to follow an operation past the point where the command returns.
+#[derive(Default)]
+pub struct Dispatcher {
+    middleware: Vec<Box<dyn Middleware>>,
+}
+
+impl Dispatcher {
+    /// Dispatcher with the middleware every build uses
+    pub fn with_default_middleware() -> Self {
+        Self::default()
+            .with(AuditLog)
+            .with(WindowPermissions)
+            .with(RateLimiter::new(default_rate_limits()))
+    }
+
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Wrap a `generate_handler!` handler so every call goes through `dispatch`
+    pub fn wrap<R, H>(self, handler: H) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+    where
+        R: Runtime,
+        H: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+    {
+        move |invoke| self.dispatch(invoke, &handler)
+    }
+
+    /// Run the middleware chain, then hand the call to `handler`
+    pub fn dispatch<R, H>(&self, invoke: Invoke<R>, handler: &H) -> bool
+    where
+        R: Runtime,
+        H: Fn(Invoke<R>) -> bool,
+    {
+        let ctx = CommandContext::from_invoke(&invoke);
+        for middleware in &self.middleware {
+            if let Err(message) = middleware.before(&ctx) {
+                log::warn!("Rejected command {}: {}", ctx.command, message);
+                invoke.resolver.reject(message);
+                return true;
+            }
+        }
+        handler(invoke)
+    }
+}
+
+/// Replace the values of sensitive-looking keys with "***", recursively
+pub fn mask_sensitive(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lower = key.to_lowercase();
+                    let masked = if SENSITIVE_KEYS.iter().any(|s| lower.contains(s)) {
+                        match value {
+                            JsonValue::Null => JsonValue::Null,
+                            _ => JsonValue::String("***".to_string()),
+                        }
+                    } else {
+                        mask_sensitive(value)
+                    };
+                    (key.clone(), masked)
+                })
+                .collect(),
+        ),
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(mask_sensitive).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Mask a command's arguments, applying `MASKED_ARGS` and `PLAIN_ARGS` by exact name
+fn mask_args(command: &str, args: &JsonValue) -> JsonValue {
+    let mut masked = mask_sensitive(args);
+    if let (JsonValue::Object(map), JsonValue::Object(original)) = (&mut masked, args) {
+        for (name, value) in map.iter_mut() {
+            if MASKED_ARGS.contains(&(command, name.as_str())) {
+                *value = JsonValue::String("***".to_string());
+            } else if PLAIN_ARGS.contains(&(command, name.as_str())) {
+                *value = original[name].clone();
+            }
+        }
+    }
+    masked
+}
+
+/// Apply the `audit_log_full_args` setting
+pub fn configure(conn: &Connection) {
+    FULL_ARGS.store(get_bool(conn, "audit_log_full_args"), Ordering::Relaxed);
+}
+
+/// Argument names with the size of their values, e.g. `model=8B prompt=1532B`
+fn describe_args(args: &JsonValue) -> String {
+    match args {
+        JsonValue::Object(map) => map
+            .iter()
+            .map(|(key, value)| format!("{}={}B", key, value.to_string().len()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        JsonValue::Null => String::new(),
+        other => format!("{}B", other.to_string().len()),
+    }
+}
+
+/// Arguments as JSON, cut to `max` bytes
+fn truncate_args(args: &JsonValue, max: usize) -> String {
+    let text = args.to_string();
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &text[..end], text.len())
+}
+
+/// Logs every command under the `audit` target
+///
+/// Prompts, memory files and environment maps pass through commands, so only
+/// argument names and sizes are logged unless `audit_log_full_args` is on;
+/// then the masked arguments are logged, truncated.
+pub struct AuditLog;
+
+impl Middleware for AuditLog {
+    fn before(&self, ctx: &CommandContext) -> Result<(), String> {
+        let args = if FULL_ARGS.load(Ordering::Relaxed) {
+            truncate_args(&ctx.args, MAX_LOGGED_ARGS)
+        } else {
+            describe_args(&ctx.args)
+        };
+        if QUIET_COMMANDS.contains(&ctx.command.as_str()) {
+            log::trace!(target: "audit", "{} {}", ctx.command, args);
+        } else {
+            log::info!(target: "audit", "{} {}", ctx.command, args);
+        }
+        Ok(())
+    }
+}
+
+/// Whether `url` is the app's own frontend: the bundled assets or the dev server
+fn is_app_url(url: &Url) -> bool {
+    match url.scheme() {
+        "tauri" => true,
+        "http" | "https" => matches!(
+            url.host_str(),
+            Some("tauri.localhost" | "localhost" | "127.0.0.1")
+        ),
+        _ => false,
+    }
+}
+
+/// Rejects calls from webviews other than the app's windows, and from app
+/// windows that navigated away to a remote page
+pub struct WindowPermissions;
+
+impl Middleware for WindowPermissions {
+    fn before(&self, ctx: &CommandContext) -> Result<(), String> {
+        let allowed = COMMAND_WINDOWS
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => ctx.webview.starts_with(prefix),
+                None => ctx.webview == *pattern,
+            });
+        if !allowed {
+            return Err(format!(
+                "{} can't be called from the {} webview",
+                ctx.command, ctx.webview
+            ));
+        }
+        if !ctx.local {
+            return Err(format!(
+                "{} can't be called from a remote page",
+                ctx.command
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How often a command may be called
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_calls: usize,
+    pub window: Duration,
+}
+
+/// Limits for commands that hit external APIs or do heavy work
+pub fn default_rate_limits() -> HashMap<&'static str, RateLimit> {
+    let per_minute = |max_calls| RateLimit {
+        max_calls,
+        window: Duration::from_secs(60),
+    };
+    HashMap::from([
+        ("fetch_github_agents", per_minute(10)),
+        ("fetch_github_agent_content", per_minute(30)),
+        ("import_agent_from_github", per_minute(10)),
+        ("fetch_available_skills", per_minute(10)),
+        ("fetch_mcp_marketplace", per_minute(10)),
+        ("get_marketplace_popularity", per_minute(10)),
+        ("install_skill", per_minute(10)),
+        ("start_skill_install", per_minute(10)),
+        ("install_skill_bundle", per_minute(5)),
+        ("apply_project_profile", per_minute(5)),
+        ("list_anthropic_models", per_minute(10)),
+        ("fetch_anthropic_usage", per_minute(5)),
+        ("benchmark_models", per_minute(2)),
+        ("refresh_pricing_table", per_minute(5)),
+        ("check_for_updates", per_minute(10)),
+        ("download_and_install_update", per_minute(2)),
+        ("run_doctor", per_minute(5)),
+        ("test_telemetry_export", per_minute(5)),
+        ("rebuild_file_history_index", per_minute(2)),
+        ("run_maintenance_now", per_minute(2)),
+        ("run_prewarm_now", per_minute(2)),
+    ])
+}
+
+/// Rejects calls beyond a command's limit within a sliding window
+pub struct RateLimiter {
+    limits: HashMap<&'static str, RateLimit>,
+    calls: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: HashMap<&'static str, RateLimit>) -> Self {
+        Self {
+            limits,
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check(&self, command: &str, now: Instant) -> Result<(), String> {
+        let limit = match self.limits.get(command) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let mut calls = self.calls.lock().map_err(|e| e.to_string())?;
+        let recent = calls.entry(command.to_string()).or_default();
+        while recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= limit.window)
+        {
+            recent.pop_front();
+        }
+
+        if recent.len() >= limit.max_calls {
+            let retry_in = recent
+                .front()
+                .map(|t| limit.window.saturating_sub(now.duration_since(*t)))
+                .unwrap_or(limit.window);
+            return Err(format!(
+                "Too many {} calls; try again in {} seconds",
+                command,
+                retry_in.as_secs().max(1)
+            ));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+impl Middleware for RateLimiter {
+    fn before(&self, ctx: &CommandContext) -> Result<(), String> {
+        self.check(&ctx.command, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mask_sensitive() {
+        let masked = mask_sensitive(&json!({
+            "apiKey": "sk-123",
+            "settings": { "otlp_headers": [{ "authorization": "Bearer x" }], "endpoint": "e" },
+            "token": null,
+        }));
+        assert_eq!(masked["apiKey"], "***");
+        assert_eq!(
+            masked["settings"]["otlp_headers"][0]["authorization"],
+            "***"
+        );
+        assert_eq!(masked["settings"]["endpoint"], "e");
+        assert!(masked["token"].is_null());
+    }
+
+    #[test]
+    fn test_mask_args_by_command() {
+        let args = json!({ "key": "control_server_token", "value": "s3cret" });
+        let masked = mask_args("set_setting", &args);
+        assert_eq!(masked["key"], "control_server_token");
+        assert_eq!(masked["value"], "***");
+
+        let masked = mask_args("get_setting", &json!({ "key": "theme" }));
+        assert_eq!(masked["key"], "***");
+        let masked = mask_args("set_setting_value", &args);
+        assert_eq!(masked["value"], "s3cret");
+    }
+
+    #[test]
+    fn test_audit_log_args() {
+        let args = json!({ "prompt": "fix the tests", "model": "sonnet" });
+        assert_eq!(describe_args(&args), "model=8B prompt=15B");
+        assert_eq!(describe_args(&JsonValue::Null), "");
+
+        assert_eq!(truncate_args(&args, 100), args.to_string());
+        let long = json!({ "prompt": "é".repeat(100) });
+        let truncated = truncate_args(&long, 20);
+        assert!(truncated.starts_with("{\"prompt\":\"é"));
+        assert!(truncated.ends_with("... (213 bytes)"));
+    }
+
+    #[test]
+    fn test_denied_calls_never_reach_the_handler() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Arc;
+        use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = handled.clone();
+        let app = tauri::test::mock_builder()
+            .invoke_handler(Dispatcher::default().with(WindowPermissions).wrap(
+                move |invoke: Invoke<tauri::test::MockRuntime>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    invoke.resolver.resolve("pong");
+                    true
+                },
+            ))
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap();
+        let call = |label: &str, url: WebviewUrl| {
+            let webview = WebviewWindowBuilder::new(&app, label, url).build().unwrap();
+            tauri::test::get_ipc_response(
+                &webview,
+                tauri::webview::InvokeRequest {
+                    cmd: "ping".into(),
+                    callback: tauri::ipc::CallbackFn(0),
+                    error: tauri::ipc::CallbackFn(1),
+                    url: "http://tauri.localhost".parse().unwrap(),
+                    body: InvokeBody::default(),
+                    headers: Default::default(),
+                    invoke_key: tauri::test::INVOKE_KEY.to_string(),
+                },
+            )
+        };
+
+        assert!(call("main", WebviewUrl::default()).is_ok());
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+        assert!(call("preview", WebviewUrl::default()).is_err());
+        let remote = WebviewUrl::External("https://example.com".parse().unwrap());
+        assert!(call("project-api", remote).is_err());
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(HashMap::from([(
+            "install_skill",
+            RateLimit {
+                max_calls: 2,
+                window: Duration::from_secs(60),
+            },
+        )]));
+        let start = Instant::now();
+
+        assert!(limiter.check("install_skill", start).is_ok());
+        assert!(limiter.check("install_skill", start).is_ok());
+        assert!(limiter.check("install_skill", start).is_err());
+        assert!(limiter.check("list_projects", start).is_ok());
+        assert!(limiter
+            .check("install_skill", start + Duration::from_secs(61))
+            .is_ok());
+    }
+}
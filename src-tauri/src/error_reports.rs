@@ -0,0 +1,385 @@
+// Panics and failed commands kept locally, and sent anonymized only when opted in
+use chrono::Utc;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::commands::agents::AgentDb;
+use crate::commands::settings::{get_bool, get_text};
+use crate::network::{send_with_retry, NetworkError};
+use crate::redaction::{self, RedactionHits};
+
+/// Reports kept; the oldest are removed beyond this
+const MAX_REPORTS: i64 = 1000;
+/// Reports sent in one request
+const SUBMIT_BATCH: usize = 100;
+/// Longest backtrace stored
+const MAX_BACKTRACE: usize = 8 * 1024;
+
+/// A recorded panic or command error
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub id: i64,
+    pub occurred_at: String,
+    /// "panic" or "command"
+    pub kind: String,
+    /// The failed command, or where the panic happened
+    pub source: String,
+    pub message: String,
+    /// Thread and backtrace of a panic
+    pub details: Option<JsonValue>,
+    pub app_version: String,
+    /// When the report was sent, if error reporting is on
+    pub submitted_at: Option<String>,
+}
+
+static DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Create the error_reports table
+pub fn init_error_reports(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS error_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            occurred_at TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            source TEXT NOT NULL,
+            message TEXT NOT NULL,
+            details TEXT,
+            app_version TEXT NOT NULL,
+            submitted_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_error_reports_submitted ON error_reports(submitted_at);",
+    )
+}
+
+/// Store a report and drop the oldest beyond the limit
+pub fn record(
+    conn: &Connection,
+    kind: &str,
+    source: &str,
+    message: &str,
+    details: Option<&JsonValue>,
+) -> SqliteResult<i64> {
+    conn.execute(
+        "INSERT INTO error_reports (occurred_at, kind, source, message, details, app_version)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            Utc::now().to_rfc3339(),
+            kind,
+            source,
+            message,
+            details.map(|d| d.to_string()),
+            env!("CARGO_PKG_VERSION"),
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM error_reports WHERE id <= ?1",
+        params![id - MAX_REPORTS],
+    )?;
+    Ok(id)
+}
+
+fn row_to_report(row: &rusqlite::Row) -> SqliteResult<ErrorReport> {
+    Ok(ErrorReport {
+        id: row.get(0)?,
+        occurred_at: row.get(1)?,
+        kind: row.get(2)?,
+        source: row.get(3)?,
+        message: row.get(4)?,
+        details: row
+            .get::<_, Option<String>>(5)?
+            .and_then(|d| serde_json::from_str(&d).ok()),
+        app_version: row.get(6)?,
+        submitted_at: row.get(7)?,
+    })
+}
+
+/// The newest reports, optionally of one kind
+pub fn recent(
+    conn: &Connection,
+    kind: Option<&str>,
+    limit: usize,
+) -> SqliteResult<Vec<ErrorReport>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, occurred_at, kind, source, message, details, app_version, submitted_at
+         FROM error_reports WHERE ?1 IS NULL OR kind = ?1
+         ORDER BY id DESC LIMIT ?2",
+    )?;
+    let reports = stmt
+        .query_map(params![kind, limit as i64], row_to_report)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(reports)
+}
+
+/// Remove every report; returns how many there were
+pub fn clear(conn: &Connection) -> SqliteResult<usize> {
+    conn.execute("DELETE FROM error_reports", [])
+}
+
+/// Record panics in the database at `app_dir`, then panic as before
+///
+/// The hook writes through its own connection, since the panicking thread may
+/// hold the shared one.
+pub fn install_panic_hook(app_dir: &Path) {
+    if DB_PATH
+        .set(app_dir.join(crate::db_health::DB_FILE))
+        .is_err()
+    {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        record_panic(info);
+        previous(info);
+    }));
+}
+
+fn record_panic(info: &PanicHookInfo) {
+    let Some(path) = DB_PATH.get() else {
+        return;
+    };
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Panic with a non-string payload".to_string());
+    let source = info
+        .location()
+        .map(|l| format!("{}:{}", l.file(), l.line()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let mut backtrace = Backtrace::force_capture().to_string();
+    if backtrace.len() > MAX_BACKTRACE {
+        let mut end = MAX_BACKTRACE;
+        while !backtrace.is_char_boundary(end) {
+            end -= 1;
+        }
+        backtrace.truncate(end);
+    }
+    let details = json!({
+        "thread": std::thread::current().name().unwrap_or("unnamed"),
+        "backtrace": backtrace,
+    });
+    let recorded = crate::db_health::open_connection(path)
+        .and_then(|conn| record(&conn, "panic", &source, &message, Some(&details)));
+    if let Err(e) = recorded {
+        eprintln!("Failed to record panic: {}", e);
+    }
+}
+
+/// Path prefixes that say nothing about the user and are kept in reports
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib64",
+    "/etc",
+    "/dev",
+    "/proc",
+    "/sys",
+    "/rustc",
+    "/nix/store",
+    "/opt/homebrew",
+    "/System",
+    "/Library",
+    "/Applications",
+];
+
+/// `text` with secrets redacted, the home directory and user name replaced
+/// and other absolute paths cut down to their file name
+///
+/// Paths like `/work/acme/app/src/lib.rs` become `<path>/lib.rs`, and
+/// directories like `/srv/acme` just `<path>`, so project names don't leave
+/// the machine; system paths such as `/usr/bin/git` are kept.
+pub fn anonymize(text: &str, home: Option<&str>, user: Option<&str>) -> String {
+    static PATH: OnceLock<regex::Regex> = OnceLock::new();
+    let mut text = redaction::redactor()
+        .redact_text(text, &mut RedactionHits::new())
+        .into_owned();
+    if let Some(home) = home.filter(|h| h.len() > 1) {
+        let home = regex::Regex::new(&format!(
+            r"{}(?P<end>/|[^\w.-]|$)",
+            regex::escape(home.trim_end_matches('/'))
+        ));
+        if let Ok(home) = home {
+            text = home.replace_all(&text, "~$end").into_owned();
+        }
+    }
+    let path = PATH.get_or_init(|| {
+        regex::Regex::new(r"(?P<before>^|[^\w~./-])(?P<path>(?:/[\w.@+-]+)+)")
+            .expect("static regex")
+    });
+    text = path
+        .replace_all(&text, |caps: &regex::Captures| {
+            let path = &caps["path"];
+            let kept = SYSTEM_PATHS.iter().any(|p| {
+                path.strip_prefix(p)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            });
+            let anonymized = match path.rsplit_once('/') {
+                _ if kept => path.to_string(),
+                Some((dir, file)) if !dir.is_empty() && file.contains('.') => {
+                    format!("<path>/{}", file)
+                }
+                _ => "<path>".to_string(),
+            };
+            format!("{}{}", &caps["before"], anonymized)
+        })
+        .into_owned();
+    if let Some(user) = user.filter(|u| u.len() > 2) {
+        if let Ok(user) = regex::Regex::new(&format!(r"\b{}\b", regex::escape(user))) {
+            text = user.replace_all(&text, "<user>").into_owned();
+        }
+    }
+    text
+}
+
+fn anonymize_json(value: &JsonValue, home: Option<&str>, user: Option<&str>) -> JsonValue {
+    match value {
+        JsonValue::String(s) => JsonValue::String(anonymize(s, home, user)),
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .iter()
+                .map(|v| anonymize_json(v, home, user))
+                .collect(),
+        ),
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), anonymize_json(v, home, user)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// What is sent for a report: no ID, and only the day it happened
+fn anonymized(report: &ErrorReport, home: Option<&str>, user: Option<&str>) -> JsonValue {
+    json!({
+        "kind": report.kind,
+        "source": anonymize(&report.source, home, user),
+        "message": anonymize(&report.message, home, user),
+        "details": report.details.as_ref().map(|d| anonymize_json(d, home, user)),
+        "occurred_on": report.occurred_at.get(..10),
+        "app_version": report.app_version,
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+    })
+}
+
+/// Send reports that haven't been sent yet, if error reporting is on;
+/// returns how many were sent
+///
+/// Nothing is sent unless `error_reporting_enabled` is on and
+/// `error_reporting_endpoint` is set.
+pub async fn submit_pending(db: &AgentDb) -> Result<usize, NetworkError> {
+    let (endpoint, reports) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        if !get_bool(&conn, "error_reporting_enabled") {
+            return Err("Error reporting is off".to_string().into());
+        }
+        let endpoint = get_text(&conn, "error_reporting_endpoint")
+            .filter(|e| !e.trim().is_empty())
+            .ok_or_else(|| "No error reporting endpoint configured".to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, occurred_at, kind, source, message, details, app_version, submitted_at
+                 FROM error_reports WHERE submitted_at IS NULL ORDER BY id LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let reports = stmt
+            .query_map(params![SUBMIT_BATCH as i64], row_to_report)
+            .and_then(|rows| rows.collect::<SqliteResult<Vec<_>>>())
+            .map_err(|e| e.to_string())?;
+        (endpoint, reports)
+    };
+    if reports.is_empty() {
+        return Ok(0);
+    }
+
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok();
+    let body = json!({
+        "reports": reports
+            .iter()
+            .map(|r| anonymized(r, home.as_deref(), user.as_deref()))
+            .collect::<Vec<_>>(),
+    });
+    let client = crate::http_client::client()?;
+    send_with_retry(|| client.post(&endpoint).json(&body))
+        .await
+        .map_err(|e| e.context("Failed to send error reports"))?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    for report in &reports {
+        conn.execute(
+            "UPDATE error_reports SET submitted_at = ?1 WHERE id = ?2",
+            params![now, report.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    log::info!("Sent {} error reports", reports.len());
+    Ok(reports.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_anonymize() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_error_reports(&conn).unwrap();
+        record(&conn, "command", "list_projects", "boom", None).unwrap();
+        let details = json!({ "backtrace": "at /home/alice/src/main.rs" });
+        record(&conn, "panic", "src/main.rs:3", "oops", Some(&details)).unwrap();
+
+        let reports = recent(&conn, None, 10).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].kind, "panic");
+        assert_eq!(recent(&conn, Some("command"), 10).unwrap().len(), 1);
+
+        let sent = anonymized(&reports[0], Some("/home/alice"), Some("alice"));
+        assert_eq!(sent["details"]["backtrace"], "at ~/src/main.rs");
+        assert_eq!(sent["occurred_on"].as_str().unwrap().len(), 10);
+        assert!(sent.get("id").is_none());
+        assert_eq!(
+            anonymize(
+                "alice failed with sk-ant-api03-abcdefghijkl",
+                None,
+                Some("alice")
+            ),
+            "<user> failed with [REDACTED]"
+        );
+        assert_eq!(
+            anonymize("redundant run by dan", None, Some("dan")),
+            "redundant run by <user>"
+        );
+        assert_eq!(
+            anonymize(
+                "failed in /work/acme/app/src/lib.rs:3 for /srv/acme and /home/alicex",
+                Some("/home/alice"),
+                None
+            ),
+            "failed in <path>/lib.rs:3 for <path> and <path>"
+        );
+        assert_eq!(
+            anonymize(
+                "spawn /usr/bin/git in /home/alice, see https://docs.rs/x/y.html",
+                Some("/home/alice"),
+                None
+            ),
+            "spawn /usr/bin/git in ~, see https://docs.rs/x/y.html"
+        );
+
+        assert_eq!(clear(&conn).unwrap(), 2);
+    }
+}
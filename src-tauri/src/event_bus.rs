@@ -0,0 +1,513 @@
+// Lifecycle events emitted to the frontend and persisted for catch-up
+use chrono::Utc;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+use crate::commands::agents::AgentDb;
+use crate::window_sessions;
+
+/// Event that carries every persisted event with its cursor
+pub const BUS_EVENT: &str = "event-bus";
+
+/// Most filtered subscriptions open at once
+const MAX_SUBSCRIPTIONS: usize = 64;
+
+/// Most events kept in the log
+const MAX_EVENTS: i64 = 10_000;
+/// Events older than this are dropped by retention
+const MAX_EVENT_AGE_HOURS: i64 = 24;
+
+/// A persisted event
+#[derive(Debug, Clone, Serialize)]
+pub struct BusEvent {
+    /// Cursor of this event; pass it to `get_events_since` to resume after it
+    pub id: i64,
+    pub topic: String,
+    pub payload: JsonValue,
+    pub created_at: String,
+}
+
+/// A page of events returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPage {
+    pub events: Vec<BusEvent>,
+    /// Cursor to pass next time; unchanged when there are no new events
+    pub cursor: i64,
+    pub has_more: bool,
+}
+
+/// How serious an event is, derived from its topic and payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Which events a subscription receives
+///
+/// Every criterion that is set must match; within a list any entry may match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// Topic prefixes such as `agent-complete`
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// Agent runs, matched on the topic suffix or a `run_id` in the payload
+    #[serde(default)]
+    pub run_ids: Vec<i64>,
+    /// Claude sessions, matched on the topic suffix or a `session_id` in the payload
+    #[serde(default)]
+    pub session_ids: Vec<String>,
+    /// Matched on a `project_path` in the payload
+    #[serde(default)]
+    pub project_paths: Vec<String>,
+    #[serde(default)]
+    pub min_severity: Option<Severity>,
+}
+
+impl BusEvent {
+    /// The topic without its `:<id>` suffix
+    fn base_topic(&self) -> &str {
+        self.topic
+            .split_once(':')
+            .map_or(self.topic.as_str(), |(base, _)| base)
+    }
+
+    fn topic_id(&self) -> Option<&str> {
+        self.topic.split_once(':').map(|(_, id)| id)
+    }
+
+    pub fn severity(&self) -> Severity {
+        let base = self.base_topic();
+        let failed = base.ends_with("-complete") && self.payload == JsonValue::Bool(false);
+        let error = self.payload.get("error").is_some_and(|e| !e.is_null());
+        if failed || error {
+            Severity::Error
+        } else if base.contains("warning") || base.contains("conflict") {
+            Severity::Warning
+        } else {
+            Severity::Info
+        }
+    }
+
+    fn run_id(&self) -> Option<i64> {
+        self.payload
+            .get("run_id")
+            .and_then(JsonValue::as_i64)
+            .or_else(|| {
+                self.base_topic()
+                    .starts_with("agent-")
+                    .then(|| self.topic_id()?.parse().ok())
+                    .flatten()
+            })
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.payload
+            .get("session_id")
+            .and_then(JsonValue::as_str)
+            .or_else(|| {
+                self.base_topic()
+                    .starts_with("claude-")
+                    .then(|| self.topic_id())
+                    .flatten()
+            })
+    }
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &BusEvent) -> bool {
+        (self.topics.is_empty()
+            || self
+                .topics
+                .iter()
+                .any(|t| event.topic.starts_with(t.as_str())))
+            && (self.run_ids.is_empty()
+                || event.run_id().is_some_and(|id| self.run_ids.contains(&id)))
+            && (self.session_ids.is_empty()
+                || event
+                    .session_id()
+                    .is_some_and(|id| self.session_ids.iter().any(|s| s == id)))
+            && (self.project_paths.is_empty()
+                || event
+                    .payload
+                    .get("project_path")
+                    .and_then(JsonValue::as_str)
+                    .is_some_and(|path| self.project_paths.iter().any(|p| p == path)))
+            && self.min_severity.is_none_or(|min| event.severity() >= min)
+    }
+}
+
+/// Open filtered subscriptions; matching events are emitted as `event-bus:<id>`
+#[derive(Default)]
+pub struct EventSubscriptions {
+    next_id: AtomicU64,
+    filters: Mutex<HashMap<u64, EventFilter>>,
+}
+
+impl EventSubscriptions {
+    /// Open a subscription and return its ID
+    pub fn subscribe(&self, filter: EventFilter) -> Result<u64, String> {
+        let mut filters = self.filters.lock().map_err(|e| e.to_string())?;
+        if filters.len() >= MAX_SUBSCRIPTIONS {
+            return Err(format!(
+                "At most {} event subscriptions can be open; unsubscribe unused ones first",
+                MAX_SUBSCRIPTIONS
+            ));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        filters.insert(id, filter);
+        Ok(id)
+    }
+
+    /// Close a subscription; returns whether it was open
+    pub fn unsubscribe(&self, id: u64) -> bool {
+        self.filters
+            .lock()
+            .map(|mut filters| filters.remove(&id).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Subscriptions that want `event`
+    fn matching(&self, event: &BusEvent) -> Vec<u64> {
+        self.filters
+            .lock()
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter(|(_, filter)| filter.matches(event))
+                    .map(|(id, _)| *id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+struct PendingEvent {
+    topic: String,
+    /// Window the event was sent to; `None` when it went to every window
+    window: Option<String>,
+    payload: JsonValue,
+    created_at: String,
+}
+
+/// Handle to the background writer that persists published events
+pub struct EventBus {
+    sender: mpsc::UnboundedSender<PendingEvent>,
+    /// Events published but not yet written
+    pending: Arc<AtomicUsize>,
+}
+
+impl EventBus {
+    /// Wait until every published event has been written, or `timeout` passes
+    ///
+    /// Returns the number of events still queued, 0 when the queue drained.
+    pub async fn flush(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let pending = self.pending.load(Ordering::SeqCst);
+            if pending == 0 || Instant::now() >= deadline {
+                return pending;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+pub fn init_event_log(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS event_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            topic TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_event_log_created_at ON event_log(created_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Emit an event to the frontend and persist it for catch-up
+///
+/// Meant for lifecycle events such as runs finishing or operations completing;
+/// high-volume streams like output lines keep using plain emits, which would
+/// swamp the log. Persisting happens on a background task, so this never
+/// blocks on the database and is safe to call while holding the `AgentDb` lock.
+pub fn publish<S: Serialize + ?Sized>(app: &AppHandle, topic: &str, payload: &S) {
+    publish_to_window(app, None, topic, payload);
+}
+
+/// Publish like `publish`, emitting an event that isn't scoped to an attached
+/// session to `window` only (see `window_sessions::emit_to_window`)
+pub fn publish_to_window<S: Serialize + ?Sized>(
+    app: &AppHandle,
+    window: Option<&str>,
+    topic: &str,
+    payload: &S,
+) {
+    let window = window_sessions::event_window(app, window, topic);
+    window_sessions::emit_to_label(app, window.as_deref(), topic, payload);
+
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::warn!("Failed to serialize {} event: {}", topic, e);
+            return;
+        }
+    };
+    if let Some(bus) = app.try_state::<EventBus>() {
+        bus.pending.fetch_add(1, Ordering::SeqCst);
+        let sent = bus.sender.send(PendingEvent {
+            topic: topic.to_string(),
+            window,
+            payload,
+            created_at: Utc::now().to_rfc3339(),
+        });
+        if sent.is_err() {
+            bus.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Start the task that writes published events to the log
+///
+/// Each persisted event is also emitted as `event-bus` with its cursor, so a
+/// live listener can keep its cursor current, and as `event-bus:<id>` for each
+/// subscription whose filter it matches. Both go to the window the event itself
+/// went to, so session-scoped events stay in their window.
+pub fn spawn_event_writer(app: AppHandle) -> EventBus {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PendingEvent>();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let written = pending.clone();
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(first) = receiver.recv().await {
+            let mut batch = vec![first];
+            while let Ok(event) = receiver.try_recv() {
+                batch.push(event);
+            }
+            let count = batch.len();
+            let windows: Vec<Option<String>> =
+                batch.iter().map(|event| event.window.clone()).collect();
+
+            let stored = {
+                let db = app.state::<AgentDb>();
+                match db.0.lock() {
+                    Ok(conn) => store_events(&conn, batch)
+                        .map_err(|e| log::warn!("Failed to persist events: {}", e))
+                        .ok(),
+                    Err(e) => {
+                        log::warn!("Failed to persist {} events: {}", count, e);
+                        None
+                    }
+                }
+            };
+
+            let subscriptions = app.try_state::<EventSubscriptions>();
+            for (event, window) in stored.iter().flatten().zip(&windows) {
+                let window = window.as_deref();
+                window_sessions::emit_to_label(&app, window, BUS_EVENT, event);
+                for id in subscriptions.iter().flat_map(|s| s.matching(event)) {
+                    let topic = format!("{}:{}", BUS_EVENT, id);
+                    window_sessions::emit_to_label(&app, window, &topic, event);
+                }
+            }
+            // Failed batches count as handled too, so a flush doesn't wait on them
+            written.fetch_sub(count, Ordering::SeqCst);
+        }
+    });
+
+    EventBus { sender, pending }
+}
+
+fn store_events(conn: &Connection, batch: Vec<PendingEvent>) -> SqliteResult<Vec<BusEvent>> {
+    let tx = conn.unchecked_transaction()?;
+    let mut stored = Vec::with_capacity(batch.len());
+    for event in batch {
+        tx.execute(
+            "INSERT INTO event_log (topic, payload, created_at) VALUES (?1, ?2, ?3)",
+            params![event.topic, event.payload.to_string(), event.created_at],
+        )?;
+        stored.push(BusEvent {
+            id: tx.last_insert_rowid(),
+            topic: event.topic,
+            payload: event.payload,
+            created_at: event.created_at,
+        });
+    }
+    tx.commit()?;
+    Ok(stored)
+}
+
+/// Events after `cursor`, oldest first, optionally limited to topics starting with `topic_prefix`
+pub fn events_since(
+    conn: &Connection,
+    cursor: i64,
+    topic_prefix: Option<&str>,
+    limit: u32,
+) -> SqliteResult<EventPage> {
+    let mut stmt = conn.prepare(
+        "SELECT id, topic, payload, created_at FROM event_log
+         WHERE id > ?1 AND (?2 IS NULL OR substr(topic, 1, length(?2)) = ?2)
+         ORDER BY id ASC LIMIT ?3",
+    )?;
+    let mut events = stmt
+        .query_map(params![cursor, topic_prefix, limit as i64 + 1], |row| {
+            let payload: String = row.get(2)?;
+            Ok(BusEvent {
+                id: row.get(0)?,
+                topic: row.get(1)?,
+                payload: serde_json::from_str(&payload).unwrap_or(JsonValue::Null),
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let has_more = events.len() > limit as usize;
+    events.truncate(limit as usize);
+    let cursor = events.last().map(|e| e.id).unwrap_or(cursor);
+    Ok(EventPage {
+        events,
+        cursor,
+        has_more,
+    })
+}
+
+/// Drop events beyond the retention window; returns how many were removed
+pub fn prune_event_log(conn: &Connection) -> SqliteResult<usize> {
+    conn.execute(
+        "DELETE FROM event_log
+         WHERE id <= (SELECT COALESCE(MAX(id), 0) FROM event_log) - ?1
+            OR created_at < ?2",
+        params![
+            MAX_EVENTS,
+            (Utc::now() - chrono::Duration::hours(MAX_EVENT_AGE_HOURS)).to_rfc3339()
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_since_pages_and_filters() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_event_log(&conn).unwrap();
+        let now = Utc::now().to_rfc3339();
+        let batch = ["agent-complete:1", "claude-complete:a", "agent-complete:2"]
+            .iter()
+            .map(|topic| PendingEvent {
+                topic: topic.to_string(),
+                window: None,
+                payload: JsonValue::Bool(true),
+                created_at: now.clone(),
+            })
+            .collect();
+        store_events(&conn, batch).unwrap();
+
+        let page = events_since(&conn, 0, None, 2).unwrap();
+        assert_eq!(page.events.len(), 2);
+        assert!(page.has_more);
+
+        let rest = events_since(&conn, page.cursor, None, 10).unwrap();
+        assert_eq!(rest.events.len(), 1);
+        assert!(!rest.has_more);
+        assert_eq!(
+            events_since(&conn, rest.cursor, None, 10).unwrap().cursor,
+            rest.cursor
+        );
+
+        let agents = events_since(&conn, 0, Some("agent-"), 10).unwrap();
+        assert_eq!(agents.events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bus_events_stay_in_their_window() {
+        use tauri::{Listener, WebviewUrl, WebviewWindowBuilder};
+
+        let app = tauri::test::mock_builder()
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        init_event_log(&conn).unwrap();
+        app.manage(AgentDb(Mutex::new(conn)));
+        app.manage(window_sessions::WindowSessions::default());
+        app.manage(spawn_event_writer(app.handle().clone()));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        for label in ["main", "project-api"] {
+            let window = WebviewWindowBuilder::new(&app, label, WebviewUrl::default())
+                .build()
+                .unwrap();
+            let tx = tx.clone();
+            window.listen(BUS_EVENT, move |_| tx.send(label).unwrap());
+        }
+        app.state::<window_sessions::WindowSessions>()
+            .attach("s1", "project-api");
+        let bus = app.state::<EventBus>();
+
+        publish(app.handle(), "claude-complete:s1", &true);
+        assert_eq!(bus.flush(Duration::from_secs(5)).await, 0);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["project-api"]);
+
+        publish(app.handle(), "agent-complete:7", &true);
+        assert_eq!(bus.flush(Duration::from_secs(5)).await, 0);
+        let mut received: Vec<_> = rx.try_iter().collect();
+        received.sort();
+        assert_eq!(received, vec!["main", "project-api"]);
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let event = |topic: &str, payload: JsonValue| BusEvent {
+            id: 1,
+            topic: topic.to_string(),
+            payload,
+            created_at: String::new(),
+        };
+        let failed = event("agent-complete:7", JsonValue::Bool(false));
+        let conflict = event(
+            "project-lock-conflict",
+            serde_json::json!({ "run_id": 8, "project_path": "/p" }),
+        );
+        let session = event("claude-complete:abc", JsonValue::Bool(true));
+        assert_eq!(failed.severity(), Severity::Error);
+        assert_eq!(conflict.severity(), Severity::Warning);
+        assert_eq!(session.severity(), Severity::Info);
+
+        let runs = EventFilter {
+            run_ids: vec![7, 8],
+            ..Default::default()
+        };
+        assert!(runs.matches(&failed) && runs.matches(&conflict) && !runs.matches(&session));
+
+        let project_warnings = EventFilter {
+            project_paths: vec!["/p".to_string()],
+            min_severity: Some(Severity::Warning),
+            ..Default::default()
+        };
+        assert!(project_warnings.matches(&conflict));
+        assert!(!project_warnings.matches(&failed));
+
+        let sessions = EventFilter {
+            topics: vec!["claude-".to_string()],
+            session_ids: vec!["abc".to_string()],
+            ..Default::default()
+        };
+        assert!(sessions.matches(&session) && !sessions.matches(&failed));
+        assert!(EventFilter::default().matches(&failed));
+    }
+}
@@ -0,0 +1,287 @@
+// Append-only audit log of the files opcode writes on the user's behalf
+use chrono::Utc;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::commands::settings as store;
+
+const DB_FILE: &str = "file_audit.db";
+const DEFAULT_LIMIT: usize = 200;
+const MAX_LIMIT: usize = 5_000;
+
+static LOG: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+/// What a command did to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOperation {
+    Create,
+    Modify,
+    Delete,
+}
+
+impl FileOperation {
+    /// `Create` when nothing is at `path` yet, `Modify` otherwise; call it
+    /// before writing
+    pub fn for_write(path: &Path) -> Self {
+        if path.exists() {
+            Self::Modify
+        } else {
+            Self::Create
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Modify => "modify",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// One recorded file mutation
+#[derive(Debug, Clone, Serialize)]
+pub struct FileAuditEntry {
+    pub id: i64,
+    pub timestamp: String,
+    /// The command that wrote the file, e.g. `install_skill`
+    pub source: String,
+    pub operation: String,
+    pub path: String,
+    /// Size after the write; `None` for deletions and directories
+    pub size: Option<u64>,
+}
+
+/// Which entries `query` returns; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FileAuditFilter {
+    pub source: Option<String>,
+    pub operation: Option<FileOperation>,
+    /// Only files under this path
+    pub path_prefix: Option<String>,
+    /// RFC 3339 timestamps
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Entries older than this ID, for paging back through the log
+    pub before_id: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+/// How long entries are kept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_age_days: i64,
+    pub max_entries: i64,
+}
+
+/// Create the file_audit_log table
+pub fn init_file_audit(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS file_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            source TEXT NOT NULL,
+            operation TEXT NOT NULL,
+            path TEXT NOT NULL,
+            size INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_file_audit_log_timestamp ON file_audit_log(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_file_audit_log_path ON file_audit_log(path);
+        CREATE TRIGGER IF NOT EXISTS file_audit_log_append_only
+            BEFORE UPDATE ON file_audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'The file audit log is append-only');
+            END;",
+    )
+}
+
+/// Open the log in the app data directory; entries recorded before this are
+/// only written to the app log
+///
+/// The log is its own database, so writers can record while they hold the app
+/// database's lock.
+pub fn open(app_dir: &Path) -> SqliteResult<()> {
+    let conn = Connection::open(app_dir.join(DB_FILE))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    init_file_audit(&conn)?;
+    let _ = LOG.set(Mutex::new(conn));
+    Ok(())
+}
+
+/// Run `f` on the open log
+pub fn with_log<T>(f: impl FnOnce(&Connection) -> SqliteResult<T>) -> Result<T, String> {
+    let log = LOG.get().ok_or("The file audit log isn't open")?;
+    let conn = log.lock().map_err(|e| e.to_string())?;
+    f(&conn).map_err(|e| e.to_string())
+}
+
+fn record_entry(
+    conn: &Connection,
+    source: &str,
+    operation: FileOperation,
+    path: &Path,
+    size: Option<u64>,
+) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO file_audit_log (timestamp, source, operation, path, size)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            Utc::now().to_rfc3339(),
+            source,
+            operation.as_str(),
+            path.to_string_lossy(),
+            size.map(|s| s as i64)
+        ],
+    )?;
+    Ok(())
+}
+
+/// Record that `source` did `operation` to `path`; call it after the write
+/// succeeded
+///
+/// Never fails the write it describes: an entry that can't be recorded goes
+/// to the app log as a warning instead.
+pub fn record(source: &str, operation: FileOperation, path: &Path) {
+    let size = match operation {
+        FileOperation::Delete => None,
+        _ => std::fs::metadata(path)
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len()),
+    };
+    if let Err(e) = with_log(|conn| record_entry(conn, source, operation, path, size)) {
+        log::warn!(
+            "Failed to record {} of {} by {} in the file audit log: {}",
+            operation.as_str(),
+            path.display(),
+            source,
+            e
+        );
+    }
+}
+
+/// Entries matching `filter`, newest first
+pub fn query(conn: &Connection, filter: &FileAuditFilter) -> SqliteResult<Vec<FileAuditEntry>> {
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, source, operation, path, size FROM file_audit_log
+         WHERE (?1 IS NULL OR source = ?1)
+           AND (?2 IS NULL OR operation = ?2)
+           AND (?3 IS NULL OR substr(path, 1, length(?3)) = ?3)
+           AND (?4 IS NULL OR timestamp >= ?4)
+           AND (?5 IS NULL OR timestamp <= ?5)
+           AND (?6 IS NULL OR id < ?6)
+         ORDER BY id DESC
+         LIMIT ?7",
+    )?;
+    let entries = stmt
+        .query_map(
+            params![
+                filter.source,
+                filter.operation.map(|o| o.as_str()),
+                filter.path_prefix,
+                filter.since,
+                filter.until,
+                filter.before_id,
+                limit as i64
+            ],
+            |row| {
+                Ok(FileAuditEntry {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    source: row.get(2)?,
+                    operation: row.get(3)?,
+                    path: row.get(4)?,
+                    size: row.get::<_, Option<i64>>(5)?.map(|s| s as u64),
+                })
+            },
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(entries)
+}
+
+/// Retention from the settings in the app database
+pub fn load_retention(conn: &Connection) -> RetentionPolicy {
+    RetentionPolicy {
+        max_age_days: store::get_integer(conn, "file_audit_retention_days").unwrap_or(365),
+        max_entries: store::get_integer(conn, "file_audit_max_entries").unwrap_or(1_000_000),
+    }
+}
+
+/// Drop entries beyond the retention policy; returns how many were removed
+pub fn prune(conn: &Connection, policy: &RetentionPolicy) -> SqliteResult<usize> {
+    conn.execute(
+        "DELETE FROM file_audit_log
+         WHERE id <= (SELECT COALESCE(MAX(id), 0) FROM file_audit_log) - ?1
+            OR timestamp < ?2",
+        params![
+            policy.max_entries,
+            (Utc::now() - chrono::Duration::days(policy.max_age_days)).to_rfc3339()
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_query_and_prune() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_file_audit(&conn).unwrap();
+        for (source, operation, path) in [
+            (
+                "install_skill",
+                FileOperation::Create,
+                "/p/.claude/skills/a/SKILL.md",
+            ),
+            (
+                "save_claude_settings",
+                FileOperation::Modify,
+                "/home/me/.claude/settings.json",
+            ),
+            (
+                "restore_checkpoint",
+                FileOperation::Delete,
+                "/p/src/main.rs",
+            ),
+        ] {
+            record_entry(&conn, source, operation, Path::new(path), Some(1)).unwrap();
+        }
+
+        let under_project = query(
+            &conn,
+            &FileAuditFilter {
+                path_prefix: Some("/p/".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let sources: Vec<&str> = under_project.iter().map(|e| e.source.as_str()).collect();
+        assert_eq!(sources, vec!["restore_checkpoint", "install_skill"]);
+        let deletes = query(
+            &conn,
+            &FileAuditFilter {
+                operation: Some(FileOperation::Delete),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(deletes.len(), 1);
+
+        assert!(conn
+            .execute("UPDATE file_audit_log SET path = '/elsewhere'", [])
+            .is_err());
+
+        let policy = RetentionPolicy {
+            max_age_days: 30,
+            max_entries: 2,
+        };
+        assert_eq!(prune(&conn, &policy).unwrap(), 1);
+        assert_eq!(query(&conn, &FileAuditFilter::default()).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,210 @@
+// Human-readable formatting of costs, durations, sizes and counts
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::commands::settings as store;
+
+/// Locales with their own number conventions
+pub const LOCALES: &[&str] = &[
+    "en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "ja-JP", "zh-CN",
+];
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Text in several languages, keyed by locale (`de-DE`) or language (`de`)
+pub type LocalizedText = BTreeMap<String, String>;
+
+/// The locale chosen in settings
+pub fn locale_from_settings(conn: &Connection) -> String {
+    store::get_text(conn, "locale").unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// `text` in `locale`, else in its language, else in English
+pub fn localize(text: &LocalizedText, locale: &str) -> String {
+    let language = locale.split('-').next().unwrap_or(locale);
+    [locale, language, DEFAULT_LOCALE, "en"]
+        .iter()
+        .find_map(|key| text.get(*key))
+        .or_else(|| text.values().next())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// How one locale writes numbers and amounts of money
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NumberStyle {
+    decimal: char,
+    group: &'static str,
+    /// `$1.00` rather than `1,00 $`
+    symbol_first: bool,
+}
+
+fn number_style(locale: &str) -> NumberStyle {
+    match locale {
+        "de-DE" | "es-ES" => NumberStyle {
+            decimal: ',',
+            group: ".",
+            symbol_first: false,
+        },
+        "fr-FR" => NumberStyle {
+            decimal: ',',
+            group: "\u{202f}",
+            symbol_first: false,
+        },
+        _ => NumberStyle {
+            decimal: '.',
+            group: ",",
+            symbol_first: true,
+        },
+    }
+}
+
+/// Formats values for the user's locale
+///
+/// Costs are always in USD, the currency Anthropic bills in; only the way the
+/// amount is written changes with the locale.
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter {
+    style: NumberStyle,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE)
+    }
+}
+
+impl Formatter {
+    pub fn new(locale: &str) -> Self {
+        Self {
+            style: number_style(locale),
+        }
+    }
+
+    /// The formatter for the locale chosen in settings
+    pub fn from_settings(conn: &Connection) -> Self {
+        Self::new(&locale_from_settings(conn))
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push_str(self.style.group);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// `value` with `decimals` digits after the separator and grouped thousands
+    pub fn decimal(&self, value: f64, decimals: usize) -> String {
+        let fixed = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let mut text = String::new();
+        if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            text.push('-');
+        }
+        text.push_str(&self.group_digits(whole));
+        if !fraction.is_empty() {
+            text.push(self.style.decimal);
+            text.push_str(fraction);
+        }
+        text
+    }
+
+    /// A whole number with grouped thousands, e.g. `12,345`
+    pub fn count(&self, value: i64) -> String {
+        self.decimal(value as f64, 0)
+    }
+
+    /// A USD amount; amounts under a cent keep four decimals so they don't show as zero
+    pub fn cost(&self, usd: f64) -> String {
+        let decimals = if usd != 0.0 && usd.abs() < 0.01 { 4 } else { 2 };
+        let amount = self.decimal(usd, decimals);
+        if self.style.symbol_first {
+            match amount.strip_prefix('-') {
+                Some(amount) => format!("-${}", amount),
+                None => format!("${}", amount),
+            }
+        } else {
+            format!("{}\u{a0}$", amount)
+        }
+    }
+
+    /// The two largest units of a duration, e.g. `4m 12s` or `850ms`
+    pub fn duration(&self, duration: Duration) -> String {
+        let secs = duration.as_secs();
+        let (days, hours, minutes, seconds) =
+            (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+        if days > 0 {
+            format!("{}d {}h", days, hours)
+        } else if hours > 0 {
+            format!("{}h {}m", hours, minutes)
+        } else if minutes > 0 {
+            format!("{}m {}s", minutes, seconds)
+        } else if secs > 0 {
+            format!("{}s", seconds)
+        } else {
+            format!("{}ms", duration.as_millis())
+        }
+    }
+
+    /// A size in bytes with a binary unit, e.g. `1.5 MB`
+    pub fn bytes(&self, bytes: u64) -> String {
+        const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+        if bytes < 1024 {
+            return format!("{} B", bytes);
+        }
+        let mut value = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let decimals = if value < 10.0 { 1 } else { 0 };
+        format!("{} {}", self.decimal(value, decimals), UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatter() {
+        let us = Formatter::new("en-US");
+        let de = Formatter::new("de-DE");
+
+        assert_eq!(us.cost(1234.5), "$1,234.50");
+        assert_eq!(us.cost(-0.5), "-$0.50");
+        assert_eq!(us.cost(0.0042), "$0.0042");
+        assert_eq!(de.cost(1234.5), "1.234,50\u{a0}$");
+        assert_eq!(
+            Formatter::new("fr-FR").count(1234567),
+            "1\u{202f}234\u{202f}567"
+        );
+        assert_eq!(Formatter::new("unknown").count(999), "999");
+
+        assert_eq!(us.duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(us.duration(Duration::from_secs(252)), "4m 12s");
+        assert_eq!(us.duration(Duration::from_secs(90_061)), "1d 1h");
+
+        assert_eq!(us.bytes(512), "512 B");
+        assert_eq!(us.bytes(1536 * 1024), "1.5 MB");
+        assert_eq!(de.bytes(1536 * 1024), "1,5 MB");
+        assert_eq!(us.bytes(200 * 1024 * 1024 * 1024), "200 GB");
+    }
+
+    #[test]
+    fn test_localize_falls_back_to_language_then_english() {
+        let text: LocalizedText = [("en", "Files"), ("de", "Dateien"), ("fr-FR", "Fichiers")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(localize(&text, "de-DE"), "Dateien");
+        assert_eq!(localize(&text, "fr-FR"), "Fichiers");
+        assert_eq!(localize(&text, "ja-JP"), "Files");
+        assert_eq!(localize(&LocalizedText::new(), "en-US"), "");
+    }
+}
@@ -0,0 +1,197 @@
+// Shared HTTP client honouring the proxy, CA and timeout settings
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+use rusqlite::Connection;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::commands::proxy::ProxySettings;
+use crate::commands::settings::get_integer;
+
+/// Hosts that never go through the proxy
+const DEFAULT_NO_PROXY: &str = "localhost,127.0.0.1,::1,0.0.0.0";
+
+static SETTINGS: RwLock<Option<ProxySettings>> = RwLock::new(None);
+static LIMITS: RwLock<Option<HttpLimits>> = RwLock::new(None);
+static CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+
+/// Timeouts and rate limits for outbound requests
+///
+/// Timeouts apply to every client; the per-host rate is enforced by
+/// `network::send_with_policy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpLimits {
+    pub connect_timeout: Duration,
+    /// Longest wait for the next bytes of a response
+    pub read_timeout: Duration,
+    /// Requests per minute to any one host, on average
+    pub host_requests_per_minute: u32,
+    /// Requests to one host that may go out at once before the rate applies
+    pub host_burst: u32,
+}
+
+impl Default for HttpLimits {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(60),
+            host_requests_per_minute: 60,
+            host_burst: 10,
+        }
+    }
+}
+
+/// Limits from the settings store
+pub fn load_limits(conn: &Connection) -> HttpLimits {
+    let defaults = HttpLimits::default();
+    let secs = |key: &str, default: Duration| {
+        get_integer(conn, key)
+            .map(|s| Duration::from_secs(s.max(1) as u64))
+            .unwrap_or(default)
+    };
+    let count = |key: &str, default: u32| {
+        get_integer(conn, key)
+            .map(|n| n.clamp(1, u32::MAX as i64) as u32)
+            .unwrap_or(default)
+    };
+    HttpLimits {
+        connect_timeout: secs("http_connect_timeout_secs", defaults.connect_timeout),
+        read_timeout: secs("http_read_timeout_secs", defaults.read_timeout),
+        host_requests_per_minute: count(
+            "http_host_requests_per_minute",
+            defaults.host_requests_per_minute,
+        ),
+        host_burst: count("http_host_burst", defaults.host_burst),
+    }
+}
+
+/// The limits currently in effect
+pub fn limits() -> HttpLimits {
+    LIMITS.read().ok().and_then(|l| *l).unwrap_or_default()
+}
+
+/// Replace the limits used for new clients and drop the cached client
+pub fn configure_limits(limits: &HttpLimits) {
+    if let Ok(mut current) = LIMITS.write() {
+        *current = Some(*limits);
+    }
+    if let Ok(mut client) = CLIENT.write() {
+        *client = None;
+    }
+}
+
+/// Replace the settings used for new clients and drop the cached client
+pub fn configure(settings: &ProxySettings) {
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = Some(settings.clone());
+    }
+    if let Ok(mut client) = CLIENT.write() {
+        *client = None;
+    }
+}
+
+/// Shared client built from the current settings
+pub fn client() -> Result<Client, String> {
+    if let Some(client) = CLIENT.read().ok().and_then(|c| c.clone()) {
+        return Ok(client);
+    }
+
+    let client = client_builder()?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    if let Ok(mut cached) = CLIENT.write() {
+        *cached = Some(client.clone());
+    }
+    Ok(client)
+}
+
+/// Builder preconfigured from the current settings, for callers that need extra options
+pub fn client_builder() -> Result<ClientBuilder, String> {
+    let settings = SETTINGS
+        .read()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_default();
+    builder_for(&settings)
+}
+
+/// Builder for the given settings; fails on invalid proxy URLs or CA bundles
+pub fn builder_for(settings: &ProxySettings) -> Result<ClientBuilder, String> {
+    let limits = limits();
+    let mut builder = Client::builder()
+        .connect_timeout(limits.connect_timeout)
+        .read_timeout(limits.read_timeout);
+
+    if settings.enabled {
+        // Use only the configured proxies, not whatever is in the environment
+        builder = builder.no_proxy();
+
+        let no_proxy = match non_empty(&settings.no_proxy) {
+            Some(user) => format!("{},{}", DEFAULT_NO_PROXY, user),
+            None => DEFAULT_NO_PROXY.to_string(),
+        };
+
+        if let Some(url) = non_empty(&settings.http_proxy) {
+            builder = builder.proxy(configured_proxy(Proxy::http(url), url, &no_proxy)?);
+        }
+        if let Some(url) = non_empty(&settings.https_proxy) {
+            builder = builder.proxy(configured_proxy(Proxy::https(url), url, &no_proxy)?);
+        }
+        if let Some(url) = non_empty(&settings.all_proxy) {
+            builder = builder.proxy(configured_proxy(Proxy::all(url), url, &no_proxy)?);
+        }
+    }
+
+    if let Some(path) = non_empty(&settings.ca_bundle_path) {
+        let pem = std::fs::read(path)
+            .map_err(|e| format!("Failed to read CA bundle '{}': {}", path, e))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA bundle '{}': {}", path, e))?;
+        if certificates.is_empty() {
+            return Err(format!("CA bundle '{}' contains no certificates", path));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    Ok(builder)
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.is_empty())
+}
+
+fn configured_proxy(
+    proxy: reqwest::Result<Proxy>,
+    url: &str,
+    no_proxy: &str,
+) -> Result<Proxy, String> {
+    proxy
+        .map(|p| p.no_proxy(NoProxy::from_string(no_proxy)))
+        .map_err(|e| format!("Invalid proxy URL '{}': {}", url, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_proxy_url_is_rejected() {
+        let settings = ProxySettings {
+            enabled: true,
+            https_proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(builder_for(&settings).is_err());
+    }
+
+    #[test]
+    fn test_missing_ca_bundle_is_rejected() {
+        let settings = ProxySettings {
+            ca_bundle_path: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        };
+        let err = builder_for(&settings).unwrap_err();
+        assert!(err.contains("Failed to read CA bundle"));
+    }
+}
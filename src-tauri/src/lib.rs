@@ -3,12 +3,51 @@
 // Declare modules
 pub mod checkpoint;
 pub mod claude_binary;
+pub mod claude_watcher;
+pub mod cli_compat;
 pub mod commands;
+pub mod control_server;
+pub mod db_health;
+pub mod diff_providers;
+pub mod dispatch;
+pub mod error_reports;
+pub mod event_bus;
+pub mod file_audit;
+pub mod format;
+pub mod http_client;
+pub mod logging;
+pub mod maintenance;
+pub mod metrics;
+pub mod network;
+pub mod notifications;
+pub mod otlp;
+pub mod path_validation;
+pub mod permissions;
+pub mod prewarm;
 pub mod process;
+pub mod project_env;
+pub mod project_locks;
+pub mod project_registry;
+pub mod prompt_history;
+pub mod prompt_template;
+pub mod redaction;
+pub mod run_env;
+pub mod run_queue;
+pub mod safe_mode;
+pub mod sandbox;
+pub mod scheduler;
+pub mod schema_drift;
+pub mod shutdown;
+pub mod storage_usage;
+pub mod tokens;
 pub mod utils;
 pub mod web_server;
+pub mod window_sessions;
+pub mod workspace;
 
 use crate::checkpoint::state::CheckpointState;
+use crate::claude_watcher::ClaudeWatcher;
+use crate::commands::agent_race::{compare_run_outputs, get_agent_race, start_agent_race};
 use crate::commands::agents::{
     cleanup_finished_processes, create_agent, delete_agent, execute_agent, export_agent,
     export_agent_to_file, fetch_github_agent_content, fetch_github_agents, get_agent,
@@ -19,6 +58,16 @@ use crate::commands::agents::{
     list_running_sessions, load_agent_session_history, set_claude_binary_path,
     stream_session_output, update_agent, AgentDb,
 };
+use crate::commands::app_config::{export_app_config, import_app_config};
+use crate::commands::benchmarks::{benchmark_models, list_model_benchmarks};
+use crate::commands::billing::fetch_anthropic_usage;
+use crate::commands::blame::explain_change;
+use crate::commands::bookmarks::{
+    add_transcript_bookmark, delete_transcript_bookmark, export_transcript,
+    import_transcript_bookmarks, jump_to_transcript_bookmark, list_transcript_bookmarks,
+    update_transcript_bookmark,
+};
+use crate::commands::capabilities::{get_capabilities, get_cli_capabilities};
 use crate::commands::claude::{
     cancel_claude_execution, check_auto_checkpoint, check_claude_version, cleanup_old_checkpoints,
     clear_checkpoint_manager, continue_claude_code, create_checkpoint, execute_claude_code,
@@ -32,98 +81,216 @@ use crate::commands::claude::{
     track_session_messages, update_checkpoint_settings, update_hooks_config, validate_hook_command,
     ClaudeProcessState,
 };
+use crate::commands::claude_dir::{
+    get_claude_dir_info, load_claude_dir_setting, set_claude_dir_location,
+};
+use crate::commands::claude_dir_import::import_claude_dir_archive;
+use crate::commands::claude_md::{check_claude_md_freshness, regenerate_claude_md_section};
+use crate::commands::claude_watcher::{unwatch_project_claude_dir, watch_project_claude_dir};
+use crate::commands::cli_migration::{
+    acknowledge_cli_migration, apply_cli_migration_fix, scan_cli_migration, spawn_cli_version_check,
+};
+use crate::commands::config_graph::get_config_graph;
+use crate::commands::control_server::{
+    get_control_server_status, rotate_control_server_token, set_control_server_enabled,
+};
+use crate::commands::deep_link::{
+    confirm_deep_link, dismiss_deep_link, get_pending_deep_links, handle_deep_link, DeepLinkState,
+};
+use crate::commands::doctor::run_doctor;
+use crate::commands::error_reports::{
+    clear_error_reports, list_recent_errors, record_command_error, submit_error_reports,
+};
+use crate::commands::event_bus::{get_events_since, subscribe_events, unsubscribe_events};
+use crate::commands::file_audit::query_audit_log;
+use crate::commands::file_history::{get_file_history, rebuild_file_history_index};
+use crate::commands::fixtures::generate_test_fixtures;
+use crate::commands::forecast::forecast_usage;
+use crate::commands::format::{format_values, set_app_locale};
+use crate::commands::git::{get_changed_files, get_working_diff, revert_files, stage_and_commit};
+use crate::commands::health::{get_health_settings, get_project_health, save_health_settings};
+use crate::commands::logging::{get_recent_logs, set_log_level};
+use crate::commands::maintenance::{
+    get_database_recovery, get_maintenance_status, run_maintenance_now,
+};
+use crate::commands::marketplace::get_marketplace_status;
 use crate::commands::mcp::{
     mcp_add, mcp_add_from_claude_desktop, mcp_add_json, mcp_get, mcp_get_server_status, mcp_list,
     mcp_read_project_config, mcp_remove, mcp_reset_project_choices, mcp_save_project_config,
     mcp_serve, mcp_test_connection, plugin_install, plugin_uninstall,
+    start_mcp_import_from_claude_desktop,
+};
+use crate::commands::notifications::{get_notification_settings, save_notification_settings};
+use crate::commands::onboarding::{complete_onboarding_step, get_onboarding_status};
+use crate::commands::operations::{cancel_operation, list_operations, OperationState};
+use crate::commands::permissions::{
+    evaluate_permission, get_permission_rules, update_permission_rules,
+};
+use crate::commands::prewarm::{get_prewarm_status, run_prewarm_now};
+use crate::commands::project_env::{delete_project_env, list_project_env, set_project_env};
+use crate::commands::project_locks::list_project_locks;
+use crate::commands::project_manager::{
+    create_project, get_project_sessions, init_claude_project, list_projects,
+};
+use crate::commands::project_profiles::{
+    apply_project_profile, delete_project_profile, install_skill_bundle, list_project_profiles,
+    save_project_profile,
 };
-use crate::commands::project_manager::{create_project, get_project_sessions, list_projects};
 
-use crate::commands::proxy::{apply_proxy_settings, get_proxy_settings, save_proxy_settings};
+use crate::commands::pricing::{
+    get_pricing, refresh_pricing_table, reset_pricing_table, save_pricing_overrides,
+};
+use crate::commands::project_registry::{
+    add_project, list_projects_registry, pin_project, remove_project,
+};
+use crate::commands::prompt_history::{delete_prompt_history, list_prompt_history, pin_prompt};
+use crate::commands::proxy::{
+    apply_proxy_settings, get_proxy_settings, load_proxy_settings, save_proxy_settings,
+};
+use crate::commands::redaction::{get_redaction_counts, set_run_redaction};
+use crate::commands::references::{list_message_references, resolve_reference};
+use crate::commands::replay::{start_session_replay, stop_session_replay, ReplayState};
+use crate::commands::run_history::{
+    delete_run, get_run, get_run_prune_policy, list_runs, load_prune_policy, prune_run_history,
+    prune_runs, save_run_prune_policy,
+};
+use crate::commands::run_manifest::{get_run_manifest, rerun_from_manifest};
+use crate::commands::run_queue::{dismiss_queued_run, list_queued_runs, start_queued_run};
+use crate::commands::safe_mode::{get_safe_mode_status, restart_in_safe_mode, restart_normally};
+use crate::commands::sandbox::{
+    delete_execution_profile, get_agent_execution_profile, get_sandbox_capabilities,
+    list_execution_profiles, save_execution_profile, set_agent_execution_profile,
+};
+use crate::commands::schedules::{
+    create_schedule, delete_schedule, list_schedules, run_schedule_now,
+};
+use crate::commands::session_export::export_session;
+use crate::commands::session_fork::fork_session;
+use crate::commands::settings::{get_all_settings, get_setting, set_setting};
+use crate::commands::skill_usage::get_skill_usage;
 use crate::commands::storage::{
     storage_delete_row, storage_execute_sql, storage_insert_row, storage_list_tables,
     storage_read_table, storage_reset_database, storage_update_row,
 };
+use crate::commands::storage_usage::{cleanup_storage, get_storage_usage};
+use crate::commands::streaming::{
+    get_live_output, get_output_streaming_settings, load_streaming_settings,
+    save_output_streaming_settings,
+};
+use crate::commands::telemetry::{
+    get_telemetry_settings, save_telemetry_settings, test_telemetry_export,
+};
+use crate::commands::tokens::{estimate_project_context, estimate_static_context, estimate_tokens};
+use crate::commands::updater::{
+    check_for_updates, download_and_install_update, spawn_update_check,
+};
 use crate::commands::usage::{
     get_session_stats, get_usage_by_date_range, get_usage_details, get_usage_stats,
 };
+use crate::commands::window_sessions::{
+    attach_session_to_window, detach_session_from_window, get_window_sessions,
+};
+use crate::commands::worktrees::{
+    create_agent_worktree, discard_worktree, list_agent_worktrees, merge_worktree_changes,
+};
+use crate::control_server::ControlServerState;
+use crate::dispatch::Dispatcher;
+use crate::event_bus::{spawn_event_writer, EventSubscriptions};
+use crate::maintenance::{spawn_maintenance_loop, MaintenanceState};
+use crate::prewarm::{spawn_prewarm_loop, PrewarmState};
 use crate::process::ProcessRegistryState;
+use crate::project_locks::ProjectLocks;
+use crate::redaction::RedactionState;
+use crate::run_queue::resume_run_queue;
+use crate::safe_mode::SafeModeGuard;
+use crate::scheduler::spawn_scheduler_loop;
+use crate::window_sessions::WindowSessions;
 use std::sync::Mutex;
-use tauri::Manager;
+use tauri::{Manager, WindowEvent};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize logger
-    env_logger::init();
+    // Initialize logging to stderr and the rotating log files
+    logging::init("opcode");
+
+    // A crash last time can be followed by a safe-mode launch
+    let launch = safe_mode::begin_launch();
 
-    tauri::Builder::default()
+    // Route every command through audit logging, window permissions and rate limits
+    let mut dispatcher = Dispatcher::with_default_middleware();
+    if launch.safe_mode {
+        dispatcher = dispatcher.with(SafeModeGuard);
+    }
+
+    let mut builder = tauri::Builder::default()
+        // Must come first: a second launch (e.g. from an opcode:// link) hands
+        // its arguments to the running instance instead of starting another
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_updater::Builder::new().build());
+    if !launch.safe_mode {
+        builder = builder
+            .plugin(tauri_plugin_deep_link::init())
+            .plugin(tauri_plugin_notification::init());
+    }
+
+    builder
+        .setup(move |app| {
             // Initialize agents database
             let conn = init_database(&app.handle()).expect("Failed to initialize agents database");
 
             // Load and apply proxy settings from the database
-            {
-                let db = AgentDb(Mutex::new(conn));
-                let proxy_settings = match db.0.lock() {
-                    Ok(conn) => {
-                        // Directly query proxy settings from the database
-                        let mut settings = crate::commands::proxy::ProxySettings::default();
-
-                        let keys = vec![
-                            ("proxy_enabled", "enabled"),
-                            ("proxy_http", "http_proxy"),
-                            ("proxy_https", "https_proxy"),
-                            ("proxy_no", "no_proxy"),
-                            ("proxy_all", "all_proxy"),
-                        ];
-
-                        for (db_key, field) in keys {
-                            if let Ok(value) = conn.query_row(
-                                "SELECT value FROM app_settings WHERE key = ?1",
-                                rusqlite::params![db_key],
-                                |row| row.get::<_, String>(0),
-                            ) {
-                                match field {
-                                    "enabled" => settings.enabled = value == "true",
-                                    "http_proxy" => {
-                                        settings.http_proxy = Some(value).filter(|s| !s.is_empty())
-                                    }
-                                    "https_proxy" => {
-                                        settings.https_proxy = Some(value).filter(|s| !s.is_empty())
-                                    }
-                                    "no_proxy" => {
-                                        settings.no_proxy = Some(value).filter(|s| !s.is_empty())
-                                    }
-                                    "all_proxy" => {
-                                        settings.all_proxy = Some(value).filter(|s| !s.is_empty())
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-
-                        log::info!("Loaded proxy settings: enabled={}", settings.enabled);
-                        settings
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to lock database for proxy settings: {}", e);
-                        crate::commands::proxy::ProxySettings::default()
+            let proxy_settings = load_proxy_settings(&conn);
+            log::info!("Loaded proxy settings: enabled={}", proxy_settings.enabled);
+            apply_proxy_settings(&proxy_settings);
+            http_client::configure_limits(&http_client::load_limits(&conn));
+            redaction::configure(&conn);
+            dispatch::configure(&conn);
+
+            // Apply the run history pruning policy
+            if !launch.safe_mode {
+                let policy = load_prune_policy(&conn);
+                match prune_run_history(&conn, &policy) {
+                    Ok(removed) if removed > 0 => {
+                        log::info!("Pruned {} runs from history", removed)
                     }
-                };
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Failed to prune run history: {}", e),
+                }
+            }
 
-                // Apply the proxy settings
-                apply_proxy_settings(&proxy_settings);
+            let streaming_settings = load_streaming_settings(&conn);
+
+            // Apply the saved log level
+            if let Some(level) = crate::commands::settings::get_text(&conn, "log_level") {
+                if let Err(e) = logging::set_level(&level) {
+                    log::warn!("Failed to apply log level: {}", e);
+                }
             }
 
-            // Re-open the connection for the app to manage
-            let conn = init_database(&app.handle()).expect("Failed to initialize agents database");
+            // Use the custom Claude directory, if one is configured
+            crate::utils::set_custom_claude_dir(load_claude_dir_setting(&conn));
+
             app.manage(AgentDb(Mutex::new(conn)));
 
+            // Open the audit log of files opcode writes
+            if let Ok(app_dir) = app.path().app_data_dir() {
+                if let Err(e) = crate::file_audit::open(&app_dir) {
+                    log::warn!("Failed to open the file audit log: {}", e);
+                }
+                // Keep panics for `list_recent_errors`
+                crate::error_reports::install_panic_hook(&app_dir);
+            }
+
             // Initialize checkpoint state
             let checkpoint_state = CheckpointState::new();
 
@@ -138,11 +305,92 @@ pub fn run() {
             app.manage(checkpoint_state);
 
             // Initialize process registry
-            app.manage(ProcessRegistryState::default());
+            let process_registry = ProcessRegistryState::default();
+            process_registry
+                .0
+                .set_output_buffer_lines(streaming_settings.buffer_lines);
+            app.manage(process_registry);
+
+            // Keep agent runs from editing the same working tree at once
+            app.manage(ProjectLocks::default());
 
             // Initialize Claude process state
             app.manage(ClaudeProcessState::default());
 
+            // Route session events to the window each session is attached to
+            app.manage(WindowSessions::default());
+
+            // Hide secrets in streamed output, per run
+            app.manage(RedactionState::default());
+
+            // Initialize session replay state
+            app.manage(ReplayState::default());
+
+            // Persist published events so the frontend can catch up after a reload
+            app.manage(spawn_event_writer(app.handle().clone()));
+            app.manage(EventSubscriptions::default());
+
+            // Send events when skills, agents, commands or settings change on disk
+            app.manage(ClaudeWatcher::start(app.handle().clone()));
+
+            // Initialize long-running operation tracking
+            app.manage(OperationState::default());
+
+            // Route opcode:// links to a confirmation flow
+            app.manage(DeepLinkState::default());
+            app.manage(MaintenanceState::default());
+            app.manage(PrewarmState::default());
+            app.manage(ControlServerState::default());
+            if launch.safe_mode {
+                log::info!(
+                    "Safe mode: deep links, maintenance, schedules, prewarm and the control server are off"
+                );
+            } else {
+                #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+                {
+                    if let Err(e) = app.deep_link().register_all() {
+                        log::warn!("Failed to register the opcode:// scheme: {}", e);
+                    }
+                }
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    for url in event.urls() {
+                        handle_deep_link(&handle, url.as_str());
+                    }
+                });
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    for url in urls {
+                        handle_deep_link(app.handle(), url.as_str());
+                    }
+                }
+
+                // Run deferred maintenance while the app is idle
+                spawn_maintenance_loop(app.handle().clone());
+
+                // Handle runs left queued when the app last exited
+                resume_run_queue(app.handle().clone());
+
+                // Start scheduled agent runs as they come due
+                spawn_scheduler_loop(app.handle().clone());
+
+                // Refresh caches shortly before the user's workday starts
+                spawn_prewarm_loop(app.handle().clone());
+
+                // Offer a migration checklist after a major Claude CLI upgrade
+                spawn_cli_version_check(app.handle().clone());
+
+                // Look for a newer opcode release if automatic checks are on
+                spawn_update_check(app.handle().clone());
+
+                // Serve the local automation API if it is enabled
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = crate::control_server::configure(&handle).await {
+                        log::warn!("Failed to start the control server: {}", e);
+                    }
+                });
+            }
+
             // Apply window vibrancy with rounded corners on macOS
             #[cfg(target_os = "macos")]
             {
@@ -179,12 +427,29 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .on_window_event(|window, event| match event {
+            WindowEvent::Focused(focused) => {
+                if let Some(state) = window.try_state::<MaintenanceState>() {
+                    state.set_focused(*focused);
+                }
+            }
+            WindowEvent::Destroyed => {
+                if let Some(sessions) = window.try_state::<WindowSessions>() {
+                    sessions.detach_window(window.label());
+                }
+            }
+            _ => {}
+        })
+        .invoke_handler(dispatcher.wrap(tauri::generate_handler![
             // Claude & Project Management
             list_projects,
             create_project,
             get_project_sessions,
+            init_claude_project,
             get_home_directory,
+            get_claude_dir_info,
+            set_claude_dir_location,
+            import_claude_dir_archive,
             get_claude_settings,
             open_new_session,
             get_system_prompt,
@@ -199,9 +464,17 @@ pub fn run() {
             execute_claude_code,
             continue_claude_code,
             resume_claude_code,
+            fork_session,
             cancel_claude_execution,
             execute_sdk_agent,
             list_running_claude_sessions,
+            // Windows
+            attach_session_to_window,
+            detach_session_from_window,
+            get_window_sessions,
+            // Redaction
+            set_run_redaction,
+            get_redaction_counts,
             get_claude_session_output,
             list_directory_contents,
             search_files,
@@ -209,6 +482,10 @@ pub fn run() {
             get_hooks_config,
             update_hooks_config,
             validate_hook_command,
+            // Permission Rules
+            get_permission_rules,
+            update_permission_rules,
+            evaluate_permission,
             // Checkpoint Management
             create_checkpoint,
             restore_checkpoint,
@@ -253,11 +530,170 @@ pub fn run() {
             fetch_github_agents,
             fetch_github_agent_content,
             import_agent_from_github,
+            // Run History
+            list_runs,
+            get_run,
+            delete_run,
+            get_run_prune_policy,
+            save_run_prune_policy,
+            prune_runs,
+            get_run_manifest,
+            rerun_from_manifest,
+            // Live Output Streaming
+            get_live_output,
+            get_output_streaming_settings,
+            save_output_streaming_settings,
+            // Transcript Bookmarks
+            add_transcript_bookmark,
+            list_transcript_bookmarks,
+            update_transcript_bookmark,
+            delete_transcript_bookmark,
+            jump_to_transcript_bookmark,
+            export_transcript,
+            import_transcript_bookmarks,
+            // Session Export
+            export_session,
+            // Transcript References
+            list_message_references,
+            resolve_reference,
+            // .claude Watcher
+            watch_project_claude_dir,
+            unwatch_project_claude_dir,
+            // File Audit Log
+            query_audit_log,
+            // File History
+            get_file_history,
+            rebuild_file_history_index,
+            explain_change,
+            // Project Health
+            get_project_health,
+            get_health_settings,
+            save_health_settings,
+            // CLAUDE.md Freshness
+            check_claude_md_freshness,
+            regenerate_claude_md_section,
+            // CLI Migration
+            scan_cli_migration,
+            apply_cli_migration_fix,
+            acknowledge_cli_migration,
+            // Onboarding
+            get_onboarding_status,
+            complete_onboarding_step,
+            // Configuration Graph
+            get_config_graph,
+            // Model Benchmarks
+            benchmark_models,
+            list_model_benchmarks,
+            // Updates
+            check_for_updates,
+            download_and_install_update,
+            // Token Estimation
+            estimate_tokens,
+            estimate_project_context,
+            estimate_static_context,
+            // Usage Forecasting
+            forecast_usage,
+            // Git
+            get_changed_files,
+            get_working_diff,
+            stage_and_commit,
+            revert_files,
+            // Project Locks
+            list_project_locks,
+            // Project Registry
+            add_project,
+            remove_project,
+            pin_project,
+            list_projects_registry,
+            // Prompt History
+            list_prompt_history,
+            pin_prompt,
+            delete_prompt_history,
+            // Project Environment
+            set_project_env,
+            list_project_env,
+            delete_project_env,
+            // Project Profiles
+            install_skill_bundle,
+            list_project_profiles,
+            save_project_profile,
+            delete_project_profile,
+            apply_project_profile,
+            // Worktrees
+            create_agent_worktree,
+            list_agent_worktrees,
+            merge_worktree_changes,
+            discard_worktree,
+            // Agent Races
+            start_agent_race,
+            get_agent_race,
+            compare_run_outputs,
+            // Formatting
+            format_values,
+            set_app_locale,
+            // Notifications
+            get_notification_settings,
+            save_notification_settings,
+            // Safe Mode
+            get_safe_mode_status,
+            restart_in_safe_mode,
+            restart_normally,
+            // Maintenance
+            get_maintenance_status,
+            run_maintenance_now,
+            get_database_recovery,
+            // Cache Prewarm
+            get_prewarm_status,
+            run_prewarm_now,
+            // Event Bus
+            get_events_since,
+            subscribe_events,
+            unsubscribe_events,
+            // Schedules
+            create_schedule,
+            list_schedules,
+            delete_schedule,
+            run_schedule_now,
+            // Run Queue
+            list_queued_runs,
+            start_queued_run,
+            dismiss_queued_run,
+            // Diagnostics
+            run_doctor,
+            get_recent_logs,
+            set_log_level,
+            generate_test_fixtures,
+            // Error Reports
+            list_recent_errors,
+            record_command_error,
+            clear_error_reports,
+            submit_error_reports,
+            // Long-running Operations
+            list_operations,
+            cancel_operation,
+            // Session Replay
+            start_session_replay,
+            stop_session_replay,
+            // Execution Profiles
+            list_execution_profiles,
+            save_execution_profile,
+            delete_execution_profile,
+            get_agent_execution_profile,
+            set_agent_execution_profile,
+            get_sandbox_capabilities,
+            // Capabilities
+            get_capabilities,
+            get_cli_capabilities,
+            // Trace Export
+            get_telemetry_settings,
+            save_telemetry_settings,
+            test_telemetry_export,
             // Usage & Analytics
             get_usage_stats,
             get_usage_by_date_range,
             get_usage_details,
             get_session_stats,
+            fetch_anthropic_usage,
             // MCP (Model Context Protocol)
             mcp_add,
             mcp_list,
@@ -265,6 +701,7 @@ pub fn run() {
             mcp_remove,
             mcp_add_json,
             mcp_add_from_claude_desktop,
+            start_mcp_import_from_claude_desktop,
             mcp_serve,
             mcp_test_connection,
             mcp_reset_project_choices,
@@ -281,6 +718,9 @@ pub fn run() {
             storage_insert_row,
             storage_execute_sql,
             storage_reset_database,
+            // Storage Cleanup
+            get_storage_usage,
+            cleanup_storage,
             // Slash Commands
             crate::commands::slash_commands::slash_commands_list,
             crate::commands::slash_commands::slash_command_get,
@@ -289,14 +729,53 @@ pub fn run() {
             // Proxy Settings
             get_proxy_settings,
             save_proxy_settings,
+            // Settings
+            get_setting,
+            set_setting,
+            get_all_settings,
+            export_app_config,
+            import_app_config,
+            // Control Server
+            get_control_server_status,
+            set_control_server_enabled,
+            rotate_control_server_token,
+            // Deep Links
+            get_pending_deep_links,
+            confirm_deep_link,
+            dismiss_deep_link,
+            // Pricing
+            get_pricing,
+            refresh_pricing_table,
+            reset_pricing_table,
+            save_pricing_overrides,
             // Models
             crate::commands::models::list_anthropic_models,
             // Skills
             crate::commands::skills::fetch_available_skills,
+            crate::commands::skills::preview_skill,
             crate::commands::skills::install_skill,
+            crate::commands::skills::start_skill_install,
+            crate::commands::skills::list_installed_skills,
+            crate::commands::skills::set_skill_enabled,
+            crate::commands::skills::promote_skill_to_user_scope,
+            crate::commands::skills::get_skill_verification_settings,
+            crate::commands::skills::save_skill_verification_settings,
             crate::commands::skills::fetch_mcp_marketplace,
+            get_marketplace_status,
+            crate::commands::skills::get_catalog_fetch_settings,
+            crate::commands::skills::save_catalog_fetch_settings,
+            crate::commands::catalog::search_catalog,
+            crate::commands::popularity::get_marketplace_popularity,
             crate::commands::skills::fetch_agent_templates,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+            crate::commands::skills::render_agent_template,
+            // Skill Usage
+            get_skill_usage,
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown::shutdown(app);
+            }
+        });
 }
@@ -0,0 +1,206 @@
+// Structured logging to stderr and daily JSON log files
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+/// Levels accepted by `set_level`, most severe first
+pub const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+pub const DEFAULT_LEVEL: &str = "info";
+
+/// Daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Targets that follow the chosen level; everything else (webview, HTTP
+/// stack) stays at warn so debug logging remains readable
+const APP_TARGETS: &[&str] = &["opcode_lib", "opcode", "opcode_web", "audit"];
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+/// Dropping the guard writes out buffered lines, see `flush`
+static FILE_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+/// One line of a log file
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message
+    pub fields: Map<String, JsonValue>,
+}
+
+/// Directory the log files are written to
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(crate::utils::APP_IDENTIFIER).join("logs"))
+}
+
+fn filter_for(level: &str) -> EnvFilter {
+    let directives: Vec<String> = std::iter::once("warn".to_string())
+        .chain(APP_TARGETS.iter().map(|t| format!("{}={}", t, level)))
+        .collect();
+    EnvFilter::new(directives.join(","))
+}
+
+/// Install the global subscriber; files are named `<file_prefix>.<date>.log`
+///
+/// The level can be changed while the app runs; RUST_LOG still wins when set.
+/// Call once, before anything logs. Falls back to stderr only if the log
+/// directory can't be created.
+pub fn init(file_prefix: &str) {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.is_empty() => EnvFilter::new(directives),
+        _ => filter_for(DEFAULT_LEVEL),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let file_layer = log_dir()
+        .and_then(|dir| {
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(file_prefix)
+                .filename_suffix("log")
+                .max_log_files(MAX_LOG_FILES)
+                .build(dir)
+                .map_err(|e| eprintln!("File logging disabled: {}", e))
+                .ok()
+        })
+        .map(|appender| {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            if let Ok(mut slot) = FILE_GUARD.lock() {
+                *slot = Some(guard);
+            }
+            fmt::layer().json().with_ansi(false).with_writer(writer)
+        });
+
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .try_init();
+    if installed.is_ok() {
+        let _ = FILTER.set(handle);
+    }
+}
+
+/// Change the level of the app's own log output
+///
+/// Ignored while RUST_LOG is set, since that was asked for explicitly.
+pub fn set_level(level: &str) -> Result<(), String> {
+    if !LEVELS.contains(&level) {
+        return Err(format!(
+            "Invalid log level '{}'; expected one of {}",
+            level,
+            LEVELS.join(", ")
+        ));
+    }
+    if std::env::var("RUST_LOG").is_ok_and(|v| !v.is_empty()) {
+        return Ok(());
+    }
+    let handle = FILTER.get().ok_or("Logging is not initialized")?;
+    handle
+        .reload(filter_for(level))
+        .map_err(|e| format!("Failed to change log level: {}", e))?;
+    // `log` macros are dropped before reaching the filter above the max level
+    if let Ok(filter) = log::LevelFilter::from_str(level) {
+        log::set_max_level(filter);
+    }
+    Ok(())
+}
+
+/// Write out log lines still buffered for the file and stop file logging
+///
+/// Called on exit; lines logged afterwards only go to stderr.
+pub fn flush() {
+    if let Ok(mut guard) = FILE_GUARD.lock() {
+        guard.take();
+    }
+}
+
+/// Parse a line written by the JSON file layer
+pub fn parse_line(line: &str) -> Option<LogEntry> {
+    let value: JsonValue = serde_json::from_str(line).ok()?;
+    let mut fields = value.get("fields")?.as_object()?.clone();
+    let message = match fields.remove("message") {
+        Some(JsonValue::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    fields.retain(|key, _| !key.starts_with("log."));
+    Some(LogEntry {
+        timestamp: value.get("timestamp")?.as_str()?.to_string(),
+        level: value.get("level")?.as_str()?.to_string(),
+        target: value
+            .get("target")?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        message,
+        fields,
+    })
+}
+
+/// The last `limit` entries at `min_level` or more severe, oldest first
+pub fn recent_entries(min_level: Level, limit: usize) -> Result<Vec<LogEntry>, String> {
+    let dir = log_dir().ok_or("Could not determine the log directory")?;
+    let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    // Oldest first; the app and the web server write separate files
+    files.sort_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok());
+
+    let mut entries = Vec::new();
+    for file in files.iter().rev() {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        let mut matching: Vec<LogEntry> = content
+            .lines()
+            .filter_map(parse_line)
+            .filter(|e| Level::from_str(&e.level).is_ok_and(|level| level <= min_level))
+            .collect();
+        matching.append(&mut entries);
+        entries = matching;
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    // Entries from the app and the web server interleave; order by time
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.split_off(skip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let entry = parse_line(
+            r#"{"timestamp":"2025-01-01T00:00:00.000Z","level":"WARN","fields":{"message":"Failed to prune","log.target":"x","run_id":3},"target":"opcode_lib::commands::run_history"}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.level, "WARN");
+        assert_eq!(entry.message, "Failed to prune");
+        assert_eq!(entry.target, "opcode_lib::commands::run_history");
+        assert_eq!(entry.fields.len(), 1);
+        assert_eq!(entry.fields["run_id"], 3);
+
+        assert!(Level::from_str(&entry.level).unwrap() <= Level::INFO);
+        assert!(parse_line("not json").is_none());
+    }
+}
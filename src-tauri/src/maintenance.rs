@@ -0,0 +1,416 @@
+// Idle-time maintenance
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::checkpoint::storage::CheckpointStorage;
+use crate::commands::agents::AgentDb;
+use crate::commands::file_history::sync_file_history;
+use crate::commands::pricing::refresh_pricing;
+use crate::commands::run_history::{load_prune_policy, prune_run_history};
+use crate::commands::settings::{get_bool, get_text};
+use crate::db_health::{backup_database, integrity_problems};
+use crate::error_reports;
+use crate::event_bus::prune_event_log;
+use crate::file_audit;
+use crate::format::Formatter;
+use crate::process::ProcessRegistryState;
+use crate::storage_usage;
+
+/// Every maintenance task, in the order they run
+pub const MAINTENANCE_TASKS: &[&str] = &[
+    "file_history_index",
+    "run_history_retention",
+    "event_log_retention",
+    "file_audit_retention",
+    "checkpoint_gc",
+    "storage_cleanup",
+    "pricing_refresh",
+    "error_report_upload",
+    "database_optimize",
+    "database_integrity",
+    "database_backup",
+];
+
+/// How often the loop checks whether the machine is idle
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How long the app must be unfocused before it counts as idle
+const IDLE_AFTER: Duration = Duration::from_secs(5 * 60);
+/// Minimum time between two automatic runs
+const RUN_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Load average per CPU above which the machine counts as busy
+const MAX_LOAD_PER_CPU: f64 = 0.5;
+
+/// Outcome of the last run of a task
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub last_run_at: Option<String>,
+    pub last_duration_ms: Option<u64>,
+    /// Short summary of what the task did
+    pub last_result: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Snapshot of the maintenance coordinator
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceStatus {
+    pub running: bool,
+    pub app_focused: bool,
+    /// Why maintenance is currently held back, if it is
+    pub blocked_by: Option<String>,
+    pub last_run_at: Option<String>,
+    pub next_run_after: Option<String>,
+    pub tasks: Vec<TaskStatus>,
+}
+
+/// Shared state of the maintenance coordinator
+pub struct MaintenanceState {
+    focused: AtomicBool,
+    unfocused_since: Mutex<Option<Instant>>,
+    running: AtomicBool,
+    last_run: Mutex<Option<DateTime<Utc>>>,
+    tasks: Mutex<HashMap<String, TaskStatus>>,
+}
+
+impl Default for MaintenanceState {
+    fn default() -> Self {
+        Self {
+            // Assume focused until the window tells us otherwise
+            focused: AtomicBool::new(true),
+            unfocused_since: Mutex::new(None),
+            running: AtomicBool::new(false),
+            last_run: Mutex::new(None),
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl MaintenanceState {
+    /// Record a window focus change
+    pub fn set_focused(&self, focused: bool) {
+        self.focused.store(focused, Ordering::Relaxed);
+        if let Ok(mut since) = self.unfocused_since.lock() {
+            *since = if focused {
+                None
+            } else {
+                since.or(Some(Instant::now()))
+            };
+        }
+    }
+
+    pub fn status(&self, blocked_by: Option<String>) -> MaintenanceStatus {
+        let last_run = self.last_run.lock().ok().and_then(|l| *l);
+        let tasks = self.tasks.lock().map(|t| t.clone()).unwrap_or_default();
+
+        MaintenanceStatus {
+            running: self.running.load(Ordering::Relaxed),
+            app_focused: self.focused.load(Ordering::Relaxed),
+            blocked_by,
+            last_run_at: last_run.map(|t| t.to_rfc3339()),
+            next_run_after: last_run
+                .and_then(|t| chrono::Duration::from_std(RUN_INTERVAL).ok().map(|d| t + d))
+                .map(|t| t.to_rfc3339()),
+            tasks: MAINTENANCE_TASKS
+                .iter()
+                .map(|name| {
+                    tasks.get(*name).cloned().unwrap_or(TaskStatus {
+                        name: name.to_string(),
+                        ..Default::default()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn due(&self) -> bool {
+        match self.last_run.lock().ok().and_then(|l| *l) {
+            Some(last) => (Utc::now() - last)
+                .to_std()
+                .map(|elapsed| elapsed >= RUN_INTERVAL)
+                .unwrap_or(true),
+            None => true,
+        }
+    }
+}
+
+/// Why the machine is not idle right now, or `None` if maintenance may run
+pub async fn idle_blocker(app: &AppHandle) -> Option<String> {
+    let state = app.state::<MaintenanceState>();
+    if state.focused.load(Ordering::Relaxed) {
+        return Some("App is focused".to_string());
+    }
+    let unfocused_for = state
+        .unfocused_since
+        .lock()
+        .ok()
+        .and_then(|s| *s)
+        .map(|since| since.elapsed())
+        .unwrap_or_default();
+    if unfocused_for < IDLE_AFTER {
+        return Some("App was focused recently".to_string());
+    }
+
+    let registry = app.state::<ProcessRegistryState>();
+    let _ = registry.0.cleanup_finished_processes().await;
+    if registry
+        .0
+        .get_running_processes()
+        .map(|p| !p.is_empty())
+        .unwrap_or(false)
+    {
+        return Some("Agents or sessions are running".to_string());
+    }
+
+    if let Some(load) = load_per_cpu() {
+        if load > MAX_LOAD_PER_CPU {
+            return Some(format!("System is busy (load {:.2} per CPU)", load));
+        }
+    }
+    None
+}
+
+/// One-minute load average divided by the number of CPUs
+#[cfg(unix)]
+fn load_per_cpu() -> Option<f64> {
+    let mut load = [0f64; 1];
+    // SAFETY: getloadavg writes at most `nelem` values into the buffer
+    let read = unsafe { libc::getloadavg(load.as_mut_ptr(), 1) };
+    if read < 1 {
+        return None;
+    }
+    let cpus = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    Some(load[0] / cpus as f64)
+}
+
+#[cfg(not(unix))]
+fn load_per_cpu() -> Option<f64> {
+    None
+}
+
+fn run_task(app: &AppHandle, name: &str) -> Result<String, String> {
+    let db = app.state::<AgentDb>();
+    let fmt = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        Formatter::from_settings(&conn)
+    };
+    match name {
+        "file_history_index" => {
+            let stats = sync_file_history(&db)?;
+            Ok(format!(
+                "{} sessions indexed, {} removed",
+                fmt.count(stats.sessions_indexed as i64),
+                fmt.count(stats.sessions_removed as i64)
+            ))
+        }
+        "run_history_retention" => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            let removed =
+                prune_run_history(&conn, &load_prune_policy(&conn)).map_err(|e| e.to_string())?;
+            Ok(format!("{} runs pruned", fmt.count(removed as i64)))
+        }
+        "event_log_retention" => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            let removed = prune_event_log(&conn).map_err(|e| e.to_string())?;
+            Ok(format!("{} events pruned", fmt.count(removed as i64)))
+        }
+        "file_audit_retention" => {
+            let policy = {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                file_audit::load_retention(&conn)
+            };
+            let removed = file_audit::with_log(|log| file_audit::prune(log, &policy))?;
+            Ok(format!(
+                "{} file audit entries pruned",
+                fmt.count(removed as i64)
+            ))
+        }
+        "checkpoint_gc" => {
+            let claude_dir = crate::utils::get_claude_dir()?;
+            let storage = CheckpointStorage::new(claude_dir.clone());
+            let mut removed = 0;
+            let projects = std::fs::read_dir(claude_dir.join("projects"))
+                .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+            for project in projects.flatten() {
+                let timelines = match std::fs::read_dir(project.path().join(".timelines")) {
+                    Ok(timelines) => timelines,
+                    Err(_) => continue,
+                };
+                let project_id = project.file_name().to_string_lossy().to_string();
+                for session in timelines.flatten().filter(|s| s.path().is_dir()) {
+                    let session_id = session.file_name().to_string_lossy().to_string();
+                    removed += storage
+                        .garbage_collect_content(&project_id, &session_id)
+                        .unwrap_or(0);
+                }
+            }
+            Ok(format!(
+                "{} unreferenced checkpoint files removed",
+                fmt.count(removed as i64)
+            ))
+        }
+        "storage_cleanup" => {
+            let options = {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                storage_usage::auto_cleanup_options(&conn)
+            };
+            let Some(options) = options else {
+                return Ok("Automatic storage cleanup is off".to_string());
+            };
+            let app_dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+            let report = storage_usage::cleanup(&db, &app_dir, &options)?;
+            Ok(format!(
+                "{} items removed, {} freed",
+                fmt.count(report.items_removed as i64),
+                fmt.bytes(report.bytes_freed)
+            ))
+        }
+        "pricing_refresh" => {
+            let configured = {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                get_text(&conn, "pricing_table_url").is_some()
+            };
+            if !configured {
+                return Ok("No pricing table URL configured".to_string());
+            }
+            let table =
+                tauri::async_runtime::block_on(refresh_pricing(&db)).map_err(String::from)?;
+            Ok(format!(
+                "Pricing table version {} with {} models",
+                table.version,
+                table.models.len()
+            ))
+        }
+        "error_report_upload" => {
+            let enabled = {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                get_bool(&conn, "error_reporting_enabled")
+            };
+            if !enabled {
+                return Ok("Error reporting is off".to_string());
+            }
+            let sent = tauri::async_runtime::block_on(error_reports::submit_pending(&db))
+                .map_err(String::from)?;
+            Ok(format!("{} error reports sent", fmt.count(sent as i64)))
+        }
+        "database_optimize" => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA optimize;")
+                .map_err(|e| e.to_string())?;
+            Ok("Query planner statistics refreshed".to_string())
+        }
+        "database_integrity" => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            let problems = integrity_problems(&conn).map_err(|e| e.to_string())?;
+            if problems.is_empty() {
+                Ok("No problems found".to_string())
+            } else {
+                // Recovery needs the database closed, so it happens at the next launch
+                Err(format!(
+                    "{} problems found; the latest backup is restored at the next launch: {}",
+                    fmt.count(problems.len() as i64),
+                    problems.join("; ")
+                ))
+            }
+        }
+        "database_backup" => {
+            let app_dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            let path = backup_database(&conn, &app_dir)?;
+            Ok(format!("Backed up to {}", path.display()))
+        }
+        _ => Err(format!("Unknown maintenance task: {}", name)),
+    }
+}
+
+/// Run the given tasks (all when `None`) and record their outcome
+///
+/// Returns `false` without doing anything if a run is already in progress.
+pub async fn run_now(app: &AppHandle, tasks: Option<Vec<String>>) -> Result<bool, String> {
+    let tasks: Vec<String> = match tasks {
+        Some(tasks) => {
+            if let Some(unknown) = tasks
+                .iter()
+                .find(|t| !MAINTENANCE_TASKS.contains(&t.as_str()))
+            {
+                return Err(format!("Unknown maintenance task: {}", unknown));
+            }
+            tasks
+        }
+        None => MAINTENANCE_TASKS.iter().map(|t| t.to_string()).collect(),
+    };
+
+    let state = app.state::<MaintenanceState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let state = handle.state::<MaintenanceState>();
+        for name in tasks {
+            let started = Instant::now();
+            let result = run_task(&handle, &name);
+            match &result {
+                Ok(summary) => log::info!("Maintenance task {}: {}", name, summary),
+                Err(e) => log::warn!("Maintenance task {} failed: {}", name, e),
+            }
+
+            let status = TaskStatus {
+                name: name.clone(),
+                last_run_at: Some(Utc::now().to_rfc3339()),
+                last_duration_ms: Some(started.elapsed().as_millis() as u64),
+                last_error: result.as_ref().err().cloned(),
+                last_result: result.ok(),
+            };
+            if let Ok(mut tasks) = state.tasks.lock() {
+                tasks.insert(name, status);
+            }
+        }
+    })
+    .await;
+
+    if let Ok(mut last_run) = state.last_run.lock() {
+        *last_run = Some(Utc::now());
+    }
+    state.running.store(false, Ordering::SeqCst);
+    result.map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Start the background loop that runs maintenance when the machine is idle
+///
+/// Housekeeping only runs while the app is unfocused and nothing is running,
+/// so it never competes with the user.
+pub fn spawn_maintenance_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            if !app.state::<MaintenanceState>().due() {
+                continue;
+            }
+            if let Some(reason) = idle_blocker(&app).await {
+                log::debug!("Maintenance deferred: {}", reason);
+                continue;
+            }
+
+            log::info!("Machine is idle, running maintenance");
+            if let Err(e) = run_now(&app, None).await {
+                log::warn!("Maintenance run failed: {}", e);
+            }
+        }
+    });
+}
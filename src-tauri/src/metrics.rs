@@ -0,0 +1,236 @@
+// Prometheus metrics derived from the run history
+use rusqlite::{Connection, Result as SqliteResult};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::utils::APP_IDENTIFIER;
+
+/// Content type for the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Aggregated run statistics read from the run history
+#[derive(Debug, Default, Clone)]
+pub struct RunMetricsSnapshot {
+    /// (run_type, status, count) triples
+    pub runs_by_status: Vec<(String, String, i64)>,
+    /// Number of runs currently in progress
+    pub runs_in_progress: i64,
+    /// Total cost in USD across all recorded runs
+    pub cost_usd: f64,
+    /// Total input tokens across all recorded runs
+    pub input_tokens: i64,
+    /// Total output tokens across all recorded runs
+    pub output_tokens: i64,
+}
+
+impl RunMetricsSnapshot {
+    /// Total number of failed runs across all run types
+    pub fn failures(&self) -> i64 {
+        self.runs_by_status
+            .iter()
+            .filter(|(_, status, _)| status == "failed")
+            .map(|(_, _, count)| count)
+            .sum()
+    }
+}
+
+/// Path to the agents database used by the desktop app
+///
+/// Mirrors Tauri's `app_data_dir()` so the web server can read the same data without an AppHandle.
+pub fn app_database_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER).join("agents.db"))
+}
+
+/// Collect run statistics from the run history table
+pub fn collect_run_metrics(conn: &Connection) -> SqliteResult<RunMetricsSnapshot> {
+    let mut stmt = conn.prepare(
+        "SELECT run_type, status, COUNT(*) FROM run_history GROUP BY run_type, status ORDER BY run_type, status",
+    )?;
+    let runs_by_status = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let (cost_usd, input_tokens, output_tokens) = conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0.0), COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0) FROM run_history",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let runs_in_progress = runs_by_status
+        .iter()
+        .filter(|(_, status, _)| status == "running")
+        .map(|(_, _, count)| count)
+        .sum();
+
+    Ok(RunMetricsSnapshot {
+        runs_by_status,
+        runs_in_progress,
+        cost_usd,
+        input_tokens,
+        output_tokens,
+    })
+}
+
+/// Builder for the Prometheus text exposition format
+#[derive(Default)]
+pub struct PrometheusWriter {
+    output: String,
+}
+
+impl PrometheusWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a metric family with a single unlabelled sample
+    pub fn metric(&mut self, name: &str, kind: &str, help: &str, value: f64) {
+        self.header(name, kind, help);
+        let _ = writeln!(self.output, "{} {}", name, value);
+    }
+
+    /// Write a metric family with labelled samples
+    pub fn labelled(
+        &mut self,
+        name: &str,
+        kind: &str,
+        help: &str,
+        samples: &[(Vec<(&str, &str)>, f64)],
+    ) {
+        self.header(name, kind, help);
+        for (labels, value) in samples {
+            let labels = labels
+                .iter()
+                .map(|(key, val)| format!("{}=\"{}\"", key, escape_label_value(val)))
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = writeln!(self.output, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.output, "# HELP {} {}", name, help);
+        let _ = writeln!(self.output, "# TYPE {} {}", name, kind);
+    }
+
+    pub fn finish(self) -> String {
+        self.output
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Write the run history metrics families
+///
+/// Retention prunes old runs, so the totals can go down and are gauges.
+pub fn write_run_metrics(writer: &mut PrometheusWriter, snapshot: &RunMetricsSnapshot) {
+    let samples: Vec<(Vec<(&str, &str)>, f64)> = snapshot
+        .runs_by_status
+        .iter()
+        .map(|(run_type, status, count)| {
+            (
+                vec![("type", run_type.as_str()), ("status", status.as_str())],
+                *count as f64,
+            )
+        })
+        .collect();
+
+    writer.labelled(
+        "opcode_runs",
+        "gauge",
+        "Runs in the retained run history by type and status",
+        &samples,
+    );
+    writer.metric(
+        "opcode_run_failures",
+        "gauge",
+        "Runs in the retained run history that finished with a failure",
+        snapshot.failures() as f64,
+    );
+    writer.metric(
+        "opcode_queue_depth",
+        "gauge",
+        "Runs currently in progress",
+        snapshot.runs_in_progress as f64,
+    );
+    writer.metric(
+        "opcode_cost_usd",
+        "gauge",
+        "Estimated cost in USD across the retained run history",
+        snapshot.cost_usd,
+    );
+    writer.labelled(
+        "opcode_tokens",
+        "gauge",
+        "Tokens used across the retained run history",
+        &[
+            (vec![("direction", "input")], snapshot.input_tokens as f64),
+            (vec![("direction", "output")], snapshot.output_tokens as f64),
+        ],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::run_history::{
+        init_run_history, record_run_finished, record_run_started, NewRun,
+    };
+    use crate::process::RunUsage;
+
+    #[test]
+    fn test_run_metrics_are_gauges() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_run_history(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO run_history (run_type, project_path, prompt, model, status, input_tokens, output_tokens, cost_usd)
+             VALUES ('agent', '/p', 'a', 'sonnet', 'completed', 10, 5, 0.25),
+                    ('agent', '/p', 'b', 'sonnet', 'failed', 1, 1, 0.5),
+                    ('session', '/p', 'c', 'opus', 'running', NULL, NULL, NULL);",
+        )
+        .unwrap();
+
+        let snapshot = collect_run_metrics(&conn).unwrap();
+        assert_eq!(snapshot.failures(), 1);
+        assert_eq!(snapshot.runs_in_progress, 1);
+
+        let mut writer = PrometheusWriter::new();
+        write_run_metrics(&mut writer, &snapshot);
+        let text = writer.finish();
+        assert!(text.contains("# TYPE opcode_runs gauge"));
+        assert!(text.contains("opcode_runs{type=\"agent\",status=\"failed\"} 1"));
+        assert!(text.contains("opcode_cost_usd 0.75"));
+        assert!(text.contains("opcode_tokens{direction=\"input\"} 11"));
+        assert!(!text.contains("counter"));
+    }
+
+    #[test]
+    fn test_non_zero_exit_counts_as_failure() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_run_history(&conn).unwrap();
+        let run = NewRun {
+            run_type: "agent",
+            project_path: "/p".to_string(),
+            agent_id: Some(1),
+            agent_name: Some("agent".to_string()),
+            agent_run_id: Some(1),
+            prompt: "task".to_string(),
+            model: "sonnet".to_string(),
+            env_overrides: None,
+            additional_dirs: Vec::new(),
+        };
+        let id = record_run_started(&conn, &run).unwrap();
+        record_run_finished(&conn, id, "failed", Some(1), &RunUsage::default()).unwrap();
+
+        let snapshot = collect_run_metrics(&conn).unwrap();
+        assert_eq!(snapshot.failures(), 1);
+        let mut writer = PrometheusWriter::new();
+        write_run_metrics(&mut writer, &snapshot);
+        assert!(writer.finish().contains("opcode_run_failures 1"));
+    }
+}
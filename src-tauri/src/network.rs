@@ -0,0 +1,414 @@
+// Typed network errors, retries and per-host rate limits for outbound API calls
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// A failed network request, classified for the frontend
+///
+/// Serializes as `{ kind, message, ... }` so the frontend can tell a rate limit
+/// from an outage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NetworkError {
+    /// The server asked us to slow down (HTTP 429, or GitHub's 403 rate limit)
+    RateLimited {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+    /// The host could not be reached or the request timed out
+    Offline { message: String },
+    /// The requested resource does not exist
+    NotFound { message: String },
+    /// Missing or rejected credentials
+    Auth { message: String },
+    /// Any other non-success HTTP status
+    Http { status: u16, message: String },
+    /// Downloaded content failed a checksum or signature check
+    Integrity { message: String },
+    /// The operation was cancelled by the user
+    Cancelled { message: String },
+    /// The response could not be read or parsed, or the request was invalid
+    Other { message: String },
+}
+
+impl NetworkError {
+    /// Classify a non-success response status
+    pub fn from_status(status: StatusCode, retry_after_secs: Option<u64>, body: &str) -> Self {
+        let message = if body.trim().is_empty() {
+            format!("HTTP {}", status)
+        } else {
+            format!("HTTP {}: {}", status, body.trim())
+        };
+
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => NetworkError::RateLimited {
+                message,
+                retry_after_secs,
+            },
+            // GitHub reports an exhausted rate limit as 403
+            StatusCode::FORBIDDEN if body.to_lowercase().contains("rate limit") => {
+                NetworkError::RateLimited {
+                    message,
+                    retry_after_secs,
+                }
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => NetworkError::Auth { message },
+            StatusCode::NOT_FOUND => NetworkError::NotFound { message },
+            _ => NetworkError::Http {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        NetworkError::Cancelled {
+            message: "Cancelled".to_string(),
+        }
+    }
+
+    /// Whether the request is worth retrying
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NetworkError::RateLimited { .. } | NetworkError::Offline { .. } => true,
+            NetworkError::Http { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            NetworkError::RateLimited { message, .. }
+            | NetworkError::Offline { message }
+            | NetworkError::NotFound { message }
+            | NetworkError::Auth { message }
+            | NetworkError::Http { message, .. }
+            | NetworkError::Integrity { message }
+            | NetworkError::Cancelled { message }
+            | NetworkError::Other { message } => message,
+        }
+    }
+
+    /// Prefix the message with what was being attempted
+    pub fn context(mut self, context: &str) -> Self {
+        match &mut self {
+            NetworkError::RateLimited { message, .. }
+            | NetworkError::Offline { message }
+            | NetworkError::NotFound { message }
+            | NetworkError::Auth { message }
+            | NetworkError::Http { message, .. }
+            | NetworkError::Integrity { message }
+            | NetworkError::Cancelled { message }
+            | NetworkError::Other { message } => *message = format!("{}: {}", context, message),
+        }
+        self
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(error: reqwest::Error) -> Self {
+        let message = error.to_string();
+        if error.is_connect() || error.is_timeout() {
+            NetworkError::Offline { message }
+        } else if let Some(status) = error.status() {
+            NetworkError::from_status(status, None, "")
+        } else {
+            NetworkError::Other { message }
+        }
+    }
+}
+
+impl From<String> for NetworkError {
+    fn from(message: String) -> Self {
+        NetworkError::Other { message }
+    }
+}
+
+impl From<NetworkError> for String {
+    fn from(error: NetworkError) -> Self {
+        error.to_string()
+    }
+}
+
+/// How often and how patiently to retry a request
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before the given retry (1-based), with up to 50% random jitter
+    ///
+    /// A server-provided Retry-After takes precedence when it is longer, but is
+    /// still capped at `max_delay`.
+    pub fn delay_for(&self, retry: u32, retry_after_secs: Option<u64>) -> Duration {
+        let exponent = retry.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        let jitter = backoff.mul_f64(jitter_fraction() * 0.5);
+        let delay = backoff.saturating_sub(jitter);
+
+        match retry_after_secs {
+            Some(secs) => delay.max(Duration::from_secs(secs)).min(self.max_delay),
+            None => delay,
+        }
+    }
+}
+
+/// Pseudo-random value in [0, 1) for backoff jitter
+fn jitter_fraction() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 10_000.0
+}
+
+/// Token buckets of outbound requests, one per host
+///
+/// A bucket holds up to `burst` tokens and refills at `per_minute`. Taking a
+/// token from an empty bucket reserves the next one, so concurrent callers
+/// queue up in order rather than all waking at once.
+#[derive(Default)]
+pub struct HostRateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl HostRateLimiter {
+    /// Take a token for `host`; returns how long to wait before sending
+    pub fn reserve(&self, host: &str, per_minute: u32, burst: u32, now: Instant) -> Duration {
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Duration::ZERO;
+        };
+        let per_sec = per_minute.max(1) as f64 / 60.0;
+        let burst = burst.max(1) as f64;
+        let bucket = buckets.entry(host.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(burst) - 1.0;
+        bucket.updated = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / per_sec)
+        }
+    }
+}
+
+fn host_limiter() -> &'static HostRateLimiter {
+    static LIMITER: OnceLock<HostRateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(HostRateLimiter::default)
+}
+
+/// Wait until the host of a request may be sent another one
+///
+/// Rate and burst come from the `http_host_*` settings, so a burst of catalog
+/// fetches is spread out instead of tripping GitHub's secondary rate limits.
+async fn wait_for_host(host: &str) {
+    let limits = crate::http_client::limits();
+    let delay = host_limiter().reserve(
+        host,
+        limits.host_requests_per_minute,
+        limits.host_burst,
+        Instant::now(),
+    );
+    if !delay.is_zero() {
+        log::debug!("Rate limiting requests to {}, waiting {:?}", host, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Send a request with the default retry policy, failing on non-success statuses
+///
+/// `build` is called once per attempt since a `RequestBuilder` can't be reused.
+pub async fn send_with_retry<F>(build: F) -> Result<Response, NetworkError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_with_policy(&RetryPolicy::default(), build).await
+}
+
+/// Send a request, retrying transient failures according to `policy`
+pub async fn send_with_policy<F>(policy: &RetryPolicy, build: F) -> Result<Response, NetworkError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let (client, request) = build().build_split();
+        let result = match request {
+            Ok(request) => {
+                if let Some(host) = request.url().host_str() {
+                    wait_for_host(host).await;
+                }
+                client.execute(request).await
+            }
+            Err(e) => Err(e),
+        };
+        let error = match result {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => error_from_response(response).await,
+            Err(e) => NetworkError::from(e),
+        };
+
+        if attempt >= policy.max_attempts || !error.is_transient() {
+            return Err(error);
+        }
+
+        let retry_after = match &error {
+            NetworkError::RateLimited {
+                retry_after_secs, ..
+            } => *retry_after_secs,
+            _ => None,
+        };
+        let delay = policy.delay_for(attempt, retry_after);
+        log::warn!(
+            "Request failed (attempt {}/{}), retrying in {:?}: {}",
+            attempt,
+            policy.max_attempts,
+            delay,
+            error
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Run `work` until it finishes or `token` is cancelled, whichever comes first
+///
+/// The work is dropped on cancellation, which aborts an in-flight request and
+/// any pending retry backoff.
+pub async fn until_cancelled<T, F>(token: &CancellationToken, work: F) -> Result<T, NetworkError>
+where
+    F: Future<Output = Result<T, NetworkError>>,
+{
+    tokio::select! {
+        _ = token.cancelled() => Err(NetworkError::cancelled()),
+        result = work => result,
+    }
+}
+
+async fn error_from_response(response: Response) -> NetworkError {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let body = response.text().await.unwrap_or_default();
+    NetworkError::from_status(status, retry_after, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        assert!(matches!(
+            NetworkError::from_status(StatusCode::TOO_MANY_REQUESTS, Some(3), ""),
+            NetworkError::RateLimited {
+                retry_after_secs: Some(3),
+                ..
+            }
+        ));
+        assert!(matches!(
+            NetworkError::from_status(StatusCode::FORBIDDEN, None, "API rate limit exceeded"),
+            NetworkError::RateLimited { .. }
+        ));
+        assert!(matches!(
+            NetworkError::from_status(StatusCode::UNAUTHORIZED, None, ""),
+            NetworkError::Auth { .. }
+        ));
+        assert!(matches!(
+            NetworkError::from_status(StatusCode::NOT_FOUND, None, ""),
+            NetworkError::NotFound { .. }
+        ));
+
+        let server = NetworkError::from_status(StatusCode::BAD_GATEWAY, None, "");
+        assert!(server.is_transient());
+        assert!(!NetworkError::from_status(StatusCode::BAD_REQUEST, None, "").is_transient());
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+
+        let first = policy.delay_for(1, None);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+        let third = policy.delay_for(3, None);
+        assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+        assert!(policy.delay_for(10, None) <= Duration::from_secs(1));
+        assert_eq!(policy.delay_for(1, Some(30)), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_host_rate_limiter() {
+        let limiter = HostRateLimiter::default();
+        let start = Instant::now();
+
+        // A burst of two goes out at once, then one every second at 60/min
+        assert_eq!(
+            limiter.reserve("api.github.com", 60, 2, start),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve("api.github.com", 60, 2, start),
+            Duration::ZERO
+        );
+        let third = limiter.reserve("api.github.com", 60, 2, start);
+        let fourth = limiter.reserve("api.github.com", 60, 2, start);
+        assert!((third.as_secs_f64() - 1.0).abs() < 1e-6);
+        assert!((fourth.as_secs_f64() - 2.0).abs() < 1e-6);
+
+        // Other hosts have their own bucket
+        assert_eq!(limiter.reserve("example.com", 60, 2, start), Duration::ZERO);
+
+        // The bucket refills over time, up to the burst
+        let later = start + Duration::from_secs(60);
+        assert_eq!(
+            limiter.reserve("api.github.com", 60, 2, later),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve("api.github.com", 60, 2, later),
+            Duration::ZERO
+        );
+        assert!(!limiter.reserve("api.github.com", 60, 2, later).is_zero());
+    }
+}
@@ -0,0 +1,190 @@
+// Desktop notifications for runs
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::agents::AgentDb;
+use crate::commands::settings as store;
+use crate::format::Formatter;
+
+/// Which run events show a desktop notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub run_completed: bool,
+    pub run_failed: bool,
+    /// A tool call was blocked because it needs approval
+    pub permission_request: bool,
+}
+
+/// Load the notification settings from the settings store
+pub fn load_notification_settings(conn: &Connection) -> NotificationSettings {
+    NotificationSettings {
+        run_completed: store::get_bool(conn, "notify_run_completed"),
+        run_failed: store::get_bool(conn, "notify_run_failed"),
+        permission_request: store::get_bool(conn, "notify_permission_request"),
+    }
+}
+
+/// The tool a stream-json line reports as waiting for permission, if any
+///
+/// Claude Code answers a tool call it isn't allowed to make with an error
+/// tool result asking for permission.
+pub fn permission_request(line: &str) -> Option<String> {
+    let msg: JsonValue = serde_json::from_str(line).ok()?;
+    if msg["type"] != "user" {
+        return None;
+    }
+    msg["message"]["content"]
+        .as_array()?
+        .iter()
+        .filter(|c| c["type"] == "tool_result" && c["is_error"] == true)
+        .find_map(|c| {
+            let text = match &c["content"] {
+                JsonValue::String(text) => text.clone(),
+                content => content.to_string(),
+            };
+            let (_, rest) = text.split_once("requested permissions to use ")?;
+            let tool = rest
+                .split(|ch: char| ch == ',' || ch.is_whitespace())
+                .next()
+                .unwrap_or_default();
+            Some(tool.to_string())
+        })
+}
+
+/// Sends the notifications for one run
+///
+/// Notifications are only shown while the main window is in the background.
+pub struct RunNotifier {
+    app: AppHandle,
+    /// Shown as the notification title, e.g. the agent's name
+    label: String,
+    /// Tools already notified about, so each is only reported once per run
+    permission_tools: Mutex<HashSet<String>>,
+}
+
+impl RunNotifier {
+    pub fn new(app: AppHandle, label: impl Into<String>) -> Self {
+        Self {
+            app,
+            label: label.into(),
+            permission_tools: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn settings(&self) -> Option<NotificationSettings> {
+        let db = self.app.state::<AgentDb>();
+        let conn = db.0.lock().ok()?;
+        Some(load_notification_settings(&conn))
+    }
+
+    fn show(&self, body: &str) {
+        // The notification plugin isn't loaded in safe mode
+        if crate::safe_mode::is_active() {
+            return;
+        }
+        // The user is already looking at the app
+        let focused = self
+            .app
+            .get_webview_window("main")
+            .and_then(|w| w.is_focused().ok())
+            .unwrap_or(false);
+        if focused {
+            return;
+        }
+        if let Err(e) = self
+            .app
+            .notification()
+            .builder()
+            .title(&self.label)
+            .body(body)
+            .show()
+        {
+            log::warn!("Failed to show notification: {}", e);
+        }
+    }
+
+    /// Check a line of run output for a permission request
+    pub fn observe_line(&self, line: &str) {
+        let Some(tool) = permission_request(line) else {
+            return;
+        };
+        let first = self
+            .permission_tools
+            .lock()
+            .map(|mut tools| tools.insert(tool.clone()))
+            .unwrap_or(false);
+        if first && self.settings().is_some_and(|s| s.permission_request) {
+            self.show(&format!("Waiting for permission to use {}", tool));
+        }
+    }
+
+    /// Report the end of a run
+    ///
+    /// The status recorded in the run history wins over `status`, so runs the
+    /// user cancelled don't notify.
+    pub fn finished(&self, history_id: Option<i64>, status: &str) {
+        let (settings, fmt, recorded) = {
+            let db = self.app.state::<AgentDb>();
+            let Ok(conn) = db.0.lock() else {
+                return;
+            };
+            let recorded: Option<(String, Option<i64>, Option<f64>)> = history_id.and_then(|id| {
+                conn.query_row(
+                    "SELECT status, CAST((julianday(ended_at) - julianday(started_at)) * 86400 AS INTEGER), cost_usd
+                     FROM run_history WHERE id = ?1",
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()
+                .ok()
+                .flatten()
+            });
+            (
+                load_notification_settings(&conn),
+                Formatter::from_settings(&conn),
+                recorded,
+            )
+        };
+
+        let (status, secs, cost) = match &recorded {
+            Some((status, secs, cost)) => (status.as_str(), *secs, *cost),
+            None => (status, None, None),
+        };
+        let mut body = match status {
+            "completed" if settings.run_completed => "Run completed".to_string(),
+            "failed" if settings.run_failed => "Run failed".to_string(),
+            _ => return,
+        };
+        if let Some(secs) = secs.filter(|s| *s >= 0) {
+            body.push_str(&format!(
+                " after {}",
+                fmt.duration(Duration::from_secs(secs as u64))
+            ));
+        }
+        if let Some(cost) = cost.filter(|c| *c > 0.0) {
+            body.push_str(&format!(" ({})", fmt.cost(cost)));
+        }
+        self.show(&body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_request() {
+        let line = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","is_error":true,"content":"Claude requested permissions to use Bash, but you haven't granted it yet."}]}}"#;
+        assert_eq!(permission_request(line).as_deref(), Some("Bash"));
+
+        let ok = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"done"}]}}"#;
+        assert_eq!(permission_request(ok), None);
+        assert_eq!(permission_request("not json"), None);
+    }
+}
@@ -0,0 +1,432 @@
+// OpenTelemetry trace export for run lifecycles
+use log::{debug, warn};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// OTLP span kind for internal operations
+const SPAN_KIND_INTERNAL: i64 = 1;
+/// OTLP status codes
+const STATUS_CODE_OK: i64 = 1;
+const STATUS_CODE_ERROR: i64 = 2;
+
+/// Collector connection details
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Base collector URL, e.g. `http://localhost:4318`
+    pub endpoint: String,
+    /// Extra headers sent with each export (e.g. authentication)
+    pub headers: HashMap<String, String>,
+    /// Value of the `service.name` resource attribute
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    /// Full URL of the traces endpoint
+    pub fn traces_url(&self) -> String {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        if endpoint.ends_with("/v1/traces") {
+            endpoint.to_string()
+        } else {
+            format!("{}/v1/traces", endpoint)
+        }
+    }
+}
+
+/// Attribute value attached to a span
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+impl AttributeValue {
+    fn to_otlp(&self) -> JsonValue {
+        match self {
+            AttributeValue::String(s) => json!({ "stringValue": s }),
+            // OTLP JSON encodes 64-bit integers as strings
+            AttributeValue::Int(i) => json!({ "intValue": i.to_string() }),
+            AttributeValue::Bool(b) => json!({ "boolValue": b }),
+        }
+    }
+}
+
+/// A span that has been started but not yet recorded
+#[derive(Debug, Clone)]
+pub struct PendingSpan {
+    span_id: String,
+    name: String,
+    start_ns: u64,
+    attributes: Vec<(String, AttributeValue)>,
+}
+
+impl PendingSpan {
+    pub fn attribute(mut self, key: &str, value: impl Into<AttributeValue>) -> Self {
+        self.attributes.push((key.to_string(), value.into()));
+        self
+    }
+}
+
+/// A completed span
+#[derive(Debug, Clone)]
+pub struct SpanRecord {
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub start_ns: u64,
+    pub end_ns: u64,
+    pub attributes: Vec<(String, AttributeValue)>,
+    pub error: Option<String>,
+}
+
+impl SpanRecord {
+    fn to_otlp(&self, trace_id: &str) -> JsonValue {
+        let status = match &self.error {
+            Some(message) => json!({ "code": STATUS_CODE_ERROR, "message": message }),
+            None => json!({ "code": STATUS_CODE_OK }),
+        };
+
+        let mut span = json!({
+            "traceId": trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": SPAN_KIND_INTERNAL,
+            "startTimeUnixNano": self.start_ns.to_string(),
+            "endTimeUnixNano": self.end_ns.to_string(),
+            "attributes": encode_attributes(&self.attributes),
+            "status": status,
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = json!(parent);
+        }
+        span
+    }
+}
+
+/// Collects the spans of a single run and exports them when the run finishes
+///
+/// The root span covers the whole run; queue, spawn, validation and tool spans
+/// are recorded as its children.
+#[derive(Debug)]
+pub struct RunTracer {
+    config: OtlpConfig,
+    trace_id: String,
+    root: Mutex<PendingSpan>,
+    spans: Mutex<Vec<SpanRecord>>,
+    open_tools: Mutex<HashMap<String, PendingSpan>>,
+}
+
+impl RunTracer {
+    pub fn new(config: OtlpConfig, root_name: &str) -> Arc<Self> {
+        Self::starting_at(config, root_name, now_ns())
+    }
+
+    /// Create a tracer whose root span started at an earlier point in time
+    pub fn starting_at(config: OtlpConfig, root_name: &str, start_ns: u64) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            trace_id: new_trace_id(),
+            root: Mutex::new(PendingSpan {
+                span_id: new_span_id(),
+                name: root_name.to_string(),
+                start_ns,
+                attributes: Vec::new(),
+            }),
+            spans: Mutex::new(Vec::new()),
+            open_tools: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// Attach an attribute to the root span
+    pub fn set_attribute(&self, key: &str, value: impl Into<AttributeValue>) {
+        if let Ok(mut root) = self.root.lock() {
+            root.attributes.retain(|(k, _)| k != key);
+            root.attributes.push((key.to_string(), value.into()));
+        }
+    }
+
+    /// Start a child span of the run
+    pub fn start_span(&self, name: &str) -> PendingSpan {
+        PendingSpan {
+            span_id: new_span_id(),
+            name: name.to_string(),
+            start_ns: now_ns(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Finish a child span, marking it failed if an error is given
+    pub fn end_span(&self, span: PendingSpan, error: Option<String>) {
+        self.record(span, now_ns(), error);
+    }
+
+    /// Record a child span with explicit start and end times
+    pub fn record_span(&self, name: &str, start_ns: u64, end_ns: u64) {
+        let span = PendingSpan {
+            span_id: new_span_id(),
+            name: name.to_string(),
+            start_ns,
+            attributes: Vec::new(),
+        };
+        self.record(span, end_ns, None);
+    }
+
+    fn record(&self, span: PendingSpan, end_ns: u64, error: Option<String>) {
+        let parent_span_id = self.root.lock().ok().map(|root| root.span_id.clone());
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.push(SpanRecord {
+                span_id: span.span_id,
+                parent_span_id,
+                name: span.name,
+                start_ns: span.start_ns,
+                end_ns: end_ns.max(span.start_ns),
+                attributes: span.attributes,
+                error,
+            });
+        }
+    }
+
+    /// Inspect a line of Claude's stream-json output and open or close tool spans
+    pub fn observe_output_line(&self, line: &str) {
+        let Ok(msg) = serde_json::from_str::<JsonValue>(line) else {
+            return;
+        };
+        let Some(content) = msg
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+        else {
+            return;
+        };
+
+        for block in content {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") => {
+                    let Some(id) = block.get("id").and_then(|i| i.as_str()) else {
+                        continue;
+                    };
+                    let tool = block
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or("unknown");
+                    let span = self
+                        .start_span(&format!("tool.{}", tool))
+                        .attribute("tool.name", tool)
+                        .attribute("tool.use_id", id);
+                    if let Ok(mut open) = self.open_tools.lock() {
+                        open.insert(id.to_string(), span);
+                    }
+                }
+                Some("tool_result") => {
+                    let Some(id) = block.get("tool_use_id").and_then(|i| i.as_str()) else {
+                        continue;
+                    };
+                    let span = self.open_tools.lock().ok().and_then(|mut o| o.remove(id));
+                    if let Some(span) = span {
+                        let is_error = block
+                            .get("is_error")
+                            .and_then(|e| e.as_bool())
+                            .unwrap_or(false);
+                        let error = is_error.then(|| "Tool returned an error".to_string());
+                        self.end_span(span, error);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Close the run and build the OTLP export payload
+    ///
+    /// Tool spans that never received a result are closed as failed.
+    pub fn finish_payload(&self, error: Option<String>) -> JsonValue {
+        let unfinished: Vec<PendingSpan> = self
+            .open_tools
+            .lock()
+            .map(|mut open| open.drain().map(|(_, span)| span).collect())
+            .unwrap_or_default();
+        for span in unfinished {
+            self.end_span(span, Some("Tool did not complete".to_string()));
+        }
+
+        let mut spans: Vec<JsonValue> = Vec::new();
+        if let Ok(root) = self.root.lock() {
+            let record = SpanRecord {
+                span_id: root.span_id.clone(),
+                parent_span_id: None,
+                name: root.name.clone(),
+                start_ns: root.start_ns,
+                end_ns: now_ns().max(root.start_ns),
+                attributes: root.attributes.clone(),
+                error,
+            };
+            spans.push(record.to_otlp(&self.trace_id));
+        }
+        if let Ok(children) = self.spans.lock() {
+            spans.extend(children.iter().map(|s| s.to_otlp(&self.trace_id)));
+        }
+
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": encode_attributes(&[(
+                        "service.name".to_string(),
+                        AttributeValue::String(self.config.service_name.clone()),
+                    )]),
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "opcode", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Close the run and export its spans in the background
+    pub fn finish(&self, error: Option<String>) {
+        let payload = self.finish_payload(error);
+        let config = self.config.clone();
+        let trace_id = self.trace_id.clone();
+        tauri::async_runtime::spawn(async move {
+            match export_traces(&config, &payload).await {
+                Ok(()) => debug!("Exported trace {} to {}", trace_id, config.traces_url()),
+                Err(e) => warn!("Failed to export trace {}: {}", trace_id, e),
+            }
+        });
+    }
+}
+
+/// Post an OTLP JSON payload to `<endpoint>/v1/traces`
+///
+/// Uses the OTLP/HTTP JSON encoding, so any collector can receive it without
+/// a protobuf/gRPC stack.
+pub async fn export_traces(config: &OtlpConfig, payload: &JsonValue) -> Result<(), String> {
+    let client = crate::http_client::client_builder()?
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut request = client.post(config.traces_url()).json(payload);
+    for (key, value) in &config.headers {
+        request = request.header(key, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach collector: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Collector returned {}: {}", status, body));
+    }
+
+    Ok(())
+}
+
+/// Current time in nanoseconds since the Unix epoch
+pub fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn encode_attributes(attributes: &[(String, AttributeValue)]) -> Vec<JsonValue> {
+    attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value.to_otlp() }))
+        .collect()
+}
+
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> OtlpConfig {
+        OtlpConfig {
+            endpoint: "http://localhost:4318/".to_string(),
+            headers: HashMap::new(),
+            service_name: "opcode-test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_traces_url() {
+        let mut config = test_config();
+        assert_eq!(config.traces_url(), "http://localhost:4318/v1/traces");
+        config.endpoint = "http://collector/v1/traces".to_string();
+        assert_eq!(config.traces_url(), "http://collector/v1/traces");
+    }
+
+    #[test]
+    fn test_tool_spans_from_stream_output() {
+        let tracer = RunTracer::new(test_config(), "agent.run");
+        tracer.set_attribute("run.id", 7i64);
+        tracer.observe_output_line(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash"},{"type":"tool_use","id":"t2","name":"Read"}]}}"#,
+        );
+        tracer.observe_output_line(
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","is_error":true}]}}"#,
+        );
+
+        let payload = tracer.finish_payload(None);
+        let spans = payload["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 3);
+
+        let root = &spans[0];
+        assert_eq!(root["name"], "agent.run");
+        assert!(root.get("parentSpanId").is_none());
+        assert_eq!(root["attributes"][0]["value"]["intValue"], "7");
+
+        let bash = spans.iter().find(|s| s["name"] == "tool.Bash").unwrap();
+        assert_eq!(bash["parentSpanId"], root["spanId"]);
+        assert_eq!(bash["status"]["code"], STATUS_CODE_ERROR);
+
+        // The Read tool never completed
+        let read = spans.iter().find(|s| s["name"] == "tool.Read").unwrap();
+        assert_eq!(read["status"]["code"], STATUS_CODE_ERROR);
+        assert_eq!(read["traceId"], tracer.trace_id());
+    }
+}
@@ -0,0 +1,176 @@
+// Validation for paths and names that come from the frontend
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Longest file name accepted by common filesystems
+const MAX_NAME_LEN: usize = 255;
+
+/// Device names Windows refuses as file names, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check that `project_path` is an existing directory usable as a project root
+///
+/// Returns the canonical path. The filesystem root is rejected.
+pub fn validate_project_root(project_path: &str) -> Result<PathBuf, String> {
+    let trimmed = project_path.trim();
+    if trimmed.is_empty() {
+        return Err("Project path is empty".to_string());
+    }
+    let path = Path::new(trimmed);
+    if !path.is_absolute() {
+        return Err(format!("Project path must be absolute: {}", trimmed));
+    }
+
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Project directory not found: {} ({})", trimmed, e))?;
+    if !canonical.is_dir() {
+        return Err(format!("Project path is not a directory: {}", trimmed));
+    }
+    if canonical.parent().is_none() {
+        return Err("The filesystem root cannot be used as a project".to_string());
+    }
+    Ok(canonical)
+}
+
+/// Check that `name` is a single, safe path component
+///
+/// Rejects separators, `.`/`..`, hidden names, control characters and names
+/// Windows reserves for devices.
+pub fn validate_name<'a>(name: &'a str, what: &str) -> Result<&'a str, String> {
+    let invalid = |reason: &str| Err(format!("Invalid {} '{}': {}", what, name, reason));
+
+    if name.is_empty() {
+        return invalid("must not be empty");
+    }
+    if name.len() > MAX_NAME_LEN {
+        return invalid("too long");
+    }
+    if name.contains(['/', '\\']) {
+        return invalid("must not contain path separators");
+    }
+    if name.starts_with('.') {
+        return invalid("must not start with '.'");
+    }
+    if name
+        .chars()
+        .any(|c| c.is_control() || matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+    {
+        return invalid("contains characters that are not allowed in file names");
+    }
+    if name.ends_with([' ', '.']) {
+        return invalid("must not end with a space or '.'");
+    }
+    let stem = name.split('.').next().unwrap_or(name);
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return invalid("is a reserved name");
+    }
+    Ok(name)
+}
+
+/// Resolve `relative` under `root`, refusing anything that would land outside it
+///
+/// `root` should come from `validate_project_root`. Parent components and
+/// absolute paths are rejected outright. Each existing component is checked
+/// for symlinks, whose targets must resolve inside the root; dangling links
+/// are rejected since writing through them could create files anywhere.
+pub fn resolve_within(root: &Path, relative: impl AsRef<Path>) -> Result<PathBuf, String> {
+    let relative = relative.as_ref();
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+    let outside = || {
+        format!(
+            "Path '{}' resolves outside {}",
+            relative.display(),
+            root.display()
+        )
+    };
+
+    let mut resolved = root.clone();
+    let mut exists = true;
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => continue,
+            _ => {
+                return Err(format!(
+                    "Path '{}' must stay inside {}",
+                    relative.display(),
+                    root.display()
+                ))
+            }
+        }
+        if !exists {
+            continue;
+        }
+        match fs::symlink_metadata(&resolved) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = resolved.canonicalize().map_err(|_| outside())?;
+                if !target.starts_with(&root) {
+                    return Err(outside());
+                }
+            }
+            Ok(_) => {}
+            Err(_) => exists = false,
+        }
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("pdf-tools", "skill name").is_ok());
+        for bad in [
+            "",
+            "..",
+            "../../etc",
+            "a/b",
+            "a\\b",
+            ".hidden",
+            "CON",
+            "nul.txt",
+            "x\0",
+        ] {
+            assert!(validate_name(bad, "skill name").is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_resolve_within_rejects_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = validate_project_root(dir.path().to_str().unwrap()).unwrap();
+
+        let inside = resolve_within(&root, ".claude/skills/pdf").unwrap();
+        assert!(inside.starts_with(&root));
+        assert!(resolve_within(&root, "../outside").is_err());
+        assert!(resolve_within(&root, "/etc/passwd").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/tmp", root.join("link")).unwrap();
+            assert!(resolve_within(&root, "link/file").is_err());
+
+            // A dangling link could be followed to create files outside
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path().join("new"), root.join("dangling")).unwrap();
+            assert!(resolve_within(&root, "dangling").is_err());
+            assert!(resolve_within(&root, "dangling/file").is_err());
+
+            // Links that stay inside the root are fine
+            std::fs::create_dir(root.join("real")).unwrap();
+            std::os::unix::fs::symlink(root.join("real"), root.join("alias")).unwrap();
+            assert!(resolve_within(&root, "alias/file").is_ok());
+        }
+    }
+}
@@ -0,0 +1,333 @@
+// Cache prewarm shortly before the user's workday starts
+use chrono::{
+    DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc,
+};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agents::AgentDb;
+use crate::commands::mcp::mcp_list;
+use crate::commands::models::list_anthropic_models;
+use crate::commands::settings::{get_bool, get_integer, get_text};
+use crate::commands::skills::{fetch_available_skills, fetch_mcp_marketplace};
+
+/// Everything a prewarm refreshes, in the order it runs
+pub const PREWARM_TASKS: &[&str] = &["models", "skills_catalog", "mcp_catalog", "mcp_health"];
+
+/// How often the loop checks whether the prewarm window has opened
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Days of session history the start time is learned from
+const LEARN_FROM_DAYS: i64 = 28;
+/// Days with activity needed before a learned start time is trusted
+const MIN_ACTIVE_DAYS: usize = 5;
+/// Activity before this hour counts toward the previous day
+const DAY_STARTS_AT_HOUR: i64 = 4;
+
+/// Outcome of one prewarm task
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmResult {
+    pub name: String,
+    pub ok: bool,
+    /// What was refreshed, or why it failed
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Snapshot of the prewarm schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmStatus {
+    pub enabled: bool,
+    /// `HH:MM`, local time
+    pub start_time: Option<String>,
+    /// `manual` or `learned`; `None` when there is no start time yet
+    pub start_time_source: Option<String>,
+    pub lead_minutes: i64,
+    pub next_prewarm_at: Option<String>,
+    pub last_prewarm_at: Option<String>,
+    pub results: Vec<PrewarmResult>,
+}
+
+/// Shared state of the prewarm loop
+#[derive(Default)]
+pub struct PrewarmState {
+    running: AtomicBool,
+    /// Start time the last scheduled prewarm was for, so each runs once
+    last_target: Mutex<Option<NaiveDateTime>>,
+    last_run: Mutex<Option<DateTime<Utc>>>,
+    results: Mutex<Vec<PrewarmResult>>,
+}
+
+/// The usual start of the day: the median of each day's earliest activity
+///
+/// Takes local times; `None` with fewer than `MIN_ACTIVE_DAYS` active days.
+pub fn learn_start_time(activity: &[NaiveDateTime]) -> Option<NaiveTime> {
+    let mut earliest: BTreeMap<NaiveDate, NaiveDateTime> = BTreeMap::new();
+    for &at in activity {
+        let day = (at - ChronoDuration::hours(DAY_STARTS_AT_HOUR)).date();
+        let first = earliest.entry(day).or_insert(at);
+        if at < *first {
+            *first = at;
+        }
+    }
+    if earliest.len() < MIN_ACTIVE_DAYS {
+        return None;
+    }
+    // Minutes past the day boundary, so 01:00 sorts after 23:00
+    let mut offsets: Vec<i64> = earliest
+        .iter()
+        .map(|(day, at)| (*at - day.and_hms_opt(0, 0, 0).unwrap()).num_minutes())
+        .collect();
+    offsets.sort_unstable();
+    let median = offsets[offsets.len() / 2];
+    NaiveTime::from_hms_opt(((median / 60) % 24) as u32, (median % 60) as u32, 0)
+}
+
+/// The next time `start` comes around, today's if it hasn't passed yet
+fn upcoming_start(now: NaiveDateTime, start: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(start);
+    if now < today {
+        today
+    } else {
+        today + ChronoDuration::days(1)
+    }
+}
+
+fn parse_timestamp(raw: &str) -> Option<NaiveDateTime> {
+    let utc = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(raw)
+                .ok()
+                .map(|t| t.naive_utc())
+        })?;
+    Some(
+        Utc.from_utc_datetime(&utc)
+            .with_timezone(&Local)
+            .naive_local(),
+    )
+}
+
+/// Local start times of the sessions of the last `LEARN_FROM_DAYS` days
+fn recent_activity(conn: &Connection) -> SqliteResult<Vec<NaiveDateTime>> {
+    let since = (Utc::now() - ChronoDuration::days(LEARN_FROM_DAYS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let mut stmt = conn.prepare(
+        "SELECT started_at FROM run_history WHERE run_type = 'session' AND started_at >= ?1",
+    )?;
+    let started = stmt
+        .query_map(params![since], |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(started.iter().filter_map(|s| parse_timestamp(s)).collect())
+}
+
+/// The configured start time, or the learned one, with where it came from
+fn start_time(conn: &Connection) -> Option<(NaiveTime, &'static str)> {
+    if let Some(raw) = get_text(conn, "prewarm_start_time").filter(|s| !s.trim().is_empty()) {
+        match NaiveTime::parse_from_str(raw.trim(), "%H:%M") {
+            Ok(time) => return Some((time, "manual")),
+            Err(_) => log::warn!("Ignoring prewarm_start_time '{}': expected HH:MM", raw),
+        }
+    }
+    match recent_activity(conn) {
+        Ok(activity) => learn_start_time(&activity).map(|time| (time, "learned")),
+        Err(e) => {
+            log::warn!("Failed to read session history for prewarm: {}", e);
+            None
+        }
+    }
+}
+
+struct Schedule {
+    enabled: bool,
+    start: Option<(NaiveTime, &'static str)>,
+    lead: ChronoDuration,
+}
+
+fn load_schedule(app: &AppHandle) -> Result<Schedule, String> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(Schedule {
+        enabled: get_bool(&conn, "prewarm_enabled"),
+        start: start_time(&conn),
+        lead: ChronoDuration::minutes(get_integer(&conn, "prewarm_lead_minutes").unwrap_or(15)),
+    })
+}
+
+fn to_local_rfc3339(at: NaiveDateTime) -> Option<String> {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|t| t.to_rfc3339())
+}
+
+/// The schedule as it stands and the outcome of the last prewarm
+pub fn status(app: &AppHandle) -> Result<PrewarmStatus, String> {
+    let schedule = load_schedule(app)?;
+    let state = app.state::<PrewarmState>();
+    let now = Local::now().naive_local();
+    Ok(PrewarmStatus {
+        enabled: schedule.enabled,
+        start_time: schedule
+            .start
+            .map(|(time, _)| format!("{:02}:{:02}", time.hour(), time.minute())),
+        start_time_source: schedule.start.map(|(_, source)| source.to_string()),
+        lead_minutes: schedule.lead.num_minutes(),
+        next_prewarm_at: schedule
+            .start
+            .filter(|_| schedule.enabled)
+            .and_then(|(time, _)| to_local_rfc3339(upcoming_start(now, time) - schedule.lead)),
+        last_prewarm_at: state
+            .last_run
+            .lock()
+            .ok()
+            .and_then(|l| *l)
+            .map(|t| t.to_rfc3339()),
+        results: state.results.lock().map(|r| r.clone()).unwrap_or_default(),
+    })
+}
+
+async fn run_task(app: &AppHandle, name: &str) -> Result<String, String> {
+    match name {
+        "models" => {
+            let models = list_anthropic_models(app.state(), None, Some(true))
+                .await
+                .map_err(String::from)?;
+            match models.error {
+                Some(e) => Err(e),
+                None => Ok(format!("{} models", models.data.len())),
+            }
+        }
+        "skills_catalog" => {
+            let skills = fetch_available_skills(app.state())
+                .await
+                .map_err(String::from)?;
+            Ok(format!("{} skills", skills.len()))
+        }
+        "mcp_catalog" => {
+            let servers = fetch_mcp_marketplace(app.state()).await?;
+            Ok(format!("{} MCP servers", servers.len()))
+        }
+        "mcp_health" => {
+            let servers = mcp_list(app.clone()).await?;
+            let connected = servers.iter().filter(|s| s.status.running).count();
+            Ok(format!(
+                "{} of {} MCP servers connected",
+                connected,
+                servers.len()
+            ))
+        }
+        _ => Err(format!("Unknown prewarm task: {}", name)),
+    }
+}
+
+/// Refresh every cache now and record the outcome
+///
+/// Returns `false` without doing anything if a prewarm is already running.
+pub async fn run_now(app: &AppHandle) -> bool {
+    let state = app.state::<PrewarmState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    let mut results = Vec::with_capacity(PREWARM_TASKS.len());
+    for name in PREWARM_TASKS {
+        let started = Instant::now();
+        let outcome = run_task(app, name).await;
+        if let Err(e) = &outcome {
+            log::warn!("Prewarm of {} failed: {}", name, e);
+        }
+        results.push(PrewarmResult {
+            name: name.to_string(),
+            ok: outcome.is_ok(),
+            detail: outcome.unwrap_or_else(|e| e),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    if let Ok(mut last_run) = state.last_run.lock() {
+        *last_run = Some(Utc::now());
+    }
+    if let Ok(mut last) = state.results.lock() {
+        *last = results;
+    }
+    state.running.store(false, Ordering::SeqCst);
+    true
+}
+
+/// Prewarm if the window before the next start time has opened and this
+/// start hasn't been prewarmed yet
+async fn prewarm_if_due(app: &AppHandle) -> Result<(), String> {
+    let schedule = load_schedule(app)?;
+    let Some((time, _)) = schedule.start.filter(|_| schedule.enabled) else {
+        return Ok(());
+    };
+    let now = Local::now().naive_local();
+    let target = upcoming_start(now, time);
+    if now < target - schedule.lead {
+        return Ok(());
+    }
+    {
+        let state = app.state::<PrewarmState>();
+        let mut last_target = state.last_target.lock().map_err(|e| e.to_string())?;
+        if *last_target == Some(target) {
+            return Ok(());
+        }
+        *last_target = Some(target);
+    }
+    log::info!("Prewarming caches ahead of {}", target);
+    run_now(app).await;
+    Ok(())
+}
+
+/// Start the background loop that prewarms caches before the workday
+///
+/// The start time is `prewarm_start_time` when set, otherwise it is learned
+/// from session history; too little history means no prewarm. If the app is
+/// opened after the start time the day is skipped, since the caches fill on
+/// first use anyway.
+pub fn spawn_prewarm_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = prewarm_if_due(&app).await {
+                log::warn!("Prewarm check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_learn_start_time() {
+        let mut activity = vec![at(2, 8, 50), at(2, 14, 0), at(3, 9, 10), at(4, 9, 0)];
+        assert_eq!(learn_start_time(&activity), None);
+
+        // The 01:30 session belongs to the night of the 4th, not the morning of the 5th
+        activity.extend([at(5, 1, 30), at(5, 9, 20), at(6, 8, 40), at(9, 9, 0)]);
+        assert_eq!(
+            learn_start_time(&activity),
+            NaiveTime::from_hms_opt(9, 0, 0)
+        );
+
+        let start = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(upcoming_start(at(10, 8, 30), start), at(10, 9, 0));
+        assert_eq!(upcoming_start(at(10, 9, 0), start), at(11, 9, 0));
+    }
+}
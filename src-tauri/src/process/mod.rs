@@ -1,3 +1,6 @@
+pub mod output_buffer;
+pub mod output_stream;
 pub mod registry;
 
+pub use output_buffer::*;
 pub use registry::*;
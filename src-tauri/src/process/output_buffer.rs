@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Default number of output lines retained per run
+pub const DEFAULT_BUFFER_LINES: usize = 5000;
+
+/// A single line of process output with its sequence number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLine {
+    pub seq: u64,
+    pub line: String,
+}
+
+/// Output retained for a run since a given sequence number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveOutputChunk {
+    pub run_id: i64,
+    /// Lines with a sequence number greater than or equal to the requested one
+    pub lines: Vec<OutputLine>,
+    /// Sequence number to pass on the next call to continue where this chunk ended
+    pub next_seq: u64,
+    /// Number of requested lines that were already evicted from the buffer
+    pub missed: u64,
+}
+
+/// Token usage and cost reported in a run's stream-json output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunUsage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+}
+
+impl RunUsage {
+    /// Add the usage of one output line, if it reports any
+    pub fn observe_line(&mut self, line: &str) {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+            return;
+        };
+        let usage = json
+            .get("usage")
+            .or_else(|| json.get("message").and_then(|m| m.get("usage")));
+        if let Some(usage) = usage {
+            self.input_tokens += usage["input_tokens"].as_i64().unwrap_or(0);
+            self.output_tokens += usage["output_tokens"].as_i64().unwrap_or(0);
+        }
+        self.cost_usd += json["cost"].as_f64().unwrap_or(0.0);
+    }
+
+    /// Usage of every line in `output`
+    pub fn from_jsonl(output: &str) -> Self {
+        let mut usage = Self::default();
+        for line in output.lines() {
+            usage.observe_line(line);
+        }
+        usage
+    }
+}
+
+/// Bounded ring buffer of output lines for a single run
+///
+/// Every appended line gets a monotonically increasing sequence number so clients
+/// can resume after a reload without re-reading the whole output. Usage is added
+/// up as lines arrive, so it still covers lines the buffer has dropped.
+#[derive(Debug, Clone)]
+pub struct OutputRingBuffer {
+    lines: VecDeque<OutputLine>,
+    capacity: usize,
+    next_seq: u64,
+    usage: RunUsage,
+}
+
+impl OutputRingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_seq: 0,
+            usage: RunUsage::default(),
+        }
+    }
+
+    /// Append a line, evicting the oldest one if the buffer is full, and return its sequence number
+    pub fn push(&mut self, line: &str) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.usage.observe_line(line);
+
+        if self.lines.len() >= self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(OutputLine {
+            seq,
+            line: line.to_string(),
+        });
+        seq
+    }
+
+    /// Sequence number of the oldest retained line
+    pub fn first_seq(&self) -> u64 {
+        self.lines.front().map(|l| l.seq).unwrap_or(self.next_seq)
+    }
+
+    /// Usage reported by every line pushed so far
+    pub fn usage(&self) -> RunUsage {
+        self.usage
+    }
+
+    /// Sequence number that will be assigned to the next line
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Lines with a sequence number at or after `since_seq`
+    pub fn since(&self, run_id: i64, since_seq: u64) -> LiveOutputChunk {
+        let first_seq = self.first_seq();
+        let missed = first_seq.saturating_sub(since_seq);
+
+        let lines = self
+            .lines
+            .iter()
+            .filter(|l| l.seq >= since_seq)
+            .cloned()
+            .collect();
+
+        LiveOutputChunk {
+            run_id,
+            lines,
+            next_seq: self.next_seq,
+            missed,
+        }
+    }
+
+    /// All retained lines joined with newlines (each line is newline-terminated)
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            text.push_str(&line.line);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Default for OutputRingBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUFFER_LINES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut buffer = OutputRingBuffer::new(2);
+        assert_eq!(buffer.push("a"), 0);
+        assert_eq!(buffer.push("b"), 1);
+        assert_eq!(buffer.push("c"), 2);
+
+        assert_eq!(buffer.first_seq(), 1);
+        assert_eq!(buffer.to_text(), "b\nc\n");
+    }
+
+    #[test]
+    fn test_usage_includes_evicted_lines() {
+        let mut buffer = OutputRingBuffer::new(1);
+        buffer.push(
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+        );
+        buffer.push(
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":3,"output_tokens":2}}}"#,
+        );
+        buffer.push(r#"{"type":"result","cost":0.5}"#);
+
+        let usage = buffer.usage();
+        assert_eq!(usage.input_tokens, 13);
+        assert_eq!(usage.output_tokens, 7);
+        assert_eq!(usage.cost_usd, 0.5);
+        assert_eq!(RunUsage::from_jsonl(&buffer.to_text()).input_tokens, 0);
+    }
+
+    #[test]
+    fn test_since_reports_missed_lines() {
+        let mut buffer = OutputRingBuffer::new(3);
+        for line in ["a", "b", "c", "d", "e"] {
+            buffer.push(line);
+        }
+
+        let chunk = buffer.since(7, 0);
+        assert_eq!(chunk.missed, 2);
+        assert_eq!(chunk.lines.len(), 3);
+        assert_eq!(chunk.next_seq, 5);
+
+        let chunk = buffer.since(7, 4);
+        assert_eq!(chunk.missed, 0);
+        assert_eq!(chunk.lines.len(), 1);
+        assert_eq!(chunk.lines[0].line, "e");
+
+        let chunk = buffer.since(7, 5);
+        assert!(chunk.lines.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use log::warn;
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+/// Batch of output lines emitted to the frontend in a single event
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputBatch {
+    pub lines: Vec<String>,
+    /// Sequence number of the first line in the batch, if it was stored in the registry
+    pub first_seq: Option<u64>,
+    /// Sequence number of the last line in the batch, if it was stored in the registry
+    pub last_seq: Option<u64>,
+}
+
+/// Settings controlling how output batches are flushed
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    pub interval_ms: u64,
+    pub max_lines: usize,
+}
+
+struct PendingLine {
+    event: String,
+    seq: Option<u64>,
+    line: String,
+}
+
+/// Coalesces output lines into batched events
+///
+/// Lines are queued on a bounded channel. When the queue is full `push` waits
+/// for room, so the process reader slows down instead of losing lines.
+pub struct OutputBatcher {
+    sender: mpsc::Sender<PendingLine>,
+    task: JoinHandle<()>,
+}
+
+impl OutputBatcher {
+    /// Batch lines for the frontend; events not scoped to an attached session
+    /// go to `window`, if set (see `window_sessions::emit_to_window`)
+    pub fn spawn(app: AppHandle, window: Option<String>, config: BatchConfig) -> Self {
+        Self::spawn_with(config, move |event, batch| {
+            crate::window_sessions::emit_to_window(&app, window.as_deref(), event, &batch)
+        })
+    }
+
+    /// Batch lines and hand each batch to `emit` instead of the frontend
+    fn spawn_with<F>(config: BatchConfig, emit: F) -> Self
+    where
+        F: Fn(&str, OutputBatch) + Send + 'static,
+    {
+        let max_lines = config.max_lines.max(1);
+        let (sender, mut receiver) = mpsc::channel::<PendingLine>(max_lines * 4);
+
+        let task = tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_millis(config.interval_ms.max(1)));
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            let mut event = String::new();
+            let mut batch: Vec<PendingLine> = Vec::new();
+
+            loop {
+                tokio::select! {
+                    pending = receiver.recv() => {
+                        match pending {
+                            Some(pending) => {
+                                // Flush early when the target event changes (e.g. once the session ID is known)
+                                if !batch.is_empty() && pending.event != event {
+                                    flush(&emit, &event, &mut batch);
+                                }
+                                event = pending.event.clone();
+                                batch.push(pending);
+                                if batch.len() >= max_lines {
+                                    flush(&emit, &event, &mut batch);
+                                }
+                            }
+                            None => break,
+                        }
+                    }
+                    _ = ticker.tick() => {
+                        if !batch.is_empty() {
+                            flush(&emit, &event, &mut batch);
+                        }
+                    }
+                }
+            }
+
+            // Final flush
+            if !batch.is_empty() {
+                flush(&emit, &event, &mut batch);
+            }
+        });
+
+        Self { sender, task }
+    }
+
+    /// Queue a line for the given batch event, waiting while the queue is full
+    pub async fn push(&self, event: &str, seq: Option<u64>, line: &str) {
+        let pending = PendingLine {
+            event: event.to_string(),
+            seq,
+            line: line.to_string(),
+        };
+        if self.sender.send(pending).await.is_err() {
+            warn!("Output batcher stopped; line not sent to the frontend");
+        }
+    }
+
+    /// Flush remaining lines and wait for the batching task to finish
+    pub async fn finish(self) {
+        drop(self.sender);
+        if let Err(e) = self.task.await {
+            warn!("Output batcher task failed: {}", e);
+        }
+    }
+}
+
+fn flush<F: Fn(&str, OutputBatch)>(emit: &F, event: &str, batch: &mut Vec<PendingLine>) {
+    let first_seq = batch.first().and_then(|l| l.seq);
+    let last_seq = batch.last().and_then(|l| l.seq);
+    let payload = OutputBatch {
+        lines: batch.drain(..).map(|l| l.line).collect(),
+        first_seq,
+        last_seq,
+    };
+    emit(event, payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_full_queue_waits_instead_of_dropping() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = batches.clone();
+        let batcher = OutputBatcher::spawn_with(
+            BatchConfig {
+                interval_ms: 1000,
+                max_lines: 2,
+            },
+            move |event, batch| {
+                // A slow window: the queue of 8 lines fills up while this runs
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                sink.lock().unwrap().push((event.to_string(), batch));
+            },
+        );
+        for i in 0..100u64 {
+            batcher.push("out", Some(i), &i.to_string()).await;
+        }
+        batcher.finish().await;
+
+        let batches = batches.lock().unwrap();
+        let lines: Vec<String> = batches
+            .iter()
+            .flat_map(|(_, b)| b.lines.iter().cloned())
+            .collect();
+        assert_eq!(lines, (0..100).map(|i| i.to_string()).collect::<Vec<_>>());
+        assert!(batches
+            .iter()
+            .all(|(event, b)| event == "out" && b.lines.len() <= 2));
+        assert_eq!(batches[0].1.first_seq, Some(0));
+        assert_eq!(batches.last().unwrap().1.last_seq, Some(99));
+    }
+}
@@ -1,9 +1,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::process::Child;
 
+use super::output_buffer::{LiveOutputChunk, OutputRingBuffer, RunUsage, DEFAULT_BUFFER_LINES};
+
 /// Type of process being tracked
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessType {
@@ -28,13 +31,14 @@ pub struct ProcessInfo {
 pub struct ProcessHandle {
     pub info: ProcessInfo,
     pub child: Arc<Mutex<Option<Child>>>,
-    pub live_output: Arc<Mutex<String>>,
+    pub live_output: Arc<Mutex<OutputRingBuffer>>,
 }
 
 /// Registry for tracking active agent processes
 pub struct ProcessRegistry {
     processes: Arc<Mutex<HashMap<i64, ProcessHandle>>>, // run_id -> ProcessHandle
     next_id: Arc<Mutex<i64>>, // Auto-incrementing ID for non-agent processes
+    buffer_lines: Arc<AtomicUsize>, // Live output lines retained per process
 }
 
 impl ProcessRegistry {
@@ -42,9 +46,21 @@ impl ProcessRegistry {
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
             next_id: Arc::new(Mutex::new(1000000)), // Start at high number to avoid conflicts
+            buffer_lines: Arc::new(AtomicUsize::new(DEFAULT_BUFFER_LINES)),
         }
     }
 
+    /// Set how many live output lines are retained for newly registered processes
+    pub fn set_output_buffer_lines(&self, lines: usize) {
+        self.buffer_lines.store(lines.max(1), Ordering::Relaxed);
+    }
+
+    fn new_output_buffer(&self) -> Arc<Mutex<OutputRingBuffer>> {
+        Arc::new(Mutex::new(OutputRingBuffer::new(
+            self.buffer_lines.load(Ordering::Relaxed),
+        )))
+    }
+
     /// Generate a unique ID for non-agent processes
     pub fn generate_id(&self) -> Result<i64, String> {
         let mut next_id = self.next_id.lock().map_err(|e| e.to_string())?;
@@ -111,7 +127,7 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(None)), // No tokio::process::Child handle for sidecar
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: self.new_output_buffer(),
         };
 
         processes.insert(run_id, process_handle);
@@ -145,7 +161,7 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(None)), // No child handle for Claude sessions
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: self.new_output_buffer(),
         };
 
         processes.insert(run_id, process_handle);
@@ -164,7 +180,7 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(Some(child))),
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: self.new_output_buffer(),
         };
 
         processes.insert(run_id, process_handle);
@@ -446,8 +462,7 @@ impl ProcessRegistry {
             if let Some(ref mut child) = child_guard.as_mut() {
                 match child.try_wait() {
                     Ok(Some(_)) => {
-                        // Process has exited
-                        *child_guard = None;
+                        // Process has exited; the handle is kept so its exit status can still be read
                         Ok(false)
                     }
                     Ok(None) => {
@@ -468,15 +483,27 @@ impl ProcessRegistry {
         }
     }
 
+    /// The child handle of a registered process, so it can be waited on even
+    /// after the process is removed from the registry
+    pub fn child_handle(&self, run_id: i64) -> Option<Arc<Mutex<Option<Child>>>> {
+        let processes = self.processes.lock().ok()?;
+        Some(processes.get(&run_id)?.child.clone())
+    }
+
+    /// Wait for a registered process to exit and return its exit status
+    pub async fn wait_for_exit(&self, run_id: i64) -> Option<std::process::ExitStatus> {
+        let child = self.child_handle(run_id)?;
+        wait_for_child(&child).await
+    }
+
     /// Append to live output for a process
-    pub fn append_live_output(&self, run_id: i64, output: &str) -> Result<(), String> {
+    pub fn append_live_output(&self, run_id: i64, output: &str) -> Result<Option<u64>, String> {
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         if let Some(handle) = processes.get(&run_id) {
             let mut live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
-            live_output.push_str(output);
-            live_output.push('\n');
+            return Ok(Some(live_output.push(output)));
         }
-        Ok(())
+        Ok(None)
     }
 
     /// Get live output for a process
@@ -484,14 +511,39 @@ impl ProcessRegistry {
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         if let Some(handle) = processes.get(&run_id) {
             let live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
-            Ok(live_output.clone())
+            Ok(live_output.to_text())
         } else {
             Ok(String::new())
         }
     }
 
+    /// Usage reported in a process's whole output, including lines no longer buffered
+    pub fn get_run_usage(&self, run_id: i64) -> Result<RunUsage, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        if let Some(handle) = processes.get(&run_id) {
+            let live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
+            Ok(live_output.usage())
+        } else {
+            Ok(RunUsage::default())
+        }
+    }
+
+    /// Get buffered live output for a process starting at a sequence number
+    pub fn get_live_output_since(
+        &self,
+        run_id: i64,
+        since_seq: u64,
+    ) -> Result<Option<LiveOutputChunk>, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        if let Some(handle) = processes.get(&run_id) {
+            let live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
+            Ok(Some(live_output.since(run_id, since_seq)))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Cleanup finished processes
-    #[allow(dead_code)]
     pub async fn cleanup_finished_processes(&self) -> Result<Vec<i64>, String> {
         let mut finished_runs = Vec::new();
         let processes_lock = self.processes.clone();
@@ -535,3 +587,60 @@ impl Default for ProcessRegistryState {
         Self(Arc::new(ProcessRegistry::new()))
     }
 }
+
+/// Wait for a child to exit and return its exit status
+///
+/// Returns `None` when the child handle was released first, e.g. because the
+/// process was killed.
+pub async fn wait_for_child(child: &Mutex<Option<Child>>) -> Option<std::process::ExitStatus> {
+    loop {
+        {
+            let mut child_guard = child.lock().ok()?;
+            match child_guard.as_mut()?.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) => {}
+                Err(_) => return None,
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_for_exit_reports_status() {
+        let registry = ProcessRegistry::new();
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "exit 3"])
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap_or(0);
+        registry
+            .register_process(
+                1,
+                1,
+                "agent".to_string(),
+                pid,
+                "/tmp".to_string(),
+                "task".to_string(),
+                "sonnet".to_string(),
+                child,
+            )
+            .unwrap();
+
+        let status = registry.wait_for_exit(1).await.unwrap();
+        assert_eq!(status.code(), Some(3));
+        // Cleanup doesn't lose the exit status of a process it removes
+        let child = registry.child_handle(1).unwrap();
+        assert_eq!(
+            registry.cleanup_finished_processes().await.unwrap(),
+            vec![1]
+        );
+        assert_eq!(wait_for_child(&child).await.unwrap().code(), Some(3));
+        assert!(registry.wait_for_exit(2).await.is_none());
+    }
+}
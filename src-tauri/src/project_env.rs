@@ -0,0 +1,232 @@
+// Environment variables set on every Claude process started in a project
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agents::AgentDb;
+use crate::run_env::{validate_env_overrides, EnvOverrides};
+
+/// Keychain service secret values are stored under
+const KEYCHAIN_SERVICE: &str = "opcode.project-env";
+const MAX_VARIABLES: usize = 100;
+
+/// A variable set for a project
+///
+/// Plain values live in the `project_env` table; secret values go to the OS
+/// keychain and the table only records that the variable exists.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectEnvVar {
+    pub key: String,
+    /// `None` for secrets, whose value stays in the keychain
+    pub value: Option<String>,
+    pub secret: bool,
+    pub updated_at: String,
+}
+
+/// Create the project_env table
+pub fn init_project_env(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_env (
+            project_path TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT,
+            secret INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (project_path, key)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn keychain_entry(project_path: &str, key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{}#{}", project_path, key))
+        .map_err(|e| format!("Failed to open the keychain: {}", e))
+}
+
+fn read_secret(project_path: &str, key: &str) -> Result<String, String> {
+    keychain_entry(project_path, key)?
+        .get_password()
+        .map_err(|e| format!("Failed to read {} from the keychain: {}", key, e))
+}
+
+fn delete_secret(project_path: &str, key: &str) -> Result<(), String> {
+    match keychain_entry(project_path, key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove {} from the keychain: {}", key, e)),
+    }
+}
+
+/// Variables of a project, by name
+pub fn list_vars(conn: &Connection, project_path: &str) -> SqliteResult<Vec<ProjectEnvVar>> {
+    let mut stmt = conn.prepare(
+        "SELECT key, value, secret, updated_at FROM project_env WHERE project_path = ?1 ORDER BY key",
+    )?;
+    let vars = stmt
+        .query_map(params![project_path], |row| {
+            Ok(ProjectEnvVar {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                secret: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(vars)
+}
+
+fn is_secret(conn: &Connection, project_path: &str, key: &str) -> SqliteResult<Option<bool>> {
+    conn.query_row(
+        "SELECT secret FROM project_env WHERE project_path = ?1 AND key = ?2",
+        params![project_path, key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Set a variable; secret values are written to the keychain
+///
+/// Names are checked like run overrides, so a project can't swap the account
+/// or endpoint Claude uses.
+pub fn set_var(
+    conn: &Connection,
+    project_path: &str,
+    key: &str,
+    value: &str,
+    secret: bool,
+) -> Result<ProjectEnvVar, String> {
+    validate_env_overrides(&EnvOverrides::from([(key.to_string(), value.to_string())]))?;
+    let existing = is_secret(conn, project_path, key).map_err(|e| e.to_string())?;
+    if existing.is_none() {
+        let count: usize = conn
+            .query_row(
+                "SELECT COUNT(*) FROM project_env WHERE project_path = ?1",
+                params![project_path],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if count >= MAX_VARIABLES {
+            return Err(format!(
+                "A project can have at most {} environment variables",
+                MAX_VARIABLES
+            ));
+        }
+    }
+
+    if secret {
+        keychain_entry(project_path, key)?
+            .set_password(value)
+            .map_err(|e| format!("Failed to save {} to the keychain: {}", key, e))?;
+    } else if existing == Some(true) {
+        delete_secret(project_path, key)?;
+    }
+
+    let var = ProjectEnvVar {
+        key: key.to_string(),
+        value: (!secret).then(|| value.to_string()),
+        secret,
+        updated_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO project_env (project_path, key, value, secret, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![project_path, var.key, var.value, var.secret, var.updated_at],
+    )
+    .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+    Ok(var)
+}
+
+/// Remove a variable and its keychain entry; returns whether it existed
+pub fn delete_var(conn: &Connection, project_path: &str, key: &str) -> Result<bool, String> {
+    let Some(secret) = is_secret(conn, project_path, key).map_err(|e| e.to_string())? else {
+        return Ok(false);
+    };
+    if secret {
+        delete_secret(project_path, key)?;
+    }
+    conn.execute(
+        "DELETE FROM project_env WHERE project_path = ?1 AND key = ?2",
+        params![project_path, key],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Values of a project's variables, secrets read from the keychain
+///
+/// Secrets that can't be read are left out with a warning. Takes the rows
+/// rather than a connection so the keychain, which may prompt, is never read
+/// with the database locked.
+pub fn resolve(vars: Vec<ProjectEnvVar>, project_path: &str) -> EnvOverrides {
+    let mut env = EnvOverrides::new();
+    for var in vars {
+        let value = match var.value {
+            Some(value) if !var.secret => value,
+            _ => match read_secret(project_path, &var.key) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!("Leaving out {}: {}", var.key, e);
+                    continue;
+                }
+            },
+        };
+        env.insert(var.key, value);
+    }
+    env
+}
+
+/// The project's variables with the run's `overrides` on top
+pub fn with_project_env(
+    app: &AppHandle,
+    project_path: &str,
+    overrides: &EnvOverrides,
+) -> EnvOverrides {
+    let project_path = std::path::Path::new(project_path)
+        .canonicalize()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| project_path.to_string());
+    let vars = app
+        .try_state::<AgentDb>()
+        .and_then(|db| {
+            let conn = db.0.lock().ok()?;
+            list_vars(&conn, &project_path)
+                .map_err(|e| log::warn!("Failed to read the project environment: {}", e))
+                .ok()
+        })
+        .unwrap_or_default();
+    let mut env = resolve(vars, &project_path);
+    env.extend(overrides.clone());
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_variables() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_project_env(&conn).unwrap();
+
+        set_var(
+            &conn,
+            "/tmp/app",
+            "DATABASE_URL",
+            "postgres://localhost/app",
+            false,
+        )
+        .unwrap();
+        set_var(&conn, "/tmp/app", "NODE_ENV", "test", false).unwrap();
+        set_var(&conn, "/tmp/other", "NODE_ENV", "production", false).unwrap();
+        assert!(set_var(&conn, "/tmp/app", "ANTHROPIC_API_KEY", "sk", false).is_err());
+
+        let env = resolve(list_vars(&conn, "/tmp/app").unwrap(), "/tmp/app");
+        assert_eq!(env.len(), 2);
+        assert_eq!(env["NODE_ENV"], "test");
+
+        assert!(delete_var(&conn, "/tmp/app", "NODE_ENV").unwrap());
+        assert!(!delete_var(&conn, "/tmp/app", "NODE_ENV").unwrap());
+        assert_eq!(list_vars(&conn, "/tmp/app").unwrap().len(), 1);
+    }
+}
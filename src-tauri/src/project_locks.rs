@@ -0,0 +1,230 @@
+// Per-project locks so two agent runs don't edit the same checkout at once
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// What happens when a run targets a project another run is working in
+pub const CONFLICT_POLICIES: &[&str] = &["queue", "warn"];
+pub const DEFAULT_CONFLICT_POLICY: &str = "queue";
+
+/// The run holding or waiting for a project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockHolder {
+    pub run_id: i64,
+    /// e.g. the agent's name
+    pub label: String,
+}
+
+/// A locked project, as shown to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectLockInfo {
+    pub project_root: String,
+    pub holder: Option<LockHolder>,
+    /// Runs waiting for the project, first in line first
+    pub queued: Vec<LockHolder>,
+}
+
+#[derive(Default)]
+struct Slot {
+    holder: Option<(u64, LockHolder)>,
+    queue: VecDeque<(u64, LockHolder)>,
+}
+
+#[derive(Default)]
+struct LockTable {
+    slots: Mutex<HashMap<PathBuf, Slot>>,
+    next_ticket: AtomicU64,
+    released: Notify,
+}
+
+impl LockTable {
+    /// Remove `ticket` from `key`, held or queued, and wake the waiters
+    fn release(&self, key: &Path, ticket: u64) {
+        if let Ok(mut slots) = self.slots.lock() {
+            if let Some(slot) = slots.get_mut(key) {
+                if slot.holder.as_ref().is_some_and(|(t, _)| *t == ticket) {
+                    slot.holder = None;
+                }
+                slot.queue.retain(|(t, _)| *t != ticket);
+                if slot.holder.is_none() && slot.queue.is_empty() {
+                    slots.remove(key);
+                }
+            }
+        }
+        self.released.notify_waiters();
+    }
+}
+
+/// Held while a run works in a project; dropping it lets the next run in
+pub struct ProjectLockGuard {
+    table: Arc<LockTable>,
+    key: PathBuf,
+    ticket: u64,
+}
+
+impl Drop for ProjectLockGuard {
+    fn drop(&mut self) {
+        self.table.release(&self.key, self.ticket);
+    }
+}
+
+/// Takes a waiting run out of the queue if it stops waiting early
+struct Waiting<'a> {
+    table: &'a LockTable,
+    key: &'a Path,
+    ticket: u64,
+    acquired: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if !self.acquired {
+            self.table.release(self.key, self.ticket);
+        }
+    }
+}
+
+/// The working tree a project belongs to, or the project itself outside git
+///
+/// Locks are keyed by it, so runs in separate worktrees never conflict.
+pub fn working_tree_root(project_path: &str) -> PathBuf {
+    let path = Path::new(project_path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(project_path));
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+        .unwrap_or(path)
+}
+
+/// Locks on the projects agent runs are working in
+///
+/// A run holds its lock until its process exits. Interactive Claude sessions
+/// don't take locks; the user is watching them.
+#[derive(Default, Clone)]
+pub struct ProjectLocks {
+    table: Arc<LockTable>,
+}
+
+impl ProjectLocks {
+    fn guard(&self, key: PathBuf, ticket: u64) -> ProjectLockGuard {
+        ProjectLockGuard {
+            table: self.table.clone(),
+            key,
+            ticket,
+        }
+    }
+
+    /// Lock the project right away, or return the run that is in the way
+    pub fn try_acquire(
+        &self,
+        project_path: &str,
+        holder: LockHolder,
+    ) -> Result<ProjectLockGuard, LockHolder> {
+        let key = working_tree_root(project_path);
+        let ticket = self.table.next_ticket.fetch_add(1, Ordering::SeqCst);
+        let mut slots = self.table.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = slots.entry(key.clone()).or_default();
+        if let Some((_, current)) = slot.holder.as_ref().or(slot.queue.front()) {
+            return Err(current.clone());
+        }
+        slot.holder = Some((ticket, holder));
+        drop(slots);
+        Ok(self.guard(key, ticket))
+    }
+
+    /// Wait in line for the project
+    pub async fn acquire(&self, project_path: &str, holder: LockHolder) -> ProjectLockGuard {
+        let key = working_tree_root(project_path);
+        let ticket = self.table.next_ticket.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut slots) = self.table.slots.lock() {
+            slots
+                .entry(key.clone())
+                .or_default()
+                .queue
+                .push_back((ticket, holder));
+        }
+        let mut waiting = Waiting {
+            table: &self.table,
+            key: &key,
+            ticket,
+            acquired: false,
+        };
+
+        loop {
+            // Created before checking so a release in between isn't missed
+            let released = self.table.released.notified();
+            {
+                let mut slots = self.table.slots.lock().unwrap_or_else(|e| e.into_inner());
+                let slot = slots.entry(key.clone()).or_default();
+                let first = slot.queue.front().is_some_and(|(t, _)| *t == ticket);
+                if slot.holder.is_none() && first {
+                    slot.holder = slot.queue.pop_front();
+                    waiting.acquired = true;
+                    break;
+                }
+            }
+            released.await;
+        }
+        drop(waiting);
+        self.guard(key, ticket)
+    }
+
+    /// Every locked project with its holder and queue
+    pub fn list(&self) -> Vec<ProjectLockInfo> {
+        let Ok(slots) = self.table.slots.lock() else {
+            return Vec::new();
+        };
+        let mut locks: Vec<ProjectLockInfo> = slots
+            .iter()
+            .map(|(root, slot)| ProjectLockInfo {
+                project_root: root.to_string_lossy().to_string(),
+                holder: slot.holder.as_ref().map(|(_, h)| h.clone()),
+                queued: slot.queue.iter().map(|(_, h)| h.clone()).collect(),
+            })
+            .collect();
+        locks.sort_by(|a, b| a.project_root.cmp(&b.project_root));
+        locks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holder(run_id: i64) -> LockHolder {
+        LockHolder {
+            run_id,
+            label: format!("run {}", run_id),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runs_queue_for_the_same_working_tree() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join(".git")).unwrap();
+        std::fs::create_dir_all(repo.path().join("app")).unwrap();
+        let root = repo.path().to_string_lossy().to_string();
+        let sub = repo.path().join("app").to_string_lossy().to_string();
+        let locks = ProjectLocks::default();
+
+        let first = locks.try_acquire(&root, holder(1)).unwrap();
+        // A subdirectory is the same working tree
+        assert_eq!(locks.try_acquire(&sub, holder(2)).err(), Some(holder(1)));
+
+        let waiter = {
+            let locks = locks.clone();
+            tokio::spawn(async move { locks.acquire(&sub, holder(3)).await })
+        };
+        tokio::task::yield_now().await;
+        drop(first);
+        let second = waiter.await.unwrap();
+        assert_eq!(locks.list()[0].holder, Some(holder(3)));
+
+        drop(second);
+        assert!(locks.list().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+// Projects the user works on, kept in the app database
+use chrono::Utc;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::path::Path;
+
+/// A registered project, as stored
+///
+/// Kept in the app database rather than read from `~/.claude/projects`, so a
+/// project shows up before its first session and stays after its sessions are
+/// cleaned up.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredProject {
+    pub path: String,
+    /// Directory name unless given
+    pub name: String,
+    pub pinned: bool,
+    pub added_at: String,
+    pub last_opened_at: String,
+}
+
+/// Create the project_registry table
+pub fn init_project_registry(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS project_registry (
+            path TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            added_at TEXT NOT NULL,
+            last_opened_at TEXT NOT NULL
+        );",
+    )
+}
+
+fn row_to_project(row: &rusqlite::Row) -> SqliteResult<RegisteredProject> {
+    Ok(RegisteredProject {
+        path: row.get(0)?,
+        name: row.get(1)?,
+        pinned: row.get(2)?,
+        added_at: row.get(3)?,
+        last_opened_at: row.get(4)?,
+    })
+}
+
+/// Register a project, or mark it opened if it is already registered
+///
+/// `name` renames it; without one a new project is named after its directory.
+pub fn add(conn: &Connection, path: &str, name: Option<&str>) -> SqliteResult<RegisteredProject> {
+    let now = Utc::now().to_rfc3339();
+    let name = name.map(str::trim).filter(|n| !n.is_empty());
+    let default_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    conn.execute(
+        "INSERT INTO project_registry (path, name, added_at, last_opened_at)
+         VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT (path) DO UPDATE SET
+             name = COALESCE(?4, name),
+             last_opened_at = excluded.last_opened_at",
+        params![path, name.unwrap_or(&default_name), now, name],
+    )?;
+    conn.query_row(
+        "SELECT path, name, pinned, added_at, last_opened_at
+         FROM project_registry WHERE path = ?1",
+        params![path],
+        row_to_project,
+    )
+}
+
+/// Mark a registered project opened; unregistered ones are left alone
+pub fn touch(conn: &Connection, path: &str) {
+    if let Err(e) = conn.execute(
+        "UPDATE project_registry SET last_opened_at = ?2 WHERE path = ?1",
+        params![path, Utc::now().to_rfc3339()],
+    ) {
+        log::warn!("Failed to update project registry: {}", e);
+    }
+}
+
+/// Unregister a project; returns whether it was registered
+pub fn remove(conn: &Connection, path: &str) -> SqliteResult<bool> {
+    Ok(conn.execute(
+        "DELETE FROM project_registry WHERE path = ?1",
+        params![path],
+    )? > 0)
+}
+
+/// Pin or unpin a project; returns whether it is registered
+pub fn set_pinned(conn: &Connection, path: &str, pinned: bool) -> SqliteResult<bool> {
+    Ok(conn.execute(
+        "UPDATE project_registry SET pinned = ?2 WHERE path = ?1",
+        params![path, pinned],
+    )? > 0)
+}
+
+/// Registered projects, pinned first, then most recently opened
+pub fn list(conn: &Connection) -> SqliteResult<Vec<RegisteredProject>> {
+    let mut stmt = conn.prepare(
+        "SELECT path, name, pinned, added_at, last_opened_at
+         FROM project_registry
+         ORDER BY pinned DESC, last_opened_at DESC",
+    )?;
+    let projects = stmt
+        .query_map([], row_to_project)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(projects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_orders_pinned_then_recent() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_project_registry(&conn).unwrap();
+
+        let api = add(&conn, "/work/api", None).unwrap();
+        assert_eq!(api.name, "api");
+        add(&conn, "/work/web", Some("Website")).unwrap();
+        assert_eq!(list(&conn).unwrap()[0].path, "/work/web");
+
+        // Adding again reopens it and keeps the name unless a new one is given
+        let again = add(&conn, "/work/web", None).unwrap();
+        assert_eq!(again.name, "Website");
+        assert_eq!(again.added_at, list(&conn).unwrap()[0].added_at);
+
+        assert!(set_pinned(&conn, "/work/api", true).unwrap());
+        assert_eq!(list(&conn).unwrap()[0].path, "/work/api");
+        assert!(!set_pinned(&conn, "/work/none", true).unwrap());
+
+        assert!(remove(&conn, "/work/api").unwrap());
+        assert!(!remove(&conn, "/work/api").unwrap());
+        assert_eq!(list(&conn).unwrap().len(), 1);
+    }
+}
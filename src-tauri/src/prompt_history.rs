@@ -0,0 +1,191 @@
+// Prompt history and favorites per project
+use chrono::Utc;
+use rusqlite::{params, params_from_iter, Connection, Result as SqliteResult};
+use serde::Serialize;
+
+use crate::commands::settings::get_bool;
+
+/// Unpinned prompts kept; the least recently used go first
+const MAX_ENTRIES: i64 = 5_000;
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1_000;
+
+/// A prompt sent in a project
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptHistoryEntry {
+    pub id: i64,
+    pub project_path: String,
+    pub prompt: String,
+    /// Model of the last session started with it
+    pub model: String,
+    pub use_count: i64,
+    pub pinned: bool,
+    pub first_used_at: String,
+    pub last_used_at: String,
+}
+
+/// Create the prompt_history table
+pub fn init_prompt_history(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS prompt_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_path TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            model TEXT NOT NULL,
+            use_count INTEGER NOT NULL DEFAULT 1,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            first_used_at TEXT NOT NULL,
+            last_used_at TEXT NOT NULL,
+            UNIQUE (project_path, prompt)
+        );
+        CREATE INDEX IF NOT EXISTS idx_prompt_history_last_used ON prompt_history(last_used_at);",
+    )
+}
+
+/// Record a prompt sent in a project and drop the oldest unpinned prompts
+/// beyond the limit
+///
+/// Sending the same prompt again in a project bumps its use count and last-used
+/// time instead of adding a row.
+pub fn record(
+    conn: &Connection,
+    project_path: &str,
+    prompt: &str,
+    model: &str,
+) -> SqliteResult<()> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO prompt_history (project_path, prompt, model, first_used_at, last_used_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT (project_path, prompt) DO UPDATE SET
+             use_count = use_count + 1,
+             model = excluded.model,
+             last_used_at = excluded.last_used_at",
+        params![project_path, prompt, model, now],
+    )?;
+    conn.execute(
+        "DELETE FROM prompt_history WHERE id IN (
+             SELECT id FROM prompt_history WHERE pinned = 0
+             ORDER BY last_used_at DESC LIMIT -1 OFFSET ?1
+         )",
+        params![MAX_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// Record a prompt if prompt history is on; failures are only logged
+pub fn remember(conn: &Connection, project_path: &str, prompt: &str, model: &str) {
+    if !get_bool(conn, "prompt_history_enabled") {
+        return;
+    }
+    if let Err(e) = record(conn, project_path, prompt, model) {
+        log::warn!("Failed to record prompt history: {}", e);
+    }
+}
+
+/// Prompts, pinned first, then most recently used
+///
+/// `project_path` limits them to one project and `query` to prompts
+/// containing it, ignoring case.
+pub fn list(
+    conn: &Connection,
+    project_path: Option<&str>,
+    query: Option<&str>,
+    limit: Option<usize>,
+) -> SqliteResult<Vec<PromptHistoryEntry>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let pattern = query.map(str::trim).filter(|q| !q.is_empty()).map(|q| {
+        format!(
+            "%{}%",
+            q.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        )
+    });
+    let mut stmt = conn.prepare(
+        "SELECT id, project_path, prompt, model, use_count, pinned, first_used_at, last_used_at
+         FROM prompt_history
+         WHERE (?1 IS NULL OR project_path = ?1)
+           AND (?2 IS NULL OR prompt LIKE ?2 ESCAPE '\\')
+         ORDER BY pinned DESC, last_used_at DESC
+         LIMIT ?3",
+    )?;
+    let entries = stmt
+        .query_map(params![project_path, pattern, limit as i64], |row| {
+            Ok(PromptHistoryEntry {
+                id: row.get(0)?,
+                project_path: row.get(1)?,
+                prompt: row.get(2)?,
+                model: row.get(3)?,
+                use_count: row.get(4)?,
+                pinned: row.get(5)?,
+                first_used_at: row.get(6)?,
+                last_used_at: row.get(7)?,
+            })
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(entries)
+}
+
+/// Pin or unpin a prompt; returns whether it exists
+pub fn set_pinned(conn: &Connection, id: i64, pinned: bool) -> SqliteResult<bool> {
+    Ok(conn.execute(
+        "UPDATE prompt_history SET pinned = ?2 WHERE id = ?1",
+        params![id, pinned],
+    )? > 0)
+}
+
+/// Delete the given prompts, pinned or not; returns how many were removed
+pub fn delete(conn: &Connection, ids: &[i64]) -> SqliteResult<usize> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    conn.execute(
+        &format!("DELETE FROM prompt_history WHERE id IN ({})", placeholders),
+        params_from_iter(ids),
+    )
+}
+
+/// Delete every unpinned prompt, of one project or all; returns how many
+pub fn clear(conn: &Connection, project_path: Option<&str>) -> SqliteResult<usize> {
+    conn.execute(
+        "DELETE FROM prompt_history WHERE pinned = 0 AND (?1 IS NULL OR project_path = ?1)",
+        params![project_path],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_dedupes_and_keeps_pinned() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_prompt_history(&conn).unwrap();
+
+        record(&conn, "/p/app", "Fix the failing tests", "sonnet").unwrap();
+        record(&conn, "/p/app", "  Fix the failing tests\n", "opus").unwrap();
+        record(&conn, "/p/app", "Add 100% coverage", "sonnet").unwrap();
+        record(&conn, "/p/other", "Fix the failing tests", "sonnet").unwrap();
+
+        let app = list(&conn, Some("/p/app"), None, None).unwrap();
+        assert_eq!(app.len(), 2);
+        let fix = app.iter().find(|e| e.prompt.starts_with("Fix")).unwrap();
+        assert_eq!((fix.use_count, fix.model.as_str()), (2, "opus"));
+
+        let percent = list(&conn, None, Some("100%"), None).unwrap();
+        assert_eq!(percent.len(), 1);
+        assert_eq!(list(&conn, None, Some("FAILING"), None).unwrap().len(), 2);
+
+        assert!(set_pinned(&conn, fix.id, true).unwrap());
+        assert_eq!(list(&conn, None, None, None).unwrap()[0].id, fix.id);
+        assert_eq!(clear(&conn, Some("/p/app")).unwrap(), 1);
+        assert_eq!(delete(&conn, &[fix.id]).unwrap(), 1);
+        assert_eq!(list(&conn, None, None, None).unwrap().len(), 1);
+    }
+}
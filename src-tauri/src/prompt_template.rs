@@ -0,0 +1,161 @@
+// Agent prompt templates with `{{name}}` variables
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A variable an agent template can be specialized with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub description: String,
+    /// Used when no value is given; without one the variable is required
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Values the variable may take; any value when empty
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Split a template into literal text and placeholder names
+///
+/// `{{` that doesn't start a valid placeholder is kept as text.
+fn parts(template: &str) -> Vec<(&str, Option<&str>)> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    let mut literal_start = 0;
+    let mut offset = 0;
+    while let Some(open) = rest.find("{{") {
+        let after = &rest[open + 2..];
+        let placeholder = after
+            .find("}}")
+            .map(|close| (close, after[..close].trim()))
+            .filter(|(_, name)| is_variable_name(name));
+        match placeholder {
+            Some((close, name)) => {
+                parts.push((&template[literal_start..offset + open], Some(name)));
+                offset += open + 2 + close + 2;
+                literal_start = offset;
+                rest = &template[offset..];
+            }
+            None => {
+                offset += open + 2;
+                rest = &template[offset..];
+            }
+        }
+    }
+    parts.push((&template[literal_start..], None));
+    parts
+}
+
+/// Names of the placeholders in a template, in order of first use
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    parts(template)
+        .into_iter()
+        .filter_map(|(_, name)| name)
+        .filter(|name| seen.insert(*name))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Fill a template's placeholders
+///
+/// Fails when a value is given for an undeclared variable, a required
+/// variable has no value, a value isn't one of the variable's choices, or the
+/// template uses a placeholder it doesn't declare.
+pub fn render(
+    template: &str,
+    declared: &[TemplateVariable],
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    if let Some(unknown) = values
+        .keys()
+        .find(|name| !declared.iter().any(|v| &v.name == *name))
+    {
+        return Err(format!("Unknown template variable '{}'", unknown));
+    }
+
+    let mut resolved: HashMap<&str, String> = HashMap::new();
+    for variable in declared {
+        let value = match values.get(&variable.name) {
+            Some(value) if !value.trim().is_empty() => value.trim().to_string(),
+            _ => variable
+                .default
+                .clone()
+                .ok_or_else(|| format!("Template variable '{}' is required", variable.name))?,
+        };
+        if !variable.choices.is_empty() && !variable.choices.contains(&value) {
+            return Err(format!(
+                "'{}' isn't a valid {}; expected one of {}",
+                value,
+                variable.name,
+                variable.choices.join(", ")
+            ));
+        }
+        resolved.insert(&variable.name, value);
+    }
+
+    let mut rendered = String::with_capacity(template.len());
+    for (literal, name) in parts(template) {
+        rendered.push_str(literal);
+        if let Some(name) = name {
+            let value = resolved
+                .get(name)
+                .ok_or_else(|| format!("Template uses undeclared variable '{}'", name))?;
+            rendered.push_str(value);
+        }
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let declared = vec![
+            TemplateVariable {
+                name: "language".to_string(),
+                description: "Main language".to_string(),
+                default: None,
+                choices: vec!["Rust".to_string(), "TypeScript".to_string()],
+            },
+            TemplateVariable {
+                name: "project_name".to_string(),
+                description: "Project".to_string(),
+                default: Some("this project".to_string()),
+                choices: Vec::new(),
+            },
+        ];
+        let template = "Review {{ language }} code in {{project_name}}. Keep {{ not a var }} and {{language}}.";
+        assert_eq!(placeholders(template), ["language", "project_name"]);
+
+        let values = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            render(template, &declared, &values(&[("language", "Rust")])).unwrap(),
+            "Review Rust code in this project. Keep {{ not a var }} and Rust."
+        );
+        assert!(render(template, &declared, &values(&[])).is_err());
+        assert!(render(template, &declared, &values(&[("language", "Go")])).is_err());
+        assert!(render(
+            template,
+            &declared,
+            &values(&[("language", "Rust"), ("extra", "x")])
+        )
+        .is_err());
+        assert!(render("{{missing}}", &[], &values(&[])).is_err());
+    }
+}
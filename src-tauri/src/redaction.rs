@@ -0,0 +1,320 @@
+// Redaction of secrets in agent output
+use regex::Regex;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::settings::{get_bool, get_value};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Name, pattern and replacement of the built-in rules
+const BUILTIN_PATTERNS: &[(&str, &str, &str)] = &[
+    (
+        "private_key",
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+        REDACTED,
+    ),
+    ("anthropic_key", r"sk-ant-[A-Za-z0-9_\-]{10,}", REDACTED),
+    ("openai_key", r"sk-[A-Za-z0-9_\-]{20,}", REDACTED),
+    ("aws_access_key", r"AKIA[0-9A-Z]{16}", REDACTED),
+    ("github_token", r"gh[pousr]_[A-Za-z0-9]{30,}", REDACTED),
+    ("slack_token", r"xox[abprs]-[A-Za-z0-9\-]{10,}", REDACTED),
+    ("google_api_key", r"AIza[0-9A-Za-z_\-]{35}", REDACTED),
+    ("stripe_key", r"[rs]k_live_[0-9A-Za-z]{16,}", REDACTED),
+    (
+        "jwt",
+        r"eyJ[A-Za-z0-9_\-]{10,}\.eyJ[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}",
+        REDACTED,
+    ),
+    (
+        "secret_assignment",
+        r#"(?i)((?:api[_-]?key|secret|token|password|passwd)["']?\s*[:=]\s*["']?)[^\s"'\\,;]{8,}"#,
+        "${1}[REDACTED]",
+    ),
+];
+
+/// Redactions made, by rule name
+pub type RedactionHits = BTreeMap<String, u64>;
+
+struct Rule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+}
+
+/// A compiled set of redaction rules
+///
+/// The built-in rules cover common key formats and `name = value` pairs with
+/// secret-looking names; `redaction_patterns` adds the user's own regexes.
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// The built-in rules and one `custom` rule per user pattern
+    pub fn new(custom_patterns: &[String]) -> Result<Self, String> {
+        let mut rules: Vec<Rule> = BUILTIN_PATTERNS
+            .iter()
+            .filter_map(|(name, pattern, replacement)| {
+                Some(Rule {
+                    name: name.to_string(),
+                    regex: Regex::new(pattern).ok()?,
+                    replacement: replacement.to_string(),
+                })
+            })
+            .collect();
+        for pattern in custom_patterns {
+            let regex = Regex::new(pattern)
+                .map_err(|e| format!("Invalid redaction pattern '{}': {}", pattern, e))?;
+            rules.push(Rule {
+                name: "custom".to_string(),
+                regex,
+                replacement: REDACTED.to_string(),
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Redact `text`, counting the matches of each rule in `hits`
+    pub fn redact_text<'a>(&self, text: &'a str, hits: &mut RedactionHits) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.rules {
+            let matches = rule.regex.find_iter(&text).count() as u64;
+            if matches == 0 {
+                continue;
+            }
+            *hits.entry(rule.name.clone()).or_default() += matches;
+            text = Cow::Owned(
+                rule.regex
+                    .replace_all(&text, rule.replacement.as_str())
+                    .into_owned(),
+            );
+        }
+        text
+    }
+
+    /// Redact every string in a JSON value
+    pub fn redact_json(&self, value: JsonValue, hits: &mut RedactionHits) -> JsonValue {
+        match value {
+            JsonValue::String(s) => match self.redact_text(&s, hits) {
+                Cow::Borrowed(_) => JsonValue::String(s),
+                Cow::Owned(redacted) => JsonValue::String(redacted),
+            },
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(|v| self.redact_json(v, hits))
+                    .collect(),
+            ),
+            JsonValue::Object(map) => JsonValue::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k, self.redact_json(v, hits)))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Redact a line of output
+    ///
+    /// Stream JSON lines are redacted string by string so escapes can't be cut
+    /// in half and the line stays valid JSON; other lines as plain text.
+    pub fn redact_line<'a>(&self, line: &'a str, hits: &mut RedactionHits) -> Cow<'a, str> {
+        if line.trim_start().starts_with('{') {
+            if let Ok(value) = serde_json::from_str::<JsonValue>(line) {
+                let before: u64 = hits.values().sum();
+                let value = self.redact_json(value, hits);
+                if hits.values().sum::<u64>() == before {
+                    return Cow::Borrowed(line);
+                }
+                if let Ok(redacted) = serde_json::to_string(&value) {
+                    return Cow::Owned(redacted);
+                }
+            }
+        }
+        self.redact_text(line, hits)
+    }
+}
+
+struct Config {
+    enabled: bool,
+    redactor: Arc<Redactor>,
+}
+
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+/// User patterns from a `redaction_patterns` value: a list of regexes
+pub fn parse_patterns(value: &JsonValue) -> Result<Vec<String>, String> {
+    match value {
+        JsonValue::Null => Ok(Vec::new()),
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| "Redaction patterns must be strings".to_string())
+            })
+            .collect(),
+        _ => Err("Redaction patterns must be a list of regexes".to_string()),
+    }
+}
+
+/// Check a `redaction_patterns` value before it is saved
+pub fn validate_patterns(value: &JsonValue) -> Result<(), String> {
+    Redactor::new(&parse_patterns(value)?).map(|_| ())
+}
+
+/// Load the redaction settings; invalid user patterns are skipped with a warning
+pub fn configure(conn: &Connection) {
+    let patterns = get_value(conn, "redaction_patterns")
+        .ok()
+        .map(|value| parse_patterns(&value).unwrap_or_default())
+        .unwrap_or_default();
+    let redactor = Redactor::new(&patterns).unwrap_or_else(|e| {
+        log::warn!("{}; using the built-in redaction patterns only", e);
+        Redactor::new(&[]).expect("built-in patterns")
+    });
+    if let Ok(mut config) = CONFIG.write() {
+        *config = Some(Config {
+            enabled: get_bool(conn, "redaction_enabled"),
+            redactor: Arc::new(redactor),
+        });
+    }
+}
+
+/// Whether redaction is on unless a run says otherwise
+pub fn enabled() -> bool {
+    CONFIG
+        .read()
+        .ok()
+        .and_then(|config| config.as_ref().map(|c| c.enabled))
+        .unwrap_or(true)
+}
+
+/// The configured rules, or the built-in ones before the settings are loaded
+pub fn redactor() -> Arc<Redactor> {
+    CONFIG
+        .read()
+        .ok()
+        .and_then(|config| config.as_ref().map(|c| c.redactor.clone()))
+        .unwrap_or_else(|| Arc::new(Redactor::new(&[]).expect("built-in patterns")))
+}
+
+/// Redactions made in one run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRedactions {
+    /// Claude session ID or agent run ID
+    pub run: String,
+    pub enabled: bool,
+    pub total: u64,
+    pub by_pattern: RedactionHits,
+}
+
+/// Per-run redaction toggles and counts
+///
+/// Runs are keyed by the Claude session ID of a session or the run ID of an
+/// agent run, so redaction can be turned on or off for one run while it streams.
+#[derive(Default)]
+pub struct RedactionState {
+    overrides: Mutex<HashMap<String, bool>>,
+    counts: Mutex<HashMap<String, RedactionHits>>,
+}
+
+impl RedactionState {
+    pub fn set_enabled(&self, run: &str, enabled: bool) {
+        if let Ok(mut overrides) = self.overrides.lock() {
+            overrides.insert(run.to_string(), enabled);
+        }
+    }
+
+    pub fn enabled_for(&self, run: Option<&str>) -> bool {
+        run.and_then(|run| self.overrides.lock().ok()?.get(run).copied())
+            .unwrap_or_else(enabled)
+    }
+
+    fn record(&self, run: &str, hits: RedactionHits) {
+        if let Ok(mut counts) = self.counts.lock() {
+            let counts = counts.entry(run.to_string()).or_default();
+            for (name, n) in hits {
+                *counts.entry(name).or_default() += n;
+            }
+        }
+    }
+
+    /// Redactions made in `run`, or in every run that had any
+    pub fn counts(&self, run: Option<&str>) -> Vec<RunRedactions> {
+        let Ok(counts) = self.counts.lock() else {
+            return Vec::new();
+        };
+        let mut runs: Vec<RunRedactions> = counts
+            .iter()
+            .filter(|(key, _)| run.is_none_or(|run| run == key.as_str()))
+            .map(|(key, hits)| RunRedactions {
+                run: key.clone(),
+                enabled: self.enabled_for(Some(key)),
+                total: hits.values().sum(),
+                by_pattern: hits.clone(),
+            })
+            .collect();
+        runs.sort_by(|a, b| a.run.cmp(&b.run));
+        runs
+    }
+}
+
+/// Redact a line of a run's output if redaction is on for the run
+///
+/// Call it before the line is streamed, kept in the process registry or saved
+/// in the run history.
+/// `run` is `None` until a session's ID is known; its redactions aren't counted.
+pub fn redact_output(app: &AppHandle, run: Option<&str>, line: String) -> String {
+    let Some(state) = app.try_state::<RedactionState>() else {
+        return line;
+    };
+    if !state.enabled_for(run) {
+        return line;
+    }
+    let mut hits = RedactionHits::new();
+    let redacted = match redactor().redact_line(&line, &mut hits) {
+        Cow::Borrowed(_) => line,
+        Cow::Owned(redacted) => redacted,
+    };
+    if let (Some(run), false) = (run, hits.is_empty()) {
+        state.record(run, hits);
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_line_keeps_json_valid() {
+        let redactor = Redactor::new(&[r"acme-[0-9]{6}".to_string()]).unwrap();
+        let mut hits = RedactionHits::new();
+
+        let line = r#"{"type":"assistant","text":"key sk-ant-api03-abcdefghijkl and PASSWORD=\"hunter2hunter2\" id acme-123456"}"#;
+        let redacted = redactor.redact_line(line, &mut hits);
+        let value: JsonValue = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(
+            value["text"],
+            "key [REDACTED] and PASSWORD=\"[REDACTED]\" id [REDACTED]"
+        );
+        assert_eq!(hits["anthropic_key"], 1);
+        assert_eq!(hits["secret_assignment"], 1);
+        assert_eq!(hits["custom"], 1);
+
+        let mut none = RedactionHits::new();
+        assert!(matches!(
+            redactor.redact_line("nothing to see", &mut none),
+            Cow::Borrowed(_)
+        ));
+        assert!(none.is_empty());
+        assert!(Redactor::new(&["(".to_string()]).is_err());
+    }
+}
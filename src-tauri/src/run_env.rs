@@ -0,0 +1,134 @@
+// Environment variable overrides for a single run
+use std::collections::BTreeMap;
+use tokio::process::Command;
+
+/// Variable name to value, applied on top of the environment the app builds
+pub type EnvOverrides = BTreeMap<String, String>;
+
+const MAX_OVERRIDES: usize = 50;
+const MAX_NAME_LEN: usize = 128;
+const MAX_VALUE_LEN: usize = 4096;
+
+/// Variables a run may not set: they pick the account, endpoint or provider
+/// Claude talks to, or change how processes load
+const DENIED_NAMES: &[&str] = &[
+    "ANTHROPIC_API_KEY",
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_BEDROCK_BASE_URL",
+    "ANTHROPIC_VERTEX_BASE_URL",
+    "ANTHROPIC_VERTEX_PROJECT_ID",
+    "CLAUDE_API_KEY",
+    "CLAUDE_CODE_OAUTH_TOKEN",
+    "CLAUDE_CODE_USE_BEDROCK",
+    "CLAUDE_CODE_USE_VERTEX",
+    "CLAUDE_CONFIG_DIR",
+    "GOOGLE_APPLICATION_CREDENTIALS",
+    "HOME",
+    "PATH",
+    "NODE_OPTIONS",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+];
+
+/// Prefixes of variables a run may not set
+const DENIED_PREFIXES: &[&str] = &["AWS_", "LD_", "DYLD_"];
+
+/// Check that `overrides` only sets well-formed, allowed variables
+pub fn validate_env_overrides(overrides: &EnvOverrides) -> Result<(), String> {
+    if overrides.len() > MAX_OVERRIDES {
+        return Err(format!(
+            "At most {} environment overrides are allowed",
+            MAX_OVERRIDES
+        ));
+    }
+    for (name, value) in overrides {
+        let valid_name = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(format!("Invalid environment variable name: {}", name));
+        }
+
+        let upper = name.to_ascii_uppercase();
+        if DENIED_NAMES.contains(&upper.as_str())
+            || DENIED_PREFIXES.iter().any(|p| upper.starts_with(p))
+        {
+            return Err(format!(
+                "{} can't be overridden per run; change it in the app settings instead",
+                name
+            ));
+        }
+
+        if value.len() > MAX_VALUE_LEN || value.contains('\0') {
+            return Err(format!("Invalid value for environment variable {}", name));
+        }
+    }
+    Ok(())
+}
+
+/// Set `overrides` on `cmd`; call after the app's own variables so these win
+pub fn apply_env_overrides(cmd: &mut Command, overrides: &EnvOverrides) {
+    for (name, value) in overrides {
+        cmd.env(name, value);
+    }
+}
+
+/// `overrides` with the values of secret-looking names replaced by "***"
+pub fn mask_env_overrides(overrides: &EnvOverrides) -> EnvOverrides {
+    serde_json::to_value(overrides)
+        .ok()
+        .and_then(|value| serde_json::from_value(crate::dispatch::mask_sensitive(&value)).ok())
+        .unwrap_or_default()
+}
+
+/// JSON stored with the run, with values of secret-looking names masked
+pub fn record_env_overrides(overrides: &EnvOverrides) -> Option<String> {
+    if overrides.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&mask_env_overrides(overrides)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_env_overrides() {
+        let allowed: EnvOverrides = [("NODE_ENV", "test"), ("FEATURE_NEW_UI", "1")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert!(validate_env_overrides(&allowed).is_ok());
+
+        for name in [
+            "ANTHROPIC_API_KEY",
+            "anthropic_api_key",
+            "AWS_SECRET_ACCESS_KEY",
+            "LD_PRELOAD",
+            "PATH",
+            "1BAD",
+            "BAD-NAME",
+            "",
+        ] {
+            let overrides = EnvOverrides::from([(name.to_string(), "x".to_string())]);
+            assert!(
+                validate_env_overrides(&overrides).is_err(),
+                "accepted {}",
+                name
+            );
+        }
+
+        let recorded = record_env_overrides(&EnvOverrides::from([
+            ("NODE_ENV".to_string(), "test".to_string()),
+            ("GITHUB_TOKEN".to_string(), "ghp_x".to_string()),
+        ]))
+        .unwrap();
+        assert_eq!(recorded, r#"{"GITHUB_TOKEN":"***","NODE_ENV":"test"}"#);
+        assert_eq!(record_env_overrides(&EnvOverrides::new()), None);
+    }
+}
@@ -0,0 +1,409 @@
+// Runs waiting to start, kept in the database so they outlive the app
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agents::{start_agent_run, AgentDb};
+use crate::commands::schedules::get_schedule;
+use crate::commands::settings as store;
+use crate::event_bus;
+use crate::process::ProcessRegistryState;
+use crate::run_env::EnvOverrides;
+use crate::scheduler;
+
+/// What happens to queued runs and missed schedule windows after a restart
+///
+/// `run` starts them, `skip` drops them and `ask` holds them until the user
+/// starts or dismisses each one.
+pub const CATCH_UP_POLICIES: &[&str] = &["run", "skip", "ask"];
+pub const DEFAULT_CATCH_UP_POLICY: &str = "skip";
+
+/// Why a run is in the queue
+pub const REASON_PROJECT_LOCK: &str = "project_lock";
+pub const REASON_MISSED_SCHEDULE: &str = "missed_schedule";
+
+/// A run waiting to start: an agent run waiting for its project lock, or a
+/// schedule window missed while the app was closed
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedRun {
+    pub id: i64,
+    /// `project_lock` or `missed_schedule`
+    pub reason: String,
+    /// `waiting` while a run in this session waits for its project lock,
+    /// `held` while it waits for the user to start or dismiss it
+    pub status: String,
+    pub agent_id: i64,
+    pub agent_name: Option<String>,
+    pub project_path: String,
+    pub task: String,
+    pub model: Option<String>,
+    /// Names of the run's environment overrides; their values may be secrets,
+    /// so they aren't stored and have to be given again to start the run
+    pub env_keys: Vec<String>,
+    pub additional_dirs: Vec<String>,
+    pub schedule_id: Option<i64>,
+    /// The agent run that was waiting, for runs queued on a project lock
+    pub agent_run_id: Option<i64>,
+    /// The missed window, for scheduled runs
+    pub scheduled_for: Option<String>,
+    pub created_at: String,
+}
+
+/// Fields needed to queue a run
+#[derive(Debug, Clone, Default)]
+pub struct NewQueuedRun {
+    pub reason: &'static str,
+    pub agent_id: i64,
+    pub project_path: String,
+    pub task: String,
+    pub model: Option<String>,
+    /// Names of the run's environment overrides; only the names are stored
+    pub env_keys: Vec<String>,
+    pub additional_dirs: Vec<String>,
+    pub schedule_id: Option<i64>,
+    pub agent_run_id: Option<i64>,
+    pub scheduled_for: Option<String>,
+}
+
+/// Create the run_queue table
+pub fn init_run_queue(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS run_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            reason TEXT NOT NULL,
+            status TEXT NOT NULL,
+            agent_id INTEGER NOT NULL,
+            project_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT,
+            env TEXT NOT NULL DEFAULT '{}',
+            additional_dirs TEXT NOT NULL DEFAULT '[]',
+            schedule_id INTEGER,
+            agent_run_id INTEGER,
+            scheduled_for TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Earlier versions stored the override values; keep only their names
+    let stored: Vec<(i64, String)> = conn
+        .prepare("SELECT id, env FROM run_queue WHERE env LIKE '{%'")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<SqliteResult<_>>()?;
+    for (id, env) in stored {
+        let env: EnvOverrides = serde_json::from_str(&env).unwrap_or_default();
+        conn.execute(
+            "UPDATE run_queue SET env = ?1 WHERE id = ?2",
+            params![env_keys_json(&env.into_keys().collect::<Vec<_>>()), id],
+        )?;
+    }
+    Ok(())
+}
+
+fn env_keys_json(keys: &[String]) -> String {
+    serde_json::to_string(keys).unwrap_or_else(|_| "[]".to_string())
+}
+
+const QUEUE_QUERY: &str = "SELECT q.id, q.reason, q.status, q.agent_id, a.name, q.project_path, q.task, q.model, q.env, q.additional_dirs, q.schedule_id, q.agent_run_id, q.scheduled_for, q.created_at
+     FROM run_queue q
+     LEFT JOIN agents a ON a.id = q.agent_id";
+
+fn row_to_queued_run(row: &Row) -> SqliteResult<QueuedRun> {
+    let env: String = row.get(8)?;
+    let additional_dirs: String = row.get(9)?;
+    Ok(QueuedRun {
+        id: row.get(0)?,
+        reason: row.get(1)?,
+        status: row.get(2)?,
+        agent_id: row.get(3)?,
+        agent_name: row.get(4)?,
+        project_path: row.get(5)?,
+        task: row.get(6)?,
+        model: row.get(7)?,
+        env_keys: serde_json::from_str(&env).unwrap_or_default(),
+        additional_dirs: serde_json::from_str(&additional_dirs).unwrap_or_default(),
+        schedule_id: row.get(10)?,
+        agent_run_id: row.get(11)?,
+        scheduled_for: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+/// Add a run to the queue as `waiting` or `held`
+pub fn enqueue(conn: &Connection, run: &NewQueuedRun, status: &str) -> SqliteResult<i64> {
+    conn.execute(
+        "INSERT INTO run_queue (reason, status, agent_id, project_path, task, model, env, additional_dirs, schedule_id, agent_run_id, scheduled_for)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            run.reason,
+            status,
+            run.agent_id,
+            run.project_path,
+            run.task,
+            run.model,
+            env_keys_json(&run.env_keys),
+            serde_json::to_string(&run.additional_dirs).unwrap_or_else(|_| "[]".to_string()),
+            run.schedule_id,
+            run.agent_run_id,
+            run.scheduled_for,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Remove the queue entry of an agent run that stopped waiting for its lock
+pub fn remove_waiting(conn: &Connection, agent_run_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM run_queue WHERE agent_run_id = ?1 AND status = 'waiting'",
+        params![agent_run_id],
+    )?;
+    Ok(())
+}
+
+/// Whether a missed window of the schedule is already queued
+///
+/// A schedule gets at most one catch-up run however many windows it missed.
+pub fn has_queued_schedule_run(conn: &Connection, schedule_id: i64) -> SqliteResult<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM run_queue WHERE schedule_id = ?1)",
+        params![schedule_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn get_queued_run(conn: &Connection, id: i64) -> Result<QueuedRun, String> {
+    conn.query_row(
+        &format!("{} WHERE q.id = ?1", QUEUE_QUERY),
+        params![id],
+        row_to_queued_run,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Queued run {} not found", id))
+}
+
+/// Every queued run, oldest first
+pub fn list_queued(conn: &Connection) -> SqliteResult<Vec<QueuedRun>> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY q.id", QUEUE_QUERY))?;
+    let runs = stmt
+        .query_map([], row_to_queued_run)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(runs)
+}
+
+pub fn delete_queued(conn: &Connection, id: i64) -> SqliteResult<bool> {
+    Ok(conn.execute("DELETE FROM run_queue WHERE id = ?1", params![id])? > 0)
+}
+
+pub fn catch_up_policy(conn: &Connection) -> String {
+    store::get_text(conn, "run_catch_up_policy")
+        .filter(|policy| CATCH_UP_POLICIES.contains(&policy.as_str()))
+        .unwrap_or_else(|| DEFAULT_CATCH_UP_POLICY.to_string())
+}
+
+/// Hold a run for the user to decide on and tell the frontend
+pub fn hold(app: &AppHandle, conn: &Connection, run: &NewQueuedRun) -> SqliteResult<i64> {
+    let id = enqueue(conn, run, "held")?;
+    event_bus::publish(
+        app,
+        "run-queue-held",
+        &serde_json::json!({ "id": id, "reason": run.reason, "schedule_id": run.schedule_id }),
+    );
+    Ok(id)
+}
+
+/// Start a queued run and take it out of the queue once it has started
+///
+/// `env` gives the values of the run's `env_keys` again. Missed schedule
+/// windows run through the schedule so the run is recorded on it. Returns the
+/// ID of the new agent run; a run that fails to start stays queued.
+pub async fn start_queued(
+    app: &AppHandle,
+    run: QueuedRun,
+    env: EnvOverrides,
+) -> Result<i64, String> {
+    let missing: Vec<&str> = run
+        .env_keys
+        .iter()
+        .filter(|key| !env.contains_key(*key))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Queued run {} needs {} again to start",
+            run.id,
+            missing.join(", ")
+        ));
+    }
+    let schedule = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        match run.schedule_id {
+            Some(schedule_id) => Some(get_schedule(&conn, schedule_id)?),
+            None => None,
+        }
+    };
+
+    let started = match schedule {
+        Some(schedule) => scheduler::run_schedule(app, &schedule).await,
+        None => {
+            start_agent_run(
+                app.clone(),
+                None,
+                run.agent_id,
+                run.project_path,
+                run.task,
+                run.model,
+                Some(env),
+                Some(run.additional_dirs),
+                app.state::<AgentDb>(),
+                app.state::<ProcessRegistryState>(),
+            )
+            .await
+        }
+    }?;
+
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    delete_queued(&conn, run.id).map_err(|e| e.to_string())?;
+    Ok(started)
+}
+
+/// Apply the catch-up policy to runs left waiting when the app last exited
+///
+/// Call once at launch before any run starts. Their old agent runs are marked
+/// cancelled; with `run` the runs start again as new agent runs. Runs with
+/// environment overrides are held instead, since the values weren't kept, and
+/// so are runs that fail to start.
+pub fn resume_run_queue(app: AppHandle) {
+    let stale = {
+        let db = app.state::<AgentDb>();
+        let Ok(conn) = db.0.lock() else {
+            return;
+        };
+        let stale = match conn
+            .prepare(&format!(
+                "{} WHERE q.status = 'waiting' ORDER BY q.id",
+                QUEUE_QUERY
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([], row_to_queued_run)?
+                    .collect::<SqliteResult<Vec<_>>>()
+            }) {
+            Ok(stale) => stale,
+            Err(e) => {
+                log::warn!("Failed to read the run queue: {}", e);
+                return;
+            }
+        };
+        if stale.is_empty() {
+            return;
+        }
+        let _ = conn.execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP
+             WHERE status = 'pending' AND id IN (SELECT agent_run_id FROM run_queue WHERE status = 'waiting')",
+            [],
+        );
+
+        let policy = catch_up_policy(&conn);
+        log::info!(
+            "{} runs were queued when the app exited; catch-up policy is '{}'",
+            stale.len(),
+            policy
+        );
+        // Runs that start now leave the queue once they have started
+        let update = match policy.as_str() {
+            "ask" | "run" => conn.execute(
+                "UPDATE run_queue SET status = 'held' WHERE status = 'waiting'",
+                [],
+            ),
+            _ => conn.execute("DELETE FROM run_queue WHERE status = 'waiting'", []),
+        };
+        if let Err(e) = update {
+            log::warn!("Failed to update the run queue: {}", e);
+            return;
+        }
+        let (held, start): (Vec<QueuedRun>, Vec<QueuedRun>) = match policy.as_str() {
+            "ask" => (stale, Vec::new()),
+            "run" => stale.into_iter().partition(|run| !run.env_keys.is_empty()),
+            _ => return,
+        };
+        for run in &held {
+            event_bus::publish(
+                &app,
+                "run-queue-held",
+                &serde_json::json!({ "id": run.id, "reason": run.reason, "schedule_id": run.schedule_id }),
+            );
+        }
+        start
+    };
+
+    tauri::async_runtime::spawn(async move {
+        for run in stale {
+            let id = run.id;
+            if let Err(e) = start_queued(&app, run, EnvOverrides::new()).await {
+                log::warn!("Queued run {} failed to restart and stays held: {}", id, e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE agents (id INTEGER PRIMARY KEY, name TEXT)",
+            [],
+        )
+        .unwrap();
+        init_run_queue(&conn).unwrap();
+
+        let run = NewQueuedRun {
+            reason: REASON_PROJECT_LOCK,
+            agent_id: 1,
+            project_path: "/tmp/project".to_string(),
+            task: "Fix the build".to_string(),
+            env_keys: vec!["API_TOKEN".to_string()],
+            additional_dirs: vec!["/tmp/shared".to_string()],
+            agent_run_id: Some(7),
+            ..Default::default()
+        };
+        let id = enqueue(&conn, &run, "waiting").unwrap();
+        let queued = get_queued_run(&conn, id).unwrap();
+        assert_eq!(queued.status, "waiting");
+        assert_eq!(queued.env_keys, run.env_keys);
+        assert_eq!(queued.additional_dirs, run.additional_dirs);
+
+        remove_waiting(&conn, 7).unwrap();
+        assert!(list_queued(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stored_env_values_are_dropped() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE agents (id INTEGER PRIMARY KEY, name TEXT)",
+            [],
+        )
+        .unwrap();
+        init_run_queue(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO run_queue (reason, status, agent_id, project_path, task, env)
+             VALUES ('project_lock', 'waiting', 1, '/tmp/project', 'Fix', '{\"API_TOKEN\":\"s3cret\"}')",
+            [],
+        )
+        .unwrap();
+
+        init_run_queue(&conn).unwrap();
+        let stored: String = conn
+            .query_row("SELECT env FROM run_queue", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "[\"API_TOKEN\"]");
+        assert_eq!(list_queued(&conn).unwrap()[0].env_keys, vec!["API_TOKEN"]);
+    }
+}
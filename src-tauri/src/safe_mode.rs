@@ -0,0 +1,212 @@
+// Safe-mode startup after a launch that crashed
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::dispatch::{CommandContext, Middleware};
+
+/// Exists while the app runs, so finding it at startup means the last launch crashed
+const LAUNCH_MARKER: &str = "launch.lock";
+/// Asks the next launch to start in safe mode
+const SAFE_MODE_FLAG: &str = "safe-mode";
+
+/// Commands available in safe mode
+///
+/// Safe mode also skips the optional plugins and background tasks, and lasts
+/// for one launch; it is asked for with a flag file, the `--safe-mode` argument
+/// or `OPCODE_SAFE_MODE=1`.
+pub const SAFE_MODE_COMMANDS: &[&str] = &[
+    // Safe mode itself
+    "get_safe_mode_status",
+    "restart_in_safe_mode",
+    "restart_normally",
+    // Claude configuration
+    "list_projects",
+    "get_home_directory",
+    "get_claude_dir_info",
+    "set_claude_dir_location",
+    "get_claude_settings",
+    "save_claude_settings",
+    "get_system_prompt",
+    "save_system_prompt",
+    "find_claude_md_files",
+    "read_claude_md_file",
+    "save_claude_md_file",
+    "get_hooks_config",
+    "update_hooks_config",
+    "validate_hook_command",
+    "check_claude_version",
+    "get_claude_binary_path",
+    "set_claude_binary_path",
+    "list_claude_installations",
+    "scan_cli_migration",
+    "apply_cli_migration_fix",
+    "acknowledge_cli_migration",
+    // MCP servers and plugins
+    "mcp_list",
+    "mcp_get",
+    "mcp_remove",
+    "mcp_read_project_config",
+    "mcp_save_project_config",
+    "plugin_uninstall",
+    // Agents and slash commands
+    "list_agents",
+    "get_agent",
+    "update_agent",
+    "delete_agent",
+    "slash_commands_list",
+    "slash_command_get",
+    "slash_command_save",
+    "slash_command_delete",
+    // App settings
+    "get_setting",
+    "set_setting",
+    "get_all_settings",
+    "export_app_config",
+    "import_app_config",
+    "get_proxy_settings",
+    "save_proxy_settings",
+    // Diagnostics
+    "run_doctor",
+    "get_recent_logs",
+    "set_log_level",
+    "list_recent_errors",
+    "record_command_error",
+    "get_capabilities",
+    "get_database_recovery",
+    "get_cli_capabilities",
+    "get_events_since",
+    "subscribe_events",
+    "unsubscribe_events",
+    "list_operations",
+];
+
+/// How the app was started
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LaunchState {
+    /// The previous launch ended without a clean exit
+    pub crashed_last_launch: bool,
+    pub safe_mode: bool,
+}
+
+static LAUNCH: OnceLock<LaunchState> = OnceLock::new();
+
+/// Directory holding the marker and flag files
+fn state_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(crate::utils::APP_IDENTIFIER))
+}
+
+/// Check how the last launch ended and mark this one as running
+///
+/// Consumes the safe-mode flag, so the launch after this one starts normally
+/// unless safe mode is asked for again.
+pub fn begin_launch_in(dir: &Path, requested: bool) -> LaunchState {
+    let marker = dir.join(LAUNCH_MARKER);
+    let flag = dir.join(SAFE_MODE_FLAG);
+    let state = LaunchState {
+        crashed_last_launch: marker.exists(),
+        safe_mode: requested || flag.exists(),
+    };
+    let _ = fs::remove_file(&flag);
+    if let Err(e) =
+        fs::create_dir_all(dir).and_then(|_| fs::write(&marker, std::process::id().to_string()))
+    {
+        log::warn!("Failed to write launch marker: {}", e);
+    }
+    state
+}
+
+/// Record the start of this launch; call once, before the app is built
+pub fn begin_launch() -> LaunchState {
+    let requested = std::env::args().any(|a| a == "--safe-mode")
+        || std::env::var("OPCODE_SAFE_MODE").is_ok_and(|v| v == "1");
+    let state = state_dir()
+        .map(|dir| begin_launch_in(&dir, requested))
+        .unwrap_or(LaunchState {
+            crashed_last_launch: false,
+            safe_mode: requested,
+        });
+    if state.crashed_last_launch {
+        log::warn!("The previous launch did not exit cleanly");
+    }
+    if state.safe_mode {
+        log::warn!("Starting in safe mode");
+    }
+    let _ = LAUNCH.set(state);
+    state
+}
+
+/// How this launch was started
+pub fn launch_state() -> LaunchState {
+    LAUNCH.get().copied().unwrap_or_default()
+}
+
+/// Whether this launch is in safe mode
+pub fn is_active() -> bool {
+    launch_state().safe_mode
+}
+
+/// Remove the launch marker; called on a clean exit
+pub fn end_launch() {
+    if let Some(dir) = state_dir() {
+        let _ = fs::remove_file(dir.join(LAUNCH_MARKER));
+    }
+}
+
+/// Ask the next launch to start in safe mode, or not
+pub fn request_safe_mode(enabled: bool) -> Result<(), String> {
+    let dir = state_dir().ok_or("Could not find the app data directory")?;
+    let flag = dir.join(SAFE_MODE_FLAG);
+    if enabled {
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&flag, ""))
+            .map_err(|e| format!("Failed to request safe mode: {}", e))
+    } else {
+        match fs::remove_file(&flag) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to clear the safe-mode request: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rejects commands outside `SAFE_MODE_COMMANDS`
+pub struct SafeModeGuard;
+
+impl Middleware for SafeModeGuard {
+    fn before(&self, ctx: &CommandContext) -> Result<(), String> {
+        // Plugin commands (dialogs, shell) are routed separately; only app commands get here
+        if SAFE_MODE_COMMANDS.contains(&ctx.command.as_str()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} is not available in safe mode; restart normally to use it",
+                ctx.command
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_launch_detects_crash_and_consumes_flag() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = begin_launch_in(dir.path(), false);
+        assert!(!first.crashed_last_launch && !first.safe_mode);
+
+        // No clean exit, and safe mode was asked for
+        fs::write(dir.path().join(SAFE_MODE_FLAG), "").unwrap();
+        let second = begin_launch_in(dir.path(), false);
+        assert!(second.crashed_last_launch && second.safe_mode);
+
+        fs::remove_file(dir.path().join(LAUNCH_MARKER)).unwrap();
+        let third = begin_launch_in(dir.path(), false);
+        assert!(!third.crashed_last_launch && !third.safe_mode);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod platform;
+pub mod profile;
+
+pub use platform::{SandboxCapabilities, SandboxPlan};
+pub use profile::ExecutionProfile;
@@ -0,0 +1,738 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::profile::ExecutionProfile;
+
+/// Tools that reach the network from inside a Claude session
+const NETWORK_TOOLS: &[&str] = &["WebFetch", "WebSearch"];
+
+/// Tools that stay available when a command allowlist is in effect
+const BASE_TOOLS: &[&str] = &[
+    "Read",
+    "Edit",
+    "MultiEdit",
+    "Write",
+    "Glob",
+    "Grep",
+    "LS",
+    "NotebookRead",
+    "NotebookEdit",
+    "TodoWrite",
+    "Task",
+];
+
+/// Port the CLI reaches the API on
+const API_PORT: u16 = 443;
+
+/// Variables naming endpoints the CLI connects to instead of the API
+const ENDPOINT_VARS: &[&str] = &[
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_BEDROCK_BASE_URL",
+    "ANTHROPIC_VERTEX_BASE_URL",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// System directories every session may read; seatbelt only confines reads
+/// inside the home directory, so macOS needs none
+#[cfg(target_os = "linux")]
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/proc", "/sys", "/dev",
+    "/run", "/nix", "/snap",
+];
+#[cfg(not(target_os = "linux"))]
+const SYSTEM_READ_PATHS: &[&str] = &[];
+
+/// Files in the home directory that shell tools such as git fail without
+const HOME_READ_PATHS: &[&str] = &[
+    ".gitconfig",
+    ".config/git",
+    #[cfg(target_os = "macos")]
+    "Library/Keychains",
+];
+
+/// Which isolation primitives are available on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxCapabilities {
+    pub platform: String,
+    /// Whether file access can be confined at the OS level
+    pub filesystem_isolation: bool,
+    /// Whether outgoing connections can be limited to the API at the OS level
+    pub network_isolation: bool,
+    /// Name of the mechanism used, e.g. "seatbelt" or "landlock"
+    pub mechanism: Option<String>,
+}
+
+impl SandboxCapabilities {
+    pub fn detect() -> Self {
+        Self {
+            platform: std::env::consts::OS.to_string(),
+            filesystem_isolation: filesystem_mechanism().is_some(),
+            network_isolation: network_isolation(),
+            mechanism: filesystem_mechanism().map(str::to_string),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn filesystem_mechanism() -> Option<&'static str> {
+    std::path::Path::new(SANDBOX_EXEC)
+        .exists()
+        .then_some("seatbelt")
+}
+
+#[cfg(target_os = "linux")]
+fn filesystem_mechanism() -> Option<&'static str> {
+    (landlock::abi_version() > 0).then_some("landlock")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn filesystem_mechanism() -> Option<&'static str> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn network_isolation() -> bool {
+    filesystem_mechanism().is_some()
+}
+
+/// Landlock confines TCP connections from ABI version 4
+#[cfg(target_os = "linux")]
+fn network_isolation() -> bool {
+    landlock::abi_version() >= 4
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn network_isolation() -> bool {
+    false
+}
+
+#[cfg(target_os = "macos")]
+const SANDBOX_EXEC: &str = "/usr/bin/sandbox-exec";
+
+/// How a Claude process is launched under an execution profile
+///
+/// Tool restrictions are enforced through CLI flags, file and network
+/// confinement through the platform sandbox. Without a filesystem sandbox a
+/// warning is recorded instead of failing the run, but a profile without
+/// network access is refused when connections can't be confined.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPlan {
+    /// Program and arguments the CLI is launched through
+    pub launcher: Option<(String, Vec<String>)>,
+    /// Directories the session may write to
+    pub writable_paths: Vec<PathBuf>,
+    /// Directories the session may read but not write
+    pub readable_paths: Vec<PathBuf>,
+    /// The CLI's global config file, which it replaces through a temporary
+    /// file next to it
+    pub config_file: Option<PathBuf>,
+    /// Whether file access outside these paths is blocked by the OS
+    pub confine_files: bool,
+    /// TCP ports the session may connect to; `None` leaves the network open
+    pub allowed_ports: Option<Vec<u16>>,
+    pub disallowed_tools: Vec<String>,
+    pub allowed_tools: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl SandboxPlan {
+    pub fn prepare(
+        profile: &ExecutionProfile,
+        project_path: &str,
+        claude_path: &str,
+    ) -> Result<Self, String> {
+        Self::prepare_with(
+            profile,
+            project_path,
+            claude_path,
+            filesystem_mechanism().is_some(),
+            network_isolation(),
+        )
+    }
+
+    fn prepare_with(
+        profile: &ExecutionProfile,
+        project_path: &str,
+        claude_path: &str,
+        filesystem_isolation: bool,
+        network_isolation: bool,
+    ) -> Result<Self, String> {
+        let mut plan = SandboxPlan {
+            writable_paths: writable_paths(profile, project_path),
+            readable_paths: readable_paths(claude_path),
+            config_file: dirs::home_dir().map(|home| resolve(home.join(".claude.json"))),
+            ..Default::default()
+        };
+
+        if !profile.network_enabled {
+            if !network_isolation {
+                return Err(format!(
+                    "Network access can't be blocked on {}; allow network access in the execution profile to run this agent",
+                    std::env::consts::OS
+                ));
+            }
+            plan.disallowed_tools = NETWORK_TOOLS.iter().map(|t| t.to_string()).collect();
+            plan.allowed_ports = Some(api_ports());
+        }
+
+        if !profile.allowed_commands.is_empty() {
+            plan.allowed_tools = BASE_TOOLS.iter().map(|t| t.to_string()).collect();
+            if profile.network_enabled {
+                plan.allowed_tools
+                    .extend(NETWORK_TOOLS.iter().map(|t| t.to_string()));
+            }
+            plan.allowed_tools.extend(
+                profile
+                    .allowed_commands
+                    .iter()
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .map(|c| format!("Bash({}:*)", c)),
+            );
+        }
+
+        if filesystem_isolation {
+            plan.confine_files = true;
+            // Landlock rules cover whole directories, not single files
+            #[cfg(target_os = "linux")]
+            if let Some(dir) = plan.config_file.as_deref().and_then(Path::parent) {
+                plan.warnings.push(format!(
+                    "Files under {} stay readable and writable so Claude can replace its config file",
+                    dir.display()
+                ));
+            }
+        } else {
+            plan.warnings.push(format!(
+                "Filesystem isolation is not available on {}; the session can read and write outside its allowed directories",
+                std::env::consts::OS
+            ));
+        }
+
+        #[cfg(target_os = "macos")]
+        if plan.confine_files || plan.allowed_ports.is_some() {
+            plan.launcher = Some((
+                SANDBOX_EXEC.to_string(),
+                vec!["-p".to_string(), seatbelt_profile(&plan)],
+            ));
+        }
+
+        Ok(plan)
+    }
+
+    /// Rewrite the CLI arguments to enforce the profile's tool restrictions
+    pub fn apply_args(&self, mut args: Vec<String>) -> Vec<String> {
+        if !self.allowed_tools.is_empty() {
+            // An allowlist only takes effect when permission prompts aren't bypassed;
+            // non-interactive runs deny anything outside it.
+            args.retain(|a| a != "--dangerously-skip-permissions");
+            args.push("--permission-mode".to_string());
+            args.push("acceptEdits".to_string());
+            args.push("--allowedTools".to_string());
+            args.extend(self.allowed_tools.iter().cloned());
+        }
+
+        if !self.disallowed_tools.is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.extend(self.disallowed_tools.iter().cloned());
+        }
+
+        args
+    }
+
+    /// Apply OS-level restrictions that must be installed in the child process
+    #[cfg(target_os = "linux")]
+    pub fn apply_to_command(&self, cmd: &mut Command) {
+        if self.confine_files || self.allowed_ports.is_some() {
+            landlock::confine(cmd, self);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply_to_command(&self, _cmd: &mut Command) {}
+}
+
+/// Resolve symlinks so the sandbox matches the real location
+fn resolve(path: PathBuf) -> PathBuf {
+    path.canonicalize().unwrap_or(path)
+}
+
+/// Project directory, configured paths and the locations the CLI itself writes to
+fn writable_paths(profile: &ExecutionProfile, project_path: &str) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(project_path)];
+    paths.extend(profile.allowed_paths.iter().map(PathBuf::from));
+    paths.push(std::env::temp_dir());
+    if let Ok(claude_dir) = crate::utils::get_claude_dir() {
+        paths.push(claude_dir);
+    }
+    paths.into_iter().map(resolve).collect()
+}
+
+/// System directories, shell tool config and the installations of the CLI
+/// and Node.js, e.g. `~/.nvm/versions/node/v22` for `~/.nvm/versions/node/v22/bin/claude`
+fn readable_paths(claude_path: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect();
+    if let Some(home) = dirs::home_dir() {
+        paths.extend(HOME_READ_PATHS.iter().map(|p| home.join(p)));
+    }
+    let node = which::which("node").ok();
+    for binary in std::iter::once(PathBuf::from(claude_path)).chain(node) {
+        if let Some(prefix) = binary
+            .parent()
+            .filter(|dir| dir.ends_with("bin"))
+            .and_then(Path::parent)
+        {
+            paths.push(prefix.to_path_buf());
+        }
+        // Where a linked binary really lives, e.g. ~/.local/share/claude/versions
+        if let Some(dir) = binary
+            .canonicalize()
+            .ok()
+            .and_then(|b| b.parent().map(Path::to_path_buf))
+        {
+            paths.push(dir);
+        }
+    }
+    paths.into_iter().map(resolve).collect()
+}
+
+/// Ports the CLI needs to reach the API, directly or through a proxy
+fn api_ports() -> Vec<u16> {
+    let mut ports = vec![API_PORT];
+    for var in ENDPOINT_VARS {
+        let port = std::env::var(var)
+            .ok()
+            .and_then(|url| reqwest::Url::parse(&url).ok())
+            .and_then(|url| url.port_or_known_default());
+        if let Some(port) = port.filter(|port| !ports.contains(port)) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+/// Quote a path as a seatbelt string
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn seatbelt_string(path: &Path) -> String {
+    format!(
+        "\"{}\"",
+        path.to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    )
+}
+
+/// Seatbelt filter matching a file or everything beneath a directory
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn seatbelt_path(path: &Path) -> String {
+    let kind = if path.is_file() { "literal" } else { "subpath" };
+    format!("({} {})", kind, seatbelt_string(path))
+}
+
+/// Seatbelt profile that permits everything except what the plan confines
+///
+/// Outside the home directory only writes are confined. The config file is
+/// matched by prefix so the temporary file it is replaced through is covered.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn seatbelt_profile(plan: &SandboxPlan) -> String {
+    let mut profile = "(version 1)\n(allow default)\n".to_string();
+
+    if plan.confine_files {
+        let mut writable = vec![
+            "(literal \"/dev/null\")".to_string(),
+            "(regex #\"^/dev/tty\")".to_string(),
+            "(subpath \"/dev/fd\")".to_string(),
+            "(subpath \"/private/var/folders\")".to_string(),
+        ];
+        writable.extend(plan.writable_paths.iter().map(|p| seatbelt_path(p)));
+        if let Some(config) = &plan.config_file {
+            let pattern = regex::escape(&config.to_string_lossy()).replace('"', "\\\"");
+            writable.push(format!("(regex #\"^{}\")", pattern));
+        }
+        let mut readable = writable.clone();
+        readable.extend(plan.readable_paths.iter().map(|p| seatbelt_path(p)));
+
+        profile.push_str(&format!(
+            "(deny file-write*)\n(allow file-write*\n    {})\n",
+            writable.join("\n    ")
+        ));
+        if let Some(home) = dirs::home_dir() {
+            profile.push_str(&format!(
+                "(deny file-read* (subpath {}))\n(allow file-read-metadata)\n(allow file-read*\n    {})\n",
+                seatbelt_string(&home),
+                readable.join("\n    ")
+            ));
+        }
+    }
+
+    if let Some(ports) = &plan.allowed_ports {
+        let remotes: Vec<String> = ports
+            .iter()
+            .map(|port| format!("(remote tcp \"*:{}\")", port))
+            .collect();
+        profile.push_str(&format!(
+            "(deny network-outbound)\n(allow network-outbound\n    (remote unix-socket)\n    {})\n",
+            remotes.join("\n    ")
+        ));
+    }
+
+    profile
+}
+
+#[cfg(target_os = "linux")]
+mod landlock {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use tokio::process::Command;
+
+    use super::SandboxPlan;
+
+    const CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+    const RULE_NET_PORT: libc::c_int = 2;
+
+    const ACCESS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_READ_DIR: u64 = 1 << 3;
+    const ACCESS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_REFER: u64 = 1 << 13;
+    const ACCESS_TRUNCATE: u64 = 1 << 14;
+
+    const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+    /// Devices shell redirections write to, writable as in `seatbelt_profile`
+    const DEVICE_PATHS: &[&str] = &["/dev/null", "/dev/tty", "/dev/fd"];
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    #[repr(C)]
+    struct NetPortAttr {
+        allowed_access: u64,
+        port: u64,
+    }
+
+    /// Landlock ABI version supported by the running kernel, or 0 if unavailable
+    pub fn abi_version() -> i64 {
+        unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        }
+        .max(0)
+    }
+
+    /// All filesystem access rights known to the given ABI version
+    fn handled_access(abi: i64) -> u64 {
+        let mut access = (1 << 13) - 1;
+        if abi >= 2 {
+            access |= ACCESS_REFER;
+        }
+        if abi >= 3 {
+            access |= ACCESS_TRUNCATE;
+        }
+        access
+    }
+
+    /// Limit file access to the plan's paths and TCP connections to its ports
+    pub fn confine(cmd: &mut Command, plan: &SandboxPlan) {
+        let handled = if plan.confine_files {
+            handled_access(abi_version())
+        } else {
+            0
+        };
+        let handled_net = if plan.allowed_ports.is_some() {
+            ACCESS_NET_CONNECT_TCP
+        } else {
+            0
+        };
+        let file_access =
+            handled & (ACCESS_EXECUTE | ACCESS_WRITE_FILE | ACCESS_READ_FILE | ACCESS_TRUNCATE);
+        let read_only = handled & (ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR);
+        // Enough to write a temporary file next to the config file and rename it over it
+        let config_access = handled
+            & (ACCESS_READ_FILE
+                | ACCESS_WRITE_FILE
+                | ACCESS_TRUNCATE
+                | ACCESS_MAKE_REG
+                | ACCESS_REMOVE_FILE
+                | ACCESS_REFER);
+
+        // Prepare everything that allocates before forking
+        let rules: Vec<(CString, u64)> = if plan.confine_files {
+            let writable = plan
+                .writable_paths
+                .iter()
+                .cloned()
+                .chain(DEVICE_PATHS.iter().map(PathBuf::from))
+                .map(|path| (path, handled));
+            let readable = plan
+                .readable_paths
+                .iter()
+                .cloned()
+                .map(|path| (path, read_only));
+            let config = plan
+                .config_file
+                .as_deref()
+                .and_then(Path::parent)
+                .map(|dir| (dir.to_path_buf(), config_access));
+            writable
+                .chain(readable)
+                .chain(config)
+                .filter_map(|(path, access)| {
+                    let access = if path.is_dir() {
+                        access
+                    } else if path.exists() {
+                        // Regular files and devices such as /dev/null
+                        access & file_access
+                    } else {
+                        return None;
+                    };
+                    CString::new(path.as_os_str().as_bytes())
+                        .ok()
+                        .map(|p| (p, access))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let ports: Vec<u64> = plan
+            .allowed_ports
+            .iter()
+            .flatten()
+            .map(|&port| u64::from(port))
+            .collect();
+
+        unsafe {
+            cmd.pre_exec(move || {
+                let attr = RulesetAttr {
+                    handled_access_fs: handled,
+                    handled_access_net: handled_net,
+                };
+                let ruleset = libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0u32,
+                ) as libc::c_int;
+                if ruleset < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                for (path, access) in &rules {
+                    add_path_rule(ruleset, path, *access);
+                }
+                for port in &ports {
+                    add_port_rule(ruleset, *port);
+                }
+
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let restricted = libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32);
+                libc::close(ruleset);
+                if restricted != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Add a path rule to the ruleset; paths that can't be opened are skipped
+    unsafe fn add_path_rule(ruleset: libc::c_int, path: &CString, access: u64) {
+        let fd = libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC);
+        if fd < 0 {
+            return;
+        }
+        let attr = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: fd,
+        };
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset,
+            RULE_PATH_BENEATH,
+            &attr as *const PathBeneathAttr,
+            0u32,
+        );
+        libc::close(fd);
+    }
+
+    /// Allow TCP connections to a port
+    unsafe fn add_port_rule(ruleset: libc::c_int, port: u64) {
+        let attr = NetPortAttr {
+            allowed_access: ACCESS_NET_CONNECT_TCP,
+            port,
+        };
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset,
+            RULE_NET_PORT,
+            &attr as *const NetPortAttr,
+            0u32,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(network_enabled: bool, allowed_commands: &[&str]) -> ExecutionProfile {
+        ExecutionProfile {
+            id: None,
+            name: "test".to_string(),
+            allowed_paths: Vec::new(),
+            network_enabled,
+            allowed_commands: allowed_commands.iter().map(|c| c.to_string()).collect(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_args_with_command_allowlist() {
+        let plan =
+            SandboxPlan::prepare_with(&profile(false, &["git", " "]), "/tmp", "claude", true, true)
+                .unwrap();
+        let args = plan.apply_args(vec![
+            "-p".to_string(),
+            "task".to_string(),
+            "--dangerously-skip-permissions".to_string(),
+        ]);
+
+        assert!(!args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(args.contains(&"Bash(git:*)".to_string()));
+        assert!(!args.contains(&"Bash(:*)".to_string()));
+
+        // Network tools are only listed as disallowed
+        let allowed = args.iter().position(|a| a == "--allowedTools").unwrap();
+        let disallowed = args.iter().position(|a| a == "--disallowedTools").unwrap();
+        assert!(args[allowed..disallowed].iter().all(|a| a != "WebFetch"));
+    }
+
+    #[test]
+    fn test_unrestricted_profile_keeps_args() {
+        let plan = SandboxPlan::prepare(&profile(true, &[]), "/tmp", "claude").unwrap();
+        let args = vec!["--dangerously-skip-permissions".to_string()];
+        assert_eq!(plan.apply_args(args.clone()), args);
+    }
+
+    #[test]
+    fn test_network_denial_fails_closed() {
+        let denied = profile(false, &[]);
+        assert!(SandboxPlan::prepare_with(&denied, "/tmp", "claude", true, false).is_err());
+
+        let plan = SandboxPlan::prepare_with(&denied, "/tmp", "claude", false, true).unwrap();
+        assert!(plan.allowed_ports.unwrap().contains(&API_PORT));
+        assert!(!plan.confine_files);
+    }
+
+    #[test]
+    fn test_seatbelt_profile_escapes_paths() {
+        let plan = SandboxPlan {
+            writable_paths: vec![PathBuf::from("/work/my \"project\"")],
+            config_file: Some(PathBuf::from("/home/me/.claude.json")),
+            confine_files: true,
+            allowed_ports: Some(vec![443]),
+            ..Default::default()
+        };
+        let profile = seatbelt_profile(&plan);
+        assert!(profile.contains("(deny file-write*)"));
+        assert!(profile.contains("(subpath \"/work/my \\\"project\\\"\")"));
+        assert!(profile.contains("(regex #\"^/home/me/\\.claude\\.json\")"));
+        assert!(profile.contains("(remote tcp \"*:443\")"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_landlock_allows_device_redirections() {
+        if landlock::abi_version() == 0 {
+            return;
+        }
+        let plan = SandboxPlan {
+            readable_paths: readable_paths("sh"),
+            confine_files: true,
+            ..Default::default()
+        };
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "echo ok > /dev/null 2>/dev/null && ! touch /etc/opcode-sandbox-test 2>/dev/null",
+        ]);
+        plan.apply_to_command(&mut cmd);
+        assert!(cmd.status().await.unwrap().success());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_landlock_confines_reads_and_allows_config_replacement() {
+        if landlock::abi_version() < 2 {
+            return;
+        }
+        let home = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let config = home.path().join(".claude.json");
+        let secret = other.path().join("secret");
+        std::fs::write(&config, "old").unwrap();
+        std::fs::write(&secret, "secret").unwrap();
+
+        let plan = SandboxPlan {
+            readable_paths: readable_paths("sh"),
+            config_file: Some(config.clone()),
+            confine_files: true,
+            ..Default::default()
+        };
+        // Replaced twice, since the rename gives the file a new inode
+        let script = format!(
+            "for v in one two; do echo $v > {c}.tmp && mv {c}.tmp {c} || exit 1; done; \
+             [ \"$(cat {c})\" = two ] && ! cat {s} 2>/dev/null",
+            c = config.display(),
+            s = secret.display()
+        );
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &script]);
+        plan.apply_to_command(&mut cmd);
+        assert!(cmd.status().await.unwrap().success());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_landlock_limits_connections_to_allowed_ports() {
+        if landlock::abi_version() < 4 || which::which("bash").is_err() {
+            return;
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        for (ports, connects) in [(vec![API_PORT], false), (vec![API_PORT, port], true)] {
+            let plan = SandboxPlan {
+                allowed_ports: Some(ports),
+                ..Default::default()
+            };
+            let mut cmd = Command::new("bash");
+            cmd.args(["-c", &format!("exec 3<>/dev/tcp/127.0.0.1/{}", port)]);
+            plan.apply_to_command(&mut cmd);
+            assert_eq!(cmd.status().await.unwrap().success(), connects);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+
+/// Restrictions applied to Claude sessions spawned for an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionProfile {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Directories the session may write to in addition to the project directory
+    pub allowed_paths: Vec<String>,
+    /// Whether the session may use network tools
+    pub network_enabled: bool,
+    /// Shell commands the session may run; empty means no restriction
+    pub allowed_commands: Vec<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Create the execution profile table and link column on agents
+pub fn init_execution_profiles(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS execution_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            allowed_paths TEXT NOT NULL DEFAULT '[]',
+            network_enabled BOOLEAN NOT NULL DEFAULT 1,
+            allowed_commands TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    let _ = conn.execute(
+        "ALTER TABLE agents ADD COLUMN execution_profile_id INTEGER",
+        [],
+    );
+
+    Ok(())
+}
+
+fn row_to_profile(row: &Row) -> SqliteResult<ExecutionProfile> {
+    let allowed_paths: String = row.get(2)?;
+    let allowed_commands: String = row.get(4)?;
+    Ok(ExecutionProfile {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        allowed_paths: serde_json::from_str(&allowed_paths).unwrap_or_default(),
+        network_enabled: row.get(3)?,
+        allowed_commands: serde_json::from_str(&allowed_commands).unwrap_or_default(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+const PROFILE_COLUMNS: &str =
+    "id, name, allowed_paths, network_enabled, allowed_commands, created_at, updated_at";
+
+/// List all execution profiles ordered by name
+pub fn list_profiles(conn: &Connection) -> SqliteResult<Vec<ExecutionProfile>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM execution_profiles ORDER BY name",
+        PROFILE_COLUMNS
+    ))?;
+    let profiles = stmt
+        .query_map([], row_to_profile)?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(profiles)
+}
+
+/// Get an execution profile by ID
+pub fn get_profile(conn: &Connection, id: i64) -> SqliteResult<Option<ExecutionProfile>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM execution_profiles WHERE id = ?1",
+            PROFILE_COLUMNS
+        ),
+        params![id],
+        row_to_profile,
+    )
+    .optional()
+}
+
+/// Insert or update a profile, returning its ID
+pub fn save_profile(conn: &Connection, profile: &ExecutionProfile) -> SqliteResult<i64> {
+    let allowed_paths =
+        serde_json::to_string(&profile.allowed_paths).unwrap_or_else(|_| "[]".to_string());
+    let allowed_commands =
+        serde_json::to_string(&profile.allowed_commands).unwrap_or_else(|_| "[]".to_string());
+
+    match profile.id {
+        Some(id) => {
+            conn.execute(
+                "UPDATE execution_profiles
+                 SET name = ?1, allowed_paths = ?2, network_enabled = ?3, allowed_commands = ?4,
+                     updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?5",
+                params![
+                    profile.name,
+                    allowed_paths,
+                    profile.network_enabled,
+                    allowed_commands,
+                    id
+                ],
+            )?;
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO execution_profiles (name, allowed_paths, network_enabled, allowed_commands)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    profile.name,
+                    allowed_paths,
+                    profile.network_enabled,
+                    allowed_commands
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Delete a profile and detach it from any agents using it
+pub fn delete_profile(conn: &Connection, id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE agents SET execution_profile_id = NULL WHERE execution_profile_id = ?1",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM execution_profiles WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Assign a profile to an agent, or clear it with `None`
+pub fn set_agent_profile(
+    conn: &Connection,
+    agent_id: i64,
+    profile_id: Option<i64>,
+) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE agents SET execution_profile_id = ?1 WHERE id = ?2",
+        params![profile_id, agent_id],
+    )?;
+    Ok(())
+}
+
+/// Get the profile assigned to an agent, if any
+pub fn profile_for_agent(
+    conn: &Connection,
+    agent_id: i64,
+) -> SqliteResult<Option<ExecutionProfile>> {
+    let profile_id: Option<i64> = conn
+        .query_row(
+            "SELECT execution_profile_id FROM agents WHERE id = ?1",
+            params![agent_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+
+    match profile_id {
+        Some(id) => get_profile(conn, id),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE agents (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        init_execution_profiles(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_agent_profile_roundtrip() {
+        let conn = setup();
+        conn.execute("INSERT INTO agents (name) VALUES ('reviewer')", [])
+            .unwrap();
+        let agent_id = conn.last_insert_rowid();
+
+        let profile_id = save_profile(
+            &conn,
+            &ExecutionProfile {
+                id: None,
+                name: "locked-down".to_string(),
+                allowed_paths: vec!["/tmp/scratch".to_string()],
+                network_enabled: false,
+                allowed_commands: vec!["git".to_string(), "cargo".to_string()],
+                created_at: None,
+                updated_at: None,
+            },
+        )
+        .unwrap();
+
+        assert!(profile_for_agent(&conn, agent_id).unwrap().is_none());
+        set_agent_profile(&conn, agent_id, Some(profile_id)).unwrap();
+
+        let profile = profile_for_agent(&conn, agent_id).unwrap().unwrap();
+        assert_eq!(profile.name, "locked-down");
+        assert!(!profile.network_enabled);
+        assert_eq!(profile.allowed_commands, vec!["git", "cargo"]);
+
+        delete_profile(&conn, profile_id).unwrap();
+        assert!(profile_for_agent(&conn, agent_id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,330 @@
+// Scheduled agent runs
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, TimeZone,
+    Timelike, Utc,
+};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agents::{start_agent_run, AgentDb};
+use crate::commands::schedules::{due_schedules, record_schedule_run, Schedule};
+use crate::event_bus;
+use crate::process::ProcessRegistryState;
+use crate::run_queue::{self, NewQueuedRun};
+
+/// How often the loop looks for due schedules
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// A run this late is considered missed and handled by the catch-up policy
+const MISSED_AFTER_MINUTES: i64 = 10;
+/// How far ahead `next_after` searches before giving up
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A parsed five-field cron expression (minute hour day-of-month month day-of-week)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    /// Cron matches either day field when both are restricted
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32, what: &str) -> Result<Vec<bool>, String> {
+    let invalid = || format!("Invalid {} field '{}'", what, field);
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (
+                a.parse().map_err(|_| invalid())?,
+                b.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value: u32 = range.parse().map_err(|_| invalid())?;
+            // `5/15` means from 5 to the end in steps of 15
+            let end = if part.contains('/') { max } else { value };
+            (value, end)
+        };
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "{} ({} must be between {} and {})",
+                invalid(),
+                what,
+                min,
+                max
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+impl CronSchedule {
+    /// Parse `minute hour day-of-month month day-of-week`, or @hourly, @daily, @weekly, @monthly
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression '{}' must have five fields: minute hour day month weekday",
+                expression
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "day of week")?;
+        // Both 0 and 7 mean Sunday
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day of month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days[date.day() as usize];
+        let weekday = self.weekdays[date.weekday().num_days_from_sunday() as usize];
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// The first matching minute strictly after `after`, in local time
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let start =
+            after.naive_local().with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let limit = start + ChronoDuration::days(MAX_SEARCH_DAYS);
+
+        let mut t: NaiveDateTime = start;
+        while t < limit {
+            if !self.months[t.month() as usize] {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !self.hours[t.hour() as usize] {
+                t = t.with_minute(0)? + ChronoDuration::hours(1);
+            } else if !self.minutes[t.minute() as usize] {
+                t += ChronoDuration::minutes(1);
+            } else {
+                // Times skipped by a DST change don't exist; move on
+                match tz.from_local_datetime(&t).earliest() {
+                    Some(time) => return Some(time),
+                    None => t += ChronoDuration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Next run of `cron` after now, as stored in the schedules table (UTC, RFC 3339)
+pub fn next_run_at(cron: &str) -> Result<Option<String>, String> {
+    let schedule = CronSchedule::parse(cron)?;
+    Ok(schedule.next_after(&Local::now()).map(|t| {
+        t.with_timezone(&Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }))
+}
+
+/// Start a run for `schedule` through the agent runner and record it on the schedule
+pub async fn run_schedule(app: &AppHandle, schedule: &Schedule) -> Result<i64, String> {
+    let result = start_agent_run(
+        app.clone(),
+        None,
+        schedule.agent_id,
+        schedule.project_path.clone(),
+        schedule.prompt.clone(),
+        schedule.model.clone(),
+        None,
+        None,
+        app.state::<AgentDb>(),
+        app.state::<ProcessRegistryState>(),
+    )
+    .await;
+
+    {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        record_schedule_run(&conn, schedule.id, &result)
+            .map_err(|e| format!("Failed to record schedule run: {}", e))?;
+    }
+    event_bus::publish(
+        app,
+        "schedule-run",
+        &serde_json::json!({
+            "schedule_id": schedule.id,
+            "name": schedule.name,
+            "agent_run_id": result.as_ref().ok(),
+            "error": result.as_ref().err(),
+        }),
+    );
+    result
+}
+
+async fn run_due_schedules(app: &AppHandle) -> Result<(), String> {
+    let now = Utc::now();
+    let (due, policy) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (
+            due_schedules(&conn, now).map_err(|e| e.to_string())?,
+            run_queue::catch_up_policy(&conn),
+        )
+    };
+
+    for (schedule, scheduled_for) in due {
+        let late = now - scheduled_for;
+        if late > ChronoDuration::minutes(MISSED_AFTER_MINUTES) {
+            match policy.as_str() {
+                "run" => log::info!(
+                    "Catching up on schedule '{}' missed at {}",
+                    schedule.name,
+                    scheduled_for
+                ),
+                "ask" => {
+                    log::info!(
+                        "Holding run of schedule '{}' missed at {}",
+                        schedule.name,
+                        scheduled_for
+                    );
+                    let db = app.state::<AgentDb>();
+                    let conn = db.0.lock().map_err(|e| e.to_string())?;
+                    let missed = NewQueuedRun {
+                        reason: run_queue::REASON_MISSED_SCHEDULE,
+                        agent_id: schedule.agent_id,
+                        project_path: schedule.project_path.clone(),
+                        task: schedule.prompt.clone(),
+                        model: schedule.model.clone(),
+                        schedule_id: Some(schedule.id),
+                        scheduled_for: Some(
+                            scheduled_for.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                        ),
+                        ..Default::default()
+                    };
+                    // One held catch-up per schedule is enough
+                    if !run_queue::has_queued_schedule_run(&conn, schedule.id)
+                        .map_err(|e| e.to_string())?
+                    {
+                        run_queue::hold(app, &conn, &missed).map_err(|e| e.to_string())?;
+                    }
+                    continue;
+                }
+                _ => {
+                    log::info!(
+                        "Skipping run of schedule '{}' missed at {}",
+                        schedule.name,
+                        scheduled_for
+                    );
+                    continue;
+                }
+            }
+        }
+        if schedule.last_run_status.as_deref() == Some("running") {
+            log::info!(
+                "Skipping schedule '{}': its previous run is still going",
+                schedule.name
+            );
+            continue;
+        }
+
+        log::info!("Starting scheduled run '{}'", schedule.name);
+        if let Err(e) = run_schedule(app, &schedule).await {
+            log::warn!("Scheduled run '{}' failed to start: {}", schedule.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// Check for due schedules periodically for as long as the app runs
+///
+/// Due schedules start through the normal agent runner, so results land in the
+/// run history like any other run. Missed windows are left to the catch-up
+/// policy in `run_queue`.
+pub fn spawn_scheduler_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = run_due_schedules(&app).await {
+                log::warn!("Scheduler check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cron_next_after() {
+        let at = |s: &str| {
+            Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+        };
+        let next = |cron: &str, from: &str| {
+            CronSchedule::parse(cron)
+                .unwrap()
+                .next_after(&at(from))
+                .unwrap()
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+
+        // Nightly at 02:30
+        assert_eq!(next("30 2 * * *", "2025-03-10 01:00"), "2025-03-10 02:30");
+        assert_eq!(next("30 2 * * *", "2025-03-10 02:30"), "2025-03-11 02:30");
+        // Every 15 minutes on weekdays; 2025-03-15 is a Saturday
+        assert_eq!(
+            next("*/15 * * * 1-5", "2025-03-14 23:50"),
+            "2025-03-17 00:00"
+        );
+        // Either day field matches when both are restricted
+        assert_eq!(next("0 0 13 * 5", "2025-03-10 00:00"), "2025-03-13 00:00");
+        assert_eq!(next("@monthly", "2025-12-15 00:00"), "2026-01-01 00:00");
+        assert_eq!(next("0 9 * * 7", "2025-03-10 00:00"), "2025-03-16 09:00");
+
+        for bad in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(CronSchedule::parse(bad).is_err(), "accepted '{}'", bad);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Schema drift detection for GitHub catalog responses
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+/// What a response item is expected to look like
+pub struct Shape {
+    /// Fields the app reads; an item without them can't be used
+    pub required: &'static [&'static str],
+    /// Every field GitHub is known to send, used ones or not
+    pub known: &'static [&'static str],
+}
+
+/// An entry of a GitHub contents API directory listing or file lookup
+pub const GITHUB_CONTENT: Shape = Shape {
+    required: &["name", "path", "sha", "type"],
+    known: &[
+        "name",
+        "path",
+        "sha",
+        "size",
+        "url",
+        "html_url",
+        "git_url",
+        "download_url",
+        "type",
+        "_links",
+        "content",
+        "encoding",
+        "target",
+        "submodule_git_url",
+    ],
+};
+
+/// How far a source's last response strayed from the expected shape, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftStatus {
+    Ok,
+    /// New fields appeared; everything the app needs is still there
+    Drifted,
+    /// Items were dropped, required fields are missing or a fallback was used
+    Degraded,
+}
+
+/// What was observed the last time a source was fetched
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub source: String,
+    pub status: DriftStatus,
+    pub checked_at: String,
+    /// Items decoded successfully
+    pub items: usize,
+    /// Items that could not be decoded and were left out
+    pub skipped_items: usize,
+    pub unknown_fields: BTreeSet<String>,
+    pub missing_fields: BTreeSet<String>,
+    /// Why the response could not be used at all
+    pub error: Option<String>,
+}
+
+impl DriftReport {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            status: DriftStatus::Ok,
+            checked_at: chrono::Utc::now().to_rfc3339(),
+            items: 0,
+            skipped_items: 0,
+            unknown_fields: BTreeSet::new(),
+            missing_fields: BTreeSet::new(),
+            error: None,
+        }
+    }
+
+    fn classify(mut self) -> Self {
+        self.status =
+            if self.error.is_some() || self.skipped_items > 0 || !self.missing_fields.is_empty() {
+                DriftStatus::Degraded
+            } else if !self.unknown_fields.is_empty() {
+                DriftStatus::Drifted
+            } else {
+                DriftStatus::Ok
+            };
+        self
+    }
+
+    fn observe(&mut self, item: &JsonValue, shape: &Shape) {
+        let Some(fields) = item.as_object() else {
+            return;
+        };
+        self.unknown_fields.extend(
+            fields
+                .keys()
+                .filter(|k| !shape.known.contains(&k.as_str()))
+                .cloned(),
+        );
+        self.missing_fields.extend(
+            shape
+                .required
+                .iter()
+                .filter(|k| !fields.contains_key(**k))
+                .map(|k| k.to_string()),
+        );
+    }
+}
+
+fn reports() -> &'static Mutex<HashMap<String, DriftReport>> {
+    static REPORTS: OnceLock<Mutex<HashMap<String, DriftReport>>> = OnceLock::new();
+    REPORTS.get_or_init(Default::default)
+}
+
+fn record(report: DriftReport) {
+    let Ok(mut reports) = reports().lock() else {
+        return;
+    };
+    let previous = reports.get(&report.source);
+    let changed = previous.map_or(true, |p| {
+        p.status != report.status
+            || p.unknown_fields != report.unknown_fields
+            || p.missing_fields != report.missing_fields
+    });
+    if changed && report.status != DriftStatus::Ok {
+        log::warn!(
+            "GitHub response for {} changed shape ({:?}): unknown fields {:?}, missing fields {:?}, {} item(s) skipped{}",
+            report.source,
+            report.status,
+            report.unknown_fields,
+            report.missing_fields,
+            report.skipped_items,
+            report
+                .error
+                .as_deref()
+                .map(|e| format!(", {}", e))
+                .unwrap_or_default()
+        );
+    }
+    reports.insert(report.source.clone(), report);
+}
+
+/// Decode a list response item by item, skipping items that don't fit `T`
+///
+/// Fails only if the response isn't a list at all, so one unexpected item
+/// doesn't lose the whole catalog. Fields that were added or dropped are
+/// recorded per source for the UI.
+pub fn decode_list<T: DeserializeOwned>(
+    source: &str,
+    body: JsonValue,
+    shape: &Shape,
+) -> Result<Vec<T>, String> {
+    let mut report = DriftReport::new(source);
+    let items = match body {
+        JsonValue::Array(items) => items,
+        other => {
+            let error = format!("expected a list, got {}", json_kind(&other));
+            report.error = Some(error.clone());
+            record(report.classify());
+            return Err(format!(
+                "Unexpected GitHub response for {}: {}",
+                source, error
+            ));
+        }
+    };
+
+    let mut decoded = Vec::with_capacity(items.len());
+    for item in items {
+        report.observe(&item, shape);
+        match serde_json::from_value(item) {
+            Ok(value) => decoded.push(value),
+            Err(_) => report.skipped_items += 1,
+        }
+    }
+    report.items = decoded.len();
+    record(report.classify());
+    Ok(decoded)
+}
+
+/// Decode a single-object response
+pub fn decode_one<T: DeserializeOwned>(
+    source: &str,
+    body: JsonValue,
+    shape: &Shape,
+) -> Result<T, String> {
+    let mut report = DriftReport::new(source);
+    report.observe(&body, shape);
+    let result = serde_json::from_value(body);
+    match &result {
+        Ok(_) => report.items = 1,
+        Err(e) => report.error = Some(e.to_string()),
+    }
+    record(report.classify());
+    result.map_err(|e| format!("Unexpected GitHub response for {}: {}", source, e))
+}
+
+/// Note that `source` fell back to built-in data because the request failed
+pub fn record_fallback(source: &str, reason: &str) {
+    let mut report = DriftReport::new(source);
+    report.error = Some(format!("using the built-in list: {}", reason));
+    record(report.classify());
+}
+
+/// The latest report for every source fetched since the app started
+pub fn latest_reports() -> Vec<DriftReport> {
+    let mut reports: Vec<DriftReport> = reports()
+        .lock()
+        .map(|r| r.values().cloned().collect())
+        .unwrap_or_default();
+    reports.sort_by(|a, b| a.source.cmp(&b.source));
+    reports
+}
+
+fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Number(_) => "a number",
+        JsonValue::String(_) => "a string",
+        JsonValue::Array(_) => "a list",
+        JsonValue::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct Item {
+        name: String,
+    }
+
+    fn report(source: &str) -> DriftReport {
+        latest_reports()
+            .into_iter()
+            .find(|r| r.source == source)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decode_list_records_drift() {
+        let ok = json!([{"name": "a", "path": "a", "sha": "1", "type": "dir"}]);
+        assert_eq!(
+            decode_list::<Item>("test_ok", ok, &GITHUB_CONTENT)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(report("test_ok").status, DriftStatus::Ok);
+
+        let added = json!([{"name": "a", "path": "a", "sha": "1", "type": "dir", "license": null}]);
+        decode_list::<Item>("test_added", added, &GITHUB_CONTENT).unwrap();
+        let added = report("test_added");
+        assert_eq!(added.status, DriftStatus::Drifted);
+        assert!(added.unknown_fields.contains("license"));
+
+        // The item without a name is dropped, not the whole list
+        let renamed = json!([
+            {"name": "a", "path": "a", "sha": "1", "type": "dir"},
+            {"title": "b", "path": "b", "sha": "2", "type": "dir"}
+        ]);
+        let items = decode_list::<Item>("test_renamed", renamed, &GITHUB_CONTENT).unwrap();
+        assert_eq!(items.len(), 1);
+        let renamed = report("test_renamed");
+        assert_eq!(renamed.status, DriftStatus::Degraded);
+        assert_eq!(renamed.skipped_items, 1);
+        assert!(renamed.missing_fields.contains("name"));
+
+        assert!(
+            decode_list::<Item>("test_object", json!({"message": "x"}), &GITHUB_CONTENT).is_err()
+        );
+        assert_eq!(report("test_object").status, DriftStatus::Degraded);
+    }
+}
@@ -0,0 +1,186 @@
+// Graceful shutdown when the app exits
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agents::AgentDb;
+use crate::commands::run_history;
+use crate::commands::settings as store;
+use crate::event_bus::EventBus;
+use crate::process::{ProcessInfo, ProcessRegistryState, ProcessType, RunUsage};
+
+/// What happens to runs still going when the app exits
+pub const RUN_POLICIES: &[&str] = &["terminate", "detach"];
+pub const DEFAULT_RUN_POLICY: &str = "terminate";
+
+/// Longest the app waits on running processes before exiting anyway
+const RUNS_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest the app waits for queued events to be written
+const EVENTS_TIMEOUT: Duration = Duration::from_secs(2);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The run history entry of a registered process that is still running
+fn running_history_id(conn: &Connection, info: &ProcessInfo) -> Option<i64> {
+    let result = match &info.process_type {
+        ProcessType::AgentRun { .. } => conn.query_row(
+            "SELECT id FROM run_history WHERE agent_run_id = ?1 AND status = 'running'",
+            params![info.run_id],
+            |row| row.get(0),
+        ),
+        ProcessType::ClaudeSession { session_id } => conn.query_row(
+            "SELECT id FROM run_history WHERE session_id = ?1 AND status = 'running'",
+            params![session_id],
+            |row| row.get(0),
+        ),
+    };
+    result.optional().ok().flatten()
+}
+
+/// Record how a run ended at shutdown, with the usage it reported so far
+///
+/// Detached runs are recorded as `detached` so the next start doesn't mark
+/// them failed; their agent run stays `running` until it is seen to exit.
+fn record_run(conn: &Connection, info: &ProcessInfo, usage: &RunUsage, terminate: bool) {
+    let status = if terminate { "cancelled" } else { "detached" };
+    if let Some(history_id) = running_history_id(conn, info) {
+        if let Err(e) = run_history::record_run_finished(conn, history_id, status, None, usage) {
+            log::warn!("Failed to record run {} at shutdown: {}", info.run_id, e);
+        }
+    }
+    if terminate && matches!(info.process_type, ProcessType::AgentRun { .. }) {
+        let _ = conn.execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+            params![info.run_id],
+        );
+    }
+}
+
+/// Terminate or detach every registered process; returns how many were handled
+async fn settle_runs(app: &AppHandle) -> Result<usize, String> {
+    let registry = app.state::<ProcessRegistryState>();
+    let processes = registry.0.get_running_processes()?;
+    if processes.is_empty() {
+        return Ok(0);
+    }
+    let terminate = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        store::get_text(&conn, "shutdown_running_runs").as_deref() != Some("detach")
+    };
+
+    for info in &processes {
+        let usage = registry.0.get_run_usage(info.run_id).unwrap_or_default();
+        // Recorded before the kill, so the run's own exit handling doesn't mark it failed
+        {
+            let db = app.state::<AgentDb>();
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            record_run(&conn, info, &usage, terminate);
+        }
+        if terminate {
+            if let Err(e) = registry.0.kill_process(info.run_id).await {
+                log::warn!("Failed to stop run {} at shutdown: {}", info.run_id, e);
+            }
+        } else {
+            log::info!(
+                "Leaving run {} (PID {}) running after exit",
+                info.run_id,
+                info.pid
+            );
+        }
+    }
+    Ok(processes.len())
+}
+
+/// Close the database, after which late writes go to a throwaway in-memory one
+fn close_database(app: &AppHandle) -> Result<(), String> {
+    let db = app.state::<AgentDb>();
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = conn.execute_batch("PRAGMA optimize");
+    let scratch = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    std::mem::replace(&mut *conn, scratch)
+        .close()
+        .map_err(|(_, e)| e.to_string())
+}
+
+/// Bring the app down cleanly; later calls do nothing
+///
+/// Runs still going are terminated or left running, as chosen in settings,
+/// and the usage they reported so far is recorded. Queued bus events are then
+/// written, the database is closed, the launch marker is removed and buffered
+/// log lines are flushed. Waiting on processes and events is time-boxed so a
+/// stuck process can't keep the app from exiting.
+///
+/// Blocks until done, so call it from the event loop rather than an async task.
+pub fn shutdown(app: &AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let started = Instant::now();
+    log::info!("Shutting down");
+
+    tauri::async_runtime::block_on(async {
+        match tokio::time::timeout(RUNS_TIMEOUT, settle_runs(app)).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => log::info!("Settled {} running process(es)", count),
+            Ok(Err(e)) => log::warn!("Failed to settle running processes: {}", e),
+            Err(_) => log::warn!("Gave up waiting for running processes to stop"),
+        }
+        if let Some(bus) = app.try_state::<EventBus>() {
+            let unwritten = bus.flush(EVENTS_TIMEOUT).await;
+            if unwritten > 0 {
+                log::warn!("{} event(s) were not written before exit", unwritten);
+            }
+        }
+    });
+
+    if let Err(e) = close_database(app) {
+        log::warn!("Failed to close the database: {}", e);
+    }
+    crate::safe_mode::end_launch();
+    log::info!("Shutdown finished in {:?}", started.elapsed());
+    crate::logging::flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_run_detached_keeps_usage() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_history::init_run_history(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO run_history (run_type, project_path, prompt, model, session_id, status)
+             VALUES ('session', '/tmp/p', 'hi', 'sonnet', 's1', 'running')",
+            [],
+        )
+        .unwrap();
+        let info = ProcessInfo {
+            run_id: 1000,
+            process_type: ProcessType::ClaudeSession {
+                session_id: "s1".to_string(),
+            },
+            pid: 1,
+            started_at: chrono::Utc::now(),
+            project_path: "/tmp/p".to_string(),
+            task: "hi".to_string(),
+            model: "sonnet".to_string(),
+        };
+        let output =
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#;
+
+        record_run(&conn, &info, output, false);
+        let (status, input_tokens): (String, Option<i64>) = conn
+            .query_row(
+                "SELECT status, input_tokens FROM run_history WHERE session_id = 's1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(status, "detached");
+        assert_eq!(input_tokens, Some(10));
+        assert_eq!(running_history_id(&conn, &info), None);
+    }
+}
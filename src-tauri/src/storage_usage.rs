@@ -0,0 +1,600 @@
+// Disk usage of the app's files, and cleanup of what is no longer needed
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::commands::agents::AgentDb;
+use crate::commands::git::git_blocking;
+use crate::commands::settings::{get_bool, get_integer};
+use crate::file_audit::{self, FileOperation};
+
+/// Every category, in the order they are reported
+pub const STORAGE_CATEGORIES: &[&str] = &[
+    "database",
+    "database_backups",
+    "logs",
+    "checkpoints",
+    "worktrees",
+    "session_artifacts",
+    "caches",
+    "run_history",
+];
+
+const DATABASE_FILES: &[&str] = &["agents.db", "file_audit.db"];
+
+/// A file or directory counted in a category
+#[derive(Debug, Clone)]
+pub struct StorageItem {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub modified: SystemTime,
+    /// Nothing refers to it any more; removed by every cleanup
+    pub orphaned: bool,
+    /// Still in use; never removed
+    pub protected: bool,
+}
+
+/// Disk used by one category
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryUsage {
+    pub category: String,
+    pub bytes: u64,
+    /// Files or directories; rows for `run_history`, whose size counts
+    /// towards `database`
+    pub items: u64,
+    /// Part of `bytes` a cleanup removes regardless of age and size limits
+    pub orphaned_bytes: u64,
+    pub orphaned_items: u64,
+}
+
+/// Disk used by the app, by category
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub total_bytes: u64,
+    pub categories: Vec<CategoryUsage>,
+}
+
+/// What `cleanup` removes
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CleanupOptions {
+    /// Categories to clean; all of them when `None`
+    pub categories: Option<Vec<String>>,
+    /// Remove items last changed longer ago than this
+    pub max_age_days: Option<u32>,
+    /// Remove the oldest items until each category uses at most this much
+    pub max_size_mb: Option<u64>,
+    /// Report what would be removed without removing it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What a cleanup removed from one category
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCleanup {
+    pub category: String,
+    pub items_removed: u64,
+    pub bytes_freed: u64,
+}
+
+/// What a cleanup removed, or would remove on a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    pub items_removed: u64,
+    pub bytes_freed: u64,
+    pub categories: Vec<CategoryCleanup>,
+}
+
+/// Size of a file, or of everything under a directory
+fn disk_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+fn item(path: PathBuf) -> Option<StorageItem> {
+    let modified = fs::symlink_metadata(&path).ok()?.modified().ok()?;
+    Some(StorageItem {
+        bytes: disk_size(&path),
+        path,
+        modified,
+        orphaned: false,
+        protected: false,
+    })
+}
+
+fn children(dir: &Path) -> Vec<StorageItem> {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().filter_map(|e| item(e.path())).collect())
+        .unwrap_or_default()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Items to remove: orphans, then items older than `max_age`, then the oldest
+/// until the rest fit in `max_bytes`; protected items are never picked
+pub fn select_for_cleanup(
+    items: &[StorageItem],
+    max_age: Option<Duration>,
+    max_bytes: Option<u64>,
+    now: SystemTime,
+) -> Vec<usize> {
+    let expired = |item: &StorageItem| {
+        max_age.is_some_and(|max| now.duration_since(item.modified).unwrap_or_default() > max)
+    };
+    let mut remove: Vec<bool> = items
+        .iter()
+        .map(|item| !item.protected && (item.orphaned || expired(item)))
+        .collect();
+
+    if let Some(max_bytes) = max_bytes {
+        let mut kept: u64 = items
+            .iter()
+            .zip(&remove)
+            .filter(|(_, removed)| !**removed)
+            .map(|(item, _)| item.bytes)
+            .sum();
+        let mut oldest_first: Vec<usize> = (0..items.len())
+            .filter(|&i| !remove[i] && !items[i].protected)
+            .collect();
+        oldest_first.sort_by_key(|&i| items[i].modified);
+        for i in oldest_first {
+            if kept <= max_bytes {
+                break;
+            }
+            remove[i] = true;
+            kept -= items[i].bytes;
+        }
+    }
+    (0..items.len()).filter(|&i| remove[i]).collect()
+}
+
+/// Where the app keeps its files, and what is still in use
+struct StorageScan {
+    app_dir: PathBuf,
+    claude_dir: Option<PathBuf>,
+    worktrees_dir: Option<PathBuf>,
+    known_worktrees: HashSet<PathBuf>,
+}
+
+impl StorageScan {
+    fn new(app_dir: &Path, conn: &Connection) -> Self {
+        // `path` is the project inside the worktree, which is a subdirectory
+        // when the project isn't at the repository root
+        let known_worktrees = conn
+            .prepare("SELECT worktree_path FROM agent_worktrees")
+            .and_then(|mut stmt| {
+                let paths = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<HashSet<String>>>()?;
+                Ok(paths)
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        Self {
+            app_dir: app_dir.to_path_buf(),
+            claude_dir: crate::utils::get_claude_dir().ok(),
+            worktrees_dir: dirs::data_dir()
+                .map(|dir| dir.join(crate::utils::APP_IDENTIFIER).join("worktrees")),
+            known_worktrees,
+        }
+    }
+
+    /// Files and directories of a category; empty for `run_history`
+    fn items(&self, category: &str) -> Vec<StorageItem> {
+        match category {
+            "database" => DATABASE_FILES
+                .iter()
+                .flat_map(|db| ["", "-wal", "-shm"].map(|suffix| format!("{}{}", db, suffix)))
+                .filter_map(|name| item(self.app_dir.join(name)))
+                .map(|item| StorageItem {
+                    protected: true,
+                    ..item
+                })
+                .collect(),
+            "database_backups" => {
+                let mut backups = children(&self.app_dir.join("backups"));
+                if let Some(newest) = backups.iter_mut().max_by_key(|b| b.modified) {
+                    newest.protected = true;
+                }
+                backups
+            }
+            "logs" => {
+                let mut logs = crate::logging::log_dir()
+                    .map(|dir| children(&dir))
+                    .unwrap_or_default();
+                // The app and the web server each write to their newest file
+                let mut newest: HashMap<String, usize> = HashMap::new();
+                for (i, log) in logs.iter().enumerate() {
+                    let name = file_name(&log.path);
+                    let prefix = name.split('.').next().unwrap_or_default().to_string();
+                    let current = newest.entry(prefix).or_insert(i);
+                    if logs[*current].modified < log.modified {
+                        *current = i;
+                    }
+                }
+                for i in newest.into_values() {
+                    logs[i].protected = true;
+                }
+                logs
+            }
+            "checkpoints" => self
+                .project_dirs()
+                .into_iter()
+                .flat_map(|project| {
+                    children(&project.join(".timelines"))
+                        .into_iter()
+                        .map(move |mut timeline| {
+                            let session = file_name(&timeline.path);
+                            timeline.orphaned =
+                                !project.join(format!("{}.jsonl", session)).exists();
+                            timeline
+                        })
+                })
+                .collect(),
+            "worktrees" => self
+                .worktrees_dir
+                .as_deref()
+                .map(children)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|mut worktree| {
+                    worktree.orphaned = !self.known_worktrees.contains(&worktree.path);
+                    worktree.protected = !worktree.orphaned;
+                    worktree
+                })
+                .collect(),
+            "session_artifacts" => {
+                let Some(claude_dir) = &self.claude_dir else {
+                    return Vec::new();
+                };
+                let sessions = self.session_ids();
+                children(&claude_dir.join("todos"))
+                    .into_iter()
+                    .map(|mut todo| {
+                        // `<session>.json`, or `<session>-agent-<agent>.json` for subagents
+                        let name = file_name(&todo.path);
+                        let stem = name.strip_suffix(".json").unwrap_or(&name);
+                        let session = stem.split("-agent-").next().unwrap_or(stem);
+                        todo.orphaned = !sessions.contains(session);
+                        todo.protected = !todo.orphaned;
+                        todo
+                    })
+                    .collect()
+            }
+            "caches" => {
+                let mut caches = children(&std::env::temp_dir().join("opcode-fixtures"));
+                if let Some(claude_dir) = &self.claude_dir {
+                    caches.extend(children(&claude_dir.join("shell-snapshots")));
+                }
+                caches
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn project_dirs(&self) -> Vec<PathBuf> {
+        self.claude_dir
+            .as_ref()
+            .and_then(|dir| fs::read_dir(dir.join("projects")).ok())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// IDs of every session with a transcript
+    fn session_ids(&self) -> HashSet<String> {
+        self.project_dirs()
+            .iter()
+            .filter_map(|project| fs::read_dir(project).ok())
+            .flat_map(|entries| entries.flatten())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+            .filter_map(|p| Some(p.file_stem()?.to_string_lossy().to_string()))
+            .collect()
+    }
+}
+
+/// Remove a worktree through its repository, so git forgets it too
+///
+/// A directory that isn't a linked worktree (no `.git` file) is just deleted.
+fn remove_worktree(path: &Path) -> std::io::Result<()> {
+    let common_dir = if path.join(".git").is_file() {
+        git_blocking(
+            path,
+            &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+        )
+        .ok()
+        .map(|output| PathBuf::from(output.trim()))
+    } else {
+        None
+    };
+    let Some(common_dir) = common_dir else {
+        return fs::remove_dir_all(path);
+    };
+    let worktree = path.to_string_lossy();
+    if git_blocking(&common_dir, &["worktree", "remove", "--force", &worktree]).is_err() {
+        fs::remove_dir_all(path)?;
+        let _ = git_blocking(&common_dir, &["worktree", "prune"]);
+    }
+    Ok(())
+}
+
+fn remove_item(category: &str, item: &StorageItem) -> std::io::Result<()> {
+    if category == "worktrees" {
+        remove_worktree(&item.path)
+    } else if item.path.is_dir() {
+        fs::remove_dir_all(&item.path)
+    } else {
+        fs::remove_file(&item.path)
+    }
+}
+
+/// Finished runs older than `max_age_days` in the run history, removed unless
+/// `dry_run`
+fn prune_runs(conn: &Connection, max_age_days: u32, dry_run: bool) -> rusqlite::Result<u64> {
+    let age = format!("-{} days", max_age_days);
+    let filter = "status != 'running' AND started_at < datetime('now', ?1)";
+    let count = if dry_run {
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM run_history WHERE {}", filter),
+            params![age],
+            |row| row.get::<_, i64>(0),
+        )? as usize
+    } else {
+        conn.execute(
+            &format!("DELETE FROM run_history WHERE {}", filter),
+            params![age],
+        )?
+    };
+    Ok(count as u64)
+}
+
+/// Disk used by each category
+pub fn usage(db: &AgentDb, app_dir: &Path) -> Result<StorageUsage, String> {
+    let (scan, runs) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let runs: i64 = conn
+            .query_row("SELECT COUNT(*) FROM run_history", [], |row| row.get(0))
+            .unwrap_or(0);
+        (StorageScan::new(app_dir, &conn), runs)
+    };
+    let categories: Vec<CategoryUsage> = STORAGE_CATEGORIES
+        .iter()
+        .map(|&category| {
+            let items = scan.items(category);
+            let orphaned = items.iter().filter(|i| i.orphaned);
+            CategoryUsage {
+                category: category.to_string(),
+                bytes: items.iter().map(|i| i.bytes).sum(),
+                items: match category {
+                    "run_history" => runs as u64,
+                    _ => items.len() as u64,
+                },
+                orphaned_bytes: orphaned.clone().map(|i| i.bytes).sum(),
+                orphaned_items: orphaned.count() as u64,
+            }
+        })
+        .collect();
+    Ok(StorageUsage {
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        categories,
+    })
+}
+
+/// Remove orphaned, old and excess items
+///
+/// What a category still needs is never removed: the live database, the
+/// newest backup, the log files being written and the worktrees of agent runs.
+/// Run history is pruned by age only; a size limit doesn't apply to it.
+pub fn cleanup(
+    db: &AgentDb,
+    app_dir: &Path,
+    options: &CleanupOptions,
+) -> Result<CleanupReport, String> {
+    let categories: Vec<&str> = match &options.categories {
+        Some(requested) => {
+            if let Some(unknown) = requested
+                .iter()
+                .find(|c| !STORAGE_CATEGORIES.contains(&c.as_str()))
+            {
+                return Err(format!("Unknown storage category: {}", unknown));
+            }
+            STORAGE_CATEGORIES
+                .iter()
+                .copied()
+                .filter(|c| requested.iter().any(|r| r == c))
+                .collect()
+        }
+        None => STORAGE_CATEGORIES.to_vec(),
+    };
+    let scan = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        StorageScan::new(app_dir, &conn)
+    };
+    let max_age = options
+        .max_age_days
+        .map(|days| Duration::from_secs(u64::from(days) * 24 * 60 * 60));
+    let max_bytes = options.max_size_mb.map(|mb| mb * 1024 * 1024);
+    let now = SystemTime::now();
+
+    let mut report = CleanupReport {
+        dry_run: options.dry_run,
+        items_removed: 0,
+        bytes_freed: 0,
+        categories: Vec::new(),
+    };
+    for category in categories {
+        let mut cleaned = CategoryCleanup {
+            category: category.to_string(),
+            items_removed: 0,
+            bytes_freed: 0,
+        };
+        if category == "run_history" {
+            if let Some(days) = options.max_age_days {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                cleaned.items_removed =
+                    prune_runs(&conn, days, options.dry_run).map_err(|e| e.to_string())?;
+            }
+        } else {
+            let items = scan.items(category);
+            for i in select_for_cleanup(&items, max_age, max_bytes, now) {
+                let item = &items[i];
+                if !options.dry_run {
+                    if let Err(e) = remove_item(category, item) {
+                        log::warn!("Failed to remove {}: {}", item.path.display(), e);
+                        continue;
+                    }
+                    file_audit::record("cleanup_storage", FileOperation::Delete, &item.path);
+                }
+                cleaned.items_removed += 1;
+                cleaned.bytes_freed += item.bytes;
+            }
+        }
+        report.items_removed += cleaned.items_removed;
+        report.bytes_freed += cleaned.bytes_freed;
+        report.categories.push(cleaned);
+    }
+    if !options.dry_run && report.items_removed > 0 {
+        log::info!(
+            "Storage cleanup removed {} items, {} bytes",
+            report.items_removed,
+            report.bytes_freed
+        );
+    }
+    Ok(report)
+}
+
+/// The cleanup the `storage_cleanup` maintenance task does; `None` while
+/// automatic cleanup is off
+pub fn auto_cleanup_options(conn: &Connection) -> Option<CleanupOptions> {
+    if !get_bool(conn, "storage_auto_cleanup") {
+        return None;
+    }
+    let limit = |key| get_integer(conn, key).filter(|v| *v > 0);
+    Some(CleanupOptions {
+        categories: None,
+        max_age_days: limit("storage_cleanup_max_age_days").map(|v| v as u32),
+        max_size_mb: limit("storage_cleanup_max_size_mb").map(|v| v as u64),
+        dry_run: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_for_cleanup() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let entry = |bytes, days_old: u32, orphaned, protected| StorageItem {
+            path: PathBuf::new(),
+            bytes,
+            modified: now - day * days_old,
+            orphaned,
+            protected,
+        };
+        let items = vec![
+            entry(100, 1, true, false),   // orphan
+            entry(100, 40, false, false), // too old
+            entry(100, 90, false, true),  // protected
+            entry(300, 10, false, false),
+            entry(300, 5, false, false),
+        ];
+
+        assert_eq!(select_for_cleanup(&items, None, None, now), vec![0]);
+        assert_eq!(
+            select_for_cleanup(&items, Some(day * 30), None, now),
+            vec![0, 1]
+        );
+        // 700 bytes left after age pruning; the oldest unprotected goes first
+        assert_eq!(
+            select_for_cleanup(&items, Some(day * 30), Some(450), now),
+            vec![0, 1, 3]
+        );
+        assert_eq!(
+            select_for_cleanup(&items, None, Some(0), now),
+            vec![0, 1, 3, 4]
+        );
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let args = [
+            &["-c", "user.name=test", "-c", "user.email=test@example.com"],
+            args,
+        ]
+        .concat();
+        git_blocking(dir, &args).unwrap();
+    }
+
+    #[test]
+    fn test_live_worktrees_are_kept() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktrees = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        fs::create_dir_all(repo.path().join("app")).unwrap();
+        fs::write(repo.path().join("app/main.rs"), "fn main() {}").unwrap();
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["commit", "-q", "-m", "init"]);
+        let live = worktrees.path().join("repo-agent-live");
+        let orphan = worktrees.path().join("repo-agent-orphan");
+        for (dir, branch) in [(&live, "agent-live"), (&orphan, "agent-orphan")] {
+            let dir = dir.to_string_lossy();
+            git(repo.path(), &["worktree", "add", "-q", "-b", branch, &dir]);
+        }
+
+        // The project is in a subdirectory, so `path` is inside the worktree
+        let conn = Connection::open_in_memory().unwrap();
+        crate::commands::worktrees::init_worktrees(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO agent_worktrees (project_path, repo_root, worktree_path, path, branch, base_sha)
+             VALUES (?1, ?2, ?3, ?4, 'agent-live', 'abc')",
+            params![
+                repo.path().join("app").to_string_lossy(),
+                repo.path().to_string_lossy(),
+                live.to_string_lossy(),
+                live.join("app").to_string_lossy(),
+            ],
+        )
+        .unwrap();
+        let mut scan = StorageScan::new(repo.path(), &conn);
+        scan.worktrees_dir = Some(worktrees.path().to_path_buf());
+
+        let items = scan.items("worktrees");
+        let find = |path: &Path| items.iter().find(|i| i.path == path).unwrap();
+        assert!(find(&live).protected && !find(&live).orphaned);
+        assert!(find(&orphan).orphaned);
+        assert_eq!(
+            select_for_cleanup(&items, None, None, SystemTime::now()).len(),
+            1
+        );
+
+        remove_item("worktrees", find(&orphan)).unwrap();
+        assert!(!orphan.exists());
+        assert!(live.exists());
+        let list = git_blocking(repo.path(), &["worktree", "list"]).unwrap();
+        assert!(!list.contains("repo-agent-orphan"));
+        assert!(list.contains("repo-agent-live"));
+    }
+}
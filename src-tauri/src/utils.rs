@@ -1,12 +1,84 @@
-use std::path::PathBuf;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
-/// Gets the path to the ~/.claude directory
-pub fn get_claude_dir() -> Result<PathBuf, String> {
+/// Identifier used by Tauri to derive the app data directory
+pub(crate) const APP_IDENTIFIER: &str = "opcode.asterisk.so";
+
+/// Environment variable the Claude CLI reads to relocate its config directory
+pub const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Custom location chosen in the app settings, if any
+static CUSTOM_CLAUDE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Where the Claude directory location came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeDirSource {
+    Setting,
+    Environment,
+    Default,
+}
+
+/// Set or clear the custom Claude directory from the app settings
+pub fn set_custom_claude_dir(path: Option<PathBuf>) {
+    if let Ok(mut current) = CUSTOM_CLAUDE_DIR.write() {
+        *current = path;
+    }
+}
+
+/// The custom Claude directory from the app settings, if any
+pub fn custom_claude_dir() -> Option<PathBuf> {
+    CUSTOM_CLAUDE_DIR.read().ok().and_then(|p| p.clone())
+}
+
+/// Resolve the Claude directory without touching the filesystem
+///
+/// The app setting takes precedence over `CLAUDE_CONFIG_DIR`, which takes
+/// precedence over `~/.claude`.
+pub fn resolve_claude_dir() -> Result<(PathBuf, ClaudeDirSource), String> {
+    if let Some(path) = custom_claude_dir() {
+        return Ok((path, ClaudeDirSource::Setting));
+    }
+    if let Some(path) = std::env::var_os(CLAUDE_CONFIG_DIR_ENV).filter(|p| !p.is_empty()) {
+        return Ok((PathBuf::from(path), ClaudeDirSource::Environment));
+    }
     dirs::home_dir()
+        .map(|home| (home.join(".claude"), ClaudeDirSource::Default))
         .ok_or_else(|| "Could not find home directory".to_string())
-        .and_then(|home| {
-            home.join(".claude")
-                .canonicalize()
-                .map_err(|_| "Could not find ~/.claude directory".to_string())
-        })
+}
+
+/// Gets the path to the Claude directory (~/.claude by default), creating it if missing
+pub fn get_claude_dir() -> Result<PathBuf, String> {
+    let (path, _) = resolve_claude_dir()?;
+    if !path.exists() {
+        std::fs::create_dir_all(&path).map_err(|e| {
+            format!(
+                "Could not create Claude directory {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        log::info!("Created Claude directory at {}", path.display());
+    }
+    path.canonicalize().map_err(|e| {
+        format!(
+            "Could not access Claude directory {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Read and parse a JSON file
+pub fn read_json_file(path: &Path) -> Result<JsonValue, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Name of the directory under `projects/` where Claude keeps a project's sessions
+pub fn encode_project_path(project_path: &str) -> String {
+    project_path.replace('/', "-")
 }
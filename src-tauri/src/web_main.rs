@@ -12,11 +12,15 @@ struct Args {
     /// Host to bind to (0.0.0.0 for all interfaces)
     #[arg(short = 'H', long, default_value = "0.0.0.0")]
     host: String,
+
+    /// Expose Prometheus-compatible metrics at /metrics (localhost only)
+    #[arg(long)]
+    metrics: bool,
 }
 
 #[tokio::main]
 async fn main() {
-    env_logger::init();
+    opcode_lib::logging::init("opcode-web");
 
     let args = Args::parse();
 
@@ -26,7 +30,7 @@ async fn main() {
         args.host, args.port
     );
 
-    if let Err(e) = web_server::start_web_mode(Some(args.port)).await {
+    if let Err(e) = web_server::start_web_mode(Some(args.port), args.metrics).await {
         eprintln!("❌ Failed to start web server: {}", e);
         std::process::exit(1);
     }
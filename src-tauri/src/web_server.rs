@@ -1,8 +1,8 @@
 use axum::extract::ws::{Message, WebSocket};
-use axum::http::Method;
+use axum::http::{header, Method, StatusCode};
 use axum::{
-    extract::{Path, State as AxumState, WebSocketUpgrade},
-    response::{Html, Json, Response},
+    extract::{ConnectInfo, Path, State as AxumState, WebSocketUpgrade},
+    response::{Html, IntoResponse, Json, Response},
     routing::get,
     Router,
 };
@@ -11,6 +11,7 @@ use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
@@ -19,6 +20,7 @@ use tower_http::services::ServeDir;
 use which;
 
 use crate::commands;
+use crate::metrics;
 
 // Find Claude binary for web mode - use bundled binary first
 fn find_claude_binary_web() -> Result<String, String> {
@@ -64,6 +66,16 @@ pub struct AppState {
     // Track active WebSocket sessions for Claude execution
     pub active_sessions:
         Arc<Mutex<std::collections::HashMap<String, tokio::sync::mpsc::Sender<String>>>>,
+    // Counters for executions started through the web server
+    pub web_metrics: Arc<WebMetrics>,
+}
+
+/// In-process counters for Claude executions driven over WebSocket
+#[derive(Default)]
+pub struct WebMetrics {
+    pub executions_started: AtomicU64,
+    pub executions_failed: AtomicU64,
+    pub executions_in_flight: AtomicU64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -249,6 +261,62 @@ async fn get_claude_session_output(Path(session_id): Path<String>) -> Json<ApiRe
     ))
 }
 
+/// Prometheus metrics endpoint - only served to loopback clients
+async fn prometheus_metrics(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    AxumState(state): AxumState<AppState>,
+) -> Response {
+    if !addr.ip().is_loopback() {
+        return (
+            StatusCode::FORBIDDEN,
+            "Metrics are only available from localhost",
+        )
+            .into_response();
+    }
+
+    let mut writer = metrics::PrometheusWriter::new();
+
+    // Run history is shared with the desktop app through the agents database
+    let snapshot = metrics::app_database_path()
+        .filter(|path| path.exists())
+        .and_then(|path| rusqlite::Connection::open(path).ok())
+        .and_then(|conn| metrics::collect_run_metrics(&conn).ok())
+        .unwrap_or_default();
+    metrics::write_run_metrics(&mut writer, &snapshot);
+
+    let web = &state.web_metrics;
+    writer.metric(
+        "opcode_web_executions_total",
+        "counter",
+        "Claude executions started over the web server",
+        web.executions_started.load(Ordering::Relaxed) as f64,
+    );
+    writer.metric(
+        "opcode_web_execution_failures_total",
+        "counter",
+        "Claude executions over the web server that failed",
+        web.executions_failed.load(Ordering::Relaxed) as f64,
+    );
+    writer.metric(
+        "opcode_web_executions_in_flight",
+        "gauge",
+        "Claude executions over the web server currently running",
+        web.executions_in_flight.load(Ordering::Relaxed) as f64,
+    );
+    writer.metric(
+        "opcode_web_sessions_active",
+        "gauge",
+        "Open WebSocket sessions",
+        state.active_sessions.lock().await.len() as f64,
+    );
+
+    (
+        [(header::CONTENT_TYPE, metrics::PROMETHEUS_CONTENT_TYPE)],
+        writer.finish(),
+    )
+        .into_response()
+}
+
 /// WebSocket handler for Claude execution with streaming output
 async fn claude_websocket(ws: WebSocketUpgrade, AxumState(state): AxumState<AppState>) -> Response {
     ws.on_upgrade(move |socket| claude_websocket_handler(socket, state))
@@ -324,6 +392,14 @@ async fn claude_websocket_handler(socket: WebSocket, state: AppState) {
                         );
                         tokio::spawn(async move {
                             println!("[TRACE] Task started for command execution");
+                            let web_metrics = state_clone.web_metrics.clone();
+                            web_metrics
+                                .executions_started
+                                .fetch_add(1, Ordering::Relaxed);
+                            web_metrics
+                                .executions_in_flight
+                                .fetch_add(1, Ordering::Relaxed);
+
                             let result = match request.command_type.as_str() {
                                 "execute" => {
                                     println!("[TRACE] Calling execute_claude_command");
@@ -373,6 +449,15 @@ async fn claude_websocket_handler(socket: WebSocket, state: AppState) {
                                 result
                             );
 
+                            web_metrics
+                                .executions_in_flight
+                                .fetch_sub(1, Ordering::Relaxed);
+                            if result.is_err() {
+                                web_metrics
+                                    .executions_failed
+                                    .fetch_add(1, Ordering::Relaxed);
+                            }
+
                             // Send completion message
                             if let Some(sender) = state_clone
                                 .active_sessions
@@ -767,9 +852,15 @@ async fn send_to_session(state: &AppState, session_id: &str, message: String) {
 }
 
 /// Create the web server
-pub async fn create_web_server(port: u16) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// When `enable_metrics` is set, a Prometheus-compatible `/metrics` endpoint is exposed to localhost.
+pub async fn create_web_server(
+    port: u16,
+    enable_metrics: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let state = AppState {
         active_sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        web_metrics: Arc::new(WebMetrics::default()),
     };
 
     // CORS layer to allow requests from phone browsers
@@ -779,7 +870,7 @@ pub async fn create_web_server(port: u16) -> Result<(), Box<dyn std::error::Erro
         .allow_headers(Any);
 
     // Create router with API endpoints
-    let app = Router::new()
+    let mut router = Router::new()
         // Frontend routes
         .route("/", get(serve_frontend))
         .route("/index.html", get(serve_frontend))
@@ -821,7 +912,14 @@ pub async fn create_web_server(port: u16) -> Result<(), Box<dyn std::error::Erro
             get(get_claude_session_output),
         )
         // WebSocket endpoint for real-time Claude execution
-        .route("/ws/claude", get(claude_websocket))
+        .route("/ws/claude", get(claude_websocket));
+
+    // Optional Prometheus metrics endpoint
+    if enable_metrics {
+        router = router.route("/metrics", get(prometheus_metrics));
+    }
+
+    let app = router
         // Serve static assets
         .nest_service("/assets", ServeDir::new("../dist/assets"))
         .nest_service("/vite.svg", ServeDir::new("../dist/vite.svg"))
@@ -831,17 +929,27 @@ pub async fn create_web_server(port: u16) -> Result<(), Box<dyn std::error::Erro
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     println!("🌐 Web server running on http://0.0.0.0:{}", port);
     println!("📱 Access from phone: http://YOUR_PC_IP:{}", port);
+    if enable_metrics {
+        println!("📈 Metrics available at http://127.0.0.1:{}/metrics", port);
+    }
 
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Start web server mode (alternative to Tauri GUI)
-pub async fn start_web_mode(port: Option<u16>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn start_web_mode(
+    port: Option<u16>,
+    enable_metrics: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let port = port.unwrap_or(8080);
 
     println!("🚀 Starting Opcode in web server mode...");
-    create_web_server(port).await
+    create_web_server(port, enable_metrics).await
 }
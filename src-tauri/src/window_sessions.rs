@@ -0,0 +1,186 @@
+// Sessions attached to windows, so output doesn't bleed into other windows
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Which window each attached session belongs to
+///
+/// Sessions and agent runs are attached to the window that started them. A
+/// session is attached to one window at a time, and closing a window detaches
+/// its sessions.
+#[derive(Default)]
+pub struct WindowSessions {
+    windows: Mutex<HashMap<String, String>>,
+}
+
+impl WindowSessions {
+    /// Attach a session to a window; returns the window it was attached to before
+    pub fn attach(&self, session_id: &str, window_label: &str) -> Option<String> {
+        self.windows
+            .lock()
+            .ok()?
+            .insert(session_id.to_string(), window_label.to_string())
+    }
+
+    /// Detach a session; returns the window it was attached to
+    pub fn detach(&self, session_id: &str) -> Option<String> {
+        self.windows.lock().ok()?.remove(session_id)
+    }
+
+    pub fn window_of(&self, session_id: &str) -> Option<String> {
+        self.windows.lock().ok()?.get(session_id).cloned()
+    }
+
+    /// Sessions attached to a window, sorted
+    pub fn sessions_of(&self, window_label: &str) -> Vec<String> {
+        let mut sessions: Vec<String> = self
+            .windows
+            .lock()
+            .map(|windows| {
+                windows
+                    .iter()
+                    .filter(|(_, label)| label.as_str() == window_label)
+                    .map(|(session, _)| session.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        sessions.sort();
+        sessions
+    }
+
+    /// Detach every session of a closed window; returns how many there were
+    pub fn detach_window(&self, window_label: &str) -> usize {
+        let Ok(mut windows) = self.windows.lock() else {
+            return 0;
+        };
+        let before = windows.len();
+        windows.retain(|_, label| label != window_label);
+        before - windows.len()
+    }
+}
+
+/// The window a session is attached to, if it is still open
+pub fn window_for(app: &AppHandle, session_id: &str) -> Option<String> {
+    let label = app.try_state::<WindowSessions>()?.window_of(session_id)?;
+    app.get_webview_window(&label).map(|_| label)
+}
+
+/// The window an event should go to, if it is scoped to an attached session
+fn target_window(app: &AppHandle, event: &str) -> Option<String> {
+    let (_, session_id) = event.rsplit_once(':')?;
+    window_for(app, session_id)
+}
+
+/// Emit an event to the window its session is attached to, or to every window
+///
+/// The session is taken from the event's `:<id>` suffix. The frontend has to
+/// listen through its window (`listenInWindow`); a global `listen` receives
+/// events sent to any window.
+pub fn emit<S: Serialize + ?Sized>(app: &AppHandle, event: &str, payload: &S) {
+    emit_to_window(app, None, event, payload);
+}
+
+/// Emit like `emit`, but send an event that isn't scoped to an attached
+/// session, such as the generic `claude-output-batch` of a session whose ID
+/// isn't known yet, to `window` while it is open
+pub fn emit_to_window<S: Serialize + ?Sized>(
+    app: &AppHandle,
+    window: Option<&str>,
+    event: &str,
+    payload: &S,
+) {
+    let label = event_window(app, window, event);
+    emit_to_label(app, label.as_deref(), event, payload);
+}
+
+/// The window `emit_to_window` sends an event to; `None` means every window
+pub fn event_window(app: &AppHandle, window: Option<&str>, event: &str) -> Option<String> {
+    target_window(app, event).or_else(|| {
+        window
+            .filter(|label| app.get_webview_window(label).is_some())
+            .map(str::to_string)
+    })
+}
+
+/// Emit to the window with `label`, or to every window when it is `None`
+pub fn emit_to_label<S: Serialize + ?Sized>(
+    app: &AppHandle,
+    label: Option<&str>,
+    event: &str,
+    payload: &S,
+) {
+    let _ = match label {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit(event, payload),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_and_detach() {
+        let sessions = WindowSessions::default();
+        assert_eq!(sessions.attach("s1", "main"), None);
+        sessions.attach("s2", "project-api");
+        sessions.attach("s3", "project-api");
+        assert_eq!(
+            sessions.attach("s1", "project-web"),
+            Some("main".to_string())
+        );
+
+        assert_eq!(sessions.sessions_of("project-api"), vec!["s2", "s3"]);
+        assert!(sessions.sessions_of("main").is_empty());
+        assert_eq!(sessions.detach_window("project-api"), 2);
+        assert_eq!(sessions.window_of("s2"), None);
+        assert_eq!(sessions.detach("s1"), Some("project-web".to_string()));
+    }
+
+    #[test]
+    fn test_other_windows_do_not_receive_session_events() {
+        use std::sync::mpsc;
+        use tauri::{Listener, WebviewUrl, WebviewWindowBuilder};
+
+        let app = tauri::test::mock_builder()
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap();
+        app.manage(WindowSessions::default());
+        let (tx, rx) = mpsc::channel();
+        for label in ["main", "project-api"] {
+            let window = WebviewWindowBuilder::new(&app, label, WebviewUrl::default())
+                .build()
+                .unwrap();
+            for event in ["claude-output-batch:s1", "claude-output-batch:s2"] {
+                let tx = tx.clone();
+                window.listen(event, move |_| tx.send((label, event)).unwrap());
+            }
+        }
+        app.state::<WindowSessions>().attach("s1", "project-api");
+
+        emit(app.handle(), "claude-output-batch:s1", "line");
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![("project-api", "claude-output-batch:s1")]
+        );
+
+        // A session that isn't attached still reaches every window
+        emit(app.handle(), "claude-output-batch:s2", "line");
+        let mut received: Vec<_> = rx.try_iter().map(|(label, _)| label).collect();
+        received.sort();
+        assert_eq!(received, vec!["main", "project-api"]);
+
+        // ...unless the window that started it is known
+        emit_to_window(app.handle(), Some("main"), "claude-output-batch:s2", "line");
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![("main", "claude-output-batch:s2")]
+        );
+        emit_to_window(app.handle(), Some("main"), "claude-output-batch:s1", "line");
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![("project-api", "claude-output-batch:s1")]
+        );
+    }
+}
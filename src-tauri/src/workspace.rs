@@ -0,0 +1,101 @@
+// Multi-root workspaces: extra project roots a run can reach with `--add-dir`
+use crate::path_validation::validate_project_root;
+
+/// Most extra roots one run may reference
+const MAX_ADDITIONAL_DIRS: usize = 16;
+
+/// Check the extra roots of a run and return them canonicalized
+///
+/// Duplicates and the working directory itself are dropped.
+pub fn validate_additional_dirs(
+    project_path: &str,
+    dirs: &[String],
+) -> Result<Vec<String>, String> {
+    if dirs.is_empty() {
+        return Ok(Vec::new());
+    }
+    if dirs.len() > MAX_ADDITIONAL_DIRS {
+        return Err(format!(
+            "A run can reference at most {} additional directories",
+            MAX_ADDITIONAL_DIRS
+        ));
+    }
+    let project = validate_project_root(project_path)?;
+    let mut validated: Vec<String> = Vec::new();
+    for dir in dirs {
+        let root = validate_project_root(dir)?;
+        if root == project {
+            continue;
+        }
+        let root = root.to_string_lossy().to_string();
+        if !validated.contains(&root) {
+            validated.push(root);
+        }
+    }
+    Ok(validated)
+}
+
+/// Claude arguments giving a run access to the extra roots
+pub fn add_dir_args(dirs: &[String]) -> Vec<String> {
+    dirs.iter()
+        .flat_map(|dir| ["--add-dir".to_string(), dir.clone()])
+        .collect()
+}
+
+/// Extra roots passed in a run's arguments
+pub fn additional_dirs_from_args(args: &[String]) -> Vec<String> {
+    args.windows(2)
+        .filter(|pair| pair[0] == "--add-dir")
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+/// Share of a run's usage attributed to each of its roots
+///
+/// Usage is split evenly: a transcript doesn't say which root a request
+/// worked on.
+pub fn usage_shares(project_path: &str, additional_dirs: &[String]) -> Vec<(String, f64)> {
+    let share = 1.0 / (additional_dirs.len() + 1) as f64;
+    std::iter::once(project_path)
+        .chain(additional_dirs.iter().map(String::as_str))
+        .map(|root| (root.to_string(), share))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_additional_dirs() {
+        let project = tempfile::tempdir().unwrap();
+        let backend = tempfile::tempdir().unwrap();
+        let project_path = project.path().to_string_lossy().to_string();
+        let backend_path = backend
+            .path()
+            .canonicalize()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let dirs = validate_additional_dirs(
+            &project_path,
+            &[
+                backend_path.clone(),
+                project_path.clone(),
+                backend_path.clone(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(dirs, [backend_path.clone()]);
+        assert!(validate_additional_dirs(&project_path, &["relative".to_string()]).is_err());
+
+        let mut args = vec!["-p".to_string(), "Fix the API".to_string()];
+        args.extend(add_dir_args(&dirs));
+        assert_eq!(additional_dirs_from_args(&args), dirs);
+        assert_eq!(
+            usage_shares("/frontend", &dirs),
+            [("/frontend".to_string(), 0.5), (backend_path, 0.5)]
+        );
+    }
+}
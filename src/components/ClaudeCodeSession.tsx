@@ -19,12 +19,11 @@ import { Popover } from "@/components/ui/popover";
 import { api, type Session } from "@/lib/api";
 import { cn } from "@/lib/utils";
 
-// Import Tauri event listener directly
-import { listen as tauriListen } from "@tauri-apps/api/event";
+import { attachToThisWindow, listenInWindow, listenOutputLines } from "@/lib/outputEvents";
 type UnlistenFn = () => void;
 
-// Use Tauri listen directly - we're in a Tauri app context
-const listen = tauriListen;
+// Listen in this window only, so sessions attached to other windows don't show up here
+const listen = listenInWindow;
 import { StreamMessage } from "./StreamMessage";
 import { FloatingPromptInput, type FloatingPromptInputRef } from "./FloatingPromptInput";
 import { ErrorBoundary } from "./ErrorBoundary";
@@ -232,6 +231,40 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
     return null;
   }, [session, extractedSessionInfo, projectPath]);
 
+  // Persist queued prompts per session so closing the window mid-run doesn't lose them
+  const queueStorageKey = effectiveSession ? `queued_prompts_${effectiveSession.id}` : null;
+  const restoredQueueKeyRef = useRef<string | null>(null);
+
+  useEffect(() => {
+    if (!queueStorageKey || restoredQueueKeyRef.current === queueStorageKey) return;
+    restoredQueueKeyRef.current = queueStorageKey;
+    try {
+      const stored = localStorage.getItem(queueStorageKey);
+      if (stored) {
+        const restored: Array<{ id: string; prompt: string; model: string }> = JSON.parse(stored);
+        setQueuedPrompts(prev => [
+          ...restored.filter(r => !prev.some(p => p.id === r.id)),
+          ...prev,
+        ]);
+      }
+    } catch (e) {
+      console.warn('[ClaudeCodeSession] Failed to restore queued prompts:', e);
+    }
+  }, [queueStorageKey]);
+
+  useEffect(() => {
+    if (!queueStorageKey || restoredQueueKeyRef.current !== queueStorageKey) return;
+    try {
+      if (queuedPrompts.length > 0) {
+        localStorage.setItem(queueStorageKey, JSON.stringify(queuedPrompts));
+      } else {
+        localStorage.removeItem(queueStorageKey);
+      }
+    } catch (e) {
+      console.warn('[ClaudeCodeSession] Failed to persist queued prompts:', e);
+    }
+  }, [queuedPrompts, queueStorageKey]);
+
   // Filter out messages that shouldn't be displayed AND merge result stats
   const displayableMessages = useMemo(() => {
     const result: ClaudeStreamMessage[] = [];
@@ -561,16 +594,19 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
     // Mark as listening
     isListeningRef.current = true;
 
+    // The session may have been started in another window
+    await attachToThisWindow(sessionId);
+
     // Set up session-specific listeners
-    const outputUnlisten = await listen(`claude-output:${sessionId}`, async (event: any) => {
+    const outputUnlisten = await listenOutputLines(`claude-output-batch:${sessionId}`, (line) => {
       try {
         if (!isMountedRef.current) return;
 
         // Parse and use buffered update for streaming optimization
-        const message = JSON.parse(event.payload) as ClaudeStreamMessage;
+        const message = JSON.parse(line) as ClaudeStreamMessage;
         addMessageToBuffer(message);
       } catch (err) {
-        console.error("Failed to parse message:", err, event.payload);
+        console.error("Failed to parse message:", err, line);
       }
     });
 
@@ -658,8 +694,8 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
         const attachSessionSpecificListeners = async (sid: string) => {
           console.log('[ClaudeCodeSession] Attaching session-specific listeners for', sid);
 
-          const specificOutputUnlisten = await listen(`claude-output:${sid}`, (evt: any) => {
-            handleStreamMessage(evt.payload);
+          const specificOutputUnlisten = await listenOutputLines(`claude-output-batch:${sid}`, (line) => {
+            handleStreamMessage(line);
           });
 
           const specificErrorUnlisten = await listen(`claude-error:${sid}`, (evt: any) => {
@@ -683,12 +719,12 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
           await attachSessionSpecificListeners(currentSessionId);
         } else {
           // Generic listeners (catch-all) - only needed for new sessions
-          const genericOutputUnlisten = await listen('claude-output', async (event: any) => {
-            handleStreamMessage(event.payload);
+          const genericOutputUnlisten = await listenOutputLines('claude-output-batch', async (line) => {
+            handleStreamMessage(line);
 
             // Attempt to extract session_id on the fly (for the very first init)
             try {
-              const msg = JSON.parse(event.payload) as ClaudeStreamMessage;
+              const msg = JSON.parse(line) as ClaudeStreamMessage;
               if (msg.type === 'system' && msg.subtype === 'init' && msg.session_id) {
                 if (!currentSessionId || currentSessionId !== msg.session_id) {
                   console.log('[ClaudeCodeSession] Detected new session_id from generic listener:', msg.session_id);
@@ -16,6 +16,7 @@ import { Input } from "@/components/ui/input";
 import { Card, CardContent, CardFooter } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
 import { api, type GitHubAgentFile, type AgentExport, type Agent } from "@/lib/api";
+import { describeNetworkError } from "@/lib/apiAdapter";
 import { type AgentIconName } from "./CCAgents";
 import { ICON_MAP } from "./IconPicker";
 import { open } from "@tauri-apps/plugin-shell";
@@ -70,7 +71,12 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
       setAgents(agentFiles);
     } catch (err) {
       console.error("Failed to fetch GitHub agents:", err);
-      setError("Failed to fetch agents from GitHub. Please check your internet connection.");
+      setError(
+        describeNetworkError(
+          err,
+          "Failed to fetch agents from GitHub. Please check your internet connection."
+        )
+      );
     } finally {
       setLoading(false);
     }
@@ -98,7 +104,7 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
         file,
         data: null,
         loading: false,
-        error: "Failed to load agent details",
+        error: describeNetworkError(err, "Failed to load agent details"),
       });
     }
   };
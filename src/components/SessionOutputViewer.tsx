@@ -10,7 +10,8 @@ import { api } from '@/lib/api';
 import { calculateToolResultsMap } from "@/lib/utils";
 import { useOutputCache } from '@/lib/outputCache';
 import type { AgentRun } from '@/lib/api';
-import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { type UnlistenFn } from '@tauri-apps/api/event';
+import { attachToThisWindow, listenInWindow, listenOutputLines } from '@/lib/outputEvents';
 import { StreamMessage } from './StreamMessage';
 import { ErrorBoundary } from './ErrorBoundary';
 
@@ -217,31 +218,34 @@ export function SessionOutputViewer({ session, onClose, className }: SessionOutp
       unlistenRefs.current.forEach(unlisten => unlisten());
       unlistenRefs.current = [];
 
+      // The run may have been started in another window
+      await attachToThisWindow(String(session.id));
+
       // Set up live event listeners with run ID isolation
-      const outputUnlisten = await listen<string>(`agent-output:${session.id}`, (event) => {
+      const outputUnlisten = await listenOutputLines(`agent-output-batch:${session.id}`, (line) => {
         try {
           // Store raw JSONL
-          setRawJsonlOutput(prev => [...prev, event.payload]);
+          setRawJsonlOutput(prev => [...prev, line]);
 
           // Parse and display
-          const message = JSON.parse(event.payload) as ClaudeStreamMessage;
+          const message = JSON.parse(line) as ClaudeStreamMessage;
           setMessages(prev => [...prev, message]);
         } catch (err) {
-          console.error("Failed to parse message:", err, event.payload);
+          console.error("Failed to parse message:", err, line);
         }
       });
 
-      const errorUnlisten = await listen<string>(`agent-error:${session.id}`, (event) => {
+      const errorUnlisten = await listenInWindow<string>(`agent-error:${session.id}`, (event) => {
         console.error("Agent error:", event.payload);
         setToast({ message: event.payload, type: 'error' });
       });
 
-      const completeUnlisten = await listen<boolean>(`agent-complete:${session.id}`, () => {
+      const completeUnlisten = await listenInWindow<boolean>(`agent-complete:${session.id}`, () => {
         setToast({ message: 'Agent execution completed', type: 'success' });
         // Don't set status here as the parent component should handle it
       });
 
-      const cancelUnlisten = await listen<boolean>(`agent-cancelled:${session.id}`, () => {
+      const cancelUnlisten = await listenInWindow<boolean>(`agent-cancelled:${session.id}`, () => {
         setToast({ message: 'Agent execution was cancelled', type: 'error' });
       });
 
@@ -227,1644 +227,4619 @@ export interface ProjectUsage {
   last_used: string;
 }
 
-export interface UsageStats {
-  total_cost: number;
-  total_tokens: number;
-  total_input_tokens: number;
-  total_output_tokens: number;
-  total_cache_creation_tokens: number;
-  total_cache_read_tokens: number;
-  total_sessions: number;
-  by_model: ModelUsage[];
-  by_date: DailyUsage[];
-  by_project: ProjectUsage[];
+/**
+ * End-of-month spend forecast for a project
+ */
+export interface ProjectForecast {
+  project_path: string;
+  month_to_date_usd: number;
+  daily_rate_usd: number;
+  projected_month_end_usd: number;
+  lower_bound_usd: number;
+  upper_bound_usd: number;
+  budget_usd: number | null;
+  projected_over_budget: boolean;
 }
 
+export type CheckStatus = "pass" | "skipped" | "warn" | "fail";
+
 /**
- * Represents a checkpoint in the session timeline
+ * Result of one doctor check
  */
-export interface Checkpoint {
+export interface DoctorCheck {
   id: string;
-  sessionId: string;
-  projectId: string;
-  messageIndex: number;
+  name: string;
+  status: CheckStatus;
+  detail: string;
+  fix: string | null;
+}
+
+/**
+ * Everything the doctor checked; `status` is the worst result
+ */
+export interface DoctorReport {
+  status: CheckStatus;
+  checks: DoctorCheck[];
+  checked_at: string;
+}
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+/**
+ * One entry from the app's log files
+ */
+export interface LogEntry {
   timestamp: string;
-  description?: string;
-  parentCheckpointId?: string;
-  metadata: CheckpointMetadata;
+  /** Upper case, e.g. "WARN" */
+  level: string;
+  target: string;
+  message: string;
+  fields: Record<string, unknown>;
 }
 
 /**
- * Metadata associated with a checkpoint
+ * Options for generating synthetic Claude data
  */
-export interface CheckpointMetadata {
-  totalTokens: number;
-  modelUsed: string;
-  userPrompt: string;
-  fileChanges: number;
-  snapshotSize: number;
+export interface FixtureOptions {
+  seed?: number;
+  projects?: number;
+  sessions_per_project?: number;
+  messages_per_session?: number;
+  months?: number;
+  /** YYYY-MM-DD, defaults to today */
+  end_date?: string;
 }
 
 /**
- * Represents a file snapshot at a checkpoint
+ * Where synthetic Claude data was written and how much
  */
-export interface FileSnapshot {
-  checkpointId: string;
-  filePath: string;
-  content: string;
-  hash: string;
-  isDeleted: boolean;
-  permissions?: number;
-  size: number;
+export interface FixtureSummary {
+  path: string;
+  projects: number;
+  sessions: number;
+  messages: number;
+  bytes: number;
 }
 
 /**
- * Represents a node in the timeline tree
+ * Inputs of a past run, recorded so it can be reproduced or audited
  */
-export interface TimelineNode {
-  checkpoint: Checkpoint;
-  children: TimelineNode[];
-  fileSnapshotIds: string[];
+export interface RunManifest {
+  manifest_version: number;
+  created_at: string;
+  run_type: string;
+  project_path: string;
+  agent_id: number | null;
+  agent_name: string | null;
+  binary: { path: string; version: string | null };
+  args: string[];
+  model: string;
+  prompt: string;
+  system_prompt: string | null;
+  context_files: { path: string; sha256: string }[];
+  /** Secret-looking values are masked as "***" */
+  env_overrides: Record<string, string>;
+  skills: { name: string; scope: string; sha: string }[];
 }
 
 /**
- * The complete timeline for a session
+ * A rerun that was started and how its inputs differ from the original run
  */
-export interface SessionTimeline {
-  sessionId: string;
-  rootNode?: TimelineNode;
-  currentCheckpointId?: string;
-  autoCheckpointEnabled: boolean;
-  checkpointStrategy: CheckpointStrategy;
-  totalCheckpoints: number;
+export interface RerunResult {
+  run_type: string;
+  agent_run_id: number | null;
+  differences: string[];
 }
 
 /**
- * Strategy for automatic checkpoint creation
+ * A recorded panic or failed command
  */
-export type CheckpointStrategy = 'manual' | 'per_prompt' | 'per_tool_use' | 'smart';
+export interface ErrorReport {
+  id: number;
+  occurred_at: string;
+  kind: "panic" | "command";
+  /** The failed command, or file:line of the panic */
+  source: string;
+  message: string;
+  /** Thread and backtrace of a panic */
+  details: { thread?: string; backtrace?: string } | null;
+  app_version: string;
+  /** When the report was sent, if error reporting is on */
+  submitted_at: string | null;
+}
 
 /**
- * Result of a checkpoint operation
+ * The local automation API for CI and scripts
  */
-export interface CheckpointResult {
-  checkpoint: Checkpoint;
-  filesProcessed: number;
-  warnings: string[];
+export interface ControlServerStatus {
+  enabled: boolean;
+  /** http://127.0.0.1:<port> while it runs */
+  address: string | null;
+  /** Send as `Authorization: Bearer <token>` */
+  token: string | null;
 }
 
 /**
- * Diff between two checkpoints
+ * Disk used by one storage category
  */
-export interface CheckpointDiff {
-  fromCheckpointId: string;
-  toCheckpointId: string;
-  modifiedFiles: FileDiff[];
-  addedFiles: string[];
-  deletedFiles: string[];
-  tokenDelta: number;
+export interface CategoryUsage {
+  /** database, database_backups, logs, checkpoints, worktrees, session_artifacts, caches or run_history */
+  category: string;
+  bytes: number;
+  /** Files or directories; rows for run_history, whose size counts towards database */
+  items: number;
+  /** Part of bytes any cleanup removes */
+  orphaned_bytes: number;
+  orphaned_items: number;
 }
 
 /**
- * Diff for a single file
+ * Disk used by the app, by category
  */
-export interface FileDiff {
-  path: string;
+export interface StorageUsage {
+  total_bytes: number;
+  categories: CategoryUsage[];
+}
+
+/**
+ * What cleanupStorage removes
+ */
+export interface CleanupOptions {
+  /** Categories to clean; all of them when omitted */
+  categories?: string[];
+  max_age_days?: number;
+  /** Each category is trimmed to this size, oldest items first */
+  max_size_mb?: number;
+  dry_run?: boolean;
+}
+
+/**
+ * What a cleanup removed, or would remove on a dry run
+ */
+export interface CleanupReport {
+  dry_run: boolean;
+  items_removed: number;
+  bytes_freed: number;
+  categories: { category: string; items_removed: number; bytes_freed: number }[];
+}
+
+/**
+ * Redactions made in one run since the app started
+ */
+export interface RunRedactions {
+  /** Claude session ID or agent run ID */
+  run: string;
+  enabled: boolean;
+  total: number;
+  /** Redactions by pattern name, e.g. anthropic_key or custom */
+  by_pattern: Record<string, number>;
+}
+
+/**
+ * One way of doing a race's task
+ */
+export interface RaceVariant {
+  agent_id: number;
+  /** Defaults to the agent's model */
+  model?: string;
+  /** Replaces the race's task for this variant */
+  task?: string;
+  /** Shown in the results view; defaults to the variant number and model */
+  label?: string;
+}
+
+/**
+ * A variant of a race and the run doing it
+ */
+export interface RaceRun {
+  /** 1-based position in the variants the race was started with */
+  variant: number;
+  label: string;
+  /** Output streams as `agent-output:<run_id>`; null if it failed to start */
+  run_id: number | null;
+  status: string | null;
+  worktree_id: number | null;
+  branch: string | null;
+  /** Why the variant didn't start */
+  error: string | null;
+}
+
+/**
+ * Agents running the same task side by side, each in its own worktree
+ */
+export interface AgentRace {
+  id: string;
+  project_path: string;
+  task: string;
+  runs: RaceRun[];
+}
+
+/**
+ * A race run for the results view
+ */
+export interface RunComparison {
+  run: AgentRun;
+  metrics: AgentRunMetrics | null;
+  /** The run's final answer */
+  result: string | null;
+  /** Branch of the worktree the run worked in, if it still exists */
+  branch: string | null;
+  worktree_id: number | null;
+  /** Changes since the worktree was created, committed or not */
+  files_changed: number;
   additions: number;
   deletions: number;
-  diffContent?: string;
 }
 
 /**
- * Represents an MCP server configuration
+ * A project in the project switcher, with the state of its checkout
  */
-export interface MCPServer {
-  /** Server name/identifier */
+export interface ProjectRegistryEntry {
+  path: string;
+  /** Directory name unless given */
   name: string;
-  /** Transport type: "stdio" or "sse" */
-  transport: string;
-  /** Command to execute (for stdio) */
-  command?: string;
-  /** Command arguments (for stdio) */
-  args: string[];
-  /** Environment variables */
-  env: Record<string, string>;
-  /** URL endpoint (for SSE) */
-  url?: string;
-  /** Configuration scope: "local", "project", or "user" */
-  scope: string;
-  /** Whether the server is currently active */
-  is_active: boolean;
-  /** Server status */
-  status: ServerStatus;
+  pinned: boolean;
+  added_at: string;
+  last_opened_at: string;
+  /** Whether the directory is still there */
+  exists: boolean;
+  is_git: boolean;
+  /** Checked out branch; null on a detached HEAD or outside git */
+  branch: string | null;
+  /** Uncommitted or untracked changes */
+  dirty: boolean;
+}
+
+/**
+ * A file of a skill in the registry
+ */
+export interface SkillFile {
+  /** Relative to the skill directory */
+  path: string;
+  size: number;
+}
+
+/**
+ * A skill as it is in the registry, read without installing it
+ */
+export interface SkillPreview {
+  name: string;
+  registry: string;
+  description: string | null;
+  /** SKILL.md without its frontmatter, ready to render */
+  markdown: string;
+  /** Tools the skill lets Claude use without asking */
+  allowed_tools: string[];
+  dependencies: {
+    skills: string[];
+    binaries: string[];
+  };
+  files: SkillFile[];
+  /** Bytes in the listed files */
+  total_size: number;
+  /** Whether the skill has more files than were listed */
+  truncated: boolean;
+}
+
+/**
+ * A new session holding the start of another session's conversation
+ */
+export interface ForkedSession {
+  session_id: string;
+  project_id: string;
+  /** Project the original session ran in */
+  project_path: string;
+  forked_from: string;
+  /** Messages copied into the new session */
+  message_count: number;
+  /** Whether a prompt was sent to continue the fork */
+  resumed: boolean;
+}
+
+/**
+ * A prompt sent in a project, deduplicated
+ */
+export interface PromptHistoryEntry {
+  id: number;
+  project_path: string;
+  prompt: string;
+  /** Model of the last session started with it */
+  model: string;
+  use_count: number;
+  pinned: boolean;
+  first_used_at: string;
+  last_used_at: string;
+}
+
+/**
+ * The permissions.allow/ask/deny rules of one Claude settings file
+ */
+export interface PermissionRules {
+  allow: string[];
+  ask: string[];
+  deny: string[];
+}
+
+/**
+ * Whether Claude would run a tool invocation, and which rule decided it
+ */
+export interface PermissionEvaluation {
+  decision: "allow" | "ask" | "deny";
+  /** null when no rule matched and the tool's default applies */
+  rule: string | null;
+  scope: "user" | "project" | "local" | null;
+  reason: string;
+}
+
+/**
+ * Outcome of one cache prewarm task
+ */
+export interface PrewarmResult {
+  name: "models" | "skills_catalog" | "mcp_catalog" | "mcp_health";
+  ok: boolean;
+  /** What was refreshed, or why it failed */
+  detail: string;
+  duration_ms: number;
+}
+
+/**
+ * Schedule of the cache prewarm before the workday
+ */
+export interface PrewarmStatus {
+  enabled: boolean;
+  /** HH:MM, local time */
+  start_time: string | null;
+  start_time_source: "manual" | "learned" | null;
+  lead_minutes: number;
+  next_prewarm_at: string | null;
+  last_prewarm_at: string | null;
+  results: PrewarmResult[];
+}
+
+/**
+ * A file opcode created, modified or deleted
+ */
+export interface FileAuditEntry {
+  id: number;
+  timestamp: string;
+  /** The command that wrote the file, e.g. install_skill */
+  source: string;
+  operation: "create" | "modify" | "delete";
+  path: string;
+  /** Size after the write; null for deletions and directories */
+  size: number | null;
+}
+
+/**
+ * Which file audit entries to return; every field is optional
+ */
+export interface FileAuditFilter {
+  source?: string;
+  operation?: "create" | "modify" | "delete";
+  /** Only files under this path */
+  path_prefix?: string;
+  /** RFC 3339 timestamps */
+  since?: string;
+  until?: string;
+  /** Entries older than this ID, for paging back through the log */
+  before_id?: number;
+  limit?: number;
+}
+
+export interface ImageInfo {
+  width: number;
+  height: number;
+  /** File extension of the detected format, e.g. png */
+  format: string | null;
+}
+
+export interface DependencyChange {
+  name: string;
+  from: string | null;
+  to: string | null;
+}
+
+export interface CellChange {
+  change: "added" | "removed" | "modified";
+  /** "code", "markdown" or "raw" */
+  cell_type: string;
+  before_index: number | null;
+  after_index: number | null;
+  source_changed: boolean;
+  outputs_changed: boolean;
+  /** First line of the cell's source */
+  preview: string;
+}
+
+/**
+ * Summary of a change to an image, lockfile or notebook, where a line diff isn't useful
+ */
+export type SpecialDiff =
+  | {
+      kind: "image";
+      before: ImageInfo | null;
+      after: ImageInfo | null;
+      /** Share of pixels that differ, 0 to 1; null unless both images have the same dimensions */
+      changed_pixel_ratio: number | null;
+    }
+  | {
+      kind: "lockfile";
+      added: DependencyChange[];
+      removed: DependencyChange[];
+      /** Dependencies locked to one version before and another after */
+      changed: DependencyChange[];
+    }
+  | {
+      kind: "notebook";
+      /** Added, removed and modified cells, in notebook order */
+      cells: CellChange[];
+      unchanged: number;
+    };
+
+/**
+ * An environment variable set for every run in a project
+ */
+export interface ProjectEnvVar {
+  key: string;
+  /** Null for secrets, whose value stays in the OS keychain */
+  value: string | null;
+  secret: boolean;
+  updated_at: string;
+}
+
+/**
+ * A file:line reference found in a transcript message
+ */
+export interface FileReference {
+  /** The reference as written, e.g. src/main.rs:42:7 */
+  text: string;
+  path: string;
+  line: number;
+  column: number | null;
+  /** Last line of a range such as src/main.rs:10-20 */
+  end_line: number | null;
+}
+
+/**
+ * Where to open a file reference
+ */
+export interface ReferenceTarget {
+  /** Absolute path of the file as it is now */
+  path: string;
+  relative_path: string;
+  line: number;
+  column: number;
+  end_line: number | null;
+  /** path:line:column, as editors take it on the command line */
+  location: string;
+  /** The path in the message, when the file was renamed since */
+  renamed_from: string | null;
+  /** Commit that renamed it, or "index" for a staged rename */
+  renamed_in: string | null;
+  /** Why the line differs from the reference */
+  note: string | null;
+}
+
+/**
+ * What importing a Claude directory archive does with one entry
+ */
+export interface ArchiveEntryPlan {
+  /** "other" for entries that aren't imported */
+  section: "sessions" | "commands" | "agents" | "other";
+  /** Path inside the Claude directory */
+  path: string;
+  action: "add" | "unchanged" | "skip" | "rename" | "overwrite" | "ignored" | "failed";
+  /** Where a renamed file is written */
+  target: string | null;
+  detail: string | null;
+}
+
+export interface ClaudeDirImportReport {
+  /** False for a dry run */
+  applied: boolean;
+  policy: "skip" | "rename" | "overwrite";
+  entries: ArchiveEntryPlan[];
+}
+
+/**
+ * Skills, MCP servers, agents and settings applied to a project together
+ */
+export interface ProjectProfile {
+  name: string;
+  /** Registry skills, installed in the project */
+  skills: string[];
+  /** Server name -> .mcp.json entry */
+  mcp_servers: Record<string, any>;
+  /** Agents to add, or update when one with the same name exists */
+  agents: AgentExport["agent"][];
+  /** App settings, validated like setSetting */
+  settings: Record<string, any>;
+}
+
+/**
+ * What happened to one item of a skill bundle or project profile
+ */
+export interface BulkItemResult {
+  kind: "skill" | "mcp_server" | "agent" | "setting";
+  name: string;
+  /** not_applied when another item failed and nothing was written */
+  status: "applied" | "unchanged" | "failed" | "not_applied";
+  detail: string | null;
+}
+
+export interface BulkReport {
+  /** False when an item failed and nothing was written */
+  applied: boolean;
+  items: BulkItemResult[];
+}
+
+/**
+ * What was restored because the app database was corrupt at launch
+ */
+export interface DatabaseRecovery {
+  /** What the integrity check or SQLite reported */
+  problems: string[];
+  /** Where the damaged database was moved */
+  corrupt_copy: string;
+  /** The backup restored; null when the app started with an empty database */
+  restored_backup: string | null;
+  /** When that backup was taken; changes after it are lost */
+  backup_taken_at: string | null;
+  recovered_at: string;
+}
+
+/**
+ * Whether the installed Claude Code CLI supports one feature
+ */
+export interface CliFeatureSupport {
+  id: string;
+  description: string;
+  /** First CLI release known to support the feature */
+  min_version: string;
+  flag: string | null;
+  supported: boolean;
+}
+
+/**
+ * The installed Claude Code CLI and what it supports
+ */
+export interface CliCapabilities {
+  binary: string | null;
+  /** null when the CLI isn't installed or its version couldn't be read */
+  version: string | null;
+  features: CliFeatureSupport[];
+  warnings: string[];
 }
 
-/**
- * Server status information
- */
-export interface ServerStatus {
-  /** Whether the server is running */
-  running: boolean;
-  /** Last error message if any */
-  error?: string;
-  /** Last checked timestamp */
-  last_checked?: number;
-}
+/**
+ * The running opcode version and the latest release
+ */
+export interface UpdateInfo {
+  current_version: string;
+  latest_version: string;
+  update_available: boolean;
+  /** Markdown notes of the latest release */
+  release_notes: string | null;
+  release_url: string;
+  published_at: string | null;
+  /** This build can install the update itself; otherwise link to release_url */
+  can_install: boolean;
+}
+
+export type EventSeverity = "info" | "warning" | "error";
+
+/**
+ * Which events a subscription receives; every criterion that is set must match
+ */
+export interface EventFilter {
+  /** Topic prefixes such as "agent-complete" */
+  topics?: string[];
+  /** Agent runs, matched on the topic suffix or a run_id in the payload */
+  run_ids?: number[];
+  /** Claude sessions, matched on the topic suffix or a session_id in the payload */
+  session_ids?: string[];
+  /** Matched on a project_path in the payload */
+  project_paths?: string[];
+  min_severity?: EventSeverity;
+}
+
+/**
+ * A run waiting for its project lock, or held after a restart until the user
+ * starts or dismisses it (see the run_catch_up_policy setting)
+ */
+export interface QueuedRun {
+  id: number;
+  reason: "project_lock" | "missed_schedule";
+  /** "waiting" starts on its own once the project is free; "held" needs a decision */
+  status: "waiting" | "held";
+  agent_id: number;
+  agent_name: string | null;
+  project_path: string;
+  task: string;
+  model: string | null;
+  /** Names of the run's environment overrides; their values have to be given again to start it */
+  env_keys: string[];
+  additional_dirs: string[];
+  schedule_id: number | null;
+  /** The agent run that was waiting, for runs queued on a project lock */
+  agent_run_id: number | null;
+  /** The missed window, for scheduled runs */
+  scheduled_for: string | null;
+  created_at: string;
+}
+
+/**
+ * One first-run setup step and whether it's done
+ */
+export interface OnboardingStep {
+  id: "claude_cli" | "credentials" | "claude_dir" | "first_project";
+  title: string;
+  /** The app found the step's requirement on this machine */
+  detected: boolean;
+  /** Detected, or marked complete by the user */
+  completed: boolean;
+  detail: string;
+  /** What to do while the step isn't complete */
+  hint: string | null;
+}
+
+export interface OnboardingStatus {
+  steps: OnboardingStep[];
+  /** The first step that isn't complete; null once setup is done */
+  current_step: OnboardingStep["id"] | null;
+  complete: boolean;
+}
+
+/**
+ * One benchmark prompt sent to one model
+ */
+export interface BenchmarkResult {
+  model: string;
+  prompt_id: string;
+  latency_ms: number;
+  input_tokens: number;
+  output_tokens: number;
+  output_chars: number;
+  cost: number;
+  /** Why the request failed; the other fields are zero then */
+  error: string | null;
+}
+
+/**
+ * A model's row of the benchmark comparison table
+ */
+export interface ModelBenchmark {
+  model: string;
+  prompt_set: string;
+  /** Prompts that succeeded */
+  runs: number;
+  failures: number;
+  avg_latency_ms: number;
+  avg_output_tokens: number;
+  avg_output_chars: number;
+  /** Cost of the whole suite */
+  total_cost: number;
+  project_path: string | null;
+  created_at: string;
+}
+
+export interface BenchmarkReport {
+  /** One row per model, in the order requested */
+  models: ModelBenchmark[];
+  results: BenchmarkResult[];
+}
+
+/**
+ * Dates to fetch billed usage for, inclusive, as YYYY-MM-DD
+ */
+export interface UsageRange {
+  start_date: string;
+  end_date: string;
+}
+
+/**
+ * A piece of a project's Claude configuration
+ */
+export interface ConfigNode {
+  id: string;
+  kind: "agent" | "skill" | "mcp_server" | "hook" | "permission";
+  name: string;
+  /** "project", "local" or "user"; empty for missing nodes */
+  scope: string;
+  /** File the node is defined in; empty for missing nodes */
+  source: string;
+  /** Hook commands, or whether a permission rule allows, asks or denies */
+  detail: string | null;
+  /** Referenced by another node but not configured anywhere */
+  missing: boolean;
+}
+
+/**
+ * `from` relies on `to`
+ */
+export interface ConfigEdge {
+  from: string;
+  to: string;
+  relation: "uses" | "triggers" | "governed_by";
+}
+
+export interface ConfigGraph {
+  nodes: ConfigNode[];
+  edges: ConfigEdge[];
+}
+
+/**
+ * A variable an agent template's prompt can be specialized with
+ */
+export interface TemplateVariable {
+  name: string;
+  description: string;
+  /** Used when no value is given; without one the variable is required */
+  default: string | null;
+  /** Values the variable may take; any value when empty */
+  choices: string[];
+}
+
+export interface AgentTemplate {
+  name: string;
+  description: string;
+  /** May contain {{variable}} placeholders, see renderAgentTemplate */
+  prompt: string;
+  category: string;
+  variables: TemplateVariable[];
+}
+
+/** Where a skill is installed: a project's .claude/skills or ~/.claude/skills */
+export type SkillScope = "project" | "user";
+
+/**
+ * A skill installed in a project or for the user
+ */
+export interface SkillEntry {
+  name: string;
+  scope: SkillScope;
+  /** SKILL.md of the skill */
+  path: string;
+  description: string | null;
+  /** Disabled skills are kept in .claude/skills/.disabled, where Claude doesn't load them */
+  enabled: boolean;
+}
+
+/**
+ * A skill or MCP server in a catalog, with the categories it was sorted into
+ */
+export interface CatalogEntry {
+  name: string;
+  description: string;
+  url: string;
+  categories: string[];
+  /** GitHub signals, when they have been fetched */
+  popularity: Popularity | null;
+}
+
+/**
+ * How popular and active a marketplace entry is, from GitHub
+ */
+export interface Popularity {
+  /** Stars of the repository the entry lives in */
+  stars: number;
+  /** Open issues and pull requests of that repository */
+  open_issues: number;
+  /** Last commit touching the entry itself */
+  last_commit_at: string | null;
+}
+
+/**
+ * One page of catalog search results
+ */
+export interface CatalogPage {
+  entries: CatalogEntry[];
+  /** Entries matching the query and category, on all pages */
+  total: number;
+  page: number;
+  page_size: number;
+  /** Categories of the entries matching the query, with counts */
+  categories: { name: string; count: number }[];
+}
+
+/**
+ * A breaking CLI change that affects one configuration file
+ */
+export interface MigrationItem {
+  /** ID of the breaking change, passed to applyCliMigrationFix */
+  id: string;
+  title: string;
+  file: string;
+  problems: string[];
+  /** Major version that introduced the change */
+  introduced_in: number;
+  /** applyCliMigrationFix can fix it */
+  fixable: boolean;
+  instructions: string;
+}
+
+/**
+ * Compatibility of settings, hooks and MCP configs with the installed CLI
+ */
+export interface MigrationReport {
+  /** Version the user last migrated to */
+  previous_version: string | null;
+  /** Installed version, when it can be detected */
+  current_version: string | null;
+  major_upgrade: boolean;
+  items: MigrationItem[];
+}
+
+/**
+ * Payload of the `claude-cli-upgraded` event
+ */
+export interface CliUpgraded {
+  previous_version: string;
+  current_version: string;
+  /** Items in the user-level migration checklist */
+  items: number;
+}
+
+/**
+ * A program an installed skill runs that isn't on PATH
+ */
+export interface MissingBinary {
+  name: string;
+  /** Skills that run it */
+  required_by: string[];
+  /** How to install it on this platform */
+  hint: string;
+}
+
+/**
+ * A skill written to disk after passing verification
+ */
+export interface InstalledSkill {
+  name: string;
+  scope: SkillScope;
+  path: string;
+  sha: string;
+  signature_verified: boolean;
+  /** Skills installed because this one depends on them, in install order */
+  dependencies: string[];
+  /** Programs the skill or its dependencies need that should be installed */
+  missing_binaries: MissingBinary[];
+}
+
+/**
+ * A claim in CLAUDE.md that doesn't match the repository
+ */
+export interface StaleClaim {
+  kind: "command" | "path" | "dependency";
+  claim: string;
+  /** 1-based line in CLAUDE.md */
+  line: number;
+  reason: string;
+}
+
+/**
+ * One heading's section of CLAUDE.md and what in it is out of date
+ */
+export interface SectionFreshness {
+  /** Empty for the text before the first heading */
+  heading: string;
+  level: number;
+  start_line: number;
+  end_line: number;
+  /** Claims that could be checked */
+  checked: number;
+  problems: StaleClaim[];
+}
+
+/**
+ * Freshness of a project's CLAUDE.md, section by section
+ */
+export interface ClaudeMdFreshness {
+  path: string;
+  sections: SectionFreshness[];
+  stale_sections: number;
+}
+
+/**
+ * A CLAUDE.md section rewritten by the model
+ */
+export interface RegeneratedSection {
+  heading: string;
+  original: string;
+  replacement: string;
+  /** Whether CLAUDE.md was updated */
+  applied: boolean;
+}
+
+/**
+ * Payload of the skill-added, skill-removed, skill-changed, agents-changed,
+ * commands-changed and settings-changed events
+ */
+export interface ClaudeDirChange {
+  scope: "user" | "project";
+  project_path: string | null;
+  /** The skill or settings file that changed */
+  name: string | null;
+}
+
+/**
+ * Payload of the session-updated event
+ */
+export interface SessionUpdated {
+  project_id: string;
+  session_id: string;
+}
+
+/**
+ * Something loaded into every session before the first prompt
+ */
+export interface ContextContributor {
+  kind: "memory" | "skill" | "mcp_server";
+  name: string;
+  /** The file it comes from, or the scope of an MCP server */
+  source: string;
+  tokens: number;
+  /** e.g. an MCP server's tool count, or why it couldn't be measured */
+  detail: string | null;
+}
+
+/**
+ * What a project's setup costs in context before any work is done
+ */
+export interface StaticContextEstimate {
+  /** Largest first */
+  contributors: ContextContributor[];
+  memory_tokens: number;
+  skill_tokens: number;
+  mcp_tokens: number;
+  total: TokenEstimate;
+}
+
+/**
+ * What to hide in an exported session before sharing it
+ */
+export interface ExportOptions {
+  /** Show the project as <project> and home directories as ~ */
+  redact_paths?: boolean;
+  /** Replace API keys, tokens and private keys with [REDACTED] */
+  redact_secrets?: boolean;
+}
+
+/**
+ * What exportSession wrote
+ */
+export interface SessionExportResult {
+  path: string;
+  messages: number;
+  bytes: number;
+}
+
+/**
+ * How one skill is used in one project
+ */
+export interface SkillUsage {
+  name: string;
+  /** "project" or "user" for installed skills; null if no longer installed */
+  scope: string | null;
+  path: string | null;
+  invocations: number;
+  sessions: number;
+  last_used: string | null;
+  /** Tokens of the name and description, loaded into every session */
+  context_tokens: number;
+}
+
+/**
+ * Skill usage in one project, most used first
+ */
+export interface ProjectSkillUsage {
+  project_path: string;
+  skills: SkillUsage[];
+}
+
+/**
+ * Estimated size of some text against a model's context window
+ */
+export interface TokenEstimate {
+  tokens: number;
+  context_window: number;
+  /** Share of the context window used; can exceed 100 */
+  percent_of_window: number;
+}
+
+/**
+ * A file counted in a project context estimate
+ */
+export interface FileTokens {
+  /** Relative to the project, or ~/.claude/CLAUDE.md for the user memory */
+  path: string;
+  bytes: number;
+  tokens: number;
+}
+
+/**
+ * Estimated context a run starts with: CLAUDE.md memory files plus selected files
+ */
+export interface ProjectContextEstimate {
+  files: FileTokens[];
+  /** Matched files left out because they are binary or too large */
+  skipped: string[];
+  /** More files matched than are counted; the estimate is a lower bound */
+  truncated: boolean;
+  total: TokenEstimate;
+  exceeds_window: boolean;
+}
+
+/**
+ * A run holding or waiting for a project
+ */
+export interface LockHolder {
+  run_id: number;
+  /** e.g. the agent's name */
+  label: string;
+}
+
+/**
+ * A working tree an agent run is editing; other runs wait or warn, see the
+ * project_lock_policy setting
+ */
+export interface ProjectLockInfo {
+  project_root: string;
+  holder: LockHolder | null;
+  /** Runs waiting for the project, first in line first */
+  queued: LockHolder[];
+}
+
+/**
+ * A git worktree an agent can run in without touching the live checkout
+ */
+export interface AgentWorktree {
+  id: number;
+  /** The checkout the worktree was created from */
+  project_path: string;
+  /** The project inside the worktree; use it as the project path of a run */
+  path: string;
+  branch: string;
+  /** Commit the branch started from */
+  base_sha: string;
+  created_at: string;
+}
+
+/**
+ * What merging a worktree brought into the project
+ */
+export interface WorktreeMergeResult {
+  /** Commits on the worktree branch, including one for uncommitted changes */
+  commits: number;
+  /** The merge commit; null when there was nothing to merge */
+  sha: string | null;
+}
+
+/**
+ * How the app was started; see restartInSafeMode
+ */
+export interface LaunchState {
+  /** The previous launch ended without a clean exit */
+  crashed_last_launch: boolean;
+  /** Only core commands are available; plugins and background tasks are off */
+  safe_mode: boolean;
+}
+
+export type FileChangeStatus =
+  | "modified"
+  | "added"
+  | "deleted"
+  | "renamed"
+  | "untracked"
+  | "conflicted";
+
+/**
+ * A file with uncommitted changes, relative to the project directory
+ */
+export interface ChangedFile {
+  path: string;
+  original_path: string | null;
+  status: FileChangeStatus;
+  staged: boolean;
+  /** null for binary files */
+  additions: number | null;
+  deletions: number | null;
+}
+
+export interface WorkingDiff {
+  diff: string;
+  truncated: boolean;
+  /** Images, lockfiles and notebooks, summarized by a diff provider */
+  special: SpecialFileDiff[];
+}
+
+export interface SpecialFileDiff {
+  path: string;
+  diff: SpecialDiff;
+}
+
+export interface CommitResult {
+  sha: string;
+  files: number;
+}
+
+/**
+ * Which run events show a desktop notification while the app is in the background
+ */
+export interface NotificationSettings {
+  run_completed: boolean;
+  run_failed: boolean;
+  /** A tool call was blocked because it needs approval */
+  permission_request: boolean;
+}
+
+/**
+ * How the latest response from one GitHub catalog compared to the expected schema
+ */
+export interface DriftReport {
+  source: string;
+  status: DriftStatus;
+  checked_at: string;
+  items: number;
+  skipped_items: number;
+  unknown_fields: string[];
+  missing_fields: string[];
+  error: string | null;
+}
+
+/** "degraded" means items were dropped or a built-in fallback was used */
+export type DriftStatus = "ok" | "drifted" | "degraded";
+
+export interface MarketplaceStatus {
+  status: DriftStatus;
+  sources: DriftReport[];
+}
+
+/**
+ * An agent run that repeats on a cron schedule while the app is open
+ */
+export interface Schedule {
+  id: number;
+  name: string;
+  project_path: string;
+  agent_id: number;
+  agent_name: string | null;
+  prompt: string;
+  /** Overrides the agent's model when set */
+  model: string | null;
+  /** Five-field cron expression in local time, e.g. "0 2 * * *" */
+  cron: string;
+  enabled: boolean;
+  next_run_at: string | null;
+  last_run_at: string | null;
+  last_agent_run_id: number | null;
+  last_run_status: string | null;
+  last_error: string | null;
+  created_at: string;
+}
+
+export interface NewSchedule {
+  name: string;
+  project_path: string;
+  agent_id: number;
+  prompt: string;
+  model?: string;
+  cron: string;
+  enabled?: boolean;
+}
+
+/**
+ * Prices for models whose name contains `model`, in USD per million tokens
+ */
+export interface ModelPricing {
+  model: string;
+  input: number;
+  output: number;
+  cache_write: number;
+  cache_read: number;
+  context_window: number | null;
+}
+
+export interface PricingTable {
+  version: number;
+  updated_at: string;
+  models: ModelPricing[];
+}
+
+/**
+ * Negotiated prices for matching models; unset fields keep the table price
+ */
+export interface PriceOverride {
+  model: string;
+  input?: number | null;
+  output?: number | null;
+  cache_write?: number | null;
+  cache_read?: number | null;
+}
+
+export interface PricingOverrides {
+  models: PriceOverride[];
+  markup_percent: number;
+}
+
+export interface Pricing {
+  table: PricingTable;
+  source: "built_in" | "remote";
+  overrides: PricingOverrides;
+}
+
+export interface UsageStats {
+  total_cost: number;
+  total_tokens: number;
+  total_input_tokens: number;
+  total_output_tokens: number;
+  total_cache_creation_tokens: number;
+  total_cache_read_tokens: number;
+  total_sessions: number;
+  by_model: ModelUsage[];
+  by_date: DailyUsage[];
+  by_project: ProjectUsage[];
+}
+
+/**
+ * Represents a checkpoint in the session timeline
+ */
+export interface Checkpoint {
+  id: string;
+  sessionId: string;
+  projectId: string;
+  messageIndex: number;
+  timestamp: string;
+  description?: string;
+  parentCheckpointId?: string;
+  metadata: CheckpointMetadata;
+}
+
+/**
+ * Metadata associated with a checkpoint
+ */
+export interface CheckpointMetadata {
+  totalTokens: number;
+  modelUsed: string;
+  userPrompt: string;
+  fileChanges: number;
+  snapshotSize: number;
+}
+
+/**
+ * Represents a file snapshot at a checkpoint
+ */
+export interface FileSnapshot {
+  checkpointId: string;
+  filePath: string;
+  content: string;
+  hash: string;
+  isDeleted: boolean;
+  permissions?: number;
+  size: number;
+}
+
+/**
+ * Represents a node in the timeline tree
+ */
+export interface TimelineNode {
+  checkpoint: Checkpoint;
+  children: TimelineNode[];
+  fileSnapshotIds: string[];
+}
+
+/**
+ * The complete timeline for a session
+ */
+export interface SessionTimeline {
+  sessionId: string;
+  rootNode?: TimelineNode;
+  currentCheckpointId?: string;
+  autoCheckpointEnabled: boolean;
+  checkpointStrategy: CheckpointStrategy;
+  totalCheckpoints: number;
+}
+
+/**
+ * Strategy for automatic checkpoint creation
+ */
+export type CheckpointStrategy = 'manual' | 'per_prompt' | 'per_tool_use' | 'smart';
+
+/**
+ * Result of a checkpoint operation
+ */
+export interface CheckpointResult {
+  checkpoint: Checkpoint;
+  filesProcessed: number;
+  warnings: string[];
+}
+
+/**
+ * Diff between two checkpoints
+ */
+export interface CheckpointDiff {
+  fromCheckpointId: string;
+  toCheckpointId: string;
+  modifiedFiles: FileDiff[];
+  addedFiles: string[];
+  deletedFiles: string[];
+  tokenDelta: number;
+}
+
+/**
+ * Diff for a single file
+ */
+export interface FileDiff {
+  path: string;
+  additions: number;
+  deletions: number;
+  diffContent?: string;
+  /** Type-aware summary for lockfiles and notebooks */
+  special?: SpecialDiff | null;
+}
+
+/**
+ * Represents an MCP server configuration
+ */
+export interface MCPServer {
+  /** Server name/identifier */
+  name: string;
+  /** Transport type: "stdio" or "sse" */
+  transport: string;
+  /** Command to execute (for stdio) */
+  command?: string;
+  /** Command arguments (for stdio) */
+  args: string[];
+  /** Environment variables */
+  env: Record<string, string>;
+  /** URL endpoint (for SSE) */
+  url?: string;
+  /** Configuration scope: "local", "project", or "user" */
+  scope: string;
+  /** Whether the server is currently active */
+  is_active: boolean;
+  /** Server status */
+  status: ServerStatus;
+}
+
+/**
+ * Server status information
+ */
+export interface ServerStatus {
+  /** Whether the server is running */
+  running: boolean;
+  /** Last error message if any */
+  error?: string;
+  /** Last checked timestamp */
+  last_checked?: number;
+}
+
+/**
+ * MCP configuration for project scope (.mcp.json)
+ */
+export interface MCPProjectConfig {
+  mcpServers: Record<string, MCPServerConfig>;
+}
+
+/**
+ * Individual server configuration in .mcp.json
+ */
+export interface MCPServerConfig {
+  command: string;
+  args: string[];
+  env: Record<string, string>;
+}
+
+/**
+ * Represents a custom slash command
+ */
+export interface SlashCommand {
+  /** Unique identifier for the command */
+  id: string;
+  /** Command name (without prefix) */
+  name: string;
+  /** Full command with prefix (e.g., "/project:optimize") */
+  full_command: string;
+  /** Command scope: "project" or "user" */
+  scope: string;
+  /** Optional namespace (e.g., "frontend" in "/project:frontend:component") */
+  namespace?: string;
+  /** Path to the markdown file */
+  file_path: string;
+  /** Command content (markdown body) */
+  content: string;
+  /** Optional description from frontmatter */
+  description?: string;
+  /** Allowed tools from frontmatter */
+  allowed_tools: string[];
+  /** Whether the command has bash commands (!) */
+  has_bash_commands: boolean;
+  /** Whether the command has file references (@) */
+  has_file_references: boolean;
+  /** Whether the command uses $ARGUMENTS placeholder */
+  accepts_arguments: boolean;
+}
+
+/**
+ * Result of adding a server
+ */
+export interface AddServerResult {
+  success: boolean;
+  message: string;
+  server_name?: string;
+}
+
+/**
+ * Whether a backend subsystem can be used on this machine
+ */
+export interface Capability {
+  available: boolean;
+  detail: string | null;
+}
+
+/**
+ * Subsystems available on this platform and build
+ */
+export interface AppCapabilities {
+  platform: string;
+  arch: string;
+  version: string;
+  sandboxing: Capability;
+  tray: Capability;
+  global_hotkeys: Capability;
+  docker: Capability;
+  ssh: Capability;
+  bedrock: Capability;
+}
+
+/**
+ * What importing one item of a configuration archive would do
+ */
+export interface ConfigChange {
+  section: "settings" | "agents" | "mcp_servers" | "secrets";
+  name: string;
+  action: "add" | "update" | "unchanged" | "skip";
+  detail: string | null;
+}
+
+export interface ConfigImportReport {
+  applied: boolean;
+  changes: ConfigChange[];
+}
+
+/**
+ * What an opcode:// link asks the app to do
+ */
+export type DeepLinkAction =
+  | { kind: "install_skill"; repo: string; name: string }
+  | { kind: "install_mcp"; name: string; config: Record<string, unknown> };
+
+/**
+ * A deep link waiting for confirmation; also emitted as `deep-link-request`
+ */
+export interface PendingDeepLink {
+  id: string;
+  action: DeepLinkAction;
+  received_at: string;
+}
+
+/**
+ * A persisted backend event; `id` is the cursor to resume after it
+ */
+export interface BusEvent {
+  id: number;
+  topic: string;
+  payload: any;
+  created_at: string;
+}
+
+/**
+ * A page of persisted events
+ */
+export interface EventPage {
+  events: BusEvent[];
+  cursor: number;
+  has_more: boolean;
+}
+
+/**
+ * Import result for multiple servers
+ */
+export interface ImportResult {
+  imported_count: number;
+  failed_count: number;
+  servers: ImportServerResult[];
+}
+
+/**
+ * Result for individual server import
+ */
+export interface ImportServerResult {
+  name: string;
+  success: boolean;
+  error?: string;
+}
+
+/**
+ * Information about an Anthropic model
+ */
+export interface ModelInfo {
+  id: string;
+  display_name: string;
+  created_at: string;
+  type: string;
+}
+
+/**
+ * Response from listing Anthropic models
+ */
+export interface ModelsResponse {
+  data: ModelInfo[];
+  has_more: boolean;
+  first_id: string | null;
+  last_id: string | null;
+  /** live from the API, the last cached list, or the table bundled with the app */
+  source: "live" | "cached" | "bundled";
+  /** When a cached list was fetched */
+  fetched_at: string | null;
+  /** Why the live list couldn't be used */
+  error: string | null;
+}
+
+/**
+ * API client for interacting with the Rust backend
+ */
+export const api = {
+  /**
+   * Gets the user's home directory path
+   * @returns Promise resolving to the home directory path
+   */
+  async getHomeDirectory(): Promise<string> {
+    try {
+      return await apiCall<string>("get_home_directory");
+    } catch (error) {
+      console.error("Failed to get home directory:", error);
+      return "/";
+    }
+  },
+
+  /**
+   * Lists all projects in the ~/.claude/projects directory
+   * @returns Promise resolving to an array of projects
+   */
+  async listProjects(): Promise<Project[]> {
+    try {
+      return await apiCall<Project[]>("list_projects");
+    } catch (error) {
+      console.error("Failed to list projects:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a new project for the given directory path
+   * @param path - The directory path to create a project for
+   * @returns Promise resolving to the created project
+   */
+  async createProject(path: string): Promise<Project> {
+    try {
+      return await apiCall<Project>('create_project', { path });
+    } catch (error) {
+      console.error("Failed to create project:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Retrieves sessions for a specific project
+   * @param projectId - The ID of the project to retrieve sessions for
+   * @returns Promise resolving to an array of sessions
+   */
+  async getProjectSessions(projectId: string): Promise<Session[]> {
+    try {
+      return await apiCall<Session[]>('get_project_sessions', { projectId });
+    } catch (error) {
+      console.error("Failed to get project sessions:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Fetch list of agents from GitHub repository
+   * @returns Promise resolving to list of available agents on GitHub
+   */
+  async fetchGitHubAgents(): Promise<GitHubAgentFile[]> {
+    try {
+      return await apiCall<GitHubAgentFile[]>('fetch_github_agents');
+    } catch (error) {
+      console.error("Failed to fetch GitHub agents:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Fetch and preview a specific agent from GitHub
+   * @param downloadUrl - The download URL for the agent file
+   * @returns Promise resolving to the agent export data
+   */
+  async fetchGitHubAgentContent(downloadUrl: string): Promise<AgentExport> {
+    try {
+      return await apiCall<AgentExport>('fetch_github_agent_content', { downloadUrl });
+    } catch (error) {
+      console.error("Failed to fetch GitHub agent content:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Import an agent directly from GitHub
+   * @param downloadUrl - The download URL for the agent file
+   * @returns Promise resolving to the imported agent
+   */
+  async importAgentFromGitHub(downloadUrl: string): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('import_agent_from_github', { downloadUrl });
+    } catch (error) {
+      console.error("Failed to import agent from GitHub:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads the Claude settings file
+   * @returns Promise resolving to the settings object
+   */
+  async getClaudeSettings(): Promise<ClaudeSettings> {
+    try {
+      const result = await apiCall<{ data: ClaudeSettings }>("get_claude_settings");
+      console.log("Raw result from get_claude_settings:", result);
+
+      // The Rust backend returns ClaudeSettings { data: ... }
+      // We need to extract the data field
+      if (result && typeof result === 'object' && 'data' in result) {
+        return result.data;
+      }
+
+      // If the result is already the settings object, return it
+      return result as ClaudeSettings;
+    } catch (error) {
+      console.error("Failed to get Claude settings:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Opens a new Claude Code session
+   * @param path - Optional path to open the session in
+   * @returns Promise resolving when the session is opened
+   */
+  async openNewSession(path?: string): Promise<string> {
+    try {
+      return await apiCall<string>("open_new_session", { path });
+    } catch (error) {
+      console.error("Failed to open new session:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads the CLAUDE.md system prompt file
+   * @returns Promise resolving to the system prompt content
+   */
+  async getSystemPrompt(): Promise<string> {
+    try {
+      return await apiCall<string>("get_system_prompt");
+    } catch (error) {
+      console.error("Failed to get system prompt:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks if Claude Code is installed and gets its version
+   * @returns Promise resolving to the version status
+   */
+  async checkClaudeVersion(): Promise<ClaudeVersionStatus> {
+    try {
+      return await apiCall<ClaudeVersionStatus>("check_claude_version");
+    } catch (error) {
+      console.error("Failed to check Claude version:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the CLAUDE.md system prompt file
+   * @param content - The new content for the system prompt
+   * @returns Promise resolving when the file is saved
+   */
+  async saveSystemPrompt(content: string): Promise<string> {
+    try {
+      return await apiCall<string>("save_system_prompt", { content });
+    } catch (error) {
+      console.error("Failed to save system prompt:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the Claude settings file
+   * @param settings - The settings object to save
+   * @returns Promise resolving when the settings are saved
+   */
+  async saveClaudeSettings(settings: ClaudeSettings): Promise<string> {
+    try {
+      return await apiCall<string>("save_claude_settings", { settings });
+    } catch (error) {
+      console.error("Failed to save Claude settings:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Finds all CLAUDE.md files in a project directory
+   * @param projectPath - The absolute path to the project
+   * @returns Promise resolving to an array of CLAUDE.md files
+   */
+  async findClaudeMdFiles(projectPath: string): Promise<ClaudeMdFile[]> {
+    try {
+      return await apiCall<ClaudeMdFile[]>("find_claude_md_files", { projectPath });
+    } catch (error) {
+      console.error("Failed to find CLAUDE.md files:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads a specific CLAUDE.md file
+   * @param filePath - The absolute path to the file
+   * @returns Promise resolving to the file content
+   */
+  async readClaudeMdFile(filePath: string): Promise<string> {
+    try {
+      return await apiCall<string>("read_claude_md_file", { filePath });
+    } catch (error) {
+      console.error("Failed to read CLAUDE.md file:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves a specific CLAUDE.md file
+   * @param filePath - The absolute path to the file
+   * @param content - The new content for the file
+   * @returns Promise resolving when the file is saved
+   */
+  async saveClaudeMdFile(filePath: string, content: string): Promise<string> {
+    try {
+      return await apiCall<string>("save_claude_md_file", { filePath, content });
+    } catch (error) {
+      console.error("Failed to save CLAUDE.md file:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves a temporary image file from base64 data
+   * @param base64Data The base64 encoded image data
+   * @param fileName Optional filename
+   * @returns The absolute path to the saved file
+   */
+  async saveTempImage(base64Data: string, fileName?: string): Promise<string> {
+    try {
+      return await apiCall<string>("save_temp_image", { base64Data, fileName });
+    } catch (error) {
+      console.error("Failed to save temp image:", error);
+      throw error;
+    }
+  },
+
+  // Agent API methods
+
+  /**
+   * Lists all CC agents
+   * @returns Promise resolving to an array of agents
+   */
+  async listAgents(): Promise<Agent[]> {
+    try {
+      return await apiCall<Agent[]>('list_agents');
+    } catch (error) {
+      console.error("Failed to list agents:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a new agent
+   * @param name - The agent name
+   * @param icon - The icon identifier
+   * @param system_prompt - The system prompt for the agent
+   * @param default_task - Optional default task
+   * @param model - Optional model (defaults to 'sonnet')
+   * @param hooks - Optional hooks configuration as JSON string
+   * @returns Promise resolving to the created agent
+   */
+  async createAgent(
+    name: string,
+    icon: string,
+    system_prompt: string,
+    default_task?: string,
+    model?: string,
+    hooks?: string
+  ): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('create_agent', {
+        name,
+        icon,
+        systemPrompt: system_prompt,
+        defaultTask: default_task,
+        model,
+        hooks
+      });
+    } catch (error) {
+      console.error("Failed to create agent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates an existing agent
+   * @param id - The agent ID
+   * @param name - The updated name
+   * @param icon - The updated icon
+   * @param system_prompt - The updated system prompt
+   * @param default_task - Optional default task
+   * @param model - Optional model
+   * @param hooks - Optional hooks configuration as JSON string
+   * @returns Promise resolving to the updated agent
+   */
+  async updateAgent(
+    id: number,
+    name: string,
+    icon: string,
+    system_prompt: string,
+    default_task?: string,
+    model?: string,
+    hooks?: string
+  ): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('update_agent', {
+        id,
+        name,
+        icon,
+        systemPrompt: system_prompt,
+        defaultTask: default_task,
+        model,
+        hooks
+      });
+    } catch (error) {
+      console.error("Failed to update agent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes an agent
+   * @param id - The agent ID to delete
+   * @returns Promise resolving when the agent is deleted
+   */
+  async deleteAgent(id: number): Promise<void> {
+    try {
+      return await apiCall('delete_agent', { id });
+    } catch (error) {
+      console.error("Failed to delete agent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a single agent by ID
+   * @param id - The agent ID
+   * @returns Promise resolving to the agent
+   */
+  async getAgent(id: number): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('get_agent', { id });
+    } catch (error) {
+      console.error("Failed to get agent:", error);
+      throw error;
+    }
+  },
+
+
+  /**
+   * Lists available Anthropic models, falling back to the cached or bundled list
+   * @param apiKey - Optional API Key (if not set in backend env)
+   * @param refresh - Ask the API even if the cached list is less than an hour old
+   * @returns Promise resolving to ModelsResponse; `source` says where the list came from
+   */
+  async listAnthropicModels(apiKey?: string, refresh?: boolean): Promise<ModelsResponse> {
+    try {
+      return await apiCall<ModelsResponse>('list_anthropic_models', { apiKey, refresh });
+    } catch (error) {
+      console.error("Failed to list Anthropic models:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Exports a single agent to JSON format
+   * @param id - The agent ID to export
+   * @returns Promise resolving to the JSON string
+   */
+  async exportAgent(id: number): Promise<string> {
+    try {
+      return await apiCall<string>('export_agent', { id });
+    } catch (error) {
+      console.error("Failed to export agent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Imports an agent from JSON data
+   * @param jsonData - The JSON string containing the agent export
+   * @returns Promise resolving to the imported agent
+   */
+  async importAgent(jsonData: string): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('import_agent', { jsonData });
+    } catch (error) {
+      console.error("Failed to import agent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Imports an agent from a file
+   * @param filePath - The path to the JSON file
+   * @returns Promise resolving to the imported agent
+   */
+  async importAgentFromFile(filePath: string): Promise<Agent> {
+    try {
+      return await apiCall<Agent>('import_agent_from_file', { filePath });
+    } catch (error) {
+      console.error("Failed to import agent from file:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Executes an agent
+   * @param agentId - The agent ID to execute
+   * @param projectPath - The project path to run the agent in
+   * @param task - The task description
+   * @param model - Optional model override
+   * @param env - Optional environment variables for this run only
+   * @param additionalDirs - Other project roots the agent may work in, e.g. a backend repo
+   * @returns Promise resolving to the run ID when execution starts
+   */
+  async executeAgent(agentId: number, projectPath: string, task: string, model?: string, env?: Record<string, string>, additionalDirs?: string[]): Promise<number> {
+    try {
+      return await apiCall<number>('execute_agent', { agentId, projectPath, task, model, env, additionalDirs });
+    } catch (error) {
+      console.error("Failed to execute agent:", error);
+      // Return a sentinel value to indicate error
+      throw new Error(`Failed to execute agent: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Lists agent runs without metrics (basic info only)
+   * @param agentId - Optional agent ID to filter runs
+   * @returns Promise resolving to an array of agent runs
+   */
+  async listAgentRuns(agentId?: number): Promise<AgentRunWithMetrics[]> {
+    try {
+      return await apiCall<AgentRunWithMetrics[]>('list_agent_runs', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent runs:", error);
+      // Return empty array instead of throwing to prevent UI crashes
+      return [];
+    }
+  },
+
+  /**
+   * Lists agent runs with metrics (includes token counts and duration)
+   * @param agentId - Optional agent ID to filter runs
+   * @returns Promise resolving to an array of agent runs with metrics
+   */
+  async listAgentRunsWithMetrics(agentId?: number): Promise<AgentRunWithMetrics[]> {
+    try {
+      return await apiCall<AgentRunWithMetrics[]>('list_agent_runs_with_metrics', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent runs with metrics:", error);
+      // Return empty array instead of throwing to prevent UI crashes
+      return [];
+    }
+  },
+
+  /**
+   * Gets a single agent run by ID with metrics
+   * @param id - The run ID
+   * @returns Promise resolving to the agent run with metrics
+   */
+  async getAgentRun(id: number): Promise<AgentRunWithMetrics> {
+    try {
+      return await apiCall<AgentRunWithMetrics>('get_agent_run', { id });
+    } catch (error) {
+      console.error("Failed to get agent run:", error);
+      throw new Error(`Failed to get agent run: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Gets a single agent run by ID with real-time metrics from JSONL
+   * @param id - The run ID
+   * @returns Promise resolving to the agent run with metrics
+   */
+  async getAgentRunWithRealTimeMetrics(id: number): Promise<AgentRunWithMetrics> {
+    try {
+      return await apiCall<AgentRunWithMetrics>('get_agent_run_with_real_time_metrics', { id });
+    } catch (error) {
+      console.error("Failed to get agent run with real-time metrics:", error);
+      throw new Error(`Failed to get agent run with real-time metrics: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Lists all currently running agent sessions
+   * @returns Promise resolving to list of running agent sessions
+   */
+  async listRunningAgentSessions(): Promise<AgentRun[]> {
+    try {
+      return await apiCall<AgentRun[]>('list_running_sessions');
+    } catch (error) {
+      console.error("Failed to list running agent sessions:", error);
+      throw new Error(`Failed to list running agent sessions: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Kills a running agent session
+   * @param runId - The run ID to kill
+   * @returns Promise resolving to whether the session was successfully killed
+   */
+  async killAgentSession(runId: number): Promise<boolean> {
+    try {
+      return await apiCall<boolean>('kill_agent_session', { runId });
+    } catch (error) {
+      console.error("Failed to kill agent session:", error);
+      throw new Error(`Failed to kill agent session: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Gets the status of a specific agent session
+   * @param runId - The run ID to check
+   * @returns Promise resolving to the session status or null if not found
+   */
+  async getSessionStatus(runId: number): Promise<string | null> {
+    try {
+      return await apiCall<string | null>('get_session_status', { runId });
+    } catch (error) {
+      console.error("Failed to get session status:", error);
+      throw new Error(`Failed to get session status: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Cleanup finished processes and update their status
+   * @returns Promise resolving to list of run IDs that were cleaned up
+   */
+  async cleanupFinishedProcesses(): Promise<number[]> {
+    try {
+      return await apiCall<number[]>('cleanup_finished_processes');
+    } catch (error) {
+      console.error("Failed to cleanup finished processes:", error);
+      throw new Error(`Failed to cleanup finished processes: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Get real-time output for a running session (with live output fallback)
+   * @param runId - The run ID to get output for
+   * @returns Promise resolving to the current session output (JSONL format)
+   */
+  async getSessionOutput(runId: number): Promise<string> {
+    try {
+      return await apiCall<string>('get_session_output', { runId });
+    } catch (error) {
+      console.error("Failed to get session output:", error);
+      throw new Error(`Failed to get session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Get live output directly from process stdout buffer
+   * @param runId - The run ID to get live output for
+   * @returns Promise resolving to the current live output
+   */
+  async getLiveSessionOutput(runId: number): Promise<string> {
+    try {
+      return await apiCall<string>('get_live_session_output', { runId });
+    } catch (error) {
+      console.error("Failed to get live session output:", error);
+      throw new Error(`Failed to get live session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Start streaming real-time output for a running session
+   * @param runId - The run ID to stream output for
+   * @returns Promise that resolves when streaming starts
+   */
+  async streamSessionOutput(runId: number): Promise<void> {
+    try {
+      return await apiCall<void>('stream_session_output', { runId });
+    } catch (error) {
+      console.error("Failed to start streaming session output:", error);
+      throw new Error(`Failed to start streaming session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  },
+
+  /**
+   * Loads the JSONL history for a specific session
+   */
+  async loadSessionHistory(sessionId: string, projectId: string): Promise<any[]> {
+    return apiCall("load_session_history", { sessionId, projectId });
+  },
+
+  /**
+   * Loads the JSONL history for a specific agent session
+   * Similar to loadSessionHistory but searches across all project directories
+   * @param sessionId - The session ID (UUID)
+   * @returns Promise resolving to array of session messages
+   */
+  async loadAgentSessionHistory(sessionId: string): Promise<any[]> {
+    try {
+      return await apiCall<any[]>('load_agent_session_history', { sessionId });
+    } catch (error) {
+      console.error("Failed to load agent session history:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Executes a new interactive Claude Code session with streaming output
+   * @param env - Optional environment variables for this run only
+   * @param additionalDirs - Other project roots the session may work in; usage is split across all roots
+   */
+  async executeClaudeCode(projectPath: string, prompt: string, model: string, env?: Record<string, string>, additionalDirs?: string[]): Promise<void> {
+    return apiCall("execute_claude_code", { projectPath, prompt, model, env, additionalDirs });
+  },
+
+  /**
+   * Continues an existing Claude Code conversation with streaming output
+   */
+  async continueClaudeCode(projectPath: string, prompt: string, model: string, env?: Record<string, string>, additionalDirs?: string[]): Promise<void> {
+    return apiCall("continue_claude_code", { projectPath, prompt, model, env, additionalDirs });
+  },
+
+  /**
+   * Resumes an existing Claude Code session by ID with streaming output
+   */
+  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string, env?: Record<string, string>, additionalDirs?: string[]): Promise<void> {
+    return apiCall("resume_claude_code", { projectPath, sessionId, prompt, model, env, additionalDirs });
+  },
+
+  /**
+   * Cancels the currently running Claude Code execution
+   * @param sessionId - Optional session ID to cancel a specific session
+   */
+  async cancelClaudeExecution(sessionId?: string): Promise<void> {
+    return apiCall("cancel_claude_execution", { sessionId });
+  },
+
+  /**
+   * Executes the Claude Agent SDK via a sidecar script (Sidecar pattern).
+   * @param projectPath - The project path to run in
+   * @param prompt - The user prompt
+   * @param model - The model to use
+   * @returns Promise resolving when the session is started
+   */
+  async executeSdkAgent(projectPath: string, prompt: string, model: string, sessionId?: string): Promise<void> {
+    return apiCall("execute_sdk_agent", { projectPath, prompt, model, sessionId });
+  },
+
+  /**
+   * Lists all currently running Claude sessions
+   * @returns Promise resolving to list of running Claude sessions
+   */
+  async listRunningClaudeSessions(): Promise<any[]> {
+    return apiCall("list_running_claude_sessions");
+  },
+
+  /**
+   * Gets live output from a Claude session
+   * @param sessionId - The session ID to get output for
+   * @returns Promise resolving to the current live output
+   */
+  async getClaudeSessionOutput(sessionId: string): Promise<string> {
+    return apiCall("get_claude_session_output", { sessionId });
+  },
+
+  /**
+   * Lists files and directories in a given path
+   */
+  async listDirectoryContents(directoryPath: string): Promise<FileEntry[]> {
+    return apiCall("list_directory_contents", { directoryPath });
+  },
+
+  /**
+   * Searches for files and directories matching a pattern
+   */
+  async searchFiles(basePath: string, query: string): Promise<FileEntry[]> {
+    return apiCall("search_files", { basePath, query });
+  },
+
+  /**
+   * Gets overall usage statistics
+   * @returns Promise resolving to usage statistics
+   */
+  async getUsageStats(): Promise<UsageStats> {
+    try {
+      return await apiCall<UsageStats>("get_usage_stats");
+    } catch (error) {
+      console.error("Failed to get usage stats:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets usage statistics filtered by date range
+   * @param startDate - Start date (ISO format)
+   * @param endDate - End date (ISO format)
+   * @returns Promise resolving to usage statistics
+   */
+  async getUsageByDateRange(startDate: string, endDate: string): Promise<UsageStats> {
+    try {
+      return await apiCall<UsageStats>("get_usage_by_date_range", { startDate, endDate });
+    } catch (error) {
+      console.error("Failed to get usage by date range:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets usage statistics grouped by session
+   * @param since - Optional start date (YYYYMMDD)
+   * @param until - Optional end date (YYYYMMDD)
+   * @param order - Optional sort order ('asc' or 'desc')
+   * @returns Promise resolving to an array of session usage data
+   */
+  async getSessionStats(
+    since?: string,
+    until?: string,
+    order?: "asc" | "desc"
+  ): Promise<ProjectUsage[]> {
+    try {
+      return await apiCall<ProjectUsage[]>("get_session_stats", {
+        since,
+        until,
+        order,
+      });
+    } catch (error) {
+      console.error("Failed to get session stats:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets detailed usage entries with optional filtering
+   * @param limit - Optional limit for number of entries
+   * @returns Promise resolving to array of usage entries
+   */
+  async getUsageDetails(limit?: number): Promise<UsageEntry[]> {
+    try {
+      return await apiCall<UsageEntry[]>("get_usage_details", { limit });
+    } catch (error) {
+      console.error("Failed to get usage details:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Forecasts end-of-month spend per project from the recent trend
+   * @param rangeDays - Days of history to fit (7-90, default 30)
+   * @param projectPath - Optional project to forecast on its own
+   * @returns Promise resolving to forecasts, highest projected spend first
+   */
+  async forecastUsage(rangeDays?: number, projectPath?: string): Promise<ProjectForecast[]> {
+    try {
+      return await apiCall<ProjectForecast[]>("forecast_usage", { rangeDays, projectPath });
+    } catch (error) {
+      console.error("Failed to forecast usage:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the manifest recorded for a run in the history
+   * @param runId - The run history ID
+   * @returns Promise resolving to the manifest, or null for runs recorded without one
+   */
+  async getRunManifest(runId: number): Promise<RunManifest | null> {
+    try {
+      return await apiCall<RunManifest | null>("get_run_manifest", { runId });
+    } catch (error) {
+      console.error("Failed to get run manifest:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Starts a new run with the inputs recorded in a past run's manifest
+   * @param runId - The run history ID to reproduce
+   * @param env - Values for masked environment overrides, or replacements
+   * @param strict - Refuse to run if the binary, context files, skills or agent changed
+   * @returns Promise resolving to the rerun and the differences found
+   */
+  async rerunFromManifest(runId: number, env?: Record<string, string>, strict?: boolean): Promise<RerunResult> {
+    try {
+      return await apiCall<RerunResult>("rerun_from_manifest", { runId, env, strict });
+    } catch (error) {
+      console.error("Failed to rerun from manifest:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists opcode:// links waiting for confirmation
+   */
+  async getPendingDeepLinks(): Promise<PendingDeepLink[]> {
+    return apiCall<PendingDeepLink[]>("get_pending_deep_links");
+  },
+
+  /**
+   * Carries out a pending deep link the user confirmed
+   * @param id - ID of the pending link
+   * @param projectPath - Project to install a skill into
+   * @param scope - Scope for an MCP server: "local", "project" or "user"
+   * @returns Promise resolving to the install result
+   */
+  async confirmDeepLink(id: string, projectPath?: string, scope?: string): Promise<unknown> {
+    try {
+      return await apiCall("confirm_deep_link", { id, projectPath, scope });
+    } catch (error) {
+      console.error("Failed to confirm deep link:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Drops a pending deep link without acting on it
+   * @param id - ID of the pending link
+   */
+  async dismissDeepLink(id: string): Promise<void> {
+    return apiCall("dismiss_deep_link", { id });
+  },
+
+  /**
+   * Gets the pricing table and overrides used for cost numbers
+   */
+  async getPricing(): Promise<Pricing> {
+    return apiCall<Pricing>("get_pricing");
+  },
+
+  /**
+   * Fetches the signed pricing table from the configured URL
+   * @returns Promise resolving to the new table
+   */
+  async refreshPricingTable(): Promise<PricingTable> {
+    try {
+      return await apiCall<PricingTable>("refresh_pricing_table");
+    } catch (error) {
+      console.error("Failed to refresh pricing table:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Goes back to the built-in pricing table
+   */
+  async resetPricingTable(): Promise<void> {
+    return apiCall("reset_pricing_table");
+  },
+
+  /**
+   * Saves negotiated prices and markups
+   * @param overrides - Per-model prices and a markup applied to every cost
+   */
+  async savePricingOverrides(overrides: PricingOverrides): Promise<void> {
+    try {
+      await apiCall("save_pricing_overrides", { overrides });
+    } catch (error) {
+      console.error("Failed to save pricing overrides:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a checkpoint for the current session state
+   */
+  async createCheckpoint(
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    messageIndex?: number,
+    description?: string
+  ): Promise<CheckpointResult> {
+    return apiCall("create_checkpoint", {
+      sessionId,
+      projectId,
+      projectPath,
+      messageIndex,
+      description
+    });
+  },
+
+  /**
+   * Restores a session to a specific checkpoint
+   */
+  async restoreCheckpoint(
+    checkpointId: string,
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<CheckpointResult> {
+    return apiCall("restore_checkpoint", {
+      checkpointId,
+      sessionId,
+      projectId,
+      projectPath
+    });
+  },
+
+  /**
+   * Lists all checkpoints for a session
+   */
+  async listCheckpoints(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<Checkpoint[]> {
+    return apiCall("list_checkpoints", {
+      sessionId,
+      projectId,
+      projectPath
+    });
+  },
+
+  /**
+   * Forks a new timeline branch from a checkpoint
+   */
+  async forkFromCheckpoint(
+    checkpointId: string,
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    newSessionId: string,
+    description?: string
+  ): Promise<CheckpointResult> {
+    return apiCall("fork_from_checkpoint", {
+      checkpointId,
+      sessionId,
+      projectId,
+      projectPath,
+      newSessionId,
+      description
+    });
+  },
+
+  /**
+   * Gets the timeline for a session
+   */
+  async getSessionTimeline(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<SessionTimeline> {
+    return apiCall("get_session_timeline", {
+      sessionId,
+      projectId,
+      projectPath
+    });
+  },
+
+  /**
+   * Updates checkpoint settings for a session
+   */
+  async updateCheckpointSettings(
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    autoCheckpointEnabled: boolean,
+    checkpointStrategy: CheckpointStrategy
+  ): Promise<void> {
+    return apiCall("update_checkpoint_settings", {
+      sessionId,
+      projectId,
+      projectPath,
+      autoCheckpointEnabled,
+      checkpointStrategy
+    });
+  },
+
+  /**
+   * Gets diff between two checkpoints
+   */
+  async getCheckpointDiff(
+    fromCheckpointId: string,
+    toCheckpointId: string,
+    sessionId: string,
+    projectId: string
+  ): Promise<CheckpointDiff> {
+    try {
+      return await apiCall<CheckpointDiff>("get_checkpoint_diff", {
+        fromCheckpointId,
+        toCheckpointId,
+        sessionId,
+        projectId
+      });
+    } catch (error) {
+      console.error("Failed to get checkpoint diff:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Tracks a message for checkpointing
+   */
+  async trackCheckpointMessage(
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    message: string
+  ): Promise<void> {
+    try {
+      await apiCall("track_checkpoint_message", {
+        sessionId,
+        projectId,
+        projectPath,
+        message
+      });
+    } catch (error) {
+      console.error("Failed to track checkpoint message:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks if auto-checkpoint should be triggered
+   */
+  async checkAutoCheckpoint(
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    message: string
+  ): Promise<boolean> {
+    try {
+      return await apiCall<boolean>("check_auto_checkpoint", {
+        sessionId,
+        projectId,
+        projectPath,
+        message
+      });
+    } catch (error) {
+      console.error("Failed to check auto checkpoint:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Triggers cleanup of old checkpoints
+   */
+  async cleanupOldCheckpoints(
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    keepCount: number
+  ): Promise<number> {
+    try {
+      return await apiCall<number>("cleanup_old_checkpoints", {
+        sessionId,
+        projectId,
+        projectPath,
+        keepCount
+      });
+    } catch (error) {
+      console.error("Failed to cleanup old checkpoints:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets checkpoint settings for a session
+   */
+  async getCheckpointSettings(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<{
+    auto_checkpoint_enabled: boolean;
+    checkpoint_strategy: CheckpointStrategy;
+    total_checkpoints: number;
+    current_checkpoint_id?: string;
+  }> {
+    try {
+      return await apiCall("get_checkpoint_settings", {
+        sessionId,
+        projectId,
+        projectPath
+      });
+    } catch (error) {
+      console.error("Failed to get checkpoint settings:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Clears checkpoint manager for a session (cleanup on session end)
+   */
+  async clearCheckpointManager(sessionId: string): Promise<void> {
+    try {
+      await apiCall("clear_checkpoint_manager", { sessionId });
+    } catch (error) {
+      console.error("Failed to clear checkpoint manager:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Tracks a batch of messages for a session for checkpointing
+   */
+  trackSessionMessages: (
+    sessionId: string,
+    projectId: string,
+    projectPath: string,
+    messages: string[]
+  ): Promise<void> =>
+    apiCall("track_session_messages", { sessionId, projectId, projectPath, messages }),
+
+  /**
+   * Adds a new MCP server
+   */
+  async mcpAdd(
+    name: string,
+    transport: string,
+    command?: string,
+    args: string[] = [],
+    env: Record<string, string> = {},
+    url?: string,
+    scope: string = "local"
+  ): Promise<AddServerResult> {
+    try {
+      return await apiCall<AddServerResult>("mcp_add", {
+        name,
+        transport,
+        command,
+        args,
+        env,
+        url,
+        scope
+      });
+    } catch (error) {
+      console.error("Failed to add MCP server:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all configured MCP servers
+   */
+  async mcpList(): Promise<MCPServer[]> {
+    try {
+      console.log("API: Calling mcp_list...");
+      const result = await apiCall<MCPServer[]>("mcp_list");
+      console.log("API: mcp_list returned:", result);
+      return result;
+    } catch (error) {
+      console.error("API: Failed to list MCP servers:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets details for a specific MCP server
+   */
+  async mcpGet(name: string): Promise<MCPServer> {
+    try {
+      return await apiCall<MCPServer>("mcp_get", { name });
+    } catch (error) {
+      console.error("Failed to get MCP server:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Removes an MCP server
+   */
+  async mcpRemove(name: string): Promise<string> {
+    try {
+      return await apiCall<string>("mcp_remove", { name });
+    } catch (error) {
+      console.error("Failed to remove MCP server:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Adds an MCP server from JSON configuration
+   */
+  async mcpAddJson(name: string, jsonConfig: string, scope: string = "local"): Promise<AddServerResult> {
+    try {
+      return await apiCall<AddServerResult>("mcp_add_json", { name, jsonConfig, scope });
+    } catch (error) {
+      console.error("Failed to add MCP server from JSON:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Imports MCP servers from Claude Desktop
+   */
+  async mcpAddFromClaudeDesktop(scope: string = "local"): Promise<ImportResult> {
+    try {
+      return await apiCall<ImportResult>("mcp_add_from_claude_desktop", { scope });
+    } catch (error) {
+      console.error("Failed to import from Claude Desktop:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Starts Claude Code as an MCP server
+   */
+  async mcpServe(): Promise<string> {
+    try {
+      return await apiCall<string>("mcp_serve");
+    } catch (error) {
+      console.error("Failed to start MCP server:", error);
+      throw error;
+    }
+  },
+
+  async pluginInstall(name: string): Promise<string> {
+    try {
+      return await apiCall<string>("plugin_install", { name });
+    } catch (error) {
+      console.error("Failed to install plugin:", error);
+      throw error;
+    }
+  },
+
+  async pluginUninstall(name: string): Promise<string> {
+    try {
+      return await apiCall<string>("plugin_uninstall", { name });
+    } catch (error) {
+      console.error("Failed to uninstall plugin:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Tests connection to an MCP server
+   */
+  async mcpTestConnection(name: string): Promise<string> {
+    try {
+      return await apiCall<string>("mcp_test_connection", { name });
+    } catch (error) {
+      console.error("Failed to test MCP connection:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Resets project-scoped server approval choices
+   */
+  async mcpResetProjectChoices(): Promise<string> {
+    try {
+      return await apiCall<string>("mcp_reset_project_choices");
+    } catch (error) {
+      console.error("Failed to reset project choices:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the status of MCP servers
+   */
+  async mcpGetServerStatus(): Promise<Record<string, ServerStatus>> {
+    try {
+      return await apiCall<Record<string, ServerStatus>>("mcp_get_server_status");
+    } catch (error) {
+      console.error("Failed to get server status:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads .mcp.json from the current project
+   */
+  async mcpReadProjectConfig(projectPath: string): Promise<MCPProjectConfig> {
+    try {
+      return await apiCall<MCPProjectConfig>("mcp_read_project_config", { projectPath });
+    } catch (error) {
+      console.error("Failed to read project MCP config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves .mcp.json to the current project
+   */
+  async mcpSaveProjectConfig(projectPath: string, config: MCPProjectConfig): Promise<string> {
+    try {
+      return await apiCall<string>("mcp_save_project_config", { projectPath, config });
+    } catch (error) {
+      console.error("Failed to save project MCP config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Get the stored Claude binary path from settings
+   * @returns Promise resolving to the path if set, null otherwise
+   */
+  async getClaudeBinaryPath(): Promise<string | null> {
+    try {
+      return await apiCall<string | null>("get_claude_binary_path");
+    } catch (error) {
+      console.error("Failed to get Claude binary path:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Set the Claude binary path in settings
+   * @param path - The absolute path to the Claude binary
+   * @returns Promise resolving when the path is saved
+   */
+  async setClaudeBinaryPath(path: string): Promise<void> {
+    try {
+      return await apiCall<void>("set_claude_binary_path", { path });
+    } catch (error) {
+      console.error("Failed to set Claude binary path:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * List all available Claude installations on the system
+   * @returns Promise resolving to an array of Claude installations
+   */
+  async listClaudeInstallations(): Promise<ClaudeInstallation[]> {
+    try {
+      return await apiCall<ClaudeInstallation[]>("list_claude_installations");
+    } catch (error) {
+      console.error("Failed to list Claude installations:", error);
+      throw error;
+    }
+  },
+
+  // Storage API methods
+
+  /**
+   * Lists all tables in the SQLite database
+   * @returns Promise resolving to an array of table information
+   */
+  async storageListTables(): Promise<any[]> {
+    try {
+      return await apiCall<any[]>("storage_list_tables");
+    } catch (error) {
+      console.error("Failed to list tables:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads table data with pagination
+   * @param tableName - Name of the table to read
+   * @param page - Page number (1-indexed)
+   * @param pageSize - Number of rows per page
+   * @param searchQuery - Optional search query
+   * @returns Promise resolving to table data with pagination info
+   */
+  async storageReadTable(
+    tableName: string,
+    page: number,
+    pageSize: number,
+    searchQuery?: string
+  ): Promise<any> {
+    try {
+      return await apiCall<any>("storage_read_table", {
+        tableName,
+        page,
+        pageSize,
+        searchQuery,
+      });
+    } catch (error) {
+      console.error("Failed to read table:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates a row in a table
+   * @param tableName - Name of the table
+   * @param primaryKeyValues - Map of primary key column names to values
+   * @param updates - Map of column names to new values
+   * @returns Promise resolving when the row is updated
+   */
+  async storageUpdateRow(
+    tableName: string,
+    primaryKeyValues: Record<string, any>,
+    updates: Record<string, any>
+  ): Promise<void> {
+    try {
+      return await apiCall<void>("storage_update_row", {
+        tableName,
+        primaryKeyValues,
+        updates,
+      });
+    } catch (error) {
+      console.error("Failed to update row:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a row from a table
+   * @param tableName - Name of the table
+   * @param primaryKeyValues - Map of primary key column names to values
+   * @returns Promise resolving when the row is deleted
+   */
+  async storageDeleteRow(
+    tableName: string,
+    primaryKeyValues: Record<string, any>
+  ): Promise<void> {
+    try {
+      return await apiCall<void>("storage_delete_row", {
+        tableName,
+        primaryKeyValues,
+      });
+    } catch (error) {
+      console.error("Failed to delete row:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Inserts a new row into a table
+   * @param tableName - Name of the table
+   * @param values - Map of column names to values
+   * @returns Promise resolving to the last insert row ID
+   */
+  async storageInsertRow(
+    tableName: string,
+    values: Record<string, any>
+  ): Promise<number> {
+    try {
+      return await apiCall<number>("storage_insert_row", {
+        tableName,
+        values,
+      });
+    } catch (error) {
+      console.error("Failed to insert row:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Executes a raw SQL query
+   * @param query - SQL query string
+   * @returns Promise resolving to query result
+   */
+  async storageExecuteSql(query: string): Promise<any> {
+    try {
+      return await apiCall<any>("storage_execute_sql", { query });
+    } catch (error) {
+      console.error("Failed to execute SQL:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Resets the entire database
+   * @returns Promise resolving when the database is reset
+   */
+  async storageResetDatabase(): Promise<void> {
+    try {
+      return await apiCall<void>("storage_reset_database");
+    } catch (error) {
+      console.error("Failed to reset database:", error);
+      throw error;
+    }
+  },
+
+  // Theme settings helpers
+
+  /**
+   * Gets a setting from the settings store
+   * @param key - The setting key to retrieve
+   * @returns Promise resolving to the setting value, or null if unset and without a default
+   */
+  async getSetting(key: string): Promise<string | null> {
+    try {
+      // Fast path: check localStorage mirror to avoid startup flicker
+      if (typeof window !== 'undefined' && 'localStorage' in window) {
+        const cached = window.localStorage.getItem(`app_setting:${key}`);
+        if (cached !== null) {
+          return cached;
+        }
+      }
+      const value = await apiCall<unknown>("get_setting", { key });
+      if (value === null || value === undefined) {
+        return null;
+      }
+      return typeof value === 'string' ? value : JSON.stringify(value);
+    } catch (error) {
+      console.error(`Failed to get setting ${key}:`, error);
+      return null;
+    }
+  },
+
+  /**
+   * Saves a setting to the settings store; the backend validates the value
+   * @param key - The setting key
+   * @param value - The setting value
+   * @returns Promise resolving when the setting is saved
+   */
+  async saveSetting(key: string, value: string): Promise<void> {
+    try {
+      await apiCall("set_setting", { key, value });
+      // Mirror to localStorage for instant availability on next startup
+      if (typeof window !== 'undefined' && 'localStorage' in window) {
+        try {
+          window.localStorage.setItem(`app_setting:${key}`, value);
+        } catch (_ignore) {
+          // best-effort; the store already has the value
+        }
+      }
+    } catch (error) {
+      console.error(`Failed to save setting ${key}:`, error);
+      throw error;
+    }
+  },
 
-/**
- * MCP configuration for project scope (.mcp.json)
- */
-export interface MCPProjectConfig {
-  mcpServers: Record<string, MCPServerConfig>;
-}
+  /**
+   * Gets every known setting with its current value
+   * @returns Promise resolving to a map of setting keys to values
+   */
+  async getAllSettings(): Promise<Record<string, unknown>> {
+    try {
+      return await apiCall<Record<string, unknown>>("get_all_settings");
+    } catch (error) {
+      console.error("Failed to get settings:", error);
+      throw error;
+    }
+  },
 
-/**
- * Individual server configuration in .mcp.json
- */
-export interface MCPServerConfig {
-  command: string;
-  args: string[];
-  env: Record<string, string>;
-}
+  /**
+   * Checks the Claude CLI, the Claude directory, network access, the API key and Node.js
+   * @returns Promise resolving to per-check results with suggested fixes
+   */
+  async runDoctor(): Promise<DoctorReport> {
+    try {
+      return await apiCall<DoctorReport>("run_doctor");
+    } catch (error) {
+      console.error("Failed to run doctor:", error);
+      throw error;
+    }
+  },
 
-/**
- * Represents a custom slash command
- */
-export interface SlashCommand {
-  /** Unique identifier for the command */
-  id: string;
-  /** Command name (without prefix) */
-  name: string;
-  /** Full command with prefix (e.g., "/project:optimize") */
-  full_command: string;
-  /** Command scope: "project" or "user" */
-  scope: string;
-  /** Optional namespace (e.g., "frontend" in "/project:frontend:component") */
-  namespace?: string;
-  /** Path to the markdown file */
-  file_path: string;
-  /** Command content (markdown body) */
-  content: string;
-  /** Optional description from frontmatter */
-  description?: string;
-  /** Allowed tools from frontmatter */
-  allowed_tools: string[];
-  /** Whether the command has bash commands (!) */
-  has_bash_commands: boolean;
-  /** Whether the command has file references (@) */
-  has_file_references: boolean;
-  /** Whether the command uses $ARGUMENTS placeholder */
-  accepts_arguments: boolean;
-}
+  /**
+   * Gets recent entries from the app's log files, oldest first
+   * @param level - Minimum severity: "error", "warn", "info" (default), "debug" or "trace"
+   * @param limit - Most entries to return (default 500)
+   */
+  async getRecentLogs(level?: LogLevel, limit?: number): Promise<LogEntry[]> {
+    try {
+      return await apiCall<LogEntry[]>("get_recent_logs", { level, limit });
+    } catch (error) {
+      console.error("Failed to get recent logs:", error);
+      throw error;
+    }
+  },
 
-/**
- * Result of adding a server
- */
-export interface AddServerResult {
-  success: boolean;
-  message: string;
-  server_name?: string;
-}
+  /**
+   * Changes the log level now and for future launches
+   * @param level - The new level
+   */
+  async setLogLevel(level: LogLevel): Promise<void> {
+    try {
+      return await apiCall<void>("set_log_level", { level });
+    } catch (error) {
+      console.error("Failed to set log level:", error);
+      throw error;
+    }
+  },
 
-/**
- * Import result for multiple servers
- */
-export interface ImportResult {
-  imported_count: number;
-  failed_count: number;
-  servers: ImportServerResult[];
-}
+  /**
+   * Generates synthetic Claude data in a temporary directory (development builds only)
+   * @param options - Seed, sizes and date range; defaults give 5 projects over 6 months
+   * @returns Promise resolving to where the data was written and how much
+   */
+  async generateTestFixtures(options?: FixtureOptions): Promise<FixtureSummary> {
+    try {
+      return await apiCall<FixtureSummary>("generate_test_fixtures", { options });
+    } catch (error) {
+      console.error("Failed to generate test fixtures:", error);
+      throw error;
+    }
+  },
 
-/**
- * Result for individual server import
- */
-export interface ImportServerResult {
-  name: string;
-  success: boolean;
-  error?: string;
-}
+  /**
+   * Moves a project skill to ~/.claude/skills so every project can use it
+   * @param projectPath - The project the skill is installed in
+   * @param skillName - The skill to move
+   * @param overwrite - Replace a user skill of the same name
+   * @returns Promise resolving to the skill at user scope
+   */
+  async promoteSkillToUserScope(projectPath: string, skillName: string, overwrite?: boolean): Promise<SkillEntry> {
+    try {
+      return await apiCall<SkillEntry>("promote_skill_to_user_scope", { projectPath, skillName, overwrite });
+    } catch (error) {
+      console.error("Failed to promote skill to user scope:", error);
+      throw error;
+    }
+  },
 
-/**
- * Information about an Anthropic model
- */
-export interface ModelInfo {
-  id: string;
-  display_name: string;
-  created_at: string;
-  type: string;
-}
+  /**
+   * Lists recorded panics and command errors, newest first
+   * @param kind - Only "panic" or "command" errors
+   * @param limit - Most reports to return (default 100)
+   * @returns Promise resolving to the reports
+   */
+  async listRecentErrors(kind?: "panic" | "command", limit?: number): Promise<ErrorReport[]> {
+    try {
+      return await apiCall<ErrorReport[]>("list_recent_errors", { kind, limit });
+    } catch (error) {
+      console.error("Failed to list recent errors:", error);
+      throw error;
+    }
+  },
 
-/**
- * Response from listing Anthropic models
- */
-export interface ModelsResponse {
-  data: ModelInfo[];
-  has_more: boolean;
-  first_id: string | null;
-  last_id: string | null;
-}
+  /**
+   * Removes every recorded error
+   * @returns Promise resolving to how many were removed
+   */
+  async clearErrorReports(): Promise<number> {
+    try {
+      return await apiCall<number>("clear_error_reports");
+    } catch (error) {
+      console.error("Failed to clear error reports:", error);
+      throw error;
+    }
+  },
 
-/**
- * API client for interacting with the Rust backend
- */
-export const api = {
   /**
-   * Gets the user's home directory path
-   * @returns Promise resolving to the home directory path
+   * Sends unsent error reports, anonymized, to the configured endpoint;
+   * fails unless error reporting is turned on in settings
+   * @returns Promise resolving to how many reports were sent
    */
-  async getHomeDirectory(): Promise<string> {
+  async submitErrorReports(): Promise<number> {
     try {
-      return await apiCall<string>("get_home_directory");
+      return await apiCall<number>("submit_error_reports");
     } catch (error) {
-      console.error("Failed to get home directory:", error);
-      return "/";
+      console.error("Failed to submit error reports:", error);
+      throw error;
     }
   },
 
   /**
-   * Lists all projects in the ~/.claude/projects directory
-   * @returns Promise resolving to an array of projects
+   * Gets whether the local automation API is on, where it listens and its token
+   * @returns Promise resolving to the control server status
    */
-  async listProjects(): Promise<Project[]> {
+  async getControlServerStatus(): Promise<ControlServerStatus> {
     try {
-      return await apiCall<Project[]>("list_projects");
+      return await apiCall<ControlServerStatus>("get_control_server_status");
     } catch (error) {
-      console.error("Failed to list projects:", error);
+      console.error("Failed to get control server status:", error);
       throw error;
     }
   },
 
   /**
-   * Creates a new project for the given directory path
-   * @param path - The directory path to create a project for
-   * @returns Promise resolving to the created project
+   * Turns the local automation API on or off; it only listens on 127.0.0.1
+   * @param enabled - Whether to serve the API
+   * @param port - Port to listen on; keeps the current one when omitted
+   * @returns Promise resolving to the new status
    */
-  async createProject(path: string): Promise<Project> {
+  async setControlServerEnabled(enabled: boolean, port?: number): Promise<ControlServerStatus> {
     try {
-      return await apiCall<Project>('create_project', { path });
+      return await apiCall<ControlServerStatus>("set_control_server_enabled", { enabled, port });
     } catch (error) {
-      console.error("Failed to create project:", error);
+      console.error("Failed to update control server:", error);
       throw error;
     }
   },
 
   /**
-   * Retrieves sessions for a specific project
-   * @param projectId - The ID of the project to retrieve sessions for
-   * @returns Promise resolving to an array of sessions
+   * Replaces the control server token; the old one stops working at once
+   * @returns Promise resolving to the status with the new token
    */
-  async getProjectSessions(projectId: string): Promise<Session[]> {
+  async rotateControlServerToken(): Promise<ControlServerStatus> {
     try {
-      return await apiCall<Session[]>('get_project_sessions', { projectId });
+      return await apiCall<ControlServerStatus>("rotate_control_server_token");
     } catch (error) {
-      console.error("Failed to get project sessions:", error);
+      console.error("Failed to rotate control server token:", error);
       throw error;
     }
   },
 
   /**
-   * Fetch list of agents from GitHub repository
-   * @returns Promise resolving to list of available agents on GitHub
+   * Gets the disk used by the app's database, backups, logs, checkpoints,
+   * worktrees, session artifacts and caches
+   * @returns Promise resolving to usage by category
    */
-  async fetchGitHubAgents(): Promise<GitHubAgentFile[]> {
+  async getStorageUsage(): Promise<StorageUsage> {
     try {
-      return await apiCall<GitHubAgentFile[]>('fetch_github_agents');
+      return await apiCall<StorageUsage>("get_storage_usage");
     } catch (error) {
-      console.error("Failed to fetch GitHub agents:", error);
+      console.error("Failed to get storage usage:", error);
       throw error;
     }
   },
 
   /**
-   * Fetch and preview a specific agent from GitHub
-   * @param downloadUrl - The download URL for the agent file
-   * @returns Promise resolving to the agent export data
+   * Removes orphaned artifacts, then items older than max_age_days, then the
+   * oldest items until each category fits in max_size_mb
+   * @param options - Categories and limits; set dry_run to preview
+   * @returns Promise resolving to what was removed
    */
-  async fetchGitHubAgentContent(downloadUrl: string): Promise<AgentExport> {
+  async cleanupStorage(options?: CleanupOptions): Promise<CleanupReport> {
     try {
-      return await apiCall<AgentExport>('fetch_github_agent_content', { downloadUrl });
+      return await apiCall<CleanupReport>("cleanup_storage", { options });
     } catch (error) {
-      console.error("Failed to fetch GitHub agent content:", error);
+      console.error("Failed to clean up storage:", error);
       throw error;
     }
   },
 
   /**
-   * Import an agent directly from GitHub
-   * @param downloadUrl - The download URL for the agent file
-   * @returns Promise resolving to the imported agent
+   * Turns secret redaction on or off for one run while it streams
+   * @param run - Claude session ID or agent run ID
+   * @param enabled - Whether to redact the run's output from now on
    */
-  async importAgentFromGitHub(downloadUrl: string): Promise<Agent> {
+  async setRunRedaction(run: string, enabled: boolean): Promise<void> {
     try {
-      return await apiCall<Agent>('import_agent_from_github', { downloadUrl });
+      return await apiCall<void>("set_run_redaction", { run, enabled });
     } catch (error) {
-      console.error("Failed to import agent from GitHub:", error);
+      console.error("Failed to set run redaction:", error);
       throw error;
     }
   },
 
   /**
-   * Reads the Claude settings file
-   * @returns Promise resolving to the settings object
+   * Gets the redactions made per run and pattern since the app started
+   * @param run - Only this run's
+   * @returns Promise resolving to the runs that had redactions
    */
-  async getClaudeSettings(): Promise<ClaudeSettings> {
+  async getRedactionCounts(run?: string): Promise<RunRedactions[]> {
     try {
-      const result = await apiCall<{ data: ClaudeSettings }>("get_claude_settings");
-      console.log("Raw result from get_claude_settings:", result);
+      return await apiCall<RunRedactions[]>("get_redaction_counts", { run });
+    } catch (error) {
+      console.error("Failed to get redaction counts:", error);
+      throw error;
+    }
+  },
 
-      // The Rust backend returns ClaudeSettings { data: ... }
-      // We need to extract the data field
-      if (result && typeof result === 'object' && 'data' in result) {
-        return result.data;
-      }
+  /**
+   * Runs several agent variants on the same task at once, each in its own worktree
+   * @param projectPath - The project to race in; it must be a git repository
+   * @param task - The task every variant works on unless it has its own
+   * @param variants - Between 2 and 8 agent, model or prompt variants
+   * @returns Promise resolving to the race with the run of each variant
+   */
+  async startAgentRace(projectPath: string, task: string, variants: RaceVariant[]): Promise<AgentRace> {
+    try {
+      return await apiCall<AgentRace>("start_agent_race", { projectPath, task, variants });
+    } catch (error) {
+      console.error("Failed to start agent race:", error);
+      throw error;
+    }
+  },
 
-      // If the result is already the settings object, return it
-      return result as ClaudeSettings;
+  /**
+   * Gets a race's variants with the current status of each run
+   * @param raceId - ID returned by startAgentRace
+   * @returns Promise resolving to the race
+   */
+  async getAgentRace(raceId: string): Promise<AgentRace> {
+    try {
+      return await apiCall<AgentRace>("get_agent_race", { raceId });
     } catch (error) {
-      console.error("Failed to get Claude settings:", error);
+      console.error("Failed to get agent race:", error);
       throw error;
     }
   },
 
   /**
-   * Opens a new Claude Code session
-   * @param path - Optional path to open the session in
-   * @returns Promise resolving when the session is opened
+   * Compares runs side by side: status, metrics, final answer and changes made
+   * @param runIds - Agent runs to compare
+   * @returns Promise resolving to one comparison per run, in order
    */
-  async openNewSession(path?: string): Promise<string> {
+  async compareRunOutputs(runIds: number[]): Promise<RunComparison[]> {
     try {
-      return await apiCall<string>("open_new_session", { path });
+      return await apiCall<RunComparison[]>("compare_run_outputs", { runIds });
     } catch (error) {
-      console.error("Failed to open new session:", error);
+      console.error("Failed to compare run outputs:", error);
       throw error;
     }
   },
 
   /**
-   * Reads the CLAUDE.md system prompt file
-   * @returns Promise resolving to the system prompt content
+   * Adds a project to the project switcher, or marks it opened if it is already there
+   * @param path - Absolute path of the project directory
+   * @param name - Name to show; defaults to the directory name
+   * @returns Promise resolving to the registered project
    */
-  async getSystemPrompt(): Promise<string> {
+  async addProject(path: string, name?: string): Promise<ProjectRegistryEntry> {
     try {
-      return await apiCall<string>("get_system_prompt");
+      return await apiCall<ProjectRegistryEntry>("add_project", { path, name });
     } catch (error) {
-      console.error("Failed to get system prompt:", error);
+      console.error("Failed to add project:", error);
       throw error;
     }
   },
 
   /**
-   * Checks if Claude Code is installed and gets its version
-   * @returns Promise resolving to the version status
+   * Removes a project from the project switcher; its files and sessions are kept
+   * @param path - Path the project was registered with
    */
-  async checkClaudeVersion(): Promise<ClaudeVersionStatus> {
+  async removeProject(path: string): Promise<void> {
     try {
-      return await apiCall<ClaudeVersionStatus>("check_claude_version");
+      return await apiCall<void>("remove_project", { path });
     } catch (error) {
-      console.error("Failed to check Claude version:", error);
+      console.error("Failed to remove project:", error);
       throw error;
     }
   },
 
   /**
-   * Saves the CLAUDE.md system prompt file
-   * @param content - The new content for the system prompt
-   * @returns Promise resolving when the file is saved
+   * Pins a project as a favorite, or unpins it
+   * @param path - Path the project was registered with
+   * @param pinned - false to unpin (default true)
    */
-  async saveSystemPrompt(content: string): Promise<string> {
+  async pinProject(path: string, pinned?: boolean): Promise<void> {
     try {
-      return await apiCall<string>("save_system_prompt", { content });
+      return await apiCall<void>("pin_project", { path, pinned });
     } catch (error) {
-      console.error("Failed to save system prompt:", error);
+      console.error("Failed to pin project:", error);
       throw error;
     }
   },
 
   /**
-   * Saves the Claude settings file
-   * @param settings - The settings object to save
-   * @returns Promise resolving when the settings are saved
+   * Lists registered projects, pinned first, then most recently opened, with git branch and dirty state
+   * @returns Promise resolving to the registered projects
    */
-  async saveClaudeSettings(settings: ClaudeSettings): Promise<string> {
+  async listProjectsRegistry(): Promise<ProjectRegistryEntry[]> {
     try {
-      return await apiCall<string>("save_claude_settings", { settings });
+      return await apiCall<ProjectRegistryEntry[]>("list_projects_registry");
     } catch (error) {
-      console.error("Failed to save Claude settings:", error);
+      console.error("Failed to list registered projects:", error);
       throw error;
     }
   },
 
   /**
-   * Finds all CLAUDE.md files in a project directory
-   * @param projectPath - The absolute path to the project
-   * @returns Promise resolving to an array of CLAUDE.md files
+   * Reads a skill from a registry without installing it, to inspect it before trusting it
+   * @param skillName - Name of the skill
+   * @param registry - GitHub owner/repo keeping skills under skills/ (default anthropics/skills)
+   * @returns Promise resolving to the skill's SKILL.md, declared tools and dependencies, and files
    */
-  async findClaudeMdFiles(projectPath: string): Promise<ClaudeMdFile[]> {
+  async previewSkill(skillName: string, registry?: string): Promise<SkillPreview> {
     try {
-      return await apiCall<ClaudeMdFile[]>("find_claude_md_files", { projectPath });
+      return await apiCall<SkillPreview>("preview_skill", { skillName, registry });
     } catch (error) {
-      console.error("Failed to find CLAUDE.md files:", error);
+      console.error("Failed to preview skill:", error);
       throw error;
     }
   },
 
   /**
-   * Reads a specific CLAUDE.md file
-   * @param filePath - The absolute path to the file
-   * @returns Promise resolving to the file content
+   * Copies a session's conversation up to a message into a new session, leaving the original untouched
+   * @param sessionId - The session to fork
+   * @param upToMessageIndex - Index of the last message to keep
+   * @param prompt - Resume the fork with this prompt right away
+   * @param model - Model to resume with; defaults to the one the original last used
+   * @returns Promise resolving to the new session
    */
-  async readClaudeMdFile(filePath: string): Promise<string> {
+  async forkSession(sessionId: string, upToMessageIndex: number, prompt?: string, model?: string): Promise<ForkedSession> {
     try {
-      return await apiCall<string>("read_claude_md_file", { filePath });
+      return await apiCall<ForkedSession>("fork_session", { sessionId, upToMessageIndex, prompt, model });
     } catch (error) {
-      console.error("Failed to read CLAUDE.md file:", error);
+      console.error("Failed to fork session:", error);
       throw error;
     }
   },
 
   /**
-   * Saves a specific CLAUDE.md file
-   * @param filePath - The absolute path to the file
-   * @param content - The new content for the file
-   * @returns Promise resolving when the file is saved
+   * Lists prompts sent in sessions, pinned first, then most recently used
+   * @param projectFilter - Only prompts sent in this project
+   * @param query - Only prompts containing this text, ignoring case
+   * @param limit - Maximum number of prompts (default 100)
+   * @returns Promise resolving to the matching prompts
    */
-  async saveClaudeMdFile(filePath: string, content: string): Promise<string> {
+  async listPromptHistory(projectFilter?: string, query?: string, limit?: number): Promise<PromptHistoryEntry[]> {
     try {
-      return await apiCall<string>("save_claude_md_file", { filePath, content });
+      return await apiCall<PromptHistoryEntry[]>("list_prompt_history", { projectFilter, query, limit });
     } catch (error) {
-      console.error("Failed to save CLAUDE.md file:", error);
+      console.error("Failed to list prompt history:", error);
       throw error;
     }
   },
 
   /**
-   * Saves a temporary image file from base64 data
-   * @param base64Data The base64 encoded image data
-   * @param fileName Optional filename
-   * @returns The absolute path to the saved file
+   * Pins a prompt as a favorite, or unpins it
+   * @param id - The prompt
+   * @param pinned - false to unpin (default true)
    */
-  async saveTempImage(base64Data: string, fileName?: string): Promise<string> {
+  async pinPrompt(id: number, pinned?: boolean): Promise<void> {
     try {
-      return await apiCall<string>("save_temp_image", { base64Data, fileName });
+      return await apiCall<void>("pin_prompt", { id, pinned });
     } catch (error) {
-      console.error("Failed to save temp image:", error);
+      console.error("Failed to pin prompt:", error);
       throw error;
     }
   },
 
-  // Agent API methods
-
   /**
-   * Lists all CC agents
-   * @returns Promise resolving to an array of agents
+   * Deletes prompts from the history
+   * @param ids - Prompts to delete, pinned or not; when omitted every unpinned prompt is deleted
+   * @param projectPath - Without ids, only clear this project's prompts
+   * @returns Promise resolving to the number of prompts removed
    */
-  async listAgents(): Promise<Agent[]> {
+  async deletePromptHistory(ids?: number[], projectPath?: string): Promise<number> {
     try {
-      return await apiCall<Agent[]>('list_agents');
+      return await apiCall<number>("delete_prompt_history", { ids, projectPath });
     } catch (error) {
-      console.error("Failed to list agents:", error);
+      console.error("Failed to delete prompt history:", error);
       throw error;
     }
   },
 
   /**
-   * Creates a new agent
-   * @param name - The agent name
-   * @param icon - The icon identifier
-   * @param system_prompt - The system prompt for the agent
-   * @param default_task - Optional default task
-   * @param model - Optional model (defaults to 'sonnet')
-   * @param hooks - Optional hooks configuration as JSON string
-   * @returns Promise resolving to the created agent
+   * Sends a session's output and events only to one window
+   * @param sessionId - The session to attach
+   * @param windowLabel - Label of the window, e.g. "main" or "project-api"
+   * @returns Promise resolving to the window the session was attached to before, if any
    */
-  async createAgent(
-    name: string,
-    icon: string,
-    system_prompt: string,
-    default_task?: string,
-    model?: string,
-    hooks?: string
-  ): Promise<Agent> {
+  async attachSessionToWindow(sessionId: string, windowLabel: string): Promise<string | null> {
     try {
-      return await apiCall<Agent>('create_agent', {
-        name,
-        icon,
-        systemPrompt: system_prompt,
-        defaultTask: default_task,
-        model,
-        hooks
-      });
+      return await apiCall<string | null>("attach_session_to_window", { sessionId, windowLabel });
     } catch (error) {
-      console.error("Failed to create agent:", error);
+      console.error("Failed to attach session to window:", error);
       throw error;
     }
   },
 
   /**
-   * Updates an existing agent
-   * @param id - The agent ID
-   * @param name - The updated name
-   * @param icon - The updated icon
-   * @param system_prompt - The updated system prompt
-   * @param default_task - Optional default task
-   * @param model - Optional model
-   * @param hooks - Optional hooks configuration as JSON string
-   * @returns Promise resolving to the updated agent
+   * Sends a session's output and events to every window again
+   * @param sessionId - The session to detach
+   * @returns Promise resolving to the window it was attached to, if any
    */
-  async updateAgent(
-    id: number,
-    name: string,
-    icon: string,
-    system_prompt: string,
-    default_task?: string,
-    model?: string,
-    hooks?: string
-  ): Promise<Agent> {
+  async detachSessionFromWindow(sessionId: string): Promise<string | null> {
     try {
-      return await apiCall<Agent>('update_agent', {
-        id,
-        name,
-        icon,
-        systemPrompt: system_prompt,
-        defaultTask: default_task,
-        model,
-        hooks
-      });
+      return await apiCall<string | null>("detach_session_from_window", { sessionId });
     } catch (error) {
-      console.error("Failed to update agent:", error);
+      console.error("Failed to detach session from window:", error);
       throw error;
     }
   },
 
   /**
-   * Deletes an agent
-   * @param id - The agent ID to delete
-   * @returns Promise resolving when the agent is deleted
+   * Lists the sessions attached to a window
+   * @param windowLabel - Label of the window
+   * @returns Promise resolving to the attached session IDs
    */
-  async deleteAgent(id: number): Promise<void> {
+  async getWindowSessions(windowLabel: string): Promise<string[]> {
     try {
-      return await apiCall('delete_agent', { id });
+      return await apiCall<string[]>("get_window_sessions", { windowLabel });
     } catch (error) {
-      console.error("Failed to delete agent:", error);
+      console.error("Failed to get window sessions:", error);
       throw error;
     }
   },
 
   /**
-   * Gets a single agent by ID
-   * @param id - The agent ID
-   * @returns Promise resolving to the agent
+   * Gets the permission rules of a Claude settings file
+   * @param scope - "user", "project" or "local"
+   * @param projectPath - The project directory, for project and local scope
+   * @returns Promise resolving to the allow, ask and deny rules
    */
-  async getAgent(id: number): Promise<Agent> {
+  async getPermissionRules(scope: string, projectPath?: string): Promise<PermissionRules> {
     try {
-      return await apiCall<Agent>('get_agent', { id });
+      return await apiCall<PermissionRules>("get_permission_rules", { scope, projectPath });
     } catch (error) {
-      console.error("Failed to get agent:", error);
+      console.error("Failed to get permission rules:", error);
       throw error;
     }
   },
 
-
   /**
-   * Lists available Anthropic models
-   * @param apiKey - Optional API Key (if not set in backend env)
-   * @returns Promise resolving to ModelsResponse
+   * Replaces the permission rules of a Claude settings file, keeping its other settings
+   * @param scope - "user", "project" or "local"
+   * @param rules - The new allow, ask and deny rules
+   * @param projectPath - The project directory, for project and local scope
+   * @returns Promise resolving to the rules as saved
    */
-  async listAnthropicModels(apiKey?: string): Promise<ModelsResponse> {
+  async updatePermissionRules(scope: string, rules: PermissionRules, projectPath?: string): Promise<PermissionRules> {
     try {
-      return await apiCall<ModelsResponse>('list_anthropic_models', { apiKey });
+      return await apiCall<PermissionRules>("update_permission_rules", { scope, rules, projectPath });
     } catch (error) {
-      console.error("Failed to list Anthropic models:", error);
+      console.error("Failed to update permission rules:", error);
       throw error;
     }
   },
 
   /**
-   * Exports a single agent to JSON format
-   * @param id - The agent ID to export
-   * @returns Promise resolving to the JSON string
+   * Checks whether Claude would run a tool invocation, without running it
+   * @param tool - Tool name, e.g. "Bash" or "mcp__github__create_issue"
+   * @param input - Tool input, e.g. { command: "npm run test" }
+   * @param scope - Only use this settings file's rules; all scopes when omitted
+   * @param projectPath - The project the invocation runs in
+   * @param draft - Unsaved rules to use in place of the scope's rules
+   * @returns Promise resolving to the decision and the rule that made it
    */
-  async exportAgent(id: number): Promise<string> {
+  async evaluatePermission(
+    tool: string,
+    input: Record<string, unknown>,
+    scope?: string,
+    projectPath?: string,
+    draft?: PermissionRules
+  ): Promise<PermissionEvaluation> {
     try {
-      return await apiCall<string>('export_agent', { id });
+      return await apiCall<PermissionEvaluation>("evaluate_permission", { tool, input, scope, projectPath, draft });
     } catch (error) {
-      console.error("Failed to export agent:", error);
+      console.error("Failed to evaluate permission:", error);
       throw error;
     }
   },
 
   /**
-   * Imports an agent from JSON data
-   * @param jsonData - The JSON string containing the agent export
-   * @returns Promise resolving to the imported agent
+   * Gets the cache prewarm schedule and the outcome of the last prewarm
+   * @returns Promise resolving to the prewarm status
    */
-  async importAgent(jsonData: string): Promise<Agent> {
+  async getPrewarmStatus(): Promise<PrewarmStatus> {
     try {
-      return await apiCall<Agent>('import_agent', { jsonData });
+      return await apiCall<PrewarmStatus>("get_prewarm_status");
     } catch (error) {
-      console.error("Failed to import agent:", error);
+      console.error("Failed to get prewarm status:", error);
       throw error;
     }
   },
 
   /**
-   * Imports an agent from a file
-   * @param filePath - The path to the JSON file
-   * @returns Promise resolving to the imported agent
+   * Refreshes the model list, marketplace catalogs and MCP health checks now
+   * @returns Promise resolving to false if a prewarm was already running
    */
-  async importAgentFromFile(filePath: string): Promise<Agent> {
+  async runPrewarmNow(): Promise<boolean> {
     try {
-      return await apiCall<Agent>('import_agent_from_file', { filePath });
+      return await apiCall<boolean>("run_prewarm_now");
     } catch (error) {
-      console.error("Failed to import agent from file:", error);
+      console.error("Failed to run prewarm:", error);
       throw error;
     }
   },
 
   /**
-   * Executes an agent
-   * @param agentId - The agent ID to execute
-   * @param projectPath - The project path to run the agent in
-   * @param task - The task description
-   * @param model - Optional model override
-   * @returns Promise resolving to the run ID when execution starts
+   * Queries the audit log of files opcode created, modified or deleted
+   * @param filter - Optional filter; page back with before_id set to the last ID returned
+   * @returns Promise resolving to the matching entries, newest first
    */
-  async executeAgent(agentId: number, projectPath: string, task: string, model?: string): Promise<number> {
+  async queryAuditLog(filter?: FileAuditFilter): Promise<FileAuditEntry[]> {
     try {
-      return await apiCall<number>('execute_agent', { agentId, projectPath, task, model });
+      return await apiCall<FileAuditEntry[]>("query_audit_log", { filter });
     } catch (error) {
-      console.error("Failed to execute agent:", error);
-      // Return a sentinel value to indicate error
-      throw new Error(`Failed to execute agent: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to query file audit log:", error);
+      throw error;
     }
   },
 
   /**
-   * Lists agent runs without metrics (basic info only)
-   * @param agentId - Optional agent ID to filter runs
-   * @returns Promise resolving to an array of agent runs
+   * Sets an environment variable for every run in a project
+   * @param projectPath - The project directory
+   * @param key - Variable name
+   * @param value - Variable value
+   * @param secret - Keep the value in the OS keychain instead of the database
+   * @returns Promise resolving to the saved variable, without the value of a secret
    */
-  async listAgentRuns(agentId?: number): Promise<AgentRunWithMetrics[]> {
+  async setProjectEnv(
+    projectPath: string,
+    key: string,
+    value: string,
+    secret: boolean
+  ): Promise<ProjectEnvVar> {
     try {
-      return await apiCall<AgentRunWithMetrics[]>('list_agent_runs', { agentId });
+      return await apiCall<ProjectEnvVar>("set_project_env", { projectPath, key, value, secret });
     } catch (error) {
-      console.error("Failed to list agent runs:", error);
-      // Return empty array instead of throwing to prevent UI crashes
-      return [];
+      console.error("Failed to set project environment variable:", error);
+      throw error;
     }
   },
 
   /**
-   * Lists agent runs with metrics (includes token counts and duration)
-   * @param agentId - Optional agent ID to filter runs
-   * @returns Promise resolving to an array of agent runs with metrics
+   * Lists the environment variables of a project
+   * @param projectPath - The project directory
+   * @returns Promise resolving to the variables; secret values are left out
    */
-  async listAgentRunsWithMetrics(agentId?: number): Promise<AgentRunWithMetrics[]> {
+  async listProjectEnv(projectPath: string): Promise<ProjectEnvVar[]> {
     try {
-      return await apiCall<AgentRunWithMetrics[]>('list_agent_runs_with_metrics', { agentId });
+      return await apiCall<ProjectEnvVar[]>("list_project_env", { projectPath });
     } catch (error) {
-      console.error("Failed to list agent runs with metrics:", error);
-      // Return empty array instead of throwing to prevent UI crashes
-      return [];
+      console.error("Failed to list project environment variables:", error);
+      throw error;
     }
   },
 
   /**
-   * Gets a single agent run by ID with metrics
-   * @param id - The run ID
-   * @returns Promise resolving to the agent run with metrics
+   * Removes an environment variable from a project
+   * @param projectPath - The project directory
+   * @param key - Variable name
+   * @returns Promise resolving to whether the variable existed
    */
-  async getAgentRun(id: number): Promise<AgentRunWithMetrics> {
+  async deleteProjectEnv(projectPath: string, key: string): Promise<boolean> {
     try {
-      return await apiCall<AgentRunWithMetrics>('get_agent_run', { id });
+      return await apiCall<boolean>("delete_project_env", { projectPath, key });
     } catch (error) {
-      console.error("Failed to get agent run:", error);
-      throw new Error(`Failed to get agent run: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to delete project environment variable:", error);
+      throw error;
     }
   },
 
   /**
-   * Gets a single agent run by ID with real-time metrics from JSONL
-   * @param id - The run ID
-   * @returns Promise resolving to the agent run with metrics
+   * Lists the file:line references in a transcript message
+   * @param sessionId - The session ID
+   * @param messageIndex - Index of the message in the transcript
+   * @returns Promise resolving to the references in the message
    */
-  async getAgentRunWithRealTimeMetrics(id: number): Promise<AgentRunWithMetrics> {
+  async listMessageReferences(sessionId: string, messageIndex: number): Promise<FileReference[]> {
     try {
-      return await apiCall<AgentRunWithMetrics>('get_agent_run_with_real_time_metrics', { id });
+      return await apiCall<FileReference[]>("list_message_references", { sessionId, messageIndex });
     } catch (error) {
-      console.error("Failed to get agent run with real-time metrics:", error);
-      throw new Error(`Failed to get agent run with real-time metrics: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to list message references:", error);
+      throw error;
     }
   },
 
   /**
-   * Lists all currently running agent sessions
-   * @returns Promise resolving to list of running agent sessions
+   * Resolves a file:line reference from a transcript message to a file in the project
+   * @param sessionId - The session ID
+   * @param messageIndex - Index of the message the reference is in
+   * @param reference - The reference, e.g. src/main.rs:42
+   * @returns Promise resolving to where to open the file, following renames
    */
-  async listRunningAgentSessions(): Promise<AgentRun[]> {
+  async resolveReference(
+    sessionId: string,
+    messageIndex: number,
+    reference: string
+  ): Promise<ReferenceTarget> {
     try {
-      return await apiCall<AgentRun[]>('list_running_sessions');
+      return await apiCall<ReferenceTarget>("resolve_reference", {
+        sessionId,
+        messageIndex,
+        reference,
+      });
     } catch (error) {
-      console.error("Failed to list running agent sessions:", error);
-      throw new Error(`Failed to list running agent sessions: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to resolve reference:", error);
+      throw error;
     }
   },
 
   /**
-   * Kills a running agent session
-   * @param runId - The run ID to kill
-   * @returns Promise resolving to whether the session was successfully killed
+   * Merges a zipped ~/.claude from another machine into the Claude directory
+   * @param archivePath - Path to the zip archive
+   * @param conflictPolicy - What to do with files that differ locally; defaults to skip
+   * @param dryRun - Only report what would happen
+   * @returns Promise resolving to what happens to each entry
    */
-  async killAgentSession(runId: number): Promise<boolean> {
+  async importClaudeDirArchive(
+    archivePath: string,
+    conflictPolicy: "skip" | "rename" | "overwrite" | undefined,
+    dryRun: boolean
+  ): Promise<ClaudeDirImportReport> {
     try {
-      return await apiCall<boolean>('kill_agent_session', { runId });
+      return await apiCall<ClaudeDirImportReport>("import_claude_dir_archive", {
+        archivePath,
+        conflictPolicy,
+        dryRun,
+      });
     } catch (error) {
-      console.error("Failed to kill agent session:", error);
-      throw new Error(`Failed to kill agent session: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to import Claude directory archive:", error);
+      throw error;
     }
   },
 
   /**
-   * Gets the status of a specific agent session
-   * @param runId - The run ID to check
-   * @returns Promise resolving to the session status or null if not found
+   * Installs several registry skills in a project, all or nothing
+   * @param projectPath - The project to install the skills in
+   * @param names - Names of the skills
+   * @returns Promise resolving to what happened to each skill
    */
-  async getSessionStatus(runId: number): Promise<string | null> {
+  async installSkillBundle(projectPath: string, names: string[]): Promise<BulkReport> {
     try {
-      return await apiCall<string | null>('get_session_status', { runId });
+      return await apiCall<BulkReport>("install_skill_bundle", { projectPath, names });
     } catch (error) {
-      console.error("Failed to get session status:", error);
-      throw new Error(`Failed to get session status: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to install skill bundle:", error);
+      throw error;
     }
   },
 
   /**
-   * Cleanup finished processes and update their status
-   * @returns Promise resolving to list of run IDs that were cleaned up
+   * Lists the saved project profiles
+   * @returns Promise resolving to the profiles
    */
-  async cleanupFinishedProcesses(): Promise<number[]> {
+  async listProjectProfiles(): Promise<ProjectProfile[]> {
     try {
-      return await apiCall<number[]>('cleanup_finished_processes');
+      return await apiCall<ProjectProfile[]>("list_project_profiles");
     } catch (error) {
-      console.error("Failed to cleanup finished processes:", error);
-      throw new Error(`Failed to cleanup finished processes: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to list project profiles:", error);
+      throw error;
     }
   },
 
   /**
-   * Get real-time output for a running session (with live output fallback)
-   * @param runId - The run ID to get output for
-   * @returns Promise resolving to the current session output (JSONL format)
+   * Saves a project profile, replacing the one with the same name
+   * @param profile - The profile to save
+   * @returns Promise resolving when the profile is saved
    */
-  async getSessionOutput(runId: number): Promise<string> {
+  async saveProjectProfile(profile: ProjectProfile): Promise<void> {
     try {
-      return await apiCall<string>('get_session_output', { runId });
+      return await apiCall<void>("save_project_profile", { profile });
     } catch (error) {
-      console.error("Failed to get session output:", error);
-      throw new Error(`Failed to get session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to save project profile:", error);
+      throw error;
     }
   },
 
   /**
-   * Get live output directly from process stdout buffer
-   * @param runId - The run ID to get live output for
-   * @returns Promise resolving to the current live output
+   * Deletes a project profile
+   * @param name - Name of the profile
+   * @returns Promise resolving to whether the profile existed
    */
-  async getLiveSessionOutput(runId: number): Promise<string> {
+  async deleteProjectProfile(name: string): Promise<boolean> {
     try {
-      return await apiCall<string>('get_live_session_output', { runId });
+      return await apiCall<boolean>("delete_project_profile", { name });
     } catch (error) {
-      console.error("Failed to get live session output:", error);
-      throw new Error(`Failed to get live session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to delete project profile:", error);
+      throw error;
     }
   },
 
   /**
-   * Start streaming real-time output for a running session
-   * @param runId - The run ID to stream output for
-   * @returns Promise that resolves when streaming starts
+   * Applies a saved profile to a project, all or nothing
+   * @param projectPath - The project to apply the profile to
+   * @param profile - Name of the profile
+   * @returns Promise resolving to what happened to each item
    */
-  async streamSessionOutput(runId: number): Promise<void> {
+  async applyProjectProfile(projectPath: string, profile: string): Promise<BulkReport> {
     try {
-      return await apiCall<void>('stream_session_output', { runId });
+      return await apiCall<BulkReport>("apply_project_profile", { projectPath, profile });
     } catch (error) {
-      console.error("Failed to start streaming session output:", error);
-      throw new Error(`Failed to start streaming session output: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      console.error("Failed to apply project profile:", error);
+      throw error;
     }
   },
 
   /**
-   * Loads the JSONL history for a specific session
+   * Gets what was restored if the app database was corrupt at launch
+   * @returns Promise resolving to the recovery report, or null when the database was sound
    */
-  async loadSessionHistory(sessionId: string, projectId: string): Promise<any[]> {
-    return apiCall("load_session_history", { sessionId, projectId });
+  async getDatabaseRecovery(): Promise<DatabaseRecovery | null> {
+    try {
+      return await apiCall<DatabaseRecovery | null>("get_database_recovery");
+    } catch (error) {
+      console.error("Failed to get database recovery:", error);
+      throw error;
+    }
   },
 
   /**
-   * Loads the JSONL history for a specific agent session
-   * Similar to loadSessionHistory but searches across all project directories
-   * @param sessionId - The session ID (UUID)
-   * @returns Promise resolving to array of session messages
+   * Gets the installed Claude Code version and which CLI features it supports
+   * @returns Promise resolving to the feature matrix and upgrade warnings
    */
-  async loadAgentSessionHistory(sessionId: string): Promise<any[]> {
+  async getCliCapabilities(): Promise<CliCapabilities> {
     try {
-      return await apiCall<any[]>('load_agent_session_history', { sessionId });
+      return await apiCall<CliCapabilities>("get_cli_capabilities");
     } catch (error) {
-      console.error("Failed to load agent session history:", error);
+      console.error("Failed to get CLI capabilities:", error);
       throw error;
     }
   },
 
   /**
-   * Executes a new interactive Claude Code session with streaming output
+   * Compares the running version with the latest opcode release
+   * @returns Promise resolving to both versions and the release notes
    */
-  async executeClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
-    return apiCall("execute_claude_code", { projectPath, prompt, model });
+  async checkForUpdates(): Promise<UpdateInfo> {
+    try {
+      return await apiCall<UpdateInfo>("check_for_updates");
+    } catch (error) {
+      console.error("Failed to check for updates:", error);
+      throw error;
+    }
   },
 
   /**
-   * Continues an existing Claude Code conversation with streaming output
+   * Downloads and installs the latest version; progress is reported as an
+   * "app_update" operation and the update applies from the next launch
+   * @returns Promise resolving to the installed version
    */
-  async continueClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
-    return apiCall("continue_claude_code", { projectPath, prompt, model });
+  async downloadAndInstallUpdate(): Promise<string> {
+    try {
+      return await apiCall<string>("download_and_install_update");
+    } catch (error) {
+      console.error("Failed to install update:", error);
+      throw error;
+    }
   },
 
   /**
-   * Resumes an existing Claude Code session by ID with streaming output
+   * Subscribes to the events matching a filter; they arrive as BusEvents on
+   * the `event-bus:<id>` event instead of every event on `event-bus`
+   * @param filter - Which events to receive
+   * @returns Promise resolving to the subscription ID
    */
-  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string): Promise<void> {
-    return apiCall("resume_claude_code", { projectPath, sessionId, prompt, model });
+  async subscribeEvents(filter: EventFilter): Promise<number> {
+    try {
+      return await apiCall<number>("subscribe_events", { filter });
+    } catch (error) {
+      console.error("Failed to subscribe to events:", error);
+      throw error;
+    }
   },
 
   /**
-   * Cancels the currently running Claude Code execution
-   * @param sessionId - Optional session ID to cancel a specific session
+   * Closes an event subscription
+   * @param id - The subscription ID
+   * @returns Promise resolving to whether the subscription was open
    */
-  async cancelClaudeExecution(sessionId?: string): Promise<void> {
-    return apiCall("cancel_claude_execution", { sessionId });
+  async unsubscribeEvents(id: number): Promise<boolean> {
+    try {
+      return await apiCall<boolean>("unsubscribe_events", { id });
+    } catch (error) {
+      console.error("Failed to unsubscribe from events:", error);
+      throw error;
+    }
   },
 
   /**
-   * Executes the Claude Agent SDK via a sidecar script (Sidecar pattern).
-   * @param projectPath - The project path to run in
-   * @param prompt - The user prompt
-   * @param model - The model to use
-   * @returns Promise resolving when the session is started
+   * Lists runs waiting for a project lock or held for a decision
+   * @returns Promise resolving to the queued runs, oldest first
    */
-  async executeSdkAgent(projectPath: string, prompt: string, model: string, sessionId?: string): Promise<void> {
-    return apiCall("execute_sdk_agent", { projectPath, prompt, model, sessionId });
+  async listQueuedRuns(): Promise<QueuedRun[]> {
+    try {
+      return await apiCall<QueuedRun[]>("list_queued_runs");
+    } catch (error) {
+      console.error("Failed to list queued runs:", error);
+      throw error;
+    }
   },
 
   /**
-   * Lists all currently running Claude sessions
-   * @returns Promise resolving to list of running Claude sessions
+   * Starts a held run now
+   * @param id - The queued run ID
+   * @param env - Values for the run's `env_keys`, which aren't stored
+   * @returns Promise resolving to the ID of the new agent run
    */
-  async listRunningClaudeSessions(): Promise<any[]> {
-    return apiCall("list_running_claude_sessions");
+  async startQueuedRun(id: number, env?: Record<string, string>): Promise<number> {
+    try {
+      return await apiCall<number>("start_queued_run", { id, env });
+    } catch (error) {
+      console.error("Failed to start queued run:", error);
+      throw error;
+    }
   },
 
   /**
-   * Gets live output from a Claude session
-   * @param sessionId - The session ID to get output for
-   * @returns Promise resolving to the current live output
+   * Drops a held run without starting it
+   * @param id - The queued run ID
    */
-  async getClaudeSessionOutput(sessionId: string): Promise<string> {
-    return apiCall("get_claude_session_output", { sessionId });
+  async dismissQueuedRun(id: number): Promise<void> {
+    try {
+      return await apiCall<void>("dismiss_queued_run", { id });
+    } catch (error) {
+      console.error("Failed to dismiss queued run:", error);
+      throw error;
+    }
   },
 
   /**
-   * Lists files and directories in a given path
+   * Gets which first-run setup steps are complete, checking each one again
+   * @returns Promise resolving to the steps in setup order and the current one
    */
-  async listDirectoryContents(directoryPath: string): Promise<FileEntry[]> {
-    return apiCall("list_directory_contents", { directoryPath });
+  async getOnboardingStatus(): Promise<OnboardingStatus> {
+    try {
+      return await apiCall<OnboardingStatus>("get_onboarding_status");
+    } catch (error) {
+      console.error("Failed to get onboarding status:", error);
+      throw error;
+    }
   },
 
   /**
-   * Searches for files and directories matching a pattern
+   * Marks a setup step complete, e.g. one the app can't detect
+   * @param step - The step ID
+   * @returns Promise resolving to the updated status
    */
-  async searchFiles(basePath: string, query: string): Promise<FileEntry[]> {
-    return apiCall("search_files", { basePath, query });
+  async completeOnboardingStep(step: OnboardingStep["id"]): Promise<OnboardingStatus> {
+    try {
+      return await apiCall<OnboardingStatus>("complete_onboarding_step", { step });
+    } catch (error) {
+      console.error("Failed to complete onboarding step:", error);
+      throw error;
+    }
   },
 
   /**
-   * Gets overall usage statistics
-   * @returns Promise resolving to usage statistics
+   * Runs a small fixed prompt suite against each model and compares latency, cost and output length
+   * @param promptSet - "quick" or "coding"
+   * @param models - Up to 6 model IDs
+   * @param projectPath - Project to save the comparison under
+   * @returns Promise resolving to the comparison table and every prompt's result
    */
-  async getUsageStats(): Promise<UsageStats> {
+  async benchmarkModels(promptSet: "quick" | "coding", models: string[], projectPath?: string): Promise<BenchmarkReport> {
     try {
-      return await apiCall<UsageStats>("get_usage_stats");
+      return await apiCall<BenchmarkReport>("benchmark_models", { promptSet, models, projectPath });
     } catch (error) {
-      console.error("Failed to get usage stats:", error);
+      console.error("Failed to benchmark models:", error);
       throw error;
     }
   },
 
   /**
-   * Gets usage statistics filtered by date range
-   * @param startDate - Start date (ISO format)
-   * @param endDate - End date (ISO format)
-   * @returns Promise resolving to usage statistics
+   * Lists saved benchmark rows, newest first
+   * @param projectPath - Only benchmarks saved under this project
+   * @param limit - Most rows to return (default 100)
    */
-  async getUsageByDateRange(startDate: string, endDate: string): Promise<UsageStats> {
+  async listModelBenchmarks(projectPath?: string, limit?: number): Promise<ModelBenchmark[]> {
     try {
-      return await apiCall<UsageStats>("get_usage_by_date_range", { startDate, endDate });
+      return await apiCall<ModelBenchmark[]>("list_model_benchmarks", { projectPath, limit });
     } catch (error) {
-      console.error("Failed to get usage by date range:", error);
+      console.error("Failed to list model benchmarks:", error);
       throw error;
     }
   },
 
   /**
-   * Gets usage statistics grouped by session
-   * @param since - Optional start date (YYYYMMDD)
-   * @param until - Optional end date (YYYYMMDD)
-   * @param order - Optional sort order ('asc' or 'desc')
-   * @returns Promise resolving to an array of session usage data
+   * Fetches the organization's billed usage and cost from the Anthropic Admin API
+   * @param range - Dates to fetch, inclusive
+   * @param apiKey - Admin key (sk-ant-admin...); defaults to the configured admin key environment variable
+   * @returns Promise resolving to usage in the shape of getUsageByDateRange, with workspaces as projects
    */
-  async getSessionStats(
-    since?: string,
-    until?: string,
-    order?: "asc" | "desc"
-  ): Promise<ProjectUsage[]> {
+  async fetchAnthropicUsage(range: UsageRange, apiKey?: string): Promise<UsageStats> {
     try {
-      return await apiCall<ProjectUsage[]>("get_session_stats", {
-        since,
-        until,
-        order,
-      });
+      return await apiCall<UsageStats>("fetch_anthropic_usage", { range, apiKey });
     } catch (error) {
-      console.error("Failed to get session stats:", error);
+      console.error("Failed to fetch Anthropic usage:", error);
       throw error;
     }
   },
 
   /**
-   * Gets detailed usage entries with optional filtering
-   * @param limit - Optional limit for number of entries
-   * @returns Promise resolving to array of usage entries
+   * Gets how a project's agents, skills, MCP servers, hooks and permission rules depend on each other
+   * @param projectPath - The project directory
+   * @returns Promise resolving to the graph; nodes without edges are candidates for pruning
    */
-  async getUsageDetails(limit?: number): Promise<UsageEntry[]> {
+  async getConfigGraph(projectPath: string): Promise<ConfigGraph> {
     try {
-      return await apiCall<UsageEntry[]>("get_usage_details", { limit });
+      return await apiCall<ConfigGraph>("get_config_graph", { projectPath });
     } catch (error) {
-      console.error("Failed to get usage details:", error);
+      console.error("Failed to get config graph:", error);
       throw error;
     }
   },
 
   /**
-   * Creates a checkpoint for the current session state
+   * Renders an agent template's prompt with values for its variables
+   * @param name - The template name
+   * @param variables - Values by variable name; missing ones use their default
+   * @returns Promise resolving to the final prompt
    */
-  async createCheckpoint(
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    messageIndex?: number,
-    description?: string
-  ): Promise<CheckpointResult> {
-    return apiCall("create_checkpoint", {
-      sessionId,
-      projectId,
-      projectPath,
-      messageIndex,
-      description
-    });
+  async renderAgentTemplate(name: string, variables: Record<string, string>): Promise<string> {
+    try {
+      return await apiCall<string>("render_agent_template", { name, variables });
+    } catch (error) {
+      console.error("Failed to render agent template:", error);
+      throw error;
+    }
   },
 
   /**
-   * Restores a session to a specific checkpoint
+   * Gets GitHub stars, open issues and last commit of marketplace entries
+   * @param urls - GitHub URLs of skills, MCP servers or agents
+   * @returns Promise resolving to the popularity by URL; entries that couldn't be fetched are missing
    */
-  async restoreCheckpoint(
-    checkpointId: string,
-    sessionId: string,
-    projectId: string,
-    projectPath: string
-  ): Promise<CheckpointResult> {
-    return apiCall("restore_checkpoint", {
-      checkpointId,
-      sessionId,
-      projectId,
-      projectPath
-    });
+  async getMarketplacePopularity(urls: string[]): Promise<Record<string, Popularity>> {
+    try {
+      return await apiCall<Record<string, Popularity>>("get_marketplace_popularity", { urls });
+    } catch (error) {
+      console.error("Failed to get marketplace popularity:", error);
+      throw error;
+    }
   },
 
   /**
-   * Lists all checkpoints for a session
+   * Lists the skills installed in a project and for the user, including disabled ones
+   * @param projectPath - The project directory; omit to list only user skills
+   * @returns Promise resolving to the skills, project skills before user skills of the same name
    */
-  async listCheckpoints(
-    sessionId: string,
-    projectId: string,
-    projectPath: string
-  ): Promise<Checkpoint[]> {
-    return apiCall("list_checkpoints", {
-      sessionId,
-      projectId,
-      projectPath
-    });
+  async listInstalledSkills(projectPath?: string): Promise<SkillEntry[]> {
+    try {
+      return await apiCall<SkillEntry[]>("list_installed_skills", { projectPath });
+    } catch (error) {
+      console.error("Failed to list installed skills:", error);
+      throw error;
+    }
   },
 
   /**
-   * Forks a new timeline branch from a checkpoint
+   * Enables or disables a project skill without deleting it
+   * @param projectPath - The project directory
+   * @param skillName - The skill to move in or out of .claude/skills/.disabled
+   * @param enabled - Whether Claude should load the skill
+   * @returns Promise resolving to the skill in its new state
    */
-  async forkFromCheckpoint(
-    checkpointId: string,
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    newSessionId: string,
-    description?: string
-  ): Promise<CheckpointResult> {
-    return apiCall("fork_from_checkpoint", {
-      checkpointId,
-      sessionId,
-      projectId,
-      projectPath,
-      newSessionId,
-      description
-    });
+  async setSkillEnabled(projectPath: string, skillName: string, enabled: boolean): Promise<SkillEntry> {
+    try {
+      return await apiCall<SkillEntry>("set_skill_enabled", { projectPath, skillName, enabled });
+    } catch (error) {
+      console.error("Failed to set skill enabled:", error);
+      throw error;
+    }
   },
 
   /**
-   * Gets the timeline for a session
+   * Searches the skill or MCP server catalog, fetching it when it isn't cached
+   * @param kind - "skills" or "mcp_servers"
+   * @param query - Words to match against names (typo-tolerant) and descriptions
+   * @param category - Only entries in this category
+   * @param page - Page to return, starting at 0
+   * @param pageSize - Entries per page (default 50, at most 200)
+   * @param sort - "relevance" (default), "popularity" (stars) or "recent" (last commit)
+   * @returns Promise resolving to the page, ordered by `sort` then name
    */
-  async getSessionTimeline(
-    sessionId: string,
-    projectId: string,
-    projectPath: string
-  ): Promise<SessionTimeline> {
-    return apiCall("get_session_timeline", {
-      sessionId,
-      projectId,
-      projectPath
-    });
+  async searchCatalog(
+    kind: "skills" | "mcp_servers",
+    query?: string,
+    category?: string,
+    page?: number,
+    pageSize?: number,
+    sort?: "relevance" | "popularity" | "recent"
+  ): Promise<CatalogPage> {
+    try {
+      return await apiCall<CatalogPage>("search_catalog", { kind, query, category, sort, page, pageSize });
+    } catch (error) {
+      console.error("Failed to search catalog:", error);
+      throw error;
+    }
   },
 
   /**
-   * Updates checkpoint settings for a session
+   * Checks settings, hooks and MCP configs against breaking changes since the last migrated CLI version
+   * @param projectPath - Also check this project's files; only user-level files otherwise
+   * @returns Promise resolving to the migration checklist
    */
-  async updateCheckpointSettings(
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    autoCheckpointEnabled: boolean,
-    checkpointStrategy: CheckpointStrategy
-  ): Promise<void> {
-    return apiCall("update_checkpoint_settings", {
-      sessionId,
-      projectId,
-      projectPath,
-      autoCheckpointEnabled,
-      checkpointStrategy
-    });
+  async scanCliMigration(projectPath?: string): Promise<MigrationReport> {
+    try {
+      return await apiCall<MigrationReport>("scan_cli_migration", { projectPath });
+    } catch (error) {
+      console.error("Failed to scan for CLI migrations:", error);
+      throw error;
+    }
   },
 
   /**
-   * Gets diff between two checkpoints
+   * Applies the automated fix for a checklist item, keeping a .bak copy of the file
+   * @param id - ID of the breaking change
+   * @param file - The file to fix, as reported by scanCliMigration
+   * @param projectPath - The project the file belongs to, for project files
+   * @returns Promise resolving to the fixed item
    */
-  async getCheckpointDiff(
-    fromCheckpointId: string,
-    toCheckpointId: string,
-    sessionId: string,
-    projectId: string
-  ): Promise<CheckpointDiff> {
+  async applyCliMigrationFix(id: string, file: string, projectPath?: string): Promise<MigrationItem> {
     try {
-      return await apiCall<CheckpointDiff>("get_checkpoint_diff", {
-        fromCheckpointId,
-        toCheckpointId,
-        sessionId,
-        projectId
-      });
+      return await apiCall<MigrationItem>("apply_cli_migration_fix", { projectPath, id, file });
     } catch (error) {
-      console.error("Failed to get checkpoint diff:", error);
+      console.error("Failed to apply CLI migration fix:", error);
       throw error;
     }
   },
 
   /**
-   * Tracks a message for checkpointing
+   * Records that the configuration was migrated to the installed CLI version
+   * @returns Promise resolving to the recorded version, or null if it can't be detected
    */
-  async trackCheckpointMessage(
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    message: string
-  ): Promise<void> {
+  async acknowledgeCliMigration(): Promise<string | null> {
     try {
-      await apiCall("track_checkpoint_message", {
-        sessionId,
-        projectId,
-        projectPath,
-        message
-      });
+      return await apiCall<string | null>("acknowledge_cli_migration");
     } catch (error) {
-      console.error("Failed to track checkpoint message:", error);
+      console.error("Failed to acknowledge CLI migration:", error);
       throw error;
     }
   },
 
   /**
-   * Checks if auto-checkpoint should be triggered
+   * Checks the commands, paths and dependencies CLAUDE.md mentions against the repository
+   * @param projectPath - The project directory
+   * @returns Promise resolving to each section with its stale claims
    */
-  async checkAutoCheckpoint(
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    message: string
-  ): Promise<boolean> {
+  async checkClaudeMdFreshness(projectPath: string): Promise<ClaudeMdFreshness> {
     try {
-      return await apiCall<boolean>("check_auto_checkpoint", {
-        sessionId,
-        projectId,
-        projectPath,
-        message
-      });
+      return await apiCall<ClaudeMdFreshness>("check_claude_md_freshness", { projectPath });
     } catch (error) {
-      console.error("Failed to check auto checkpoint:", error);
+      console.error("Failed to check CLAUDE.md freshness:", error);
       throw error;
     }
   },
 
   /**
-   * Triggers cleanup of old checkpoints
+   * Rewrites a stale CLAUDE.md section with the Anthropic API
+   * @param projectPath - The project directory
+   * @param heading - Heading of the section to rewrite
+   * @param apply - Write the result to CLAUDE.md instead of only returning it
+   * @param model - Model to use; a default is used if omitted
+   * @returns Promise resolving to the original and rewritten section
    */
-  async cleanupOldCheckpoints(
-    sessionId: string,
-    projectId: string,
+  async regenerateClaudeMdSection(
     projectPath: string,
-    keepCount: number
-  ): Promise<number> {
+    heading: string,
+    apply: boolean,
+    model?: string
+  ): Promise<RegeneratedSection> {
     try {
-      return await apiCall<number>("cleanup_old_checkpoints", {
-        sessionId,
-        projectId,
+      return await apiCall<RegeneratedSection>("regenerate_claude_md_section", {
         projectPath,
-        keepCount
+        heading,
+        apply,
+        model,
       });
     } catch (error) {
-      console.error("Failed to cleanup old checkpoints:", error);
+      console.error("Failed to regenerate CLAUDE.md section:", error);
       throw error;
     }
   },
 
   /**
-   * Gets checkpoint settings for a session
+   * Starts live events for changes in a project's .claude directory
+   * @param projectPath - The open project
+   * @returns Promise resolving when the project is watched
    */
-  async getCheckpointSettings(
-    sessionId: string,
-    projectId: string,
-    projectPath: string
-  ): Promise<{
-    auto_checkpoint_enabled: boolean;
-    checkpoint_strategy: CheckpointStrategy;
-    total_checkpoints: number;
-    current_checkpoint_id?: string;
-  }> {
+  async watchProjectClaudeDir(projectPath: string): Promise<void> {
     try {
-      return await apiCall("get_checkpoint_settings", {
-        sessionId,
-        projectId,
-        projectPath
-      });
+      return await apiCall<void>("watch_project_claude_dir", { projectPath });
     } catch (error) {
-      console.error("Failed to get checkpoint settings:", error);
+      console.error("Failed to watch project .claude directory:", error);
       throw error;
     }
   },
 
   /**
-   * Clears checkpoint manager for a session (cleanup on session end)
+   * Stops live events for a project that was closed
+   * @param projectPath - The closed project
+   * @returns Promise resolving when the project is no longer watched
    */
-  async clearCheckpointManager(sessionId: string): Promise<void> {
+  async unwatchProjectClaudeDir(projectPath: string): Promise<void> {
     try {
-      await apiCall("clear_checkpoint_manager", { sessionId });
+      return await apiCall<void>("unwatch_project_claude_dir", { projectPath });
     } catch (error) {
-      console.error("Failed to clear checkpoint manager:", error);
+      console.error("Failed to unwatch project .claude directory:", error);
       throw error;
     }
   },
 
   /**
-   * Tracks a batch of messages for a session for checkpointing
+   * Totals the context a project's CLAUDE.md files, skills and MCP servers take up
+   * @param projectPath - The project directory
+   * @param model - Model name or alias, used to find its context window
+   * @returns Promise resolving to the contributors, largest first, and totals
    */
-  trackSessionMessages: (
-    sessionId: string,
-    projectId: string,
-    projectPath: string,
-    messages: string[]
-  ): Promise<void> =>
-    apiCall("track_session_messages", { sessionId, projectId, projectPath, messages }),
+  async estimateStaticContext(projectPath: string, model?: string): Promise<StaticContextEstimate> {
+    try {
+      return await apiCall<StaticContextEstimate>("estimate_static_context", { projectPath, model });
+    } catch (error) {
+      console.error("Failed to estimate static context:", error);
+      throw error;
+    }
+  },
 
   /**
-   * Adds a new MCP server
+   * Exports a session transcript with its tool calls and timestamps for sharing
+   * @param sessionId - The session to export
+   * @param format - "markdown", "html" or "json"
+   * @param path - Absolute path of the file to write
+   * @param options - Redaction of file paths and secrets
+   * @returns Promise resolving to what was written
    */
-  async mcpAdd(
-    name: string,
-    transport: string,
-    command?: string,
-    args: string[] = [],
-    env: Record<string, string> = {},
-    url?: string,
-    scope: string = "local"
-  ): Promise<AddServerResult> {
+  async exportSession(
+    sessionId: string,
+    format: "markdown" | "html" | "json",
+    path: string,
+    options?: ExportOptions
+  ): Promise<SessionExportResult> {
     try {
-      return await apiCall<AddServerResult>("mcp_add", {
-        name,
-        transport,
-        command,
-        args,
-        env,
-        url,
-        scope
-      });
+      return await apiCall<SessionExportResult>("export_session", { sessionId, format, path, options });
     } catch (error) {
-      console.error("Failed to add MCP server:", error);
+      console.error("Failed to export session:", error);
       throw error;
     }
   },
 
   /**
-   * Lists all configured MCP servers
+   * Gets which skills are used and how often, per project, from session transcripts
+   * @param projectPath - Only this project; all projects if omitted
+   * @param days - Only the last this many days; all time if omitted
+   * @returns Promise resolving to usage per project, including unused installed skills
    */
-  async mcpList(): Promise<MCPServer[]> {
+  async getSkillUsage(projectPath?: string, days?: number): Promise<ProjectSkillUsage[]> {
     try {
-      console.log("API: Calling mcp_list...");
-      const result = await apiCall<MCPServer[]>("mcp_list");
-      console.log("API: mcp_list returned:", result);
-      return result;
+      return await apiCall<ProjectSkillUsage[]>("get_skill_usage", { projectPath, days });
     } catch (error) {
-      console.error("API: Failed to list MCP servers:", error);
+      console.error("Failed to get skill usage:", error);
       throw error;
     }
   },
 
   /**
-   * Gets details for a specific MCP server
+   * Estimates how many tokens a text takes up for a model (approximate)
+   * @param text - The text to measure
+   * @param model - Model name or alias, used to find its context window
+   * @returns Promise resolving to the estimate
    */
-  async mcpGet(name: string): Promise<MCPServer> {
+  async estimateTokens(text: string, model: string): Promise<TokenEstimate> {
     try {
-      return await apiCall<MCPServer>("mcp_get", { name });
+      return await apiCall<TokenEstimate>("estimate_tokens", { text, model });
     } catch (error) {
-      console.error("Failed to get MCP server:", error);
+      console.error("Failed to estimate tokens:", error);
       throw error;
     }
   },
 
   /**
-   * Removes an MCP server
+   * Estimates the context a run would start with, to warn before it overflows
+   * @param projectPath - The project directory
+   * @param includeGlobs - Files to include, relative to the project, e.g. "src/**\/*.ts"
+   * @param model - Model name or alias
+   * @returns Promise resolving to per-file and total estimates
    */
-  async mcpRemove(name: string): Promise<string> {
+  async estimateProjectContext(
+    projectPath: string,
+    includeGlobs: string[],
+    model: string
+  ): Promise<ProjectContextEstimate> {
     try {
-      return await apiCall<string>("mcp_remove", { name });
+      return await apiCall<ProjectContextEstimate>("estimate_project_context", {
+        projectPath,
+        includeGlobs,
+        model,
+      });
     } catch (error) {
-      console.error("Failed to remove MCP server:", error);
+      console.error("Failed to estimate project context:", error);
       throw error;
     }
   },
 
   /**
-   * Adds an MCP server from JSON configuration
+   * Lists projects agent runs are working in, with the runs waiting for each
+   * @returns Promise resolving to the locked projects
    */
-  async mcpAddJson(name: string, jsonConfig: string, scope: string = "local"): Promise<AddServerResult> {
+  async listProjectLocks(): Promise<ProjectLockInfo[]> {
     try {
-      return await apiCall<AddServerResult>("mcp_add_json", { name, jsonConfig, scope });
+      return await apiCall<ProjectLockInfo[]>("list_project_locks");
     } catch (error) {
-      console.error("Failed to add MCP server from JSON:", error);
+      console.error("Failed to list project locks:", error);
       throw error;
     }
   },
 
   /**
-   * Imports MCP servers from Claude Desktop
+   * Creates a worktree on a new branch so an agent can run without touching the live checkout
+   * @param projectPath - The project directory
+   * @param branch - Name of the new branch
+   * @returns Promise resolving to the worktree; run agents in its `path`
    */
-  async mcpAddFromClaudeDesktop(scope: string = "local"): Promise<ImportResult> {
+  async createAgentWorktree(projectPath: string, branch: string): Promise<AgentWorktree> {
     try {
-      return await apiCall<ImportResult>("mcp_add_from_claude_desktop", { scope });
+      return await apiCall<AgentWorktree>("create_agent_worktree", { projectPath, branch });
     } catch (error) {
-      console.error("Failed to import from Claude Desktop:", error);
+      console.error("Failed to create worktree:", error);
       throw error;
     }
   },
 
   /**
-   * Starts Claude Code as an MCP server
+   * Lists agent worktrees
+   * @param projectPath - Only list worktrees created from this project
+   * @returns Promise resolving to the worktrees, newest first
    */
-  async mcpServe(): Promise<string> {
+  async listAgentWorktrees(projectPath?: string): Promise<AgentWorktree[]> {
     try {
-      return await apiCall<string>("mcp_serve");
+      return await apiCall<AgentWorktree[]>("list_agent_worktrees", { projectPath });
     } catch (error) {
-      console.error("Failed to start MCP server:", error);
+      console.error("Failed to list worktrees:", error);
       throw error;
     }
   },
 
-  async pluginInstall(name: string): Promise<string> {
+  /**
+   * Merges a worktree's changes into the project and removes the worktree
+   * @param id - The worktree ID
+   * @param message - Commit message for changes not yet committed in the worktree
+   * @returns Promise resolving to what was merged
+   */
+  async mergeWorktreeChanges(id: number, message?: string): Promise<WorktreeMergeResult> {
     try {
-      return await apiCall<string>("plugin_install", { name });
+      return await apiCall<WorktreeMergeResult>("merge_worktree_changes", { id, message });
     } catch (error) {
-      console.error("Failed to install plugin:", error);
+      console.error("Failed to merge worktree changes:", error);
       throw error;
     }
   },
 
-  async pluginUninstall(name: string): Promise<string> {
+  /**
+   * Deletes a worktree and its branch without merging
+   * @param id - The worktree ID
+   */
+  async discardWorktree(id: number): Promise<void> {
     try {
-      return await apiCall<string>("plugin_uninstall", { name });
+      return await apiCall<void>("discard_worktree", { id });
     } catch (error) {
-      console.error("Failed to uninstall plugin:", error);
+      console.error("Failed to discard worktree:", error);
       throw error;
     }
   },
 
   /**
-   * Tests connection to an MCP server
+   * Tells whether the last launch crashed and whether this one is in safe mode
+   * @returns Promise resolving to the launch state
    */
-  async mcpTestConnection(name: string): Promise<string> {
+  async getSafeModeStatus(): Promise<LaunchState> {
     try {
-      return await apiCall<string>("mcp_test_connection", { name });
+      return await apiCall<LaunchState>("get_safe_mode_status");
     } catch (error) {
-      console.error("Failed to test MCP connection:", error);
+      console.error("Failed to get safe mode status:", error);
       throw error;
     }
   },
 
   /**
-   * Resets project-scoped server approval choices
+   * Restarts the app in safe mode, with only the commands needed to fix settings
+   * @returns Promise that resolves if the restart could not happen
    */
-  async mcpResetProjectChoices(): Promise<string> {
+  async restartInSafeMode(): Promise<void> {
     try {
-      return await apiCall<string>("mcp_reset_project_choices");
+      return await apiCall<void>("restart_in_safe_mode");
     } catch (error) {
-      console.error("Failed to reset project choices:", error);
+      console.error("Failed to restart in safe mode:", error);
       throw error;
     }
   },
 
   /**
-   * Gets the status of MCP servers
+   * Restarts the app with plugins and background tasks enabled
+   * @returns Promise that resolves if the restart could not happen
    */
-  async mcpGetServerStatus(): Promise<Record<string, ServerStatus>> {
+  async restartNormally(): Promise<void> {
     try {
-      return await apiCall<Record<string, ServerStatus>>("mcp_get_server_status");
+      return await apiCall<void>("restart_normally");
     } catch (error) {
-      console.error("Failed to get server status:", error);
+      console.error("Failed to restart normally:", error);
       throw error;
     }
   },
 
   /**
-   * Reads .mcp.json from the current project
+   * Lists files with uncommitted changes in a project
+   * @param projectPath - The project directory
+   * @returns Promise resolving to the changed files with line counts
    */
-  async mcpReadProjectConfig(projectPath: string): Promise<MCPProjectConfig> {
+  async getChangedFiles(projectPath: string): Promise<ChangedFile[]> {
     try {
-      return await apiCall<MCPProjectConfig>("mcp_read_project_config", { projectPath });
+      return await apiCall<ChangedFile[]>("get_changed_files", { projectPath });
     } catch (error) {
-      console.error("Failed to read project MCP config:", error);
+      console.error("Failed to get changed files:", error);
       throw error;
     }
   },
 
   /**
-   * Saves .mcp.json to the current project
+   * Gets the uncommitted changes of a project as a unified diff, untracked files included
+   * @param projectPath - The project directory
+   * @returns Promise resolving to the diff
    */
-  async mcpSaveProjectConfig(projectPath: string, config: MCPProjectConfig): Promise<string> {
+  async getWorkingDiff(projectPath: string): Promise<WorkingDiff> {
     try {
-      return await apiCall<string>("mcp_save_project_config", { projectPath, config });
+      return await apiCall<WorkingDiff>("get_working_diff", { projectPath });
     } catch (error) {
-      console.error("Failed to save project MCP config:", error);
+      console.error("Failed to get working diff:", error);
       throw error;
     }
   },
 
   /**
-   * Get the stored Claude binary path from settings
-   * @returns Promise resolving to the path if set, null otherwise
+   * Stages the given files and commits only them
+   * @param projectPath - The project directory
+   * @param message - The commit message
+   * @param files - Paths relative to the project directory
+   * @returns Promise resolving to the new commit
    */
-  async getClaudeBinaryPath(): Promise<string | null> {
+  async stageAndCommit(projectPath: string, message: string, files: string[]): Promise<CommitResult> {
     try {
-      return await apiCall<string | null>("get_claude_binary_path");
+      return await apiCall<CommitResult>("stage_and_commit", { projectPath, message, files });
     } catch (error) {
-      console.error("Failed to get Claude binary path:", error);
+      console.error("Failed to commit changes:", error);
       throw error;
     }
   },
 
   /**
-   * Set the Claude binary path in settings
-   * @param path - The absolute path to the Claude binary
-   * @returns Promise resolving when the path is saved
+   * Discards uncommitted changes to the given files; new files are deleted
+   * @param projectPath - The project directory
+   * @param files - Paths relative to the project directory
+   * @returns Promise resolving to the files that were reverted
    */
-  async setClaudeBinaryPath(path: string): Promise<void> {
+  async revertFiles(projectPath: string, files: string[]): Promise<string[]> {
     try {
-      return await apiCall<void>("set_claude_binary_path", { path });
+      return await apiCall<string[]>("revert_files", { projectPath, files });
     } catch (error) {
-      console.error("Failed to set Claude binary path:", error);
+      console.error("Failed to revert files:", error);
       throw error;
     }
   },
 
   /**
-   * List all available Claude installations on the system
-   * @returns Promise resolving to an array of Claude installations
+   * Formats numbers for the locale chosen in settings, matching backend text
+   * @param kind - "cost" (USD), "duration_ms", "bytes" or "count"
+   * @param values - The numbers to format
+   * @returns Promise resolving to one string per value
    */
-  async listClaudeInstallations(): Promise<ClaudeInstallation[]> {
+  async formatValues(
+    kind: "cost" | "duration_ms" | "bytes" | "count",
+    values: number[]
+  ): Promise<string[]> {
     try {
-      return await apiCall<ClaudeInstallation[]>("list_claude_installations");
+      return await apiCall<string[]>("format_values", { kind, values });
     } catch (error) {
-      console.error("Failed to list Claude installations:", error);
+      console.error("Failed to format values:", error);
       throw error;
     }
   },
 
-  // Storage API methods
-
   /**
-   * Lists all tables in the SQLite database
-   * @returns Promise resolving to an array of table information
+   * Switches the app's locale for number formatting and catalog descriptions
+   * @param locale - One of en-US, en-GB, de-DE, fr-FR, es-ES, ja-JP or zh-CN
    */
-  async storageListTables(): Promise<any[]> {
+  async setAppLocale(locale: string): Promise<void> {
     try {
-      return await apiCall<any[]>("storage_list_tables");
+      return await apiCall<void>("set_app_locale", { locale });
     } catch (error) {
-      console.error("Failed to list tables:", error);
+      console.error("Failed to set app locale:", error);
       throw error;
     }
   },
 
   /**
-   * Reads table data with pagination
-   * @param tableName - Name of the table to read
-   * @param page - Page number (1-indexed)
-   * @param pageSize - Number of rows per page
-   * @param searchQuery - Optional search query
-   * @returns Promise resolving to table data with pagination info
+   * Gets which run events show a desktop notification
+   * @returns Promise resolving to the notification toggles
    */
-  async storageReadTable(
-    tableName: string,
-    page: number,
-    pageSize: number,
-    searchQuery?: string
-  ): Promise<any> {
+  async getNotificationSettings(): Promise<NotificationSettings> {
     try {
-      return await apiCall<any>("storage_read_table", {
-        tableName,
-        page,
-        pageSize,
-        searchQuery,
-      });
+      return await apiCall<NotificationSettings>("get_notification_settings");
     } catch (error) {
-      console.error("Failed to read table:", error);
+      console.error("Failed to get notification settings:", error);
       throw error;
     }
   },
 
   /**
-   * Updates a row in a table
-   * @param tableName - Name of the table
-   * @param primaryKeyValues - Map of primary key column names to values
-   * @param updates - Map of column names to new values
-   * @returns Promise resolving when the row is updated
+   * Saves which run events show a desktop notification
+   * @param settings - The notification toggles
    */
-  async storageUpdateRow(
-    tableName: string,
-    primaryKeyValues: Record<string, any>,
-    updates: Record<string, any>
-  ): Promise<void> {
+  async saveNotificationSettings(settings: NotificationSettings): Promise<void> {
     try {
-      return await apiCall<void>("storage_update_row", {
-        tableName,
-        primaryKeyValues,
-        updates,
-      });
+      return await apiCall<void>("save_notification_settings", { settings });
     } catch (error) {
-      console.error("Failed to update row:", error);
+      console.error("Failed to save notification settings:", error);
       throw error;
     }
   },
 
   /**
-   * Deletes a row from a table
-   * @param tableName - Name of the table
-   * @param primaryKeyValues - Map of primary key column names to values
-   * @returns Promise resolving when the row is deleted
+   * Gets whether GitHub catalog responses still match the expected schema
+   * @returns Promise resolving to "degraded" when the marketplace may be incomplete
    */
-  async storageDeleteRow(
-    tableName: string,
-    primaryKeyValues: Record<string, any>
-  ): Promise<void> {
+  async getMarketplaceStatus(): Promise<MarketplaceStatus> {
     try {
-      return await apiCall<void>("storage_delete_row", {
-        tableName,
-        primaryKeyValues,
-      });
+      return await apiCall<MarketplaceStatus>("get_marketplace_status");
     } catch (error) {
-      console.error("Failed to delete row:", error);
+      console.error("Failed to get marketplace status:", error);
       throw error;
     }
   },
 
   /**
-   * Inserts a new row into a table
-   * @param tableName - Name of the table
-   * @param values - Map of column names to values
-   * @returns Promise resolving to the last insert row ID
+   * Creates a schedule that runs an agent on a project while the app is open
+   * @param schedule - Agent, project, prompt, optional model and cron expression
+   * @returns Promise resolving to the created schedule with its next run time
    */
-  async storageInsertRow(
-    tableName: string,
-    values: Record<string, any>
-  ): Promise<number> {
+  async createSchedule(schedule: NewSchedule): Promise<Schedule> {
     try {
-      return await apiCall<number>("storage_insert_row", {
-        tableName,
-        values,
-      });
+      return await apiCall<Schedule>("create_schedule", { schedule });
     } catch (error) {
-      console.error("Failed to insert row:", error);
+      console.error("Failed to create schedule:", error);
       throw error;
     }
   },
 
   /**
-   * Executes a raw SQL query
-   * @param query - SQL query string
-   * @returns Promise resolving to query result
+   * Lists all schedules, soonest next run first
+   * @returns Promise resolving to the schedules
    */
-  async storageExecuteSql(query: string): Promise<any> {
+  async listSchedules(): Promise<Schedule[]> {
     try {
-      return await apiCall<any>("storage_execute_sql", { query });
+      return await apiCall<Schedule[]>("list_schedules");
     } catch (error) {
-      console.error("Failed to execute SQL:", error);
+      console.error("Failed to list schedules:", error);
       throw error;
     }
   },
 
   /**
-   * Resets the entire database
-   * @returns Promise resolving when the database is reset
+   * Deletes a schedule; runs it already started stay in the run history
+   * @param id - The schedule ID
    */
-  async storageResetDatabase(): Promise<void> {
+  async deleteSchedule(id: number): Promise<void> {
     try {
-      return await apiCall<void>("storage_reset_database");
+      return await apiCall<void>("delete_schedule", { id });
     } catch (error) {
-      console.error("Failed to reset database:", error);
+      console.error("Failed to delete schedule:", error);
       throw error;
     }
   },
 
-  // Theme settings helpers
+  /**
+   * Runs a schedule immediately without changing when it next runs
+   * @param id - The schedule ID
+   * @returns Promise resolving to the agent run ID
+   */
+  async runScheduleNow(id: number): Promise<number> {
+    try {
+      return await apiCall<number>("run_schedule_now", { id });
+    } catch (error) {
+      console.error("Failed to run schedule:", error);
+      throw error;
+    }
+  },
 
   /**
-   * Gets a setting from the app_settings table
-   * @param key - The setting key to retrieve
-   * @returns Promise resolving to the setting value or null if not found
+   * Exports settings, agents and user MCP servers to a single archive
+   * @param path - File to write
+   * @param passphrase - Encrypts secrets into the archive; without it they are left out
    */
-  async getSetting(key: string): Promise<string | null> {
+  async exportAppConfig(path: string, passphrase?: string): Promise<void> {
     try {
-      // Fast path: check localStorage mirror to avoid startup flicker
-      if (typeof window !== 'undefined' && 'localStorage' in window) {
-        const cached = window.localStorage.getItem(`app_setting:${key}`);
-        if (cached !== null) {
-          return cached;
-        }
-      }
-      // Use storageReadTable to safely query the app_settings table
-      const result = await this.storageReadTable('app_settings', 1, 1000);
-      const setting = result?.data?.find((row: any) => row.key === key);
-      return setting?.value || null;
+      await apiCall("export_app_config", { path, passphrase });
     } catch (error) {
-      console.error(`Failed to get setting ${key}:`, error);
-      return null;
+      console.error("Failed to export configuration:", error);
+      throw error;
     }
   },
 
   /**
-   * Saves a setting to the app_settings table (insert or update)
-   * @param key - The setting key
-   * @param value - The setting value
-   * @returns Promise resolving when the setting is saved
+   * Imports an archive written by exportAppConfig
+   * @param path - Archive to read
+   * @param dryRun - Only report what would change
+   * @param passphrase - Needed when the archive has encrypted secrets
+   * @returns Promise resolving to the changes made, or that would be made
    */
-  async saveSetting(key: string, value: string): Promise<void> {
+  async importAppConfig(path: string, dryRun: boolean, passphrase?: string): Promise<ConfigImportReport> {
     try {
-      // Mirror to localStorage for instant availability on next startup
-      if (typeof window !== 'undefined' && 'localStorage' in window) {
-        try {
-          window.localStorage.setItem(`app_setting:${key}`, value);
-        } catch (_ignore) {
-          // best-effort; continue to persist in DB
-        }
-      }
-      // Try to update first
-      try {
-        await this.storageUpdateRow(
-          'app_settings',
-          { key },
-          { value }
-        );
-      } catch (updateError) {
-        // If update fails (row doesn't exist), insert new row
-        await this.storageInsertRow('app_settings', { key, value });
-      }
+      return await apiCall<ConfigImportReport>("import_app_config", { path, dryRun, passphrase });
     } catch (error) {
-      console.error(`Failed to save setting ${key}:`, error);
+      console.error("Failed to import configuration:", error);
       throw error;
     }
   },
@@ -2036,7 +5011,7 @@ export const api = {
      }
   },
 
-  async fetchAgentTemplates(): Promise<{name: string, description: string, prompt: string, category: string}[]> {
+  async fetchAgentTemplates(): Promise<AgentTemplate[]> {
       try {
           // @ts-ignore
           return await invoke("fetch_agent_templates");
@@ -2046,8 +5021,30 @@ export const api = {
       }
   },
 
-  async installSkill(projectPath: string, skillName: string): Promise<void> {
-    return apiCall("install_skill", { projectPath, skillName });
+  /**
+   * Installs a skill and the skills it depends on
+   * @param projectPath - The project to install into; not needed for user skills
+   * @param scope - "project" (default) or "user" for ~/.claude/skills
+   * @returns The installed skill, with any programs it needs that are missing
+   */
+  async installSkill(projectPath: string | undefined, skillName: string, scope?: SkillScope): Promise<InstalledSkill> {
+    return apiCall<InstalledSkill>("install_skill", { projectPath, skillName, scope });
+  },
+
+  /**
+   * Gets events published after `cursor`, to catch up after a reload
+   * @param cursor - Cursor returned by the previous call, or omitted for the oldest retained events
+   * @param topicPrefix - Only return topics starting with this, e.g. "agent-complete"
+   */
+  async getEventsSince(cursor?: number, topicPrefix?: string, limit?: number): Promise<EventPage> {
+    return apiCall<EventPage>("get_events_since", { cursor, topicPrefix, limit });
+  },
+
+  /**
+   * Gets which subsystems are available, so features that would fail can be hidden
+   */
+  async getCapabilities(): Promise<AppCapabilities> {
+    return apiCall<AppCapabilities>("get_capabilities");
   },
 
 };
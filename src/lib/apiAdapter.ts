@@ -50,6 +50,57 @@ function detectEnvironment(): boolean {
   return isTauri;
 }
 
+/**
+ * Typed error returned by commands that call GitHub or the Anthropic API
+ */
+export type NetworkError =
+  | { kind: 'rate_limited'; message: string; retry_after_secs: number | null }
+  | { kind: 'offline'; message: string }
+  | { kind: 'not_found'; message: string }
+  | { kind: 'auth'; message: string }
+  | { kind: 'http'; status: number; message: string }
+  | { kind: 'integrity'; message: string }
+  | { kind: 'cancelled'; message: string }
+  | { kind: 'other'; message: string };
+
+const NETWORK_ERROR_KINDS = ['rate_limited', 'offline', 'not_found', 'auth', 'http', 'integrity', 'cancelled', 'other'];
+
+export function isNetworkError(error: unknown): error is NetworkError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    NETWORK_ERROR_KINDS.includes((error as any).kind) &&
+    typeof (error as any).message === 'string'
+  );
+}
+
+/**
+ * User-facing description of a failed network call
+ */
+export function describeNetworkError(error: unknown, fallback: string): string {
+  if (!isNetworkError(error)) {
+    return fallback;
+  }
+  switch (error.kind) {
+    case 'rate_limited':
+      return error.retry_after_secs
+        ? `Rate limited. Please try again in ${error.retry_after_secs} seconds.`
+        : 'Rate limited. Please try again later.';
+    case 'offline':
+      return 'Could not reach the server. Please check your internet connection.';
+    case 'not_found':
+      return 'The requested resource was not found.';
+    case 'auth':
+      return 'Authentication failed. Please check your credentials.';
+    case 'integrity':
+      return `Download failed verification: ${error.message}`;
+    case 'cancelled':
+      return 'Cancelled.';
+    default:
+      return `${fallback}: ${error.message}`;
+  }
+}
+
 /**
  * Response wrapper for REST API calls
  */
@@ -128,6 +179,22 @@ async function restApiCall<T>(endpoint: string, params?: any): Promise<T> {
   }
 }
 
+/**
+ * Record a failed command for list_recent_errors; never throws
+ */
+function reportCommandError(command: string, error: unknown): void {
+  if (command === 'record_command_error') {
+    return;
+  }
+  const message =
+    typeof error === 'string'
+      ? error
+      : error instanceof Error
+        ? error.message
+        : JSON.stringify(error);
+  invoke('record_command_error', { command, message }).catch(() => {});
+}
+
 /**
  * Unified API adapter that works in both Tauri and web environments
  */
@@ -140,6 +207,11 @@ export async function apiCall<T>(command: string, params?: any): Promise<T> {
     try {
       return await invoke<T>(command, params);
     } catch (error) {
+      reportCommandError(command, error);
+      // The command ran and reported a network failure; don't retry over REST
+      if (isNetworkError(error)) {
+        throw error;
+      }
       console.warn(`[Tauri] invoke failed, falling back to web mode:`, error);
       // Fall through to web mode
     }
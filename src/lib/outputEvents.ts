@@ -0,0 +1,53 @@
+import type { EventCallback, UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { api } from "@/lib/api";
+
+/**
+ * Output lines of a run, emitted together on an `*-output-batch` event
+ */
+export interface OutputBatch {
+  lines: string[];
+  first_seq: number | null;
+  last_seq: number | null;
+}
+
+/**
+ * Listens for an event sent to this window
+ *
+ * Unlike the global `listen`, this doesn't receive events the backend sends to
+ * another window, such as the output of a session attached there.
+ * @param event - The event name, e.g. `claude-complete:${sessionId}`
+ * @param handler - Called with each event
+ * @returns A function that stops listening
+ */
+export function listenInWindow<T>(event: string, handler: EventCallback<T>): Promise<UnlistenFn> {
+  return getCurrentWebviewWindow().listen<T>(event, handler);
+}
+
+/**
+ * Moves a running session or agent run to this window, so its events come here
+ * and no longer go to the window that started it
+ * @param sessionId - A Claude session ID, or an agent run ID
+ */
+export async function attachToThisWindow(sessionId: string): Promise<void> {
+  try {
+    await api.attachSessionToWindow(sessionId, getCurrentWebviewWindow().label);
+  } catch {
+    // Logged by the API; events keep going to the window they went to before
+  }
+}
+
+/**
+ * Listens in this window for batched output and calls `onLine` for every line, in order
+ * @param event - The batch event, e.g. `agent-output-batch:${runId}`
+ * @param onLine - Called with each raw JSONL line
+ * @returns A function that stops listening
+ */
+export function listenOutputLines(
+  event: string,
+  onLine: (line: string) => void
+): Promise<UnlistenFn> {
+  return listenInWindow<OutputBatch>(event, ({ payload }) => {
+    payload.lines.forEach(onLine);
+  });
+}